@@ -1,11 +1,9 @@
-use nvd_cve::cache::{search_by_id, search_description, sync_blocking, CacheConfig};
-
-use nvd_cve::client::{BlockingHttpClient, ReqwestBlockingClient};
+use nvd_cve::prelude::*;
 
 pub fn main() {
     let mut config = CacheConfig::new();
 
-    config.feeds = vec!["2019".to_string()];
+    config.feeds = vec![FeedName::Year(2019)];
 
     let client = ReqwestBlockingClient::new(&config.url, None, None, None);
 
@@ -21,10 +19,10 @@ pub fn main() {
     ) {
         println!("\n\nFound {} matching CVE(s): ", cves.len());
         for cve in cves {
-            let cve_result = search_by_id(&config, &cve).unwrap();
+            let cve_result = search_by_id(&config, &cve.id).unwrap();
             println!(
                 "{}: {}",
-                cve, &cve_result.description.description_data[0].value
+                cve.id, &cve_result.description.description_data[0].value
             );
         }
     }
@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nvd_cve::cpe::Cpe;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(cpe) = String::from_utf8_lossy(data).parse::<Cpe>() {
+        // Formatted strings keep the case of values, URIs are lower case
+        assert_eq!(cpe.to_string().parse::<Cpe>(), Ok(cpe.clone()));
+        let uri = cpe.to_uri().to_lowercase();
+        assert_eq!(Cpe::from_uri(&uri).map(|cpe| cpe.to_uri()), Ok(uri));
+    }
+});
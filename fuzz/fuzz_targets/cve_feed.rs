@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nvd_cve::cve::CveFeed;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(feed) = serde_json::from_slice::<CveFeed>(data) {
+        for item in &feed.cve_items {
+            item.severity();
+        }
+    }
+});
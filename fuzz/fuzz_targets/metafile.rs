@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nvd_cve::feed::Metafile;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(metafile) = Metafile::from_string(String::from_utf8_lossy(data).into_owned()) {
+        metafile.format_last_modified_date();
+    }
+});
@@ -0,0 +1,101 @@
+use crate::cache::{open, readable_schema, CacheConfig, CacheError};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+/// Another identifier of a CVE, given by an advisory or another vulnerability database.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Alias {
+    /// The other identifier, e.g. ``RHSA-2023:1234``.
+    pub alias: String,
+
+    /// CVE ID the alias refers to.
+    pub cve: String,
+
+    /// Where the alias comes from, such as the publisher of an advisory or the system naming
+    /// the vulnerability.
+    pub source: String,
+}
+
+/// Record ``aliases`` in the cache opened by ``conn``, replacing the source of those already
+/// recorded. Returns the number of aliases written.
+pub(crate) fn insert_aliases(conn: &Connection, aliases: &[Alias]) -> Result<usize, CacheError> {
+    let mut stmt = conn.prepare(
+        "INSERT INTO aliases (alias, cve, source) VALUES (?1, ?2, ?3)
+        ON CONFLICT (alias, cve) DO UPDATE SET source = ?3",
+    )?;
+    for alias in aliases {
+        stmt.execute(params![alias.alias, alias.cve, alias.source])?;
+    }
+    stmt.finalize()?;
+    Ok(aliases.len())
+}
+
+/// Whether the cache opened by ``conn`` has an ``aliases`` table, read-only caches made by older
+/// versions don't
+fn has_aliases(conn: &Connection) -> Result<bool, CacheError> {
+    let mut tbl_stmt =
+        conn.prepare("SELECT name FROM sqlite_master where type = 'table' and name = 'aliases'")?;
+    let exists = tbl_stmt.exists([])?;
+    tbl_stmt.finalize()?;
+    Ok(exists)
+}
+
+/// Returns the aliases of a CVE, sorted by alias.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::aliases::get_aliases;
+/// use nvd_cve::cache::CacheConfig;
+///
+/// let config = CacheConfig::new();
+///
+/// for alias in get_aliases(&config, "CVE-2023-0286").unwrap() {
+///     println!("{} ({})", alias.alias, alias.source);
+/// }
+/// ```
+pub fn get_aliases(config: &CacheConfig, cve: &str) -> Result<Vec<Alias>, CacheError> {
+    readable_schema(config)?;
+
+    let conn = open(config)?;
+
+    let mut aliases = vec![];
+    if has_aliases(&conn)? {
+        let mut stmt = conn.prepare("SELECT * FROM aliases WHERE cve = ?1 ORDER BY alias")?;
+        aliases = stmt
+            .query_map([cve], |row| {
+                Ok(Alias {
+                    alias: row.get("alias")?,
+                    cve: row.get("cve")?,
+                    source: row.get("source")?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        stmt.finalize()?;
+    }
+
+    match conn.close() {
+        Ok(_) => Ok(aliases),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Returns the IDs of the CVEs an alias refers to, sorted, as an advisory may cover several CVEs.
+pub fn resolve_alias(config: &CacheConfig, alias: &str) -> Result<Vec<String>, CacheError> {
+    readable_schema(config)?;
+
+    let conn = open(config)?;
+
+    let mut cves = vec![];
+    if has_aliases(&conn)? {
+        let mut stmt = conn.prepare("SELECT cve FROM aliases WHERE alias = ?1 ORDER BY cve")?;
+        cves = stmt
+            .query_map([alias], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        stmt.finalize()?;
+    }
+
+    match conn.close() {
+        Ok(_) => Ok(cves),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
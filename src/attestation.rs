@@ -0,0 +1,146 @@
+use crate::cache::{open, readable_schema, CacheConfig, CacheError};
+use crate::feed::{FeedName, Metafile};
+use hmac::{Hmac, Mac};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+/// A feed as of a sync run, described by the ``Metafile`` it was synced or found up to date with.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FeedAttestation {
+    /// Name of the feed, e.g. ``2021`` or ``recent``.
+    pub feed: String,
+
+    /// Last modified date of the feed, as formatted in the local cache.
+    pub last_modified_date: String,
+
+    /// Size of the uncompressed feed in bytes.
+    pub size: u64,
+
+    /// SHA256 sum of the uncompressed feed.
+    pub sha256: String,
+
+    /// Whether the feed was fetched and synced, rather than already up to date.
+    pub updated: bool,
+}
+
+impl FeedAttestation {
+    pub(crate) fn new(name: &FeedName, metafile: &Metafile, updated: bool) -> Self {
+        Self {
+            feed: name.to_string(),
+            last_modified_date: metafile.format_last_modified_date(),
+            size: metafile.size,
+            sha256: metafile.sha256.clone(),
+            updated,
+        }
+    }
+}
+
+/// Record of a sync run, stating when and from which feeds the local cache was built, signed with
+/// a local key so it can later be proven to have been recorded by its holder.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::attestation::get_attestations;
+/// use nvd_cve::cache::CacheConfig;
+///
+/// let config = CacheConfig::new();
+/// let key = std::fs::read("/etc/nvd_cve/attestation.key").unwrap();
+///
+/// for attestation in get_attestations(&config).unwrap() {
+///     println!("{}: {}", attestation.finished, attestation.verify(&key));
+/// }
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Attestation {
+    /// Name of the source that was synced.
+    pub source: String,
+
+    /// Version of ``nvd_cve`` that ran the sync.
+    pub app_version: String,
+
+    /// When the sync started, RFC 3339 formatted.
+    pub started: String,
+
+    /// When the sync finished, RFC 3339 formatted.
+    pub finished: String,
+
+    /// Every feed of the sync, in the order they were synced.
+    pub feeds: Vec<FeedAttestation>,
+
+    /// Number of CVEs inserted or updated.
+    pub cves_updated: usize,
+
+    /// Number of CVEs left as they were, since they have been modified after the feed.
+    pub cves_skipped: usize,
+
+    /// Hex encoded HMAC-SHA256 of all the other fields serialized as JSON. Set by ``sign``.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+impl Attestation {
+    /// HMAC of the attestation without its signature, keyed with ``key``
+    fn mac(&self, key: &[u8]) -> Hmac<Sha256> {
+        let unsigned = Attestation {
+            signature: None,
+            ..self.clone()
+        };
+        let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+        mac.update(&serde_json::to_vec(&unsigned).expect("Attestations serialize to JSON"));
+        mac
+    }
+
+    /// Sign the attestation with ``key``, replacing any previous signature.
+    pub fn sign(&mut self, key: &[u8]) {
+        self.signature = Some(hex::encode(self.mac(key).finalize().into_bytes()));
+    }
+
+    /// Returns ``true`` if the attestation was signed with ``key`` and hasn't been altered since.
+    pub fn verify(&self, key: &[u8]) -> bool {
+        match self.signature.as_deref().map(hex::decode) {
+            Some(Ok(signature)) => self.mac(key).verify_slice(&signature).is_ok(),
+            _ => false,
+        }
+    }
+}
+
+/// Store an attestation in the local cache.
+pub(crate) fn record_attestation(
+    config: &CacheConfig,
+    attestation: &Attestation,
+) -> Result<(), CacheError> {
+    let conn = open(config)?;
+
+    conn.execute(
+        "INSERT INTO attestation (finished, data) values (?1, ?2)",
+        params![attestation.finished, serde_json::to_string(attestation)?],
+    )?;
+
+    match conn.close() {
+        Ok(_) => Ok(()),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Returns the attestations of all the sync runs recorded in the local cache, oldest first.
+pub fn get_attestations(config: &CacheConfig) -> Result<Vec<Attestation>, CacheError> {
+    readable_schema(config)?;
+
+    let conn = open(config)?;
+
+    let mut stmt = conn.prepare("SELECT data FROM attestation ORDER BY id")?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>("data"))?;
+
+    let mut attestations = vec![];
+    for data in rows {
+        attestations.push(serde_json::from_str(&data?)?);
+    }
+
+    stmt.finalize()?;
+
+    match conn.close() {
+        Ok(_) => Ok(attestations),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
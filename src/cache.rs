@@ -1,27 +1,144 @@
-use crate::client::{BlockingHttpClient, HttpError};
-use crate::cve::{Cve, CveContainer, CveFeed};
-use crate::feed::{Feed, Metafile, MetafileError};
-use chrono::NaiveDateTime;
+use crate::aliases::{get_aliases, resolve_alias, Alias};
+use crate::attestation::{record_attestation, Attestation, FeedAttestation};
+use crate::client::{AsyncHttpClient, HttpError, DEFAULT_MAX_REDIRECTS};
+#[cfg(feature = "blocking")]
+use crate::client::{BlockingHttpClient, ReqwestBlockingClient};
+use crate::clock::{Clock, SystemClock};
+use crate::compression::Compression;
+use crate::cve::{Configuration, Cve, CveContainer, CveFeed, CveSummary, Impact};
+use crate::cvss::Severity;
+use crate::datetime::{format_datetime, parse_datetime};
+use crate::feed::{Feed, FeedName, Metafile, MetafileError};
+#[cfg(feature = "blocking")]
+use crate::history::{fetch_changes_between, CHANGE_DATE_FORMAT};
+use crate::history::{last_sync_run, record_sync_run, ChangeEvent, SyncRun};
+use crate::osv::{query_osv, Ecosystem, OsvVulnerability, OSV_ALIAS_SOURCE, OSV_URL};
+#[cfg(feature = "blocking")]
+use crate::pipeline::{self, Payload, STAGE_BACKLOG};
+use crate::query::{reference_location, SearchQuery};
+use crate::risk::{score_cves, RiskWeights};
+use crate::trace::write_trace;
+use chrono::{DateTime, Utc};
+#[cfg(feature = "blocking")]
 use humansize::{file_size_opts as options, FileSize};
-use log::debug;
-use rusqlite::{params, Connection, Result, Transaction, TransactionBehavior};
+use log::{debug, warn};
+use rusqlite::types::{Type, Value};
+use rusqlite::{
+    params, params_from_iter, Connection, OpenFlags, OptionalExtension, Result, Row, Transaction,
+    TransactionBehavior,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
 use std::fmt;
+#[cfg(feature = "blocking")]
+use std::panic::resume_unwind;
 use std::path::PathBuf;
+#[cfg(feature = "bulk-import")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "blocking")]
+use std::sync::mpsc::sync_channel;
+use std::sync::Arc;
+#[cfg(feature = "blocking")]
+use std::thread;
+use std::time::{Duration, Instant};
 use std::{env, fs, io};
 
-const SCHEMA_VERSION: &str = "0.1.0";
+pub(crate) const SCHEMA_VERSION: &str = "0.2.0";
+
+/// Name of the source used when none is given, the official NIST NVD feeds.
+pub const DEFAULT_SOURCE: &str = "nvd";
+
+/// Number of CVEs written to the cache between two progress updates of a sync.
+const WRITE_PROGRESS_STEP: usize = 1000;
+
+/// Number of days of change events fetched by each request of ``sync_change_history()``, the
+/// longest range the CVE Change History API allows. The first sync fetches this many days.
+#[cfg(feature = "blocking")]
+const CHANGE_HISTORY_WINDOW_DAYS: i64 = 120;
+
+/// Columns added to the ``cve`` table after its initial schema, populated from the feed at sync.
+const CVE_COLUMNS: &[(&str, &str)] = &[
+    ("attack_vector", "VARCHAR"),
+    ("attack_complexity", "VARCHAR"),
+    ("privileges_required", "VARCHAR"),
+    ("user_interaction", "VARCHAR"),
+    ("scope", "VARCHAR"),
+    ("confidentiality_impact", "VARCHAR"),
+    ("integrity_impact", "VARCHAR"),
+    ("availability_impact", "VARCHAR"),
+    ("exploitability_score", "REAL"),
+    ("impact_score", "REAL"),
+    ("base_severity", "VARCHAR"),
+    ("base_score", "REAL"),
+    ("published_date", "VARCHAR"),
+    ("feed", "VARCHAR"),
+    ("last_modified_date", "VARCHAR"),
+    ("configurations", "TEXT"),
+    ("impact", "TEXT"),
+    ("assigner", "VARCHAR"),
+];
+
+/// Columns added to the ``overrides`` table after its initial schema, left empty for existing
+/// overrides.
+const OVERRIDE_COLUMNS: &[(&str, &str)] = &[("tags", "TEXT"), ("advisory", "VARCHAR")];
+
+/// Columns added to the ``cve_history`` table after its initial schema, copied from the ``cve``
+/// table along with the data of each version kept. Versions kept before are left without them.
+const CVE_HISTORY_COLUMNS: &[(&str, &str)] = &[
+    ("published_date", "VARCHAR"),
+    ("base_severity", "VARCHAR"),
+    ("base_score", "REAL"),
+];
+
+/// Indexes on the ``cve`` table, by name, created along with the schema for the columns searched
+/// and listed the most. ``cve_summary`` covers the ``SUMMARY_COLUMNS`` so that listings and text
+/// searches never read the JSON data, which precedes the columns added to the table later on.
+const CVE_INDEXES: &[(&str, &str)] = &[
+    (
+        "cve_summary",
+        "id, description, base_severity, base_score, published_date",
+    ),
+    ("cve_severity", "base_severity, base_score"),
+    ("cve_score", "base_score"),
+    ("cve_published_date", "published_date"),
+    ("cve_assigner", "assigner COLLATE NOCASE"),
+];
 
 /// Configuration details about how to sync remote feeds to a local cache.
-#[derive(Debug)]
+///
+/// With the ``serde`` feature enabled configs can be (de)serialized, any fields missing when
+/// deserializing take their value from ``CacheConfig::new()``.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
 pub struct CacheConfig {
     /// A URL where  NIST CVE 1.1  feeds can be found. This can be your own mirror but it must have the
     /// same file and directory structure as served by the official NIST feeds.
     pub url: String,
 
+    /// Compression format the feeds are fetched in.
+    pub compression: Compression,
+
+    /// Number of threads used to parse the items of each feed, ``0`` for one per available CPU.
+    pub parse_threads: usize,
+
     /// All feeds that are to be synced. They are synced in the order provided so if you intend to
     /// sync the``recent`` or ``modified`` feeds, they should always be provided last or else it is
     /// possible to overwrite a newer ``modified`` version of a CVE record with stale data.
-    pub feeds: Vec<String>,
+    ///
+    /// When deserialized, ranges of yearly feeds such as ``"2018.."`` are expanded to the feeds
+    /// they select, see ``FeedSelector``.
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "crate::feed::deserialize_feeds")
+    )]
+    pub feeds: Vec<FeedName>,
+
+    /// Name of the source the feeds are synced from, such as the official NVD feeds or an internal
+    /// mirror. Each source is cached in its own database so that records from one source never
+    /// overwrite those of another.
+    pub source: String,
 
     /// Path to the SQLite database used to store the synced CVE data.
     pub db: String,
@@ -32,6 +149,241 @@ pub struct CacheConfig {
     /// If ``True`` the ``last_modified_date`` provided by the feed's ``Metafile`` will be ignored
     /// and the feed will always be fetched.
     pub force_update: bool,
+
+    /// Path to a file holding the key used to sign an ``Attestation`` of each sync, which is
+    /// recorded in the local cache. No attestations are recorded when ``None``.
+    pub attestation_key: Option<String>,
+
+    /// Namespace of the local overrides, their asset tags and the risk scores computed from them,
+    /// so that one cache can hold the assessments of several teams, e.g. business units, which
+    /// share the CVEs synced. Overrides and risk scores are only ever read from and written to the
+    /// namespace of the config. Empty, the default, is the namespace of caches made before
+    /// namespaces.
+    pub namespace: String,
+
+    /// If ``True`` the local cache is opened strictly read-only, e.g. for a cache built centrally
+    /// and distributed as an immutable artifact. Syncing, overriding or reindexing a read-only
+    /// cache fails with ``CacheError::ReadOnly``.
+    pub read_only: bool,
+
+    /// Longest time fetching a feed along with its Metafile may take, so that a hung download
+    /// can't stall a sync. Given in seconds in config files, ``None`` for no limit.
+    #[cfg_attr(feature = "serde", serde(with = "optional_secs"))]
+    pub feed_timeout: Option<Duration>,
+
+    /// Longest time a sync may spend fetching feeds, from its start. Feeds that aren't fetched by
+    /// then time out. Given in seconds in config files, ``None`` for no limit.
+    #[cfg_attr(feature = "serde", serde(with = "optional_secs"))]
+    pub sync_deadline: Option<Duration>,
+
+    /// What a sync does when a feed times out, see ``TimeoutPolicy``.
+    pub on_timeout: TimeoutPolicy,
+
+    /// URL of a CVE Change History API, such as ``history::NVD_CHANGE_HISTORY_URL``, whose change
+    /// events the ``sync`` command syncs with ``sync_change_history()`` after the feeds. ``None``,
+    /// the default, skips them.
+    pub change_history_url: Option<String>,
+
+    /// URL of the directory of a CPE match feed, such as ``cpematch::NVD_CPE_MATCH_URL``, which the
+    /// ``sync`` command syncs with ``cpematch::sync_cpe_matches()`` after the feeds, so that CPE
+    /// matches are matched against the CPE names they expand to. ``None``, the default, skips it.
+    pub cpe_match_url: Option<String>,
+
+    /// URL of the directory of a CPE dictionary, such as ``cpedict::NVD_CPE_DICTIONARY_URL``, which
+    /// the ``sync`` command syncs with ``cpedict::sync_cpe_dictionary()`` after the feeds, so that
+    /// the vendor and product names of queries can be checked locally. ``None``, the default,
+    /// skips it.
+    pub cpe_dictionary_url: Option<String>,
+
+    /// URL of the directory of a Known Exploited Vulnerabilities catalog, such as
+    /// ``kev::CISA_KEV_URL``, which the ``sync`` command syncs with ``kev::sync_kev()`` after the
+    /// feeds, so that searches can be limited to the CVEs known to be exploited. ``None``, the
+    /// default, skips it.
+    pub kev_url: Option<String>,
+
+    /// URL of the directory of a CWE list, such as ``cwe::MITRE_CWE_URL``, which the ``sync``
+    /// command syncs with ``cwe::sync_cwes()`` after the feeds, so that the weaknesses of CVEs are
+    /// named. ``None``, the default, skips it.
+    pub cwe_url: Option<String>,
+
+    /// URL of the directory of the daily EPSS scores, such as ``epss::EPSS_URL``, which the
+    /// ``sync`` command syncs with ``epss::sync_epss()`` after the feeds, so that searches can
+    /// filter CVEs by their probability of being exploited. ``None``, the default, skips them.
+    pub epss_url: Option<String>,
+
+    /// Ecosystems whose OSV advisories the ``sync`` command syncs with ``osv::sync_osv()`` after
+    /// the feeds, for package searches. Empty, the default, skips them.
+    pub osv_ecosystems: Vec<Ecosystem>,
+
+    /// Base URL of the OSV data dumps the advisories of the ``osv_ecosystems`` are synced from,
+    /// defaults to ``osv::OSV_URL``.
+    pub osv_url: String,
+
+    /// If ``True`` the version of each CVE a sync replaces with a modified one is kept in the local
+    /// cache, for ``search_by_id_as_of()`` to return the version current at a given date. CVEs
+    /// the NVD only re-scores are modified too, their kept versions hold the former scores.
+    /// Bulk imports keep no versions, so only the first feed written to a new cache is bulk
+    /// imported then.
+    pub track_history: bool,
+
+    /// Weights the risk score of each CVE is computed with by ``score_cves()`` once a sync wrote
+    /// the feeds, for searches to filter and sort CVEs by. ``None``, the default, leaves the
+    /// scores as they are.
+    pub risk_weights: Option<RiskWeights>,
+
+    /// Largest size in bytes the local cache may take once synced. A sync leaving it larger prunes
+    /// CVEs in the order of the ``prune_policy`` until it fits, see ``enforce_size_budget()``.
+    /// ``None``, the default, lets it grow.
+    pub max_db_size: Option<u64>,
+
+    /// Which CVEs are pruned first to keep the local cache within ``max_db_size``.
+    pub prune_policy: PrunePolicy,
+
+    /// If ``True`` the time each stage of a sync spent on each feed is recorded in the
+    /// ``feed_timings`` of its ``SyncReport``, e.g. to report a slow sync.
+    pub trace_feeds: bool,
+
+    /// Path of a trace file the ``feed_timings`` of each sync are written to, which implies
+    /// ``trace_feeds``, see ``trace::write_trace()``. ``None``, the default, writes none.
+    pub trace_file: Option<String>,
+
+    /// Path of a file the ``sync`` command writes the metrics of the cache to once it ends, whether
+    /// it succeeded or not, for the textfile collector of node_exporter, see
+    /// ``metrics::write_metrics()``. ``None``, the default, writes none.
+    pub metrics_file: Option<String>,
+
+    /// Number of redirects in a row a sync follows before failing, applied to the client of
+    /// ``sync_blocking()`` and ``sync_async()``, and by the CLI to the clients of every other
+    /// source it syncs or fetches from, see ``ReqwestBlockingClient::with_max_redirects``.
+    /// Defaults to ``client::DEFAULT_MAX_REDIRECTS``.
+    pub max_redirects: usize,
+
+    /// Hosts a sync may contact, such as an internal mirror, so that it never reaches any other
+    /// host even when redirected there. Applied like ``max_redirects``, see
+    /// ``ReqwestBlockingClient::with_allowed_hosts``. Empty, the default, allows any host.
+    pub allowed_hosts: Vec<String>,
+
+    /// Clock dating sync runs, the versions of CVEs kept in the history and overrides, and which
+    /// the age of the cache is measured against by ``check()``. Defaults to the ``SystemClock``, tests can
+    /// give a ``ManualClock`` to simulate time passing. Never (de)serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub clock: Arc<dyn Clock>,
+}
+
+/// What a sync does when fetching a feed exceeds the ``feed_timeout`` of its config, or the
+/// ``sync_deadline`` passes before a feed is fetched.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum TimeoutPolicy {
+    /// Fail the sync with ``CacheError::TimedOut``, the default. Feeds synced before are kept.
+    #[default]
+    Abort,
+    /// Sync the other feeds and list those that timed out in the ``SyncReport``.
+    Continue,
+}
+
+impl fmt::Display for TimeoutPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeoutPolicy::Abort => write!(f, "abort"),
+            TimeoutPolicy::Continue => write!(f, "continue"),
+        }
+    }
+}
+
+impl std::str::FromStr for TimeoutPolicy {
+    type Err = String;
+
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        match policy.trim().to_ascii_lowercase().as_str() {
+            "abort" => Ok(TimeoutPolicy::Abort),
+            "continue" => Ok(TimeoutPolicy::Continue),
+            _ => Err(format!(
+                "unknown policy {:?}, expected abort or continue",
+                policy
+            )),
+        }
+    }
+}
+
+/// Which CVEs are pruned first when a sync leaves the local cache larger than its ``max_db_size``.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum PrunePolicy {
+    /// CVEs published first, so the oldest years go first, the default.
+    #[default]
+    Oldest,
+    /// CVEs of the lowest severity, from CVSS v3 or else v2, then the oldest among those. Unscored
+    /// CVEs go first.
+    LowestSeverity,
+}
+
+impl PrunePolicy {
+    /// ``ORDER BY`` clause of the ``cve`` table listing CVEs in the order they are pruned
+    fn order_by(&self) -> &'static str {
+        match self {
+            PrunePolicy::Oldest => "published_date, id",
+            PrunePolicy::LowestSeverity => {
+                "CASE base_severity WHEN 'CRITICAL' THEN 4 WHEN 'HIGH' THEN 3 WHEN 'MEDIUM' THEN 2
+                WHEN 'LOW' THEN 1 ELSE 0 END, base_score, published_date, id"
+            }
+        }
+    }
+}
+
+impl fmt::Display for PrunePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrunePolicy::Oldest => write!(f, "oldest"),
+            PrunePolicy::LowestSeverity => write!(f, "lowest-severity"),
+        }
+    }
+}
+
+impl std::str::FromStr for PrunePolicy {
+    type Err = String;
+
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        match policy.trim().to_ascii_lowercase().as_str() {
+            "oldest" => Ok(PrunePolicy::Oldest),
+            "lowest-severity" => Ok(PrunePolicy::LowestSeverity),
+            _ => Err(format!(
+                "unknown policy {:?}, expected oldest or lowest-severity",
+                policy
+            )),
+        }
+    }
+}
+
+/// (De)serialize an optional ``Duration`` as a number of seconds
+#[cfg(feature = "serde")]
+mod optional_secs {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(
+        duration: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match duration {
+            Some(duration) => serializer.serialize_some(&duration.as_secs()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Ok(Option::<u64>::deserialize(deserializer)?.map(Duration::from_secs))
+    }
 }
 
 impl CacheConfig {
@@ -48,9 +400,15 @@ impl CacheConfig {
     /// If `$HOME` can't be determined, stray from the basedir spec and try the OS's temporary
     /// directory. Failing that, set a relative path.
     pub fn default_db_path() -> String {
+        Self::default_db_path_for_source(DEFAULT_SOURCE)
+    }
+
+    /// Same as ``default_db_path()`` but for the database of the named ``source``, e.g.
+    /// ``$XDG_CACHE_HOME/nvd/<source>.sqlite3``.
+    pub fn default_db_path_for_source(source: &str) -> String {
         let mut path = std::path::PathBuf::new();
         let cache_namespace = "nvd";
-        let db_name = "nvd.sqlite3";
+        let db_name = format!("{}.sqlite3", source);
 
         // Try $XDG_CACHE_HOME
         if let Ok(xdg_cache_home) = env::var("XDG_CACHE_HOME") {
@@ -65,7 +423,7 @@ impl CacheConfig {
         }
 
         path.push(cache_namespace);
-        path.push(db_name);
+        path.push(&db_name);
 
         // Try converting path to string though not all paths may be UTF-8 safe
         if let Some(string_path) = path.to_str() {
@@ -80,15 +438,45 @@ impl CacheConfig {
 
     /// Create a new ``CacheConfig`` with some reasonable defaults.
     pub fn new() -> Self {
-        let mut feeds: Vec<String> = (2002..=2024).map(|x| x.to_string()).collect();
-        feeds.push("recent".to_string());
-        feeds.push("modified".to_string());
+        Self::for_source(DEFAULT_SOURCE)
+    }
+
+    /// Create a new ``CacheConfig`` with some reasonable defaults for the named ``source``, whose
+    /// records are stored in a database of their own.
+    pub fn for_source(source: &str) -> Self {
         Self {
             url: "https://nvd.nist.gov/feeds/json/cve/1.1/".to_string(),
-            feeds,
-            db: Self::default_db_path(),
+            compression: Compression::default(),
+            parse_threads: 0,
+            feeds: FeedName::all(),
+            source: source.to_string(),
+            db: Self::default_db_path_for_source(source),
             show_progress: true,
             force_update: false,
+            attestation_key: None,
+            namespace: String::new(),
+            read_only: false,
+            feed_timeout: None,
+            sync_deadline: None,
+            on_timeout: TimeoutPolicy::default(),
+            change_history_url: None,
+            cpe_match_url: None,
+            cpe_dictionary_url: None,
+            kev_url: None,
+            cwe_url: None,
+            epss_url: None,
+            osv_ecosystems: vec![],
+            osv_url: OSV_URL.to_string(),
+            track_history: false,
+            risk_weights: None,
+            max_db_size: None,
+            prune_policy: PrunePolicy::default(),
+            trace_feeds: false,
+            trace_file: None,
+            metrics_file: None,
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            allowed_hosts: vec![],
+            clock: Arc::new(SystemClock),
         }
     }
 }
@@ -103,15 +491,192 @@ impl fmt::Display for CacheConfig {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "Url: {}\nFeeds: {}\nDB Path: {}\nProgress Bar: {}\n",
+            "Url: {}\nCompression: {}\nParse Threads: {}\nFeeds: {}\nSource: {}\nDB Path: {}\nProgress Bar: {}\nRead Only: {}\n",
             self.url,
-            self.feeds.join(","),
+            self.compression,
+            match self.parse_threads {
+                0 => "auto".to_string(),
+                threads => threads.to_string(),
+            },
+            self.feeds
+                .iter()
+                .map(|feed| feed.to_string())
+                .collect::<Vec<String>>()
+                .join(","),
+            self.source,
             self.db,
-            self.show_progress
+            self.show_progress,
+            self.read_only
         )
     }
 }
 
+/// Summary of the changes made to the local cache by a sync.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SyncReport {
+    /// Feeds that were fetched and synced, in the order they were synced.
+    pub updated_feeds: Vec<FeedName>,
+
+    /// Feeds that weren't fetched as the cache already held their latest version.
+    pub skipped_feeds: Vec<FeedName>,
+
+    /// Number of CVEs inserted or updated, whether or not their data changed.
+    pub cves_updated: usize,
+
+    /// Number of the ``cves_updated`` that weren't in the cache yet.
+    pub cves_added: usize,
+
+    /// Number of the ``cves_updated`` that were in the cache with different data.
+    pub cves_modified: usize,
+
+    /// Number of CVEs left as they were, since they have been modified after the feed.
+    pub cves_skipped: usize,
+
+    /// Changes made by each of the ``updated_feeds``, in the order they were synced.
+    pub feed_changes: Vec<FeedChanges>,
+
+    /// Feeds that weren't synced as they timed out, with the ``Continue`` timeout policy.
+    pub timed_out_feeds: Vec<FeedName>,
+
+    /// Number of CVEs pruned to keep the cache within its ``max_db_size``.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub cves_pruned: usize,
+
+    /// Time spent in each stage of the sync.
+    pub timings: SyncTimings,
+
+    /// Time spent in each stage on each of the updated and skipped feeds, in the order they were
+    /// written, if the sync traced its feeds. Feeds that timed out aren't listed.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub feed_timings: Vec<FeedTimings>,
+}
+
+impl SyncReport {
+    /// Account for the changes made by the sync of a feed
+    fn add_feed_changes(&mut self, changes: FeedChanges) {
+        debug!(
+            "[Feed: {}] {} CVEs added, {} modified, {} unchanged, {} skipped",
+            changes.feed, changes.added, changes.modified, changes.unchanged, changes.skipped
+        );
+        self.cves_skipped += changes.skipped;
+        self.cves_updated += changes.updated();
+        self.cves_added += changes.added;
+        self.cves_modified += changes.modified;
+        self.feed_changes.push(changes);
+    }
+}
+
+/// Time spent by a sync downloading, decompressing, parsing and writing feeds. The stages work on
+/// different feeds at the same time, so a sync takes about as long as its slowest stage rather
+/// than the sum of all of them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SyncTimings {
+    /// Time spent fetching Metafiles and feeds.
+    pub download: Duration,
+
+    /// Time spent decompressing feeds.
+    pub decompress: Duration,
+
+    /// Time spent parsing feeds.
+    pub parse: Duration,
+
+    /// Time spent writing CVEs and Metafiles to the local cache.
+    pub write: Duration,
+}
+
+/// When a stage of a sync started working on a feed, relative to the start of the sync, and how
+/// long it took.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StageSpan {
+    pub start: Duration,
+    pub duration: Duration,
+}
+
+impl StageSpan {
+    /// The span of a stage that started at ``start`` and just finished, in a sync started at
+    /// ``epoch``
+    pub(crate) fn since(epoch: Instant, start: Instant) -> Self {
+        Self {
+            start: start.saturating_duration_since(epoch),
+            duration: start.elapsed(),
+        }
+    }
+}
+
+/// Time each stage of a sync spent on a feed. Stages that had nothing to do, such as decompressing
+/// a feed that wasn't fetched as the cache held its latest version, have no span.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FeedTimings {
+    /// The feed the stages worked on.
+    pub feed: FeedName,
+
+    /// Fetching the Metafile and the feed.
+    pub download: Option<StageSpan>,
+
+    pub decompress: Option<StageSpan>,
+
+    pub parse: Option<StageSpan>,
+
+    /// Writing the CVEs and the Metafile to the local cache.
+    pub write: Option<StageSpan>,
+}
+
+impl FeedTimings {
+    pub(crate) fn new(feed: FeedName) -> Self {
+        Self {
+            feed,
+            download: None,
+            decompress: None,
+            parse: None,
+            write: None,
+        }
+    }
+}
+
+/// Number of CVEs added, modified, unchanged or skipped by the sync of a feed. Spikes in
+/// ``modified`` usually follow a mass re-scoring by the NVD.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FeedChanges {
+    /// The feed that was synced.
+    pub feed: FeedName,
+
+    /// Number of CVEs that weren't in the cache yet.
+    pub added: usize,
+
+    /// Number of CVEs that were in the cache with different data, scores, configurations or last
+    /// modification date.
+    pub modified: usize,
+
+    /// Number of CVEs that were in the cache as they are in the feed.
+    pub unchanged: usize,
+
+    /// Number of CVEs left as they were, since they have been modified after the feed.
+    pub skipped: usize,
+}
+
+impl FeedChanges {
+    /// No changes made yet by the sync of ``feed``
+    pub(crate) fn new(feed: FeedName) -> Self {
+        Self {
+            feed,
+            added: 0,
+            modified: 0,
+            unchanged: 0,
+            skipped: 0,
+        }
+    }
+
+    /// Number of CVEs inserted or updated, whether or not their data changed.
+    pub fn updated(&self) -> usize {
+        self.added + self.modified + self.unchanged
+    }
+}
+
 /// Errors related to Cache
 #[derive(Debug)]
 pub enum CacheError {
@@ -120,6 +685,25 @@ pub enum CacheError {
     MetafileError(MetafileError),
     HttpError(HttpError),
     JsonError(serde_json::Error),
+    /// The cache is ``read_only`` and can't be written to.
+    ReadOnly,
+    /// Fetching the feed exceeded the ``feed_timeout``, or the ``sync_deadline`` passed before it
+    /// was fetched, with the ``Abort`` timeout policy.
+    TimedOut(FeedName),
+}
+
+impl CacheError {
+    /// Returns ``true`` if the error is due to a CVE missing from the cache, including a cache
+    /// that was never synced.
+    pub fn is_not_found(&self) -> bool {
+        match self {
+            CacheError::RusqliteError(rusqlite::Error::QueryReturnedNoRows) => true,
+            CacheError::RusqliteError(rusqlite::Error::SqliteFailure(_, Some(message))) => {
+                message.starts_with("no such table")
+            }
+            _ => false,
+        }
+    }
 }
 
 impl From<io::Error> for CacheError {
@@ -152,8 +736,280 @@ impl From<serde_json::Error> for CacheError {
     }
 }
 
+/// Open the local cache, strictly read-only if the config is ``read_only``. Foreign keys are
+/// enforced, which SQLite leaves off by default, so deleting a CVE cascades to its derived rows.
+pub(crate) fn open(config: &CacheConfig) -> Result<Connection, CacheError> {
+    let conn = if config.read_only {
+        let flags = OpenFlags::SQLITE_OPEN_READ_ONLY
+            | OpenFlags::SQLITE_OPEN_URI
+            | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+        Connection::open_with_flags(&config.db, flags)?
+    } else {
+        Connection::open(&config.db)?
+    };
+    conn.pragma_update(None, "foreign_keys", true)?;
+    Ok(conn)
+}
+
+/// Fail with ``CacheError::ReadOnly`` if the config is ``read_only``, otherwise create the schema
+/// of the local cache before writing to it
+pub(crate) fn writable_schema(config: &CacheConfig) -> Result<(), CacheError> {
+    if config.read_only {
+        return Err(CacheError::ReadOnly);
+    }
+    create_schema(&config.db)
+}
+
+/// Create the schema of the local cache before reading it, unless the config is ``read_only`` in
+/// which case the cache is read as it is
+pub(crate) fn readable_schema(config: &CacheConfig) -> Result<(), CacheError> {
+    if config.read_only {
+        return Ok(());
+    }
+    create_schema(&config.db)
+}
+
+/// Names of the columns of ``table``, none if the table doesn't exist
+fn table_columns(conn: &Connection, table: &str) -> Result<Vec<String>, CacheError> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = stmt
+        .query_map([], |row| row.get::<_, String>("name"))?
+        .collect::<Result<Vec<String>>>()?;
+    stmt.finalize()?;
+    Ok(columns)
+}
+
+/// Schema of the ``cve_history`` table, whose versions reference their data in ``blobs``
+const CVE_HISTORY_TABLE: &str = "CREATE TABLE cve_history (
+    cve VARCHAR NOT NULL,
+    last_modified_date VARCHAR NOT NULL,
+    blob VARCHAR NOT NULL REFERENCES blobs (hash),
+    replaced VARCHAR NOT NULL,
+    published_date VARCHAR,
+    base_severity VARCHAR,
+    base_score REAL,
+    PRIMARY KEY (cve, last_modified_date))";
+
+/// Schema of the ``overrides`` table, keyed by namespace, see ``CacheConfig::namespace``
+const OVERRIDES_TABLE: &str = "CREATE TABLE overrides (
+    namespace VARCHAR NOT NULL DEFAULT '',
+    id VARCHAR NOT NULL,
+    severity VARCHAR,
+    affected INTEGER,
+    note TEXT,
+    updated VARCHAR NOT NULL,
+    tags TEXT,
+    advisory VARCHAR,
+    PRIMARY KEY (namespace, id))";
+
+/// Schema of the ``risk_scores`` table, keyed by namespace as they are computed from the tags of
+/// the overrides of each
+const RISK_SCORES_TABLE: &str = "CREATE TABLE risk_scores (
+    namespace VARCHAR NOT NULL DEFAULT '',
+    cve VARCHAR NOT NULL REFERENCES cve (id) ON DELETE CASCADE,
+    score REAL NOT NULL,
+    PRIMARY KEY (namespace, cve))";
+
+/// Hash addressing ``data`` in the ``blobs`` table, the hex SHA-256 digest of the data
+pub(crate) fn blob_hash(data: &str) -> String {
+    hex::encode(Sha256::digest(data.as_bytes()))
+}
+
+/// Move the data of the versions kept in the ``cve_history`` table of a cache created by an older
+/// version into ``blobs``, then rebuild the table without its ``data`` column
+fn move_history_to_blobs(conn: &Connection) -> Result<(), CacheError> {
+    if !table_columns(conn, "cve_history")?
+        .iter()
+        .any(|column| column == "data")
+    {
+        return Ok(());
+    }
+    debug!("Moving the data of the kept versions of CVEs to the blobs table");
+
+    let tx = Transaction::new_unchecked(conn, TransactionBehavior::Immediate)?;
+    tx.execute_batch(&format!(
+        "ALTER TABLE cve_history RENAME TO cve_history_data; {};",
+        CVE_HISTORY_TABLE
+    ))?;
+    {
+        let mut versions = tx.prepare(
+            "SELECT cve, last_modified_date, data, replaced, published_date, base_severity,
+                base_score
+            FROM cve_history_data",
+        )?;
+        let mut blob_stmt =
+            tx.prepare("INSERT OR IGNORE INTO blobs (hash, data) VALUES (?1, ?2)")?;
+        let mut history_stmt = tx.prepare(
+            "INSERT INTO cve_history (
+                cve, last_modified_date, blob, replaced, published_date, base_severity, base_score
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        )?;
+        let mut rows = versions.query([])?;
+        while let Some(row) = rows.next()? {
+            let data: String = row.get("data")?;
+            let hash = blob_hash(&data);
+            blob_stmt.execute(params![hash, data])?;
+            history_stmt.execute(params![
+                row.get::<_, String>("cve")?,
+                row.get::<_, String>("last_modified_date")?,
+                hash,
+                row.get::<_, String>("replaced")?,
+                row.get::<_, Option<String>>("published_date")?,
+                row.get::<_, Option<String>>("base_severity")?,
+                row.get::<_, Option<f64>>("base_score")?,
+            ])?;
+        }
+    }
+    tx.execute("DROP TABLE cve_history_data", [])?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Rebuild the ``overrides`` and ``risk_scores`` tables of a cache created by an older version with
+/// a ``namespace`` column, moving their rows to the default namespace
+fn add_namespaces(conn: &Connection) -> Result<(), CacheError> {
+    let tables = [
+        (
+            "overrides",
+            OVERRIDES_TABLE,
+            "id, severity, affected, note, updated, tags, advisory",
+        ),
+        ("risk_scores", RISK_SCORES_TABLE, "cve, score"),
+    ];
+    for (table, schema, columns) in tables {
+        if table_columns(conn, table)?
+            .iter()
+            .any(|column| column == "namespace")
+        {
+            continue;
+        }
+        debug!(
+            "Moving the rows of the {} table to the default namespace",
+            table
+        );
+
+        let tx = Transaction::new_unchecked(conn, TransactionBehavior::Immediate)?;
+        tx.execute_batch(&format!(
+            "ALTER TABLE {table} RENAME TO {table}_unnamespaced;
+            {schema};
+            INSERT INTO {table} ({columns}) SELECT {columns} FROM {table}_unnamespaced;
+            DROP TABLE {table}_unnamespaced;",
+            table = table,
+            schema = schema,
+            columns = columns
+        ))?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+/// Whether the ``overrides`` and ``risk_scores`` tables of the cache opened by ``conn`` are keyed
+/// by namespace, which read-only caches made by older versions aren't
+pub(crate) fn has_namespaces(conn: &Connection) -> Result<bool, CacheError> {
+    Ok(table_columns(conn, "overrides")?
+        .iter()
+        .any(|column| column == "namespace"))
+}
+
+/// Namespace whose risk scores the queries on the cache opened by ``conn`` match, ``None`` for
+/// read-only caches made by older versions, whose scores aren't keyed by namespace
+pub(crate) fn risk_namespace<'a>(
+    conn: &Connection,
+    config: &'a CacheConfig,
+) -> Result<Option<&'a str>, CacheError> {
+    Ok(has_namespaces(conn)?.then_some(config.namespace.as_str()))
+}
+
+/// A date of a feed item as stored in the ``cve`` and ``cve_history`` tables, formatted by
+/// ``format_datetime()`` so that dates compare as text. Dates that don't parse are stored as given.
+fn cached_date(date: &str) -> String {
+    parse_datetime(date)
+        .map(|parsed| format_datetime(&parsed))
+        .unwrap_or_else(|_| date.to_string())
+}
+
+/// Schema version recorded last in the ``migration`` table of the cache opened by ``conn``
+fn schema_version(conn: &Connection) -> Result<Option<String>, CacheError> {
+    Ok(conn
+        .query_row(
+            "SELECT schema_version FROM migration ORDER BY rowid DESC LIMIT 1",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+/// Rewrite the dates of the CVEs and kept versions cached by older versions as the feeds give
+/// them, e.g. ``2021-12-10T10:15Z``, as ``cached_date()`` does, then record the
+/// ``SCHEMA_VERSION`` of the cache
+fn normalize_dates(conn: &Connection) -> Result<(), CacheError> {
+    debug!("Formatting the dates of the cached CVEs alike");
+
+    let tx = Transaction::new_unchecked(conn, TransactionBehavior::Immediate)?;
+    for (table, key) in [("cve", "id"), ("cve_history", "rowid")] {
+        let mut select = tx.prepare(&format!(
+            "SELECT {}, published_date, last_modified_date FROM {}",
+            key, table
+        ))?;
+        // Kept versions are keyed by their last modified date, a version rewritten alike to one
+        // kept already is the same version
+        let mut update = tx.prepare(&format!(
+            "UPDATE OR REPLACE {} SET published_date = ?2, last_modified_date = ?3 WHERE {} = ?1",
+            table, key
+        ))?;
+        let rows = select
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, Value>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        for (key, published, last_modified) in rows {
+            let normalized = (
+                published.as_deref().map(cached_date),
+                last_modified.as_deref().map(cached_date),
+            );
+            if normalized != (published, last_modified) {
+                update.execute(params![key, normalized.0, normalized.1])?;
+            }
+        }
+    }
+    tx.execute(
+        "INSERT OR REPLACE INTO migration (schema_version, app_version, status) VALUES (?1, ?2, 0)",
+        [
+            SCHEMA_VERSION,
+            option_env!("CARGO_PKG_VERSION").unwrap_or("?.?.?"),
+        ],
+    )?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Add any of the ``columns`` missing from ``table``, returning the number of columns added
+fn add_missing_columns(
+    conn: &Connection,
+    table: &str,
+    columns: &[(&str, &str)],
+) -> Result<usize, CacheError> {
+    let existing = table_columns(conn, table)?;
+
+    let mut added = 0;
+    for (name, kind) in columns {
+        if !existing.iter().any(|column| column == name) {
+            conn.execute(
+                &format!("ALTER TABLE {} ADD COLUMN {} {}", table, name, kind),
+                [],
+            )?;
+            added += 1;
+        }
+    }
+    Ok(added)
+}
+
 /// Create ``Metafile`` and CVE tables for local cache
-fn create_schema(path: &str) -> Result<(), CacheError> {
+pub(crate) fn create_schema(path: &str) -> Result<(), CacheError> {
     let mut db_path = PathBuf::from(&path);
     db_path.pop();
     fs::create_dir_all(db_path)?;
@@ -163,7 +1019,9 @@ fn create_schema(path: &str) -> Result<(), CacheError> {
     let mut tbl_stmt =
         conn.prepare("SELECT name FROM sqlite_master where type = 'table' and name = ?;")?;
 
-    if !tbl_stmt.exists(["cve"])? {
+    let cve_existed = tbl_stmt.exists(["cve"])?;
+
+    if !cve_existed {
         conn.execute(
             "CREATE TABLE cve (
                id VARCHAR PRIMARY KEY,
@@ -186,80 +1044,457 @@ fn create_schema(path: &str) -> Result<(), CacheError> {
         )?;
     }
 
-    if !tbl_stmt.exists(["migration"])? {
+    if !tbl_stmt.exists(["attestation"])? {
         conn.execute(
-            "CREATE TABLE migration (
-                schema_version VARCHAR PRIMARY KEY,
-                app_version VARCHAR NOT NULL,
-                status INTEGER NOT NULL)",
+            "CREATE TABLE attestation (
+                id INTEGER PRIMARY KEY,
+                finished VARCHAR NOT NULL,
+                data TEXT NOT NULL)",
             [],
         )?;
+    }
+
+    if !tbl_stmt.exists(["sync_run"])? {
         conn.execute(
-            "INSERT into migration (schema_version, app_version, status) values (?1, ?2, 0)",
-            [
-                SCHEMA_VERSION,
-                option_env!("CARGO_PKG_VERSION").unwrap_or("?.?.?"),
-            ],
+            "CREATE TABLE sync_run (
+                id INTEGER PRIMARY KEY,
+                started VARCHAR NOT NULL,
+                finished VARCHAR NOT NULL,
+                cves_updated INTEGER NOT NULL,
+                cves_skipped INTEGER NOT NULL)",
+            [],
         )?;
     }
 
-    tbl_stmt.finalize()?;
+    if !tbl_stmt.exists(["sync_feed"])? {
+        conn.execute(
+            "CREATE TABLE sync_feed (
+                run INTEGER NOT NULL REFERENCES sync_run (id) ON DELETE CASCADE,
+                position INTEGER NOT NULL,
+                feed VARCHAR NOT NULL,
+                added INTEGER NOT NULL,
+                modified INTEGER NOT NULL,
+                unchanged INTEGER NOT NULL,
+                skipped INTEGER NOT NULL,
+                PRIMARY KEY (run, position))",
+            [],
+        )?;
+    }
 
-    match conn.close() {
-        Ok(_) => Ok(()),
-        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    // Tables derived from the CVEs must reference ``cve (id) ON DELETE CASCADE``, for
+    // ``delete_cves()`` to leave no rows behind
+    let exploit_refs_existed = tbl_stmt.exists(["exploit_refs"])?;
+    if !exploit_refs_existed {
+        conn.execute(
+            "CREATE TABLE exploit_refs (
+                cve VARCHAR NOT NULL REFERENCES cve (id) ON DELETE CASCADE,
+                url VARCHAR NOT NULL,
+                source VARCHAR NOT NULL,
+                PRIMARY KEY (cve, url))",
+            [],
+        )?;
     }
-}
 
-/// Get all cached ``Metafiles``
-fn get_metafiles(config: &CacheConfig) -> Result<Vec<Feed>, CacheError> {
-    let conn = Connection::open(&config.db)?;
+    // CPE matches are indexed by the product and vendor they name, for the configurations that may
+    // apply to a platform to be found without parsing all of them. They are derived from the
+    // cached configurations, so an existing cache is indexed without fetching the feeds again.
+    let cpe_match_existed = tbl_stmt.exists(["cpe_match"])?;
+    if !cpe_match_existed {
+        conn.execute_batch(
+            "CREATE TABLE cpe_match (
+                cve VARCHAR NOT NULL REFERENCES cve (id) ON DELETE CASCADE,
+                product VARCHAR NOT NULL,
+                vendor VARCHAR NOT NULL,
+                part VARCHAR NOT NULL,
+                PRIMARY KEY (cve, product, vendor, part));
+            CREATE INDEX cpe_match_product ON cpe_match (product, vendor);",
+        )?;
+    }
 
-    let mut stmt = conn.prepare("SELECT * FROM metafile where feed=?1")?;
+    // Weaknesses are derived from the cached problem types, like CPE matches, and named by the CWE
+    // list once it is synced
+    let cve_cwe_existed = tbl_stmt.exists(["cve_cwe"])?;
+    if !cve_cwe_existed {
+        conn.execute_batch(
+            "CREATE TABLE cve_cwe (
+                cve VARCHAR NOT NULL REFERENCES cve (id) ON DELETE CASCADE,
+                cwe VARCHAR NOT NULL,
+                PRIMARY KEY (cve, cwe));
+            CREATE INDEX cve_cwe_cwe ON cve_cwe (cwe);",
+        )?;
+    }
 
-    let cached_feeds = config
-        .feeds
-        .iter()
-        .map(|name| {
-            let meta = stmt
-                .query_row([&name], |row| {
-                    let last_modified_row: String =
-                        row.get("last_modified_date").unwrap_or_default();
-                    let last_modified_date = Metafile::parse_datetime(last_modified_row.as_str());
-                    let metafile = Metafile {
-                        last_modified_date,
-                        size: row.get("size").unwrap_or_default(),
-                        zip_size: row.get("zip_size").unwrap_or_default(),
-                        gz_size: row.get("gz_size").unwrap_or_default(),
-                        sha256: row.get("sha256").unwrap_or_default(),
-                    };
-                    Ok(metafile)
-                })
-                .ok();
-            Feed {
-                name: name.clone(),
-                metafile: meta,
-            }
-        })
-        .collect();
+    // References are indexed by their host and path, for the CVEs citing an advisory, a
+    // repository or any page of a domain to be found without reading the CVEs
+    let cve_reference_existed = tbl_stmt.exists(["cve_reference"])?;
+    if !cve_reference_existed {
+        conn.execute_batch(
+            "CREATE TABLE cve_reference (
+                cve VARCHAR NOT NULL REFERENCES cve (id) ON DELETE CASCADE,
+                url VARCHAR NOT NULL,
+                host VARCHAR NOT NULL,
+                path VARCHAR NOT NULL,
+                PRIMARY KEY (cve, url));
+            CREATE INDEX cve_reference_host ON cve_reference (host, path);",
+        )?;
+    }
+    if !tbl_stmt.exists(["cwe"])? {
+        conn.execute(
+            "CREATE TABLE cwe (
+                id VARCHAR PRIMARY KEY,
+                name VARCHAR NOT NULL)",
+            [],
+        )?;
+    }
 
-    stmt.finalize()?;
+    // Risk scores are computed from the cached CVEs by ``score_cves()``, rather than synced, so
+    // a cache missing them is scored without fetching the feeds again
+    if !tbl_stmt.exists(["risk_scores"])? {
+        conn.execute(RISK_SCORES_TABLE, [])?;
+    }
 
-    match conn.close() {
-        Ok(_) => Ok(cached_feeds),
-        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    // Local overrides aren't derived from the CVEs and are never written by a sync, so they
+    // survive any number of them
+    if !tbl_stmt.exists(["overrides"])? {
+        conn.execute(OVERRIDES_TABLE, [])?;
     }
-}
 
-/// Update or insert ``Metafile``
-fn update_metafile(
-    config: &CacheConfig,
-    feed: &str,
-    metafile: &Metafile,
-) -> Result<(), CacheError> {
-    let conn = Connection::open(&config.db)?;
-    let upsert_sql = "
-        insert into
+    // Aliases and the statuses vendors state in their advisories aren't derived from the feeds
+    // either, and are kept for CVEs that aren't cached
+    if !tbl_stmt.exists(["aliases"])? {
+        conn.execute_batch(
+            "CREATE TABLE aliases (
+                alias VARCHAR NOT NULL,
+                cve VARCHAR NOT NULL,
+                source VARCHAR NOT NULL,
+                PRIMARY KEY (alias, cve));
+            CREATE INDEX aliases_cve ON aliases (cve);",
+        )?;
+    }
+
+    if !tbl_stmt.exists(["advisory_status"])? {
+        conn.execute_batch(
+            "CREATE TABLE advisory_status (
+                advisory VARCHAR NOT NULL,
+                cve VARCHAR NOT NULL,
+                product VARCHAR NOT NULL,
+                status VARCHAR NOT NULL,
+                PRIMARY KEY (advisory, cve, product, status));
+            CREATE INDEX advisory_status_cve ON advisory_status (cve);",
+        )?;
+    }
+
+    // OSV advisories are keyed by package and range of versions rather than by CPE name, the
+    // ranges of an advisory are replaced along with it
+    if !tbl_stmt.exists(["osv"])? {
+        conn.execute_batch(
+            "CREATE TABLE osv (
+                id VARCHAR PRIMARY KEY,
+                modified VARCHAR NOT NULL,
+                summary TEXT,
+                data TEXT NOT NULL);
+            CREATE TABLE osv_affected (
+                id VARCHAR NOT NULL REFERENCES osv (id) ON DELETE CASCADE,
+                ecosystem VARCHAR NOT NULL,
+                package VARCHAR NOT NULL,
+                introduced VARCHAR,
+                fixed VARCHAR,
+                last_affected VARCHAR);
+            CREATE INDEX osv_affected_package ON osv_affected (ecosystem, package);
+            CREATE INDEX osv_affected_id ON osv_affected (id);",
+        )?;
+    }
+
+    // Tickets are kept when their CVE is deleted, so that it isn't ticketed again once synced back
+    if !tbl_stmt.exists(["tickets"])? {
+        conn.execute(
+            "CREATE TABLE tickets (
+                tracker VARCHAR NOT NULL,
+                cve VARCHAR NOT NULL,
+                key VARCHAR,
+                created VARCHAR NOT NULL,
+                PRIMARY KEY (tracker, cve))",
+            [],
+        )?;
+    }
+
+    // Change events are kept for any CVE, cached or not, so they don't reference the CVEs
+    if !tbl_stmt.exists(["cve_changes"])? {
+        conn.execute_batch(
+            "CREATE TABLE cve_changes (
+                id VARCHAR PRIMARY KEY,
+                cve VARCHAR NOT NULL,
+                created VARCHAR NOT NULL,
+                data TEXT NOT NULL);
+            CREATE INDEX cve_changes_cve ON cve_changes (cve, created);",
+        )?;
+    }
+
+    // Replaced versions are kept once their CVE is deleted, like change events, so they don't
+    // reference the CVEs either. Their data is kept in the content-addressed ``blobs`` table so
+    // that identical versions are stored once
+    if !tbl_stmt.exists(["blobs"])? {
+        conn.execute(
+            "CREATE TABLE blobs (
+                hash VARCHAR PRIMARY KEY,
+                data TEXT NOT NULL)",
+            [],
+        )?;
+    }
+
+    if !tbl_stmt.exists(["cve_history"])? {
+        conn.execute(CVE_HISTORY_TABLE, [])?;
+    }
+
+    if !tbl_stmt.exists(["cve_changes_sync"])? {
+        conn.execute(
+            "CREATE TABLE cve_changes_sync (
+                id INTEGER PRIMARY KEY,
+                until VARCHAR NOT NULL)",
+            [],
+        )?;
+    }
+
+    // The CPE names matched by the criteria of CPE matches are kept for any criteria, whether a
+    // cached CVE cites it or not, and replaced as a whole by each sync of the CPE match feed
+    if !tbl_stmt.exists(["cpe_match_criteria"])? {
+        conn.execute_batch(
+            "CREATE TABLE cpe_match_criteria (
+                criteria VARCHAR PRIMARY KEY,
+                product VARCHAR NOT NULL,
+                names TEXT NOT NULL);
+            CREATE INDEX cpe_match_criteria_product ON cpe_match_criteria (product);
+            CREATE TABLE cpe_match_criteria_sync (
+                id INTEGER PRIMARY KEY,
+                sha256 VARCHAR NOT NULL);",
+        )?;
+    }
+
+    // The names of the CPE dictionary are replaced as a whole by each sync of it
+    if !tbl_stmt.exists(["cpe_dictionary"])? {
+        conn.execute_batch(
+            "CREATE TABLE cpe_dictionary (
+                name VARCHAR PRIMARY KEY,
+                vendor VARCHAR NOT NULL,
+                product VARCHAR NOT NULL,
+                title VARCHAR,
+                deprecated INTEGER NOT NULL);
+            CREATE INDEX cpe_dictionary_product ON cpe_dictionary (product, vendor);
+            CREATE TABLE cpe_dictionary_sync (
+                id INTEGER PRIMARY KEY,
+                sha256 VARCHAR NOT NULL);",
+        )?;
+    }
+
+    // The catalog lists CVEs whether they are cached or not, and is replaced as a whole by each
+    // sync of it
+    if !tbl_stmt.exists(["kev"])? {
+        conn.execute(
+            "CREATE TABLE kev (
+                cve VARCHAR PRIMARY KEY,
+                date_added VARCHAR NOT NULL,
+                due_date VARCHAR NOT NULL,
+                data TEXT NOT NULL)",
+            [],
+        )?;
+    }
+
+    // Scores are replaced as a whole by each sync of them, the model version and date of those
+    // cached telling whether the next scores published are new
+    if !tbl_stmt.exists(["epss"])? {
+        conn.execute_batch(
+            "CREATE TABLE epss (
+                cve VARCHAR PRIMARY KEY,
+                score REAL NOT NULL,
+                percentile REAL NOT NULL);
+            CREATE TABLE epss_sync (
+                id INTEGER PRIMARY KEY,
+                model_version VARCHAR,
+                score_date VARCHAR);",
+        )?;
+    }
+
+    // A single row counting the changes made to the tables searched, see ``bump_generation()``
+    if !tbl_stmt.exists(["generation"])? {
+        conn.execute(GENERATION_TABLE, [])?;
+    }
+
+    // Caches recorded at an older schema version are migrated once their columns are added
+    let migration_existed = tbl_stmt.exists(["migration"])?;
+    let migrated = match migration_existed {
+        true => schema_version(&conn)?.as_deref() == Some(SCHEMA_VERSION),
+        false => !cve_existed,
+    };
+    if !migration_existed {
+        conn.execute(
+            "CREATE TABLE migration (
+                schema_version VARCHAR PRIMARY KEY,
+                app_version VARCHAR NOT NULL,
+                status INTEGER NOT NULL)",
+            [],
+        )?;
+        conn.execute(
+            "INSERT into migration (schema_version, app_version, status) values (?1, ?2, 0)",
+            [
+                SCHEMA_VERSION,
+                option_env!("CARGO_PKG_VERSION").unwrap_or("?.?.?"),
+            ],
+        )?;
+    }
+
+    tbl_stmt.finalize()?;
+
+    add_missing_columns(&conn, "overrides", OVERRIDE_COLUMNS)?;
+    add_namespaces(&conn)?;
+
+    // Deleting CVEs looks up the risk scores referencing them
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS risk_scores_cve ON risk_scores (cve)",
+        [],
+    )?;
+    add_missing_columns(&conn, "cve_history", CVE_HISTORY_COLUMNS)?;
+    move_history_to_blobs(&conn)?;
+
+    // Columns and tables added to an existing cache can only be populated by fetching the feeds
+    // again
+    let columns_added = add_missing_columns(&conn, "cve", CVE_COLUMNS)? > 0;
+    if (columns_added || !exploit_refs_existed) && cve_existed {
+        debug!("Added new columns or tables to existing cache, all feeds will be synced again");
+        conn.execute("DELETE FROM metafile", [])?;
+    }
+
+    if !migrated {
+        normalize_dates(&conn)?;
+    }
+
+    for (name, columns) in CVE_INDEXES {
+        conn.execute(
+            &format!("CREATE INDEX IF NOT EXISTS {} ON cve ({})", name, columns),
+            [],
+        )?;
+    }
+
+    if !cpe_match_existed && cve_existed {
+        debug!("Indexing the CPE matches of the cached configurations");
+        let tx = Transaction::new_unchecked(&conn, TransactionBehavior::Immediate)?;
+        rebuild_cpe_matches(&tx, "SELECT id FROM cve")?;
+        tx.commit()?;
+    }
+
+    if !cve_cwe_existed && cve_existed {
+        debug!("Indexing the weaknesses of the cached CVEs");
+        let tx = Transaction::new_unchecked(&conn, TransactionBehavior::Immediate)?;
+        rebuild_cwes(&tx, "SELECT id FROM cve")?;
+        tx.commit()?;
+    }
+
+    if !cve_reference_existed && cve_existed {
+        debug!("Indexing the references of the cached CVEs");
+        let tx = Transaction::new_unchecked(&conn, TransactionBehavior::Immediate)?;
+        rebuild_references(&tx, "SELECT id FROM cve")?;
+        tx.commit()?;
+    }
+
+    match conn.close() {
+        Ok(_) => Ok(()),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Schema of the ``generation`` table, whose single row counts the changes made to the tables
+/// searched
+const GENERATION_TABLE: &str = "CREATE TABLE IF NOT EXISTS generation (
+    id INTEGER PRIMARY KEY CHECK (id = 0),
+    value INTEGER NOT NULL)";
+
+/// Bump the generation of the cache opened by ``conn``, telling each ``QueryCache`` of it that
+/// searches may return other results since. Called by every function writing to the tables
+/// searched, within the transaction of its changes. The table is created in caches created by an
+/// older version, such as installed snapshots.
+pub(crate) fn bump_generation(conn: &Connection) -> Result<(), CacheError> {
+    conn.execute_batch(&format!(
+        "{};
+        INSERT INTO generation (id, value) VALUES (0, 1)
+            ON CONFLICT(id) DO UPDATE SET value = value + 1;",
+        GENERATION_TABLE
+    ))?;
+    Ok(())
+}
+
+/// Generation of the cache opened by ``conn``, see ``bump_generation()``. ``None`` until the cache
+/// is first written to, or if the ``generation`` table doesn't exist, as in a read-only cache
+/// created by an older version.
+pub(crate) fn generation(conn: &Connection) -> Result<Option<i64>, CacheError> {
+    let mut stmt = conn
+        .prepare("SELECT name FROM sqlite_master where type = 'table' and name = 'generation'")?;
+    let exists = stmt.exists([])?;
+    stmt.finalize()?;
+    if !exists {
+        return Ok(None);
+    }
+    Ok(conn
+        .query_row("SELECT value FROM generation WHERE id = 0", [], |row| {
+            row.get(0)
+        })
+        .optional()?)
+}
+
+/// Build a ``Metafile`` from a row of the ``metafile`` table. Rows written by older versions hold
+/// the last modified date in UTC without an offset, see ``datetime::parse_datetime()``, dates that
+/// don't parse fail the row rather than being read as some other date.
+fn metafile_from_row(row: &Row) -> Result<Metafile> {
+    let last_modified_row: String = row.get("last_modified_date")?;
+    let last_modified_date = Metafile::parse_datetime(&last_modified_row).map_err(|error| {
+        rusqlite::Error::FromSqlConversionFailure(
+            row.as_ref().column_index("last_modified_date").unwrap_or(1),
+            Type::Text,
+            Box::new(error),
+        )
+    })?;
+    Ok(Metafile {
+        last_modified_date,
+        size: row.get("size").unwrap_or_default(),
+        zip_size: row.get("zip_size").unwrap_or_default(),
+        gz_size: row.get("gz_size").unwrap_or_default(),
+        sha256: row.get("sha256").unwrap_or_default(),
+    })
+}
+
+/// Get all cached ``Metafiles``
+fn get_metafiles(config: &CacheConfig) -> Result<Vec<Feed>, CacheError> {
+    let conn = open(config)?;
+
+    let mut stmt = conn.prepare("SELECT * FROM metafile where feed=?1")?;
+
+    let cached_feeds = config
+        .feeds
+        .iter()
+        .map(|name| {
+            let meta = stmt.query_row([name.to_string()], metafile_from_row).ok();
+            Feed {
+                name: *name,
+                metafile: meta,
+            }
+        })
+        .collect();
+
+    stmt.finalize()?;
+
+    match conn.close() {
+        Ok(_) => Ok(cached_feeds),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Update or insert ``Metafile``
+fn update_metafile(
+    config: &CacheConfig,
+    feed: &FeedName,
+    metafile: &Metafile,
+) -> Result<(), CacheError> {
+    let conn = open(config)?;
+    let upsert_sql = "
+        insert into
         metafile (
             feed,
             last_modified_date,
@@ -280,7 +1515,7 @@ fn update_metafile(
 
     let mut stmt = conn.prepare(upsert_sql)?;
     stmt.insert(params![
-        feed,
+        feed.to_string(),
         metafile.format_last_modified_date(),
         metafile.size,
         metafile.zip_size,
@@ -294,284 +1529,2697 @@ fn update_metafile(
     }
 }
 
-/// Update or insert CVEs from a ``CVEContainer``
-fn update_cves(
-    config: &CacheConfig,
-    cve_feed: &[CveContainer],
-    last_modified_date: Option<&NaiveDateTime>,
-) -> Result<(), CacheError> {
-    let conn = Connection::open(&config.db)?;
-    let upsert_sql = "
-        insert into
-        cve (
-            id,
-            description,
-            data
-        )
-        values
-            (?1, ?2, ?3) on conflict(id) do
-        update
-        set
-            description=?2,
-            data=?3;";
+/// Columns of the ``cve`` table written from each ``CveContainer`` by ``cve_values()``, besides
+/// the ``CVE_COLUMNS``.
+const CVE_BASE_COLUMNS: &[(&str, &str)] = &[
+    ("id", "VARCHAR"),
+    ("description", "VARCHAR"),
+    ("data", "VARCHAR"),
+];
+
+/// Columns of the ``cve`` table compared to tell whether a feed modifies a cached CVE, starting
+/// with its JSON data. The data leaves out the scores and configurations of the CVE, which the NVD
+/// may change on their own when it re-scores CVEs.
+const COMPARED_COLUMNS: &[&str] = &["data", "last_modified_date", "configurations", "impact"];
+
+/// Indexes of the ``COMPARED_COLUMNS`` within the values returned by ``cve_values()``
+fn compared_indexes() -> Vec<usize> {
+    COMPARED_COLUMNS
+        .iter()
+        .filter_map(|compared| {
+            CVE_BASE_COLUMNS
+                .iter()
+                .chain(CVE_COLUMNS)
+                .position(|(column, _)| column == compared)
+        })
+        .collect()
+}
+
+/// Values of all the ``CVE_BASE_COLUMNS`` followed by all the ``CVE_COLUMNS`` for a CVE written
+/// by the sync of ``feed``.
+fn cve_values(cve: &CveContainer, feed: FeedName) -> Vec<Value> {
+    let mut description = None;
+    if !cve.cve.description.description_data.is_empty() {
+        for d in &cve.cve.description.description_data {
+            if d.lang == "en" {
+                description = Some(String::from(&d.value));
+            }
+        }
+    }
+    let metric = cve.impact.base_metric_v3.as_ref();
+    let cvss = metric.map(|m| &m.cvss_v3);
+    let severity = cve.severity();
+
+    let text = |value: Option<&str>| value.map_or(Value::Null, |v| Value::Text(v.to_string()));
+    let real = |value: Option<f64>| value.map_or(Value::Null, Value::Real);
+
+    vec![
+        Value::Text(cve.cve.cve_data_meta.id.clone()),
+        text(description.as_deref()),
+        Value::Text(serde_json::to_string(&cve.cve).unwrap_or_else(|_| "{}".to_string())),
+        text(cvss.map(|c| c.attack_vector.as_str())),
+        text(cvss.map(|c| c.attack_complexity.as_str())),
+        text(cvss.map(|c| c.privileges_required.as_str())),
+        text(cvss.map(|c| c.user_interaction.as_str())),
+        text(cvss.map(|c| c.scope.as_str())),
+        text(cvss.map(|c| c.confidentiality_impact.as_str())),
+        text(cvss.map(|c| c.integrity_impact.as_str())),
+        text(cvss.map(|c| c.availability_impact.as_str())),
+        real(metric.as_ref().map(|m| m.exploitability_score)),
+        real(metric.as_ref().map(|m| m.impact_score)),
+        text(severity.map(|(severity, _)| severity.as_str())),
+        real(severity.map(|(_, score)| score)),
+        Value::Text(cached_date(&cve.published_date)),
+        Value::Text(feed.to_string()),
+        Value::Text(cached_date(&cve.last_modified_date)),
+        // The JSON of the feed is stored as it was given, when it is known
+        Value::Text(match &cve.raw_configurations {
+            Some(configurations) => configurations.to_string(),
+            None => serde_json::to_string(&cve.configurations).unwrap_or_else(|_| "{}".to_string()),
+        }),
+        Value::Text(match &cve.raw_impact {
+            Some(impact) => impact.to_string(),
+            None => serde_json::to_string(&cve.impact).unwrap_or_else(|_| "{}".to_string()),
+        }),
+        Value::Text(cve.cve.cve_data_meta.assigner.clone()),
+    ]
+}
+
+/// Replace the ``exploit_refs`` of each of the ``cves`` with the public exploits they reference
+fn write_exploit_refs<'a, I>(conn: &Connection, cves: I) -> Result<(), CacheError>
+where
+    I: IntoIterator<Item = &'a Cve>,
+{
+    let mut delete = conn.prepare_cached("DELETE FROM exploit_refs WHERE cve = ?1")?;
+    let mut insert = conn.prepare_cached(
+        "INSERT OR IGNORE INTO exploit_refs (cve, url, source) VALUES (?1, ?2, ?3)",
+    )?;
+
+    for cve in cves {
+        let id = &cve.cve_data_meta.id;
+        delete.execute([id])?;
+        for exploit in cve.exploit_refs() {
+            insert.execute(params![id, exploit.url, exploit.source.to_string()])?;
+        }
+    }
+    Ok(())
+}
+
+/// Replace the ``cve_cwe`` rows of each of the ``cves`` with the weaknesses of their problem types
+fn write_cwes<'a, I>(conn: &Connection, cves: I) -> Result<(), CacheError>
+where
+    I: IntoIterator<Item = &'a Cve>,
+{
+    let mut delete = conn.prepare_cached("DELETE FROM cve_cwe WHERE cve = ?1")?;
+    let mut insert =
+        conn.prepare_cached("INSERT OR IGNORE INTO cve_cwe (cve, cwe) VALUES (?1, ?2)")?;
+
+    for cve in cves {
+        let id = &cve.cve_data_meta.id;
+        delete.execute([id])?;
+        for cwe in cve.cwe_ids() {
+            insert.execute(params![id, cwe])?;
+        }
+    }
+    Ok(())
+}
+
+/// Rebuild the ``cve_cwe`` rows of the CVEs whose IDs are selected by the ``ids`` query from their
+/// cached data. CVEs no longer cached lose theirs.
+fn rebuild_cwes(conn: &Connection, ids: &str) -> Result<(), CacheError> {
+    conn.execute(&format!("DELETE FROM cve_cwe WHERE cve IN ({})", ids), [])?;
+
+    let mut stmt = conn.prepare(&format!("SELECT data FROM cve WHERE id IN ({})", ids))?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let cve: Cve = serde_json::from_str(&row.get::<_, String>(0)?)?;
+        write_cwes(conn, [&cve])?;
+    }
+    Ok(())
+}
+
+/// Replace the ``cve_reference`` rows of each of the ``cves`` with the URLs they reference
+fn write_references<'a, I>(conn: &Connection, cves: I) -> Result<(), CacheError>
+where
+    I: IntoIterator<Item = &'a Cve>,
+{
+    let mut delete = conn.prepare_cached("DELETE FROM cve_reference WHERE cve = ?1")?;
+    let mut insert = conn.prepare_cached(
+        "INSERT OR IGNORE INTO cve_reference (cve, url, host, path) VALUES (?1, ?2, ?3, ?4)",
+    )?;
+
+    for cve in cves {
+        let id = &cve.cve_data_meta.id;
+        delete.execute([id])?;
+        for reference in &cve.references.reference_data {
+            if let Some((host, path)) = reference_location(&reference.url) {
+                insert.execute(params![id, reference.url, host, path])?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Rebuild the ``cve_reference`` rows of the CVEs whose IDs are selected by the ``ids`` query from
+/// their cached data. CVEs no longer cached lose theirs.
+fn rebuild_references(conn: &Connection, ids: &str) -> Result<(), CacheError> {
+    conn.execute(
+        &format!("DELETE FROM cve_reference WHERE cve IN ({})", ids),
+        [],
+    )?;
+
+    let mut stmt = conn.prepare(&format!("SELECT data FROM cve WHERE id IN ({})", ids))?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let cve: Cve = serde_json::from_str(&row.get::<_, String>(0)?)?;
+        write_references(conn, [&cve])?;
+    }
+    Ok(())
+}
+
+/// Replace the ``cpe_match`` rows of the CVE ``id`` with the CPE matches of its ``configurations``
+fn write_cpe_matches(
+    conn: &Connection,
+    id: &str,
+    configurations: &Configuration,
+) -> Result<(), CacheError> {
+    let mut delete = conn.prepare_cached("DELETE FROM cpe_match WHERE cve = ?1")?;
+    let mut insert = conn.prepare_cached(
+        "INSERT OR IGNORE INTO cpe_match (cve, product, vendor, part) VALUES (?1, ?2, ?3, ?4)",
+    )?;
+
+    delete.execute([id])?;
+    for cpe in configurations
+        .cpe_matches()
+        .iter()
+        .filter_map(|cpe_match| cpe_match.cpe())
+    {
+        insert.execute(params![
+            id,
+            cpe.product.index_key(),
+            cpe.vendor.index_key(),
+            cpe.part.index_key()
+        ])?;
+    }
+    Ok(())
+}
+
+/// Rebuild the ``cpe_match`` rows of the CVEs whose IDs are selected by the ``ids`` query from
+/// their cached configurations. CVEs no longer cached lose theirs.
+fn rebuild_cpe_matches(conn: &Connection, ids: &str) -> Result<(), CacheError> {
+    conn.execute(&format!("DELETE FROM cpe_match WHERE cve IN ({})", ids), [])?;
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, configurations FROM cve WHERE configurations IS NOT NULL AND id IN ({})",
+        ids
+    ))?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let id: String = row.get(0)?;
+        let configurations: Configuration = serde_json::from_str(&row.get::<_, String>(1)?)?;
+        write_cpe_matches(conn, &id, &configurations)?;
+    }
+    Ok(())
+}
+
+/// Same as ``write_exploit_refs()``, ``write_cwes()``, ``write_references()`` and
+/// ``write_cpe_matches()`` in a transaction of its own, once the CVEs were imported
+#[cfg(feature = "bulk-import")]
+fn import_derived_rows<'a, I>(config: &CacheConfig, cves: I) -> Result<(), CacheError>
+where
+    I: IntoIterator<Item = &'a CveContainer>,
+{
+    let mut conn = open(config)?;
+    let tx = Transaction::new(&mut conn, TransactionBehavior::Immediate)?;
+    for cve in cves {
+        write_exploit_refs(&tx, [&cve.cve])?;
+        write_cwes(&tx, [&cve.cve])?;
+        write_references(&tx, [&cve.cve])?;
+        write_cpe_matches(&tx, &cve.cve.cve_data_meta.id, &cve.configurations)?;
+    }
+    bump_generation(&tx)?;
+    tx.commit()?;
+
+    match conn.close() {
+        Ok(_) => Ok(()),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Rebuild the ``exploit_refs``, ``cve_cwe``, ``cve_reference`` and ``cpe_match`` rows of the CVEs
+/// whose IDs are selected by the ``ids`` query from their cached data, after they were written by
+/// something else than a sync. CVEs no longer cached lose theirs. Caches without the tables are
+/// left as they are.
+#[cfg(feature = "snapshot")]
+pub(crate) fn rebuild_derived_rows(conn: &Connection, ids: &str) -> Result<(), CacheError> {
+    let mut tbl_stmt =
+        conn.prepare("SELECT name FROM sqlite_master where type = 'table' and name = ?1")?;
+    let exploit_refs = tbl_stmt.exists(["exploit_refs"])?;
+    let cve_cwe = tbl_stmt.exists(["cve_cwe"])?;
+    let cve_reference = tbl_stmt.exists(["cve_reference"])?;
+    let cpe_match = tbl_stmt.exists(["cpe_match"])?;
+    tbl_stmt.finalize()?;
+    if cpe_match {
+        rebuild_cpe_matches(conn, ids)?;
+    }
+    if cve_cwe {
+        rebuild_cwes(conn, ids)?;
+    }
+    if cve_reference {
+        rebuild_references(conn, ids)?;
+    }
+    if !exploit_refs {
+        return Ok(());
+    }
+
+    conn.execute(
+        &format!("DELETE FROM exploit_refs WHERE cve IN ({})", ids),
+        [],
+    )?;
+
+    let mut stmt = conn.prepare(&format!("SELECT data FROM cve WHERE id IN ({})", ids))?;
+    let cves = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .map(|data| Ok(serde_json::from_str::<Cve>(&data?)?))
+        .collect::<Result<Vec<Cve>, CacheError>>()?;
+    stmt.finalize()?;
+
+    write_exploit_refs(conn, &cves)
+}
+
+/// Whether a CVE was modified after the ``Metafile`` of the feed it came from, in which case the
+/// cache may already hold a newer version of it from another feed.
+fn modified_after(cve: &CveContainer, last_modified_date: Option<&DateTime<Utc>>) -> bool {
+    match (cve.last_modified(), last_modified_date) {
+        (Some(cve_datetime), Some(metafile_datetime)) => cve_datetime > *metafile_datetime,
+        _ => false,
+    }
+}
+
+/// Update or insert CVEs from a ``CVEContainer``, counting the changes made to the cache.
+/// ``progress`` is called with the number of CVEs written so far every ``WRITE_PROGRESS_STEP``
+/// CVEs, and once all of them are written, before they are committed.
+fn update_cves<F: FnMut(usize)>(
+    config: &CacheConfig,
+    feed: FeedName,
+    cve_feed: &[CveContainer],
+    last_modified_date: Option<&DateTime<Utc>>,
+    mut progress: F,
+) -> Result<FeedChanges, CacheError> {
+    let conn = open(config)?;
+    let upsert_sql = "
+        insert into
+        cve (
+            id,
+            description,
+            data,
+            attack_vector,
+            attack_complexity,
+            privileges_required,
+            user_interaction,
+            scope,
+            confidentiality_impact,
+            integrity_impact,
+            availability_impact,
+            exploitability_score,
+            impact_score,
+            base_severity,
+            base_score,
+            published_date,
+            feed,
+            last_modified_date,
+            configurations,
+            impact,
+            assigner
+        )
+        values
+            (
+                ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18,
+                ?19, ?20, ?21
+            )
+        on conflict(id) do
+        update
+        set
+            description=?2,
+            data=?3,
+            attack_vector=?4,
+            attack_complexity=?5,
+            privileges_required=?6,
+            user_interaction=?7,
+            scope=?8,
+            confidentiality_impact=?9,
+            integrity_impact=?10,
+            availability_impact=?11,
+            exploitability_score=?12,
+            impact_score=?13,
+            base_severity=?14,
+            base_score=?15,
+            published_date=?16,
+            feed=?17,
+            last_modified_date=?18,
+            configurations=?19,
+            impact=?20,
+            assigner=?21;";
+
+    let mut stmt = conn.prepare(upsert_sql)?;
+    let mut cached_stmt = conn.prepare(&format!(
+        "SELECT {} FROM cve WHERE id = ?1",
+        COMPARED_COLUMNS.join(", ")
+    ))?;
+    let compared = compared_indexes();
+    let mut blob_stmt = conn.prepare(
+        "INSERT OR IGNORE INTO blobs (hash, data)
+         SELECT ?2, data FROM cve WHERE id = ?1 AND last_modified_date IS NOT NULL",
+    )?;
+    let mut history_stmt = conn.prepare(
+        "INSERT OR REPLACE INTO cve_history (
+            cve, last_modified_date, blob, replaced, published_date, base_severity, base_score
+         )
+         SELECT id, last_modified_date, ?3, ?2, published_date, base_severity, base_score
+         FROM cve WHERE id = ?1 AND last_modified_date IS NOT NULL",
+    )?;
+    let replaced = config.clock.now().to_rfc3339();
+    let mut changes = FeedChanges::new(feed);
+
+    // We can't borrow conn immutably for the prepared statement AND mutably for a transaction
+    // Transaction::new_unchecked() allows for an immutable borrow of the connection
+    // see: https://github.com/rusqlite/rusqlite/pull/693
+    let tx = Transaction::new_unchecked(&conn, TransactionBehavior::Exclusive)?;
+
+    for (written, cve) in cve_feed.iter().enumerate() {
+        if written > 0 && written % WRITE_PROGRESS_STEP == 0 {
+            progress(written);
+        }
+
+        if modified_after(cve, last_modified_date) {
+            changes.skipped += 1;
+            continue;
+        }
+
+        let values = cve_values(cve, feed);
+        let cached: Option<(String, Vec<Value>)> = cached_stmt
+            .query_row([&cve.cve.cve_data_meta.id], |row| {
+                let columns = (0..COMPARED_COLUMNS.len())
+                    .map(|index| row.get(index))
+                    .collect::<Result<_>>()?;
+                Ok((row.get(0)?, columns))
+            })
+            .optional()?;
+        match cached {
+            None => changes.added += 1,
+            Some((_, columns))
+                if compared
+                    .iter()
+                    .zip(&columns)
+                    .all(|(index, column)| values[*index] == *column) =>
+            {
+                changes.unchanged += 1
+            }
+            Some((data, _)) => {
+                if config.track_history {
+                    let id = &cve.cve.cve_data_meta.id;
+                    let hash = blob_hash(&data);
+                    blob_stmt.execute(params![id, hash])?;
+                    history_stmt.execute(params![id, replaced, hash])?;
+                }
+                changes.modified += 1;
+            }
+        }
+        stmt.insert(params_from_iter(values))?;
+        write_exploit_refs(&conn, [&cve.cve])?;
+        write_cwes(&conn, [&cve.cve])?;
+        write_references(&conn, [&cve.cve])?;
+        write_cpe_matches(&conn, &cve.cve.cve_data_meta.id, &cve.configurations)?;
+    }
+
+    progress(cve_feed.len());
+    bump_generation(&conn)?;
+    tx.commit()?;
+
+    stmt.finalize()?;
+    cached_stmt.finalize()?;
+    blob_stmt.finalize()?;
+    history_stmt.finalize()?;
+    match conn.close() {
+        Ok(_) => Ok(changes),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Numbers the temporary CSV files of bulk imports, which may run concurrently within a process.
+#[cfg(feature = "bulk-import")]
+static IMPORT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Same as ``update_cves()`` but the CVEs are written to a temporary CSV file first, which is then
+/// imported with a single statement through SQLite's ``csv`` virtual table. This avoids executing
+/// a statement per CVE, which dominates the time taken to populate a new cache.
+#[cfg(feature = "bulk-import")]
+fn bulk_import_cves<F: FnMut(usize)>(
+    config: &CacheConfig,
+    feed: FeedName,
+    cve_feed: &[CveContainer],
+    last_modified_date: Option<&DateTime<Utc>>,
+    progress: F,
+) -> Result<FeedChanges, CacheError> {
+    let path = env::temp_dir().join(format!(
+        "nvd_cve-import-{}-{}.csv",
+        std::process::id(),
+        IMPORT_ID.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let mut changes = FeedChanges::new(feed);
+    let result =
+        write_import_csv(&path, feed, cve_feed, last_modified_date, progress).and_then(|skipped| {
+            let (imported, modified, unchanged) = import_csv(config, &path)?;
+            import_derived_rows(
+                config,
+                cve_feed
+                    .iter()
+                    .filter(|cve| !modified_after(cve, last_modified_date)),
+            )?;
+            changes.added = imported - modified - unchanged;
+            changes.modified = modified;
+            changes.unchanged = unchanged;
+            changes.skipped = skipped;
+            Ok(changes)
+        });
+
+    fs::remove_file(&path).ok();
+    result
+}
+
+/// Write the ``cve_values()`` of each CVE of ``feed`` to a CSV file at ``path``, with ``NULL`` values left
+/// empty, reporting ``progress`` as ``update_cves()`` does. Returns the number of CVEs left out
+/// since they were modified after the feed.
+#[cfg(feature = "bulk-import")]
+fn write_import_csv<F: FnMut(usize)>(
+    path: &std::path::Path,
+    feed: FeedName,
+    cve_feed: &[CveContainer],
+    last_modified_date: Option<&DateTime<Utc>>,
+    mut progress: F,
+) -> Result<usize, CacheError> {
+    let mut writer = csv::Writer::from_path(path).map_err(io::Error::from)?;
+    let mut skipped = 0;
+
+    for (written, cve) in cve_feed.iter().enumerate() {
+        if written > 0 && written % WRITE_PROGRESS_STEP == 0 {
+            progress(written);
+        }
+
+        if modified_after(cve, last_modified_date) {
+            skipped += 1;
+            continue;
+        }
+        let record = cve_values(cve, feed).into_iter().map(|value| match value {
+            Value::Text(text) => text,
+            Value::Real(real) => real.to_string(),
+            Value::Integer(integer) => integer.to_string(),
+            Value::Null | Value::Blob(_) => String::new(),
+        });
+        writer.write_record(record).map_err(io::Error::from)?;
+    }
+
+    writer.flush()?;
+    progress(cve_feed.len());
+    Ok(skipped)
+}
+
+/// Upsert all the rows of the CSV file at ``path`` into the ``cve`` table, returning the number of
+/// rows imported and how many of them were already cached with different or the same
+/// ``COMPARED_COLUMNS``.
+#[cfg(feature = "bulk-import")]
+fn import_csv(
+    config: &CacheConfig,
+    path: &std::path::Path,
+) -> Result<(usize, usize, usize), CacheError> {
+    let conn = open(config)?;
+    rusqlite::vtab::csvtab::load_module(&conn)?;
+
+    let columns: Vec<_> = CVE_BASE_COLUMNS.iter().chain(CVE_COLUMNS).collect();
+
+    // The csv virtual table reads every value as text, so empty values are turned back into NULLs
+    // and scores back into numbers
+    let values: Vec<_> = columns
+        .iter()
+        .enumerate()
+        .map(|(index, (_, column_type))| match *column_type {
+            "REAL" => format!("CAST(NULLIF(c{}, '') AS REAL)", index),
+            _ if index == 0 => "c0".to_string(),
+            _ => format!("NULLIF(c{}, '')", index),
+        })
+        .collect();
+
+    let updates: Vec<_> = columns
+        .iter()
+        .skip(1)
+        .map(|(column, _)| format!("{0}=excluded.{0}", column))
+        .collect();
+
+    conn.execute(
+        &format!(
+            "CREATE VIRTUAL TABLE temp.cve_import USING csv(filename='{}', columns={})",
+            path.display().to_string().replace('\'', "''"),
+            columns.len()
+        ),
+        [],
+    )?;
+
+    // Changes are counted before the upsert, against the CVEs as they were cached
+    let same: Vec<_> = COMPARED_COLUMNS
+        .iter()
+        .zip(compared_indexes())
+        .map(|(column, index)| format!("cve.{} IS {}", column, values[index]))
+        .collect();
+    let (imported, cached, unchanged): (usize, usize, usize) = conn.query_row(
+        &format!(
+            "SELECT count(*), count(cve.id), count(CASE WHEN {} THEN 1 END)
+            FROM temp.cve_import i LEFT JOIN cve ON cve.id = i.c0",
+            same.join(" AND ")
+        ),
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+
+    conn.execute_batch(&format!(
+        "BEGIN;
+        INSERT INTO cve ({})
+            SELECT {} FROM temp.cve_import WHERE true
+            ON CONFLICT(id) DO UPDATE SET {};
+        COMMIT;
+        DROP TABLE temp.cve_import;",
+        columns
+            .iter()
+            .map(|(column, _)| *column)
+            .collect::<Vec<_>>()
+            .join(", "),
+        values.join(", "),
+        updates.join(", ")
+    ))?;
+
+    match conn.close() {
+        Ok(_) => Ok((imported, cached - unchanged, unchanged)),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Returns ``true`` if the local cache doesn't hold any CVEs yet.
+fn is_empty(config: &CacheConfig) -> Result<bool, CacheError> {
+    let conn = open(config)?;
+    let empty = conn.query_row("SELECT NOT EXISTS (SELECT 1 FROM cve)", [], |row| {
+        row.get(0)
+    })?;
+
+    match conn.close() {
+        Ok(_) => Ok(empty),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Syncs the remote feeds to the local cache using the provided ``BlockingHttpClient``, returning
+/// a summary of the changes made.
+///
+/// The client fetches the feeds on a thread of its own while the feeds already fetched are
+/// decompressed, parsed and written, see ``SyncTimings``. Fetches are bounded by the
+/// ``feed_timeout`` and ``sync_deadline`` of the config through the client's
+/// ``set_request_timeout``, and its redirects by the ``max_redirects`` and ``allowed_hosts`` of
+/// the config through ``set_redirect_limits``.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::{CacheConfig, sync_blocking};
+/// use nvd_cve::client::{ReqwestBlockingClient, BlockingHttpClient};
+///
+/// let mut config = CacheConfig::new();
+///
+/// let client = ReqwestBlockingClient::new(&config.url, None, None, None);
+///
+/// match sync_blocking(&config, client) {
+///     Ok(report) => println!("Updated {} CVEs", report.cves_updated),
+///     Err(error) => {
+///         eprintln!("Fatal Error while syncing feeds: {:?}", error);
+///         std::process::exit(1);
+///     }
+/// }
+/// ```
+#[cfg(feature = "blocking")]
+pub fn sync_blocking<C: BlockingHttpClient + Send>(
+    config: &CacheConfig,
+    mut client: C,
+) -> Result<SyncReport, CacheError> {
+    // A read-only cache is never synced, not even partially
+    if config.read_only {
+        return Err(CacheError::ReadOnly);
+    }
+    client.set_redirect_limits(config.max_redirects, &config.allowed_hosts);
+
+    let mut report = SyncReport::default();
+    let started = config.clock.now().to_rfc3339();
+
+    // Read the key first, so a missing key fails the sync before anything is fetched
+    let attestation_key = match &config.attestation_key {
+        Some(path) => Some(fs::read(path)?),
+        None => None,
+    };
+    let mut attested_feeds = vec![];
+
+    let mut bar = progress::Bar::new();
+
+    let mut synced = 0;
+
+    // Each operation is a progress point: fetch metafile, fetch feeds, insert CVEs, insert metafile
+    let to_sync = config.feeds.len() * 4;
+
+    if config.show_progress {
+        bar.set_job_title("Syncing CVE Data");
+        bar.reach_percent((synced as f32 / to_sync as f32 * 100.0).round() as i32);
+    }
+
+    create_schema(&config.db)?;
+
+    let feeds = get_metafiles(config)?;
+
+    // A new cache is populated with bulk imports, as there are no existing CVEs to update
+    let bulk_import = cfg!(feature = "bulk-import") && is_empty(config)?;
+
+    // Feeds are downloaded, decompressed, parsed and written by concurrent stages, each working on
+    // a feed of its own, so that fetching a feed overlaps with parsing and writing the previous
+    // ones. Writes stay on this thread, in the order of the feeds.
+    let (downloaded, to_decompress) = sync_channel(STAGE_BACKLOG);
+    let (decompressed, to_parse) = sync_channel(STAGE_BACKLOG);
+    let (parsed, to_write) = sync_channel(STAGE_BACKLOG);
+    let epoch = Instant::now();
+    let trace_feeds = config.trace_feeds || config.trace_file.is_some();
+
+    thread::scope(|scope| {
+        let download =
+            scope.spawn(move || pipeline::download(client, feeds, config, epoch, downloaded));
+        let decompress =
+            scope.spawn(move || pipeline::decompress(to_decompress, decompressed, epoch));
+        let parse =
+            scope.spawn(move || pipeline::parse(to_parse, parsed, config.parse_threads, epoch));
+
+        let written = loop {
+            let mut job = match to_write.recv() {
+                Ok(Ok(job)) => job,
+                Ok(Err(error)) => break Err(error),
+                // Every feed has been written
+                Err(_) => break Ok(()),
+            };
+
+            let cve_feed = match job.payload {
+                Payload::Parsed(cve_feed) => cve_feed,
+                Payload::Skipped => {
+                    // Skip insert metafile, fetch feeds, insert CVEs
+                    synced += 4;
+                    if config.show_progress {
+                        bar.reach_percent((synced as f32 / to_sync as f32 * 100.0).round() as i32);
+                    }
+                    report.skipped_feeds.push(job.name);
+                    attested_feeds.push(FeedAttestation::new(&job.name, &job.metafile, false));
+                    if trace_feeds {
+                        report.feed_timings.push(job.timings);
+                    }
+                    continue;
+                }
+                _ => unreachable!("Feeds are parsed before they are written"),
+            };
+
+            if config.show_progress {
+                synced += 2;
+                // Metafiles only list the size of the zipped and GZipped feeds
+                let feed_size = match config.compression {
+                    Compression::Zip => job.metafile.zip_size,
+                    _ => job.metafile.gz_size,
+                };
+                bar.set_job_title(
+                    format!(
+                        "[Feed: {}] Syncing {} CVEs ({})",
+                        job.name,
+                        cve_feed.cve_items.len(),
+                        feed_size
+                            .file_size(options::CONVENTIONAL)
+                            .unwrap_or_default()
+                    )
+                    .as_str(),
+                );
+                bar.reach_percent((synced as f32 / to_sync as f32 * 100.0).round() as i32);
+            }
+
+            let start = Instant::now();
+            let last_modified = match job.cached {
+                true => Some(&job.metafile.last_modified_date),
+                false => None,
+            };
+
+            // Writing a large feed takes a while, so the CVEs written so far are shown as part of
+            // the insert step, followed by the commit which waits on the disk
+            let total = cve_feed.cve_items.len();
+            let progress = |written: usize| {
+                debug!("[Feed: {}] Wrote {}/{} CVEs", job.name, written, total);
+                if config.show_progress {
+                    let title = match written < total {
+                        true => format!("[Feed: {}] Writing CVEs {}/{}", job.name, written, total),
+                        false => format!("[Feed: {}] Committing {} CVEs", job.name, total),
+                    };
+                    bar.set_job_title(&title);
+                    let step = written as f32 / total.max(1) as f32;
+                    bar.reach_percent(
+                        ((synced as f32 + step) / to_sync as f32 * 100.0).round() as i32
+                    );
+                }
+            };
+
+            let changes = match write_cves(
+                config,
+                job.name,
+                &cve_feed.cve_items,
+                last_modified,
+                bulk_import,
+                progress,
+            ) {
+                Ok(changes) => changes,
+                Err(error) => break Err(error),
+            };
+            report.add_feed_changes(changes);
+
+            if config.show_progress {
+                synced += 1;
+                bar.reach_percent((synced as f32 / to_sync as f32 * 100.0).round() as i32);
+            }
+
+            if let Err(error) = update_metafile(config, &job.name, &job.metafile) {
+                break Err(error);
+            }
+            report.timings.write += start.elapsed();
+            report.updated_feeds.push(job.name);
+            attested_feeds.push(FeedAttestation::new(&job.name, &job.metafile, true));
+            if trace_feeds {
+                job.timings.write = Some(StageSpan::since(epoch, start));
+                report.feed_timings.push(job.timings);
+            }
+
+            if config.show_progress {
+                synced += 1;
+                bar.reach_percent((synced as f32 / to_sync as f32 * 100.0).round() as i32);
+            }
+        };
+
+        // Stop the other stages, if they are still running
+        drop(to_write);
+
+        (report.timings.download, report.timed_out_feeds) =
+            download.join().unwrap_or_else(|panic| resume_unwind(panic));
+        report.timings.decompress = decompress
+            .join()
+            .unwrap_or_else(|panic| resume_unwind(panic));
+        report.timings.parse = parse.join().unwrap_or_else(|panic| resume_unwind(panic));
+        written
+    })?;
+
+    finish_sync(
+        config,
+        &mut report,
+        started,
+        attestation_key,
+        attested_feeds,
+    )?;
+
+    Ok(report)
+}
+
+/// Syncs the remote feeds to the local cache using the provided ``AsyncHttpClient``, returning a
+/// summary of the changes made, as ``sync_blocking()`` does but without blocking on requests, so
+/// that a Tokio service can sync its cache on one of its tasks.
+///
+/// Feeds are fetched and written one after the other, with no progress bar. SQLite has no async
+/// API so the writes of each feed still block the task running the sync, which should run on a
+/// multi-threaded runtime for the other tasks to make progress meanwhile. Decompressing and
+/// parsing are left to the client and counted as download time in the ``SyncTimings``.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::{CacheConfig, sync_async};
+/// use nvd_cve::client::{AsyncHttpClient, ReqwestAsyncClient};
+///
+/// async fn sync() {
+///     let config = CacheConfig::new();
+///     let client = ReqwestAsyncClient::new(&config.url, None, None, None);
+///
+///     match sync_async(&config, client).await {
+///         Ok(report) => println!("Updated {} CVEs", report.cves_updated),
+///         Err(error) => eprintln!("Fatal Error while syncing feeds: {:?}", error),
+///     }
+/// }
+/// ```
+pub async fn sync_async<C: AsyncHttpClient + Send>(
+    config: &CacheConfig,
+    mut client: C,
+) -> Result<SyncReport, CacheError> {
+    if config.read_only {
+        return Err(CacheError::ReadOnly);
+    }
+    client.set_redirect_limits(config.max_redirects, &config.allowed_hosts);
+
+    let mut report = SyncReport::default();
+    let started = config.clock.now().to_rfc3339();
+
+    let attestation_key = match &config.attestation_key {
+        Some(path) => Some(fs::read(path)?),
+        None => None,
+    };
+    let mut attested_feeds = vec![];
+
+    create_schema(&config.db)?;
+
+    let feeds = get_metafiles(config)?;
+
+    let bulk_import = cfg!(feature = "bulk-import") && is_empty(config)?;
+
+    let sync_deadline = config
+        .sync_deadline
+        .map(|deadline| Instant::now() + deadline);
+    let epoch = Instant::now();
+    let trace_feeds = config.trace_feeds || config.trace_file.is_some();
+
+    for feed in feeds {
+        let name = feed.name;
+        let mut timings = FeedTimings::new(name);
+        let start = Instant::now();
+        let deadline = match (config.feed_timeout, sync_deadline) {
+            (Some(timeout), Some(deadline)) => Some(deadline.min(start + timeout)),
+            (Some(timeout), None) => Some(start + timeout),
+            (None, deadline) => deadline,
+        };
+
+        let fetched = fetch_feed_async(&mut client, &feed, config.force_update, deadline).await;
+        report.timings.download += start.elapsed();
+        timings.download = Some(StageSpan::since(epoch, start));
+
+        let (metafile, cve_feed) = match fetched {
+            Ok(fetched) => fetched,
+            Err(CacheError::TimedOut(_)) if config.on_timeout == TimeoutPolicy::Continue => {
+                warn!("[Feed: {}] Timed out, skipping it", name);
+                report.timed_out_feeds.push(name);
+                continue;
+            }
+            Err(error) => return Err(error),
+        };
+
+        let cve_feed = match cve_feed {
+            Some(cve_feed) => cve_feed,
+            None => {
+                report.skipped_feeds.push(name);
+                attested_feeds.push(FeedAttestation::new(&name, &metafile, false));
+                if trace_feeds {
+                    report.feed_timings.push(timings);
+                }
+                continue;
+            }
+        };
+
+        let start = Instant::now();
+        let last_modified = feed.metafile.as_ref().map(|_| &metafile.last_modified_date);
+        let total = cve_feed.cve_items.len();
+        let changes = write_cves(
+            config,
+            name,
+            &cve_feed.cve_items,
+            last_modified,
+            bulk_import,
+            |written| debug!("[Feed: {}] Wrote {}/{} CVEs", name, written, total),
+        )?;
+        report.add_feed_changes(changes);
+
+        update_metafile(config, &name, &metafile)?;
+        report.timings.write += start.elapsed();
+        report.updated_feeds.push(name);
+        attested_feeds.push(FeedAttestation::new(&name, &metafile, true));
+        if trace_feeds {
+            timings.write = Some(StageSpan::since(epoch, start));
+            report.feed_timings.push(timings);
+        }
+    }
+
+    finish_sync(
+        config,
+        &mut report,
+        started,
+        attestation_key,
+        attested_feeds,
+    )?;
+
+    Ok(report)
+}
+
+/// Fetch the Metafile of ``feed``, then the feed unless the cache already holds its latest
+/// version, failing with ``CacheError::TimedOut`` once ``deadline`` passes.
+async fn fetch_feed_async<C: AsyncHttpClient>(
+    client: &mut C,
+    feed: &Feed,
+    force_update: bool,
+    deadline: Option<Instant>,
+) -> Result<(Metafile, Option<CveFeed>), CacheError> {
+    let name = feed.name;
+    let set_timeout = |client: &mut C| {
+        let timeout = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        if timeout == Some(Duration::ZERO) {
+            return Err(CacheError::TimedOut(name));
+        }
+        client.set_request_timeout(timeout);
+        Ok(())
+    };
+    let timed_out = |error: HttpError| match error {
+        HttpError::TimedOut => CacheError::TimedOut(name),
+        error => CacheError::HttpError(error),
+    };
+
+    set_timeout(client)?;
+    let metafile = Metafile::from_string(client.get_metafile(&name).await.map_err(timed_out)?)?;
+
+    if let Some(db_metafile) = &feed.metafile {
+        if !force_update && (db_metafile.last_modified_date >= metafile.last_modified_date) {
+            debug!(
+                "Cached Metafile: {} is the latest ({})",
+                name, metafile.last_modified_date
+            );
+            return Ok((metafile, None));
+        }
+    }
+
+    set_timeout(client)?;
+    let cve_feed = client.get_feed(&name).await.map_err(timed_out)?;
+    Ok((metafile, Some(cve_feed)))
+}
+
+/// Write the CVEs of a feed, bulk importing them when ``bulk_import`` is set, unless versions of
+/// the CVEs the feed modifies are kept and the cache holds some already
+#[cfg_attr(not(feature = "bulk-import"), allow(unused_variables))]
+fn write_cves<F: FnMut(usize)>(
+    config: &CacheConfig,
+    feed: FeedName,
+    cve_feed: &[CveContainer],
+    last_modified_date: Option<&DateTime<Utc>>,
+    bulk_import: bool,
+    progress: F,
+) -> Result<FeedChanges, CacheError> {
+    #[cfg(feature = "bulk-import")]
+    if bulk_import && (!config.track_history || is_empty(config)?) {
+        return bulk_import_cves(config, feed, cve_feed, last_modified_date, progress);
+    }
+    update_cves(config, feed, cve_feed, last_modified_date, progress)
+}
+
+/// Score the CVEs and prune those over the size budget, then record the sync run and its
+/// attestation once every feed is written
+fn finish_sync(
+    config: &CacheConfig,
+    report: &mut SyncReport,
+    started: String,
+    attestation_key: Option<Vec<u8>>,
+    attested_feeds: Vec<FeedAttestation>,
+) -> Result<(), CacheError> {
+    // Every CVE is scored again, as the asset tags of their overrides may have changed since
+    if let Some(weights) = &config.risk_weights {
+        score_cves(config, weights)?;
+    }
+
+    report.cves_pruned = enforce_size_budget(config)?;
+
+    if let Some(path) = &config.trace_file {
+        write_trace(path, &report.feed_timings)?;
+    }
+
+    let run = SyncRun {
+        started,
+        finished: config.clock.now().to_rfc3339(),
+        cves_updated: report.cves_updated,
+        cves_skipped: report.cves_skipped,
+        feeds: report.feed_changes.clone(),
+    };
+    record_sync_run(config, &run)?;
+
+    if let Some(key) = attestation_key {
+        let mut attestation = Attestation {
+            source: config.source.clone(),
+            app_version: option_env!("CARGO_PKG_VERSION")
+                .unwrap_or("?.?.?")
+                .to_string(),
+            started: run.started,
+            finished: run.finished,
+            feeds: attested_feeds,
+            cves_updated: report.cves_updated,
+            cves_skipped: report.cves_skipped,
+            signature: None,
+        };
+        attestation.sign(&key);
+        record_attestation(config, &attestation)?;
+    }
+
+    Ok(())
+}
+
+/// Rebuilds all the indexes of the local cache from the records they index, creating any that are
+/// missing, to recover from a corrupted index without syncing the feeds again.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::{CacheConfig, reindex};
+///
+/// let config = CacheConfig::new();
+///
+/// reindex(&config).unwrap();
+/// ```
+pub fn reindex(config: &CacheConfig) -> Result<(), CacheError> {
+    writable_schema(config)?;
+
+    let conn = open(config)?;
+    conn.execute_batch("REINDEX")?;
+
+    match conn.close() {
+        Ok(_) => Ok(()),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Deletes the CVEs with the given ``ids`` from the local cache along with all the rows derived
+/// from them, returning the number of CVEs deleted. Their local overrides are kept. A deleted CVE
+/// is only cached again once the feed holding it is updated, or synced with ``force_update``.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::{CacheConfig, delete_cves};
+///
+/// let config = CacheConfig::new();
+///
+/// delete_cves(&config, &["CVE-2021-44228"]).unwrap();
+/// ```
+pub fn delete_cves<S: AsRef<str>>(config: &CacheConfig, ids: &[S]) -> Result<usize, CacheError> {
+    writable_schema(config)?;
+
+    let mut conn = open(config)?;
+    let tx = Transaction::new(&mut conn, TransactionBehavior::Immediate)?;
+    let mut deleted = 0;
+    {
+        let mut stmt = tx.prepare("DELETE FROM cve WHERE id = ?1")?;
+        for id in ids {
+            deleted += stmt.execute([id.as_ref()])?;
+        }
+    }
+    bump_generation(&tx)?;
+    tx.commit()?;
+
+    match conn.close() {
+        Ok(_) => Ok(deleted),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Deletes the CVE with the given ``id`` from the local cache as ``delete_cves()`` does, returning
+/// ``true`` if it was cached.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::{CacheConfig, delete_cve};
+///
+/// let config = CacheConfig::new();
+///
+/// if !delete_cve(&config, "CVE-2021-44228").unwrap() {
+///     println!("CVE-2021-44228 isn't cached");
+/// }
+/// ```
+pub fn delete_cve(config: &CacheConfig, id: &str) -> Result<bool, CacheError> {
+    Ok(delete_cves(config, &[id])? > 0)
+}
+
+/// Deletes the CVEs last written by the sync of ``feed`` from the local cache, along with the
+/// rows derived from them and the feed's ``Metafile``, returning the number of CVEs deleted. CVEs
+/// of the feed that were written again by a later feed, such as ``modified``, are kept.
+///
+/// A feed still listed in the ``feeds`` of the config is fetched again by the next sync, which
+/// replaces a bad import. Otherwise this reclaims the space taken by a feed no longer synced.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::{CacheConfig, delete_feed_records};
+/// use nvd_cve::feed::FeedName;
+///
+/// let config = CacheConfig::new();
+///
+/// let deleted = delete_feed_records(&config, &FeedName::Year(2002)).unwrap();
+/// println!("Deleted {} CVEs", deleted);
+/// ```
+pub fn delete_feed_records(config: &CacheConfig, feed: &FeedName) -> Result<usize, CacheError> {
+    writable_schema(config)?;
+
+    let mut conn = open(config)?;
+    let tx = Transaction::new(&mut conn, TransactionBehavior::Immediate)?;
+    let deleted = tx.execute("DELETE FROM cve WHERE feed = ?1", [feed.to_string()])?;
+    tx.execute("DELETE FROM metafile WHERE feed = ?1", [feed.to_string()])?;
+    bump_generation(&tx)?;
+    tx.commit()?;
+
+    match conn.close() {
+        Ok(_) => Ok(deleted),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Size in bytes of the pages of the cache opened by ``conn``
+fn db_size(conn: &Connection) -> Result<u64, CacheError> {
+    Ok(conn.query_row(
+        "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+        [],
+        |row| row.get(0),
+    )?)
+}
+
+/// Prunes CVEs from the local cache in the order of the ``prune_policy`` of the config until it
+/// fits within its ``max_db_size``, returning the number of CVEs pruned. Syncs call this once the
+/// feeds are written, it does nothing without a ``max_db_size``.
+///
+/// The pages freed by each batch of CVEs pruned are returned to the filesystem by an incremental
+/// vacuum. A cache created without incremental vacuum is converted by a full ``VACUUM`` the first
+/// time it exceeds its budget, which briefly takes as much free space as the cache itself.
+///
+/// Pruned CVEs are synced again along with their feed once it is updated, then pruned again if
+/// the cache still exceeds its budget. Should the other tables alone exceed it, every CVE is
+/// pruned.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::{CacheConfig, PrunePolicy, enforce_size_budget};
+///
+/// let config = CacheConfig {
+///     max_db_size: Some(256 * 1024 * 1024),
+///     prune_policy: PrunePolicy::LowestSeverity,
+///     ..CacheConfig::new()
+/// };
+///
+/// let pruned = enforce_size_budget(&config).unwrap();
+/// println!("Pruned {} CVEs", pruned);
+/// ```
+pub fn enforce_size_budget(config: &CacheConfig) -> Result<usize, CacheError> {
+    let Some(max_db_size) = config.max_db_size else {
+        return Ok(0);
+    };
+    writable_schema(config)?;
+
+    let conn = open(config)?;
+    let mut pruned = 0;
+
+    if db_size(&conn)? > max_db_size {
+        // Freed pages are only returned by incremental vacuums once the cache is converted
+        let auto_vacuum: i64 = conn.query_row("PRAGMA auto_vacuum", [], |row| row.get(0))?;
+        if auto_vacuum != 2 {
+            conn.pragma_update(None, "auto_vacuum", "INCREMENTAL")?;
+            conn.execute_batch("VACUUM")?;
+        }
+    }
+
+    let prune = format!(
+        "DELETE FROM cve WHERE id IN (SELECT id FROM cve ORDER BY {} LIMIT ?1)",
+        config.prune_policy.order_by()
+    );
+    loop {
+        let size = db_size(&conn)?;
+        if size <= max_db_size {
+            break;
+        }
+        let cves: u64 = conn.query_row("SELECT count(*) FROM cve", [], |row| row.get(0))?;
+        if cves == 0 {
+            warn!(
+                "{} still takes {} bytes with every CVE pruned, over its budget of {}",
+                config.db, size, max_db_size
+            );
+            break;
+        }
+
+        // Prune the share of the CVEs the cache exceeds its budget by, at least one
+        let batch = (cves * (size - max_db_size)).div_ceil(size).max(1);
+        pruned += conn.execute(&prune, [batch])?;
+        bump_generation(&conn)?;
+        conn.execute_batch("PRAGMA incremental_vacuum")?;
+        debug!(
+            "Pruned {} CVEs from {} to fit {} bytes",
+            pruned, config.db, max_db_size
+        );
+    }
+
+    match conn.close() {
+        Ok(_) => Ok(pruned),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Syncs the change events of all the CVEs from the CVE Change History API at the base URL of
+/// ``client``, such as ``history::NVD_CHANGE_HISTORY_URL``, returning the number of events fetched.
+/// The first sync fetches the events of the last 120 days, the next ones the events since the
+/// previous sync. Each range of 120 days is committed once fetched, so a failed sync resumes from
+/// the last one.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::{sync_change_history, CacheConfig};
+/// use nvd_cve::client::{BlockingHttpClient, ReqwestBlockingClient};
+/// use nvd_cve::history::NVD_CHANGE_HISTORY_URL;
+///
+/// let config = CacheConfig::new();
+/// let client = ReqwestBlockingClient::new(NVD_CHANGE_HISTORY_URL, None, None, None);
+///
+/// let fetched = sync_change_history(&config, &client).unwrap();
+/// println!("Fetched {} change events", fetched);
+/// ```
+#[cfg(feature = "blocking")]
+pub fn sync_change_history(
+    config: &CacheConfig,
+    client: &ReqwestBlockingClient,
+) -> Result<usize, CacheError> {
+    writable_schema(config)?;
+
+    let now = config.clock.now().naive_utc();
+    let window = chrono::Duration::days(CHANGE_HISTORY_WINDOW_DAYS);
+
+    let mut conn = open(config)?;
+    let synced_until: Option<String> = conn
+        .query_row("SELECT until FROM cve_changes_sync", [], |row| row.get(0))
+        .optional()?;
+    let mut start = synced_until
+        .and_then(|until| parse_datetime(&until).ok())
+        .map(|until| until.naive_utc())
+        .unwrap_or(now - window);
+
+    let mut fetched = 0;
+    while start < now {
+        let end = (start + window).min(now);
+        let events = fetch_changes_between(client, start, end)?;
+        debug!(
+            "Fetched {} change events from {} to {}",
+            events.len(),
+            start,
+            end
+        );
+
+        let tx = Transaction::new(&mut conn, TransactionBehavior::Immediate)?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO cve_changes (id, cve, created, data) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for event in &events {
+                stmt.execute(params![
+                    event.cve_change_id,
+                    event.cve_id,
+                    event.created,
+                    serde_json::to_string(event)?
+                ])?;
+            }
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO cve_changes_sync (id, until) VALUES (0, ?1)",
+            [end.format(CHANGE_DATE_FORMAT).to_string()],
+        )?;
+        tx.commit()?;
+
+        fetched += events.len();
+        start = end;
+    }
+
+    match conn.close() {
+        Ok(_) => Ok(fetched),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Returns the change events of a CVE synced to the local cache by ``sync_change_history()``,
+/// oldest first. Events made before the first sync of the change history aren't cached, see
+/// ``history::fetch_change_events`` to fetch them all.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::{get_change_events, CacheConfig};
+///
+/// let config = CacheConfig::new();
+///
+/// for event in get_change_events(&config, "CVE-2021-44228").unwrap() {
+///     println!("{} {}", event.created, event.event_name);
+/// }
+/// ```
+pub fn get_change_events(config: &CacheConfig, id: &str) -> Result<Vec<ChangeEvent>, CacheError> {
+    readable_schema(config)?;
+
+    let conn = open(config)?;
+
+    // A read-only cache created by an older version has no change events
+    let mut events = vec![];
+    let mut tbl_stmt = conn
+        .prepare("SELECT name FROM sqlite_master where type = 'table' and name = 'cve_changes'")?;
+    if tbl_stmt.exists([])? {
+        let mut stmt =
+            conn.prepare("SELECT data FROM cve_changes WHERE cve = ?1 ORDER BY created")?;
+        let rows = stmt.query_map([id], |row| row.get::<_, String>(0))?;
+        for data in rows {
+            events.push(serde_json::from_str(&data?)?);
+        }
+        stmt.finalize()?;
+    }
+    tbl_stmt.finalize()?;
+
+    match conn.close() {
+        Ok(_) => Ok(events),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// State of the local cache, as found by ``check``.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheHealth {
+    /// The last sync run of the cache, or ``None`` if it was never synced.
+    pub last_sync: Option<SyncRun>,
+
+    /// Number of feeds synced to the cache.
+    pub feeds: usize,
+
+    /// Number of CVEs in the cache.
+    pub cves: usize,
+
+    /// Problems found by SQLite's integrity and foreign key checks, empty if the database is
+    /// consistent.
+    pub integrity_errors: Vec<String>,
+
+    /// When the cache was checked, by the ``clock`` of its config. Its age is measured up to then.
+    pub checked_at: DateTime<Utc>,
+}
+
+impl CacheHealth {
+    /// Whether the database passed its integrity check and holds synced feeds and CVEs.
+    pub fn is_consistent(&self) -> bool {
+        self.integrity_errors.is_empty() && self.feeds > 0 && self.cves > 0
+    }
+
+    /// Time elapsed between the end of the last sync and the check, or ``None`` if the cache was
+    /// never synced.
+    pub fn age(&self) -> Option<chrono::Duration> {
+        let finished = DateTime::parse_from_rfc3339(&self.last_sync.as_ref()?.finished).ok()?;
+        Some(self.checked_at.signed_duration_since(finished))
+    }
+
+    /// Whether the last sync finished at most ``max_age`` ago.
+    pub fn is_fresh(&self, max_age: std::time::Duration) -> bool {
+        match (self.age(), chrono::Duration::from_std(max_age)) {
+            (Some(age), Ok(max_age)) => age <= max_age,
+            _ => false,
+        }
+    }
+}
+
+/// Check the health of the local cache: when it was last synced, what it holds and whether its
+/// database is consistent. The cache is always opened read-only, so checking a missing cache
+/// fails rather than creating it.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::{CacheConfig, check};
+/// use std::time::Duration;
+///
+/// let config = CacheConfig::new();
+/// let health = check(&config).unwrap();
+///
+/// if !health.is_consistent() || !health.is_fresh(Duration::from_secs(48 * 60 * 60)) {
+///     std::process::exit(1);
+/// }
+/// ```
+pub fn check(config: &CacheConfig) -> Result<CacheHealth, CacheError> {
+    let conn = open(&CacheConfig {
+        read_only: true,
+        ..config.clone()
+    })?;
+
+    let mut stmt = conn.prepare("PRAGMA quick_check")?;
+    let integrity_errors = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<String>>>()?
+        .into_iter()
+        .filter(|result| result != "ok")
+        .collect::<Vec<String>>();
+    stmt.finalize()?;
+
+    // Rows left behind by a writer that didn't enforce foreign keys
+    let mut stmt = conn.prepare("PRAGMA foreign_key_check")?;
+    let orphans = stmt
+        .query_map([], |row| {
+            Ok(format!(
+                "row {} of {} references a missing row of {}",
+                row.get::<_, Option<i64>>(1)?.unwrap_or_default(),
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(2)?
+            ))
+        })?
+        .collect::<Result<Vec<String>>>()?;
+    stmt.finalize()?;
+    let integrity_errors = [integrity_errors, orphans].concat();
+
+    let feeds = conn.query_row("SELECT count(*) FROM metafile", [], |row| row.get(0))?;
+    let cves = conn.query_row("SELECT count(*) FROM cve", [], |row| row.get(0))?;
+    let last_sync = last_sync_run(&conn)?;
+
+    match conn.close() {
+        Ok(_) => Ok(CacheHealth {
+            last_sync,
+            feeds,
+            cves,
+            integrity_errors,
+            checked_at: config.clock.now(),
+        }),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Returns the ``Metafile`` of the named feed as of its last sync to the local cache, or ``None`` if
+/// the feed was never synced.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::{CacheConfig, get_metafile};
+/// use nvd_cve::feed::FeedName;
+///
+/// let config = CacheConfig::new();
+///
+/// if let Some(metafile) = get_metafile(&config, &FeedName::Year(2021)).unwrap() {
+///     println!("{}", metafile.sha256);
+/// }
+/// ```
+pub fn get_metafile(config: &CacheConfig, feed: &FeedName) -> Result<Option<Metafile>, CacheError> {
+    let conn = open(config)?;
+
+    let mut stmt = conn.prepare("SELECT * FROM metafile where feed=?1")?;
+    let metafile = stmt
+        .query_row([feed.to_string()], metafile_from_row)
+        .optional()?;
+
+    stmt.finalize()?;
+
+    match conn.close() {
+        Ok(_) => Ok(metafile),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Returns all the feeds synced to the local cache along with their ``Metafile`` as of their last
+/// sync, yearly feeds first.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::{CacheConfig, get_cached_feeds};
+///
+/// let config = CacheConfig::new();
+///
+/// for feed in get_cached_feeds(&config).unwrap() {
+///     println!("{}: {:?}", feed.name, feed.metafile);
+/// }
+/// ```
+pub fn get_cached_feeds(config: &CacheConfig) -> Result<Vec<Feed>, CacheError> {
+    let conn = open(config)?;
+
+    let mut stmt = conn.prepare("SELECT * FROM metafile ORDER BY feed")?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>("feed")?, metafile_from_row(row)?))
+    })?;
+
+    let mut feeds = vec![];
+    for row in rows {
+        let (name, metafile) = row?;
+        match name.parse() {
+            Ok(name) => feeds.push(Feed {
+                name,
+                metafile: Some(metafile),
+            }),
+            Err(error) => debug!("Skipped cached feed {:?}: {}", name, error),
+        }
+    }
+
+    stmt.finalize()?;
+
+    match conn.close() {
+        Ok(_) => Ok(feeds),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Columns of the ``cve`` table selected to build a ``CveSummary``.
+pub(crate) const SUMMARY_COLUMNS: &str =
+    "id, description, base_severity, base_score, published_date";
+
+/// Condition matching the rows of the ``overrides`` table in the ``namespace`` of ``config``, in the
+/// cache opened by ``conn``. ``None`` for read-only caches made by older versions without
+/// overrides, and every override matches in those made before namespaces.
+pub(crate) fn override_scope(
+    conn: &Connection,
+    config: &CacheConfig,
+) -> Result<Option<String>, CacheError> {
+    let columns = table_columns(conn, "overrides")?;
+    if columns.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(
+        match columns.iter().any(|column| column == "namespace") {
+            true => format!(
+                "overrides.namespace = '{}'",
+                config.namespace.replace('\'', "''")
+            ),
+            false => "true".to_string(),
+        },
+    ))
+}
+
+/// The ``SUMMARY_COLUMNS`` along with whether each CVE is listed in the ``kev`` table, as
+/// ``known_exploited``, its score in the ``epss`` table, as ``epss``, and the severity of its
+/// override in the namespace of ``config``, as ``override_severity``, selected to build a
+/// ``CveSummary``. Read-only caches made by older versions may have none of these tables, so no CVE
+/// is known exploited, scored or overridden.
+pub(crate) fn summary_columns(
+    conn: &Connection,
+    config: &CacheConfig,
+) -> Result<String, CacheError> {
+    let mut tbl_stmt =
+        conn.prepare("SELECT name FROM sqlite_master where type = 'table' and name = ?1")?;
+    let kev = tbl_stmt.exists(["kev"])?;
+    let epss = tbl_stmt.exists(["epss"])?;
+    tbl_stmt.finalize()?;
+
+    let known_exploited = if kev {
+        "EXISTS (SELECT 1 FROM kev WHERE kev.cve = cve.id)"
+    } else {
+        "0"
+    };
+    let score = if epss {
+        "(SELECT score FROM epss WHERE epss.cve = cve.id)"
+    } else {
+        "NULL"
+    };
+    let override_severity = match override_scope(conn, config)? {
+        Some(scope) => format!(
+            "(SELECT severity FROM overrides WHERE overrides.id = cve.id AND {})",
+            scope
+        ),
+        None => "NULL".to_string(),
+    };
+    Ok(format!(
+        "{}, {} AS known_exploited, {} AS epss, {} AS override_severity",
+        SUMMARY_COLUMNS, known_exploited, score, override_severity
+    ))
+}
+
+/// Build a ``CveSummary`` from a row selecting the ``summary_columns()``.
+pub(crate) fn summary_from_row(row: &Row) -> Result<CveSummary> {
+    Ok(CveSummary {
+        id: row.get("id")?,
+        description: row
+            .get::<_, Option<String>>("description")?
+            .unwrap_or_default(),
+        severity: row.get("base_severity")?,
+        score: row.get("base_score")?,
+        published: row.get("published_date")?,
+        known_exploited: row.get("known_exploited")?,
+        epss: row.get("epss")?,
+        override_severity: row.get("override_severity")?,
+    })
+}
+
+/// Returns all the CVEs available in the database, sorted by CVE ID.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::{CacheConfig, get_all};
+///
+/// let config = CacheConfig::new();
+///
+/// let all_cves = get_all(&config).unwrap();
+/// println!("{:?}", &all_cves);
+/// ```
+pub fn get_all(config: &CacheConfig) -> Result<Vec<Cve>, CacheError> {
+    let conn = open(config)?;
+    let mut stmt = conn.prepare("SELECT * FROM cve ORDER BY id")?;
+
+    let cves = stmt.query_map(params![], |row| {
+        let data: String = row.get("data")?;
+        Ok(data)
+    })?;
+
+    let mut cve_list = vec![];
+    for cve in cves {
+        let result: Cve = serde_json::from_str(cve?.as_str())?;
+        cve_list.push(result);
+    }
+    stmt.finalize()?;
+
+    match conn.close() {
+        Ok(_) => Ok(cve_list),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Returns the IDs of all the CVEs available in the database, sorted. Unlike ``get_all()`` none of
+/// the CVE data is read.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::{CacheConfig, get_all_ids};
+///
+/// let config = CacheConfig::new();
+///
+/// let ids = get_all_ids(&config).unwrap();
+/// println!("{} CVEs cached", ids.len());
+/// ```
+pub fn get_all_ids(config: &CacheConfig) -> Result<Vec<String>, CacheError> {
+    let conn = open(config)?;
+    let mut stmt = conn.prepare("SELECT id FROM cve ORDER BY id")?;
+
+    let ids = stmt
+        .query_map(params![], |row| row.get("id"))?
+        .collect::<Result<Vec<String>>>()?;
+
+    stmt.finalize()?;
+
+    match conn.close() {
+        Ok(_) => Ok(ids),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Returns a ``CveSummary`` of all the CVEs available in the database, sorted by CVE ID. The
+/// summaries are built from their own columns, without deserializing the full CVE data as
+/// ``get_all()`` does.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::{CacheConfig, get_all_summaries};
+///
+/// let config = CacheConfig::new();
+///
+/// for cve in get_all_summaries(&config).unwrap() {
+///     println!("{}", cve);
+/// }
+/// ```
+pub fn get_all_summaries(config: &CacheConfig) -> Result<Vec<CveSummary>, CacheError> {
+    search(config, &SearchQuery::default())
+}
+
+/// Returns the full CVE object that is extracted from the feed for the provided CVE ID.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::{CacheConfig, search_by_id};
+///
+/// let config = CacheConfig::new();
+///
+/// let cve_result = search_by_id(&config, "CVE-2019-18254").unwrap();
+/// println!("{:?}", &cve_result);
+/// ```
+pub fn search_by_id(config: &CacheConfig, cve: &str) -> Result<Cve, CacheError> {
+    let conn = open(config)?;
+
+    let mut stmt = conn.prepare("SELECT * FROM cve where id=?1")?;
+
+    let data = stmt.query_row([&cve], |row| {
+        let data: String = row.get("data")?;
+        Ok(data)
+    })?;
+
+    stmt.finalize()?;
+
+    let result: Cve = serde_json::from_str(data.as_str())?;
+
+    match conn.close() {
+        Ok(_) => Ok(result),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Returns the full ``CveContainer`` of a cached CVE: the CVE along with its CVSS metrics, the
+/// configurations of the products it affects and its dates, as formatted by
+/// ``datetime::format_datetime()``. CVEs cached by older versions are returned without the metrics
+/// or configurations they didn't record until the feeds are synced again.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::{CacheConfig, search_container_by_id};
+///
+/// let config = CacheConfig::new();
+///
+/// let log4shell = search_container_by_id(&config, "CVE-2021-44228").unwrap();
+/// println!("{:?}", log4shell.severity());
+/// for cpe_match in log4shell.configurations.cpe_matches() {
+///     println!("{}", cpe_match.cpe23_uri);
+/// }
+/// ```
+pub fn search_container_by_id(config: &CacheConfig, cve: &str) -> Result<CveContainer, CacheError> {
+    readable_schema(config)?;
+
+    let conn = open(config)?;
+
+    // A read-only cache created by an older version may lack the columns
+    let columns = table_columns(&conn, "cve")?;
+    let column = |name: &str| match columns.iter().any(|column| column == name) {
+        true => name.to_string(),
+        false => format!("NULL AS {}", name),
+    };
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT data, {}, {}, {}, {} FROM cve WHERE id = ?1",
+        column("configurations"),
+        column("impact"),
+        column("published_date"),
+        column("last_modified_date")
+    ))?;
+    let (data, configurations, impact, published_date, last_modified_date) =
+        stmt.query_row([cve], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, Option<String>>(4)?,
+            ))
+        })?;
+    stmt.finalize()?;
+
+    let raw_configurations: Option<serde_json::Value> = configurations
+        .map(|configurations| serde_json::from_str(&configurations))
+        .transpose()?;
+    let raw_impact: Option<serde_json::Value> = impact
+        .map(|impact| serde_json::from_str(&impact))
+        .transpose()?;
+    let container = CveContainer {
+        cve: serde_json::from_str(&data)?,
+        configurations: match &raw_configurations {
+            Some(configurations) => serde_json::from_value(configurations.clone())?,
+            None => Configuration {
+                cve_data_version: String::new(),
+                nodes: vec![],
+            },
+        },
+        impact: match &raw_impact {
+            Some(impact) => serde_json::from_value(impact.clone())?,
+            None => Impact::default(),
+        },
+        published_date: published_date.unwrap_or_default(),
+        last_modified_date: last_modified_date.unwrap_or_default(),
+        raw_configurations,
+        raw_impact,
+    };
+
+    match conn.close() {
+        Ok(_) => Ok(container),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Returns the JSON stored in the ``column`` of the ``cve`` table for the CVE ``cve``, ``null`` if
+/// the cache was created by an older version without the column and hasn't been synced since.
+fn get_json_column(
+    config: &CacheConfig,
+    cve: &str,
+    column: &str,
+) -> Result<serde_json::Value, CacheError> {
+    readable_schema(config)?;
+
+    let conn = open(config)?;
+
+    let select = match table_columns(&conn, "cve")?
+        .iter()
+        .any(|name| name == column)
+    {
+        true => column.to_string(),
+        false => "NULL".to_string(),
+    };
+    let json = conn.query_row(
+        &format!("SELECT {} FROM cve WHERE id = ?1", select),
+        [cve],
+        |row| row.get::<_, Option<String>>(0),
+    )?;
+    let value = match json {
+        Some(json) => serde_json::from_str(&json)?,
+        None => serde_json::Value::Null,
+    };
+
+    match conn.close() {
+        Ok(_) => Ok(value),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Returns the ``impact`` of a cached CVE as stored, the JSON of its CVSS v3 and v2 metrics as
+/// given by the feed, without parsing it into an ``Impact``. It is ``null`` for CVEs cached by
+/// older versions until the feeds are synced again.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::{CacheConfig, get_impact};
+///
+/// let config = CacheConfig::new();
+///
+/// let impact = get_impact(&config, "CVE-2021-44228").unwrap();
+/// println!("{}", impact["baseMetricV3"]["cvssV3"]["vectorString"]);
+/// ```
+pub fn get_impact(config: &CacheConfig, cve: &str) -> Result<serde_json::Value, CacheError> {
+    get_json_column(config, cve, "impact")
+}
+
+/// Returns the ``configurations`` of a cached CVE as stored, the JSON of the nodes of CPE matches
+/// of the products it affects as given by the feed, without parsing it into a ``Configuration``.
+/// It is ``null`` for CVEs cached by older versions until the feeds are synced again.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::{CacheConfig, get_configurations};
+///
+/// let config = CacheConfig::new();
+///
+/// let configurations = get_configurations(&config, "CVE-2021-44228").unwrap();
+/// for node in configurations["nodes"].as_array().into_iter().flatten() {
+///     println!("{}", node["operator"]);
+/// }
+/// ```
+pub fn get_configurations(
+    config: &CacheConfig,
+    cve: &str,
+) -> Result<serde_json::Value, CacheError> {
+    get_json_column(config, cve, "configurations")
+}
+
+/// Returns the version of the CVE ``cve`` that was current at ``as_of``: the cached version or one
+/// of those kept by syncs with ``track_history``, whichever was last modified by the NVD at or
+/// before ``as_of``. Fails like ``search_by_id()`` when no version was modified by then, e.g. the
+/// CVE wasn't published yet or history was tracked only since later versions.
+///
+/// Versions are only kept as they are synced, so a version modified and replaced between two
+/// syncs is never known, in which case the version before it is returned.
+///
+/// ## Example:
+/// ```no_run
+/// use chrono::{TimeZone, Utc};
+/// use nvd_cve::cache::{search_by_id_as_of, CacheConfig};
+///
+/// let config = CacheConfig::new();
+/// let triaged = Utc.with_ymd_and_hms(2021, 12, 11, 9, 0, 0).unwrap();
+///
+/// let cve = search_by_id_as_of(&config, "CVE-2021-44228", &triaged).unwrap();
+/// println!("{:?}", cve.description);
+/// ```
+pub fn search_by_id_as_of(
+    config: &CacheConfig,
+    cve: &str,
+    as_of: &DateTime<Utc>,
+) -> Result<Cve, CacheError> {
+    Ok(cached_version(config, cve, Some(as_of))?.cve)
+}
+
+/// Returns the ``CachedCve`` of the cached version of ``cve``, or of the version current at
+/// ``as_of`` as ``search_by_id_as_of()`` finds it. The attack vector of kept versions is unknown,
+/// as are the scores and publication date of those kept before they were recorded.
+pub(crate) fn cached_version(
+    config: &CacheConfig,
+    cve: &str,
+    as_of: Option<&DateTime<Utc>>,
+) -> Result<CachedCve, CacheError> {
+    readable_schema(config)?;
+
+    let conn = open(config)?;
+
+    // A read-only cache created by an older version may have no kept versions, keep them without
+    // their scores or with their data rather than in blobs
+    let history_columns = match as_of {
+        Some(_) => table_columns(&conn, "cve_history")?,
+        None => vec![],
+    };
+    let versions = if history_columns.is_empty() {
+        String::new()
+    } else {
+        let column = |name: &str| match history_columns.iter().any(|column| column == name) {
+            true => name.to_string(),
+            false => format!("NULL AS {}", name),
+        };
+        let (data, blobs) = match history_columns.iter().any(|column| column == "blob") {
+            true => ("blobs.data", "JOIN blobs ON blobs.hash = cve_history.blob"),
+            false => ("data", ""),
+        };
+        format!(
+            "UNION ALL SELECT {}, {}, last_modified_date, {}, {}, NULL, 0 FROM cve_history {}
+            WHERE cve = ?1",
+            data,
+            column("published_date"),
+            column("base_severity"),
+            column("base_score"),
+            blobs
+        )
+    };
+
+    // Cached dates sort as they compare, the cached version wins over a kept version modified at
+    // the same time
+    let mut stmt = conn.prepare(&format!(
+        "SELECT data, published_date, last_modified_date, base_severity, base_score, attack_vector
+        FROM (
+            SELECT data, published_date, last_modified_date, base_severity, base_score,
+                attack_vector, 1 AS cached
+            FROM cve WHERE id = ?1 {}
+        )
+        WHERE ?2 IS NULL OR last_modified_date <= ?2
+        ORDER BY last_modified_date DESC, cached DESC
+        LIMIT 1",
+        versions
+    ))?;
+
+    let as_of = as_of.map(format_datetime);
+    let (data, published, last_modified, severity, attack_vector) =
+        stmt.query_row(params![cve, as_of], |row| {
+            let severity = match (row.get(3)?, row.get(4)?) {
+                (Some(severity), Some(score)) => Some((severity, score)),
+                _ => None,
+            };
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                severity,
+                row.get::<_, Option<String>>(5)?,
+            ))
+        })?;
+    stmt.finalize()?;
+
+    let version = CachedCve {
+        cve: serde_json::from_str(&data)?,
+        published,
+        last_modified,
+        severity,
+        attack_vector,
+    };
+
+    match conn.close() {
+        Ok(_) => Ok(version),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Searches all local CVE descriptions for the provided ``text`` string, and returns a Vec of
+/// ``CveSummary`` for any matches, sorted by CVE ID.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::{CacheConfig, search_description};
+///
+/// let config = CacheConfig::new();
+///
+/// if let Ok(cves) = search_description(&config, "implanted cardiac device") {
+///     for cve in cves {
+///         println!("{}", cve);
+///     }
+/// }
+/// ```
+pub fn search_description(config: &CacheConfig, text: &str) -> Result<Vec<CveSummary>, CacheError> {
+    let conn = open(config)?;
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM cve where description like '%' || ?1 || '%' ORDER BY id",
+        summary_columns(&conn, config)?
+    ))?;
+
+    let cves = stmt.query_map(params![text], summary_from_row)?;
+
+    let mut cve_list = vec![];
+
+    for cve in cves {
+        cve_list.push(cve?);
+    }
+
+    stmt.finalize()?;
+
+    match conn.close() {
+        Ok(_) => Ok(cve_list),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Searches the local cache for all CVEs matching the criteria of a ``SearchQuery``, and returns a
+/// Vec of ``CveSummary`` for any matches, sorted by CVE ID.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::{CacheConfig, search};
+/// use nvd_cve::cvss::AttackVector;
+/// use nvd_cve::query::SearchQuery;
+///
+/// let config = CacheConfig::new();
+///
+/// let mut query = SearchQuery::default();
+/// query.attack_vector = Some(AttackVector::Network);
+///
+/// if let Ok(cves) = search(&config, &query) {
+///     for cve in cves {
+///         println!("{}: {:?}", cve.id, cve.severity);
+///     }
+/// }
+/// ```
+pub fn search(config: &CacheConfig, query: &SearchQuery) -> Result<Vec<CveSummary>, CacheError> {
+    let conn = open(config)?;
+
+    let (sql, values) = search_sql(&conn, config, query)?;
+    let mut stmt = conn.prepare(&sql)?;
 
-    let mut stmt = conn.prepare(upsert_sql)?;
-    let mut unecessary = 0;
+    let cves = stmt.query_map(params_from_iter(values), summary_from_row)?;
 
-    // We can't borrow conn immutably for the prepared statement AND mutably for a transaction
-    // Transaction::new_unchecked() allows for an immutable borrow of the connection
-    // see: https://github.com/rusqlite/rusqlite/pull/693
-    let tx = Transaction::new_unchecked(&conn, TransactionBehavior::Exclusive)?;
+    let mut cve_list = vec![];
 
-    for cve in cve_feed {
-        let mut skip = false;
+    for cve in cves {
+        cve_list.push(cve?);
+    }
 
-        if let Some(metafile_datetime) = last_modified_date {
-            if let Ok(cve_datetime) =
-                NaiveDateTime::parse_from_str(&cve.last_modified_date, "%Y-%m-%dT%H:%M%Z")
-            {
-                if cve_datetime > *metafile_datetime {
-                    skip = true;
-                }
-            }
-        }
+    stmt.finalize()?;
 
-        if skip {
-            unecessary += 1;
-        } else {
-            let mut description = None;
-            if !cve.cve.description.description_data.is_empty() {
-                for d in &cve.cve.description.description_data {
-                    if d.lang == "en" {
-                        description = Some(String::from(&d.value));
-                    }
-                }
-            }
-            stmt.insert(params![
-                cve.cve.cve_data_meta.id,
-                description,
-                serde_json::to_string(&cve.cve).unwrap_or_else(|_| { "{}".to_string() })
-            ])?;
-        }
+    match conn.close() {
+        Ok(_) => Ok(cve_list),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
     }
+}
 
-    tx.commit()?;
+/// The statement ``search()`` runs for ``query`` on the cache opened by ``conn``, and its
+/// positional parameters
+fn search_sql(
+    conn: &Connection,
+    config: &CacheConfig,
+    query: &SearchQuery,
+) -> Result<(String, Vec<Value>), CacheError> {
+    let (where_clause, values) = query.where_clause(
+        risk_namespace(conn, config)?,
+        override_scope(conn, config)?.as_deref(),
+    );
+    let sql = format!(
+        "SELECT {} FROM cve{} ORDER BY id",
+        summary_columns(conn, config)?,
+        where_clause
+    );
+    Ok((sql, values))
+}
+
+/// Returns the plan SQLite picks for the statement ``search()`` runs for ``query``, one detail of
+/// ``EXPLAIN QUERY PLAN`` per step, to tell which indexes a search reads.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::{explain_search, CacheConfig};
+/// use nvd_cve::query::SearchQuery;
+///
+/// let config = CacheConfig::new();
+/// let query = SearchQuery {
+///     text: Some("log4j".to_string()),
+///     ..Default::default()
+/// };
+///
+/// for step in explain_search(&config, &query).unwrap() {
+///     println!("{}", step);
+/// }
+/// ```
+pub fn explain_search(
+    config: &CacheConfig,
+    query: &SearchQuery,
+) -> Result<Vec<String>, CacheError> {
+    let conn = open(config)?;
 
-    debug!("Skipped {} unnecessary inserts", unecessary);
+    let (sql, values) = search_sql(&conn, config, query)?;
+    let mut stmt = conn.prepare(&format!("EXPLAIN QUERY PLAN {}", sql))?;
+    let steps = stmt
+        .query_map(params_from_iter(values), |row| {
+            row.get::<_, String>("detail")
+        })?
+        .collect::<Result<Vec<String>>>()?;
     stmt.finalize()?;
+
     match conn.close() {
-        Ok(_) => Ok(()),
+        Ok(_) => Ok(steps),
         Err((_, error)) => Err(CacheError::RusqliteError(error)),
     }
 }
 
-/// Syncs the remote feeds to the local cache using the provided ``BlockingHttpClient``
+/// Returns the full CVE objects of the cached CVEs matching ``query``, all of them for an empty
+/// query, sorted by CVE ID. Listings should use ``search()``, which never reads the CVE data.
 ///
 /// ## Example:
 /// ```no_run
-/// use nvd_cve::cache::{CacheConfig, sync_blocking};
-/// use nvd_cve::client::{ReqwestBlockingClient, BlockingHttpClient};
+/// use nvd_cve::cache::{search_cves, CacheConfig};
+/// use nvd_cve::query::SearchQuery;
 ///
-/// let mut config = CacheConfig::new();
-///
-/// let client = ReqwestBlockingClient::new(&config.url, None, None, None);
+/// let config = CacheConfig::new();
+/// let query = SearchQuery {
+///     id_prefix: Some("CVE-2021-44".to_string()),
+///     ..Default::default()
+/// };
 ///
-/// if let Err(error) = sync_blocking(&config, client) {
-///     eprintln!("Fatal Error while syncing feeds: {:?}", error);
-///     std::process::exit(1);
+/// for cve in search_cves(&config, &query).unwrap() {
+///     println!("{}: {} references", cve.cve_data_meta.id, cve.references.reference_data.len());
 /// }
 /// ```
-pub fn sync_blocking<C: BlockingHttpClient>(
+pub fn search_cves(config: &CacheConfig, query: &SearchQuery) -> Result<Vec<Cve>, CacheError> {
+    let conn = open(config)?;
+
+    let (where_clause, values) = query.where_clause(
+        risk_namespace(&conn, config)?,
+        override_scope(&conn, config)?.as_deref(),
+    );
+    let mut stmt = conn.prepare(&format!("SELECT data FROM cve{} ORDER BY id", where_clause))?;
+
+    let cves = stmt.query_map(params_from_iter(values), |row| row.get::<_, String>(0))?;
+
+    let mut cve_list = vec![];
+    for cve in cves {
+        cve_list.push(serde_json::from_str(&cve?)?);
+    }
+    stmt.finalize()?;
+
+    match conn.close() {
+        Ok(_) => Ok(cve_list),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Calls ``f`` with the ID and the JSON data of each cached CVE matching ``query``, sorted by CVE
+/// ID, without parsing the data or holding more than one CVE in memory. Returns the number of CVEs.
+pub(crate) fn for_each_cve_data<F, E>(
     config: &CacheConfig,
-    client: C,
-) -> Result<(), CacheError> {
-    let mut bar = progress::Bar::new();
+    query: &SearchQuery,
+    mut f: F,
+) -> Result<usize, E>
+where
+    F: FnMut(&str, &str) -> Result<(), E>,
+    E: From<CacheError>,
+{
+    readable_schema(config)?;
 
-    let mut synced = 0;
+    let conn = open(config)?;
 
-    // Each operation is a progress point: fetch metafile, insert metafile, fetch feeds, insert CVEs
-    let to_sync = config.feeds.len() * 4;
+    let (where_clause, values) = query.where_clause(
+        risk_namespace(&conn, config)?,
+        override_scope(&conn, config)?.as_deref(),
+    );
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT id, data FROM cve{} ORDER BY id",
+            where_clause
+        ))
+        .map_err(CacheError::from)?;
 
-    if config.show_progress {
-        bar.set_job_title("Syncing CVE Data");
-        bar.reach_percent((synced as f32 / to_sync as f32 * 100.0).round() as i32);
+    let mut count = 0;
+    let mut rows = stmt
+        .query(params_from_iter(values))
+        .map_err(CacheError::from)?;
+    while let Some(row) = rows.next().map_err(CacheError::from)? {
+        let id: String = row.get(0).map_err(CacheError::from)?;
+        let data: String = row.get(1).map_err(CacheError::from)?;
+        f(&id, &data)?;
+        count += 1;
     }
+    drop(rows);
+    stmt.finalize().map_err(CacheError::from)?;
 
-    create_schema(&config.db)?;
+    match conn.close() {
+        Ok(_) => Ok(count),
+        Err((_, error)) => Err(CacheError::RusqliteError(error).into()),
+    }
+}
 
-    let feeds = get_metafiles(config)?;
+/// A cached CVE along with the dates and CVSS metrics of its feed item, which are kept in columns
+/// of their own rather than in its data, for exports needing more than the ``Cve``.
+pub(crate) struct CachedCve {
+    pub cve: Cve,
+    /// Publication date as given in the feed, empty if unknown
+    pub published: String,
+    /// Last modification date as given in the feed, empty if unknown
+    pub last_modified: String,
+    pub severity: Option<(Severity, f64)>,
+    pub attack_vector: Option<String>,
+}
 
-    for feed in feeds {
-        let mut last_modified = None;
-        if config.show_progress {
-            bar.set_job_title(format!("[Feed: {}] Fetching Metafile", feed.name).as_str());
-        }
-
-        let metafile = Metafile::from_blocking_http_client(&client, &feed.name)?;
-
-        if config.show_progress {
-            synced += 1;
-            bar.set_job_title(
-                format!(
-                    "[Feed: {}] Fetching feed ({})",
-                    feed.name,
-                    metafile
-                        .gz_size
-                        .file_size(options::CONVENTIONAL)
-                        .unwrap_or_default()
-                )
-                .as_str(),
-            );
-            bar.reach_percent((synced as f32 / to_sync as f32 * 100.0).round() as i32);
-        }
+/// Returns the ``CachedCve`` of each cached CVE matching ``query``, sorted by CVE ID.
+pub(crate) fn search_cached_cves(
+    config: &CacheConfig,
+    query: &SearchQuery,
+) -> Result<Vec<CachedCve>, CacheError> {
+    readable_schema(config)?;
 
-        if let Some(db_metafile) = feed.metafile {
-            last_modified = Some(&metafile.last_modified_date);
-            if !config.force_update
-                && (db_metafile.last_modified_date >= metafile.last_modified_date)
-            {
-                debug!(
-                    "Cached Metafile: {} is the latest ({})",
-                    feed.name, metafile.last_modified_date
-                );
-                // Skip insert metafile, fetch feeds, insert CVEs
-                synced += 3;
-                continue;
-            }
-        }
+    let conn = open(config)?;
 
-        let cve_feed = CveFeed::from_blocking_http_client(&client, &feed.name)?;
+    let (where_clause, values) = query.where_clause(
+        risk_namespace(&conn, config)?,
+        override_scope(&conn, config)?.as_deref(),
+    );
+    let mut stmt = conn.prepare(&format!(
+        "SELECT data, published_date, last_modified_date, base_severity, base_score, attack_vector
+        FROM cve{} ORDER BY id",
+        where_clause
+    ))?;
 
-        if config.show_progress {
-            synced += 1;
-            bar.set_job_title(
-                format!(
-                    "[Feed: {}] Syncing {} CVEs",
-                    feed.name,
-                    cve_feed.cve_items.len()
-                )
-                .as_str(),
-            );
-            bar.reach_percent((synced as f32 / to_sync as f32 * 100.0).round() as i32);
-        }
+    let rows = stmt.query_map(params_from_iter(values), |row| {
+        let severity = match (row.get(3)?, row.get(4)?) {
+            (Some(severity), Some(score)) => Some((severity, score)),
+            _ => None,
+        };
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+            row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+            severity,
+            row.get::<_, Option<String>>(5)?,
+        ))
+    })?;
 
-        update_cves(config, &cve_feed.cve_items, last_modified)?;
+    let mut cves = vec![];
+    for row in rows {
+        let (data, published, last_modified, severity, attack_vector) = row?;
+        cves.push(CachedCve {
+            cve: serde_json::from_str(&data)?,
+            published,
+            last_modified,
+            severity,
+            attack_vector,
+        });
+    }
+    stmt.finalize()?;
 
-        if config.show_progress {
-            synced += 1;
-            bar.reach_percent((synced as f32 / to_sync as f32 * 100.0).round() as i32);
-        }
+    match conn.close() {
+        Ok(_) => Ok(cves),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
 
-        update_metafile(config, &feed.name, &metafile)?;
+/// Returns the CVE from the first of the ``configs`` whose cache contains it, along with the name
+/// of that cache's source. Caches are consulted in the order provided, so sources with precedence
+/// should be listed first.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::{CacheConfig, search_by_id_in_sources};
+///
+/// let configs = vec![CacheConfig::for_source("internal"), CacheConfig::new()];
+///
+/// let (source, cve) = search_by_id_in_sources(&configs, "CVE-2019-18254").unwrap();
+/// println!("{}: {:?}", source, &cve);
+/// ```
+pub fn search_by_id_in_sources(
+    configs: &[CacheConfig],
+    cve: &str,
+) -> Result<(String, Cve), CacheError> {
+    let mut last_error = CacheError::RusqliteError(rusqlite::Error::QueryReturnedNoRows);
 
-        if config.show_progress {
-            synced += 1;
-            bar.reach_percent((synced as f32 / to_sync as f32 * 100.0).round() as i32);
+    for config in configs {
+        match search_by_id(config, cve) {
+            Ok(result) => return Ok((config.source.clone(), result)),
+            Err(error) => {
+                debug!("{} not found in source {}: {:?}", cve, config.source, error);
+                last_error = error;
+            }
         }
     }
 
-    Ok(())
+    Err(last_error)
+}
+
+/// A record of a vulnerability held by a cache.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "snake_case"))]
+pub enum Record {
+    /// The full container of a CVE synced from the feeds of a source.
+    Cve(Box<CveContainer>),
+    /// An OSV advisory, synced with ``osv_ecosystems``.
+    Osv(Box<OsvVulnerability>),
+}
+
+/// A record of a vulnerability, along with where it comes from.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct SourcedRecord {
+    /// Name of the source of the cache holding a CVE, e.g. ``nvd``, or ``OSV`` for an advisory.
+    pub source: String,
+    pub id: String,
+    pub record: Record,
+}
+
+/// Everything the caches hold about a vulnerability known by one of its identifiers, see
+/// ``resolve()``.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Resolution {
+    /// The identifier resolved, as given.
+    pub id: String,
+
+    /// CVE IDs of the vulnerability, sorted. An advisory covering several CVEs resolves to all of
+    /// them.
+    pub cves: Vec<String>,
+
+    /// Every other identifier of the CVEs, sorted, along with the source naming it.
+    pub aliases: Vec<Alias>,
+
+    /// The records of the CVEs followed by those of the OSV advisories.
+    pub records: Vec<SourcedRecord>,
 }
 
-/// Returns all the CVEs available in the database.
+/// Resolves any identifier of a vulnerability, a CVE ID or an alias such as a GHSA or OSV ID, to
+/// the CVEs it names and returns their records merged with the OSV advisories of their aliases,
+/// each with its source. Fails like ``search_by_id()`` when the cache knows nothing of ``id``.
 ///
 /// ## Example:
 /// ```no_run
-/// use nvd_cve::cache::{CacheConfig, get_all};
+/// use nvd_cve::cache::{CacheConfig, resolve};
 ///
 /// let config = CacheConfig::new();
 ///
-/// let all_cves = get_all(&config).unwrap();
-/// println!("{:?}", &all_cves);
+/// let resolution = resolve(&config, "GHSA-35jh-r3h4-6jhm").unwrap();
+/// for record in resolution.records {
+///     println!("{} {}", record.source, record.id);
+/// }
 /// ```
-pub fn get_all(config: &CacheConfig) -> Result<Vec<Cve>, CacheError> {
-    let conn = Connection::open(&config.db)?;
-    let mut stmt = conn.prepare("SELECT * FROM cve")?;
+pub fn resolve(config: &CacheConfig, id: &str) -> Result<Resolution, CacheError> {
+    let id = id.trim();
+    let mut resolution = Resolution {
+        id: id.to_string(),
+        ..Default::default()
+    };
 
-    let cves = stmt.query_map(params![], |row| {
-        let data: String = row.get("data")?;
-        Ok(data)
-    })?;
+    let mut advisories = BTreeSet::new();
+    if id.to_ascii_uppercase().starts_with("CVE-") {
+        resolution.cves.push(id.to_ascii_uppercase());
+    } else {
+        resolution.cves = resolve_alias(config, id)?;
+        advisories.insert(id.to_string());
+    }
 
-    let mut cve_list = vec![];
-    for cve in cves {
-        let result: Cve = serde_json::from_str(cve?.as_str())?;
-        cve_list.push(result);
+    for cve in &resolution.cves {
+        resolution.aliases.extend(get_aliases(config, cve)?);
+        match search_container_by_id(config, cve) {
+            Ok(container) => resolution.records.push(SourcedRecord {
+                source: config.source.clone(),
+                id: cve.clone(),
+                record: Record::Cve(Box::new(container)),
+            }),
+            Err(error) if error.is_not_found() => continue,
+            Err(error) => return Err(error),
+        }
     }
-    stmt.finalize()?;
+    resolution.aliases.sort();
+    resolution.aliases.dedup();
+    advisories.extend(resolution.aliases.iter().map(|alias| alias.alias.clone()));
 
-    match conn.close() {
-        Ok(_) => Ok(cve_list),
-        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    let conn = open(config)?;
+    for advisory in advisories {
+        if let Some(vulnerability) = query_osv(&conn, &advisory)? {
+            resolution.records.push(SourcedRecord {
+                source: OSV_ALIAS_SOURCE.to_string(),
+                id: advisory,
+                record: Record::Osv(Box::new(vulnerability)),
+            });
+        }
+    }
+    if let Err((_, error)) = conn.close() {
+        return Err(CacheError::RusqliteError(error));
+    }
+
+    match resolution.records.is_empty() && resolution.aliases.is_empty() {
+        true => Err(CacheError::RusqliteError(
+            rusqlite::Error::QueryReturnedNoRows,
+        )),
+        false => Ok(resolution),
     }
 }
 
-/// Returns the full CVE object that is extracted from the feed for the provided CVE ID.
+/// Resolves ``id`` in the caches of all the ``configs`` like ``resolve()`` does, and merges what
+/// they hold. The records of a CVE held by several caches are all kept, each with the source of
+/// its cache, while OSV advisories are kept once. Fails when none of the caches know ``id``.
 ///
 /// ## Example:
 /// ```no_run
-/// use nvd_cve::cache::{CacheConfig, search_by_id};
+/// use nvd_cve::cache::{CacheConfig, resolve_in_sources};
 ///
-/// let config = CacheConfig::new();
+/// let configs = vec![CacheConfig::for_source("internal"), CacheConfig::new()];
 ///
-/// let cve_result = search_by_id(&config, "CVE-2019-18254").unwrap();
-/// println!("{:?}", &cve_result);
+/// let resolution = resolve_in_sources(&configs, "CVE-2021-44228").unwrap();
+/// println!("{:?}", resolution.aliases);
 /// ```
-pub fn search_by_id(config: &CacheConfig, cve: &str) -> Result<Cve, CacheError> {
-    let conn = Connection::open(&config.db)?;
-
-    let mut stmt = conn.prepare("SELECT * FROM cve where id=?1")?;
+pub fn resolve_in_sources(configs: &[CacheConfig], id: &str) -> Result<Resolution, CacheError> {
+    let mut last_error = CacheError::RusqliteError(rusqlite::Error::QueryReturnedNoRows);
+    let mut merged: Option<Resolution> = None;
 
-    let data = stmt.query_row([&cve], |row| {
-        let data: String = row.get("data")?;
-        Ok(data)
-    })?;
+    for config in configs {
+        let resolution = match resolve(config, id) {
+            Ok(resolution) => resolution,
+            Err(error) if error.is_not_found() => {
+                debug!("{} not found in source {}: {:?}", id, config.source, error);
+                last_error = error;
+                continue;
+            }
+            Err(error) => return Err(error),
+        };
+        let Some(merged) = &mut merged else {
+            merged = Some(resolution);
+            continue;
+        };
+        merged.cves.extend(resolution.cves);
+        merged.cves.sort();
+        merged.cves.dedup();
+        merged.aliases.extend(resolution.aliases);
+        merged.aliases.sort();
+        merged.aliases.dedup();
+        for record in resolution.records {
+            if !merged
+                .records
+                .iter()
+                .any(|known| known.source == record.source && known.id == record.id)
+            {
+                merged.records.push(record);
+            }
+        }
+    }
 
-    stmt.finalize()?;
+    let mut merged = merged.ok_or(last_error)?;
+    merged
+        .records
+        .sort_by_key(|record| matches!(record.record, Record::Osv(_)));
+    Ok(merged)
+}
 
-    let result: Cve = serde_json::from_str(data.as_str())?;
+/// Searches the caches of all the ``configs`` for CVEs matching the ``SearchQuery``, and returns
+/// the merged Vec of ``CveSummary`` sorted by CVE ID. A CVE found in several caches is summarized
+/// from the first of the ``configs`` containing it.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::{CacheConfig, search_sources};
+/// use nvd_cve::query::SearchQuery;
+///
+/// let configs = vec![CacheConfig::new(), CacheConfig::for_source("internal")];
+///
+/// let query = SearchQuery {
+///     text: Some("implanted cardiac device".to_string()),
+///     ..Default::default()
+/// };
+///
+/// for cve in search_sources(&configs, &query).unwrap() {
+///     println!("{}", cve);
+/// }
+/// ```
+pub fn search_sources(
+    configs: &[CacheConfig],
+    query: &SearchQuery,
+) -> Result<Vec<CveSummary>, CacheError> {
+    let mut cve_list = vec![];
 
-    match conn.close() {
-        Ok(_) => Ok(result),
-        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    for config in configs {
+        cve_list.extend(search(config, query)?);
     }
+
+    // The sort is stable, so the summary from the first config is the one kept
+    cve_list.sort_by(|a, b| a.id.cmp(&b.id));
+    cve_list.dedup_by(|a, b| a.id == b.id);
+
+    Ok(cve_list)
 }
 
-/// Searches all local CVE descriptions for the provided ``text`` string, and returns a Vec of CVE ID Strings for any matches.
+/// Returns the summaries of all CVEs in the local cache with the weakness ``cwe``, such as
+/// ``CWE-79`` or only ``79``, listed in their problem types.
 ///
 /// ## Example:
 /// ```no_run
-/// use nvd_cve::cache::{CacheConfig, search_description};
+/// use nvd_cve::cache::{CacheConfig, search_by_cwe};
 ///
 /// let config = CacheConfig::new();
 ///
-/// if let Ok(cves) = search_description(&config, "implanted cardiac device") {
-///     for cve_id in cves {
-///         println!("{}", cve_id);
-///     }
+/// for cve in search_by_cwe(&config, "CWE-79").unwrap() {
+///     println!("{}", cve.id);
 /// }
 /// ```
+pub fn search_by_cwe(config: &CacheConfig, cwe: &str) -> Result<Vec<CveSummary>, CacheError> {
+    let query = SearchQuery {
+        cwe: Some(cwe.to_string()),
+        ..Default::default()
+    };
+    search(config, &query)
+}
 
-pub fn search_description(config: &CacheConfig, text: &str) -> Result<Vec<String>, CacheError> {
-    let conn = Connection::open(&config.db)?;
+/// Returns the summaries of all CVEs in the local cache whose configurations name ``product`` in
+/// a CPE, along with ``vendor`` in the same CPE if given, both ignoring case.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::{CacheConfig, search_by_product};
+///
+/// let config = CacheConfig::new();
+///
+/// for cve in search_by_product(&config, Some("microsoft"), "exchange_server").unwrap() {
+///     println!("{}", cve.id);
+/// }
+/// ```
+pub fn search_by_product(
+    config: &CacheConfig,
+    vendor: Option<&str>,
+    product: &str,
+) -> Result<Vec<CveSummary>, CacheError> {
+    let query = SearchQuery {
+        vendor: vendor.map(str::to_string),
+        product: Some(product.to_string()),
+        ..Default::default()
+    };
+    search(config, &query)
+}
 
-    let mut stmt = conn.prepare("SELECT id FROM cve where description like '%' || ?1 || '%'")?;
+/// Returns the summaries of all CVEs in the local cache assigned by the CNA ``assigner``, such as
+/// ``security@apache.org``, ignoring case.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::{CacheConfig, search_by_assigner};
+///
+/// let config = CacheConfig::new();
+///
+/// for cve in search_by_assigner(&config, "security@apache.org").unwrap() {
+///     println!("{}", cve.id);
+/// }
+/// ```
+pub fn search_by_assigner(
+    config: &CacheConfig,
+    assigner: &str,
+) -> Result<Vec<CveSummary>, CacheError> {
+    let query = SearchQuery {
+        assigner: Some(assigner.to_string()),
+        ..Default::default()
+    };
+    search(config, &query)
+}
 
-    let cves = stmt.query_map(params![text], |row| {
-        let id: String = row.get("id")?;
-        Ok(id)
-    })?;
+/// Returns the summaries of all CVEs in the local cache referencing ``reference``, a URL or its
+/// beginning, such as a repository ``github.com/org/repo`` or a domain ``example.com``, see
+/// ``SearchQuery::reference``.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::{CacheConfig, search_by_reference};
+///
+/// let config = CacheConfig::new();
+///
+/// for cve in search_by_reference(&config, "github.com/apache/logging-log4j2").unwrap() {
+///     println!("{}", cve.id);
+/// }
+/// ```
+pub fn search_by_reference(
+    config: &CacheConfig,
+    reference: &str,
+) -> Result<Vec<CveSummary>, CacheError> {
+    let query = SearchQuery {
+        reference: Some(reference.to_string()),
+        ..Default::default()
+    };
+    search(config, &query)
+}
 
-    let mut cve_list = vec![];
+/// Returns the summaries of all CVEs in the local cache of the given ``severity``, from their CVSS
+/// v3 metrics or else their CVSS v2 metrics.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::{CacheConfig, search_by_severity};
+/// use nvd_cve::cvss::Severity;
+///
+/// let config = CacheConfig::new();
+///
+/// for cve in search_by_severity(&config, Severity::Critical).unwrap() {
+///     println!("{} {:?}", cve.id, cve.score);
+/// }
+/// ```
+pub fn search_by_severity(
+    config: &CacheConfig,
+    severity: Severity,
+) -> Result<Vec<CveSummary>, CacheError> {
+    let query = SearchQuery {
+        severity: Some(severity),
+        ..Default::default()
+    };
+    search(config, &query)
+}
 
-    for cve in cves {
-        cve_list.push(cve?);
-    }
+/// Returns the full CVE objects of the cached CVEs published at or ``after`` a date and ``before``
+/// another, either bound left open with ``None``, sorted by CVE ID.
+///
+/// ## Example:
+/// ```no_run
+/// use chrono::{TimeZone, Utc};
+/// use nvd_cve::cache::{search_published_between, CacheConfig};
+///
+/// let config = CacheConfig::new();
+/// let after = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+///
+/// for cve in search_published_between(&config, Some(after), None).unwrap() {
+///     println!("{}", cve.cve_data_meta.id);
+/// }
+/// ```
+pub fn search_published_between(
+    config: &CacheConfig,
+    after: Option<DateTime<Utc>>,
+    before: Option<DateTime<Utc>>,
+) -> Result<Vec<Cve>, CacheError> {
+    let query = SearchQuery {
+        published_after: after,
+        published_before: before,
+        ..Default::default()
+    };
+    search_cves(config, &query)
+}
 
-    stmt.finalize()?;
+/// Same as ``search_published_between()`` for the date the CVEs were last modified.
+pub fn search_modified_between(
+    config: &CacheConfig,
+    after: Option<DateTime<Utc>>,
+    before: Option<DateTime<Utc>>,
+) -> Result<Vec<Cve>, CacheError> {
+    let query = SearchQuery {
+        modified_after: after,
+        modified_before: before,
+        ..Default::default()
+    };
+    search_cves(config, &query)
+}
 
-    match conn.close() {
-        Ok(_) => Ok(cve_list),
-        Err((_, error)) => Err(CacheError::RusqliteError(error)),
-    }
+/// Returns the summaries of all CVEs in the local cache with an ID beginning with ``prefix``, such as
+/// all CVEs with an ID starting ``CVE-2024-123``.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::{CacheConfig, search_id_prefix};
+///
+/// let config = CacheConfig::new();
+///
+/// for cve in search_id_prefix(&config, "CVE-2021-4422").unwrap() {
+///     println!("{}", cve.id);
+/// }
+/// ```
+pub fn search_id_prefix(config: &CacheConfig, prefix: &str) -> Result<Vec<CveSummary>, CacheError> {
+    let query = SearchQuery {
+        id_prefix: Some(prefix.to_string()),
+        ..Default::default()
+    };
+    search(config, &query)
 }
@@ -1,64 +1,954 @@
-use clap::ArgMatches;
-use nvd_cve::cache::{search_by_id, CacheConfig};
-use nvd_cve::cache::{search_description, sync_blocking};
+use crate::picker;
+use crate::service::{absolute, Service, ServiceKind};
+#[cfg(feature = "scan-host")]
+use crate::ScanHostArgs;
+#[cfg(feature = "snapshot")]
+use crate::SnapshotCommand;
+use crate::{
+    AttestationsArgs, CheckArgs, CompareArgs, CriteriaArgs, CsafArgs, DoctorArgs, ExportArgs,
+    FeedsArgs, FilterArgs, HistoryArgs, InstallServiceArgs, OverrideArgs, PackageArgs, ReindexArgs,
+    ResolveArgs, SearchArgs, SyncArgs, TicketsArgs,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use log::info;
+use nvd_cve::attestation::get_attestations;
+use nvd_cve::cache::{
+    check as check_cache, get_cached_feeds, get_change_events, reindex as reindex_cache,
+    resolve_in_sources, search_by_id_as_of, search_by_id_in_sources, search_container_by_id,
+    search_cves, search_sources, sync_blocking, sync_change_history, CacheConfig, CacheError,
+    StageSpan,
+};
 use nvd_cve::client::{BlockingHttpClient, ReqwestBlockingClient};
+use nvd_cve::compare::{get_record, CveComparison};
+use nvd_cve::cpedict::{is_known_product, sync_cpe_dictionary};
+use nvd_cve::cpematch::sync_cpe_matches;
+use nvd_cve::csaf::{fetch_provider_advisories, ingest_advisories, Advisory, CsafError};
+use nvd_cve::cve::{Cve, CveFeed, CveSummary};
+use nvd_cve::cvss::UserInteraction;
+use nvd_cve::cwe::{get_weaknesses, sync_cwes};
+use nvd_cve::doctor::{diagnose, Status};
+use nvd_cve::epss::sync_epss;
+use nvd_cve::feed::{FeedName, FeedSelector};
+use nvd_cve::filter::FilteredFeed;
+use nvd_cve::history::{fetch_change_events, get_sync_runs, NVD_CHANGE_HISTORY_URL};
+use nvd_cve::kev::sync_kev;
+use nvd_cve::kv::write_kv_index;
+use nvd_cve::metrics::write_metrics;
+use nvd_cve::misp::write_misp_feed;
+use nvd_cve::osv::{search_package, sync_osv};
+use nvd_cve::overrides::{get_override, remove_override, set_override, CveWithOverride};
+use nvd_cve::query::SearchQuery;
+use nvd_cve::risk::{score_cves, search_by_risk, ScoredCve};
+#[cfg(feature = "scan-host")]
+use nvd_cve::scan::{
+    installed_packages, packages_from_json, scan_packages, CpeDictionary, PackageManager,
+    ScanError, Suppressions,
+};
+#[cfg(feature = "snapshot")]
+use nvd_cve::snapshot::{fetch_diff, fetch_snapshot, publish_diff, publish_snapshot};
+use nvd_cve::stix::export_stix;
+use nvd_cve::template::Template;
+use nvd_cve::ticket::{
+    create_tickets, JiraTracker, TicketError, TicketTemplate, TicketTracker, WebhookTracker,
+    DEFAULT_DESCRIPTION_TEMPLATE, DEFAULT_SUMMARY_TEMPLATE,
+};
+#[cfg(feature = "scan-host")]
+use nvd_cve::vex::{CsafDocument, OpenVexDocument, VexPublisher};
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::Path;
+#[cfg(feature = "snapshot")]
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::Level;
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::fmt;
+use tracing_subscriber::prelude::*;
 
-pub fn sync(matches: &ArgMatches) {
+/// Parse the name of a source, which must be usable as a database filename
+pub fn parse_source(source: &str) -> Result<String, String> {
+    if source.is_empty() || source.contains(['/', '\\', '.']) {
+        return Err(String::from(
+            "source names can't be empty or contain '/', '\\' or '.'",
+        ));
+    }
+    Ok(source.to_string())
+}
+
+/// Parse the name of a namespace of overrides and risk scores
+pub fn parse_namespace(namespace: &str) -> Result<String, String> {
+    if namespace.trim().is_empty() {
+        return Err(String::from("namespace names can't be empty"));
+    }
+    Ok(namespace.to_string())
+}
+
+/// Parse a duration such as ``30s``, ``500ms``, ``2m``, ``1h`` or ``2d``, plain numbers are
+/// seconds
+pub fn parse_duration(duration: &str) -> Result<Duration, String> {
+    let duration = duration.trim();
+    let split = duration
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(duration.len());
+    let (value, unit) = duration.split_at(split);
+
+    let value: u64 = value
+        .parse()
+        .map_err(|_| format!("invalid duration {:?}, e.g. 30s, 500ms or 2m", duration))?;
+
+    match unit {
+        "ms" => Ok(Duration::from_millis(value)),
+        "" | "s" => Ok(Duration::from_secs(value)),
+        "m" => Ok(Duration::from_secs(value * 60)),
+        "h" => Ok(Duration::from_secs(value * 60 * 60)),
+        "d" => Ok(Duration::from_secs(value * 60 * 60 * 24)),
+        _ => Err(format!(
+            "unknown duration unit {:?}, expected ms, s, m, h or d",
+            unit
+        )),
+    }
+}
+
+/// Parse a size such as ``500M`` or ``2G`` into bytes, the ``K``, ``M`` and ``G`` units being
+/// multiples of 1024 and plain numbers bytes
+pub fn parse_size(size: &str) -> Result<u64, String> {
+    let size = size.trim();
+    let split = size
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(size.len());
+    let (value, unit) = size.split_at(split);
+
+    let value: u64 = value
+        .parse()
+        .map_err(|_| format!("invalid size {:?}, e.g. 500M or 2G", size))?;
+
+    let multiple: u64 = match unit.to_ascii_uppercase().trim_end_matches('B') {
+        "" => 1,
+        "K" => 1024,
+        "M" => 1024 * 1024,
+        "G" => 1024 * 1024 * 1024,
+        _ => return Err(format!("unknown size unit {:?}, expected K, M or G", unit)),
+    };
+    value
+        .checked_mul(multiple)
+        .ok_or_else(|| format!("size {:?} is too large", size))
+}
+
+/// Parse the date of ``search --as-of``, ``csaf --since`` or the date criteria such as
+/// ``--published-after``, a day such as ``2023-06-01`` taken at midnight UTC, or an RFC 3339 date
+/// and time
+pub fn parse_as_of(date: &str) -> Result<DateTime<Utc>, String> {
+    let date = date.trim();
+    if let Ok(day) = NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+        return Ok(day.and_hms_opt(0, 0, 0).unwrap().and_utc());
+    }
+    DateTime::parse_from_rfc3339(date)
+        .map(|date| date.with_timezone(&Utc))
+        .map_err(|_| {
+            format!(
+                "invalid date {:?}, e.g. 2023-06-01 or 2023-06-01T14:30:00Z",
+                date
+            )
+        })
+}
+
+/// Parse the delimiter of ``search --fields``, where ``\t`` stands for a tab
+pub fn parse_delimiter(delimiter: &str) -> Result<String, String> {
+    match delimiter {
+        "" => Err(String::from("the delimiter can't be empty")),
+        "\\t" => Ok(String::from("\t")),
+        _ => Ok(delimiter.to_string()),
+    }
+}
+
+/// A CVE given to ``compare``, ``CVE-ID`` for its cached version or ``CVE-ID@DATE`` for the
+/// version current at a date, parsed as with ``search --as-of``
+#[derive(Debug, Clone, PartialEq)]
+pub struct CveVersion {
+    pub id: String,
+    pub as_of: Option<DateTime<Utc>>,
+}
+
+impl FromStr for CveVersion {
+    type Err = String;
+
+    fn from_str(version: &str) -> Result<Self, Self::Err> {
+        let (id, as_of) = match version.split_once('@') {
+            Some((id, date)) => (id, Some(parse_as_of(date)?)),
+            None => (version, None),
+        };
+        if id.trim().is_empty() {
+            return Err(String::from("the CVE ID can't be empty"));
+        }
+        Ok(Self {
+            id: id.trim().to_string(),
+            as_of,
+        })
+    }
+}
+
+/// Format of the CVEs written by ``export``
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Stix,
+    Misp,
+    Kv,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format.trim().to_lowercase().as_str() {
+            "json" => Ok(ExportFormat::Json),
+            "stix" => Ok(ExportFormat::Stix),
+            "misp" => Ok(ExportFormat::Misp),
+            "kv" => Ok(ExportFormat::Kv),
+            _ => Err(format!(
+                "unknown format {:?}, expected json, stix, misp or kv",
+                format
+            )),
+        }
+    }
+}
+
+/// Format of the results of ``scan-host``
+#[cfg(feature = "scan-host")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanFormat {
+    Text,
+    Json,
+    OpenVex,
+    Csaf,
+}
+
+#[cfg(feature = "scan-host")]
+impl FromStr for ScanFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format.trim().to_lowercase().as_str() {
+            "text" => Ok(ScanFormat::Text),
+            "json" => Ok(ScanFormat::Json),
+            "openvex" => Ok(ScanFormat::OpenVex),
+            "csaf" => Ok(ScanFormat::Csaf),
+            _ => Err(format!(
+                "unknown format {:?}, expected text, json, openvex or csaf",
+                format
+            )),
+        }
+    }
+}
+
+/// Field of a CVE listed by ``search --fields``
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Id,
+    /// Severity of the local override of the CVE if it sets one, otherwise of the feed
+    Severity,
+    Score,
+    Published,
+    Description,
+    /// Whether the CVE is known exploited, ``yes`` or ``no``
+    Kev,
+    /// EPSS probability of the CVE being exploited
+    Epss,
+}
+
+impl FromStr for Field {
+    type Err = String;
+
+    fn from_str(field: &str) -> Result<Self, Self::Err> {
+        match field.trim().to_lowercase().as_str() {
+            "id" => Ok(Field::Id),
+            "severity" => Ok(Field::Severity),
+            "score" => Ok(Field::Score),
+            "published" => Ok(Field::Published),
+            "description" => Ok(Field::Description),
+            "kev" => Ok(Field::Kev),
+            "epss" => Ok(Field::Epss),
+            _ => Err(format!(
+                "unknown field {:?}, expected id, severity, score, published, description, kev or epss",
+                field
+            )),
+        }
+    }
+}
+
+impl Field {
+    /// Value of the field for ``cve``, ``-`` if it is unknown
+    fn value(&self, cve: &CveSummary) -> String {
+        match self {
+            Field::Id => cve.id.clone(),
+            Field::Severity => cve
+                .assessed_severity()
+                .map_or("-".to_string(), |s| s.to_string()),
+            Field::Score => cve
+                .score
+                .map_or("-".to_string(), |score| format!("{:.1}", score)),
+            Field::Published => cve.published.clone().unwrap_or_else(|| "-".to_string()),
+            Field::Description => cve.description.clone(),
+            Field::Kev => if cve.known_exploited { "yes" } else { "no" }.to_string(),
+            Field::Epss => cve
+                .epss
+                .map_or("-".to_string(), |epss| format!("{:.5}", epss)),
+        }
+    }
+}
+
+/// Print the ``fields`` of a CVE on one line, separated by ``delimiter``. Line breaks and
+/// delimiters within values are replaced with spaces so each CVE is a single record.
+fn print_fields(cve: &CveSummary, fields: &[Field], delimiter: &str) {
+    let values: Vec<String> = fields
+        .iter()
+        .map(|field| {
+            field
+                .value(cve)
+                .replace(delimiter, " ")
+                .replace(['\n', '\r'], " ")
+        })
+        .collect();
+    println!("{}", values.join(delimiter));
+}
+
+/// Template of the line printed for each CVE listed by ``search --format-str``, in which a
+/// placeholder such as ``{score}`` is replaced by the value of one of the ``--fields`` and may be
+/// formatted as ``{field:<WIDTH.PRECISION}``, see ``Template``. ``\t``, ``\n`` and ``\\`` are a
+/// tab, a line break and a backslash. Line breaks and tabs within values are replaced with spaces.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineTemplate(Template<Field>);
+
+impl FromStr for LineTemplate {
+    type Err = String;
+
+    fn from_str(template: &str) -> Result<Self, Self::Err> {
+        let mut unescaped = String::with_capacity(template.len());
+        let mut chars = template.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('t') => unescaped.push('\t'),
+                    Some('n') => unescaped.push('\n'),
+                    Some('\\') => unescaped.push('\\'),
+                    other => {
+                        return Err(format!(
+                            "unknown escape \\{}, expected \\t, \\n or \\\\",
+                            other.map(String::from).unwrap_or_default()
+                        ))
+                    }
+                },
+                c => unescaped.push(c),
+            }
+        }
+        Ok(LineTemplate(unescaped.parse()?))
+    }
+}
+
+impl LineTemplate {
+    /// The line of ``cve``
+    fn render(&self, cve: &CveSummary) -> String {
+        self.0
+            .render(|field| field.value(cve).replace(['\n', '\r', '\t'], " "))
+    }
+}
+
+/// Print the debug events of the HTTP client, such as request URLs and response statuses, to
+/// stderr
+fn init_http_tracing() {
+    let filter = Targets::new().with_target("nvd_cve::client", Level::DEBUG);
+    tracing_subscriber::registry()
+        .with(fmt::layer().with_writer(std::io::stderr))
+        .with(filter)
+        .init();
+}
+
+/// Disable the verification of TLS certificates by ``client`` if ``--tls-insecure`` was given,
+/// warning loudly that the feeds fetched can't be trusted
+fn tls_insecure(client: ReqwestBlockingClient, enabled: bool) -> ReqwestBlockingClient {
+    if enabled {
+        eprintln!(
+            "WARNING: TLS certificate verification is disabled by --tls-insecure, anyone on the \
+             network path could tamper with the data fetched"
+        );
+    }
+    client.with_tls_insecure(enabled)
+}
+
+/// Limit the redirects ``client`` follows and the hosts it may contact to those of ``config``
+fn restrict_hosts(client: ReqwestBlockingClient, config: &CacheConfig) -> ReqwestBlockingClient {
+    client
+        .with_max_redirects(config.max_redirects)
+        .with_allowed_hosts(&config.allowed_hosts)
+}
+
+/// Read a TOML config file, exiting with an error if it can't be read or parsed. Values missing
+/// from the file are left at their defaults.
+fn read_config(path: &Path) -> CacheConfig {
+    let table: toml::Table = match fs::read_to_string(path).map(|contents| contents.parse()) {
+        Ok(Ok(table)) => table,
+        Ok(Err(error)) => {
+            eprintln!("Error: Invalid config file {:?}: {}", path, error);
+            std::process::exit(1);
+        }
+        Err(error) => {
+            eprintln!("Error: Failed reading config file {:?}: {}", path, error);
+            std::process::exit(1);
+        }
+    };
+
+    let has_db = table.contains_key("db");
+
+    let mut config: CacheConfig = match table.try_into() {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("Error: Invalid config file {:?}: {}", path, error);
+            std::process::exit(1);
+        }
+    };
+
+    // Without an explicit database, the configured source is cached in its own database
+    if !has_db {
+        config.db = CacheConfig::default_db_path_for_source(&config.source);
+    }
+
+    config
+}
+
+pub fn sync(args: &SyncArgs) {
     let mut config = CacheConfig::new();
 
-    if matches.is_present("show") {
-        println!("Default Config Values:\n{}", config);
+    if args.show {
+        print!("{}", toml::to_string_pretty(&config).unwrap());
         return;
     }
 
-    if let Some(url) = matches.value_of("url") {
-        config.url = String::from(url);
+    if let Some(path) = &args.config {
+        config = read_config(path);
+    }
+
+    if let Some(source) = &args.source {
+        config.db = CacheConfig::default_db_path_for_source(source);
+        config.source = source.clone();
+    }
+
+    if let Some(url) = &args.url {
+        config.url = url.to_string();
     }
 
-    if let Some(feeds) = matches.value_of("feeds") {
-        config.feeds = feeds.split(',').map(|feed| feed.to_string()).collect();
+    if let Some(namespace) = &args.namespace {
+        config.namespace = namespace.clone();
     }
 
-    if let Some(db) = matches.value_of("db") {
-        config.db = String::from(db);
+    if let Some(compression) = args.compression {
+        config.compression = compression;
     }
 
-    if matches.is_present("no_progress") {
+    if let Some(threads) = args.threads {
+        config.parse_threads = threads;
+    }
+
+    if let Some(feeds) = &args.feeds {
+        config.feeds = FeedSelector::expand(feeds);
+    }
+
+    if let Some(db) = &args.db {
+        config.db = db.to_string_lossy().into_owned();
+    }
+
+    if args.no_progress {
         config.show_progress = false;
     }
 
-    if matches.is_present("force") {
+    if args.force {
         config.force_update = true;
     }
 
-    if matches.is_present("verbose") {
+    if let Some(timeout) = args.feed_timeout {
+        config.feed_timeout = Some(timeout);
+    }
+
+    if let Some(max_redirects) = args.max_redirects {
+        config.max_redirects = max_redirects;
+    }
+
+    if let Some(hosts) = &args.allow_hosts {
+        config.allowed_hosts = hosts.clone();
+    }
+
+    if let Some(deadline) = args.deadline {
+        config.sync_deadline = Some(deadline);
+    }
+
+    if let Some(policy) = args.on_timeout {
+        config.on_timeout = policy;
+    }
+
+    if let Some(path) = &args.attestation_key {
+        config.attestation_key = Some(path.to_string_lossy().into_owned());
+    }
+
+    if let Some(url) = &args.change_history {
+        config.change_history_url = Some(url.to_string());
+    }
+
+    if let Some(url) = &args.cpe_match {
+        config.cpe_match_url = Some(url.to_string());
+    }
+
+    if let Some(url) = &args.cpe_dictionary {
+        config.cpe_dictionary_url = Some(url.to_string());
+    }
+
+    if let Some(url) = &args.kev {
+        config.kev_url = Some(url.to_string());
+    }
+
+    if let Some(url) = &args.cwe_list {
+        config.cwe_url = Some(url.to_string());
+    }
+
+    if let Some(url) = &args.epss {
+        config.epss_url = Some(url.to_string());
+    }
+
+    if let Some(ecosystems) = &args.osv {
+        config.osv_ecosystems = ecosystems.clone();
+    }
+
+    if let Some(url) = &args.osv_url {
+        config.osv_url = url.to_string();
+    }
+
+    if args.track_history {
+        config.track_history = true;
+    }
+
+    if let Some(size) = args.max_db_size {
+        config.max_db_size = Some(size);
+    }
+
+    if let Some(policy) = args.prune {
+        config.prune_policy = policy;
+    }
+
+    if args.trace_feeds {
+        config.trace_feeds = true;
+    }
+
+    if let Some(path) = &args.trace {
+        config.trace_file = Some(path.to_string_lossy().into_owned());
+    }
+
+    if let Some(path) = &args.metrics_file {
+        config.metrics_file = Some(path.to_string_lossy().into_owned());
+    }
+
+    if args.verbose {
         env_logger::init();
     }
 
-    let client = ReqwestBlockingClient::new(&config.url, None, None, None);
+    if args.debug_http {
+        init_http_tracing();
+    }
 
-    if let Err(error) = sync_blocking(&config, client) {
-        eprintln!("Fatal Error: {:?}", error);
-        std::process::exit(1);
+    let client = tls_insecure(
+        restrict_hosts(
+            ReqwestBlockingClient::new(&config.url, args.connect_timeout, None, None)
+                .with_compression(config.compression)
+                .with_parse_threads(config.parse_threads)
+                .with_env_proxy(!args.no_proxy),
+            &config,
+        ),
+        args.tls_insecure,
+    );
+
+    match sync_blocking(&config, client) {
+        Ok(report) => {
+            for changes in &report.feed_changes {
+                info!(
+                    "[Feed: {}] {} CVEs added, {} modified, {} unchanged, {} skipped",
+                    changes.feed,
+                    changes.added,
+                    changes.modified,
+                    changes.unchanged,
+                    changes.skipped
+                );
+            }
+            info!(
+                "Synced {} feeds ({} CVEs added, {} modified, {} skipped), {} feeds already up to date",
+                report.updated_feeds.len(),
+                report.cves_added,
+                report.cves_modified,
+                report.cves_skipped,
+                report.skipped_feeds.len()
+            );
+            if report.cves_pruned > 0 {
+                info!(
+                    "Pruned {} CVEs to keep the cache within {} bytes",
+                    report.cves_pruned,
+                    config.max_db_size.unwrap_or_default()
+                );
+            }
+            info!(
+                "Spent {:.1?} downloading, {:.1?} decompressing, {:.1?} parsing and {:.1?} writing",
+                report.timings.download,
+                report.timings.decompress,
+                report.timings.parse,
+                report.timings.write
+            );
+            if config.trace_feeds {
+                let span = |span: Option<StageSpan>| {
+                    span.map_or("-".to_string(), |span| format!("{:.1?}", span.duration))
+                };
+                for timings in &report.feed_timings {
+                    info!(
+                        "[Feed: {}] download {}, decompress {}, parse {}, write {}",
+                        timings.feed,
+                        span(timings.download),
+                        span(timings.decompress),
+                        span(timings.parse),
+                        span(timings.write)
+                    );
+                }
+            }
+            if !report.timed_out_feeds.is_empty() {
+                eprintln!(
+                    "WARNING: Feeds timed out and weren't synced: {}",
+                    report
+                        .timed_out_feeds
+                        .iter()
+                        .map(|feed| feed.to_string())
+                        .collect::<Vec<_>>()
+                        .join(",")
+                );
+            }
+        }
+        Err(CacheError::TimedOut(feed)) => {
+            eprintln!("Error: Timed out fetching the {} feed", feed);
+            exit_failed_sync(&config);
+        }
+        Err(CacheError::ReadOnly) => {
+            eprintln!(
+                "Error: The cache {:?} is read-only and can't be synced",
+                config.db
+            );
+            exit_failed_sync(&config);
+        }
+        Err(error) => {
+            eprintln!("Fatal Error: {:?}", error);
+            exit_failed_sync(&config);
+        }
+    }
+
+    if let Some(url) = &config.change_history_url {
+        sync_dataset(&config, args, url, sync_change_history, |fetched| {
+            format!("Synced {} change events", fetched)
+        });
+    }
+
+    if let Some(url) = &config.cpe_match_url {
+        sync_dataset(&config, args, url, sync_cpe_matches, |recorded| {
+            format!("Synced the CPE names of {} criteria", recorded)
+        });
+    }
+
+    if let Some(url) = &config.cpe_dictionary_url {
+        sync_dataset(&config, args, url, sync_cpe_dictionary, |recorded| {
+            format!("Synced {} CPE names of the dictionary", recorded)
+        });
+    }
+
+    if let Some(url) = &config.kev_url {
+        sync_dataset(&config, args, url, sync_kev, |listed| {
+            format!("Synced {} known exploited CVEs", listed)
+        });
+    }
+
+    if let Some(url) = &config.cwe_url {
+        sync_dataset(&config, args, url, sync_cwes, |named| {
+            format!("Synced the names of {} weaknesses", named)
+        });
+    }
+
+    if let Some(url) = &config.epss_url {
+        sync_dataset(&config, args, url, sync_epss, |scored| match scored {
+            0 => String::from("EPSS scores are up to date"),
+            scored => format!("Synced the EPSS scores of {} CVEs", scored),
+        });
+    }
+
+    // The sync scored the CVEs before the catalog and EPSS scores were updated
+    if config.kev_url.is_some() || config.epss_url.is_some() {
+        if let Some(weights) = &config.risk_weights {
+            if let Err(error) = score_cves(&config, weights) {
+                eprintln!("Fatal Error: {:?}", error);
+                exit_failed_sync(&config);
+            }
+        }
     }
+
+    if !config.osv_ecosystems.is_empty() {
+        sync_dataset(&config, args, &config.osv_url, sync_osv, |report| {
+            format!(
+                "Synced {} OSV advisories ({} ranges, {} aliases), {} unchanged",
+                report.vulnerabilities, report.ranges, report.aliases, report.unchanged
+            )
+        });
+    }
+
+    write_sync_metrics(&config, true);
 }
 
-pub fn search(matches: &ArgMatches) {
-    let mut config = CacheConfig::new();
+/// Sync one of the datasets of the cache of ``config`` besides the feeds, such as the KEV catalog,
+/// from ``url`` with ``sync``, logging the ``message`` of its result. A failure exits as a failed
+/// sync. The TLS warning was already printed for the feeds.
+fn sync_dataset<T, E: std::fmt::Debug>(
+    config: &CacheConfig,
+    args: &SyncArgs,
+    url: &str,
+    sync: fn(&CacheConfig, &ReqwestBlockingClient) -> Result<T, E>,
+    message: impl FnOnce(T) -> String,
+) {
+    let client = restrict_hosts(
+        ReqwestBlockingClient::new(url, args.connect_timeout, None, None)
+            .with_env_proxy(!args.no_proxy)
+            .with_tls_insecure(args.tls_insecure),
+        config,
+    );
+
+    match sync(config, &client) {
+        Ok(synced) => info!("{}", message(synced)),
+        Err(error) => {
+            eprintln!("Fatal Error: {:?}", error);
+            exit_failed_sync(config);
+        }
+    }
+}
+
+/// Exit once the sync of the cache of ``config`` failed, recording it in its ``metrics_file``
+fn exit_failed_sync(config: &CacheConfig) -> ! {
+    write_sync_metrics(config, false);
+    std::process::exit(1);
+}
+
+/// Write the metrics of the cache of ``config`` to its ``metrics_file``, if it has one
+fn write_sync_metrics(config: &CacheConfig, succeeded: bool) {
+    if let Some(path) = &config.metrics_file {
+        if let Err(error) = write_metrics(config, path, succeeded) {
+            eprintln!("Error: Failed writing metrics to {:?}: {}", path, error);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Search the ``configs`` for a CVE by ID. When it isn't cached, the yearly feed that lists it is
+/// synced to the first of the ``configs`` with ``--auto-fetch``, otherwise the feed is suggested.
+fn search_by_id_or_fetch(
+    configs: &[CacheConfig],
+    cve: &str,
+    args: &SearchArgs,
+) -> Result<(String, Cve), CacheError> {
+    let error = match search_by_id_in_sources(configs, cve) {
+        Err(error) if error.is_not_found() => error,
+        result => return result,
+    };
+
+    let Some(feed) = FeedName::for_cve_id(cve) else {
+        return Err(error);
+    };
+
+    if !args.auto_fetch {
+        eprintln!(
+            "{} isn't cached, it can be synced from the {} feed with: nvd_cve sync -l {}",
+            cve, feed, feed
+        );
+        return Err(error);
+    }
+
+    let mut config = configs[0].clone();
+    config.feeds = vec![feed];
+    config.show_progress = false;
+    if let Some(url) = &args.url {
+        config.url = url.to_string();
+    }
+
+    info!("Fetching the {} feed for {}", feed, cve);
+    let client = tls_insecure(
+        restrict_hosts(
+            ReqwestBlockingClient::new(&config.url, None, None, None)
+                .with_env_proxy(!args.no_proxy),
+            &config,
+        ),
+        args.tls_insecure,
+    );
+    sync_blocking(&config, client)?;
+
+    search_by_id_in_sources(configs, cve)
+}
+
+/// Look a CVE up in the yearly feed listing it, fetched without touching any cache
+fn search_online(cve: &str, args: &SearchArgs) {
+    let Some(feed) = FeedName::for_cve_id(cve) else {
+        eprintln!("Fatal Error: {:?} isn't a CVE ID", cve);
+        std::process::exit(3);
+    };
+
+    let config = CacheConfig::new();
+    let url = match &args.url {
+        Some(url) => url.to_string(),
+        None => config.url.clone(),
+    };
+
+    info!("Fetching the {} feed for {}", feed, cve);
+    let client = tls_insecure(
+        restrict_hosts(
+            ReqwestBlockingClient::new(&url, None, None, None).with_env_proxy(!args.no_proxy),
+            &config,
+        ),
+        args.tls_insecure,
+    );
+
+    match CveFeed::from_blocking_http_client(&client, &feed) {
+        Ok(cve_feed) => match cve_feed.find(cve) {
+            Some(item) => println!("{}", serde_json::to_string_pretty(&item.cve).unwrap()),
+            None => {
+                eprintln!("{} not found in the {} feed", cve, feed);
+                std::process::exit(3);
+            }
+        },
+        Err(error) => {
+            eprintln!("Fatal Error: {:?}", error);
+            std::process::exit(3);
+        }
+    }
+}
+
+/// Query for the CVEs containing ``text`` and matching the CVSS v3 ``criteria``
+fn criteria_query(text: Option<String>, criteria: &CriteriaArgs) -> SearchQuery {
+    let mut query = SearchQuery {
+        id_prefix: None,
+        text,
+        cwe: criteria.cwe.clone(),
+        assigner: criteria.assigner.clone(),
+        vendor: criteria.vendor.clone(),
+        product: criteria.product.clone(),
+        reference: criteria.reference.clone(),
+        attack_vector: criteria.attack_vector,
+        attack_complexity: criteria.attack_complexity,
+        privileges_required: criteria.privileges_required,
+        user_interaction: criteria.user_interaction,
+        scope: criteria.scope,
+        confidentiality_impact: criteria.confidentiality,
+        integrity_impact: criteria.integrity,
+        availability_impact: criteria.availability,
+        min_exploitability_score: criteria.min_exploitability,
+        min_impact_score: criteria.min_impact,
+        min_severity: criteria.min_severity,
+        severity: criteria.severity,
+        min_base_score: criteria.min_cvss,
+        published_after: criteria.published_after,
+        published_before: criteria.published_before,
+        modified_after: criteria.modified_after,
+        modified_before: criteria.modified_before,
+        has_public_exploit: criteria.has_public_exploit,
+        known_exploited: criteria.kev_only,
+        min_epss: criteria.min_epss,
+        min_risk_score: criteria.min_risk,
+    };
+
+    if let Some(vector) = &criteria.vector {
+        query.set_vector(vector);
+    }
+
+    if criteria.no_user_interaction {
+        query.user_interaction = Some(UserInteraction::None);
+    }
+
+    query
+}
+
+pub fn search(args: &SearchArgs) {
+    if args.verbose {
+        env_logger::init();
+    }
+
+    if let (true, Some(cve)) = (args.online, &args.cve) {
+        search_online(cve, args);
+        return;
+    }
+
+    let configs: Vec<CacheConfig> = match &args.source {
+        Some(sources) => sources
+            .iter()
+            .map(|source| CacheConfig::for_source(source))
+            .collect(),
+        None => {
+            let mut config = CacheConfig::new();
+            if let Some(db) = &args.db {
+                config.db = db.to_string_lossy().into_owned();
+            }
+            vec![config]
+        }
+    };
+
+    let configs: Vec<CacheConfig> = configs
+        .into_iter()
+        .map(|config| CacheConfig {
+            read_only: args.read_only,
+            namespace: args.namespace.clone().unwrap_or(config.namespace),
+            ..config
+        })
+        .collect();
+
+    if let (Some(as_of), Some(cve)) = (&args.as_of, &args.cve) {
+        search_as_of(&configs, cve, as_of);
+        return;
+    }
 
-    if let Some(db) = matches.value_of("db") {
-        config.db = String::from(db);
+    let mut query = criteria_query(args.text.clone(), &args.criteria);
+
+    // A trailing wildcard lists all CVEs with IDs beginning with the rest of the argument
+    let mut exact_id = None;
+    if let Some(cve) = &args.cve {
+        match cve.strip_suffix('*') {
+            Some(prefix) => query.id_prefix = Some(prefix.to_string()),
+            // Fields and risk scores are printed for a single CVE by listing it alone
+            None if args.fields.is_some()
+                || args.format_str.is_some()
+                || args.by_risk
+                || args.pick =>
+            {
+                query.id_prefix = Some(cve.clone());
+                exact_id = Some(cve.as_str());
+            }
+            None => (),
+        }
     }
 
-    if let Some(text) = matches.value_of("text") {
-        match search_description(&config, text) {
-            Ok(cves) => {
+    if args.pick {
+        pick_cve(&configs, &query);
+    } else if args.by_risk {
+        search_by_risk_in_sources(&configs, &query, exact_id, args);
+    } else if !query.is_empty() {
+        match search_sources(&configs, &query) {
+            Ok(mut cves) => {
+                if let Some(id) = exact_id {
+                    cves.retain(|cve| cve.id.eq_ignore_ascii_case(id));
+                }
                 if cves.is_empty() {
+                    warn_unknown_product(&configs, &query);
                     eprintln!("No results found");
                     std::process::exit(1);
-                } else {
-                    for cve in cves {
-                        println!("{}", cve);
+                }
+                for cve in cves {
+                    match (&args.fields, &args.format_str) {
+                        (Some(fields), _) => print_fields(&cve, fields, &args.delimiter),
+                        (None, Some(template)) => println!("{}", template.render(&cve)),
+                        (None, None) => println!("{}", cve.id),
                     }
                 }
             }
@@ -67,13 +957,1208 @@ pub fn search(matches: &ArgMatches) {
                 std::process::exit(2);
             }
         }
-    } else if let Some(cve) = matches.value_of("CVE") {
-        match search_by_id(&config, cve) {
-            Ok(cve_result) => println!("{}", serde_json::to_string_pretty(&cve_result).unwrap()),
+    } else if let Some(cve) = &args.cve {
+        match search_by_id_or_fetch(&configs, cve, args) {
+            Ok((source, cve_result)) => print_record(&configs, &source, cve_result, args.full),
+            Err(error) => {
+                eprintln!("Fatal Error: {:?}", error);
+                std::process::exit(3);
+            }
+        }
+    }
+}
+
+/// Print a CVE found in the cache of ``source`` along with its local override from the first of
+/// the ``configs`` holding one, or its full container if ``full``
+fn print_record(configs: &[CacheConfig], source: &str, cve: Cve, full: bool) {
+    let id = cve.cve_data_meta.id.clone();
+    let mut merged = CveWithOverride {
+        cve,
+        local_override: None,
+    };
+    for config in configs {
+        match get_override(config, &id) {
+            Ok(None) => continue,
+            Ok(local_override) => merged.local_override = local_override,
             Err(error) => {
                 eprintln!("Fatal Error: {:?}", error);
                 std::process::exit(3);
             }
         }
+        break;
+    }
+    if full {
+        print_container(configs, source, merged);
+    } else {
+        println!("{}", serde_json::to_string_pretty(&merged).unwrap())
+    }
+}
+
+/// Pick one of the CVEs matching ``query`` in the caches of ``configs`` from a fuzzy finder on the
+/// terminal, then print its full record
+fn pick_cve(configs: &[CacheConfig], query: &SearchQuery) {
+    let cves = match search_sources(configs, query) {
+        Ok(cves) => cves,
+        Err(error) => {
+            eprintln!("Fatal Error: {:?}", error);
+            std::process::exit(2);
+        }
+    };
+    if cves.is_empty() {
+        eprintln!("No results found");
+        std::process::exit(1);
+    }
+
+    let items: Vec<String> = cves
+        .iter()
+        .map(|cve| {
+            format!(
+                "{:<16} {:<8} {:>4}  {}",
+                cve.id,
+                Field::Severity.value(cve),
+                Field::Score.value(cve),
+                cve.description.replace(['\n', '\r', '\t'], " ")
+            )
+        })
+        .collect();
+
+    let picked = match picker::pick(&items) {
+        Ok(Some(index)) => &cves[index].id,
+        // Cancelled, as when interrupted
+        Ok(None) => std::process::exit(130),
+        Err(error) if error.kind() == io::ErrorKind::Unsupported => {
+            eprintln!("Error: search --pick needs a Unix terminal");
+            std::process::exit(2);
+        }
+        Err(error) => {
+            eprintln!("Error: search --pick needs a terminal: {}", error);
+            std::process::exit(2);
+        }
+    };
+
+    match search_by_id_in_sources(configs, picked) {
+        Ok((source, cve)) => print_record(configs, &source, cve, true),
+        Err(error) => {
+            eprintln!("Fatal Error: {:?}", error);
+            std::process::exit(3);
+        }
+    }
+}
+
+/// Print the full container of a CVE from the cache of ``source`` it was found in, along with the
+/// local override of ``merged``
+fn print_container(configs: &[CacheConfig], source: &str, merged: CveWithOverride) {
+    let Some(config) = configs.iter().find(|config| config.source == source) else {
+        return;
+    };
+    match search_container_by_id(config, &merged.cve.cve_data_meta.id) {
+        Ok(container) => {
+            let mut container = serde_json::to_value(container).unwrap();
+            match get_weaknesses(config, &merged.cve.cve_data_meta.id) {
+                Ok(weaknesses) => {
+                    container["weaknesses"] = serde_json::to_value(weaknesses).unwrap()
+                }
+                Err(error) => {
+                    eprintln!("Fatal Error: {:?}", error);
+                    std::process::exit(3);
+                }
+            }
+            if let Some(local_override) = merged.local_override {
+                container["local_override"] = serde_json::to_value(local_override).unwrap();
+            }
+            println!("{}", serde_json::to_string_pretty(&container).unwrap())
+        }
+        Err(error) => {
+            eprintln!("Fatal Error: {:?}", error);
+            std::process::exit(3);
+        }
+    }
+}
+
+/// Warn when the product searched by ``query`` isn't named by the CPE dictionary synced to any of
+/// ``configs``, as a misspelled vendor or product matches no CVE
+fn warn_unknown_product(configs: &[CacheConfig], query: &SearchQuery) {
+    let Some(product) = &query.product else {
+        return;
+    };
+
+    let mut synced = false;
+    for config in configs {
+        match is_known_product(config, query.vendor.as_deref(), product) {
+            Ok(Some(true)) => return,
+            Ok(Some(false)) => synced = true,
+            Ok(None) => (),
+            Err(error) => {
+                eprintln!("Fatal Error: {:?}", error);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    if synced {
+        match &query.vendor {
+            Some(vendor) => eprintln!(
+                "Warning: the CPE dictionary names no product {} of vendor {}",
+                product, vendor
+            ),
+            None => eprintln!("Warning: the CPE dictionary names no product {}", product),
+        }
+    }
+}
+
+/// List the scored CVEs matching ``query`` in the caches of ``configs`` from the highest risk score
+/// to the lowest, the score from the first of the ``configs`` holding a CVE being kept
+fn search_by_risk_in_sources(
+    configs: &[CacheConfig],
+    query: &SearchQuery,
+    exact_id: Option<&str>,
+    args: &SearchArgs,
+) {
+    let mut cves: Vec<ScoredCve> = vec![];
+    for config in configs {
+        match search_by_risk(config, query) {
+            Ok(scored) => {
+                for cve in scored {
+                    if !cves.iter().any(|known| known.summary.id == cve.summary.id) {
+                        cves.push(cve);
+                    }
+                }
+            }
+            Err(error) => {
+                eprintln!("Fatal Error: {:?}", error);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    if let Some(id) = exact_id {
+        cves.retain(|cve| cve.summary.id.eq_ignore_ascii_case(id));
+    }
+    if cves.is_empty() {
+        eprintln!("No results found");
+        std::process::exit(1);
+    }
+
+    cves.sort_by(|a, b| {
+        b.risk_score
+            .total_cmp(&a.risk_score)
+            .then_with(|| a.summary.id.cmp(&b.summary.id))
+    });
+    for cve in cves {
+        match (&args.fields, &args.format_str) {
+            (Some(fields), _) => print_fields(&cve.summary, fields, &args.delimiter),
+            (None, Some(template)) => println!("{}", template.render(&cve.summary)),
+            (None, None) => println!("{}\t{:.1}", cve.summary.id, cve.risk_score),
+        }
+    }
+}
+
+/// Print the version of ``cve`` current at ``as_of`` from the first of the ``configs`` holding one
+fn search_as_of(configs: &[CacheConfig], cve: &str, as_of: &DateTime<Utc>) {
+    for config in configs {
+        match search_by_id_as_of(config, cve, as_of) {
+            Ok(cve) => {
+                println!("{}", serde_json::to_string_pretty(&cve).unwrap());
+                return;
+            }
+            Err(error) if error.is_not_found() => continue,
+            Err(error) => {
+                eprintln!("Fatal Error: {:?}", error);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    eprintln!("No results found");
+    std::process::exit(1);
+}
+
+pub fn compare(args: &CompareArgs) {
+    let mut config = CacheConfig::new();
+
+    if let Some(source) = &args.source {
+        config = CacheConfig::for_source(source);
+    }
+
+    if let Some(db) = &args.db {
+        config.db = db.to_string_lossy().into_owned();
+    }
+
+    config.read_only = args.read_only;
+
+    let record =
+        |version: &CveVersion| match get_record(&config, &version.id, version.as_of.as_ref()) {
+            Ok(record) => record,
+            Err(error) if error.is_not_found() => {
+                eprintln!("No results found for {}", version.id);
+                std::process::exit(1);
+            }
+            Err(error) => {
+                eprintln!("Fatal Error: {:?}", error);
+                std::process::exit(2);
+            }
+        };
+
+    let comparison = CveComparison::new(&record(&args.cve), &record(&args.other));
+    println!("{}", serde_json::to_string_pretty(&comparison).unwrap());
+}
+
+pub fn export(args: &ExportArgs) {
+    let mut config = CacheConfig::new();
+
+    if let Some(source) = &args.source {
+        config = CacheConfig::for_source(source);
+    }
+
+    if let Some(db) = &args.db {
+        config.db = db.to_string_lossy().into_owned();
+    }
+
+    if let Some(namespace) = &args.namespace {
+        config.namespace = namespace.clone();
+    }
+
+    config.read_only = args.read_only;
+
+    let mut query = criteria_query(args.text.clone(), &args.criteria);
+    query.id_prefix = args.prefix.clone();
+
+    let output = args.output.as_deref();
+    let exported = match args.format {
+        ExportFormat::Json => {
+            search_cves(&config, &query).and_then(|cves| write_json(output, &cves))
+        }
+        ExportFormat::Stix => {
+            export_stix(&config, &query).and_then(|bundle| write_json(output, &bundle))
+        }
+        ExportFormat::Misp => {
+            let dir = output.expect("--format misp requires --output");
+            write_misp_feed(&config, &query, dir).map(|events| {
+                println!("Wrote {} events to {}", events, dir.display());
+            })
+        }
+        ExportFormat::Kv => {
+            let dir = output.expect("--format kv requires --output");
+            match write_kv_index(&config, &query, dir) {
+                Ok(cves) => println!("Wrote {} CVEs to {}", cves, dir.display()),
+                Err(error) => {
+                    eprintln!("Fatal Error: {:?}", error);
+                    std::process::exit(1);
+                }
+            }
+            Ok(())
+        }
+    };
+
+    if let Err(error) = exported {
+        eprintln!("Fatal Error: {:?}", error);
+        std::process::exit(1);
+    }
+}
+
+/// Write ``value`` as JSON to the file at ``output``, or to the standard output
+fn write_json<T: Serialize>(output: Option<&Path>, value: &T) -> Result<(), CacheError> {
+    let json = serde_json::to_string(value)?;
+    match output {
+        Some(path) => fs::write(path, json)?,
+        None => println!("{}", json),
+    }
+    Ok(())
+}
+
+pub fn filter(args: &FilterArgs) {
+    if args.verbose {
+        env_logger::init();
+    }
+
+    if args.debug_http {
+        init_http_tracing();
+    }
+
+    let config = CacheConfig::new();
+    let url = match &args.url {
+        Some(url) => url.to_string(),
+        None => config.url,
+    };
+    let feeds = match &args.feeds {
+        Some(feeds) => FeedSelector::expand(feeds),
+        None => config.feeds,
+    };
+    let compression = args.compression.unwrap_or(config.compression);
+
+    let mut query = criteria_query(args.text.clone(), &args.criteria);
+    query.id_prefix = args.prefix.clone();
+
+    let client = tls_insecure(
+        ReqwestBlockingClient::new(&url, args.connect_timeout, None, None)
+            .with_env_proxy(!args.no_proxy),
+        args.tls_insecure,
+    );
+
+    let mut filtered = FilteredFeed::new(query).with_clock(config.clock.clone());
+    for feed in feeds {
+        let matched = client
+            .get_url(&feed.compressed_feed_filename(compression))
+            .and_then(|body| Ok(compression.decompress(&body)?))
+            .and_then(|json| Ok(filtered.add_feed(&json)?));
+
+        match matched {
+            Ok(matched) => info!("[Feed: {}] {} CVEs matched", feed, matched),
+            Err(error) => {
+                eprintln!("Fatal Error: {:?}", error);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    match filtered.write_to_dir(&args.output, compression) {
+        Ok(_) => println!(
+            "Wrote {} CVEs to {}",
+            filtered.len(),
+            args.output
+                .join(FeedName::Custom.compressed_feed_filename(compression))
+                .display()
+        ),
+        Err(error) => {
+            eprintln!("Fatal Error: {:?}", error);
+            std::process::exit(1);
+        }
+    }
+}
+
+pub fn tickets(args: &TicketsArgs) {
+    if args.verbose {
+        env_logger::init();
+    }
+
+    let mut config = CacheConfig::new();
+
+    if let Some(source) = &args.source {
+        config = CacheConfig::for_source(source);
+    }
+
+    if let Some(db) = &args.db {
+        config.db = db.to_string_lossy().into_owned();
+    }
+
+    if let Some(namespace) = &args.namespace {
+        config.namespace = namespace.clone();
+    }
+
+    let mut query = criteria_query(args.text.clone(), &args.criteria);
+    query.id_prefix = args.prefix.clone();
+
+    let template = TicketTemplate::new(
+        args.summary.as_deref().unwrap_or(DEFAULT_SUMMARY_TEMPLATE),
+        args.description
+            .as_deref()
+            .unwrap_or(DEFAULT_DESCRIPTION_TEMPLATE),
+    );
+    let authorization = std::env::var("NVD_CVE_TICKET_AUTHORIZATION")
+        .ok()
+        .filter(|authorization| !authorization.is_empty());
+
+    let url = args.jira.as_ref().or(args.webhook.as_ref()).unwrap();
+    let client = tls_insecure(
+        ReqwestBlockingClient::new(url.as_str(), args.connect_timeout, None, None)
+            .with_env_proxy(!args.no_proxy),
+        args.tls_insecure,
+    );
+
+    let created = template.and_then(|template| match (&args.jira, &args.project) {
+        (Some(jira), Some(project)) => {
+            let mut tracker = JiraTracker::new(client, jira.as_str(), project);
+            if let Some(issue_type) = &args.issue_type {
+                tracker = tracker.with_issue_type(issue_type);
+            }
+            if let Some(authorization) = &authorization {
+                tracker = tracker.with_authorization(authorization);
+            }
+            ticket_watchlist(&config, &query, &template, tracker)
+        }
+        _ => {
+            let mut tracker = WebhookTracker::new(client, url.as_str());
+            if let Some(authorization) = &authorization {
+                tracker = tracker.with_authorization(authorization);
+            }
+            ticket_watchlist(&config, &query, &template, tracker)
+        }
+    });
+
+    match created {
+        Ok(0) => println!("No new CVEs to ticket"),
+        Ok(created) => println!("Created {} tickets", created),
+        Err(error) => {
+            eprintln!("Fatal Error: {:?}", error);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Create the tickets of the CVEs matching ``query`` in ``tracker``, printing each of them
+fn ticket_watchlist<T: TicketTracker>(
+    config: &CacheConfig,
+    query: &SearchQuery,
+    template: &TicketTemplate,
+    mut tracker: T,
+) -> Result<usize, TicketError> {
+    let created = create_tickets(config, query, template, &mut tracker)?;
+    for ticket in &created {
+        println!("{}\t{}", ticket.cve, ticket.key.as_deref().unwrap_or("-"));
+    }
+    Ok(created.len())
+}
+
+pub fn override_cve(args: &OverrideArgs) {
+    let mut config = CacheConfig::new();
+
+    if let Some(source) = &args.source {
+        config = CacheConfig::for_source(source);
+    }
+
+    if let Some(db) = &args.db {
+        config.db = db.to_string_lossy().into_owned();
+    }
+
+    if let Some(namespace) = &args.namespace {
+        config.namespace = namespace.clone();
+    }
+
+    config.read_only = args.read_only;
+
+    let cve = args.cve.as_str();
+
+    if args.clear {
+        match remove_override(&config, cve) {
+            Ok(true) => return,
+            Ok(false) => {
+                eprintln!("No override found");
+                std::process::exit(1);
+            }
+            Err(error) => {
+                eprintln!("Fatal Error: {:?}", error);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    let mut cve_override = match get_override(&config, cve) {
+        Ok(cve_override) => cve_override,
+        Err(error) => {
+            eprintln!("Fatal Error: {:?}", error);
+            std::process::exit(2);
+        }
+    };
+
+    let updating = args.severity.is_some()
+        || args.affected.is_some()
+        || args.note.is_some()
+        || args.tags.is_some();
+
+    if !updating {
+        match cve_override {
+            Some(cve_override) => {
+                println!("{}", serde_json::to_string_pretty(&cve_override).unwrap())
+            }
+            None => {
+                eprintln!("No override found");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let cve_override = cve_override.get_or_insert_with(Default::default);
+
+    // An override edited by hand is the analyst's, advisories ingested later must leave it alone
+    cve_override.advisory = None;
+
+    if let Some(severity) = args.severity {
+        cve_override.severity = Some(severity);
+    }
+
+    if let Some(affected) = args.affected {
+        cve_override.affected = Some(affected);
+    }
+
+    if let Some(note) = &args.note {
+        cve_override.note = Some(note.clone());
+    }
+
+    if let Some(tags) = &args.tags {
+        cve_override.tags = tags
+            .iter()
+            .map(|tag| tag.trim().to_string())
+            .filter(|tag| !tag.is_empty())
+            .collect();
+    }
+
+    if let Err(error) = set_override(&config, cve, cve_override) {
+        eprintln!("Fatal Error: {:?}", error);
+        std::process::exit(2);
+    }
+}
+
+pub fn csaf(args: &CsafArgs) {
+    if args.verbose {
+        env_logger::init();
+    }
+
+    if args.debug_http {
+        init_http_tracing();
+    }
+
+    let mut config = CacheConfig::new();
+
+    if let Some(source) = &args.source {
+        config = CacheConfig::for_source(source);
+    }
+
+    if let Some(db) = &args.db {
+        config.db = db.to_string_lossy().into_owned();
+    }
+
+    if let Some(namespace) = &args.namespace {
+        config.namespace = namespace.clone();
+    }
+
+    let advisories = match &args.provider {
+        Some(provider) => {
+            let client = tls_insecure(
+                ReqwestBlockingClient::new(provider.as_str(), args.connect_timeout, None, None)
+                    .with_env_proxy(!args.no_proxy),
+                args.tls_insecure,
+            );
+            fetch_provider_advisories(&client, provider.as_str(), args.since)
+        }
+        None => args
+            .files
+            .iter()
+            .map(|path| match fs::read(path) {
+                Ok(json) => Advisory::from_json(&json),
+                Err(error) => Err(CsafError::InvalidDocument(format!(
+                    "{}: {}",
+                    path.display(),
+                    error
+                ))),
+            })
+            .collect(),
+    };
+
+    let advisories = match advisories {
+        Ok(advisories) => advisories,
+        Err(error) => {
+            eprintln!("Fatal Error: {:?}", error);
+            std::process::exit(1);
+        }
+    };
+
+    let report = match ingest_advisories(&config, &advisories, &args.platform) {
+        Ok(report) => report,
+        Err(error) => {
+            eprintln!("Fatal Error: {:?}", error);
+            std::process::exit(2);
+        }
+    };
+
+    for cve in &report.overrides {
+        let affected = match get_override(&config, cve) {
+            Ok(Some(cve_override)) => match cve_override.affected {
+                Some(true) => "affected",
+                Some(false) => "not affected",
+                None => "-",
+            },
+            Ok(None) => "cleared",
+            Err(error) => {
+                eprintln!("Fatal Error: {:?}", error);
+                std::process::exit(2);
+            }
+        };
+        println!("{}\t{}", cve, affected);
+    }
+    for cve in &report.kept {
+        eprintln!("Kept the analyst override of {}", cve);
+    }
+    println!(
+        "Ingested {} advisories: {} aliases, {} statuses, {} overrides",
+        advisories.len(),
+        report.aliases,
+        report.statuses,
+        report.overrides.len()
+    );
+}
+
+pub fn package(args: &PackageArgs) {
+    let mut config = CacheConfig::new();
+
+    if let Some(source) = &args.source {
+        config = CacheConfig::for_source(source);
+    }
+
+    if let Some(db) = &args.db {
+        config.db = db.to_string_lossy().into_owned();
+    }
+
+    let vulnerabilities = match search_package(
+        &config,
+        args.ecosystem,
+        &args.package,
+        args.package_version.as_deref(),
+    ) {
+        Ok(vulnerabilities) => vulnerabilities,
+        Err(error) => {
+            eprintln!("Fatal Error: {:?}", error);
+            std::process::exit(2);
+        }
+    };
+
+    if args.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&vulnerabilities).unwrap()
+        );
+        return;
+    }
+
+    for vulnerability in &vulnerabilities {
+        println!(
+            "{}\t{}\t{}",
+            vulnerability.id,
+            vulnerability.cves().join(","),
+            vulnerability.summary.as_deref().unwrap_or_default()
+        );
+    }
+}
+
+pub fn resolve(args: &ResolveArgs) {
+    let configs: Vec<CacheConfig> = match &args.source {
+        Some(sources) => sources
+            .iter()
+            .map(|source| CacheConfig::for_source(source))
+            .collect(),
+        None => {
+            let mut config = CacheConfig::new();
+            if let Some(db) = &args.db {
+                config.db = db.to_string_lossy().into_owned();
+            }
+            vec![config]
+        }
+    };
+
+    let configs: Vec<CacheConfig> = configs
+        .into_iter()
+        .map(|config| CacheConfig {
+            read_only: args.read_only,
+            ..config
+        })
+        .collect();
+
+    match resolve_in_sources(&configs, &args.id) {
+        Ok(resolution) => println!("{}", serde_json::to_string_pretty(&resolution).unwrap()),
+        Err(error) if error.is_not_found() => {
+            eprintln!("{} isn't known to the local cache", args.id);
+            std::process::exit(1);
+        }
+        Err(error) => {
+            eprintln!("Fatal Error: {:?}", error);
+            std::process::exit(2);
+        }
+    }
+}
+
+pub fn reindex(args: &ReindexArgs) {
+    let mut config = CacheConfig::new();
+
+    if let Some(source) = &args.source {
+        config = CacheConfig::for_source(source);
+    }
+
+    if let Some(db) = &args.db {
+        config.db = db.to_string_lossy().into_owned();
+    }
+
+    if let Err(error) = reindex_cache(&config) {
+        eprintln!("Fatal Error: {:?}", error);
+        std::process::exit(2);
+    }
+}
+
+pub fn feeds(args: &FeedsArgs) {
+    let mut config = CacheConfig::new();
+
+    if let Some(source) = &args.source {
+        config = CacheConfig::for_source(source);
+    }
+
+    if let Some(db) = &args.db {
+        config.db = db.to_string_lossy().into_owned();
+    }
+
+    config.read_only = args.read_only;
+
+    let feeds = match get_cached_feeds(&config) {
+        Ok(feeds) => feeds,
+        Err(error) if error.is_not_found() => vec![],
+        Err(error) => {
+            eprintln!("Fatal Error: {:?}", error);
+            std::process::exit(2);
+        }
+    };
+
+    if feeds.is_empty() {
+        eprintln!("No feeds synced");
+        std::process::exit(1);
+    }
+
+    println!(
+        "{:<8} {:<20} {:>12} {:>12} {:>12} SHA256",
+        "FEED", "LAST MODIFIED", "SIZE", "ZIP SIZE", "GZ SIZE"
+    );
+    for feed in feeds {
+        if let Some(metafile) = feed.metafile {
+            println!(
+                "{:<8} {:<20} {:>12} {:>12} {:>12} {}",
+                feed.name.to_string(),
+                metafile.format_last_modified_date(),
+                metafile.size,
+                metafile.zip_size,
+                metafile.gz_size,
+                metafile.sha256
+            );
+        }
+    }
+}
+
+pub fn attestations(args: &AttestationsArgs) {
+    let mut config = CacheConfig::new();
+
+    if let Some(source) = &args.source {
+        config = CacheConfig::for_source(source);
+    }
+
+    if let Some(db) = &args.db {
+        config.db = db.to_string_lossy().into_owned();
+    }
+
+    config.read_only = args.read_only;
+
+    let attestations = match get_attestations(&config) {
+        Ok(attestations) => attestations,
+        Err(error) => {
+            eprintln!("Fatal Error: {:?}", error);
+            std::process::exit(2);
+        }
+    };
+
+    println!("{}", serde_json::to_string_pretty(&attestations).unwrap());
+
+    if let Some(path) = &args.verify {
+        let key = match fs::read(path) {
+            Ok(key) => key,
+            Err(error) => {
+                eprintln!("Error: Failed reading key file {:?}: {}", path, error);
+                std::process::exit(2);
+            }
+        };
+
+        let invalid = attestations
+            .iter()
+            .filter(|attestation| !attestation.verify(&key))
+            .inspect(|attestation| {
+                eprintln!(
+                    "Invalid signature for the sync finished at {}",
+                    attestation.finished
+                )
+            })
+            .count();
+
+        if invalid > 0 {
+            std::process::exit(1);
+        }
+    }
+}
+
+pub fn history(args: &HistoryArgs) {
+    let mut config = CacheConfig::new();
+
+    if let Some(source) = &args.source {
+        config = CacheConfig::for_source(source);
+    }
+
+    if let Some(db) = &args.db {
+        config.db = db.to_string_lossy().into_owned();
+    }
+
+    config.read_only = args.read_only;
+
+    if let Some(cve) = &args.cve {
+        let events = if args.remote {
+            let url = args
+                .url
+                .as_ref()
+                .map_or(NVD_CHANGE_HISTORY_URL.to_string(), |url| url.to_string());
+            let client = tls_insecure(
+                restrict_hosts(
+                    ReqwestBlockingClient::new(url, None, None, None)
+                        .with_env_proxy(!args.no_proxy),
+                    &config,
+                ),
+                args.tls_insecure,
+            );
+            fetch_change_events(&client, cve).map_err(CacheError::from)
+        } else {
+            get_change_events(&config, cve)
+        };
+
+        match events {
+            Ok(events) => println!("{}", serde_json::to_string_pretty(&events).unwrap()),
+            Err(error) => {
+                eprintln!("Fatal Error: {:?}", error);
+                std::process::exit(2);
+            }
+        }
+        return;
+    }
+
+    match get_sync_runs(&config) {
+        Ok(runs) => println!("{}", serde_json::to_string_pretty(&runs).unwrap()),
+        Err(error) => {
+            eprintln!("Fatal Error: {:?}", error);
+            std::process::exit(2);
+        }
+    }
+}
+
+pub fn check(args: &CheckArgs) {
+    let mut config = CacheConfig::new();
+
+    if let Some(source) = &args.source {
+        config = CacheConfig::for_source(source);
+    }
+
+    if let Some(db) = &args.db {
+        config.db = db.to_string_lossy().into_owned();
+    }
+
+    let health = match check_cache(&config) {
+        Ok(health) => health,
+        Err(error) => {
+            println!(
+                "CRITICAL: Failed reading the cache {:?}: {:?}",
+                config.db, error
+            );
+            std::process::exit(2);
+        }
+    };
+
+    if !health.is_consistent() {
+        if health.integrity_errors.is_empty() {
+            println!("CRITICAL: The cache {:?} holds no synced feeds", config.db);
+        }
+        for error in &health.integrity_errors {
+            println!("CRITICAL: {}", error);
+        }
+        std::process::exit(2);
+    }
+
+    let finished = match &health.last_sync {
+        Some(run) => &run.finished,
+        None => {
+            println!("WARNING: The cache {:?} has no recorded sync", config.db);
+            std::process::exit(1);
+        }
+    };
+
+    if let Some(max_age) = args.max_age {
+        if !health.is_fresh(max_age) {
+            println!(
+                "WARNING: Last synced at {}, longer than {}s ago",
+                finished,
+                max_age.as_secs()
+            );
+            std::process::exit(1);
+        }
+    }
+
+    println!(
+        "OK: {} CVEs from {} feeds, last synced at {}",
+        health.cves, health.feeds, finished
+    );
+}
+
+pub fn doctor(args: &DoctorArgs) {
+    let mut config = match &args.config {
+        Some(path) => read_config(path),
+        None => CacheConfig::new(),
+    };
+
+    if let Some(source) = &args.source {
+        config.db = CacheConfig::default_db_path_for_source(source);
+        config.source = source.clone();
+    }
+
+    if let Some(url) = &args.url {
+        config.url = url.to_string();
+    }
+
+    if let Some(db) = &args.db {
+        config.db = db.to_string_lossy().into_owned();
+    }
+
+    if args.read_only {
+        config.read_only = true;
+    }
+
+    let diagnoses = diagnose(&config, !args.no_proxy, args.connect_timeout);
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&diagnoses).unwrap());
+    } else {
+        for diagnosis in &diagnoses {
+            println!(
+                "{:<7} {:<11} {}",
+                diagnosis.status.to_string(),
+                diagnosis.check,
+                diagnosis.detail
+            );
+            if let Some(hint) = &diagnosis.hint {
+                println!("{:<19} hint: {}", "", hint);
+            }
+        }
+    }
+
+    match diagnoses.iter().map(|diagnosis| diagnosis.status).max() {
+        Some(Status::Error) => std::process::exit(2),
+        Some(Status::Warning) => std::process::exit(1),
+        _ => {}
+    }
+}
+
+pub fn install_service(args: &InstallServiceArgs) {
+    let program = match std::env::current_exe() {
+        Ok(program) => program,
+        Err(error) => {
+            eprintln!("Error: Failed finding the nvd_cve binary: {}", error);
+            std::process::exit(1);
+        }
+    };
+
+    let mut sync_args = vec!["sync".to_string(), "--no-progress".to_string()];
+    if let Some(config) = &args.config {
+        // Validate the config now rather than on every run of the service
+        read_config(config);
+        sync_args.push("--config".to_string());
+        sync_args.push(absolute(config).to_string_lossy().into_owned());
+    }
+
+    let service = Service {
+        name: &args.name,
+        program: &program,
+        args: sync_args,
+        interval: args.interval,
+    };
+
+    let (files, hint) = match service.files(args.kind.unwrap_or_else(ServiceKind::native)) {
+        Ok(files) => files,
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            std::process::exit(1);
+        }
+    };
+
+    for file in files {
+        let path = args.output.join(&file.name);
+        if let Err(error) = fs::write(&path, file.contents) {
+            eprintln!("Error: Failed writing {:?}: {}", path, error);
+            std::process::exit(1);
+        }
+        println!("Wrote {}", path.display());
+    }
+    println!("Enable it with: {}", hint);
+}
+
+#[cfg(feature = "scan-host")]
+pub fn scan_host(args: &ScanHostArgs) {
+    let mut config = CacheConfig::new();
+
+    if let Some(source) = &args.source {
+        config = CacheConfig::for_source(source);
+    }
+
+    if let Some(db) = &args.db {
+        config.db = db.to_string_lossy().into_owned();
+    }
+
+    config.read_only = args.read_only;
+
+    let mut dictionary = CpeDictionary::builtin();
+    if let Some(path) = &args.dictionary {
+        match CpeDictionary::read(path) {
+            Ok(entries) => dictionary.extend(entries),
+            Err(error) => {
+                eprintln!("Error: Invalid dictionary {:?}: {:?}", path, error);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let packages = match &args.input {
+        Some(path) if path.as_os_str() == "-" => io::read_to_string(io::stdin())
+            .map_err(ScanError::from)
+            .and_then(|json| packages_from_json(&json)),
+        Some(path) => fs::read_to_string(path)
+            .map_err(ScanError::from)
+            .and_then(|json| packages_from_json(&json)),
+        None => installed_packages(
+            args.manager
+                .as_deref()
+                .unwrap_or_else(|| PackageManager::for_host()),
+        ),
+    };
+    let suppressions = match &args.suppressions {
+        Some(path) => match Suppressions::read(path) {
+            Ok(suppressions) => suppressions,
+            Err(error) => {
+                eprintln!("Error: Invalid suppressions {:?}: {:?}", path, error);
+                std::process::exit(1);
+            }
+        },
+        None => Suppressions::default(),
+    };
+
+    let scan =
+        packages.and_then(|packages| scan_packages(&config, &packages, &dictionary, args.scheme));
+    let mut scan = match scan {
+        Ok(scan) => scan,
+        Err(error) => {
+            eprintln!("Fatal Error: {:?}", error);
+            std::process::exit(2);
+        }
+    };
+
+    for suppression in scan.suppress(&suppressions, Utc::now().date_naive()) {
+        eprintln!(
+            "Warning: The suppression of {} for {} expired on {}",
+            suppression.cve,
+            suppression.component.as_deref().unwrap_or("every package"),
+            suppression.expires.as_deref().unwrap_or_default()
+        );
+    }
+
+    let publisher = VexPublisher {
+        name: args.author.clone(),
+        namespace: args.namespace.clone(),
+    };
+    let format = match args.json {
+        true => ScanFormat::Json,
+        false => args.format,
+    };
+    let json = match format {
+        ScanFormat::Json => Some(serde_json::to_string_pretty(&scan)),
+        ScanFormat::OpenVex => Some(serde_json::to_string_pretty(&OpenVexDocument::new(
+            &scan,
+            &publisher,
+            Utc::now(),
+        ))),
+        ScanFormat::Csaf => Some(serde_json::to_string_pretty(&CsafDocument::new(
+            &scan,
+            &publisher,
+            Utc::now(),
+        ))),
+        ScanFormat::Text => None,
+    };
+
+    if let Some(json) = json {
+        println!("{}", json.unwrap());
+    } else {
+        for finding in &scan.findings {
+            let mut line = format!(
+                "{}\t{} {}\t{}",
+                finding.cve, finding.package.name, finding.package.version, finding.confidence
+            );
+            if let Some(warning) = &finding.warning {
+                line.push('\t');
+                line.push_str(warning);
+            }
+            println!("{}", line);
+        }
+        for suppressed in &scan.suppressed {
+            let finding = &suppressed.finding;
+            eprintln!(
+                "Suppressed: {}\t{} {}\t{}",
+                finding.cve,
+                finding.package.name,
+                finding.package.version,
+                suppressed.suppression.reason
+            );
+        }
+        eprintln!(
+            "Scanned {} packages, {} not in the CPE dictionary: {} findings, {} suppressed",
+            scan.packages,
+            scan.unmapped.len(),
+            scan.findings.len(),
+            scan.suppressed.len()
+        );
+    }
+
+    if args.fail_on_findings && !scan.findings.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+#[cfg(feature = "snapshot")]
+pub fn snapshot(command: &SnapshotCommand) {
+    let config = |source: &Option<String>, db: &Option<PathBuf>| {
+        let mut config = CacheConfig::new();
+
+        if let Some(source) = source {
+            config = CacheConfig::for_source(source);
+        }
+
+        if let Some(db) = db {
+            config.db = db.to_string_lossy().into_owned();
+        }
+
+        config
+    };
+
+    let manifest = match command {
+        SnapshotCommand::Publish(args) => {
+            publish_snapshot(&config(&args.source, &args.db), &args.output)
+                .map(|manifest| serde_json::to_string_pretty(&manifest))
+        }
+        SnapshotCommand::Fetch(args) => {
+            if args.debug_http {
+                init_http_tracing();
+            }
+
+            let config = config(&args.source, &args.db);
+            let client = tls_insecure(
+                restrict_hosts(
+                    ReqwestBlockingClient::new(&config.url, args.connect_timeout, None, None)
+                        .with_env_proxy(!args.no_proxy),
+                    &config,
+                ),
+                args.tls_insecure,
+            );
+
+            if args.diff {
+                fetch_diff(&config, &client, args.url.as_str())
+                    .map(|manifest| serde_json::to_string_pretty(&manifest))
+            } else {
+                fetch_snapshot(&config, &client, args.url.as_str())
+                    .map(|manifest| serde_json::to_string_pretty(&manifest))
+            }
+        }
+        SnapshotCommand::Diff(args) => publish_diff(&args.base, &args.target, &args.output)
+            .map(|manifest| serde_json::to_string_pretty(&manifest)),
+    };
+
+    match manifest {
+        Ok(json) => println!("{}", json.unwrap()),
+        Err(error) => {
+            eprintln!("Fatal Error: {:?}", error);
+            std::process::exit(1);
+        }
     }
 }
@@ -1,10 +1,22 @@
+use crate::compression::Compression;
 use crate::cve::CveFeed;
-use flate2::read::MultiGzDecoder;
-use reqwest::Url;
-use std::io::Read;
-use std::time::Duration;
+use crate::feed::FeedName;
+#[cfg(feature = "blocking")]
+use reqwest::header::AUTHORIZATION;
+use reqwest::redirect::Policy;
+use reqwest::{NoProxy, Proxy, Url};
+#[cfg(feature = "blocking")]
+use serde::Serialize;
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
 use url::ParseError;
 
+/// Source of the IDs that tie together the log events of a single HTTP request.
+static REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
 /// Errors related to HTTP clients
 #[derive(Debug, Clone)]
 pub enum HttpError {
@@ -12,6 +24,18 @@ pub enum HttpError {
     ReqwestError,
     JsonError,
     IOError,
+    /// The request took longer than the timeout set with ``set_request_timeout``
+    TimedOut,
+    /// The response was an HTML page rather than the file asked for, such as the login page of a
+    /// captive portal or a single sign-on redirect served with a ``200`` status, holding the first
+    /// characters of its body
+    UnexpectedContent(String),
+    /// A response redirected the request more times than allowed by ``with_max_redirects``,
+    /// holding the limit
+    TooManyRedirects(usize),
+    /// The request or one of its redirects was to a host missing from the hosts allowed by
+    /// ``with_allowed_hosts``, holding the host
+    HostNotAllowed(String),
 }
 
 impl From<ParseError> for HttpError {
@@ -21,8 +45,24 @@ impl From<ParseError> for HttpError {
 }
 
 impl From<reqwest::Error> for HttpError {
-    fn from(_: reqwest::Error) -> Self {
-        HttpError::ReqwestError
+    fn from(error: reqwest::Error) -> Self {
+        // Redirects refused by the ``redirect_policy`` fail with the error it gave
+        let mut source = std::error::Error::source(&error);
+        while let Some(cause) = source {
+            match cause.downcast_ref::<RedirectError>() {
+                Some(RedirectError::TooMany(limit)) => return HttpError::TooManyRedirects(*limit),
+                Some(RedirectError::HostNotAllowed(host)) => {
+                    return HttpError::HostNotAllowed(host.clone())
+                }
+                None => source = cause.source(),
+            }
+        }
+
+        if error.is_timeout() {
+            HttpError::TimedOut
+        } else {
+            HttpError::ReqwestError
+        }
     }
 }
 
@@ -38,7 +78,108 @@ impl From<std::io::Error> for HttpError {
     }
 }
 
+/// Number of redirects in a row a client follows by default, as many as Reqwest does.
+pub const DEFAULT_MAX_REDIRECTS: usize = 10;
+
+/// Redirect the ``redirect_policy`` of a client refused to follow
+#[derive(Debug)]
+enum RedirectError {
+    TooMany(usize),
+    HostNotAllowed(String),
+}
+
+impl fmt::Display for RedirectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RedirectError::TooMany(limit) => write!(f, "more than {} redirects", limit),
+            RedirectError::HostNotAllowed(host) => {
+                write!(f, "redirected to {} which isn't allowed", host)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RedirectError {}
+
+/// Check that the host of ``url`` is one of the ``allowed_hosts``, ignoring case, unless none are
+/// given. A URL without a host, which a client can't fetch, is never allowed.
+fn check_host(url: &Url, allowed_hosts: &[String]) -> Result<(), HttpError> {
+    if allowed_hosts.is_empty() {
+        return Ok(());
+    }
+
+    let host = url.host_str().unwrap_or_default().trim_end_matches('.');
+    match allowed_hosts
+        .iter()
+        .any(|allowed| !host.is_empty() && allowed.eq_ignore_ascii_case(host))
+    {
+        true => Ok(()),
+        false => Err(HttpError::HostNotAllowed(host.to_ascii_lowercase())),
+    }
+}
+
+/// ``url`` without its username and password, if any, so that credentials embedded in a base URL
+/// never end up in the logs
+fn redacted(url: &Url) -> Url {
+    let mut url = url.clone();
+    // Only fails for URLs that can't have credentials in the first place
+    let _ = url.set_username("");
+    let _ = url.set_password(None);
+    url
+}
+
+/// Redirect policy following at most ``max_redirects`` redirects in a row, only to the
+/// ``allowed_hosts`` if any are given
+pub(crate) fn redirect_policy(max_redirects: usize, allowed_hosts: &[String]) -> Policy {
+    let allowed_hosts = allowed_hosts.to_vec();
+    Policy::custom(move |attempt| {
+        // The previous URLs start with the one first requested
+        if attempt.previous().len() > max_redirects {
+            return attempt.error(RedirectError::TooMany(max_redirects));
+        }
+        match check_host(attempt.url(), &allowed_hosts) {
+            Err(HttpError::HostNotAllowed(host)) => {
+                attempt.error(RedirectError::HostNotAllowed(host))
+            }
+            _ => attempt.follow(),
+        }
+    })
+}
+
+/// Number of bytes of an unexpected body kept in ``HttpError::UnexpectedContent``.
+const CONTENT_PREVIEW_LEN: usize = 64;
+
+/// Check that the ``body`` of a feed or Metafile isn't markup, which neither of them nor any of
+/// their compression formats start with, so that an HTML page served in their place fails with a
+/// descriptive error rather than when it is decompressed or parsed.
+fn check_content(body: &[u8]) -> Result<(), HttpError> {
+    let body = body.strip_prefix(b"\xef\xbb\xbf").unwrap_or(body);
+    let start = body
+        .iter()
+        .position(|byte| !byte.is_ascii_whitespace())
+        .unwrap_or(body.len());
+    let content = &body[start..];
+
+    if !content.starts_with(b"<") {
+        return Ok(());
+    }
+
+    let preview = String::from_utf8_lossy(&content[..content.len().min(CONTENT_PREVIEW_LEN)]);
+    Err(HttpError::UnexpectedContent(
+        preview.split_whitespace().collect::<Vec<_>>().join(" "),
+    ))
+}
+
+/// A CVE feed as fetched by a client, either still compressed or already parsed
+#[cfg(feature = "blocking")]
+pub enum FetchedFeed {
+    /// The body of the feed file, left for the caller to decompress and parse
+    Compressed(Vec<u8>, Compression),
+    Parsed(CveFeed),
+}
+
 /// Trait for fetching CVE feed and Metafiles
+#[cfg(feature = "blocking")]
 pub trait BlockingHttpClient {
     fn new<S: Into<String>>(
         base_url: S,
@@ -46,16 +187,148 @@ pub trait BlockingHttpClient {
         pool_idle_timeout: Option<Duration>,
         keepalive: Option<Duration>,
     ) -> Self;
-    fn get_metafile(&self, metafile: &str) -> Result<String, HttpError>;
-    fn get_feed(&self, name: &str) -> Result<CveFeed, HttpError>;
+    fn get_metafile(&self, name: &FeedName) -> Result<String, HttpError>;
+    fn get_feed(&self, name: &FeedName) -> Result<CveFeed, HttpError>;
+
+    /// Fetches a CVE feed without decompressing or parsing it if the client can, so that a sync
+    /// decompresses and parses a feed while the next one is downloading. Defaults to the parsed
+    /// feed from ``get_feed``.
+    fn fetch_feed(&self, name: &FeedName) -> Result<FetchedFeed, HttpError> {
+        Ok(FetchedFeed::Parsed(self.get_feed(name)?))
+    }
+
+    /// Limit the time each request made from now on may take, from connecting until its body is
+    /// read, failing it with ``HttpError::TimedOut``. ``None`` removes the limit. Clients that
+    /// can't interrupt their requests ignore it, which is the default.
+    fn set_request_timeout(&mut self, _timeout: Option<Duration>) {}
+
+    /// Follow at most ``max_redirects`` redirects in a row and only contact the ``allowed_hosts``,
+    /// any if empty, replacing the limits the client was built with. Used by a sync to apply the
+    /// ``max_redirects`` and ``allowed_hosts`` of its config. Clients that never follow redirects
+    /// ignore it, which is the default.
+    fn set_redirect_limits(&mut self, _max_redirects: usize, _allowed_hosts: &[String]) {}
+}
+
+/// Trait for fetching CVE feeds and Metafiles without blocking, e.g. from within a Tokio service,
+/// see ``cache::sync_async``.
+pub trait AsyncHttpClient {
+    fn new<S: Into<String>>(
+        base_url: S,
+        connection_timeout: Option<Duration>,
+        pool_idle_timeout: Option<Duration>,
+        keepalive: Option<Duration>,
+    ) -> Self;
+    fn get_metafile(
+        &self,
+        name: &FeedName,
+    ) -> impl Future<Output = Result<String, HttpError>> + Send;
+    fn get_feed(&self, name: &FeedName) -> impl Future<Output = Result<CveFeed, HttpError>> + Send;
+
+    /// Limit the time each request made from now on may take, as with
+    /// ``BlockingHttpClient::set_request_timeout``. Ignored by default.
+    fn set_request_timeout(&mut self, _timeout: Option<Duration>) {}
+
+    /// Limit the redirects followed and the hosts contacted, see
+    /// ``BlockingHttpClient::set_redirect_limits``. Ignored by default.
+    fn set_redirect_limits(&mut self, _max_redirects: usize, _allowed_hosts: &[String]) {}
 }
 
 /// HTTP Client for Reqwest's Blocking API
+///
+/// Requests go through the proxies set by the standard environment variables, see
+/// ``with_env_proxy``.
+#[cfg(feature = "blocking")]
 pub struct ReqwestBlockingClient {
     client: reqwest::blocking::Client,
     base_url: String,
+    compression: Compression,
+    parse_threads: usize,
+    connection_timeout: Option<Duration>,
+    pool_idle_timeout: Option<Duration>,
+    keepalive: Option<Duration>,
+    request_timeout: Option<Duration>,
+    env_proxy: bool,
+    tls_insecure: bool,
+    max_redirects: usize,
+    allowed_hosts: Vec<String>,
+}
+
+/// Value of the first of the environment variables ``names`` that is set and not empty
+fn env_var(names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.trim().is_empty())
+}
+
+/// Proxies set by the ``http_proxy``, ``https_proxy`` and ``all_proxy`` environment variables or
+/// their uppercase variants, bypassed for the hosts listed in ``no_proxy`` or ``NO_PROXY``.
+/// ``HTTP_PROXY`` is ignored when run as a CGI script, as it can then be set by a request header.
+pub(crate) fn env_proxies() -> Vec<Proxy> {
+    let http = match std::env::var_os("REQUEST_METHOD") {
+        Some(_) => env_var(&["http_proxy"]),
+        None => env_var(&["http_proxy", "HTTP_PROXY"]),
+    };
+    let https = env_var(&["https_proxy", "HTTPS_PROXY"]);
+    let all = env_var(&["all_proxy", "ALL_PROXY"]);
+
+    let mut proxies = vec![];
+    let configured = [
+        ("http_proxy", http.map(Proxy::http)),
+        ("https_proxy", https.map(Proxy::https)),
+        ("all_proxy", all.map(Proxy::all)),
+    ];
+    for (variable, proxy) in configured {
+        match proxy {
+            Some(Ok(proxy)) => proxies.push(proxy.no_proxy(NoProxy::from_env())),
+            // The URL isn't logged as it may hold the proxy's credentials
+            Some(Err(error)) => warn!(%error, "ignoring invalid {} proxy", variable),
+            None => {}
+        }
+    }
+    proxies
 }
 
+/// Build the Reqwest client, with the proxies set by the environment if ``env_proxy`` is ``true``.
+/// TLS connections are verified against the Mozilla root certificates built into the binary, so no
+/// system certificate store is needed, unless ``tls_insecure`` is ``true``. Redirects are followed
+/// as the ``redirect`` policy allows.
+#[cfg(feature = "blocking")]
+pub(crate) fn build_client(
+    connection_timeout: Option<Duration>,
+    pool_idle_timeout: Option<Duration>,
+    keepalive: Option<Duration>,
+    env_proxy: bool,
+    tls_insecure: bool,
+    redirect: Policy,
+) -> reqwest::blocking::Client {
+    // Proxies are only ever the ones set here, never the ones Reqwest would pick up on its own
+    let mut builder = reqwest::blocking::Client::builder()
+        .user_agent(concat!(
+            env!("CARGO_PKG_NAME"),
+            "/",
+            env!("CARGO_PKG_VERSION")
+        ))
+        .connect_timeout(connection_timeout)
+        .pool_idle_timeout(pool_idle_timeout)
+        .tcp_keepalive(keepalive)
+        .use_rustls_tls()
+        .danger_accept_invalid_certs(tls_insecure)
+        .redirect(redirect)
+        .no_proxy();
+
+    if env_proxy {
+        for proxy in env_proxies() {
+            builder = builder.proxy(proxy);
+        }
+    }
+
+    builder
+        .build()
+        .expect("Failed to build Reqwest Blocking Client")
+}
+
+#[cfg(feature = "blocking")]
 impl BlockingHttpClient for ReqwestBlockingClient {
     fn new<S: Into<String>>(
         base_url: S,
@@ -63,48 +336,513 @@ impl BlockingHttpClient for ReqwestBlockingClient {
         pool_idle_timeout: Option<Duration>,
         keepalive: Option<Duration>,
     ) -> Self {
-        let client = reqwest::blocking::Client::builder()
-            .user_agent(concat!(
-                env!("CARGO_PKG_NAME"),
-                "/",
-                env!("CARGO_PKG_VERSION")
-            ))
-            .connect_timeout(connection_timeout)
-            .pool_idle_timeout(pool_idle_timeout)
-            .tcp_keepalive(keepalive)
-            .build()
-            .expect("Failed to build Reqwest Blocking Client");
         Self {
             base_url: base_url.into(),
-            client,
+            client: build_client(
+                connection_timeout,
+                pool_idle_timeout,
+                keepalive,
+                true,
+                false,
+                redirect_policy(DEFAULT_MAX_REDIRECTS, &[]),
+            ),
+            compression: Compression::default(),
+            parse_threads: 0,
+            connection_timeout,
+            pool_idle_timeout,
+            keepalive,
+            request_timeout: None,
+            env_proxy: true,
+            tls_insecure: false,
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            allowed_hosts: vec![],
         }
     }
 
     /// Fetches a Metafile text file
-    fn get_metafile(&self, name: &str) -> Result<String, HttpError> {
-        let filename = format!("nvdcve-1.1-{}.meta", name);
-        let url = Url::parse(self.base_url.as_str())?.join(filename.as_str())?;
-        Ok(self.client.get(url).send()?.text()?)
+    fn get_metafile(&self, name: &FeedName) -> Result<String, HttpError> {
+        let url = Url::parse(self.base_url.as_str())?.join(&name.metafile_filename())?;
+        let body = self.get_bytes(url)?;
+        check_content(&body)?;
+        Ok(String::from_utf8_lossy(&body).into_owned())
     }
 
-    /// Fetches a GZipped CVE JSON feed
-    fn get_feed(&self, name: &str) -> Result<CveFeed, HttpError> {
-        let filename = format!("nvdcve-1.1-{}.json.gz", name);
+    /// Fetches a compressed CVE JSON feed
+    fn get_feed(&self, name: &FeedName) -> Result<CveFeed, HttpError> {
+        let url = Url::parse(self.base_url.as_str())?
+            .join(&name.compressed_feed_filename(self.compression))?;
+
+        let body = self.get_bytes(url)?;
+        check_content(&body)?;
+
+        let decompressed_bytes = self.compression.decompress(&body)?;
+
+        Ok(CveFeed::from_slice_parallel(
+            &decompressed_bytes,
+            self.parse_threads,
+        )?)
+    }
 
-        let url = Url::parse(self.base_url.as_str())?.join(filename.as_str())?;
+    /// Fetches a compressed CVE JSON feed, leaving it compressed
+    fn fetch_feed(&self, name: &FeedName) -> Result<FetchedFeed, HttpError> {
+        let url = Url::parse(self.base_url.as_str())?
+            .join(&name.compressed_feed_filename(self.compression))?;
 
-        let response = self.client.get(url).send()?;
+        let body = self.get_bytes(url)?;
+        check_content(&body)?;
+
+        Ok(FetchedFeed::Compressed(body, self.compression))
+    }
+
+    fn set_request_timeout(&mut self, timeout: Option<Duration>) {
+        self.request_timeout = timeout;
+    }
+
+    fn set_redirect_limits(&mut self, max_redirects: usize, allowed_hosts: &[String]) {
+        self.max_redirects = max_redirects;
+        self.allowed_hosts = allowed_hosts.to_vec();
+        self.client = self.build();
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl ReqwestBlockingClient {
+    /// Fetch feeds compressed in the given format instead of GZipped feeds.
+    ///
+    /// ## Example:
+    /// ```no_run
+    /// use nvd_cve::client::{BlockingHttpClient, ReqwestBlockingClient};
+    /// use nvd_cve::compression::Compression;
+    ///
+    /// let client = ReqwestBlockingClient::new("https://mirror.example.com/nvd/", None, None, None)
+    ///     .with_compression(Compression::Zip);
+    /// ```
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Parse the items of each feed using this many threads instead of one per available CPU.
+    ///
+    /// ## Example:
+    /// ```no_run
+    /// use nvd_cve::client::{BlockingHttpClient, ReqwestBlockingClient};
+    ///
+    /// let client = ReqwestBlockingClient::new("https://nvd.nist.gov/feeds/json/cve/1.1/", None, None, None)
+    ///     .with_parse_threads(2);
+    /// ```
+    pub fn with_parse_threads(mut self, threads: usize) -> Self {
+        self.parse_threads = threads;
+        self
+    }
+
+    /// Whether requests go through the proxies set by the ``http_proxy``, ``https_proxy`` and
+    /// ``all_proxy`` environment variables, or their uppercase variants, except for the hosts listed
+    /// in ``no_proxy`` or ``NO_PROXY``, such as an internal mirror. Enabled by default, a disabled
+    /// client always connects directly.
+    ///
+    /// ## Example:
+    /// ```no_run
+    /// use nvd_cve::client::{BlockingHttpClient, ReqwestBlockingClient};
+    ///
+    /// let client = ReqwestBlockingClient::new("https://mirror.example.com/nvd/", None, None, None)
+    ///     .with_env_proxy(false);
+    /// ```
+    pub fn with_env_proxy(mut self, enabled: bool) -> Self {
+        self.env_proxy = enabled;
+        self.rebuild()
+    }
+
+    /// Accept any TLS certificate, including self-signed and expired ones, e.g. for a lab mirror.
+    /// This leaves the feeds open to tampering by anyone on the network path, so it is logged as a
+    /// warning and should never be enabled for the NVD itself.
+    ///
+    /// ## Example:
+    /// ```no_run
+    /// use nvd_cve::client::{BlockingHttpClient, ReqwestBlockingClient};
+    ///
+    /// let client = ReqwestBlockingClient::new("https://mirror.lab.example/nvd/", None, None, None)
+    ///     .with_tls_insecure(true);
+    /// ```
+    pub fn with_tls_insecure(mut self, enabled: bool) -> Self {
+        if enabled {
+            warn!(base_url = %self.base_url, "TLS certificate verification is disabled");
+        }
+        self.tls_insecure = enabled;
+        self.rebuild()
+    }
+
+    /// Follow at most ``max_redirects`` redirects in a row, failing the request with
+    /// ``HttpError::TooManyRedirects`` once exceeded. ``0`` follows none, the default is
+    /// ``DEFAULT_MAX_REDIRECTS``.
+    ///
+    /// ## Example:
+    /// ```no_run
+    /// use nvd_cve::client::{BlockingHttpClient, ReqwestBlockingClient};
+    ///
+    /// let client = ReqwestBlockingClient::new("https://mirror.example.com/nvd/", None, None, None)
+    ///     .with_max_redirects(0);
+    /// ```
+    pub fn with_max_redirects(mut self, max_redirects: usize) -> Self {
+        self.max_redirects = max_redirects;
+        self.rebuild()
+    }
+
+    /// Only contact the ``hosts`` given, ignoring case, so that a misconfigured or compromised
+    /// mirror can't send the client elsewhere. Requests and redirects to any other host fail with
+    /// ``HttpError::HostNotAllowed`` before connecting to it. Empty, the default, allows any host.
+    ///
+    /// ## Example:
+    /// ```no_run
+    /// use nvd_cve::client::{BlockingHttpClient, ReqwestBlockingClient};
+    ///
+    /// let client = ReqwestBlockingClient::new("https://mirror.example.com/nvd/", None, None, None)
+    ///     .with_allowed_hosts(&["mirror.example.com"]);
+    /// ```
+    pub fn with_allowed_hosts<S: AsRef<str>>(mut self, hosts: &[S]) -> Self {
+        self.allowed_hosts = hosts.iter().map(|host| host.as_ref().to_string()).collect();
+        self.rebuild()
+    }
+
+    /// Replace the Reqwest client after its settings changed
+    fn rebuild(mut self) -> Self {
+        self.client = self.build();
+        self
+    }
+
+    /// A Reqwest client with the current settings
+    fn build(&self) -> reqwest::blocking::Client {
+        build_client(
+            self.connection_timeout,
+            self.pool_idle_timeout,
+            self.keepalive,
+            self.env_proxy,
+            self.tls_insecure,
+            redirect_policy(self.max_redirects, &self.allowed_hosts),
+        )
+    }
+
+    /// Fetch any file such as a cache snapshot, a relative ``url`` is resolved against the base URL
+    /// of the client.
+    pub fn get_url(&self, url: &str) -> Result<Vec<u8>, HttpError> {
+        let url = Url::parse(self.base_url.as_str())?.join(url)?;
+        self.get_bytes(url)
+    }
+
+    /// Post ``body`` as JSON, e.g. to a webhook, with the ``authorization`` header if any, and return
+    /// the body of the response. A relative ``url`` is resolved against the base URL of the client,
+    /// responses with an error status fail.
+    pub fn post_json<T: Serialize + ?Sized>(
+        &self,
+        url: &str,
+        body: &T,
+        authorization: Option<&str>,
+    ) -> Result<Vec<u8>, HttpError> {
+        let url = Url::parse(self.base_url.as_str())?.join(url)?;
+        check_host(&url, &self.allowed_hosts)?;
+        let request_id = REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+        let start = Instant::now();
+
+        debug!(request_id, url = %redacted(&url), "POST");
+
+        let mut request = self.client.post(url).json(body);
+        if let Some(authorization) = authorization {
+            request = request.header(AUTHORIZATION, authorization);
+        }
+        if let Some(timeout) = self.request_timeout {
+            request = request.timeout(timeout);
+        }
+
+        let response = request.send().and_then(|response| {
+            debug!(
+                request_id,
+                status = response.status().as_u16(),
+                elapsed_ms = start.elapsed().as_millis() as u64,
+                "response headers received"
+            );
+            response.error_for_status()?.bytes()
+        });
+
+        match response {
+            Ok(body) => Ok(body.to_vec()),
+            Err(error) => {
+                debug!(request_id, elapsed_ms = start.elapsed().as_millis() as u64, %error, "request failed");
+                Err(error.into())
+            }
+        }
+    }
+
+    /// Fetch the body of ``url``, logging the request's URL, response status, timing and size (but
+    /// never its body) as debug events under the ``nvd_cve::client`` target.
+    fn get_bytes(&self, url: Url) -> Result<Vec<u8>, HttpError> {
+        check_host(&url, &self.allowed_hosts)?;
+        let request_id = REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+        let start = Instant::now();
+
+        debug!(request_id, url = %redacted(&url), "GET");
+
+        let mut request = self.client.get(url);
+        if let Some(timeout) = self.request_timeout {
+            request = request.timeout(timeout);
+        }
+
+        let response = match request.send() {
+            Ok(response) => response,
+            Err(error) => {
+                debug!(request_id, elapsed_ms = start.elapsed().as_millis() as u64, %error, "request failed");
+                return Err(error.into());
+            }
+        };
+
+        debug!(
+            request_id,
+            status = response.status().as_u16(),
+            elapsed_ms = start.elapsed().as_millis() as u64,
+            content_length = response.content_length(),
+            "response headers received"
+        );
+
+        let body = match response.bytes() {
+            Ok(body) => body,
+            Err(error) => {
+                debug!(request_id, elapsed_ms = start.elapsed().as_millis() as u64, %error, "reading body failed");
+                return Err(error.into());
+            }
+        };
+
+        debug!(
+            request_id,
+            bytes = body.len(),
+            elapsed_ms = start.elapsed().as_millis() as u64,
+            "response body received"
+        );
+
+        Ok(body.to_vec())
+    }
+}
+
+/// HTTP Client for Reqwest's async API, to be used within a Tokio runtime. Feeds are decompressed
+/// and parsed on the task awaiting them.
+///
+/// Requests go through the proxies set by the standard environment variables, see
+/// ``with_env_proxy``.
+pub struct ReqwestAsyncClient {
+    client: reqwest::Client,
+    base_url: String,
+    compression: Compression,
+    connection_timeout: Option<Duration>,
+    pool_idle_timeout: Option<Duration>,
+    keepalive: Option<Duration>,
+    request_timeout: Option<Duration>,
+    env_proxy: bool,
+    tls_insecure: bool,
+    max_redirects: usize,
+    allowed_hosts: Vec<String>,
+}
+
+/// Build the async Reqwest client, configured as ``build_client`` configures the blocking one
+fn build_async_client(
+    connection_timeout: Option<Duration>,
+    pool_idle_timeout: Option<Duration>,
+    keepalive: Option<Duration>,
+    env_proxy: bool,
+    tls_insecure: bool,
+    redirect: Policy,
+) -> reqwest::Client {
+    let mut builder = reqwest::Client::builder()
+        .user_agent(concat!(
+            env!("CARGO_PKG_NAME"),
+            "/",
+            env!("CARGO_PKG_VERSION")
+        ))
+        .pool_idle_timeout(pool_idle_timeout)
+        .tcp_keepalive(keepalive)
+        .use_rustls_tls()
+        .danger_accept_invalid_certs(tls_insecure)
+        .redirect(redirect)
+        .no_proxy();
+
+    // Unlike the blocking builder, the async one has no way to unset the connect timeout
+    if let Some(timeout) = connection_timeout {
+        builder = builder.connect_timeout(timeout);
+    }
+    if env_proxy {
+        for proxy in env_proxies() {
+            builder = builder.proxy(proxy);
+        }
+    }
+
+    builder
+        .build()
+        .expect("Failed to build Reqwest Async Client")
+}
+
+impl AsyncHttpClient for ReqwestAsyncClient {
+    fn new<S: Into<String>>(
+        base_url: S,
+        connection_timeout: Option<Duration>,
+        pool_idle_timeout: Option<Duration>,
+        keepalive: Option<Duration>,
+    ) -> Self {
+        Self {
+            base_url: base_url.into(),
+            client: build_async_client(
+                connection_timeout,
+                pool_idle_timeout,
+                keepalive,
+                true,
+                false,
+                redirect_policy(DEFAULT_MAX_REDIRECTS, &[]),
+            ),
+            compression: Compression::default(),
+            connection_timeout,
+            pool_idle_timeout,
+            keepalive,
+            request_timeout: None,
+            env_proxy: true,
+            tls_insecure: false,
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            allowed_hosts: vec![],
+        }
+    }
+
+    /// Fetches a Metafile text file
+    async fn get_metafile(&self, name: &FeedName) -> Result<String, HttpError> {
+        let url = Url::parse(self.base_url.as_str())?.join(&name.metafile_filename())?;
+        let body = self.get_bytes(url).await?;
+        check_content(&body)?;
+        Ok(String::from_utf8_lossy(&body).into_owned())
+    }
+
+    /// Fetches a compressed CVE JSON feed
+    async fn get_feed(&self, name: &FeedName) -> Result<CveFeed, HttpError> {
+        let url = Url::parse(self.base_url.as_str())?
+            .join(&name.compressed_feed_filename(self.compression))?;
+
+        let body = self.get_bytes(url).await?;
+        check_content(&body)?;
+
+        let decompressed_bytes = self.compression.decompress(&body)?;
+
+        Ok(serde_json::from_slice(&decompressed_bytes)?)
+    }
+
+    fn set_request_timeout(&mut self, timeout: Option<Duration>) {
+        self.request_timeout = timeout;
+    }
+
+    fn set_redirect_limits(&mut self, max_redirects: usize, allowed_hosts: &[String]) {
+        self.max_redirects = max_redirects;
+        self.allowed_hosts = allowed_hosts.to_vec();
+        self.client = self.build();
+    }
+}
+
+impl ReqwestAsyncClient {
+    /// Fetch feeds compressed in the given format instead of GZipped feeds, see
+    /// ``ReqwestBlockingClient::with_compression``.
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Whether requests go through the proxies set by the environment, enabled by default, see
+    /// ``ReqwestBlockingClient::with_env_proxy``.
+    pub fn with_env_proxy(mut self, enabled: bool) -> Self {
+        self.env_proxy = enabled;
+        self.rebuild()
+    }
+
+    /// Accept any TLS certificate, see ``ReqwestBlockingClient::with_tls_insecure``.
+    pub fn with_tls_insecure(mut self, enabled: bool) -> Self {
+        if enabled {
+            warn!(base_url = %self.base_url, "TLS certificate verification is disabled");
+        }
+        self.tls_insecure = enabled;
+        self.rebuild()
+    }
+
+    /// Follow at most ``max_redirects`` redirects in a row, see
+    /// ``ReqwestBlockingClient::with_max_redirects``.
+    pub fn with_max_redirects(mut self, max_redirects: usize) -> Self {
+        self.max_redirects = max_redirects;
+        self.rebuild()
+    }
+
+    /// Only contact the ``hosts`` given, see ``ReqwestBlockingClient::with_allowed_hosts``.
+    pub fn with_allowed_hosts<S: AsRef<str>>(mut self, hosts: &[S]) -> Self {
+        self.allowed_hosts = hosts.iter().map(|host| host.as_ref().to_string()).collect();
+        self.rebuild()
+    }
+
+    /// Replace the Reqwest client after its settings changed
+    fn rebuild(mut self) -> Self {
+        self.client = self.build();
+        self
+    }
+
+    /// A Reqwest client with the current settings
+    fn build(&self) -> reqwest::Client {
+        build_async_client(
+            self.connection_timeout,
+            self.pool_idle_timeout,
+            self.keepalive,
+            self.env_proxy,
+            self.tls_insecure,
+            redirect_policy(self.max_redirects, &self.allowed_hosts),
+        )
+    }
+
+    /// Fetch any file, a relative ``url`` is resolved against the base URL of the client.
+    pub async fn get_url(&self, url: &str) -> Result<Vec<u8>, HttpError> {
+        let url = Url::parse(self.base_url.as_str())?.join(url)?;
+        self.get_bytes(url).await
+    }
+
+    /// Fetch the body of ``url``, logging the request as ``ReqwestBlockingClient`` does.
+    async fn get_bytes(&self, url: Url) -> Result<Vec<u8>, HttpError> {
+        check_host(&url, &self.allowed_hosts)?;
+        let request_id = REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+        let start = Instant::now();
+
+        debug!(request_id, url = %redacted(&url), "GET");
+
+        let mut request = self.client.get(url);
+        if let Some(timeout) = self.request_timeout {
+            request = request.timeout(timeout);
+        }
 
-        let mut decoder = MultiGzDecoder::new(response);
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(error) => {
+                debug!(request_id, elapsed_ms = start.elapsed().as_millis() as u64, %error, "request failed");
+                return Err(error.into());
+            }
+        };
 
-        let mut decompressed_bytes = vec![];
+        debug!(
+            request_id,
+            status = response.status().as_u16(),
+            elapsed_ms = start.elapsed().as_millis() as u64,
+            content_length = response.content_length(),
+            "response headers received"
+        );
 
-        std::io::copy(&mut decoder, &mut decompressed_bytes)?;
+        let body = match response.bytes().await {
+            Ok(body) => body,
+            Err(error) => {
+                debug!(request_id, elapsed_ms = start.elapsed().as_millis() as u64, %error, "reading body failed");
+                return Err(error.into());
+            }
+        };
 
-        decoder
-            .read_to_end(&mut decompressed_bytes)
-            .expect("Failed to read to end of GZipped data.");
+        debug!(
+            request_id,
+            bytes = body.len(),
+            elapsed_ms = start.elapsed().as_millis() as u64,
+            "response body received"
+        );
 
-        Ok(serde_json::from_slice::<CveFeed>(&decompressed_bytes)?)
+        Ok(body.to_vec())
     }
 }
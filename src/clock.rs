@@ -0,0 +1,60 @@
+use chrono::{DateTime, Duration, Utc};
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+/// Source of the current time of a ``CacheConfig``, which dates sync runs, the versions of CVEs
+/// kept in the history and the health of the cache is checked at.
+pub trait Clock: Debug + Send + Sync {
+    /// The current time, in UTC.
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The wall clock of the system, the default clock of a ``CacheConfig``.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock which only moves when it is set or advanced, e.g. to simulate a cache going stale.
+/// Clones share the same time, so a clone given to a ``CacheConfig`` can be advanced from outside.
+///
+/// ## Example:
+/// ```
+/// use chrono::{Duration, TimeZone, Utc};
+/// use nvd_cve::clock::{Clock, ManualClock};
+///
+/// let clock = ManualClock::new(Utc.with_ymd_and_hms(2021, 12, 18, 19, 0, 0).unwrap());
+/// let shared = clock.clone();
+///
+/// clock.advance(Duration::hours(2));
+/// assert_eq!(shared.now(), Utc.with_ymd_and_hms(2021, 12, 18, 21, 0, 0).unwrap());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ManualClock(Arc<Mutex<DateTime<Utc>>>);
+
+impl ManualClock {
+    /// Create a clock stopped at ``now``.
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self(Arc::new(Mutex::new(now)))
+    }
+
+    /// Set the clock to ``now``, which may be earlier than its current time.
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.0.lock().unwrap_or_else(|error| error.into_inner()) = now;
+    }
+
+    /// Move the clock forward by ``duration``, or back if it's negative.
+    pub fn advance(&self, duration: Duration) {
+        *self.0.lock().unwrap_or_else(|error| error.into_inner()) += duration;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.0.lock().unwrap_or_else(|error| error.into_inner())
+    }
+}
@@ -0,0 +1,201 @@
+use crate::cache::{cached_version, CacheConfig, CacheError};
+use crate::cve::Cve;
+use crate::cvss::Severity;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// A version of a CVE as compared by ``CveComparison``, along with its scores and dates from the
+/// columns of the local cache.
+#[derive(Debug, Clone)]
+pub struct CveRecord {
+    pub cve: Cve,
+
+    /// CVSS v3 base severity, or the CVSS v2 severity for CVEs that were never scored with v3.
+    pub severity: Option<Severity>,
+
+    /// CVSS base score matching ``severity``.
+    pub score: Option<f64>,
+
+    /// Publication date as cached, e.g. ``2021-12-10T10:15:00Z``.
+    pub published: Option<String>,
+
+    /// Last modification date as cached.
+    pub last_modified: Option<String>,
+}
+
+/// Returns the cached version of the CVE ``id``, or with ``as_of`` the version that was current at
+/// that date as ``search_by_id_as_of()`` finds it. Scores and publication dates are unknown for
+/// versions kept by versions of this crate that didn't record them.
+///
+/// ## Example:
+/// ```no_run
+/// use chrono::{TimeZone, Utc};
+/// use nvd_cve::cache::CacheConfig;
+/// use nvd_cve::compare::{get_record, CveComparison};
+///
+/// let config = CacheConfig::new();
+/// let triaged = Utc.with_ymd_and_hms(2021, 12, 11, 9, 0, 0).unwrap();
+///
+/// let then = get_record(&config, "CVE-2021-44228", Some(&triaged)).unwrap();
+/// let now = get_record(&config, "CVE-2021-44228", None).unwrap();
+///
+/// let comparison = CveComparison::new(&then, &now);
+/// println!("{} references added", comparison.references_added.len());
+/// ```
+pub fn get_record(
+    config: &CacheConfig,
+    id: &str,
+    as_of: Option<&DateTime<Utc>>,
+) -> Result<CveRecord, CacheError> {
+    let version = cached_version(config, id, as_of)?;
+    let known = |value: String| Some(value).filter(|value| !value.is_empty());
+    Ok(CveRecord {
+        cve: version.cve,
+        severity: version.severity.map(|(severity, _)| severity),
+        score: version.severity.map(|(_, score)| score),
+        published: known(version.published),
+        last_modified: known(version.last_modified),
+    })
+}
+
+/// A field whose value differs between the compared records.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Change<T> {
+    pub from: T,
+    pub to: T,
+}
+
+impl<T: PartialEq> Change<T> {
+    /// The change from ``from`` to ``to``, ``None`` if they are equal.
+    fn of(from: T, to: T) -> Option<Self> {
+        (from != to).then_some(Self { from, to })
+    }
+}
+
+/// Field-by-field differences between two CVE records, such as two related CVEs or two versions of
+/// the same CVE. Fields that are equal are left as ``None``, or empty for lists, and aren't
+/// serialized.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CveComparison {
+    /// ID of the CVE compared from.
+    pub from: String,
+
+    /// ID of the CVE compared to.
+    pub to: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub severity: Option<Change<Option<Severity>>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<Change<Option<f64>>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub published: Option<Change<Option<String>>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<Change<Option<String>>>,
+
+    /// English descriptions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<Change<String>>,
+
+    /// Weaknesses of the CVE compared to, such as ``CWE-502``, that the other doesn't list.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub weaknesses_added: Vec<String>,
+
+    /// Weaknesses of the CVE compared from that the other doesn't list.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub weaknesses_removed: Vec<String>,
+
+    /// URLs of the references of the CVE compared to that the other doesn't list.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub references_added: Vec<String>,
+
+    /// URLs of the references of the CVE compared from that the other doesn't list.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub references_removed: Vec<String>,
+}
+
+impl CveComparison {
+    /// Compare the record ``from`` to the record ``to``. Weaknesses and references are listed in
+    /// the order of the record listing them.
+    pub fn new(from: &CveRecord, to: &CveRecord) -> Self {
+        let (weaknesses_added, weaknesses_removed) =
+            added_removed(weaknesses(&from.cve), weaknesses(&to.cve));
+        let (references_added, references_removed) =
+            added_removed(references(&from.cve), references(&to.cve));
+
+        Self {
+            from: from.cve.cve_data_meta.id.clone(),
+            to: to.cve.cve_data_meta.id.clone(),
+            severity: Change::of(from.severity, to.severity),
+            score: Change::of(from.score, to.score),
+            published: Change::of(from.published.clone(), to.published.clone()),
+            last_modified: Change::of(from.last_modified.clone(), to.last_modified.clone()),
+            description: Change::of(description(&from.cve), description(&to.cve)),
+            weaknesses_added,
+            weaknesses_removed,
+            references_added,
+            references_removed,
+        }
+    }
+
+    /// Returns ``true`` if the records differ by nothing but their IDs.
+    pub fn is_identical(&self) -> bool {
+        self.severity.is_none()
+            && self.score.is_none()
+            && self.published.is_none()
+            && self.last_modified.is_none()
+            && self.description.is_none()
+            && self.weaknesses_added.is_empty()
+            && self.weaknesses_removed.is_empty()
+            && self.references_added.is_empty()
+            && self.references_removed.is_empty()
+    }
+}
+
+/// The last English description of ``cve``, as cached
+fn description(cve: &Cve) -> String {
+    cve.description
+        .description_data
+        .iter()
+        .rev()
+        .find(|description| description.lang == "en")
+        .map(|description| description.value.clone())
+        .unwrap_or_default()
+}
+
+/// The weaknesses listed by the problem types of ``cve``, such as ``CWE-502``
+fn weaknesses(cve: &Cve) -> Vec<String> {
+    cve.problem_type
+        .problem_type_data
+        .iter()
+        .flat_map(|data| &data.description)
+        .filter_map(|description| description.get("value")?.as_str())
+        .map(str::to_string)
+        .collect()
+}
+
+/// The URLs of the references of ``cve``
+fn references(cve: &Cve) -> Vec<String> {
+    cve.references
+        .reference_data
+        .iter()
+        .map(|reference| reference.url.clone())
+        .collect()
+}
+
+/// The values of ``to`` missing from ``from``, and those of ``from`` missing from ``to``, without
+/// duplicates
+fn added_removed(from: Vec<String>, to: Vec<String>) -> (Vec<String>, Vec<String>) {
+    let missing = |values: &[String], other: &[String]| {
+        let mut missing: Vec<String> = vec![];
+        for value in values {
+            if !other.contains(value) && !missing.contains(value) {
+                missing.push(value.clone());
+            }
+        }
+        missing
+    };
+    (missing(&to, &from), missing(&from, &to))
+}
@@ -0,0 +1,253 @@
+use flate2::bufread::GzDecoder;
+use flate2::write::GzEncoder;
+use log::warn;
+use std::fmt;
+use std::io::{self, Cursor, Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Magic bytes every GZip member starts with.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Compression format a CVE JSON feed is served in.
+///
+/// NIST publishes every feed both GZipped and zipped, mirrors may also recompress them with
+/// Zstandard. Zstandard is only supported when the ``zstd`` feature is enabled, without it
+/// ``Compression::Zstd`` can't be parsed and fails to compress or decompress anything.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Compression {
+    /// ``.json.gz`` feeds, the default.
+    #[default]
+    Gzip,
+    /// ``.json.zip`` archives containing the single feed JSON file.
+    Zip,
+    /// ``.json.zst`` feeds, see ``Compression::all``.
+    Zstd,
+}
+
+/// Errors related to selecting a compression format
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompressionError {
+    /// Name or extension isn't a known compression format
+    UnknownFormat(String),
+    /// Format is known but support for it wasn't enabled when building this crate
+    UnsupportedFormat(String),
+}
+
+impl fmt::Display for CompressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompressionError::UnknownFormat(name) => write!(
+                f,
+                "unknown compression format {:?}, expected gzip, zip or zstd",
+                name
+            ),
+            CompressionError::UnsupportedFormat(name) => write!(
+                f,
+                "{:?} compression isn't supported by this build, enable the zstd feature",
+                name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CompressionError {}
+
+impl Compression {
+    /// All compression formats supported by this build.
+    pub fn all() -> Vec<Self> {
+        vec![
+            Compression::Gzip,
+            Compression::Zip,
+            #[cfg(feature = "zstd")]
+            Compression::Zstd,
+        ]
+    }
+
+    /// File extension of feeds compressed in this format, without the leading ``.``
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gz",
+            Compression::Zip => "zip",
+            Compression::Zstd => "zst",
+        }
+    }
+
+    /// Select the compression format by the extension of ``path``, ``None`` if the extension
+    /// isn't one of a supported format.
+    ///
+    /// ## Example:
+    /// ```
+    /// use nvd_cve::compression::Compression;
+    ///
+    /// assert_eq!(
+    ///     Compression::from_path("mirror/nvdcve-1.1-2021.json.zip"),
+    ///     Some(Compression::Zip)
+    /// );
+    /// assert_eq!(Compression::from_path("nvdcve-1.1-2021.json"), None);
+    /// ```
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?;
+        Self::all()
+            .into_iter()
+            .find(|compression| compression.extension().eq_ignore_ascii_case(extension))
+    }
+
+    /// Decompress the complete contents of a feed file.
+    ///
+    /// GZip and Zstandard files may consist of several concatenated members (or frames), which
+    /// are all decompressed. Data following the last of them, such as junk appended by a proxy, is
+    /// ignored with a warning. Malformed input is returned as an ``InvalidData`` error.
+    pub fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, io::Error> {
+        let mut decompressed_bytes = vec![];
+
+        match self {
+            Compression::Gzip => {
+                let mut remaining = data;
+                let mut members = 0;
+                while remaining.starts_with(&GZIP_MAGIC) {
+                    GzDecoder::new(&mut remaining).read_to_end(&mut decompressed_bytes)?;
+                    members += 1;
+                }
+                check_trailing_data(self, members, remaining.len())?;
+            }
+            Compression::Zip => {
+                let mut archive = zip::ZipArchive::new(Cursor::new(data))?;
+                archive.by_index(0)?.read_to_end(&mut decompressed_bytes)?;
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => {
+                let mut remaining = data;
+                let mut frames = 0;
+                while let Ok(size) = zstd::zstd_safe::find_frame_compressed_size(remaining) {
+                    let (frame, rest) = remaining.split_at(size);
+                    zstd::stream::read::Decoder::with_buffer(frame)?
+                        .single_frame()
+                        .read_to_end(&mut decompressed_bytes)?;
+                    remaining = rest;
+                    frames += 1;
+                }
+                check_trailing_data(self, frames, remaining.len())?;
+            }
+            #[cfg(not(feature = "zstd"))]
+            Compression::Zstd => return Err(self.unsupported()),
+        }
+
+        Ok(decompressed_bytes)
+    }
+
+    /// Compress the contents of a feed file, zip archives hold them as a single file named
+    /// ``file_name``.
+    ///
+    /// ## Example:
+    /// ```
+    /// use nvd_cve::compression::Compression;
+    ///
+    /// let json = br#"{"CVE_Items": []}"#;
+    /// let zipped = Compression::Zip.compress(json, "nvdcve-1.1-custom.json").unwrap();
+    ///
+    /// assert_eq!(Compression::Zip.decompress(&zipped).unwrap(), json);
+    /// ```
+    pub fn compress(&self, data: &[u8], file_name: &str) -> Result<Vec<u8>, io::Error> {
+        match self {
+            Compression::Gzip => {
+                let mut encoder = GzEncoder::new(vec![], flate2::Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            Compression::Zip => {
+                let mut archive = zip::ZipWriter::new(Cursor::new(vec![]));
+                let options = zip::write::SimpleFileOptions::default()
+                    .compression_method(zip::CompressionMethod::Deflated);
+                archive.start_file(file_name, options)?;
+                archive.write_all(data)?;
+                Ok(archive.finish()?.into_inner())
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => zstd::encode_all(data, 0),
+            #[cfg(not(feature = "zstd"))]
+            Compression::Zstd => Err(self.unsupported()),
+        }
+    }
+
+    /// Error of compressing or decompressing in a format this build doesn't support
+    #[cfg(not(feature = "zstd"))]
+    fn unsupported(&self) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            CompressionError::UnsupportedFormat(self.to_string()),
+        )
+    }
+}
+
+/// Warn about ``trailing`` bytes left over after decompressing the ``members`` found at the start
+/// of a file, failing if none were found at all.
+fn check_trailing_data(
+    compression: &Compression,
+    members: usize,
+    trailing: usize,
+) -> Result<(), io::Error> {
+    if members == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Data isn't {} compressed", compression),
+        ));
+    }
+
+    if trailing > 0 {
+        warn!(
+            "Ignoring {} bytes of trailing data after {} compressed feed",
+            trailing, compression
+        );
+    }
+
+    Ok(())
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Compression::Gzip => write!(f, "gzip"),
+            Compression::Zip => write!(f, "zip"),
+            Compression::Zstd => write!(f, "zstd"),
+        }
+    }
+}
+
+impl FromStr for Compression {
+    type Err = CompressionError;
+
+    /// Parse either the name of the format or its file extension, e.g. ``gzip`` or ``gz``.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let name = name.trim().trim_start_matches('.').to_ascii_lowercase();
+
+        if let Some(compression) = Self::all()
+            .into_iter()
+            .find(|compression| compression.to_string() == name || compression.extension() == name)
+        {
+            return Ok(compression);
+        }
+
+        match name.as_str() {
+            "zstd" | "zst" => Err(CompressionError::UnsupportedFormat(name)),
+            _ => Err(CompressionError::UnknownFormat(name)),
+        }
+    }
+}
+
+/// Serialized as its name, e.g. ``gzip``
+#[cfg(feature = "serde")]
+impl serde::Serialize for Compression {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Compression {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse()
+            .map_err(|error| serde::de::Error::custom(format!("{:?}", error)))
+    }
+}
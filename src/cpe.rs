@@ -0,0 +1,644 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Prefix of CPE 2.3 formatted strings, e.g. ``cpe:2.3:a:openssl:openssl:1.1.1k:*:*:*:*:*:*:*``.
+pub const FORMATTED_STRING_PREFIX: &str = "cpe:2.3:";
+
+/// Prefix of CPE URIs, e.g. ``cpe:/a:openssl:openssl:1.1.1k``.
+pub const URI_PREFIX: &str = "cpe:/";
+
+/// Errors related to parsing CPE names
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CpeError {
+    /// Name is neither a formatted string beginning with ``cpe:2.3:`` nor a URI beginning with
+    /// ``cpe:/``
+    UnknownBinding(String),
+    /// Name doesn't have the 11 attributes of a formatted string, or has more than the 7
+    /// components of a URI
+    InvalidComponentCount(usize),
+    /// Part is neither ``a``, ``o``, ``h`` nor ANY
+    InvalidPart(String),
+    /// Attribute value has a misplaced wildcard, a dangling escape or a character that isn't
+    /// allowed
+    InvalidValue(String),
+}
+
+impl fmt::Display for CpeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CpeError::UnknownBinding(name) => write!(
+                f,
+                "unknown CPE binding {:?}, expected a name beginning with {} or {}",
+                name, FORMATTED_STRING_PREFIX, URI_PREFIX
+            ),
+            CpeError::InvalidComponentCount(count) => {
+                write!(f, "invalid number of CPE components: {}", count)
+            }
+            CpeError::InvalidPart(part) => {
+                write!(f, "invalid CPE part {:?}, expected a, o or h", part)
+            }
+            CpeError::InvalidValue(value) => write!(f, "invalid CPE attribute value {:?}", value),
+        }
+    }
+}
+
+impl std::error::Error for CpeError {}
+
+/// Value of an attribute of a CPE name, as defined for well-formed names (WFN) by NISTIR 7695.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub enum CpeValue {
+    /// Any value, bound as ``*`` in formatted strings and left empty in URIs.
+    #[default]
+    Any,
+
+    /// Not applicable, bound as ``-``.
+    NotApplicable,
+
+    /// A value as found in a WFN: characters other than letters, digits and underscores are quoted
+    /// with a backslash, except for the ``*`` and ``?`` wildcards at either end, e.g.
+    /// ``1\.1\.1k`` or ``10\.*``.
+    Value(String),
+}
+
+impl CpeValue {
+    /// The value matching ``text`` literally, quoting its characters as needed.
+    ///
+    /// ## Example:
+    /// ```
+    /// use nvd_cve::cpe::CpeValue;
+    ///
+    /// assert_eq!(CpeValue::literal("1.1.1k"), CpeValue::Value(r"1\.1\.1k".to_string()));
+    /// ```
+    pub fn literal(text: &str) -> Self {
+        let mut value = String::new();
+        for c in text.chars() {
+            if !is_alphanumeric(c) {
+                value.push('\\');
+            }
+            value.push(c);
+        }
+        CpeValue::Value(value)
+    }
+
+    /// The text of the value without its quoting, wildcards included, or ``None`` for ANY and NA.
+    pub fn unquoted(&self) -> Option<String> {
+        match self {
+            CpeValue::Value(value) => Some(
+                logical_chars(value)
+                    .into_iter()
+                    .map(|(c, _)| c)
+                    .collect::<String>(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Returns ``true`` if the value is ANY, or contains an unquoted ``*`` or ``?`` wildcard.
+    pub fn has_wildcards(&self) -> bool {
+        match self {
+            CpeValue::Any => true,
+            CpeValue::NotApplicable => false,
+            CpeValue::Value(value) => logical_chars(value).iter().any(|(_, wildcard)| *wildcard),
+        }
+    }
+
+    /// Returns ``true`` if every value matched by ``target`` is matched by this value, following
+    /// the name matching rules of NISTIR 7696. Letters are compared ignoring case, a target with
+    /// wildcards is only matched by ANY.
+    pub fn matches(&self, target: &CpeValue) -> bool {
+        match (self, target) {
+            (CpeValue::Any, _) => true,
+            (CpeValue::NotApplicable, target) => *target == CpeValue::NotApplicable,
+            (CpeValue::Value(_), CpeValue::Any | CpeValue::NotApplicable) => false,
+            (CpeValue::Value(source), CpeValue::Value(value)) => {
+                !target.has_wildcards() && wildcard_match(source, value)
+            }
+        }
+    }
+
+    /// Key the value is indexed by in the local cache: its unquoted text in lowercase, ``*`` if it
+    /// has wildcards and ``-`` for NA, so that a value without wildcards is matched by the values
+    /// keyed by it or by ``*``.
+    pub(crate) fn index_key(&self) -> String {
+        match self {
+            CpeValue::NotApplicable => "-".to_string(),
+            value if value.has_wildcards() => "*".to_string(),
+            value => value.unquoted().unwrap_or_default().to_lowercase(),
+        }
+    }
+
+    /// Parse a value of a formatted string, quoting the characters left unquoted by the binding
+    fn from_formatted(component: &str) -> Result<Self, CpeError> {
+        match component {
+            "*" => return Ok(CpeValue::Any),
+            "-" => return Ok(CpeValue::NotApplicable),
+            "" => return Err(CpeError::InvalidValue(component.to_string())),
+            _ => (),
+        }
+
+        let invalid = || CpeError::InvalidValue(component.to_string());
+        let chars: Vec<char> = component.chars().collect();
+        let last = chars.len() - 1;
+        let mut value = String::new();
+        let mut index = 0;
+
+        while index < chars.len() {
+            let c = chars[index];
+            if is_alphanumeric(c) {
+                value.push(c);
+            } else if c == '\\' {
+                let quoted = *chars.get(index + 1).ok_or_else(invalid)?;
+                if !is_printable(quoted) {
+                    return Err(invalid());
+                }
+                // Quoting letters and digits changes nothing
+                if !is_alphanumeric(quoted) {
+                    value.push(c);
+                }
+                value.push(quoted);
+                index += 1;
+            } else if c == '*' {
+                // Only allowed at the beginning or the end
+                if index != 0 && index != last {
+                    return Err(invalid());
+                }
+                value.push(c);
+            } else if c == '?' {
+                // Only allowed in runs at the beginning or the end
+                let leading = chars[..index].iter().all(|c| *c == '?');
+                let trailing = chars[index + 1..].iter().all(|c| *c == '?');
+                if !leading && !trailing {
+                    return Err(invalid());
+                }
+                value.push(c);
+            } else if is_printable(c) {
+                value.push('\\');
+                value.push(c);
+            } else {
+                return Err(invalid());
+            }
+            index += 1;
+        }
+
+        Ok(CpeValue::Value(value))
+    }
+
+    /// Bind the value into a component of a formatted string
+    fn to_formatted(&self) -> String {
+        match self {
+            CpeValue::Any => "*".to_string(),
+            CpeValue::NotApplicable => "-".to_string(),
+            CpeValue::Value(value) => {
+                let mut component = String::new();
+                let mut chars = value.chars();
+                while let Some(c) = chars.next() {
+                    if c != '\\' {
+                        component.push(c);
+                        continue;
+                    }
+                    match chars.next() {
+                        Some(quoted @ ('.' | '-' | '_')) => component.push(quoted),
+                        Some(quoted) => {
+                            component.push(c);
+                            component.push(quoted);
+                        }
+                        None => component.push(c),
+                    }
+                }
+                // A lone quoted hyphen stays quoted so it isn't read back as NA
+                if component == "-" {
+                    component.insert(0, '\\');
+                }
+                component
+            }
+        }
+    }
+
+    /// Parse a component of a URI, decoding its percent-encoded characters
+    fn from_uri(component: &str) -> Result<Self, CpeError> {
+        match component {
+            "" => return Ok(CpeValue::Any),
+            "-" => return Ok(CpeValue::NotApplicable),
+            _ => (),
+        }
+
+        let invalid = || CpeError::InvalidValue(component.to_string());
+        let component = component.to_ascii_lowercase();
+        let bytes = component.as_bytes();
+        let mut value = String::new();
+        let mut index = 0;
+
+        while index < bytes.len() {
+            let c = bytes[index] as char;
+            if matches!(c, '.' | '-' | '~') {
+                value.push('\\');
+                value.push(c);
+                index += 1;
+                continue;
+            }
+            if c != '%' {
+                if !is_alphanumeric(c) {
+                    return Err(invalid());
+                }
+                value.push(c);
+                index += 1;
+                continue;
+            }
+
+            let form = component.get(index..index + 3).ok_or_else(invalid)?;
+            let last = index + 3 == bytes.len();
+            match form {
+                "%01" => {
+                    let only = |wildcards: &str| {
+                        wildcards.len() % 3 == 0
+                            && wildcards
+                                .as_bytes()
+                                .chunks(3)
+                                .all(|chunk| chunk == form.as_bytes())
+                    };
+                    let leading = only(&component[..index]);
+                    let trailing = only(&component[index + 3..]);
+                    if !leading && !trailing {
+                        return Err(invalid());
+                    }
+                    value.push('?');
+                }
+                "%02" => {
+                    if index != 0 && !last {
+                        return Err(invalid());
+                    }
+                    value.push('*');
+                }
+                _ => {
+                    let decoded = u8::from_str_radix(&form[1..], 16)
+                        .map(char::from)
+                        .map_err(|_| invalid())?;
+                    if !is_printable(decoded) {
+                        return Err(invalid());
+                    }
+                    if !is_alphanumeric(decoded) {
+                        value.push('\\');
+                    }
+                    value.push(decoded.to_ascii_lowercase());
+                }
+            }
+            index += 3;
+        }
+
+        Ok(CpeValue::Value(value))
+    }
+
+    /// Bind the value into a component of a URI
+    fn to_uri(&self) -> String {
+        match self {
+            CpeValue::Any => String::new(),
+            CpeValue::NotApplicable => "-".to_string(),
+            CpeValue::Value(value) => {
+                let mut component = String::new();
+                let mut chars = value.chars();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => match chars.next() {
+                            Some(quoted @ ('.' | '-')) => component.push(quoted),
+                            Some(quoted) => component.push_str(&percent_encode(quoted)),
+                            None => component.push_str(&percent_encode(c)),
+                        },
+                        '?' => component.push_str("%01"),
+                        '*' => component.push_str("%02"),
+                        _ => component.push(c),
+                    }
+                }
+                if component == "-" {
+                    component = percent_encode('-');
+                }
+                component
+            }
+        }
+    }
+}
+
+/// CPE name, identifying a platform or a range of platforms by its 11 attributes as defined for
+/// well-formed names (WFN) by NISTIR 7695. Names are parsed from and bound to both CPE 2.3
+/// formatted strings and CPE 2.2 URIs, following the escaping and wildcard rules of the
+/// specification. Attributes left to their default are ANY.
+///
+/// ## Example:
+/// ```
+/// use nvd_cve::cpe::{Cpe, CpeValue};
+///
+/// let range: Cpe = "cpe:2.3:a:openssl:openssl:1.1.1*:*:*:*:*:*:*:*".parse().unwrap();
+/// let installed: Cpe = "cpe:/a:openssl:openssl:1.1.1k".parse().unwrap();
+///
+/// assert!(range.matches(&installed));
+/// assert_eq!(installed.version, CpeValue::literal("1.1.1k"));
+/// assert_eq!(
+///     installed.to_string(),
+///     "cpe:2.3:a:openssl:openssl:1.1.1k:*:*:*:*:*:*:*"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Cpe {
+    /// ``a`` for applications, ``o`` for operating systems and ``h`` for hardware.
+    pub part: CpeValue,
+    pub vendor: CpeValue,
+    pub product: CpeValue,
+    pub version: CpeValue,
+    pub update: CpeValue,
+    pub edition: CpeValue,
+    pub language: CpeValue,
+    pub sw_edition: CpeValue,
+    pub target_sw: CpeValue,
+    pub target_hw: CpeValue,
+    pub other: CpeValue,
+}
+
+impl Cpe {
+    /// Parse a CPE 2.3 formatted string, such as
+    /// ``cpe:2.3:a:microsoft:exchange_server:2019:cumulative_update_8:*:*:*:*:*:*``.
+    pub fn from_formatted_string(name: &str) -> Result<Self, CpeError> {
+        let attributes = name
+            .strip_prefix(FORMATTED_STRING_PREFIX)
+            .ok_or_else(|| CpeError::UnknownBinding(name.to_string()))?;
+
+        // Colons quoted with a backslash are part of the values
+        let mut components = vec![];
+        let mut start = 0;
+        let mut quoted = false;
+        for (index, c) in attributes.char_indices() {
+            match c {
+                _ if quoted => quoted = false,
+                '\\' => quoted = true,
+                ':' => {
+                    components.push(&attributes[start..index]);
+                    start = index + 1;
+                }
+                _ => (),
+            }
+        }
+        components.push(&attributes[start..]);
+
+        if components.len() != 11 {
+            return Err(CpeError::InvalidComponentCount(components.len()));
+        }
+
+        let values = components
+            .into_iter()
+            .map(CpeValue::from_formatted)
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::from_values(values)
+    }
+
+    /// Parse a CPE URI, such as ``cpe:/a:microsoft:exchange_server:2019:cumulative_update_8``,
+    /// including editions packing the extended attributes of CPE 2.3, such as
+    /// ``cpe:/a:hp:insight_diagnostics:7.4.0.1570::~~online~win2003~x64~``. Components missing at
+    /// the end are ANY.
+    pub fn from_uri(name: &str) -> Result<Self, CpeError> {
+        let prefix = name.get(..URI_PREFIX.len()).unwrap_or_default();
+        if !prefix.eq_ignore_ascii_case(URI_PREFIX) {
+            return Err(CpeError::UnknownBinding(name.to_string()));
+        }
+
+        let components: Vec<&str> = name[URI_PREFIX.len()..].split(':').collect();
+        if components.len() > 7 {
+            return Err(CpeError::InvalidComponentCount(components.len()));
+        }
+
+        let mut values = vec![];
+        for (index, component) in components.iter().enumerate() {
+            if index == 5 && component.starts_with('~') {
+                let packed: Vec<&str> = component[1..].split('~').collect();
+                if packed.len() != 5 {
+                    return Err(CpeError::InvalidValue(component.to_string()));
+                }
+                values.push(CpeValue::from_uri(packed[0])?);
+                values.push(CpeValue::Any);
+                values.extend(
+                    packed[1..]
+                        .iter()
+                        .map(|value| CpeValue::from_uri(value))
+                        .collect::<Result<Vec<_>, _>>()?,
+                );
+            } else if index == 6 {
+                // Language comes before the extended attributes in a WFN
+                let language = CpeValue::from_uri(component)?;
+                if values.len() > 6 {
+                    values[6] = language;
+                } else {
+                    values.push(language);
+                }
+            } else {
+                values.push(CpeValue::from_uri(component)?);
+            }
+        }
+        values.resize(11, CpeValue::Any);
+
+        Self::from_values(values)
+    }
+
+    /// Bind the name to a CPE 2.3 formatted string, also returned by ``to_string()``.
+    pub fn to_formatted_string(&self) -> String {
+        let components: Vec<String> = self
+            .attributes()
+            .iter()
+            .map(|value| value.to_formatted())
+            .collect();
+        format!("{}{}", FORMATTED_STRING_PREFIX, components.join(":"))
+    }
+
+    /// Bind the name to a CPE URI, packing the extended attributes of CPE 2.3 in the edition when
+    /// any of them isn't ANY. Components that are ANY at the end are left out.
+    pub fn to_uri(&self) -> String {
+        let extended = [
+            &self.sw_edition,
+            &self.target_sw,
+            &self.target_hw,
+            &self.other,
+        ];
+        let edition = if extended.iter().all(|value| **value == CpeValue::Any) {
+            self.edition.to_uri()
+        } else {
+            let mut packed = self.edition.to_uri();
+            for value in extended {
+                packed = format!("{}~{}", packed, value.to_uri());
+            }
+            format!("~{}", packed)
+        };
+
+        let components = [
+            self.part.to_uri(),
+            self.vendor.to_uri(),
+            self.product.to_uri(),
+            self.version.to_uri(),
+            self.update.to_uri(),
+            edition,
+            self.language.to_uri(),
+        ];
+        format!(
+            "{}{}",
+            URI_PREFIX,
+            components.join(":").trim_end_matches(':')
+        )
+    }
+
+    /// Returns ``true`` if every platform identified by ``target`` is identified by this name,
+    /// following the name matching rules of NISTIR 7696 attribute by attribute, e.g.
+    /// ``cpe:2.3:a:openssl:openssl:*:*:*:*:*:*:*:*`` matches every version of OpenSSL.
+    pub fn matches(&self, target: &Cpe) -> bool {
+        self.attributes()
+            .iter()
+            .zip(target.attributes())
+            .all(|(source, target)| source.matches(target))
+    }
+
+    /// The attributes in the order they are bound to formatted strings
+    fn attributes(&self) -> [&CpeValue; 11] {
+        [
+            &self.part,
+            &self.vendor,
+            &self.product,
+            &self.version,
+            &self.update,
+            &self.edition,
+            &self.language,
+            &self.sw_edition,
+            &self.target_sw,
+            &self.target_hw,
+            &self.other,
+        ]
+    }
+
+    /// Build a name from its 11 attributes, checking its part
+    fn from_values(values: Vec<CpeValue>) -> Result<Self, CpeError> {
+        let mut values = values.into_iter();
+        let mut next = || values.next().unwrap_or_default();
+
+        let part = next();
+        match &part {
+            CpeValue::Any => (),
+            CpeValue::Value(value) if matches!(value.as_str(), "a" | "o" | "h") => (),
+            CpeValue::Value(value) => return Err(CpeError::InvalidPart(value.clone())),
+            CpeValue::NotApplicable => return Err(CpeError::InvalidPart("-".to_string())),
+        }
+
+        Ok(Self {
+            part,
+            vendor: next(),
+            product: next(),
+            version: next(),
+            update: next(),
+            edition: next(),
+            language: next(),
+            sw_edition: next(),
+            target_sw: next(),
+            target_hw: next(),
+            other: next(),
+        })
+    }
+}
+
+impl fmt::Display for Cpe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_formatted_string())
+    }
+}
+
+impl FromStr for Cpe {
+    type Err = CpeError;
+
+    /// Parse a CPE 2.3 formatted string, or a CPE URI
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        if name.starts_with(FORMATTED_STRING_PREFIX) {
+            Cpe::from_formatted_string(name)
+        } else {
+            Cpe::from_uri(name)
+        }
+    }
+}
+
+/// Letters, digits and underscores are the only characters never quoted in a WFN
+fn is_alphanumeric(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Characters other than letters, digits and whitespace allowed in a WFN, quoted or as wildcards
+fn is_printable(c: char) -> bool {
+    c.is_ascii_graphic()
+}
+
+/// Percent-encode a character quoted in a WFN for binding to a URI
+fn percent_encode(c: char) -> String {
+    format!("%{:02x}", c as u32)
+}
+
+/// The characters of a WFN value without their quoting, each along with whether it is an
+/// unquoted wildcard
+fn logical_chars(value: &str) -> Vec<(char, bool)> {
+    let mut logical = vec![];
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => logical.push((chars.next().unwrap_or(c), false)),
+            '*' | '?' => logical.push((c, true)),
+            _ => logical.push((c, false)),
+        }
+    }
+    logical
+}
+
+/// How many characters the wildcards at one end of a WFN value match
+#[derive(Clone, Copy)]
+enum Padding {
+    /// ``*``, any number of characters
+    Any,
+    /// A run of ``?``, up to as many characters as there are question marks
+    AtMost(usize),
+}
+
+impl Padding {
+    fn allows(self, length: usize) -> bool {
+        match self {
+            Padding::Any => true,
+            Padding::AtMost(max) => length <= max,
+        }
+    }
+}
+
+/// Match the WFN value ``source``, which may have wildcards at either end, against the WFN value
+/// ``target`` without any, ignoring case
+fn wildcard_match(source: &str, target: &str) -> bool {
+    let mut source = logical_chars(source);
+    let target: Vec<char> = logical_chars(target)
+        .into_iter()
+        .map(|(c, _)| c.to_ascii_lowercase())
+        .collect();
+
+    let padding = |wildcards: &[(char, bool)]| match wildcards.first() {
+        Some(('*', true)) => Padding::Any,
+        _ => Padding::AtMost(wildcards.len()),
+    };
+
+    let leading = source.iter().take_while(|(_, wildcard)| *wildcard).count();
+    let before = padding(&source[..leading]);
+    source.drain(..leading);
+    let trailing = source
+        .iter()
+        .rev()
+        .take_while(|(_, wildcard)| *wildcard)
+        .count();
+    let after = padding(&source[source.len() - trailing..]);
+    source.truncate(source.len() - trailing);
+
+    let body: Vec<char> = source
+        .into_iter()
+        .map(|(c, _)| c.to_ascii_lowercase())
+        .collect();
+    if body.len() > target.len() {
+        return false;
+    }
+
+    (0..=target.len() - body.len()).any(|start| {
+        target[start..start + body.len()] == body[..]
+            && before.allows(start)
+            && after.allows(target.len() - start - body.len())
+    })
+}
@@ -0,0 +1,317 @@
+use crate::cache::{open, readable_schema, writable_schema, CacheConfig, CacheError};
+#[cfg(feature = "blocking")]
+use crate::client::ReqwestBlockingClient;
+#[cfg(feature = "blocking")]
+use crate::compression::Compression;
+use crate::cpe::Cpe;
+#[cfg(feature = "blocking")]
+use crate::feed::Metafile;
+use rusqlite::{params, Connection};
+#[cfg(feature = "blocking")]
+use rusqlite::{OptionalExtension, Transaction, TransactionBehavior};
+use serde::{Deserialize, Serialize};
+
+/// Directory of the NVD's official CPE dictionary.
+pub const NVD_CPE_DICTIONARY_URL: &str = "https://nvd.nist.gov/feeds/xml/cpe/dictionary/";
+
+/// File name of the GZipped CPE dictionary, relative to ``NVD_CPE_DICTIONARY_URL``.
+pub const CPE_DICTIONARY: &str = "official-cpe-dictionary_v2.3.xml.gz";
+
+/// File name of the Metafile of the CPE dictionary, relative to ``NVD_CPE_DICTIONARY_URL``.
+pub const CPE_DICTIONARY_METAFILE: &str = "official-cpe-dictionary_v2.3.meta";
+
+/// An item of the official CPE dictionary
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DictionaryEntry {
+    /// CPE 2.3 name, such as ``cpe:2.3:a:openssl:openssl:1.1.1k:*:*:*:*:*:*:*``.
+    pub cpe23_uri: String,
+
+    /// English title, such as ``OpenSSL Project OpenSSL 1.1.1k``, ``None`` if it has none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    /// Whether the name is deprecated, usually in favor of a corrected one.
+    #[serde(default)]
+    pub deprecated: bool,
+}
+
+/// Parse the items of the uncompressed XML of a CPE dictionary. Items without a CPE 2.3 name are
+/// skipped.
+///
+/// ## Example:
+/// ```
+/// use nvd_cve::cpedict::parse_cpe_dictionary;
+///
+/// let entries = parse_cpe_dictionary(
+///     r#"<cpe-item name="cpe:/a:openssl:openssl:1.1.1k">
+///       <title xml:lang="en-US">OpenSSL Project OpenSSL 1.1.1k</title>
+///       <cpe-23:cpe23-item name="cpe:2.3:a:openssl:openssl:1.1.1k:*:*:*:*:*:*:*"/>
+///     </cpe-item>"#,
+/// );
+/// assert_eq!(entries[0].cpe23_uri, "cpe:2.3:a:openssl:openssl:1.1.1k:*:*:*:*:*:*:*");
+/// assert_eq!(entries[0].title.as_deref(), Some("OpenSSL Project OpenSSL 1.1.1k"));
+/// ```
+pub fn parse_cpe_dictionary(xml: &str) -> Vec<DictionaryEntry> {
+    let mut entries = vec![];
+    for item in xml.split("<cpe-item ").skip(1) {
+        let item = item.split("</cpe-item>").next().unwrap_or_default();
+        let attributes = item.split('>').next().unwrap_or_default();
+
+        let Some(cpe23_uri) = item
+            .split_once("cpe23-item ")
+            .and_then(|(_, rest)| attribute(rest.split('>').next().unwrap_or_default(), "name"))
+        else {
+            continue;
+        };
+
+        // Titles may be given in several languages, the first English one is kept
+        let title = item.split("<title").skip(1).find_map(|title| {
+            let (attributes, rest) = title.split_once('>')?;
+            attribute(attributes, "xml:lang")
+                .is_some_and(|lang| lang.starts_with("en"))
+                .then(|| unescape(rest.split("</title>").next().unwrap_or_default().trim()))
+        });
+
+        entries.push(DictionaryEntry {
+            cpe23_uri,
+            title,
+            deprecated: attribute(attributes, "deprecated").is_some_and(|value| value == "true"),
+        });
+    }
+    entries
+}
+
+/// The unescaped value of the attribute ``name`` of an XML element, given the attributes of its
+/// start tag
+pub(crate) fn attribute(attributes: &str, name: &str) -> Option<String> {
+    let mut rest = attributes;
+    while let Some(position) = rest.find(&format!("{}=\"", name)) {
+        let preceded = rest[..position]
+            .chars()
+            .next_back()
+            .is_none_or(char::is_whitespace);
+        rest = &rest[position + name.len() + 2..];
+        if preceded {
+            return Some(unescape(&rest[..rest.find('"')?]));
+        }
+    }
+    None
+}
+
+/// ``text`` with the predefined entities of XML replaced by the characters they stand for
+pub(crate) fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Replace the CPE names cached from the CPE dictionary with ``entries``, returning the number of
+/// names recorded. Entries whose CPE name doesn't parse are skipped.
+pub fn import_cpe_dictionary(
+    config: &CacheConfig,
+    entries: &[DictionaryEntry],
+) -> Result<usize, CacheError> {
+    writable_schema(config)?;
+
+    let mut conn = open(config)?;
+    let tx = conn.transaction()?;
+    tx.execute("DELETE FROM cpe_dictionary", [])?;
+
+    let mut recorded = 0;
+    {
+        let mut stmt = tx.prepare(
+            "INSERT OR REPLACE INTO cpe_dictionary (name, vendor, product, title, deprecated)
+            VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
+        for entry in entries {
+            let Ok(cpe) = entry.cpe23_uri.parse::<Cpe>() else {
+                continue;
+            };
+            stmt.execute(params![
+                entry.cpe23_uri,
+                cpe.vendor.index_key(),
+                cpe.product.index_key(),
+                entry.title,
+                entry.deprecated
+            ])?;
+            recorded += 1;
+        }
+    }
+    tx.commit()?;
+
+    match conn.close() {
+        Ok(_) => Ok(recorded),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Sync the CPE dictionary from the base URL of ``client``, such as ``NVD_CPE_DICTIONARY_URL``, to
+/// the local cache, returning the number of CPE names recorded. The dictionary is only fetched
+/// when its Metafile changed since the last sync, unless ``force_update`` is set, otherwise
+/// nothing is recorded.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::CacheConfig;
+/// use nvd_cve::client::{BlockingHttpClient, ReqwestBlockingClient};
+/// use nvd_cve::cpedict::{sync_cpe_dictionary, NVD_CPE_DICTIONARY_URL};
+///
+/// let config = CacheConfig::new();
+/// let client = ReqwestBlockingClient::new(NVD_CPE_DICTIONARY_URL, None, None, None);
+///
+/// let recorded = sync_cpe_dictionary(&config, &client).unwrap();
+/// println!("Recorded {} CPE names", recorded);
+/// ```
+#[cfg(feature = "blocking")]
+pub fn sync_cpe_dictionary(
+    config: &CacheConfig,
+    client: &ReqwestBlockingClient,
+) -> Result<usize, CacheError> {
+    writable_schema(config)?;
+
+    let metafile = client.get_url(CPE_DICTIONARY_METAFILE)?;
+    let metafile = Metafile::from_string(String::from_utf8_lossy(&metafile).into_owned())?;
+
+    let conn = open(config)?;
+    let synced: Option<String> = conn
+        .query_row("SELECT sha256 FROM cpe_dictionary_sync", [], |row| {
+            row.get(0)
+        })
+        .optional()?;
+    if let Err((_, error)) = conn.close() {
+        return Err(CacheError::RusqliteError(error));
+    }
+    if !config.force_update && synced.as_ref() == Some(&metafile.sha256) {
+        return Ok(0);
+    }
+
+    let xml = Compression::Gzip.decompress(&client.get_url(CPE_DICTIONARY)?)?;
+    let recorded = import_cpe_dictionary(
+        config,
+        &parse_cpe_dictionary(&String::from_utf8_lossy(&xml)),
+    )?;
+
+    let mut conn = open(config)?;
+    let tx = Transaction::new(&mut conn, TransactionBehavior::Immediate)?;
+    tx.execute(
+        "INSERT OR REPLACE INTO cpe_dictionary_sync (id, sha256) VALUES (0, ?1)",
+        [&metafile.sha256],
+    )?;
+    tx.commit()?;
+
+    match conn.close() {
+        Ok(_) => Ok(recorded),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Key of a vendor or product name in the ``cpe_dictionary`` table, see ``CpeValue::index_key()``
+fn name_key(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// Whether the cache has CPE names of the dictionary. Read-only caches made by older versions have
+/// no ``cpe_dictionary`` table.
+fn has_dictionary(conn: &Connection) -> Result<bool, CacheError> {
+    let mut tbl_stmt = conn.prepare(
+        "SELECT name FROM sqlite_master where type = 'table' and name = 'cpe_dictionary'",
+    )?;
+    let exists = tbl_stmt.exists([])?;
+    tbl_stmt.finalize()?;
+    if !exists {
+        return Ok(false);
+    }
+
+    let mut stmt = conn.prepare("SELECT 1 FROM cpe_dictionary LIMIT 1")?;
+    let synced = stmt.exists([])?;
+    stmt.finalize()?;
+    Ok(synced)
+}
+
+/// The entries of the CPE dictionary naming ``product``, made by ``vendor`` if given, both
+/// ignoring case, sorted by CPE name. Empty if the dictionary wasn't synced.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::CacheConfig;
+/// use nvd_cve::cpedict::get_dictionary_entries;
+///
+/// let config = CacheConfig::new();
+/// for entry in get_dictionary_entries(&config, Some("openssl"), "openssl").unwrap() {
+///     println!("{} {}", entry.cpe23_uri, entry.title.unwrap_or_default());
+/// }
+/// ```
+pub fn get_dictionary_entries(
+    config: &CacheConfig,
+    vendor: Option<&str>,
+    product: &str,
+) -> Result<Vec<DictionaryEntry>, CacheError> {
+    readable_schema(config)?;
+
+    let conn = open(config)?;
+    let mut entries = vec![];
+    if has_dictionary(&conn)? {
+        let mut stmt = conn.prepare(
+            "SELECT name, title, deprecated FROM cpe_dictionary
+            WHERE product = ?1 AND (?2 IS NULL OR vendor = ?2)
+            ORDER BY name",
+        )?;
+        let rows = stmt.query_map(params![name_key(product), vendor.map(name_key)], |row| {
+            Ok(DictionaryEntry {
+                cpe23_uri: row.get(0)?,
+                title: row.get(1)?,
+                deprecated: row.get(2)?,
+            })
+        })?;
+        for entry in rows {
+            entries.push(entry?);
+        }
+    }
+
+    match conn.close() {
+        Ok(_) => Ok(entries),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Whether the CPE dictionary names ``product``, made by ``vendor`` if given, both ignoring case,
+/// to validate the names of a CPE-based query. ``None`` if the dictionary wasn't synced, as
+/// nothing is known then.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::CacheConfig;
+/// use nvd_cve::cpedict::is_known_product;
+///
+/// let config = CacheConfig::new();
+/// if is_known_product(&config, Some("opensll"), "openssl").unwrap() == Some(false) {
+///     eprintln!("No product openssl of vendor opensll is known");
+/// }
+/// ```
+pub fn is_known_product(
+    config: &CacheConfig,
+    vendor: Option<&str>,
+    product: &str,
+) -> Result<Option<bool>, CacheError> {
+    readable_schema(config)?;
+
+    let conn = open(config)?;
+    let known = match has_dictionary(&conn)? {
+        true => {
+            let mut stmt = conn.prepare(
+                "SELECT 1 FROM cpe_dictionary WHERE product = ?1 AND (?2 IS NULL OR vendor = ?2)",
+            )?;
+            let known = stmt.exists(params![name_key(product), vendor.map(name_key)])?;
+            stmt.finalize()?;
+            Some(known)
+        }
+        false => None,
+    };
+
+    match conn.close() {
+        Ok(_) => Ok(known),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
@@ -0,0 +1,282 @@
+use crate::cache::{open, writable_schema, CacheConfig, CacheError};
+#[cfg(feature = "blocking")]
+use crate::client::ReqwestBlockingClient;
+#[cfg(feature = "blocking")]
+use crate::compression::Compression;
+use crate::cpe::Cpe;
+#[cfg(feature = "blocking")]
+use crate::feed::Metafile;
+use crate::version::VersionRange;
+use rusqlite::{params, params_from_iter, Connection};
+#[cfg(feature = "blocking")]
+use rusqlite::{OptionalExtension, Transaction, TransactionBehavior};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Directory of the NVD's CPE match feed.
+pub const NVD_CPE_MATCH_URL: &str = "https://nvd.nist.gov/feeds/json/cpematch/1.0/";
+
+/// File name of the GZipped CPE match feed, relative to ``NVD_CPE_MATCH_URL``.
+pub const CPE_MATCH_FEED: &str = "nvdcpematch-1.0.json.gz";
+
+/// File name of the Metafile of the CPE match feed, relative to ``NVD_CPE_MATCH_URL``.
+pub const CPE_MATCH_METAFILE: &str = "nvdcpematch-1.0.meta";
+
+/// CPE names of the official CPE dictionary matched by the criteria of CPE matches, keyed by
+/// ``criteria_key()``
+pub(crate) type CpeExpansions = HashMap<String, Vec<Cpe>>;
+
+/// The NVD's CPE match feed, listing the CPE names each criteria of the configurations of CVEs
+/// matches.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CpeMatchFeed {
+    pub matches: Vec<CpeMatchCriteria>,
+}
+
+impl CpeMatchFeed {
+    /// Parse the uncompressed JSON of a CPE match feed.
+    pub fn from_slice(json: &[u8]) -> Result<Self, serde_json::Error> {
+        serde_json::from_slice(json)
+    }
+}
+
+/// Criteria of a CPE match, a CPE name along with a range of its versions, and the CPE names it
+/// matches.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CpeMatchCriteria {
+    /// CPE 2.3 name matched, such as ``cpe:2.3:a:openssl:openssl:*:*:*:*:*:*:*:*``.
+    #[serde(rename = "cpe23Uri")]
+    pub cpe23_uri: String,
+
+    #[serde(
+        default,
+        rename = "versionStartIncluding",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub version_start_including: Option<String>,
+
+    #[serde(
+        default,
+        rename = "versionStartExcluding",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub version_start_excluding: Option<String>,
+
+    #[serde(
+        default,
+        rename = "versionEndIncluding",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub version_end_including: Option<String>,
+
+    #[serde(
+        default,
+        rename = "versionEndExcluding",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub version_end_excluding: Option<String>,
+
+    /// CPE names of the dictionary matched, empty when none is known.
+    #[serde(default, rename = "cpe_name")]
+    pub cpe_names: Vec<CpeName>,
+}
+
+/// A CPE name of the official CPE dictionary
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CpeName {
+    #[serde(rename = "cpe23Uri")]
+    pub cpe23_uri: String,
+}
+
+impl CpeMatchCriteria {
+    /// The range of versions matched, unbounded if the criteria has no version range.
+    pub fn range(&self) -> VersionRange {
+        VersionRange {
+            start_including: self.version_start_including.clone(),
+            start_excluding: self.version_start_excluding.clone(),
+            end_including: self.version_end_including.clone(),
+            end_excluding: self.version_end_excluding.clone(),
+        }
+    }
+
+    /// Key the criteria is cached under, see ``criteria_key()``.
+    pub fn key(&self) -> String {
+        criteria_key(&self.cpe23_uri, &self.range())
+    }
+}
+
+/// Key of the criteria of a CPE match, such as a ``cve::CpeMatch`` of a configuration, made of its
+/// CPE name and the bounds of its range of versions, which can't contain spaces.
+///
+/// ## Example:
+/// ```
+/// use nvd_cve::cpematch::criteria_key;
+/// use nvd_cve::version::VersionRange;
+///
+/// let range = VersionRange {
+///     start_including: Some("1.1.1".to_string()),
+///     end_excluding: Some("1.1.1l".to_string()),
+///     ..Default::default()
+/// };
+/// assert_eq!(
+///     criteria_key("cpe:2.3:a:openssl:openssl:*:*:*:*:*:*:*:*", &range),
+///     "cpe:2.3:a:openssl:openssl:*:*:*:*:*:*:*:* 1.1.1 - - 1.1.1l"
+/// );
+/// ```
+pub fn criteria_key(cpe23_uri: &str, range: &VersionRange) -> String {
+    let bound = |bound: &Option<String>| bound.clone().unwrap_or_else(|| "-".to_string());
+    format!(
+        "{} {} {} {} {}",
+        cpe23_uri,
+        bound(&range.start_including),
+        bound(&range.start_excluding),
+        bound(&range.end_including),
+        bound(&range.end_excluding)
+    )
+}
+
+/// Replace the CPE names cached for the criteria of CPE matches with those of ``feed``, returning
+/// the number of criteria recorded. Criteria whose CPE name doesn't parse are skipped.
+pub fn import_cpe_matches(config: &CacheConfig, feed: &CpeMatchFeed) -> Result<usize, CacheError> {
+    writable_schema(config)?;
+
+    let mut conn = open(config)?;
+    let tx = conn.transaction()?;
+    tx.execute("DELETE FROM cpe_match_criteria", [])?;
+
+    let mut recorded = 0;
+    {
+        let mut stmt = tx.prepare(
+            "INSERT OR REPLACE INTO cpe_match_criteria (criteria, product, names)
+            VALUES (?1, ?2, ?3)",
+        )?;
+        for criteria in &feed.matches {
+            let Ok(cpe) = criteria.cpe23_uri.parse::<Cpe>() else {
+                continue;
+            };
+            let names: Vec<&str> = criteria
+                .cpe_names
+                .iter()
+                .map(|name| name.cpe23_uri.as_str())
+                .collect();
+            stmt.execute(params![
+                criteria.key(),
+                cpe.product.index_key(),
+                serde_json::to_string(&names)?
+            ])?;
+            recorded += 1;
+        }
+    }
+    tx.commit()?;
+
+    match conn.close() {
+        Ok(_) => Ok(recorded),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Sync the CPE match feed from the base URL of ``client``, such as ``NVD_CPE_MATCH_URL``, to the
+/// local cache, returning the number of criteria recorded. The feed is only fetched when its
+/// Metafile changed since the last sync, unless ``force_update`` is set, otherwise nothing is
+/// recorded.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::CacheConfig;
+/// use nvd_cve::client::{BlockingHttpClient, ReqwestBlockingClient};
+/// use nvd_cve::cpematch::{sync_cpe_matches, NVD_CPE_MATCH_URL};
+///
+/// let config = CacheConfig::new();
+/// let client = ReqwestBlockingClient::new(NVD_CPE_MATCH_URL, None, None, None);
+///
+/// let recorded = sync_cpe_matches(&config, &client).unwrap();
+/// println!("Recorded the CPE names of {} criteria", recorded);
+/// ```
+#[cfg(feature = "blocking")]
+pub fn sync_cpe_matches(
+    config: &CacheConfig,
+    client: &ReqwestBlockingClient,
+) -> Result<usize, CacheError> {
+    writable_schema(config)?;
+
+    let metafile = client.get_url(CPE_MATCH_METAFILE)?;
+    let metafile = Metafile::from_string(String::from_utf8_lossy(&metafile).into_owned())?;
+
+    let conn = open(config)?;
+    let synced: Option<String> = conn
+        .query_row("SELECT sha256 FROM cpe_match_criteria_sync", [], |row| {
+            row.get(0)
+        })
+        .optional()?;
+    if let Err((_, error)) = conn.close() {
+        return Err(CacheError::RusqliteError(error));
+    }
+    if !config.force_update && synced.as_ref() == Some(&metafile.sha256) {
+        return Ok(0);
+    }
+
+    let feed = Compression::Gzip.decompress(&client.get_url(CPE_MATCH_FEED)?)?;
+    let recorded = import_cpe_matches(config, &CpeMatchFeed::from_slice(&feed)?)?;
+
+    let mut conn = open(config)?;
+    let tx = Transaction::new(&mut conn, TransactionBehavior::Immediate)?;
+    tx.execute(
+        "INSERT OR REPLACE INTO cpe_match_criteria_sync (id, sha256) VALUES (0, ?1)",
+        [&metafile.sha256],
+    )?;
+    tx.commit()?;
+
+    match conn.close() {
+        Ok(_) => Ok(recorded),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// The CPE names cached for the criteria naming the product of one of ``platforms``. Empty if the
+/// cache has no ``cpe_match_criteria`` table, as read-only caches made by older versions don't,
+/// or if the product of a platform has wildcards.
+pub(crate) fn criteria_expansions(
+    conn: &Connection,
+    platforms: &[Cpe],
+) -> Result<CpeExpansions, CacheError> {
+    let mut tbl_stmt = conn.prepare(
+        "SELECT name FROM sqlite_master where type = 'table' and name = 'cpe_match_criteria'",
+    )?;
+    let synced = tbl_stmt.exists([])?;
+    tbl_stmt.finalize()?;
+
+    let mut expansions = CpeExpansions::new();
+    if !synced
+        || platforms.is_empty()
+        || platforms
+            .iter()
+            .any(|platform| platform.product.has_wildcards())
+    {
+        return Ok(expansions);
+    }
+
+    let mut products: Vec<String> = platforms
+        .iter()
+        .map(|platform| platform.product.index_key())
+        .collect();
+    products.push("*".to_string());
+    products.sort();
+    products.dedup();
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT criteria, names FROM cpe_match_criteria WHERE product IN ({})",
+        vec!["?"; products.len()].join(", ")
+    ))?;
+    let mut rows = stmt.query(params_from_iter(&products))?;
+    while let Some(row) = rows.next()? {
+        let names: Vec<String> = serde_json::from_str(&row.get::<_, String>(1)?)?;
+        expansions.insert(
+            row.get(0)?,
+            names.iter().filter_map(|name| name.parse().ok()).collect(),
+        );
+    }
+    drop(rows);
+    stmt.finalize()?;
+
+    Ok(expansions)
+}
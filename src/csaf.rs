@@ -0,0 +1,658 @@
+use crate::aliases::{insert_aliases, Alias};
+use crate::cache::{bump_generation, open, writable_schema, CacheConfig, CacheError};
+use crate::client::HttpError;
+#[cfg(feature = "blocking")]
+use crate::client::ReqwestBlockingClient;
+use crate::cpe::Cpe;
+use crate::overrides::{upsert_override, CveOverride};
+#[cfg(feature = "blocking")]
+use chrono::{DateTime, Utc};
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+#[cfg(feature = "blocking")]
+use url::Url;
+
+/// Version of the CSAF specification advisories must follow to be ingested.
+pub const CSAF_VERSION: &str = "2.0";
+
+/// Errors related to ingesting CSAF advisories
+#[derive(Debug)]
+pub enum CsafError {
+    CacheError(CacheError),
+    HttpError(HttpError),
+    JsonError(serde_json::Error),
+    /// The document isn't a CSAF 2.0 advisory, or the provider metadata lists no directory of
+    /// advisories.
+    InvalidDocument(String),
+}
+
+impl From<CacheError> for CsafError {
+    fn from(error: CacheError) -> Self {
+        CsafError::CacheError(error)
+    }
+}
+
+impl From<rusqlite::Error> for CsafError {
+    fn from(error: rusqlite::Error) -> Self {
+        CsafError::CacheError(CacheError::RusqliteError(error))
+    }
+}
+
+impl From<HttpError> for CsafError {
+    fn from(error: HttpError) -> Self {
+        CsafError::HttpError(error)
+    }
+}
+
+impl From<serde_json::Error> for CsafError {
+    fn from(error: serde_json::Error) -> Self {
+        CsafError::JsonError(error)
+    }
+}
+
+/// A CSAF 2.0 advisory, reduced to what is ingested: its tracking ID, products and the status
+/// of each product with regard to the vulnerabilities it covers.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Advisory {
+    pub document: AdvisoryDocument,
+
+    #[serde(default)]
+    pub product_tree: ProductTree,
+
+    #[serde(default)]
+    pub vulnerabilities: Vec<Vulnerability>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct AdvisoryDocument {
+    pub category: String,
+    pub csaf_version: String,
+    pub title: String,
+    pub publisher: Publisher,
+    pub tracking: Tracking,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Publisher {
+    pub name: String,
+    pub namespace: String,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Tracking {
+    /// ID of the advisory given by its publisher, e.g. ``RHSA-2023:1234``.
+    pub id: String,
+    pub current_release_date: String,
+    pub status: String,
+    pub version: String,
+}
+
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ProductTree {
+    #[serde(default)]
+    pub branches: Vec<Branch>,
+
+    #[serde(default)]
+    pub full_product_names: Vec<FullProductName>,
+
+    #[serde(default)]
+    pub relationships: Vec<Relationship>,
+}
+
+/// A branch of the product tree, such as a vendor, product family or version, ending with a
+/// product.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Branch {
+    pub category: String,
+    pub name: String,
+
+    #[serde(default)]
+    pub branches: Vec<Branch>,
+
+    pub product: Option<FullProductName>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct FullProductName {
+    pub name: String,
+    pub product_id: String,
+    pub product_identification_helper: Option<ProductIdentificationHelper>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ProductIdentificationHelper {
+    /// CPE name of the product, as a CPE 2.3 formatted string or a CPE URI.
+    pub cpe: Option<String>,
+    pub purl: Option<String>,
+}
+
+/// A product made of two others, such as a package shipped as a component of a distribution.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Relationship {
+    pub category: String,
+    pub full_product_name: FullProductName,
+    pub product_reference: String,
+    pub relates_to_product_reference: String,
+}
+
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Vulnerability {
+    /// CVE ID of the vulnerability, advisories may cover vulnerabilities without one.
+    pub cve: Option<String>,
+
+    /// Other identifiers of the vulnerability, such as the vendor's bug tracker ID.
+    #[serde(default)]
+    pub ids: Vec<VulnerabilityId>,
+
+    #[serde(default)]
+    pub product_status: ProductStatus,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct VulnerabilityId {
+    pub system_name: String,
+    pub text: String,
+}
+
+/// IDs of the products of the product tree in each status.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ProductStatus {
+    #[serde(default)]
+    pub first_affected: Vec<String>,
+    #[serde(default)]
+    pub first_fixed: Vec<String>,
+    #[serde(default)]
+    pub fixed: Vec<String>,
+    #[serde(default)]
+    pub known_affected: Vec<String>,
+    #[serde(default)]
+    pub known_not_affected: Vec<String>,
+    #[serde(default)]
+    pub last_affected: Vec<String>,
+    #[serde(default)]
+    pub recommended: Vec<String>,
+    #[serde(default)]
+    pub under_investigation: Vec<String>,
+}
+
+impl ProductStatus {
+    /// Each product ID along with its status
+    fn products(&self) -> impl Iterator<Item = (VendorStatus, &String)> {
+        [
+            (VendorStatus::FirstAffected, &self.first_affected),
+            (VendorStatus::FirstFixed, &self.first_fixed),
+            (VendorStatus::Fixed, &self.fixed),
+            (VendorStatus::KnownAffected, &self.known_affected),
+            (VendorStatus::KnownNotAffected, &self.known_not_affected),
+            (VendorStatus::LastAffected, &self.last_affected),
+            (VendorStatus::Recommended, &self.recommended),
+            (VendorStatus::UnderInvestigation, &self.under_investigation),
+        ]
+        .into_iter()
+        .flat_map(|(status, ids)| ids.iter().map(move |id| (status, id)))
+    }
+}
+
+/// Status of a product with regard to a vulnerability, as stated by its vendor.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum VendorStatus {
+    FirstAffected,
+    FirstFixed,
+    Fixed,
+    KnownAffected,
+    KnownNotAffected,
+    LastAffected,
+    Recommended,
+    UnderInvestigation,
+}
+
+impl VendorStatus {
+    pub const ALL: [VendorStatus; 8] = [
+        VendorStatus::FirstAffected,
+        VendorStatus::FirstFixed,
+        VendorStatus::Fixed,
+        VendorStatus::KnownAffected,
+        VendorStatus::KnownNotAffected,
+        VendorStatus::LastAffected,
+        VendorStatus::Recommended,
+        VendorStatus::UnderInvestigation,
+    ];
+
+    /// Name of the status, as in CSAF advisories
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VendorStatus::FirstAffected => "first_affected",
+            VendorStatus::FirstFixed => "first_fixed",
+            VendorStatus::Fixed => "fixed",
+            VendorStatus::KnownAffected => "known_affected",
+            VendorStatus::KnownNotAffected => "known_not_affected",
+            VendorStatus::LastAffected => "last_affected",
+            VendorStatus::Recommended => "recommended",
+            VendorStatus::UnderInvestigation => "under_investigation",
+        }
+    }
+
+    /// Whether a product in this status is affected, ``None`` when the vendor doesn't say, as for
+    /// products under investigation.
+    pub fn affected(&self) -> Option<bool> {
+        match self {
+            VendorStatus::FirstAffected
+            | VendorStatus::KnownAffected
+            | VendorStatus::LastAffected => Some(true),
+            VendorStatus::FirstFixed | VendorStatus::Fixed | VendorStatus::KnownNotAffected => {
+                Some(false)
+            }
+            VendorStatus::Recommended | VendorStatus::UnderInvestigation => None,
+        }
+    }
+}
+
+impl fmt::Display for VendorStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for VendorStatus {
+    type Err = String;
+
+    fn from_str(status: &str) -> Result<Self, Self::Err> {
+        VendorStatus::ALL
+            .into_iter()
+            .find(|known| known.as_str() == status)
+            .ok_or_else(|| format!("Unknown vendor status: {}", status))
+    }
+}
+
+/// The status of a product with regard to a CVE, as stated by an advisory.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AdvisoryStatus {
+    /// Tracking ID of the advisory.
+    pub advisory: String,
+    pub cve: String,
+    /// Name of the product.
+    pub product: String,
+    pub status: VendorStatus,
+}
+
+impl Advisory {
+    /// Parse a CSAF advisory, failing if it doesn't follow version 2.0 of the specification.
+    pub fn from_json(json: &[u8]) -> Result<Self, CsafError> {
+        let advisory: Advisory = serde_json::from_slice(json)?;
+        if advisory.document.csaf_version != CSAF_VERSION {
+            return Err(CsafError::InvalidDocument(format!(
+                "unsupported CSAF version {}",
+                advisory.document.csaf_version
+            )));
+        }
+        if advisory.document.tracking.id.trim().is_empty() {
+            return Err(CsafError::InvalidDocument(
+                "the advisory has no tracking ID".to_string(),
+            ));
+        }
+        Ok(advisory)
+    }
+
+    /// Tracking ID of the advisory, e.g. ``RHSA-2023:1234``.
+    pub fn id(&self) -> &str {
+        &self.document.tracking.id
+    }
+
+    /// Every product defined by the product tree, by product ID
+    fn products(&self) -> HashMap<&str, &FullProductName> {
+        fn walk<'a>(branches: &'a [Branch], products: &mut HashMap<&'a str, &'a FullProductName>) {
+            for branch in branches {
+                if let Some(product) = &branch.product {
+                    products.insert(&product.product_id, product);
+                }
+                walk(&branch.branches, products);
+            }
+        }
+
+        let tree = &self.product_tree;
+        let mut products = HashMap::new();
+        walk(&tree.branches, &mut products);
+        for product in &tree.full_product_names {
+            products.insert(&product.product_id, product);
+        }
+        for relationship in &tree.relationships {
+            let product = &relationship.full_product_name;
+            products.insert(&product.product_id, product);
+        }
+        products
+    }
+
+    /// IDs of the products run on one of ``platforms``: those whose CPE name matches one of
+    /// them, or is matched by one, and those composed of such a product
+    fn products_run(&self, platforms: &[Cpe]) -> HashSet<&str> {
+        let mut run: HashSet<&str> = self
+            .products()
+            .into_iter()
+            .filter(|(_, product)| {
+                let cpe = product
+                    .product_identification_helper
+                    .as_ref()
+                    .and_then(|helper| helper.cpe.as_deref())
+                    .and_then(|cpe| cpe.parse::<Cpe>().ok());
+                cpe.is_some_and(|cpe| {
+                    platforms
+                        .iter()
+                        .any(|platform| platform.matches(&cpe) || cpe.matches(platform))
+                })
+            })
+            .map(|(id, _)| id)
+            .collect();
+
+        for relationship in &self.product_tree.relationships {
+            if run.contains(relationship.product_reference.as_str())
+                || run.contains(relationship.relates_to_product_reference.as_str())
+            {
+                run.insert(&relationship.full_product_name.product_id);
+            }
+        }
+        run
+    }
+
+    /// Other identifiers of the CVEs covered by the advisory: its own tracking ID, and the IDs
+    /// given to each vulnerability by other systems, sorted.
+    pub fn aliases(&self) -> Vec<Alias> {
+        let mut aliases = vec![];
+        for vulnerability in &self.vulnerabilities {
+            let Some(cve) = &vulnerability.cve else {
+                continue;
+            };
+            aliases.push(Alias {
+                alias: self.id().to_string(),
+                cve: cve.clone(),
+                source: self.document.publisher.name.clone(),
+            });
+            for id in &vulnerability.ids {
+                aliases.push(Alias {
+                    alias: id.text.clone(),
+                    cve: cve.clone(),
+                    source: id.system_name.clone(),
+                });
+            }
+        }
+        aliases.sort();
+        aliases.dedup();
+        aliases
+    }
+
+    /// Status of the products run on one of ``platforms`` with regard to each CVE the advisory
+    /// covers, sorted. Without platforms, the status of every product is returned.
+    pub fn statuses(&self, platforms: &[Cpe]) -> Vec<AdvisoryStatus> {
+        let products = self.products();
+        let run = self.products_run(platforms);
+
+        let mut statuses = vec![];
+        for vulnerability in &self.vulnerabilities {
+            let Some(cve) = &vulnerability.cve else {
+                continue;
+            };
+            for (status, id) in vulnerability.product_status.products() {
+                if !platforms.is_empty() && !run.contains(id.as_str()) {
+                    continue;
+                }
+                statuses.push(AdvisoryStatus {
+                    advisory: self.id().to_string(),
+                    cve: cve.clone(),
+                    product: products
+                        .get(id.as_str())
+                        .map_or(id.clone(), |product| product.name.clone()),
+                    status,
+                });
+            }
+        }
+        statuses.sort();
+        statuses.dedup();
+        statuses
+    }
+}
+
+/// What ``ingest_advisories`` recorded in the local cache.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IngestReport {
+    /// Number of aliases recorded.
+    pub aliases: usize,
+
+    /// Number of product statuses recorded.
+    pub statuses: usize,
+
+    /// CVEs whose override was set or cleared from the statuses of the advisories, sorted.
+    pub overrides: Vec<String>,
+
+    /// CVEs whose override was set by an analyst and left as it was, sorted.
+    pub kept: Vec<String>,
+}
+
+/// Record the aliases of the CVEs covered by ``advisories``, and the status of the products run on
+/// one of ``platforms`` (or of every product, without platforms) with regard to each CVE.
+///
+/// The statuses of an advisory replace those recorded from an earlier release of it. Each CVE
+/// with a vendor-stated status is then overridden as affected when any product is, or else as not
+/// affected when a product is fixed or not affected, with a note listing the statuses. Overrides
+/// set by an analyst are never replaced.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::CacheConfig;
+/// use nvd_cve::cpe::Cpe;
+/// use nvd_cve::csaf::{ingest_advisories, Advisory};
+/// use std::fs;
+///
+/// let config = CacheConfig::new();
+/// let advisory = Advisory::from_json(&fs::read("rhsa-2023_1405.json").unwrap()).unwrap();
+/// let platforms: Vec<Cpe> = vec!["cpe:/o:redhat:enterprise_linux:9".parse().unwrap()];
+///
+/// let report = ingest_advisories(&config, &[advisory], &platforms).unwrap();
+/// println!("Overrode {} CVEs", report.overrides.len());
+/// ```
+pub fn ingest_advisories(
+    config: &CacheConfig,
+    advisories: &[Advisory],
+    platforms: &[Cpe],
+) -> Result<IngestReport, CsafError> {
+    writable_schema(config)?;
+
+    let mut conn = open(config)?;
+    let tx = conn.transaction()?;
+
+    let mut report = IngestReport::default();
+    let mut cves = BTreeSet::new();
+
+    for advisory in advisories {
+        let mut stmt =
+            tx.prepare("SELECT DISTINCT cve FROM advisory_status WHERE advisory = ?1")?;
+        for cve in stmt.query_map([advisory.id()], |row| row.get::<_, String>(0))? {
+            cves.insert(cve?);
+        }
+        stmt.finalize()?;
+        tx.execute(
+            "DELETE FROM advisory_status WHERE advisory = ?1",
+            [advisory.id()],
+        )?;
+
+        let mut stmt = tx.prepare(
+            "INSERT OR IGNORE INTO advisory_status (advisory, cve, product, status)
+            VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for status in advisory.statuses(platforms) {
+            stmt.execute(params![
+                status.advisory,
+                status.cve,
+                status.product,
+                status.status.as_str()
+            ])?;
+            cves.insert(status.cve);
+            report.statuses += 1;
+        }
+        stmt.finalize()?;
+
+        report.aliases += insert_aliases(&tx, &advisory.aliases())?;
+    }
+
+    for cve in cves {
+        let existing: Option<Option<String>> = tx
+            .query_row(
+                "SELECT advisory FROM overrides WHERE namespace = ?1 AND id = ?2",
+                [&config.namespace, &cve],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(None) = existing {
+            report.kept.push(cve);
+            continue;
+        }
+
+        let mut stmt = tx.prepare(
+            "SELECT advisory, product, status FROM advisory_status WHERE cve = ?1
+            ORDER BY advisory, status, product",
+        )?;
+        let rows = stmt
+            .query_map([&cve], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        stmt.finalize()?;
+
+        // Products of each advisory in each status, for the note
+        let mut stated: BTreeMap<String, BTreeMap<VendorStatus, Vec<String>>> = BTreeMap::new();
+        let mut affected = None;
+        for (advisory, product, status) in rows {
+            let Ok(status) = status.parse::<VendorStatus>() else {
+                continue;
+            };
+            affected = match (affected, status.affected()) {
+                (Some(true), _) | (_, Some(true)) => Some(true),
+                (_, Some(false)) => Some(false),
+                (affected, None) => affected,
+            };
+            stated
+                .entry(advisory)
+                .or_default()
+                .entry(status)
+                .or_default()
+                .push(product);
+        }
+
+        match affected {
+            Some(affected) => {
+                let note = stated
+                    .iter()
+                    .map(|(advisory, statuses)| {
+                        let statuses: Vec<String> = statuses
+                            .iter()
+                            .map(|(status, products)| format!("{} {}", status, products.join(", ")))
+                            .collect();
+                        format!("{}: {}", advisory, statuses.join("; "))
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                let cve_override = CveOverride {
+                    affected: Some(affected),
+                    note: Some(note),
+                    advisory: Some(stated.keys().cloned().collect::<Vec<_>>().join(", ")),
+                    ..Default::default()
+                };
+                upsert_override(&tx, config, &cve, &cve_override)?;
+            }
+            None if existing.is_some() => {
+                tx.execute(
+                    "DELETE FROM overrides WHERE namespace = ?1 AND id = ?2",
+                    [&config.namespace, &cve],
+                )?;
+                bump_generation(&tx)?;
+            }
+            None => continue,
+        }
+        report.overrides.push(cve);
+    }
+
+    tx.commit()?;
+
+    match conn.close() {
+        Ok(_) => Ok(report),
+        Err((_, error)) => Err(CsafError::CacheError(CacheError::RusqliteError(error))),
+    }
+}
+
+/// The parts of a CSAF provider metadata document used to find its advisories
+#[cfg(feature = "blocking")]
+#[derive(Deserialize)]
+struct ProviderMetadata {
+    #[serde(default)]
+    distributions: Vec<Distribution>,
+}
+
+#[cfg(feature = "blocking")]
+#[derive(Deserialize)]
+struct Distribution {
+    directory_url: Option<String>,
+}
+
+/// Paths of the advisories listed by the ``changes.csv`` file of a directory of advisories, of
+/// those changed at or after ``since`` if given
+#[cfg(feature = "blocking")]
+fn changed_paths(changes: &str, since: Option<DateTime<Utc>>) -> Result<Vec<String>, CsafError> {
+    let mut paths = vec![];
+    for line in changes.lines().filter(|line| !line.trim().is_empty()) {
+        let invalid = || CsafError::InvalidDocument(format!("invalid changes.csv line: {}", line));
+        let (path, changed) = line.split_once(',').ok_or_else(invalid)?;
+        let changed = DateTime::parse_from_rfc3339(changed.trim().trim_matches('"'))
+            .map_err(|_| invalid())?;
+        if since.is_none_or(|since| changed >= since) {
+            paths.push(path.trim().trim_matches('"').to_string());
+        }
+    }
+    Ok(paths)
+}
+
+/// Fetch the advisories of a CSAF provider from the directories listed by its provider metadata,
+/// e.g. ``https://access.redhat.com/security/data/csaf/v2/provider-metadata.json``, only those
+/// changed at or after ``since`` if given. Directories are read from their ``changes.csv`` file.
+#[cfg(feature = "blocking")]
+pub fn fetch_provider_advisories(
+    client: &ReqwestBlockingClient,
+    provider_metadata_url: &str,
+    since: Option<DateTime<Utc>>,
+) -> Result<Vec<Advisory>, CsafError> {
+    let metadata_url = Url::parse(provider_metadata_url).map_err(HttpError::from)?;
+    let metadata: ProviderMetadata =
+        serde_json::from_slice(&client.get_url(metadata_url.as_str())?)?;
+
+    let directories: Vec<String> = metadata
+        .distributions
+        .into_iter()
+        .filter_map(|distribution| distribution.directory_url)
+        .map(|url| match url.ends_with('/') {
+            true => url,
+            false => format!("{}/", url),
+        })
+        .collect();
+    if directories.is_empty() {
+        return Err(CsafError::InvalidDocument(
+            "the provider metadata lists no directory of advisories".to_string(),
+        ));
+    }
+
+    let mut advisories = vec![];
+    for directory in directories {
+        let directory = metadata_url.join(&directory).map_err(HttpError::from)?;
+        let changes = directory.join("changes.csv").map_err(HttpError::from)?;
+        let changes = client.get_url(changes.as_str())?;
+        for path in changed_paths(&String::from_utf8_lossy(&changes), since)? {
+            let url = directory.join(&path).map_err(HttpError::from)?;
+            advisories.push(Advisory::from_json(&client.get_url(url.as_str())?)?);
+        }
+    }
+    Ok(advisories)
+}
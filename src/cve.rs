@@ -1,8 +1,25 @@
-use crate::client::{BlockingHttpClient, HttpError};
+#[cfg(feature = "blocking")]
+use crate::client::BlockingHttpClient;
+use crate::client::{AsyncHttpClient, HttpError};
+use crate::cpe::Cpe;
+use crate::cvss::{
+    AttackComplexity, AttackVector, ImpactLevel, PrivilegesRequired, Scope, Severity,
+    UserInteraction,
+};
+use crate::datetime::parse_datetime;
+use crate::exploit::{ExploitRef, ExploitSource};
+use crate::feed::FeedName;
+use crate::version::VersionRange;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
 use serde_json::Value;
+use std::cmp::Ordering;
+use std::num::NonZeroUsize;
+use std::{fmt, thread};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct CveMeta {
     #[serde(alias = "ID")]
     pub id: String,
@@ -11,50 +28,349 @@ pub struct CveMeta {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct ProblemTypeData {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_fields::strings))]
     pub description: Vec<Value>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct ProblemType {
     #[serde(alias = "problemtype_data")]
     pub problem_type_data: Vec<ProblemTypeData>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct References {
     pub reference_data: Vec<ReferenceData>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct ReferenceData {
     pub url: String,
     pub name: String,
 
     #[serde(alias = "refsource")]
     pub ref_source: String,
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_fields::strings))]
     pub tags: Vec<Value>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Description {
     pub description_data: Vec<DescriptionData>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct DescriptionData {
     pub lang: String,
     pub value: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Configuration {
     #[serde(alias = "CVE_data_version")]
     pub cve_data_version: String,
-    pub nodes: Vec<Value>,
+    pub nodes: Vec<Node>,
+}
+
+impl Configuration {
+    /// The CPE matches of every node of the configurations, children included, in the order they
+    /// are listed.
+    pub fn cpe_matches(&self) -> Vec<&CpeMatch> {
+        let mut cpe_matches = vec![];
+        let mut nodes: Vec<&Node> = self.nodes.iter().rev().collect();
+        while let Some(node) = nodes.pop() {
+            cpe_matches.extend(&node.cpe_match);
+            nodes.extend(node.children.iter().rev());
+        }
+        cpe_matches
+    }
+}
+
+/// How the CPE matches and children of a configuration node combine.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Operator {
+    /// The node applies if all of them do, e.g. both a vulnerable firmware and the hardware
+    /// running it.
+    And,
+    /// The node applies if any of them does.
+    #[default]
+    Or,
+}
+
+/// A node of the configurations of a CVE, combining CPE matches and child nodes.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct Node {
+    #[serde(default)]
+    pub operator: Operator,
+
+    /// If ``true`` the node applies when what it lists doesn't.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub negate: bool,
+
+    #[serde(default)]
+    pub children: Vec<Node>,
+
+    #[serde(default)]
+    pub cpe_match: Vec<CpeMatch>,
+}
+
+/// A CPE name matched by a configuration node, along with the range of versions it matches.
+/// CPE matches are vulnerable by default.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct CpeMatch {
+    /// Whether the CPEs matched are vulnerable, rather than only a platform a vulnerable CPE must
+    /// run on.
+    #[serde(default = "default_vulnerable")]
+    pub vulnerable: bool,
+
+    /// CPE 2.3 name matched, such as ``cpe:2.3:a:apache:log4j:*:*:*:*:*:*:*:*``.
+    #[serde(alias = "cpe23Uri")]
+    pub cpe23_uri: String,
+
+    #[serde(
+        default,
+        alias = "versionStartIncluding",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub version_start_including: Option<String>,
+
+    #[serde(
+        default,
+        alias = "versionStartExcluding",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub version_start_excluding: Option<String>,
+
+    #[serde(
+        default,
+        alias = "versionEndIncluding",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub version_end_including: Option<String>,
+
+    #[serde(
+        default,
+        alias = "versionEndExcluding",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub version_end_excluding: Option<String>,
+
+    /// CPE names of the dictionary matched, listed by the NVD for some CPE matches.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_fields::strings))]
+    pub cpe_name: Vec<Value>,
+}
+
+fn default_vulnerable() -> bool {
+    true
+}
+
+impl Default for CpeMatch {
+    fn default() -> Self {
+        Self {
+            vulnerable: default_vulnerable(),
+            cpe23_uri: String::new(),
+            version_start_including: None,
+            version_start_excluding: None,
+            version_end_including: None,
+            version_end_excluding: None,
+            cpe_name: vec![],
+        }
+    }
+}
+
+impl CpeMatch {
+    /// The CPE name matched, ``None`` if it isn't a valid CPE 2.3 name.
+    pub fn cpe(&self) -> Option<Cpe> {
+        self.cpe23_uri.parse().ok()
+    }
+
+    /// The range of versions matched, unbounded if the CPE match has no version range.
+    pub fn range(&self) -> VersionRange {
+        VersionRange {
+            start_including: self.version_start_including.clone(),
+            start_excluding: self.version_start_excluding.clone(),
+            end_including: self.version_end_including.clone(),
+            end_excluding: self.version_end_excluding.clone(),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct CvssV3 {
+    pub version: String,
+
+    #[serde(alias = "vectorString")]
+    pub vector_string: String,
+
+    #[serde(alias = "attackVector")]
+    pub attack_vector: AttackVector,
+
+    #[serde(alias = "attackComplexity")]
+    pub attack_complexity: AttackComplexity,
+
+    #[serde(alias = "privilegesRequired")]
+    pub privileges_required: PrivilegesRequired,
+
+    #[serde(alias = "userInteraction")]
+    pub user_interaction: UserInteraction,
+
+    pub scope: Scope,
+
+    #[serde(alias = "confidentialityImpact")]
+    pub confidentiality_impact: ImpactLevel,
+
+    #[serde(alias = "integrityImpact")]
+    pub integrity_impact: ImpactLevel,
+
+    #[serde(alias = "availabilityImpact")]
+    pub availability_impact: ImpactLevel,
+
+    #[serde(alias = "baseScore")]
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_fields::score))]
+    pub base_score: f64,
+
+    #[serde(alias = "baseSeverity")]
+    pub base_severity: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct BaseMetricV3 {
+    #[serde(alias = "cvssV3")]
+    pub cvss_v3: CvssV3,
+
+    #[serde(alias = "exploitabilityScore")]
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_fields::score))]
+    pub exploitability_score: f64,
+
+    #[serde(alias = "impactScore")]
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_fields::score))]
+    pub impact_score: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct CvssV2 {
+    pub version: String,
+
+    #[serde(alias = "vectorString")]
+    pub vector_string: String,
+
+    #[serde(alias = "accessVector")]
+    pub access_vector: String,
+
+    #[serde(alias = "accessComplexity")]
+    pub access_complexity: String,
+
+    pub authentication: String,
+
+    #[serde(alias = "confidentialityImpact")]
+    pub confidentiality_impact: String,
+
+    #[serde(alias = "integrityImpact")]
+    pub integrity_impact: String,
+
+    #[serde(alias = "availabilityImpact")]
+    pub availability_impact: String,
+
+    #[serde(alias = "baseScore")]
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_fields::score))]
+    pub base_score: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct BaseMetricV2 {
+    #[serde(alias = "cvssV2")]
+    pub cvss_v2: CvssV2,
+
+    /// Severity rated by the NVD, rated from the base score by ``CveContainer::severity()`` when
+    /// missing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub severity: Option<String>,
+
+    #[serde(alias = "exploitabilityScore")]
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_fields::score))]
+    pub exploitability_score: f64,
+
+    #[serde(alias = "impactScore")]
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_fields::score))]
+    pub impact_score: f64,
+
+    #[serde(
+        default,
+        alias = "acInsufInfo",
+        alias = "acInsufficientInfo",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub ac_insuf_info: Option<bool>,
+
+    #[serde(default, alias = "obtainAllPrivilege")]
+    pub obtain_all_privilege: bool,
+
+    #[serde(default, alias = "obtainUserPrivilege")]
+    pub obtain_user_privilege: bool,
+
+    #[serde(default, alias = "obtainOtherPrivilege")]
+    pub obtain_other_privilege: bool,
+
+    #[serde(
+        default,
+        alias = "userInteractionRequired",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub user_interaction_required: Option<bool>,
+}
+
+/// CVSS metrics of a CVE, as scored by the NVD. Either version is ``None`` when the CVE wasn't
+/// scored with it, or when its metrics can't be parsed.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct Impact {
+    #[serde(
+        default,
+        alias = "baseMetricV3",
+        deserialize_with = "ignore_invalid",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub base_metric_v3: Option<BaseMetricV3>,
+
+    #[serde(
+        default,
+        alias = "baseMetricV2",
+        deserialize_with = "ignore_invalid",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub base_metric_v2: Option<BaseMetricV2>,
+}
+
+/// Deserialize metrics, or ``None`` if they are invalid, so that a single malformed metric doesn't
+/// fail the parsing of a whole feed
+fn ignore_invalid<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: serde::de::DeserializeOwned,
+{
+    let value = Value::deserialize(deserializer)?;
+    Ok(serde_json::from_value(value).ok())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Cve {
     pub data_type: String,
     pub data_format: String,
@@ -70,20 +386,253 @@ pub struct Cve {
     pub description: Description,
 }
 
+impl Cve {
+    /// The references of the CVE that link to a public exploit of it, such as an Exploit Database
+    /// entry or a Metasploit module, in the order they are listed and without duplicates.
+    pub fn exploit_refs(&self) -> Vec<ExploitRef> {
+        let mut exploits: Vec<ExploitRef> = vec![];
+        for reference in &self.references.reference_data {
+            if let Some(source) = ExploitSource::of_url(&reference.url) {
+                if !exploits.iter().any(|exploit| exploit.url == reference.url) {
+                    exploits.push(ExploitRef {
+                        source,
+                        url: reference.url.clone(),
+                    });
+                }
+            }
+        }
+        exploits
+    }
+
+    /// IDs of the weaknesses of the CVE, such as ``CWE-79``, in the order they are listed and
+    /// without duplicates. The ``NVD-CWE-Other`` and ``NVD-CWE-noinfo`` placeholders of CVEs whose
+    /// weakness isn't in the CWE list, or isn't known, are left out.
+    pub fn cwe_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = vec![];
+        for data in &self.problem_type.problem_type_data {
+            for description in &data.description {
+                let Some(id) = description.get("value").and_then(Value::as_str) else {
+                    continue;
+                };
+                if id.starts_with("CWE-") && !ids.iter().any(|known| known == id) {
+                    ids.push(id.to_string());
+                }
+            }
+        }
+        ids
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(try_from = "RawCveContainer")]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct CveContainer {
     pub cve: Cve,
     pub configurations: Configuration,
-    pub impact: Value,
+    pub impact: Impact,
+    pub published_date: String,
+    pub last_modified_date: String,
+
+    /// The ``configurations`` JSON as it was parsed, with the field names of the feed, ``None``
+    /// for containers that weren't parsed. The local cache stores it rather than
+    /// ``configurations``.
+    #[serde(skip)]
+    #[cfg_attr(feature = "arbitrary", arbitrary(default))]
+    pub raw_configurations: Option<Value>,
+
+    /// The ``impact`` JSON as it was parsed, see ``raw_configurations``.
+    #[serde(skip)]
+    #[cfg_attr(feature = "arbitrary", arbitrary(default))]
+    pub raw_impact: Option<Value>,
+}
+
+/// A ``CveContainer`` whose configurations and impact are left unparsed, so that their JSON is
+/// kept along with them.
+#[derive(Deserialize)]
+struct RawCveContainer {
+    cve: Cve,
+    configurations: Value,
+    #[serde(default)]
+    impact: Option<Value>,
 
     #[serde(alias = "publishedDate")]
-    pub published_date: String,
+    published_date: String,
 
     #[serde(alias = "lastModifiedDate")]
-    pub last_modified_date: String,
+    last_modified_date: String,
+}
+
+impl TryFrom<RawCveContainer> for CveContainer {
+    type Error = serde_json::Error;
+
+    fn try_from(raw: RawCveContainer) -> Result<Self, Self::Error> {
+        Ok(CveContainer {
+            cve: raw.cve,
+            configurations: Configuration::deserialize(&raw.configurations)?,
+            impact: match &raw.impact {
+                Some(impact) => Impact::deserialize(impact)?,
+                None => Impact::default(),
+            },
+            published_date: raw.published_date,
+            last_modified_date: raw.last_modified_date,
+            raw_configurations: Some(raw.configurations),
+            raw_impact: raw.impact,
+        })
+    }
+}
+
+impl CveContainer {
+    /// Date and time the CVE was published, ``None`` if its ``published_date`` doesn't parse, see
+    /// ``datetime::parse_datetime()``.
+    pub fn published(&self) -> Option<DateTime<Utc>> {
+        parse_datetime(&self.published_date).ok()
+    }
+
+    /// Date and time the CVE was last modified, ``None`` if its ``last_modified_date`` doesn't
+    /// parse, see ``datetime::parse_datetime()``.
+    ///
+    /// ## Example:
+    /// ```
+    /// use nvd_cve::cve::CveFeed;
+    ///
+    /// let json = std::fs::read("./tests/files/nvdcve-1.1-sample.json").unwrap();
+    /// let feed = CveFeed::from_slice_parallel(&json, 1).unwrap();
+    /// let log4shell = feed.find("CVE-2021-44228").unwrap();
+    ///
+    /// assert!(log4shell.last_modified() > log4shell.published());
+    /// ```
+    pub fn last_modified(&self) -> Option<DateTime<Utc>> {
+        parse_datetime(&self.last_modified_date).ok()
+    }
+
+    /// The CVSS v3 metrics of the CVE, if it has been scored with v3.
+    #[deprecated(note = "use impact.base_metric_v3")]
+    pub fn base_metric_v3(&self) -> Option<BaseMetricV3> {
+        self.impact.base_metric_v3.clone()
+    }
+
+    /// Severity and base score of the CVE, from its CVSS v3 metrics or else its CVSS v2 metrics.
+    /// Severities missing from the metrics are rated from the base score.
+    pub fn severity(&self) -> Option<(Severity, f64)> {
+        if let Some(metric) = &self.impact.base_metric_v3 {
+            let score = metric.cvss_v3.base_score;
+            let severity = metric.cvss_v3.base_severity.parse();
+            return Some((severity.unwrap_or(Severity::from_v3_score(score)), score));
+        }
+        let metric = self.impact.base_metric_v2.as_ref()?;
+        let score = metric.cvss_v2.base_score;
+        let severity = metric
+            .severity
+            .as_deref()
+            .and_then(|severity| severity.parse().ok());
+        Some((severity.unwrap_or(Severity::from_v2_score(score)), score))
+    }
+}
+
+/// Summary of a CVE as listed by searches of the local cache.
+///
+/// Summaries are ordered by their CVE ID. The severity, score and publication date are only known
+/// for CVEs synced since they were added to the cache, ``None`` otherwise.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CveSummary {
+    /// ID of the CVE, such as ``CVE-2021-44228``.
+    pub id: String,
+
+    /// English description of the CVE.
+    pub description: String,
+
+    /// CVSS v3 base severity, or the CVSS v2 severity for CVEs that were never scored with v3.
+    pub severity: Option<Severity>,
+
+    /// CVSS v3 base score, or the CVSS v2 base score for CVEs that were never scored with v3.
+    pub score: Option<f64>,
+
+    /// Date and time the CVE was published, as cached, e.g. ``2021-12-10T10:15:00Z``.
+    pub published: Option<String>,
+
+    /// Whether the CVE is listed in CISA's Known Exploited Vulnerabilities catalog, as last synced
+    /// with ``kev::sync_kev()``.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub known_exploited: bool,
+
+    /// EPSS probability of the CVE being exploited in the next 30 days, as last synced with
+    /// ``epss::sync_epss()``, ``None`` if it wasn't scored.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub epss: Option<f64>,
+
+    /// Severity set by the local override of the CVE in the namespace searched, which takes
+    /// precedence over the ``severity`` of the feed, ``None`` if no override sets one.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub override_severity: Option<Severity>,
+}
+
+impl CveSummary {
+    /// Severity of the CVE as assessed locally: the ``override_severity`` if any, otherwise the
+    /// ``severity`` of the feed.
+    pub fn assessed_severity(&self) -> Option<Severity> {
+        self.override_severity.or(self.severity)
+    }
+}
+
+impl Ord for CveSummary {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.id
+            .cmp(&other.id)
+            .then_with(|| self.published.cmp(&other.published))
+            .then_with(|| self.severity.cmp(&other.severity))
+            .then_with(|| match (self.score, other.score) {
+                (Some(score), Some(other)) => score.total_cmp(&other),
+                (score, other) => score.is_some().cmp(&other.is_some()),
+            })
+            .then_with(|| self.description.cmp(&other.description))
+            .then_with(|| self.known_exploited.cmp(&other.known_exploited))
+            .then_with(|| match (self.epss, other.epss) {
+                (Some(epss), Some(other)) => epss.total_cmp(&other),
+                (epss, other) => epss.is_some().cmp(&other.is_some()),
+            })
+            .then_with(|| self.override_severity.cmp(&other.override_severity))
+    }
+}
+
+impl PartialOrd for CveSummary {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for CveSummary {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for CveSummary {}
+
+/// One line per CVE: its ID, assessed severity, score, publication date and description, with
+/// ``-`` for anything unknown.
+impl fmt::Display for CveSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<16} {:<8} {:>4} {:<10} {}",
+            self.id,
+            self.assessed_severity()
+                .map_or("-", |severity| severity.as_str()),
+            self.score
+                .map(|score| format!("{:.1}", score))
+                .unwrap_or_else(|| "-".to_string()),
+            self.published
+                .as_deref()
+                .map(|published| published.get(..10).unwrap_or(published))
+                .unwrap_or("-"),
+            self.description
+        )
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct CveFeed {
     #[serde(alias = "CVE_data_type")]
     pub cve_data_type: String,
@@ -104,6 +653,28 @@ pub struct CveFeed {
     pub cve_items: Vec<CveContainer>,
 }
 
+/// A ``CveFeed`` whose items are left unparsed, so they can be parsed in parallel.
+#[derive(Deserialize)]
+struct RawCveFeed<'a> {
+    #[serde(alias = "CVE_data_type")]
+    cve_data_type: String,
+
+    #[serde(alias = "CVE_data_format")]
+    cve_data_format: String,
+
+    #[serde(alias = "CVE_data_version")]
+    cve_data_version: String,
+
+    #[serde(alias = "CVE_data_numberOfCVEs")]
+    cve_data_number_of_cves: String,
+
+    #[serde(alias = "CVE_data_timestamp")]
+    cve_data_timestamp: String,
+
+    #[serde(borrow, alias = "CVE_Items")]
+    cve_items: Vec<&'a RawValue>,
+}
+
 /// Errors related to parsing a CVE Feed
 #[derive(Debug)]
 pub enum CveFeedError {
@@ -111,10 +682,96 @@ pub enum CveFeedError {
 }
 
 impl CveFeed {
+    /// Parse a JSON feed, splitting the parsing of its items across ``threads`` threads, or one
+    /// thread per available CPU when ``threads`` is ``0``. Items keep the order of the feed.
+    ///
+    /// ## Example:
+    /// ```no_run
+    /// use nvd_cve::cve::CveFeed;
+    ///
+    /// let json = std::fs::read("nvdcve-1.1-2021.json").unwrap();
+    /// let feed = CveFeed::from_slice_parallel(&json, 0).unwrap();
+    /// println!("{} CVEs", feed.cve_items.len());
+    /// ```
+    pub fn from_slice_parallel(json: &[u8], threads: usize) -> serde_json::Result<Self> {
+        let raw: RawCveFeed = serde_json::from_slice(json)?;
+
+        let threads = match threads {
+            0 => thread::available_parallelism().map_or(1, NonZeroUsize::get),
+            threads => threads,
+        };
+        let chunk_size = raw.cve_items.len().div_ceil(threads).max(1);
+
+        let chunks = thread::scope(|scope| {
+            let handles: Vec<_> = raw
+                .cve_items
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .map(|item| serde_json::from_str::<CveContainer>(item.get()))
+                            .collect::<serde_json::Result<Vec<_>>>()
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("Feed parsing thread panicked"))
+                .collect::<serde_json::Result<Vec<_>>>()
+        })?;
+
+        Ok(Self {
+            cve_data_type: raw.cve_data_type,
+            cve_data_format: raw.cve_data_format,
+            cve_data_version: raw.cve_data_version,
+            cve_data_number_of_cves: raw.cve_data_number_of_cves,
+            cve_data_timestamp: raw.cve_data_timestamp,
+            cve_items: chunks.into_iter().flatten().collect(),
+        })
+    }
+
+    /// Returns the item of the CVE with the given ID, which is matched case-insensitively.
+    pub fn find(&self, id: &str) -> Option<&CveContainer> {
+        self.cve_items
+            .iter()
+            .find(|item| item.cve.cve_data_meta.id.eq_ignore_ascii_case(id.trim()))
+    }
+
+    #[cfg(feature = "blocking")]
     pub fn from_blocking_http_client<C: BlockingHttpClient>(
         client: &C,
-        name: &str,
+        name: &FeedName,
     ) -> Result<Self, HttpError> {
         client.get_feed(name)
     }
+
+    pub async fn from_async_http_client<C: AsyncHttpClient>(
+        client: &C,
+        name: &FeedName,
+    ) -> Result<Self, HttpError> {
+        client.get_feed(name).await
+    }
+}
+
+/// Generators for the fields whose types don't implement ``Arbitrary``, producing values like
+/// those found in the NVD JSON feeds.
+#[cfg(feature = "arbitrary")]
+mod arbitrary_fields {
+    use arbitrary::{Arbitrary, Result, Unstructured};
+    use serde_json::Value;
+
+    /// List of JSON strings
+    pub fn strings(u: &mut Unstructured) -> Result<Vec<Value>> {
+        Ok(Vec::<String>::arbitrary(u)?
+            .into_iter()
+            .map(Value::String)
+            .collect())
+    }
+
+    /// Score from 0.0 to 10.0 with a single decimal place
+    pub fn score(u: &mut Unstructured) -> Result<f64> {
+        Ok(f64::from(u.int_in_range(0..=100u8)?) / 10.0)
+    }
 }
@@ -0,0 +1,284 @@
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Errors related to parsing CVSS metric values
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CvssError {
+    /// Value is not one of the known values for the metric
+    UnknownValue(String),
+    /// Vector component is not a known ``METRIC:VALUE`` pair
+    UnknownComponent(String),
+}
+
+impl fmt::Display for CvssError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CvssError::UnknownValue(value) => write!(f, "unknown metric value {:?}", value),
+            CvssError::UnknownComponent(component) => {
+                write!(f, "unknown vector component {:?}", component)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CvssError {}
+
+/// Defines an enumerated CVSS metric using the upper case values found in the NVD JSON feeds and
+/// the abbreviated values used in vector strings.
+macro_rules! cvss_metric {
+    ($(#[$meta:meta])* $name:ident { $($variant:ident => ($value:literal, $abbr:literal)),+ $(,)? }) => {
+        $(#[$meta])*
+        #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+        pub enum $name {
+            $(
+                #[serde(rename = $value)]
+                $variant,
+            )+
+        }
+
+        impl $name {
+            /// Value as it appears in the NVD JSON feeds and the local cache
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $($name::$variant => $value,)+
+                }
+            }
+
+            /// Abbreviated value as it appears in a vector string
+            pub fn abbreviation(&self) -> &'static str {
+                match self {
+                    $($name::$variant => $abbr,)+
+                }
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}", self.as_str())
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = CvssError;
+
+            /// Parse a metric value or its abbreviation, ignoring case
+            fn from_str(value: &str) -> Result<Self, Self::Err> {
+                match value.to_ascii_uppercase().as_str() {
+                    $($value | $abbr => Ok($name::$variant),)+
+                    _ => Err(CvssError::UnknownValue(value.to_string())),
+                }
+            }
+        }
+    };
+}
+
+cvss_metric!(
+    /// CVSS v3 Attack Vector (AV): the context by which exploitation is possible.
+    AttackVector {
+        Network => ("NETWORK", "N"),
+        AdjacentNetwork => ("ADJACENT_NETWORK", "A"),
+        Local => ("LOCAL", "L"),
+        Physical => ("PHYSICAL", "P"),
+    }
+);
+
+cvss_metric!(
+    /// CVSS v3 Attack Complexity (AC): conditions beyond the attacker's control.
+    AttackComplexity {
+        Low => ("LOW", "L"),
+        High => ("HIGH", "H"),
+    }
+);
+
+cvss_metric!(
+    /// CVSS v3 Privileges Required (PR): the level of privileges an attacker must possess.
+    PrivilegesRequired {
+        None => ("NONE", "N"),
+        Low => ("LOW", "L"),
+        High => ("HIGH", "H"),
+    }
+);
+
+cvss_metric!(
+    /// CVSS v3 User Interaction (UI): whether a user other than the attacker must participate.
+    UserInteraction {
+        None => ("NONE", "N"),
+        Required => ("REQUIRED", "R"),
+    }
+);
+
+cvss_metric!(
+    /// CVSS v3 Scope (S): whether a vulnerability impacts resources beyond its security scope.
+    Scope {
+        Unchanged => ("UNCHANGED", "U"),
+        Changed => ("CHANGED", "C"),
+    }
+);
+
+cvss_metric!(
+    /// CVSS v3 Confidentiality (C), Integrity (I) or Availability (A) impact.
+    ImpactLevel {
+        None => ("NONE", "N"),
+        Low => ("LOW", "L"),
+        High => ("HIGH", "H"),
+    }
+);
+
+/// The components of a CVSS v3 vector string. Components missing from a partial vector such as
+/// ``AV:N/AC:L/C:H`` are left as ``None``.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CvssV3Vector {
+    pub attack_vector: Option<AttackVector>,
+    pub attack_complexity: Option<AttackComplexity>,
+    pub privileges_required: Option<PrivilegesRequired>,
+    pub user_interaction: Option<UserInteraction>,
+    pub scope: Option<Scope>,
+    pub confidentiality_impact: Option<ImpactLevel>,
+    pub integrity_impact: Option<ImpactLevel>,
+    pub availability_impact: Option<ImpactLevel>,
+}
+
+impl FromStr for CvssV3Vector {
+    type Err = CvssError;
+
+    /// Parse a full or partial vector string, with or without the ``CVSS:3.x`` prefix
+    fn from_str(vector: &str) -> Result<Self, Self::Err> {
+        let mut parsed = Self::default();
+
+        for component in vector.split('/').filter(|c| !c.is_empty()) {
+            let (metric, value) = component
+                .split_once(':')
+                .ok_or_else(|| CvssError::UnknownComponent(component.to_string()))?;
+
+            match metric.to_ascii_uppercase().as_str() {
+                "CVSS" => (),
+                "AV" => parsed.attack_vector = Some(value.parse()?),
+                "AC" => parsed.attack_complexity = Some(value.parse()?),
+                "PR" => parsed.privileges_required = Some(value.parse()?),
+                "UI" => parsed.user_interaction = Some(value.parse()?),
+                "S" => parsed.scope = Some(value.parse()?),
+                "C" => parsed.confidentiality_impact = Some(value.parse()?),
+                "I" => parsed.integrity_impact = Some(value.parse()?),
+                "A" => parsed.availability_impact = Some(value.parse()?),
+                _ => return Err(CvssError::UnknownComponent(component.to_string())),
+            }
+        }
+
+        Ok(parsed)
+    }
+}
+
+impl fmt::Display for CvssV3Vector {
+    /// Format the components that are set as a vector string without the ``CVSS:3.x`` prefix
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let components = [
+            ("AV", self.attack_vector.map(|v| v.abbreviation())),
+            ("AC", self.attack_complexity.map(|v| v.abbreviation())),
+            ("PR", self.privileges_required.map(|v| v.abbreviation())),
+            ("UI", self.user_interaction.map(|v| v.abbreviation())),
+            ("S", self.scope.map(|v| v.abbreviation())),
+            ("C", self.confidentiality_impact.map(|v| v.abbreviation())),
+            ("I", self.integrity_impact.map(|v| v.abbreviation())),
+            ("A", self.availability_impact.map(|v| v.abbreviation())),
+        ];
+        let formatted: Vec<String> = components
+            .iter()
+            .filter_map(|(metric, value)| value.map(|value| format!("{}:{}", metric, value)))
+            .collect();
+        write!(f, "{}", formatted.join("/"))
+    }
+}
+
+/// Qualitative severity of a CVE, ordered from ``None`` to ``Critical``. CVSS v2 has no ``None``
+/// or ``Critical`` severities, its scores are rated ``Low``, ``Medium`` or ``High``.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Severity {
+    None,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    /// Every severity, from lowest to highest
+    pub const ALL: [Severity; 5] = [
+        Severity::None,
+        Severity::Low,
+        Severity::Medium,
+        Severity::High,
+        Severity::Critical,
+    ];
+
+    /// Value as it appears in the NVD JSON feeds and the local cache
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::None => "NONE",
+            Severity::Low => "LOW",
+            Severity::Medium => "MEDIUM",
+            Severity::High => "HIGH",
+            Severity::Critical => "CRITICAL",
+        }
+    }
+
+    /// Rate a CVSS v3 base score: ``0.0`` is ``None``, up to ``3.9`` ``Low``, up to ``6.9``
+    /// ``Medium``, up to ``8.9`` ``High`` and above ``Critical``
+    pub fn from_v3_score(score: f64) -> Self {
+        match score {
+            score if score >= 9.0 => Severity::Critical,
+            score if score >= 7.0 => Severity::High,
+            score if score >= 4.0 => Severity::Medium,
+            score if score > 0.0 => Severity::Low,
+            _ => Severity::None,
+        }
+    }
+
+    /// Rate a CVSS v2 base score: up to ``3.9`` is ``Low``, up to ``6.9`` ``Medium`` and above
+    /// ``High``
+    pub fn from_v2_score(score: f64) -> Self {
+        match score {
+            score if score >= 7.0 => Severity::High,
+            score if score >= 4.0 => Severity::Medium,
+            _ => Severity::Low,
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Severity {
+    type Err = CvssError;
+
+    /// Parse a severity, ignoring case
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Severity::ALL
+            .into_iter()
+            .find(|severity| severity.as_str().eq_ignore_ascii_case(value))
+            .ok_or_else(|| CvssError::UnknownValue(value.to_string()))
+    }
+}
+
+/// Severities are stored as text in the local cache
+impl ToSql for Severity {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl FromSql for Severity {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|error| FromSqlError::Other(Box::new(error)))
+    }
+}
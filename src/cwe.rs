@@ -0,0 +1,246 @@
+use crate::cache::{open, readable_schema, writable_schema, CacheConfig, CacheError};
+use crate::client::HttpError;
+#[cfg(feature = "blocking")]
+use crate::client::ReqwestBlockingClient;
+use crate::cpedict::attribute;
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "blocking")]
+use std::io::{Cursor, Read};
+
+/// Directory of MITRE's CWE list.
+pub const MITRE_CWE_URL: &str = "https://cwe.mitre.org/data/xml/";
+
+/// File name of the zip archive of the latest CWE list, relative to ``MITRE_CWE_URL``.
+pub const CWE_LIST: &str = "cwec_latest.xml.zip";
+
+/// Errors related to syncing the CWE list
+#[derive(Debug)]
+pub enum CweError {
+    CacheError(CacheError),
+    HttpError(HttpError),
+    /// The CWE list isn't a zip archive holding an XML file.
+    ArchiveError(zip::result::ZipError),
+}
+
+impl From<CacheError> for CweError {
+    fn from(error: CacheError) -> Self {
+        CweError::CacheError(error)
+    }
+}
+
+impl From<HttpError> for CweError {
+    fn from(error: HttpError) -> Self {
+        CweError::HttpError(error)
+    }
+}
+
+impl From<zip::result::ZipError> for CweError {
+    fn from(error: zip::result::ZipError) -> Self {
+        CweError::ArchiveError(error)
+    }
+}
+
+/// A weakness of the CWE list, or a category grouping weaknesses, which CVEs can both list.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Weakness {
+    /// ID of the weakness, such as ``CWE-79``.
+    pub id: String,
+
+    /// Name of the weakness, ``None`` if the CWE list wasn't synced or doesn't list it.
+    pub name: Option<String>,
+}
+
+/// The ID of a weakness as listed by CVEs, such as ``CWE-79``, given with or without its ``CWE-``
+/// prefix in any case.
+///
+/// ## Example:
+/// ```
+/// use nvd_cve::cwe::cwe_id;
+///
+/// assert_eq!(cwe_id("79"), "CWE-79");
+/// assert_eq!(cwe_id(" cwe-79"), "CWE-79");
+/// ```
+pub fn cwe_id(id: &str) -> String {
+    let id = id.trim();
+    match id.get(..4) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("CWE-") => format!("CWE-{}", &id[4..]),
+        _ => format!("CWE-{}", id),
+    }
+}
+
+/// Parse the weaknesses and categories of the XML of the CWE list, along with their names.
+///
+/// ## Example:
+/// ```
+/// use nvd_cve::cwe::parse_cwe_list;
+///
+/// let xml = r#"<Weakness_Catalog><Weaknesses>
+///     <Weakness ID="79" Name="Improper Neutralization of Input During Web Page Generation ('Cross-site Scripting')" Abstraction="Base">
+///     </Weakness>
+/// </Weaknesses></Weakness_Catalog>"#;
+/// let weaknesses = parse_cwe_list(xml);
+///
+/// assert_eq!(weaknesses[0].id, "CWE-79");
+/// assert!(weaknesses[0].name.as_ref().unwrap().ends_with("('Cross-site Scripting')"));
+/// ```
+pub fn parse_cwe_list(xml: &str) -> Vec<Weakness> {
+    let mut weaknesses = vec![];
+    for element in xml.split('<').skip(1) {
+        let (tag, attributes) = element
+            .split_once(char::is_whitespace)
+            .unwrap_or((element, ""));
+        if tag != "Weakness" && tag != "Category" {
+            continue;
+        }
+        let attributes = attributes.split('>').next().unwrap_or_default();
+        if let (Some(id), Some(name)) = (attribute(attributes, "ID"), attribute(attributes, "Name"))
+        {
+            weaknesses.push(Weakness {
+                id: cwe_id(&id),
+                name: Some(name),
+            });
+        }
+    }
+    weaknesses
+}
+
+/// Replace the CWE list cached with ``weaknesses``, returning the number of weaknesses named.
+pub fn import_cwes(config: &CacheConfig, weaknesses: &[Weakness]) -> Result<usize, CacheError> {
+    writable_schema(config)?;
+
+    let mut conn = open(config)?;
+    let tx = conn.transaction()?;
+    tx.execute("DELETE FROM cwe", [])?;
+    let mut named = 0;
+    {
+        let mut stmt = tx.prepare("INSERT OR REPLACE INTO cwe (id, name) VALUES (?1, ?2)")?;
+        for weakness in weaknesses {
+            if let Some(name) = &weakness.name {
+                stmt.execute(params![weakness.id, name])?;
+                named += 1;
+            }
+        }
+    }
+    tx.commit()?;
+
+    match conn.close() {
+        Ok(_) => Ok(named),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Sync the CWE list from the base URL of ``client``, such as ``MITRE_CWE_URL``, to the local
+/// cache, returning the number of weaknesses named. The weaknesses of CVEs are known without it,
+/// the list only names them.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::CacheConfig;
+/// use nvd_cve::client::{BlockingHttpClient, ReqwestBlockingClient};
+/// use nvd_cve::cwe::{sync_cwes, MITRE_CWE_URL};
+///
+/// let config = CacheConfig::new();
+/// let client = ReqwestBlockingClient::new(MITRE_CWE_URL, None, None, None);
+///
+/// let named = sync_cwes(&config, &client).unwrap();
+/// println!("Synced the names of {} weaknesses", named);
+/// ```
+#[cfg(feature = "blocking")]
+pub fn sync_cwes(config: &CacheConfig, client: &ReqwestBlockingClient) -> Result<usize, CweError> {
+    let archive = client.get_url(CWE_LIST)?;
+    let mut archive = zip::ZipArchive::new(Cursor::new(archive))?;
+
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+        if !file.is_file() || !file.name().ends_with(".xml") {
+            continue;
+        }
+        let mut xml = String::new();
+        file.read_to_string(&mut xml)
+            .map_err(|error| CweError::ArchiveError(error.into()))?;
+        return Ok(import_cwes(config, &parse_cwe_list(&xml))?);
+    }
+    Err(CweError::ArchiveError(zip::result::ZipError::FileNotFound))
+}
+
+/// Returns the weaknesses listed by the problem types of a cached CVE, named if the CWE list was
+/// synced with ``sync_cwes()``.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::CacheConfig;
+/// use nvd_cve::cwe::get_weaknesses;
+///
+/// let config = CacheConfig::new();
+///
+/// for weakness in get_weaknesses(&config, "CVE-2021-44228").unwrap() {
+///     println!("{}: {}", weakness.id, weakness.name.unwrap_or_default());
+/// }
+/// ```
+pub fn get_weaknesses(config: &CacheConfig, id: &str) -> Result<Vec<Weakness>, CacheError> {
+    readable_schema(config)?;
+
+    let conn = open(config)?;
+
+    // Read-only caches made by older versions have no weaknesses
+    let mut tbl_stmt =
+        conn.prepare("SELECT name FROM sqlite_master where type = 'table' and name = ?1")?;
+    let indexed = tbl_stmt.exists(["cve_cwe"])?;
+    let named = tbl_stmt.exists(["cwe"])?;
+    tbl_stmt.finalize()?;
+
+    let mut weaknesses = vec![];
+    if indexed {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT cwe, {} FROM cve_cwe WHERE cve = ?1 ORDER BY rowid",
+            if named {
+                "(SELECT name FROM cwe WHERE cwe.id = cve_cwe.cwe)"
+            } else {
+                "NULL"
+            }
+        ))?;
+        let rows = stmt.query_map([id], |row| {
+            Ok(Weakness {
+                id: row.get(0)?,
+                name: row.get(1)?,
+            })
+        })?;
+        for weakness in rows {
+            weaknesses.push(weakness?);
+        }
+        stmt.finalize()?;
+    }
+
+    match conn.close() {
+        Ok(_) => Ok(weaknesses),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Returns the name of a weakness, such as ``CWE-79`` or only ``79``, from the CWE list synced
+/// with ``sync_cwes()``, ``None`` if it wasn't synced or doesn't list it.
+pub fn get_cwe_name(config: &CacheConfig, id: &str) -> Result<Option<String>, CacheError> {
+    readable_schema(config)?;
+
+    let conn = open(config)?;
+
+    let mut tbl_stmt =
+        conn.prepare("SELECT name FROM sqlite_master where type = 'table' and name = 'cwe'")?;
+    let synced = tbl_stmt.exists([])?;
+    tbl_stmt.finalize()?;
+
+    let name = if synced {
+        conn.query_row("SELECT name FROM cwe WHERE id = ?1", [cwe_id(id)], |row| {
+            row.get(0)
+        })
+        .optional()?
+    } else {
+        None
+    };
+
+    match conn.close() {
+        Ok(_) => Ok(name),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
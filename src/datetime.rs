@@ -0,0 +1,55 @@
+use chrono::{DateTime, NaiveDateTime, ParseError, SecondsFormat, Utc};
+
+/// Formats of the dates in UTC: with minutes only, as the ``publishedDate``, ``lastModifiedDate``
+/// and ``CVE_data_timestamp`` of feeds, e.g. ``2021-12-10T10:15Z``, or without an offset, as stored
+/// in the local cache by older versions and given by the NVD's APIs with fractional seconds
+const UTC_FORMATS: [&str; 3] = ["%Y-%m-%dT%H:%MZ", "%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%dT%H:%M"];
+
+/// Format of the dates with minutes only and an offset, as given by mirrors rewriting feeds
+const MINUTES_OFFSET_FORMAT: &str = "%Y-%m-%dT%H:%M%:z";
+
+/// Parse a date as given by a Metafile, a feed item or the local cache: an RFC 3339 date with any
+/// offset, converted to UTC, a date with minutes only as in feeds, with the ``Z`` of UTC or an
+/// offset, or a date in UTC without an offset, as stored by older versions. Anything else fails
+/// rather than being read as some other date.
+///
+/// ## Example:
+/// ```
+/// use nvd_cve::datetime::{format_datetime, parse_datetime};
+///
+/// let parsed = parse_datetime("2021-12-18T14:00:00-05:00").unwrap();
+/// assert_eq!(format_datetime(&parsed), "2021-12-18T19:00:00Z");
+///
+/// // Dates of feeds have minutes only
+/// assert_eq!(parse_datetime("2021-12-18T19:00Z").unwrap(), parsed);
+/// assert_eq!(parse_datetime("2021-12-18T20:00+01:00").unwrap(), parsed);
+///
+/// // Dates cached by older versions are in UTC
+/// assert_eq!(parse_datetime("2021-12-18T19:00:00").unwrap(), parsed);
+/// assert_eq!(parse_datetime("2021-12-18T19:00:00.000").unwrap(), parsed);
+///
+/// assert!(parse_datetime("18/12/2021").is_err());
+/// ```
+pub fn parse_datetime(datetime: &str) -> Result<DateTime<Utc>, ParseError> {
+    let datetime = datetime.trim();
+    let error = match DateTime::parse_from_rfc3339(datetime) {
+        Ok(parsed) => return Ok(parsed.with_timezone(&Utc)),
+        Err(error) => error,
+    };
+
+    if let Ok(parsed) = DateTime::parse_from_str(datetime, MINUTES_OFFSET_FORMAT) {
+        return Ok(parsed.with_timezone(&Utc));
+    }
+    for format in UTC_FORMATS {
+        if let Ok(parsed) = NaiveDateTime::parse_from_str(datetime, format) {
+            return Ok(parsed.and_utc());
+        }
+    }
+    Err(error)
+}
+
+/// Format a date as stored in the local cache, in RFC 3339 with seconds and the ``Z`` offset of
+/// UTC, e.g. ``2021-12-18T19:00:00Z``. Dates formatted alike sort as they compare.
+pub fn format_datetime(datetime: &DateTime<Utc>) -> String {
+    datetime.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
@@ -0,0 +1,570 @@
+use crate::cache::{CacheConfig, SCHEMA_VERSION};
+use crate::client::{build_client, env_proxies, redirect_policy};
+use crate::feed::FeedName;
+use humansize::{file_size_opts as options, FileSize};
+use rusqlite::{Connection, OpenFlags, OptionalExtension};
+#[cfg(feature = "serde")]
+use serde::Serialize;
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::time::Duration;
+use url::Url;
+
+/// Oldest SQLite release supporting every statement of the local cache, ``3.24.0`` introduced the
+/// ``ON CONFLICT`` upserts.
+pub const MIN_SQLITE_VERSION: i32 = 3_024_000;
+
+/// Free space below which a sync is likely to fail, in bytes.
+pub const MIN_FREE_SPACE: u64 = 100 * 1024 * 1024;
+
+/// Free space a sync of every feed, which rewrites most of the cache, is expected to need, in
+/// bytes.
+pub const RECOMMENDED_FREE_SPACE: u64 = 1024 * 1024 * 1024;
+
+/// Features this build of the crate was compiled with, among the optional ones.
+pub fn enabled_features() -> Vec<&'static str> {
+    let features = [
+        ("zstd", cfg!(feature = "zstd")),
+        ("bulk-import", cfg!(feature = "bulk-import")),
+        ("snapshot", cfg!(feature = "snapshot")),
+        ("scan-host", cfg!(feature = "scan-host")),
+        ("bundled-sqlite", cfg!(feature = "bundled-sqlite")),
+    ];
+    features
+        .into_iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(name, _)| name)
+        .collect()
+}
+
+/// Outcome of a single check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "lowercase"))]
+pub enum Status {
+    Ok,
+    /// Not checked, because a check it depends on failed or it doesn't apply
+    Skipped,
+    /// Works for now but is likely to cause trouble, e.g. a nearly full disk
+    Warning,
+    /// Keeps syncing or searching the local cache from working
+    Error,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Status::Ok => write!(f, "OK"),
+            Status::Skipped => write!(f, "SKIPPED"),
+            Status::Warning => write!(f, "WARNING"),
+            Status::Error => write!(f, "ERROR"),
+        }
+    }
+}
+
+/// Result of one of the checks run by ``diagnose()``
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Diagnosis {
+    /// Name of the check, such as ``dns`` or ``schema``
+    pub check: &'static str,
+    pub status: Status,
+    /// What was found
+    pub detail: String,
+    /// What to do about it, for warnings and errors
+    pub hint: Option<String>,
+}
+
+impl Diagnosis {
+    fn ok<S: Into<String>>(check: &'static str, detail: S) -> Self {
+        Self {
+            check,
+            status: Status::Ok,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn skipped<S: Into<String>>(check: &'static str, detail: S) -> Self {
+        Self {
+            check,
+            status: Status::Skipped,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn failed<S: Into<String>, H: Into<String>>(
+        check: &'static str,
+        status: Status,
+        detail: S,
+        hint: H,
+    ) -> Self {
+        Self {
+            check,
+            status,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+/// Check the environment the local cache of ``config`` is synced and searched in, e.g. when a new
+/// install fails to sync: that the URL of the feeds resolves, accepts connections, verifies over
+/// TLS and serves Metafiles, that the disk holding the cache has space left, that the cache can be
+/// read and written, that its schema is the one of this build and that SQLite is recent enough.
+///
+/// Each check is run even when others failed, except for those depending on them, which are
+/// skipped. ``env_proxy`` and ``connect_timeout`` are used as by ``ReqwestBlockingClient``.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::CacheConfig;
+/// use nvd_cve::doctor::{diagnose, Status};
+/// use std::time::Duration;
+///
+/// for diagnosis in diagnose(&CacheConfig::new(), true, Duration::from_secs(10)) {
+///     if diagnosis.status >= Status::Warning {
+///         println!("{}: {}", diagnosis.check, diagnosis.detail);
+///     }
+/// }
+/// ```
+pub fn diagnose(
+    config: &CacheConfig,
+    env_proxy: bool,
+    connect_timeout: Duration,
+) -> Vec<Diagnosis> {
+    let mut diagnoses = diagnose_network(config, env_proxy, connect_timeout);
+    diagnoses.push(diagnose_disk_space(config));
+    diagnoses.push(diagnose_permissions(config));
+    diagnoses.push(diagnose_schema(config));
+    diagnoses.push(diagnose_sqlite());
+    diagnoses
+}
+
+/// The ``url``, ``dns``, ``connect``, ``tls`` and ``http`` checks
+fn diagnose_network(
+    config: &CacheConfig,
+    env_proxy: bool,
+    connect_timeout: Duration,
+) -> Vec<Diagnosis> {
+    let url = match Url::parse(&config.url) {
+        Ok(url) if url.host_str().is_some() => url,
+        _ => {
+            return vec![Diagnosis::failed(
+                "url",
+                Status::Error,
+                format!("{:?} isn't a valid HTTP URL", config.url),
+                "Set the URL of the directory holding the feeds with --url or the url of the config \
+                 file, e.g. https://nvd.nist.gov/feeds/json/cve/1.1/",
+            )];
+        }
+    };
+    let host = url.host_str().unwrap_or_default();
+    let port = url.port_or_known_default().unwrap_or(443);
+    let mut diagnoses = vec![Diagnosis::ok("url", url.as_str())];
+
+    // Behind a proxy, the proxy resolves and connects to the host, which may well be unreachable
+    // from here
+    let proxied = env_proxy && !env_proxies().is_empty();
+    let unreachable = if proxied {
+        Status::Warning
+    } else {
+        Status::Error
+    };
+    let proxy_hint = "Requests go through the proxy set by the environment, which may reach the \
+                      host anyway, see the http check";
+
+    let (addresses, resolve_error) = match (host, port).to_socket_addrs() {
+        Ok(addresses) => (addresses.collect::<Vec<_>>(), "no addresses".to_string()),
+        Err(error) => (vec![], error.to_string()),
+    };
+
+    // Without a proxy, the feeds can't be fetched from a host that can't be reached directly
+    let mut unreachable_reason = None;
+    if addresses.is_empty() {
+        diagnoses.push(Diagnosis::failed(
+            "dns",
+            unreachable,
+            format!("Failed resolving {}: {}", host, resolve_error),
+            if proxied {
+                proxy_hint
+            } else {
+                "Check the name servers of /etc/resolv.conf, or set HTTPS_PROXY if the network \
+                 only reaches the internet through a proxy"
+            },
+        ));
+        diagnoses.push(Diagnosis::skipped(
+            "connect",
+            format!("{} doesn't resolve", host),
+        ));
+        unreachable_reason = Some(format!("{} doesn't resolve", host));
+    } else {
+        diagnoses.push(Diagnosis::ok(
+            "dns",
+            format!("{} resolves to {}", host, addresses[0].ip()),
+        ));
+        match addresses
+            .iter()
+            .map(|address| TcpStream::connect_timeout(address, connect_timeout))
+            .find(|stream| stream.is_ok())
+        {
+            Some(_) => diagnoses.push(Diagnosis::ok(
+                "connect",
+                format!("Connected to {}:{}", host, port),
+            )),
+            None => {
+                diagnoses.push(Diagnosis::failed(
+                    "connect",
+                    unreachable,
+                    format!(
+                        "Failed connecting to {}:{} within {}s",
+                        host,
+                        port,
+                        connect_timeout.as_secs_f32()
+                    ),
+                    if proxied {
+                        proxy_hint
+                    } else {
+                        "Check that a firewall allows outgoing connections to the host, or set \
+                         HTTPS_PROXY if the network only reaches the internet through a proxy"
+                    },
+                ));
+                unreachable_reason = Some(format!("{}:{} can't be connected to", host, port));
+            }
+        }
+    }
+
+    let feed = config.feeds.first().cloned().unwrap_or(FeedName::Modified);
+    let metafile_url = match url.join(&feed.metafile_filename()) {
+        Ok(metafile_url) => metafile_url,
+        Err(error) => {
+            diagnoses.push(Diagnosis::skipped("tls", error.to_string()));
+            diagnoses.push(Diagnosis::skipped("http", error.to_string()));
+            return diagnoses;
+        }
+    };
+    if let (Some(reason), false) = (unreachable_reason, proxied) {
+        diagnoses.push(Diagnosis::skipped("tls", reason.clone()));
+        diagnoses.push(Diagnosis::skipped("http", reason));
+        return diagnoses;
+    }
+
+    let client = build_client(
+        Some(connect_timeout),
+        None,
+        None,
+        env_proxy,
+        false,
+        redirect_policy(config.max_redirects, &config.allowed_hosts),
+    );
+    let response = client
+        .get(metafile_url.clone())
+        .timeout(connect_timeout * 3)
+        .send();
+
+    let https = url.scheme() == "https";
+    match (&response, https) {
+        (Ok(_), true) => diagnoses.push(Diagnosis::ok(
+            "tls",
+            format!("The certificate of {} is trusted", host),
+        )),
+        (Err(error), true) if is_tls_error(error) => diagnoses.push(Diagnosis::failed(
+            "tls",
+            Status::Error,
+            format!("TLS handshake with {} failed: {}", host, error_chain(error)),
+            "Check that the system clock is right and that no proxy intercepts TLS. \
+             Certificates are verified against the Mozilla roots built into nvd_cve, use \
+             --tls-insecure only for lab mirrors with self-signed certificates",
+        )),
+        (Err(_), true) => diagnoses.push(Diagnosis::skipped(
+            "tls",
+            format!("No TLS connection to {} could be made", host),
+        )),
+        (_, false) => diagnoses.push(Diagnosis::failed(
+            "tls",
+            Status::Warning,
+            format!("{} is fetched over plain HTTP", url),
+            "Feeds fetched without TLS can be tampered with on the network path, use an https:// \
+             URL unless the mirror is on a trusted network",
+        )),
+    }
+
+    match response {
+        Ok(response) if response.status().is_success() => {
+            diagnoses.push(Diagnosis::ok("http", format!("Fetched {}", metafile_url)))
+        }
+        Ok(response) => diagnoses.push(Diagnosis::failed(
+            "http",
+            Status::Error,
+            format!("Fetching {} returned {}", metafile_url, response.status()),
+            format!(
+                "The URL must be the directory holding the {} files of the feeds, as served by \
+                 https://nvd.nist.gov/feeds/json/cve/1.1/",
+                feed.metafile_filename()
+            ),
+        )),
+        Err(error) if https && is_tls_error(&error) => {
+            diagnoses.push(Diagnosis::skipped("http", "The TLS handshake failed"))
+        }
+        Err(error) => diagnoses.push(Diagnosis::failed(
+            "http",
+            Status::Error,
+            format!("Fetching {} failed: {}", metafile_url, error_chain(&error)),
+            if error.is_timeout() {
+                "The server is slow to respond, retry later or raise --connect-timeout"
+            } else {
+                "Check that the host serves HTTP on this port, and the proxy settings of \
+                 HTTP_PROXY, HTTPS_PROXY and NO_PROXY"
+            },
+        )),
+    }
+
+    diagnoses
+}
+
+/// Whether ``error`` failed the TLS handshake, as opposed to failing to connect at all
+fn is_tls_error(error: &reqwest::Error) -> bool {
+    let chain = error_chain(error).to_lowercase();
+    ["certificate", "tls", "handshake", "ssl"]
+        .iter()
+        .any(|word| chain.contains(word))
+}
+
+/// ``error`` followed by the errors it was caused by, as Reqwest's own message rarely says what
+/// went wrong
+fn error_chain(error: &dyn Error) -> String {
+    let mut chain = error.to_string();
+    let mut source = error.source();
+    while let Some(error) = source {
+        // Some errors already include their source in their message
+        let message = error.to_string();
+        if !chain.ends_with(&message) {
+            chain.push_str(": ");
+            chain.push_str(&message);
+        }
+        source = error.source();
+    }
+    chain
+}
+
+/// The closest directory to the database that exists, where it and its journal are created
+fn existing_dir(db: &Path) -> Option<&Path> {
+    let mut dir = db.parent();
+    while let Some(path) = dir {
+        if path.as_os_str().is_empty() {
+            return Some(Path::new("."));
+        }
+        if path.is_dir() {
+            return Some(path);
+        }
+        dir = path.parent();
+    }
+    None
+}
+
+/// Bytes available to unprivileged users on the file system holding ``path``
+#[cfg(unix)]
+fn available_space(path: &Path) -> std::io::Result<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = CString::new(path.as_os_str().as_bytes())?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: ``path`` is a valid C string and ``stat`` is only read once filled in
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    #[allow(clippy::unnecessary_cast)]
+    Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+/// The ``disk`` check
+fn diagnose_disk_space(config: &CacheConfig) -> Diagnosis {
+    let dir = match existing_dir(Path::new(&config.db)) {
+        Some(dir) => dir,
+        None => return Diagnosis::skipped("disk", "No directory of the cache exists"),
+    };
+
+    #[cfg(unix)]
+    let available = available_space(dir);
+    #[cfg(not(unix))]
+    let available: std::io::Result<u64> = Err(std::io::ErrorKind::Unsupported.into());
+
+    let available = match available {
+        Ok(available) => available,
+        Err(error) if error.kind() == std::io::ErrorKind::Unsupported => {
+            return Diagnosis::skipped("disk", "Free space isn't checked on this platform")
+        }
+        Err(error) => {
+            return Diagnosis::failed(
+                "disk",
+                Status::Warning,
+                format!("Failed reading the free space of {:?}: {}", dir, error),
+                "Check that the directory of the cache is on a mounted file system",
+            )
+        }
+    };
+    let detail = format!(
+        "{} free in {:?}",
+        available.file_size(options::BINARY).unwrap_or_default(),
+        dir
+    );
+    let hint = "Free up space, or move the cache to a larger disk with --db. --max-db-size keeps \
+                the cache within a size once synced";
+
+    if available < MIN_FREE_SPACE {
+        Diagnosis::failed("disk", Status::Error, detail, hint)
+    } else if available < RECOMMENDED_FREE_SPACE && !config.read_only {
+        Diagnosis::failed("disk", Status::Warning, detail, hint)
+    } else {
+        Diagnosis::ok("disk", detail)
+    }
+}
+
+/// The ``permissions`` check
+fn diagnose_permissions(config: &CacheConfig) -> Diagnosis {
+    let db = Path::new(&config.db);
+    let hint = format!(
+        "Give the user running nvd_cve access with chown or chmod, or point --db at a path it \
+         can write{}",
+        if config.read_only {
+            ""
+        } else {
+            ". Caches that are only searched can be opened with --read-only"
+        }
+    );
+
+    if db.exists() {
+        if let Err(error) = OpenOptions::new()
+            .read(true)
+            .write(!config.read_only)
+            .open(db)
+        {
+            return Diagnosis::failed(
+                "permissions",
+                Status::Error,
+                format!("Failed opening {:?}: {}", db, error),
+                hint,
+            );
+        }
+        if config.read_only {
+            return Diagnosis::ok("permissions", format!("{:?} is readable", db));
+        }
+    }
+
+    // SQLite creates its journal next to the database, so the directory must be writable too
+    let dir = match existing_dir(db) {
+        Some(dir) => dir,
+        None => {
+            return Diagnosis::failed(
+                "permissions",
+                Status::Error,
+                format!("No parent directory of {:?} exists", db),
+                hint,
+            )
+        }
+    };
+    let probe = dir.join(format!(".nvd_cve-doctor-{}", std::process::id()));
+    match fs::write(&probe, []) {
+        Ok(_) => {
+            fs::remove_file(&probe).ok();
+            Diagnosis::ok("permissions", format!("{:?} is writable", dir))
+        }
+        Err(error) => Diagnosis::failed(
+            "permissions",
+            Status::Error,
+            format!("Failed writing to {:?}: {}", dir, error),
+            hint,
+        ),
+    }
+}
+
+/// The ``schema`` check
+fn diagnose_schema(config: &CacheConfig) -> Diagnosis {
+    if !Path::new(&config.db).exists() {
+        return Diagnosis::failed(
+            "schema",
+            Status::Warning,
+            format!("The cache {:?} doesn't exist yet", config.db),
+            "Run nvd_cve sync to create it",
+        );
+    }
+
+    let migration = Connection::open_with_flags(&config.db, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .and_then(|conn| {
+            let tables: i64 = conn.query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'migration'",
+                [],
+                |row| row.get(0),
+            )?;
+            if tables == 0 {
+                return Ok(None);
+            }
+            conn.query_row(
+                "SELECT schema_version, app_version FROM migration ORDER BY rowid DESC LIMIT 1",
+                [],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+            )
+            .optional()
+        });
+
+    match migration {
+        Ok(Some((schema_version, _))) if schema_version == SCHEMA_VERSION => {
+            Diagnosis::ok("schema", format!("Schema version {}", schema_version))
+        }
+        Ok(Some((schema_version, app_version))) => Diagnosis::failed(
+            "schema",
+            Status::Error,
+            format!(
+                "Schema version {} written by nvd_cve {}, this build uses {}",
+                schema_version, app_version, SCHEMA_VERSION
+            ),
+            format!(
+                "Search the cache with nvd_cve {}, or delete it and run nvd_cve sync to rebuild it",
+                app_version
+            ),
+        ),
+        Ok(None) => Diagnosis::failed(
+            "schema",
+            Status::Warning,
+            "The cache records no schema version",
+            "Run nvd_cve sync to migrate it, or delete it if it isn't a cache of nvd_cve",
+        ),
+        Err(error) => Diagnosis::failed(
+            "schema",
+            Status::Error,
+            format!("Failed reading {:?}: {}", config.db, error),
+            "The file may not be an SQLite database or be corrupt, delete it and run nvd_cve \
+             sync to rebuild it",
+        ),
+    }
+}
+
+/// The ``sqlite`` check, also listing the optional features of this build
+fn diagnose_sqlite() -> Diagnosis {
+    let features = enabled_features();
+    let detail = format!(
+        "SQLite {}, features: {}",
+        rusqlite::version(),
+        if features.is_empty() {
+            "none".to_string()
+        } else {
+            features.join(", ")
+        }
+    );
+
+    if rusqlite::version_number() < MIN_SQLITE_VERSION {
+        Diagnosis::failed(
+            "sqlite",
+            Status::Error,
+            detail,
+            "The system SQLite is older than 3.24.0, upgrade it or build nvd_cve with \
+             --features bundled-sqlite",
+        )
+    } else {
+        Diagnosis::ok("sqlite", detail)
+    }
+}
@@ -0,0 +1,245 @@
+use crate::cache::{
+    bump_generation, open, readable_schema, writable_schema, CacheConfig, CacheError,
+};
+use crate::client::HttpError;
+#[cfg(feature = "blocking")]
+use crate::client::ReqwestBlockingClient;
+#[cfg(feature = "blocking")]
+use crate::compression::Compression;
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+/// Directory of FIRST's daily EPSS scores.
+pub const EPSS_URL: &str = "https://epss.cyentia.com/";
+
+/// File name of the GZipped CSV of the current EPSS scores, relative to ``EPSS_URL``.
+pub const EPSS_SCORES: &str = "epss_scores-current.csv.gz";
+
+/// Errors related to syncing EPSS scores
+#[derive(Debug)]
+pub enum EpssError {
+    CacheError(CacheError),
+    HttpError(HttpError),
+    /// The line of the CSV, counting from 1, isn't a CVE ID followed by its score and percentile.
+    InvalidLine(usize),
+}
+
+impl From<CacheError> for EpssError {
+    fn from(error: CacheError) -> Self {
+        EpssError::CacheError(error)
+    }
+}
+
+impl From<rusqlite::Error> for EpssError {
+    fn from(error: rusqlite::Error) -> Self {
+        EpssError::CacheError(CacheError::RusqliteError(error))
+    }
+}
+
+impl From<HttpError> for EpssError {
+    fn from(error: HttpError) -> Self {
+        EpssError::HttpError(error)
+    }
+}
+
+/// The Exploit Prediction Scoring System score of a CVE.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EpssScore {
+    /// ID of the CVE, such as ``CVE-2021-44228``.
+    pub cve_id: String,
+
+    /// Probability of the CVE being exploited in the wild in the next 30 days, from 0 to 1.
+    pub epss: f64,
+
+    /// Share of the scored CVEs with a lower score, from 0 to 1.
+    pub percentile: f64,
+}
+
+/// The EPSS scores of all the CVEs scored on a day, as published by FIRST.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct EpssScores {
+    /// Version of the model the scores were computed with, such as ``v2023.03.01``.
+    pub model_version: Option<String>,
+
+    /// Date the scores were computed on, such as ``2024-06-12T00:00:00+0000``.
+    pub score_date: Option<String>,
+
+    pub scores: Vec<EpssScore>,
+}
+
+impl EpssScores {
+    /// Parse the uncompressed CSV of the EPSS scores, made of a ``#model_version:...,score_date:...``
+    /// comment, a ``cve,epss,percentile`` header and a line per CVE.
+    ///
+    /// ## Example:
+    /// ```
+    /// use nvd_cve::epss::EpssScores;
+    ///
+    /// let csv = b"#model_version:v2023.03.01,score_date:2024-06-12T00:00:00+0000
+    /// cve,epss,percentile
+    /// CVE-2021-44228,0.97565,0.99996
+    /// ";
+    /// let scores = EpssScores::from_csv(csv).unwrap();
+    ///
+    /// assert_eq!(scores.model_version.as_deref(), Some("v2023.03.01"));
+    /// assert_eq!(scores.scores[0].epss, 0.97565);
+    /// ```
+    pub fn from_csv(csv: &[u8]) -> Result<Self, EpssError> {
+        let mut scores = Self::default();
+
+        for (index, line) in String::from_utf8_lossy(csv).lines().enumerate() {
+            let line = line.trim();
+            if let Some(comment) = line.strip_prefix('#') {
+                for field in comment.split(',') {
+                    match field.split_once(':') {
+                        Some(("model_version", version)) => {
+                            scores.model_version = Some(version.to_string())
+                        }
+                        Some(("score_date", date)) => scores.score_date = Some(date.to_string()),
+                        _ => {}
+                    }
+                }
+                continue;
+            }
+            if line.is_empty() || line.starts_with("cve,") {
+                continue;
+            }
+
+            let mut fields = line.split(',');
+            let score = match (fields.next(), fields.next(), fields.next()) {
+                (Some(cve_id), Some(epss), Some(percentile)) => epss
+                    .parse()
+                    .and_then(|epss| Ok((epss, percentile.parse()?)))
+                    .ok()
+                    .map(|(epss, percentile)| EpssScore {
+                        cve_id: cve_id.to_string(),
+                        epss,
+                        percentile,
+                    }),
+                _ => None,
+            };
+            scores
+                .scores
+                .push(score.ok_or(EpssError::InvalidLine(index + 1))?);
+        }
+        Ok(scores)
+    }
+}
+
+/// Replace the EPSS scores cached with ``scores``, returning the number of CVEs scored. Scores are
+/// recorded whether the cache holds their CVE or not, so the feeds they're in can be synced later.
+pub fn import_epss(config: &CacheConfig, scores: &EpssScores) -> Result<usize, CacheError> {
+    writable_schema(config)?;
+
+    let mut conn = open(config)?;
+    let tx = conn.transaction()?;
+    tx.execute("DELETE FROM epss", [])?;
+    {
+        let mut stmt =
+            tx.prepare("INSERT OR REPLACE INTO epss (cve, score, percentile) VALUES (?1, ?2, ?3)")?;
+        for score in &scores.scores {
+            stmt.execute(params![score.cve_id, score.epss, score.percentile])?;
+        }
+    }
+    tx.execute(
+        "INSERT OR REPLACE INTO epss_sync (id, model_version, score_date) VALUES (0, ?1, ?2)",
+        params![scores.model_version, scores.score_date],
+    )?;
+    bump_generation(&tx)?;
+    tx.commit()?;
+
+    match conn.close() {
+        Ok(_) => Ok(scores.scores.len()),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Sync the current EPSS scores from the base URL of ``client``, such as ``EPSS_URL``, to the local
+/// cache, returning the number of CVEs scored. The scores are only written when they were computed
+/// on another day than those cached, unless ``force_update`` is set, otherwise nothing is recorded.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::CacheConfig;
+/// use nvd_cve::client::{BlockingHttpClient, ReqwestBlockingClient};
+/// use nvd_cve::epss::{sync_epss, EPSS_URL};
+///
+/// let config = CacheConfig::new();
+/// let client = ReqwestBlockingClient::new(EPSS_URL, None, None, None);
+///
+/// let scored = sync_epss(&config, &client).unwrap();
+/// println!("Synced the EPSS scores of {} CVEs", scored);
+/// ```
+#[cfg(feature = "blocking")]
+pub fn sync_epss(config: &CacheConfig, client: &ReqwestBlockingClient) -> Result<usize, EpssError> {
+    writable_schema(config)?;
+
+    let csv = Compression::Gzip
+        .decompress(&client.get_url(EPSS_SCORES)?)
+        .map_err(HttpError::from)?;
+    let scores = EpssScores::from_csv(&csv)?;
+
+    let conn = open(config)?;
+    let synced: Option<Option<String>> = conn
+        .query_row("SELECT score_date FROM epss_sync", [], |row| row.get(0))
+        .optional()?;
+    if let Err((_, error)) = conn.close() {
+        return Err(CacheError::RusqliteError(error).into());
+    }
+    if !config.force_update
+        && scores.score_date.is_some()
+        && synced == Some(scores.score_date.clone())
+    {
+        return Ok(0);
+    }
+
+    Ok(import_epss(config, &scores)?)
+}
+
+/// Returns the EPSS score cached for a CVE, ``None`` if it has none or the scores were never
+/// synced.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::CacheConfig;
+/// use nvd_cve::epss::get_epss;
+///
+/// let config = CacheConfig::new();
+///
+/// if let Some(score) = get_epss(&config, "CVE-2021-44228").unwrap() {
+///     println!("{:.1}% likely to be exploited", score.epss * 100.0);
+/// }
+/// ```
+pub fn get_epss(config: &CacheConfig, id: &str) -> Result<Option<EpssScore>, CacheError> {
+    readable_schema(config)?;
+
+    let conn = open(config)?;
+
+    // Read-only caches made by older versions have no scores
+    let mut tbl_stmt =
+        conn.prepare("SELECT name FROM sqlite_master where type = 'table' and name = 'epss'")?;
+    let synced = tbl_stmt.exists([])?;
+    tbl_stmt.finalize()?;
+
+    let score = if synced {
+        conn.query_row(
+            "SELECT cve, score, percentile FROM epss WHERE cve = ?1",
+            [id],
+            |row| {
+                Ok(EpssScore {
+                    cve_id: row.get(0)?,
+                    epss: row.get(1)?,
+                    percentile: row.get(2)?,
+                })
+            },
+        )
+        .optional()?
+    } else {
+        None
+    };
+
+    match conn.close() {
+        Ok(_) => Ok(score),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
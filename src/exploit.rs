@@ -0,0 +1,76 @@
+use std::fmt;
+use std::str::FromStr;
+use url::Url;
+
+/// Where a public exploit referenced by a CVE is published.
+///
+/// ## Example:
+/// ```
+/// use nvd_cve::exploit::ExploitSource;
+///
+/// assert_eq!(
+///     ExploitSource::of_url("https://www.exploit-db.com/exploits/50592"),
+///     Some(ExploitSource::ExploitDb)
+/// );
+/// assert_eq!(ExploitSource::of_url("https://logging.apache.org/log4j/2.x/security.html"), None);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ExploitSource {
+    /// An exploit of the Exploit Database, ``exploit-db.com``.
+    ExploitDb,
+    /// A module of the Metasploit Framework, on GitHub or in Rapid7's module database.
+    Metasploit,
+}
+
+impl ExploitSource {
+    /// The source of the exploit that ``url`` links to, or ``None`` if it doesn't link to one.
+    pub fn of_url(url: &str) -> Option<Self> {
+        let url = Url::parse(url.trim()).ok()?;
+        let host = url.host_str()?.to_ascii_lowercase();
+        let path = url.path();
+
+        match host.strip_prefix("www.").unwrap_or(&host) {
+            "exploit-db.com" if path.starts_with("/exploits/") => Some(ExploitSource::ExploitDb),
+            "rapid7.com" if path.starts_with("/db/modules/") => Some(ExploitSource::Metasploit),
+            "github.com" | "raw.githubusercontent.com"
+                if path.starts_with("/rapid7/metasploit-framework/")
+                    && path.contains("/modules/") =>
+            {
+                Some(ExploitSource::Metasploit)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ExploitSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExploitSource::ExploitDb => write!(f, "exploit-db"),
+            ExploitSource::Metasploit => write!(f, "metasploit"),
+        }
+    }
+}
+
+impl FromStr for ExploitSource {
+    type Err = String;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        match source {
+            "exploit-db" => Ok(ExploitSource::ExploitDb),
+            "metasploit" => Ok(ExploitSource::Metasploit),
+            _ => Err(format!(
+                "unknown exploit source {:?}, expected exploit-db or metasploit",
+                source
+            )),
+        }
+    }
+}
+
+/// A reference of a CVE linking to a public exploit of it, as found by
+/// ``Cve::exploit_refs()``.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExploitRef {
+    pub source: ExploitSource,
+    pub url: String,
+}
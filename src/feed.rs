@@ -1,18 +1,275 @@
+#[cfg(feature = "blocking")]
+use crate::client::BlockingHttpClient;
 /// A Metafile is a small text file containing metadata about a compressed JSON CVE feed.
 /// Every CVE feed file has an associated Metafile.
-use crate::client::{BlockingHttpClient, HttpError};
-use chrono::{DateTime, NaiveDateTime, ParseError};
-use log::warn;
+use crate::client::{AsyncHttpClient, HttpError};
+use crate::compression::Compression;
+use crate::datetime::{format_datetime, parse_datetime};
+use chrono::{DateTime, ParseError, SecondsFormat, Utc};
+use log::debug;
+use sha2::{Digest, Sha256};
+use std::fmt;
 use std::fs;
 use std::io::Error;
 use std::num::ParseIntError;
 use std::path::Path;
 use std::str::FromStr;
 
+/// Year of the oldest yearly feed published by NIST.
+pub const FIRST_FEED_YEAR: u16 = 2002;
+
+/// Year of the newest yearly feed published by NIST.
+pub const LAST_FEED_YEAR: u16 = 2024;
+
+/// Name of a CVE JSON feed as published by NIST.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FeedName {
+    /// All CVEs published in the given year.
+    Year(u16),
+    /// CVEs recently published, as of the last 8 days.
+    Recent,
+    /// CVEs recently published or modified, as of the last 8 days.
+    Modified,
+    /// CVEs matching a query, in a reduced feed written by a mirror rather than published by NIST.
+    Custom,
+}
+
+/// Errors related to parsing a feed name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeedNameError {
+    /// Name is neither a year, ``recent``, ``modified`` nor ``custom``
+    UnknownFeed(String),
+    /// Year is before the first feed published by NIST
+    InvalidYear(u16),
+    /// Range of years starts after it ends
+    InvalidRange(u16, u16),
+}
+
+impl fmt::Display for FeedNameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeedNameError::UnknownFeed(name) => write!(
+                f,
+                "unknown feed {:?}, expected a year, recent, modified or custom",
+                name
+            ),
+            FeedNameError::InvalidYear(year) => write!(
+                f,
+                "no feed for {}, the first feed is for {}",
+                year, FIRST_FEED_YEAR
+            ),
+            FeedNameError::InvalidRange(first, last) => write!(
+                f,
+                "invalid range of feeds {}..{}, the first year is after the last",
+                first, last
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FeedNameError {}
+
+impl FeedName {
+    /// All known feeds, yearly feeds first, followed by ``recent`` and ``modified``.
+    pub fn all() -> Vec<Self> {
+        let mut feeds: Vec<Self> = (FIRST_FEED_YEAR..=LAST_FEED_YEAR)
+            .map(FeedName::Year)
+            .collect();
+        feeds.push(FeedName::Recent);
+        feeds.push(FeedName::Modified);
+        feeds
+    }
+
+    /// Yearly feed listing the CVE with the given ID, e.g. the ``2016`` feed for ``CVE-2016-1234``.
+    /// CVEs are listed in the feed of the year in their ID, CVEs from before the first feed are
+    /// listed in it. Returns ``None`` if ``id`` isn't a CVE ID.
+    pub fn for_cve_id(id: &str) -> Option<Self> {
+        let mut parts = id.trim().splitn(3, '-');
+        if !parts.next()?.eq_ignore_ascii_case("CVE") {
+            return None;
+        }
+        let year = u16::from_str(parts.next()?).ok()?;
+        let number = parts.next()?;
+        if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        Some(FeedName::Year(year.max(FIRST_FEED_YEAR)))
+    }
+
+    /// Filename of the feed's Metafile
+    pub fn metafile_filename(&self) -> String {
+        format!("nvdcve-1.1-{}.meta", self)
+    }
+
+    /// Filename of the GZipped JSON feed
+    pub fn feed_filename(&self) -> String {
+        self.compressed_feed_filename(Compression::Gzip)
+    }
+
+    /// Filename of the JSON feed compressed in the given format
+    pub fn compressed_feed_filename(&self, compression: Compression) -> String {
+        format!("nvdcve-1.1-{}.json.{}", self, compression.extension())
+    }
+}
+
+impl fmt::Display for FeedName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeedName::Year(year) => write!(f, "{}", year),
+            FeedName::Recent => write!(f, "recent"),
+            FeedName::Modified => write!(f, "modified"),
+            FeedName::Custom => write!(f, "custom"),
+        }
+    }
+}
+
+impl FromStr for FeedName {
+    type Err = FeedNameError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "recent" => Ok(FeedName::Recent),
+            "modified" => Ok(FeedName::Modified),
+            "custom" => Ok(FeedName::Custom),
+            other => match u16::from_str(other) {
+                Ok(year) if year >= FIRST_FEED_YEAR => Ok(FeedName::Year(year)),
+                Ok(year) => Err(FeedNameError::InvalidYear(year)),
+                Err(_) => Err(FeedNameError::UnknownFeed(name.to_string())),
+            },
+        }
+    }
+}
+
+/// Serialized as its name, e.g. ``2021`` or ``recent``
+#[cfg(feature = "serde")]
+impl serde::Serialize for FeedName {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for FeedName {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse()
+            .map_err(|error| serde::de::Error::custom(format!("{:?}", error)))
+    }
+}
+
+/// A feed given by name, or a range of yearly feeds such as ``2018..2020``. Ranges include both
+/// years, the first or last year can be left out for a range starting at ``FIRST_FEED_YEAR`` or
+/// ending at ``LAST_FEED_YEAR``, so ``2018..`` selects every yearly feed since 2018.
+///
+/// ## Example:
+/// ```
+/// use nvd_cve::feed::{FeedName, FeedSelector};
+///
+/// let selectors: Vec<FeedSelector> = vec!["2023..".parse().unwrap(), "recent".parse().unwrap()];
+///
+/// assert_eq!(
+///     FeedSelector::expand(&selectors),
+///     vec![FeedName::Year(2023), FeedName::Year(2024), FeedName::Recent]
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FeedSelector {
+    /// A single feed.
+    Feed(FeedName),
+    /// The yearly feeds from the first to the last year, included.
+    Years(u16, u16),
+}
+
+impl FeedSelector {
+    /// The feeds selected, in order.
+    pub fn feeds(&self) -> Vec<FeedName> {
+        match self {
+            FeedSelector::Feed(name) => vec![*name],
+            FeedSelector::Years(first, last) => (*first..=*last).map(FeedName::Year).collect(),
+        }
+    }
+
+    /// The feeds selected by all the ``selectors``, in order and without duplicates.
+    pub fn expand(selectors: &[FeedSelector]) -> Vec<FeedName> {
+        let mut feeds: Vec<FeedName> = vec![];
+        for feed in selectors.iter().flat_map(|selector| selector.feeds()) {
+            if !feeds.contains(&feed) {
+                feeds.push(feed);
+            }
+        }
+        feeds
+    }
+}
+
+impl fmt::Display for FeedSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeedSelector::Feed(name) => write!(f, "{}", name),
+            FeedSelector::Years(first, last) => write!(f, "{}..{}", first, last),
+        }
+    }
+}
+
+impl FromStr for FeedSelector {
+    type Err = FeedNameError;
+
+    fn from_str(selector: &str) -> Result<Self, Self::Err> {
+        let (first, last) = match selector.trim().split_once("..") {
+            Some(range) => range,
+            None => return Ok(FeedSelector::Feed(selector.parse()?)),
+        };
+
+        let year = |year: &str, default: u16| match year.trim() {
+            "" => Ok(default),
+            year => match year.parse()? {
+                FeedName::Year(year) => Ok(year),
+                _ => Err(FeedNameError::UnknownFeed(selector.to_string())),
+            },
+        };
+        let first = year(first, FIRST_FEED_YEAR)?;
+        let last = year(last, LAST_FEED_YEAR)?;
+
+        if first > last {
+            return Err(FeedNameError::InvalidRange(first, last));
+        }
+        Ok(FeedSelector::Years(first, last))
+    }
+}
+
+/// Deserialize a list of feeds which may hold ranges of yearly feeds, such as the ``feeds`` of a
+/// ``CacheConfig``
+#[cfg(feature = "serde")]
+pub(crate) fn deserialize_feeds<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<FeedName>, D::Error> {
+    let selectors = <Vec<String> as serde::Deserialize>::deserialize(deserializer)?
+        .iter()
+        .map(|selector| selector.parse())
+        .collect::<Result<Vec<FeedSelector>, _>>()
+        .map_err(serde::de::Error::custom)?;
+    Ok(FeedSelector::expand(&selectors))
+}
+
+/// Formatted as served by NIST, with CRLF line endings
+impl fmt::Display for Metafile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "lastModifiedDate:{}\r\nsize:{}\r\nzipSize:{}\r\ngzSize:{}\r\nsha256:{}\r\n",
+            self.last_modified_date
+                .to_rfc3339_opts(SecondsFormat::Secs, false),
+            self.size,
+            self.zip_size,
+            self.gz_size,
+            self.sha256
+        )
+    }
+}
+
 /// CVE JSON feed and associated Metafile data.
 #[derive(Debug)]
 pub struct Feed {
-    pub name: String,
+    pub name: FeedName,
     pub metafile: Option<Metafile>,
 }
 
@@ -21,7 +278,7 @@ pub struct Feed {
 pub enum MetafileError {
     /// File IO Error when reading Metafile
     FileError(Error),
-    /// Incorrect number of lines in Metafile
+    /// A field is missing from the Metafile
     LineError,
     /// Failure to split Metafile fields as expected
     SplitError,
@@ -39,6 +296,12 @@ impl From<Error> for MetafileError {
     }
 }
 
+impl From<ParseError> for MetafileError {
+    fn from(error: ParseError) -> Self {
+        MetafileError::ParseDateTimeError(error)
+    }
+}
+
 impl From<ParseIntError> for MetafileError {
     fn from(error: ParseIntError) -> Self {
         MetafileError::ParseIntError(error)
@@ -46,10 +309,10 @@ impl From<ParseIntError> for MetafileError {
 }
 
 /// Metafile describing a CVE JSON feed
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Metafile {
-    /// Last modified date of JSON feed.
-    pub last_modified_date: NaiveDateTime,
+    /// Last modified date of JSON feed, in UTC whatever the offset the Metafile gives it with.
+    pub last_modified_date: DateTime<Utc>,
 
     /// Size of feed in bytes.
     pub size: u64,
@@ -66,9 +329,10 @@ pub struct Metafile {
 
 /// A parse feed Metafile
 impl Metafile {
+    #[cfg(feature = "blocking")]
     pub fn from_blocking_http_client<C: BlockingHttpClient>(
         client: &C,
-        name: &str,
+        name: &FeedName,
     ) -> Result<Self, MetafileError> {
         match client.get_metafile(name) {
             Ok(metafile_text) => Self::from_string(metafile_text),
@@ -76,55 +340,140 @@ impl Metafile {
         }
     }
 
+    pub async fn from_async_http_client<C: AsyncHttpClient>(
+        client: &C,
+        name: &FeedName,
+    ) -> Result<Self, MetafileError> {
+        match client.get_metafile(name).await {
+            Ok(metafile_text) => Self::from_string(metafile_text),
+            Err(error) => Err(MetafileError::FetchError(error)),
+        }
+    }
+
     /// Parse Metafile from a local file
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, MetafileError> {
         Self::from_string(fs::read_to_string(path)?)
     }
 
-    /// Parse Metafile from a String
+    /// Parse Metafile from a String. Fields can come in any order, unknown fields are ignored and
+    /// a leading byte order mark, CRLF line endings and spaces around keys and values are trimmed,
+    /// as added to Metafiles by some proxies.
     pub fn from_string(contents: String) -> Result<Self, MetafileError> {
-        macro_rules! next {
-            ($lines:expr) => {
-                $lines
-                    .next()
-                    .ok_or(MetafileError::LineError)?
-                    .split_once(':')
-                    .ok_or(MetafileError::SplitError)?
-                    .1
-            };
+        let mut last_modified_date = None;
+        let mut size = None;
+        let mut zip_size = None;
+        let mut gz_size = None;
+        let mut sha256 = None;
+
+        for line in contents.trim_start_matches('\u{feff}').lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (key, value) = line.split_once(':').ok_or(MetafileError::SplitError)?;
+            let value = value.trim();
+
+            match key.trim().to_ascii_lowercase().as_str() {
+                "lastmodifieddate" => last_modified_date = Some(Self::parse_datetime(value)?),
+                "size" => size = Some(u64::from_str(value)?),
+                "zipsize" => zip_size = Some(u64::from_str(value)?),
+                "gzsize" => gz_size = Some(u64::from_str(value)?),
+                "sha256" => sha256 = Some(value.to_string()),
+                _ => debug!("Ignored unknown Metafile field: {:?}", key),
+            }
         }
 
-        let mut lines = contents.lines();
+        Ok(Self {
+            last_modified_date: last_modified_date.ok_or(MetafileError::LineError)?,
+            size: size.ok_or(MetafileError::LineError)?,
+            zip_size: zip_size.ok_or(MetafileError::LineError)?,
+            gz_size: gz_size.ok_or(MetafileError::LineError)?,
+            sha256: sha256.ok_or(MetafileError::LineError)?,
+        })
+    }
 
+    /// Generate the Metafile of a feed from its uncompressed ``json``, compressing it to find the
+    /// size of its zipped and GZipped files. The zip archive holds the JSON as ``file_name``, as in
+    /// ``nvdcve-1.1-2021.json``.
+    ///
+    /// ## Example:
+    /// ```
+    /// use chrono::{DateTime, Utc};
+    /// use nvd_cve::feed::Metafile;
+    ///
+    /// let json = br#"{"CVE_Items": []}"#;
+    /// let last_modified = "2024-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+    /// let metafile = Metafile::from_feed(json, "nvdcve-1.1-custom.json", last_modified).unwrap();
+    ///
+    /// assert_eq!(metafile.size, json.len() as u64);
+    /// ```
+    pub fn from_feed(
+        json: &[u8],
+        file_name: &str,
+        last_modified_date: DateTime<Utc>,
+    ) -> Result<Self, MetafileError> {
         Ok(Self {
-            last_modified_date: Self::parse_datetime(next!(lines)),
-            size: u64::from_str(next!(lines))?,
-            zip_size: u64::from_str(next!(lines))?,
-            gz_size: u64::from_str(next!(lines))?,
-            sha256: next!(lines).to_string(),
+            last_modified_date,
+            size: json.len() as u64,
+            zip_size: Compression::Zip.compress(json, file_name)?.len() as u64,
+            gz_size: Compression::Gzip.compress(json, file_name)?.len() as u64,
+            sha256: hex::encode_upper(Sha256::digest(json)),
         })
     }
 
-    /// Parse date from either a metafile or from a record in the local cache
-    pub fn parse_datetime(datetime: &str) -> NaiveDateTime {
-        match DateTime::parse_from_rfc3339(datetime) {
-            Ok(parsed_dt) => parsed_dt.naive_utc(),
-            Err(_) => match NaiveDateTime::parse_from_str(datetime, "%Y-%m-%dT%H:%M:%S") {
-                Ok(parsed_ndt) => parsed_ndt,
-                Err(_) => {
-                    warn!("Failed parsing datetime: {:?}", datetime);
-                    DateTime::from_timestamp(0, 0)
-                        .unwrap_or_default()
-                        .naive_utc()
-                }
-            },
+    /// Generate the Metafile of a local feed file, either uncompressed or compressed in any
+    /// supported format as told by its extension. The size of a zipped or GZipped file is its own,
+    /// the other size is found by compressing the feed. The last modified date is the feed's
+    /// ``CVE_data_timestamp``.
+    pub fn from_feed_file<P: AsRef<Path>>(path: P) -> Result<Self, MetafileError> {
+        let path = path.as_ref();
+        let contents = fs::read(path)?;
+
+        let compression = Compression::from_path(path);
+        let json = match compression {
+            Some(compression) => compression.decompress(&contents)?,
+            None => contents.clone(),
+        };
+
+        #[derive(serde::Deserialize)]
+        struct Timestamp {
+            #[serde(rename = "CVE_data_timestamp")]
+            timestamp: String,
         }
+        let timestamp: Timestamp = serde_json::from_slice(&json).map_err(Error::from)?;
+        let last_modified_date = parse_datetime(&timestamp.timestamp)?;
+
+        // The JSON file of a feed is named after its compressed files, without their extension
+        let file_name = match compression {
+            Some(_) => path.file_stem(),
+            None => path.file_name(),
+        };
+        let file_name = file_name.unwrap_or_default().to_string_lossy();
+
+        let mut metafile = Self::from_feed(&json, &file_name, last_modified_date)?;
+        match compression {
+            Some(Compression::Zip) => metafile.zip_size = contents.len() as u64,
+            Some(Compression::Gzip) => metafile.gz_size = contents.len() as u64,
+            _ => {}
+        }
+        Ok(metafile)
+    }
+
+    /// Write the Metafile to a local file, as served alongside its feed
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), MetafileError> {
+        Ok(fs::write(path, self.to_string())?)
+    }
+
+    /// Parse date from either a metafile or from a record in the local cache, see
+    /// ``datetime::parse_datetime()``
+    pub fn parse_datetime(datetime: &str) -> Result<DateTime<Utc>, ParseError> {
+        parse_datetime(datetime)
     }
 
-    /// Format NaiveDate to a string for storing in local cache
+    /// Format the last modified date for storing in local cache, in RFC 3339 such as
+    /// ``2021-12-18T19:00:00Z``
     pub fn format_last_modified_date(&self) -> String {
-        self.last_modified_date
-            .format("%Y-%m-%dT%H:%M:%S")
-            .to_string()
+        format_datetime(&self.last_modified_date)
     }
 }
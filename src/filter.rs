@@ -0,0 +1,154 @@
+use crate::clock::{Clock, SystemClock};
+use crate::compression::Compression;
+use crate::cve::CveContainer;
+use crate::feed::{FeedName, Metafile, MetafileError};
+use crate::query::SearchQuery;
+use chrono::{DateTime, Timelike, Utc};
+use serde::Deserialize;
+use serde_json::value::RawValue;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+/// The items of a CVE JSON feed, left as they were published.
+#[derive(Deserialize)]
+struct RawItems<'a> {
+    #[serde(borrow, rename = "CVE_Items")]
+    cve_items: Vec<&'a RawValue>,
+}
+
+/// A reduced feed holding only the CVEs of some feeds that match a query, such as a watchlist of
+/// products, for devices that only need a slice of the NVD. Its items are kept exactly as they
+/// were published and are written as a feed and Metafile like those served by NIST.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::compression::Compression;
+/// use nvd_cve::filter::FilteredFeed;
+/// use nvd_cve::query::SearchQuery;
+/// use std::fs;
+///
+/// let query = SearchQuery {
+///     text: Some("openssl".to_string()),
+///     ..Default::default()
+/// };
+///
+/// let mut feed = FilteredFeed::new(query);
+/// for year in 2020..=2024 {
+///     let json = fs::read(format!("feeds/nvdcve-1.1-{}.json", year)).unwrap();
+///     feed.add_feed(&json).unwrap();
+/// }
+/// feed.write_to_dir("mirror", Compression::Gzip).unwrap();
+/// ```
+pub struct FilteredFeed {
+    query: SearchQuery,
+    items: BTreeMap<String, Box<RawValue>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl FilteredFeed {
+    /// An empty feed, to be filled with the CVEs matching ``query``.
+    pub fn new(query: SearchQuery) -> Self {
+        Self {
+            query,
+            items: BTreeMap::new(),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Date the feed by ``clock`` when it's written rather than by the system's, usually the
+    /// ``clock`` of a ``CacheConfig``.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Add the CVEs of the uncompressed feed ``json`` that match the query, returning how many
+    /// matched. CVEs already added are replaced by their version in this feed, or removed if it no
+    /// longer matches, so feeds should be added in the order they are synced with ``modified``
+    /// last.
+    pub fn add_feed(&mut self, json: &[u8]) -> serde_json::Result<usize> {
+        let raw: RawItems = serde_json::from_slice(json)?;
+
+        let mut matched = 0;
+        for item in raw.cve_items {
+            let cve: CveContainer = serde_json::from_str(item.get())?;
+            let id = cve.cve.cve_data_meta.id.clone();
+
+            if self.query.matches(&cve) {
+                self.items.insert(id, item.to_owned());
+                matched += 1;
+            } else {
+                self.items.remove(&id);
+            }
+        }
+        Ok(matched)
+    }
+
+    /// Number of CVEs in the feed.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns ``true`` if no CVE matched the query.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// The feed as uncompressed JSON, with its CVEs sorted by ID and ``timestamp`` as its
+    /// ``CVE_data_timestamp``.
+    pub fn to_json(&self, timestamp: DateTime<Utc>) -> Vec<u8> {
+        let mut json = format!(
+            "{{\"CVE_data_type\":\"CVE\",\"CVE_data_format\":\"MITRE\",\"CVE_data_version\":\"4.0\",\
+             \"CVE_data_numberOfCVEs\":\"{}\",\"CVE_data_timestamp\":\"{}\",\"CVE_Items\":[",
+            self.items.len(),
+            timestamp.format("%Y-%m-%dT%H:%MZ")
+        );
+        for (position, item) in self.items.values().enumerate() {
+            if position > 0 {
+                json.push(',');
+            }
+            json.push_str(item.get());
+        }
+        json.push_str("]}");
+        json.into_bytes()
+    }
+
+    /// Write the feed to ``dir`` as the ``custom`` feed, ``nvdcve-1.1-custom.json`` compressed in
+    /// the given format, along with its ``nvdcve-1.1-custom.meta`` Metafile, which is returned.
+    /// Both are written with the current time of the feed's clock as their last modified date.
+    pub fn write_to_dir<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        compression: Compression,
+    ) -> Result<Metafile, MetafileError> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+
+        // The timestamp of a feed only has minutes, the Metafile's date is kept consistent with it
+        let now = self.clock.now();
+        let timestamp = now
+            .with_second(0)
+            .and_then(|now| now.with_nanosecond(0))
+            .unwrap_or(now);
+
+        let json = self.to_json(timestamp);
+        let json_name = format!("nvdcve-1.1-{}.json", FeedName::Custom);
+        let compressed = compression.compress(&json, &json_name)?;
+
+        let mut metafile = Metafile::from_feed(&json, &json_name, timestamp)?;
+        match compression {
+            Compression::Zip => metafile.zip_size = compressed.len() as u64,
+            Compression::Gzip => metafile.gz_size = compressed.len() as u64,
+            Compression::Zstd => {}
+        }
+
+        fs::write(
+            dir.join(FeedName::Custom.compressed_feed_filename(compression)),
+            compressed,
+        )?;
+        metafile.write_to_file(dir.join(FeedName::Custom.metafile_filename()))?;
+        Ok(metafile)
+    }
+}
@@ -0,0 +1,362 @@
+use crate::cache::{open, readable_schema, CacheConfig, CacheError, FeedChanges};
+#[cfg(feature = "blocking")]
+use crate::client::{HttpError, ReqwestBlockingClient};
+#[cfg(feature = "blocking")]
+use chrono::NaiveDateTime;
+use log::debug;
+use rusqlite::{params, Connection, OptionalExtension, Row, Transaction, TransactionBehavior};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "blocking")]
+use url::form_urlencoded;
+
+/// Endpoint of the NVD's CVE Change History API.
+pub const NVD_CHANGE_HISTORY_URL: &str = "https://services.nvd.nist.gov/rest/json/cvehistory/2.0";
+
+/// Most change events the CVE Change History API returns per request.
+#[cfg(feature = "blocking")]
+const CHANGE_HISTORY_PAGE_SIZE: usize = 5000;
+
+/// Format of the dates of change events and of the date ranges queried from the API, in UTC.
+#[cfg(feature = "blocking")]
+pub(crate) const CHANGE_DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3f";
+
+/// Record of a sync run of the local cache, kept whether or not the run was attested.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::CacheConfig;
+/// use nvd_cve::history::get_last_sync_run;
+///
+/// let config = CacheConfig::new();
+///
+/// if let Some(run) = get_last_sync_run(&config).unwrap() {
+///     println!("Last synced at {}", run.finished);
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SyncRun {
+    /// When the sync started, RFC 3339 formatted.
+    pub started: String,
+
+    /// When the sync finished, RFC 3339 formatted.
+    pub finished: String,
+
+    /// Number of CVEs inserted or updated.
+    pub cves_updated: usize,
+
+    /// Number of CVEs left as they were, since they have been modified after the feed.
+    pub cves_skipped: usize,
+
+    /// Changes made by each feed that was fetched and synced, in the order they were synced.
+    pub feeds: Vec<FeedChanges>,
+}
+
+/// Build a ``SyncRun`` from a row of the ``sync_run`` table
+fn sync_run_from_row(row: &Row) -> rusqlite::Result<SyncRun> {
+    Ok(SyncRun {
+        started: row.get("started")?,
+        finished: row.get("finished")?,
+        cves_updated: row.get("cves_updated")?,
+        cves_skipped: row.get("cves_skipped")?,
+        feeds: vec![],
+    })
+}
+
+/// Read the changes made by each feed of the sync run with the given ``id``
+fn feed_changes(conn: &Connection, id: i64) -> Result<Vec<FeedChanges>, CacheError> {
+    let mut stmt = conn.prepare("SELECT * FROM sync_feed WHERE run = ?1 ORDER BY position")?;
+    let rows = stmt.query_map([id], |row| {
+        Ok((
+            row.get::<_, String>("feed")?,
+            row.get("added")?,
+            row.get("modified")?,
+            row.get("unchanged")?,
+            row.get("skipped")?,
+        ))
+    })?;
+
+    let mut feeds = vec![];
+    for row in rows {
+        let (name, added, modified, unchanged, skipped) = row?;
+        match name.parse() {
+            Ok(feed) => feeds.push(FeedChanges {
+                feed,
+                added,
+                modified,
+                unchanged,
+                skipped,
+            }),
+            Err(error) => debug!("Skipped synced feed {:?}: {}", name, error),
+        }
+    }
+
+    stmt.finalize()?;
+    Ok(feeds)
+}
+
+/// Whether the ``sync_run`` table exists, it may not in a read-only cache created by an older
+/// version
+fn has_sync_runs(conn: &Connection) -> Result<bool, CacheError> {
+    let mut stmt =
+        conn.prepare("SELECT name FROM sqlite_master where type = 'table' and name = 'sync_run'")?;
+    let exists = stmt.exists([])?;
+    stmt.finalize()?;
+    Ok(exists)
+}
+
+/// Store the record of a sync run, called once the run has finished
+pub(crate) fn record_sync_run(config: &CacheConfig, run: &SyncRun) -> Result<(), CacheError> {
+    let mut conn = open(config)?;
+
+    let tx = Transaction::new(&mut conn, TransactionBehavior::Immediate)?;
+    tx.execute(
+        "INSERT INTO sync_run (started, finished, cves_updated, cves_skipped)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![
+            run.started,
+            run.finished,
+            run.cves_updated,
+            run.cves_skipped
+        ],
+    )?;
+    let id = tx.last_insert_rowid();
+
+    for (position, changes) in run.feeds.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO sync_feed (run, position, feed, added, modified, unchanged, skipped)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                id,
+                position,
+                changes.feed.to_string(),
+                changes.added,
+                changes.modified,
+                changes.unchanged,
+                changes.skipped
+            ],
+        )?;
+    }
+    tx.commit()?;
+
+    match conn.close() {
+        Ok(_) => Ok(()),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Returns the records of every sync run of the local cache, oldest first, so that spikes in the
+/// changes made by a feed can be spotted.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::CacheConfig;
+/// use nvd_cve::history::get_sync_runs;
+///
+/// let config = CacheConfig::new();
+///
+/// for run in get_sync_runs(&config).unwrap() {
+///     for changes in run.feeds {
+///         println!("{} {}: {} modified", run.finished, changes.feed, changes.modified);
+///     }
+/// }
+/// ```
+pub fn get_sync_runs(config: &CacheConfig) -> Result<Vec<SyncRun>, CacheError> {
+    readable_schema(config)?;
+
+    let conn = open(config)?;
+
+    let mut runs = vec![];
+    if has_sync_runs(&conn)? {
+        let mut stmt = conn.prepare("SELECT * FROM sync_run ORDER BY id")?;
+        let rows = stmt.query_map([], |row| Ok((row.get("id")?, sync_run_from_row(row)?)))?;
+        for row in rows {
+            let (id, mut run) = row?;
+            run.feeds = feed_changes(&conn, id)?;
+            runs.push(run);
+        }
+        stmt.finalize()?;
+    }
+
+    match conn.close() {
+        Ok(_) => Ok(runs),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Returns the record of the last sync run of the local cache, or ``None`` if it was never synced.
+pub fn get_last_sync_run(config: &CacheConfig) -> Result<Option<SyncRun>, CacheError> {
+    readable_schema(config)?;
+
+    let conn = open(config)?;
+    let run = last_sync_run(&conn)?;
+
+    match conn.close() {
+        Ok(_) => Ok(run),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// The last sync run recorded in the opened cache, if any
+pub(crate) fn last_sync_run(conn: &Connection) -> Result<Option<SyncRun>, CacheError> {
+    if !has_sync_runs(conn)? {
+        return Ok(None);
+    }
+
+    let mut stmt = conn.prepare("SELECT * FROM sync_run ORDER BY id DESC LIMIT 1")?;
+    let run = stmt
+        .query_row([], |row| Ok((row.get("id")?, sync_run_from_row(row)?)))
+        .optional()?;
+    stmt.finalize()?;
+
+    match run {
+        Some((id, mut run)) => {
+            run.feeds = feed_changes(conn, id)?;
+            Ok(Some(run))
+        }
+        None => Ok(None),
+    }
+}
+
+/// A change made to a CVE, as listed by the NVD's official change log of the CVE.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::{get_change_events, CacheConfig};
+///
+/// let config = CacheConfig::new();
+///
+/// for event in get_change_events(&config, "CVE-2021-44228").unwrap() {
+///     println!("{} {} by {}", event.created, event.event_name, event.source_identifier);
+/// }
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeEvent {
+    /// ID of the CVE that was changed.
+    pub cve_id: String,
+
+    /// What happened to the CVE, e.g. ``Initial Analysis`` or ``CVE Modified``.
+    pub event_name: String,
+
+    /// Unique ID of the change.
+    pub cve_change_id: String,
+
+    /// Who made the change, e.g. ``nvd@nist.gov`` or the CNA of the CVE.
+    pub source_identifier: String,
+
+    /// When the change was made, in UTC.
+    pub created: String,
+
+    /// The values added, changed or removed by the change, if any.
+    #[serde(default)]
+    pub details: Vec<ChangeDetail>,
+}
+
+/// A value added, changed or removed by a ``ChangeEvent``.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeDetail {
+    /// ``Added``, ``Changed`` or ``Removed``.
+    #[serde(default)]
+    pub action: String,
+
+    /// What the value is, e.g. ``CVSS V3.1``, ``CWE`` or ``Reference``.
+    #[serde(rename = "type")]
+    pub kind: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old_value: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_value: Option<String>,
+}
+
+/// A page of results of the CVE Change History API
+#[cfg(feature = "blocking")]
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChangeHistoryPage {
+    start_index: usize,
+    total_results: usize,
+    #[serde(default)]
+    cve_changes: Vec<ChangeItem>,
+}
+
+#[cfg(feature = "blocking")]
+#[derive(Deserialize)]
+struct ChangeItem {
+    change: ChangeEvent,
+}
+
+/// Fetch the change log of a CVE from the CVE Change History API at the base URL of ``client``,
+/// such as ``NVD_CHANGE_HISTORY_URL``, oldest change first. Nothing is cached, see
+/// ``cache::sync_change_history`` for that.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::client::{BlockingHttpClient, ReqwestBlockingClient};
+/// use nvd_cve::history::{fetch_change_events, NVD_CHANGE_HISTORY_URL};
+///
+/// let client = ReqwestBlockingClient::new(NVD_CHANGE_HISTORY_URL, None, None, None);
+///
+/// for event in fetch_change_events(&client, "CVE-2021-44228").unwrap() {
+///     println!("{} {}", event.created, event.event_name);
+/// }
+/// ```
+#[cfg(feature = "blocking")]
+pub fn fetch_change_events(
+    client: &ReqwestBlockingClient,
+    id: &str,
+) -> Result<Vec<ChangeEvent>, HttpError> {
+    fetch_pages(client, &[("cveId", id)])
+}
+
+/// Fetch the changes made to any CVE between ``start`` and ``end``, which the API allows to be at
+/// most 120 days apart
+#[cfg(feature = "blocking")]
+pub(crate) fn fetch_changes_between(
+    client: &ReqwestBlockingClient,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+) -> Result<Vec<ChangeEvent>, HttpError> {
+    let start = start.format(CHANGE_DATE_FORMAT).to_string();
+    let end = end.format(CHANGE_DATE_FORMAT).to_string();
+    fetch_pages(
+        client,
+        &[("changeStartDate", &start), ("changeEndDate", &end)],
+    )
+}
+
+/// Fetch every page of the change events matching the query ``parameters``
+#[cfg(feature = "blocking")]
+fn fetch_pages(
+    client: &ReqwestBlockingClient,
+    parameters: &[(&str, &str)],
+) -> Result<Vec<ChangeEvent>, HttpError> {
+    let mut events = vec![];
+    let mut start_index = 0;
+
+    loop {
+        let query = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(parameters)
+            .append_pair("resultsPerPage", &CHANGE_HISTORY_PAGE_SIZE.to_string())
+            .append_pair("startIndex", &start_index.to_string())
+            .finish();
+        let page: ChangeHistoryPage =
+            serde_json::from_slice(&client.get_url(&format!("?{}", query))?)?;
+
+        let fetched = page.cve_changes.len();
+        events.extend(page.cve_changes.into_iter().map(|item| item.change));
+        start_index = page.start_index + fetched;
+
+        debug!(
+            "Fetched {}/{} change events",
+            start_index, page.total_results
+        );
+        if fetched == 0 || start_index >= page.total_results {
+            break;
+        }
+    }
+    Ok(events)
+}
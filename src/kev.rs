@@ -0,0 +1,169 @@
+use crate::cache::{
+    bump_generation, open, readable_schema, writable_schema, CacheConfig, CacheError,
+};
+#[cfg(feature = "blocking")]
+use crate::client::ReqwestBlockingClient;
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+/// Directory of CISA's Known Exploited Vulnerabilities catalog.
+pub const CISA_KEV_URL: &str = "https://www.cisa.gov/sites/default/files/feeds/";
+
+/// File name of the JSON catalog, relative to ``CISA_KEV_URL``.
+pub const KEV_CATALOG: &str = "known_exploited_vulnerabilities.json";
+
+/// CISA's catalog of the vulnerabilities known to be exploited in the wild.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct KevCatalog {
+    #[serde(default)]
+    pub title: String,
+
+    /// Version of the catalog, such as ``2024.06.12``.
+    #[serde(default)]
+    pub catalog_version: String,
+
+    #[serde(default)]
+    pub date_released: String,
+
+    pub vulnerabilities: Vec<KnownExploitedVulnerability>,
+}
+
+impl KevCatalog {
+    /// Parse the JSON of a catalog.
+    pub fn from_slice(json: &[u8]) -> Result<Self, serde_json::Error> {
+        serde_json::from_slice(json)
+    }
+}
+
+/// A CVE listed in the Known Exploited Vulnerabilities catalog, along with the action required
+/// of US federal agencies and its due date.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct KnownExploitedVulnerability {
+    /// ID of the CVE, such as ``CVE-2021-44228``.
+    #[serde(rename = "cveID")]
+    pub cve_id: String,
+
+    pub vendor_project: String,
+
+    pub product: String,
+
+    pub vulnerability_name: String,
+
+    /// Date the CVE was added to the catalog, such as ``2021-12-10``.
+    pub date_added: String,
+
+    pub short_description: String,
+
+    pub required_action: String,
+
+    /// Date the required action is due by.
+    pub due_date: String,
+
+    /// ``Known`` if the CVE is known to be used by ransomware campaigns, ``Unknown`` otherwise.
+    #[serde(default)]
+    pub known_ransomware_campaign_use: String,
+
+    #[serde(default)]
+    pub notes: String,
+}
+
+/// Replace the catalog cached with ``catalog``, returning the number of CVEs it lists. CVEs are
+/// recorded whether the cache holds them or not, so the feeds they're in can be synced later.
+pub fn import_kev(config: &CacheConfig, catalog: &KevCatalog) -> Result<usize, CacheError> {
+    writable_schema(config)?;
+
+    let mut conn = open(config)?;
+    let tx = conn.transaction()?;
+    tx.execute("DELETE FROM kev", [])?;
+    {
+        let mut stmt = tx.prepare(
+            "INSERT OR REPLACE INTO kev (cve, date_added, due_date, data) VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for vulnerability in &catalog.vulnerabilities {
+            stmt.execute(params![
+                vulnerability.cve_id,
+                vulnerability.date_added,
+                vulnerability.due_date,
+                serde_json::to_string(vulnerability)?
+            ])?;
+        }
+    }
+    bump_generation(&tx)?;
+    tx.commit()?;
+
+    match conn.close() {
+        Ok(_) => Ok(catalog.vulnerabilities.len()),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Sync the Known Exploited Vulnerabilities catalog from the base URL of ``client``, such as
+/// ``CISA_KEV_URL``, to the local cache, returning the number of CVEs it lists. Searches can then
+/// be limited to the CVEs known to be exploited, and their summaries flag them.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::CacheConfig;
+/// use nvd_cve::client::{BlockingHttpClient, ReqwestBlockingClient};
+/// use nvd_cve::kev::{sync_kev, CISA_KEV_URL};
+///
+/// let config = CacheConfig::new();
+/// let client = ReqwestBlockingClient::new(CISA_KEV_URL, None, None, None);
+///
+/// let listed = sync_kev(&config, &client).unwrap();
+/// println!("{} CVEs are known to be exploited", listed);
+/// ```
+#[cfg(feature = "blocking")]
+pub fn sync_kev(config: &CacheConfig, client: &ReqwestBlockingClient) -> Result<usize, CacheError> {
+    let catalog = KevCatalog::from_slice(&client.get_url(KEV_CATALOG)?)?;
+    import_kev(config, &catalog)
+}
+
+/// Returns the entry of the Known Exploited Vulnerabilities catalog for a CVE, ``None`` if the
+/// catalog doesn't list it or was never synced.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::CacheConfig;
+/// use nvd_cve::kev::get_known_exploited;
+///
+/// let config = CacheConfig::new();
+///
+/// if let Some(kev) = get_known_exploited(&config, "CVE-2021-44228").unwrap() {
+///     println!("{}, due by {}", kev.required_action, kev.due_date);
+/// }
+/// ```
+pub fn get_known_exploited(
+    config: &CacheConfig,
+    id: &str,
+) -> Result<Option<KnownExploitedVulnerability>, CacheError> {
+    readable_schema(config)?;
+
+    let conn = open(config)?;
+
+    // Read-only caches made by older versions have no catalog
+    let mut tbl_stmt =
+        conn.prepare("SELECT name FROM sqlite_master where type = 'table' and name = 'kev'")?;
+    let synced = tbl_stmt.exists([])?;
+    tbl_stmt.finalize()?;
+
+    let data: Option<String> = if synced {
+        conn.query_row("SELECT data FROM kev WHERE cve = ?1", [id], |row| {
+            row.get(0)
+        })
+        .optional()?
+    } else {
+        None
+    };
+    let kev = match data {
+        Some(data) => Some(serde_json::from_str(&data)?),
+        None => None,
+    };
+
+    match conn.close() {
+        Ok(_) => Ok(kev),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
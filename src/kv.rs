@@ -0,0 +1,238 @@
+use crate::cache::{for_each_cve_data, CacheConfig, CacheError};
+use crate::cve::Cve;
+use crate::query::SearchQuery;
+use fst::automaton::{Automaton, Str};
+use fst::{IntoStreamer, Map, MapBuilder, Streamer};
+use memmap2::Mmap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Name of the file of a key-value index mapping each CVE ID to the offset of its record.
+pub const IDS_FILENAME: &str = "cves.fst";
+
+/// Name of the file of a key-value index packing the records of the CVEs.
+pub const VALUES_FILENAME: &str = "cves.bin";
+
+/// First bytes of the values file, followed by the records of the CVEs. A record is the length of
+/// the CVE ID as a little-endian ``u16``, the ID, the length of the JSON data of the CVE as a
+/// little-endian ``u32`` and the data.
+const VALUES_MAGIC: &[u8; 8] = b"NVDCVEKV";
+
+/// Errors related to writing and reading key-value indexes
+#[derive(Debug)]
+pub enum KvIndexError {
+    CacheError(CacheError),
+    IOError(io::Error),
+    FstError(fst::Error),
+    JsonError(serde_json::Error),
+    /// The files aren't a key-value index, or the IDs don't match the values, e.g. because the
+    /// index was read while it was being written
+    InvalidIndex(String),
+}
+
+impl From<CacheError> for KvIndexError {
+    fn from(error: CacheError) -> Self {
+        KvIndexError::CacheError(error)
+    }
+}
+
+impl From<io::Error> for KvIndexError {
+    fn from(error: io::Error) -> Self {
+        KvIndexError::IOError(error)
+    }
+}
+
+impl From<fst::Error> for KvIndexError {
+    fn from(error: fst::Error) -> Self {
+        KvIndexError::FstError(error)
+    }
+}
+
+impl From<serde_json::Error> for KvIndexError {
+    fn from(error: serde_json::Error) -> Self {
+        KvIndexError::JsonError(error)
+    }
+}
+
+/// Write the cached CVEs matching ``query``, all of them for an empty query, to ``dir`` as a
+/// compact read-only key-value index for devices that can't comfortably run SQLite, to be read
+/// with ``KvIndex``. Returns the number of CVEs written.
+///
+/// The index is made of two files: ``cves.fst``, a finite state transducer mapping each CVE ID to
+/// the offset of its record, and ``cves.bin``, packing the records. Each file is written aside and
+/// renamed over the previous one once complete.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::CacheConfig;
+/// use nvd_cve::kv::write_kv_index;
+/// use nvd_cve::query::SearchQuery;
+///
+/// let config = CacheConfig::new();
+///
+/// let cves = write_kv_index(&config, &SearchQuery::default(), "/srv/firmware/nvd").unwrap();
+/// println!("Wrote {} CVEs", cves);
+/// ```
+pub fn write_kv_index<P: AsRef<Path>>(
+    config: &CacheConfig,
+    query: &SearchQuery,
+    dir: P,
+) -> Result<usize, KvIndexError> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir)?;
+
+    let ids_path = dir.join(IDS_FILENAME);
+    let values_path = dir.join(VALUES_FILENAME);
+    let ids_temp_path = dir.join(format!("{}.tmp", IDS_FILENAME));
+    let values_temp_path = dir.join(format!("{}.tmp", VALUES_FILENAME));
+
+    let mut ids = MapBuilder::new(BufWriter::new(File::create(&ids_temp_path)?))?;
+    let mut values = BufWriter::new(File::create(&values_temp_path)?);
+    values.write_all(VALUES_MAGIC)?;
+    let mut offset = VALUES_MAGIC.len() as u64;
+
+    let count = for_each_cve_data(config, query, |id, data| {
+        let id_length = u16::try_from(id.len())
+            .map_err(|_| KvIndexError::InvalidIndex(format!("CVE ID too long: {}", id)))?;
+        let data_length = u32::try_from(data.len())
+            .map_err(|_| KvIndexError::InvalidIndex(format!("{} is too large", id)))?;
+
+        ids.insert(id, offset)?;
+        values.write_all(&id_length.to_le_bytes())?;
+        values.write_all(id.as_bytes())?;
+        values.write_all(&data_length.to_le_bytes())?;
+        values.write_all(data.as_bytes())?;
+        offset += 2 + id.len() as u64 + 4 + data.len() as u64;
+        Ok::<_, KvIndexError>(())
+    })?;
+
+    ids.into_inner()?.flush()?;
+    values.flush()?;
+    drop(values);
+
+    // Records are checked against the ID they're looked up with, so a reader opening the new
+    // values with the previous IDs fails rather than returning the wrong CVE
+    fs::rename(&values_temp_path, &values_path)?;
+    fs::rename(&ids_temp_path, &ids_path)?;
+
+    Ok(count)
+}
+
+/// Read-only key-value index of CVEs written by ``write_kv_index()``, memory mapped so lookups by
+/// ID take microseconds while only the pages they touch are loaded.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::kv::KvIndex;
+///
+/// let index = KvIndex::open("/srv/firmware/nvd").unwrap();
+///
+/// if let Some(cve) = index.get("CVE-2021-44228").unwrap() {
+///     println!("{}: {} references", cve.cve_data_meta.id, cve.references.reference_data.len());
+/// }
+///
+/// for id in index.ids_with_prefix("CVE-2024-") {
+///     println!("{}", id);
+/// }
+/// ```
+pub struct KvIndex {
+    ids: Map<Mmap>,
+    values: Mmap,
+}
+
+impl KvIndex {
+    /// Open the index written to ``dir``.
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self, KvIndexError> {
+        let dir = dir.as_ref();
+
+        // SAFETY: the index is read-only, it is only ever replaced by renaming new files over it,
+        // which leaves the mapped files untouched
+        let ids = unsafe { Mmap::map(&File::open(dir.join(IDS_FILENAME))?)? };
+        let values = unsafe { Mmap::map(&File::open(dir.join(VALUES_FILENAME))?)? };
+
+        if !values.starts_with(VALUES_MAGIC) {
+            return Err(KvIndexError::InvalidIndex(format!(
+                "{} isn't the values of a key-value index",
+                VALUES_FILENAME
+            )));
+        }
+
+        Ok(Self {
+            ids: Map::new(ids)?,
+            values,
+        })
+    }
+
+    /// Number of CVEs in the index.
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Returns ``true`` if the index holds no CVE.
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Returns ``true`` if the index holds the CVE ``id``.
+    pub fn contains(&self, id: &str) -> bool {
+        self.ids.contains_key(id)
+    }
+
+    /// The IDs of the CVEs beginning with ``prefix``, such as ``CVE-2024-``, sorted.
+    pub fn ids_with_prefix(&self, prefix: &str) -> Vec<String> {
+        let mut stream = self
+            .ids
+            .search(Str::new(prefix).starts_with())
+            .into_stream();
+
+        let mut ids = vec![];
+        while let Some((id, _)) = stream.next() {
+            ids.push(String::from_utf8_lossy(id).into_owned());
+        }
+        ids
+    }
+
+    /// The JSON data of the CVE ``id``, as stored in the local cache, borrowed from the mapped
+    /// values without copying them.
+    pub fn get_raw(&self, id: &str) -> Result<Option<&[u8]>, KvIndexError> {
+        let offset = match self.ids.get(id) {
+            Some(offset) => offset as usize,
+            None => return Ok(None),
+        };
+
+        let invalid = || KvIndexError::InvalidIndex(format!("record of {} is corrupted", id));
+
+        let id_length = self.read_length::<2>(offset).ok_or_else(invalid)?;
+        let id_end = offset + 2 + id_length;
+        if self.values.get(offset + 2..id_end) != Some(id.as_bytes()) {
+            return Err(invalid());
+        }
+
+        let data_length = self.read_length::<4>(id_end).ok_or_else(invalid)?;
+        let data_start = id_end + 4;
+        self.values
+            .get(data_start..data_start + data_length)
+            .map(Some)
+            .ok_or_else(invalid)
+    }
+
+    /// The CVE ``id``, or ``None`` if the index doesn't hold it.
+    pub fn get(&self, id: &str) -> Result<Option<Cve>, KvIndexError> {
+        match self.get_raw(id)? {
+            Some(data) => Ok(Some(serde_json::from_slice(data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Read the little-endian length of ``N`` bytes at ``offset`` of the values
+    fn read_length<const N: usize>(&self, offset: usize) -> Option<usize> {
+        let bytes = self.values.get(offset..offset + N)?;
+        Some(
+            bytes
+                .iter()
+                .rev()
+                .fold(0, |length, byte| length << 8 | *byte as usize),
+        )
+    }
+}
@@ -1,11 +1,149 @@
+//! Sync and search a local cached copy of the NIST National Vulnerability Database (NVD).
+//!
+//! Most programs only need the [prelude]:
+//!
+//! ```no_run
+//! use nvd_cve::prelude::*;
+//! ```
+//!
+//! ## Stability
+//!
+//! Everything re-exported from [prelude] follows semantic versioning: items are only added in
+//! minor releases and only removed or changed in major releases. The modules they are defined in
+//! may still be reorganized in minor releases, so prefer importing from the prelude over the full
+//! module paths.
+//!
+//! ## Ordering
+//!
+//! Everything listed from the local cache comes in a stable order, so two listings of the same
+//! cache are identical and diffs between listings only show changes to the data: CVEs and their
+//! summaries are sorted by CVE ID, also when merged from several sources, feeds by name, and
+//! attestations and sync runs from oldest to newest.
+
+/// Other identifiers of CVEs, given by advisories and other vulnerability databases
+pub mod aliases;
+
+/// Signed records of sync runs
+pub mod attestation;
+
 /// Local CVE cache synced from remote feeds
 pub mod cache;
 
 /// Client for fetching remote feeds
 pub mod client;
 
+/// Clocks dating sync runs, replaceable to simulate time passing in tests
+pub mod clock;
+
+/// Field-by-field comparisons of CVE records
+pub mod compare;
+
+/// CPE names and their matching rules
+pub mod cpe;
+
+/// Vendor and product names of the official CPE dictionary
+pub mod cpedict;
+
+/// CPE names matched by the criteria of CPE matches, from the NVD's CPE match feed
+pub mod cpematch;
+
+/// Decompression of CVE feeds
+pub mod compression;
+
+/// CSAF 2.0 advisories of vendors, ingested as aliases and overrides of the CVEs they cover
+pub mod csaf;
+
 /// CVE structures
 pub mod cve;
 
+/// CVSS metrics
+pub mod cvss;
+
+/// MITRE's list of the weaknesses of CVEs
+pub mod cwe;
+
+/// Parsing and formatting of the dates of feeds and of the local cache
+pub mod datetime;
+
+/// Diagnoses of the environment the local cache is synced in
+#[cfg(feature = "blocking")]
+pub mod doctor;
+
+/// FIRST's daily scores of the probability of CVEs being exploited
+pub mod epss;
+
+/// Public exploits referenced by CVEs
+pub mod exploit;
+
 /// Meta(data) files
 pub mod feed;
+
+/// Reduced feeds of the CVEs matching a query
+pub mod filter;
+
+/// History of the sync runs of the local cache, and of the changes made to CVEs by the NVD
+pub mod history;
+
+/// CISA's catalog of the CVEs known to be exploited in the wild
+pub mod kev;
+
+/// Compact read-only key-value indexes of the local cache
+pub mod kv;
+
+/// Applicability of CVEs to platforms from the CPE matches of their configurations
+pub mod matching;
+
+/// Prometheus metrics of the local cache, written after each sync
+pub mod metrics;
+
+/// MISP feeds of the local cache
+pub mod misp;
+
+/// OSV advisories of ecosystem packages, linked to their CVEs by aliases
+pub mod osv;
+
+/// Local analyst overrides of CVE data
+pub mod overrides;
+
+/// Concurrent stages of a sync
+#[cfg(feature = "blocking")]
+mod pipeline;
+
+/// Search criteria for the local cache
+pub mod query;
+
+/// In-memory cache of search results
+pub mod query_cache;
+
+/// Organisation-specific risk scores of CVEs
+pub mod risk;
+
+/// Offline audits of the packages installed on a host against the local cache
+#[cfg(feature = "scan-host")]
+pub mod scan;
+
+/// Pre-built snapshots of the local cache
+#[cfg(feature = "snapshot")]
+pub mod snapshot;
+
+/// STIX 2.1 exports of the local cache
+pub mod stix;
+
+/// Version comparison across common versioning schemes
+pub mod version;
+
+/// OpenVEX and CSAF VEX documents of host scans
+#[cfg(feature = "scan-host")]
+pub mod vex;
+
+/// Placeholder templates of the lines and tickets printed for CVEs
+pub mod template;
+
+/// Trace files of the stage timings of syncs
+pub mod trace;
+
+/// Tickets opened in issue trackers for the CVEs of a watchlist
+pub mod ticket;
+
+/// Commonly used types and functions
+pub mod prelude;
@@ -1,42 +1,1531 @@
-use clap::clap_app;
+use chrono::{DateTime, Utc};
+use clap::builder::BoolishValueParser;
+use clap::error::ErrorKind;
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use nvd_cve::cache::{PrunePolicy, TimeoutPolicy};
+use nvd_cve::compression::Compression;
+use nvd_cve::cpe::Cpe;
+use nvd_cve::cpedict::NVD_CPE_DICTIONARY_URL;
+use nvd_cve::cpematch::NVD_CPE_MATCH_URL;
+use nvd_cve::cvss::{
+    AttackComplexity, AttackVector, CvssV3Vector, ImpactLevel, PrivilegesRequired, Scope, Severity,
+    UserInteraction,
+};
+use nvd_cve::cwe::MITRE_CWE_URL;
+use nvd_cve::epss::EPSS_URL;
+use nvd_cve::feed::FeedSelector;
+use nvd_cve::history::NVD_CHANGE_HISTORY_URL;
+use nvd_cve::kev::CISA_KEV_URL;
+use nvd_cve::osv::Ecosystem;
+#[cfg(feature = "scan-host")]
+use nvd_cve::scan::PackageManager;
+#[cfg(feature = "scan-host")]
+use nvd_cve::version::VersionScheme;
+use reqwest::Url;
+use std::path::PathBuf;
+use std::time::Duration;
 
 mod cli;
-use cli::{search, sync};
+mod picker;
+mod service;
+#[cfg(feature = "snapshot")]
+use cli::snapshot;
+use cli::{
+    attestations, check, compare, csaf, doctor, export, feeds, filter, history, install_service,
+    override_cve, package, reindex, resolve, search, sync, tickets,
+};
+#[cfg(feature = "scan-host")]
+use cli::{scan_host, ScanFormat};
+use cli::{CveVersion, ExportFormat, Field, LineTemplate};
+use service::ServiceKind;
 
-fn main() {
-    let matches = clap_app!(nvd_cve =>
-        (version: option_env!("CARGO_PKG_VERSION").unwrap_or("?"))
-        (author: option_env!("CARGO_PKG_AUTHORS").unwrap_or("?"))
-        (@subcommand sync =>
-            (about: "Sync CVE feeds to local database")
-            (version: option_env!("CARGO_PKG_VERSION").unwrap_or("?"))
-            (@arg url: -u --url [URL] "URL to use for fetching feeds, defaults to: https://nvd.nist.gov/feeds/json/cve/1.1")
-            (@arg feeds: -l --feeds [LIST] "Comma separated list of CVE feeds to fetch and sync, defaults to: all known feeds")
-            (@arg db: -d --db [FILE] "Path to SQLite database where CVE feed data will be stored")
-            (@arg show: -s --("show-default") "Show default config values and exit")
-            (@arg no_progress: -n --("no-progress") "Don't show progress bar when syncing feeds")
-            (@arg force: -f --force "Ignore existing Metafiles and force update all feeds")
-            (@arg verbose: -v --verbose "Print verbose logs (Set level with RUST_LOG)")
-        )
-        (@subcommand search =>
-            (about: "Search for a CVE by ID in the local cache")
-            (version: option_env!("CARGO_PKG_VERSION").unwrap_or("?"))
-            (@arg CVE: "CVE ID to retrieve")
-            (@arg db: -d --db [FILE] "Path to SQLite database where CVE feed data will be stored")
-            (@arg text: -t --text [STRING] "Search the CVE descriptions instead.")
-            (@arg verbose: -v --verbose "Print verbose logs (Set level with RUST_LOG)")
-        )
-    ).get_matches();
-
-    if let Some(matches) = matches.subcommand_matches("sync") {
-        return sync(matches);
-    }
+/// Search for CVEs against a local cached copy of NIST National Vulnerability Database (NVD).
+#[derive(Parser)]
+#[command(version, author, propagate_version = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
 
-    if let Some(matches) = matches.subcommand_matches("search") {
-        return search(matches);
-    }
+#[derive(Subcommand)]
+enum Command {
+    /// Sync CVE feeds to local database
+    #[command(after_help = SYNC_EXAMPLES)]
+    Sync(Box<SyncArgs>),
+
+    /// Search for a CVE by ID in the local cache
+    #[command(after_help = SEARCH_EXAMPLES)]
+    Search(SearchArgs),
+
+    /// Compare two cached CVEs, or two versions of a CVE, field by field as JSON
+    #[command(after_help = COMPARE_EXAMPLES)]
+    Compare(CompareArgs),
+
+    /// Export the cached CVEs matching a query, or all of them, as JSON, a STIX 2.1 bundle, a MISP feed or a key-value index
+    #[command(after_help = EXPORT_EXAMPLES)]
+    Export(ExportArgs),
+
+    /// Write a reduced feed and Metafile of the CVEs matching a query, e.g. for devices that only need a slice of the NVD
+    #[command(after_help = FILTER_EXAMPLES)]
+    Filter(FilterArgs),
+
+    /// Open a ticket in Jira or through a webhook for each cached CVE matching a watchlist query, once per CVE
+    #[command(after_help = TICKETS_EXAMPLES)]
+    Tickets(TicketsArgs),
+
+    /// Set, show or clear the local override of a CVE, which is kept across syncs
+    #[command(after_help = OVERRIDE_EXAMPLES)]
+    Override(OverrideArgs),
+
+    /// Ingest CSAF 2.0 vendor advisories as aliases of their CVEs and overrides of the affectedness stated for the platforms run
+    #[command(after_help = CSAF_EXAMPLES)]
+    Csaf(CsafArgs),
+
+    /// Search the OSV advisories of an ecosystem package synced with sync --osv, along with their CVEs
+    #[command(after_help = PACKAGE_EXAMPLES)]
+    Package(PackageArgs),
+
+    /// Resolve a CVE, GHSA or OSV ID to every record the local cache holds of the vulnerability, as JSON
+    #[command(after_help = RESOLVE_EXAMPLES)]
+    Resolve(ResolveArgs),
+
+    /// Rebuild the indexes of the local cache, e.g. after it was corrupted
+    Reindex(ReindexArgs),
+
+    /// List the feeds synced to the local cache with the details of their Metafile
+    Feeds(FeedsArgs),
+
+    /// Export the signed attestations of the syncs recorded in the local cache as JSON
+    #[command(after_help = ATTESTATIONS_EXAMPLES)]
+    Attestations(AttestationsArgs),
+
+    /// Export the history of the syncs of the local cache as JSON, with the CVEs changed by each feed, or the change log of a CVE
+    #[command(after_help = HISTORY_EXAMPLES)]
+    History(HistoryArgs),
+
+    /// Check that the local cache is consistent and was recently synced, e.g. for liveness probes
+    #[command(after_help = CHECK_EXAMPLES)]
+    Check(CheckArgs),
+
+    /// Diagnose why syncing or searching fails: the network path to the feeds, disk space, permissions, schema and features
+    #[command(after_help = DOCTOR_EXAMPLES)]
+    Doctor(DoctorArgs),
+
+    /// Write a systemd service and timer, cron entry or Windows task that periodically syncs
+    #[command(after_help = INSTALL_SERVICE_EXAMPLES)]
+    InstallService(InstallServiceArgs),
+
+    /// Scan the packages installed on this host or in a container image for CVEs of the local cache, entirely offline
+    #[cfg(feature = "scan-host")]
+    #[command(after_help = SCAN_HOST_EXAMPLES)]
+    ScanHost(ScanHostArgs),
+
+    /// Publish or fetch pre-built snapshots of the local cache
+    #[cfg(feature = "snapshot")]
+    #[command(subcommand, after_help = SNAPSHOT_EXAMPLES)]
+    Snapshot(SnapshotCommand),
+}
+
+#[cfg(feature = "snapshot")]
+#[derive(Subcommand)]
+pub enum SnapshotCommand {
+    /// Write a snapshot of the local cache, a Zstandard compressed tar of the database and a manifest
+    Publish(SnapshotPublishArgs),
+
+    /// Replace the local cache with a snapshot, once verified against the checksum of its manifest
+    Fetch(SnapshotFetchArgs),
+
+    /// Write a diff of the CVEs changed between two snapshots, to be fetched with fetch --diff
+    Diff(SnapshotDiffArgs),
+}
+
+const SYNC_EXAMPLES: &str = "Examples:
+  nvd_cve sync
+  nvd_cve sync -l 2022,recent,modified -n
+  nvd_cve sync -l 2018..,recent,modified
+  nvd_cve sync --source internal -u https://mirror.example.com/nvd/feeds/json/cve/1.1/
+  nvd_cve sync --osv crates.io,PyPI,npm
+  nvd_cve sync --max-db-size 512M --prune lowest-severity
+  nvd_cve sync --show-default > nvd_cve.toml";
+
+const SEARCH_EXAMPLES: &str = "Examples:
+  nvd_cve search CVE-2021-44228
+  nvd_cve search 'CVE-2021-442*'
+  nvd_cve search --auto-fetch CVE-2016-5195
+  nvd_cve search --full CVE-2021-44228
+  nvd_cve search CVE-2021-44228 --as-of 2021-12-11
+  nvd_cve search --online CVE-2016-5195
+  nvd_cve search -t 'insulin pump'
+  nvd_cve search 'CVE-2021-*' -t openssl
+  nvd_cve search --vector AV:N/AC:L/PR:N --min-impact 5.9
+  nvd_cve search 'CVE-2021-*' --fields id,score,published | sort -t $'\\t' -k2 -rn
+  nvd_cve search --by-risk --min-risk 12
+  nvd_cve search -t openssl -f id,severity,description --delimiter ';'";
+
+const COMPARE_EXAMPLES: &str = "Examples:
+  nvd_cve compare CVE-2021-44228 CVE-2021-45046
+  nvd_cve compare CVE-2021-44228@2021-12-11 CVE-2021-44228";
+
+const EXPORT_EXAMPLES: &str = "Examples:
+  nvd_cve export > cves.json
+  nvd_cve export --format stix --min-severity HIGH > bundle.json
+  nvd_cve export -f stix -p CVE-2024- --has-public-exploit
+  nvd_cve export -f misp -o /srv/www/misp/nvd/ --min-severity CRITICAL
+  nvd_cve export -f kv -o ./firmware/nvd/ -p CVE-2020-";
+
+const FILTER_EXAMPLES: &str = "Examples:
+  nvd_cve filter -t openssl /srv/www/nvd/
+  nvd_cve filter -l 2020..,recent,modified -t nginx --min-severity HIGH /srv/www/nvd/
+  nvd_cve filter -p CVE-2024- --vector AV:N/PR:N -c zip ./feeds/
+
+Writes nvdcve-1.1-custom.json.gz and nvdcve-1.1-custom.meta to the directory, which can be synced \
+with: nvd_cve sync -u URL -l custom";
+
+const TICKETS_EXAMPLES: &str = "Examples:
+  nvd_cve sync && nvd_cve tickets --webhook https://hooks.example.com/nvd -t openssl --min-severity HIGH
+  nvd_cve tickets --jira https://jira.example.com/ --project SEC -t nginx --has-public-exploit
+  nvd_cve tickets --jira https://jira.example.com/ --project SEC -p CVE-2024- --summary 'Patch {id}'
+
+The Authorization header of the requests, e.g. 'Bearer TOKEN', is read from NVD_CVE_TICKET_AUTHORIZATION. \
+CVEs that already have a ticket in the webhook or Jira project are skipped.";
+
+const OVERRIDE_EXAMPLES: &str = "Examples:
+  nvd_cve override CVE-2021-44228 --affected no --note 'Only the log4j-api jar is deployed'
+  nvd_cve override CVE-2021-44228 --tags internet-facing,pci
+  nvd_cve override CVE-2021-44228
+  nvd_cve override CVE-2021-44228 --clear";
+
+const CSAF_EXAMPLES: &str = "Examples:
+  nvd_cve csaf rhsa-2023_1405.json --platform cpe:/o:redhat:enterprise_linux:9
+  nvd_cve csaf --provider https://access.redhat.com/security/data/csaf/v2/provider-metadata.json \\
+    --since 2024-01-01 --platform cpe:/o:redhat:enterprise_linux:9,cpe:/a:redhat:openshift:4
+
+Each CVE a product run is affected by, per any advisory, is overridden as affected, and each CVE \
+of products run that are only fixed or not affected as not affected. Overrides set with the \
+override command are left as they are.";
+
+const PACKAGE_EXAMPLES: &str = "Examples:
+  nvd_cve package crates.io openssl 0.10.45
+  nvd_cve package PyPI Flask_Login
+  nvd_cve package npm lodash 4.17.15 --json
+
+Prints the ID, CVE IDs and summary of each advisory, separated by tabs.";
+
+const RESOLVE_EXAMPLES: &str = "Examples:
+  nvd_cve resolve CVE-2021-23337
+  nvd_cve resolve GHSA-35jh-r3h4-6jhm
+  nvd_cve resolve RHSA-2023:1234 -S internal,nvd
+
+Prints the CVE IDs the ID resolves to, their aliases and the records of the CVEs and OSV \
+advisories, each along with its source.";
+
+const ATTESTATIONS_EXAMPLES: &str = "Examples:
+  nvd_cve sync --attestation-key /etc/nvd_cve/attestation.key
+  nvd_cve attestations > attestations.json
+  nvd_cve attestations --verify /etc/nvd_cve/attestation.key";
+
+const HISTORY_EXAMPLES: &str = "Examples:
+  nvd_cve history > history.json
+  nvd_cve history --source internal
+  nvd_cve sync --change-history && nvd_cve history --cve CVE-2021-44228
+  nvd_cve history --cve CVE-2021-44228 --remote";
+
+const CHECK_EXAMPLES: &str = "Examples:
+  nvd_cve check --max-age 48h
+  nvd_cve check --source internal --max-age 2d
+
+Exits 0 if the cache is healthy, 1 if it is stale or was never synced and 2 if it is inconsistent \
+or can't be read.";
+
+const DOCTOR_EXAMPLES: &str = "Examples:
+  nvd_cve doctor
+  nvd_cve doctor -C /etc/nvd_cve/nvd_cve.toml
+  nvd_cve doctor --url https://mirror.example.com/nvd/ --db /srv/nvd/nvd.sqlite3
+  nvd_cve doctor --json
+
+Exits 0 if every check passed, 1 if some only warned and 2 if any failed.";
+
+const INSTALL_SERVICE_EXAMPLES: &str = "Examples:
+  nvd_cve install-service -C /etc/nvd_cve/nvd_cve.toml -o /etc/systemd/system
+  nvd_cve install-service --kind cron --interval 12h
+  nvd_cve install-service --kind windows -C C:\\nvd_cve\\nvd_cve.toml";
+
+#[cfg(feature = "scan-host")]
+const SCAN_HOST_EXAMPLES: &str = "Examples:
+  nvd_cve scan-host
+  nvd_cve scan-host --manager dpkg --dictionary site-packages.toml
+  nvd_cve scan-host --json > findings.json
+  syft nginx:1.25 -o json | nvd_cve scan-host --input -
+  docker image inspect nginx:1.25 > nginx.json && nvd_cve scan-host -i nginx.json
+  nvd_cve scan-host --suppressions accepted.json --fail-on-findings
+  nvd_cve scan-host --suppressions accepted.json -f openvex --author 'Example Corp' > scan.openvex.json";
+
+#[cfg(feature = "snapshot")]
+const SNAPSHOT_EXAMPLES: &str = "Examples:
+  nvd_cve snapshot publish /srv/www/nvd.tar.zst
+  nvd_cve snapshot fetch https://mirror.example.com/nvd.tar.zst
+  nvd_cve snapshot diff nvd-1.tar.zst nvd-2.tar.zst /srv/www/nvd-1-2.diff.tar.zst
+  nvd_cve snapshot fetch --diff https://mirror.example.com/nvd-1-2.diff.tar.zst";
+
+#[derive(Args)]
+pub struct SyncArgs {
+    /// URL to use for fetching feeds, defaults to: https://nvd.nist.gov/feeds/json/cve/1.1
+    #[arg(short, long, value_name = "URL")]
+    pub url: Option<Url>,
+
+    /// Comma separated list of CVE feeds to fetch and sync, ranges of years like 2018.. included, defaults to: all known feeds
+    #[arg(short = 'l', long, value_name = "LIST", value_delimiter = ',')]
+    pub feeds: Option<Vec<FeedSelector>>,
+
+    /// Path to SQLite database where CVE feed data will be stored
+    #[arg(short, long, value_name = "FILE")]
+    pub db: Option<PathBuf>,
+
+    /// Name of the source being synced, each source is cached in its own database, defaults to: nvd
+    #[arg(
+        short = 'S',
+        long,
+        value_name = "NAME",
+        conflicts_with = "db",
+        value_parser = cli::parse_source
+    )]
+    pub source: Option<String>,
+
+    /// Namespace whose risk scores are computed from the asset tags of its overrides, e.g. a
+    /// business unit, defaults to: the default namespace
+    #[arg(long, value_name = "NAME", value_parser = cli::parse_namespace)]
+    pub namespace: Option<String>,
+
+    /// Path to a TOML config file, options given on the command line take precedence over it
+    #[arg(short = 'C', long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Print the default config as TOML and exit, e.g. to create a config file
+    #[arg(short, long = "show-default")]
+    pub show: bool,
+
+    /// Don't show progress bar when syncing feeds
+    #[arg(short, long)]
+    pub no_progress: bool,
+
+    /// Ignore existing Metafiles and force update all feeds
+    #[arg(short, long)]
+    pub force: bool,
+
+    /// Compression format of the feeds: gzip, zip or zstd, defaults to: gzip
+    #[arg(short, long, value_name = "FORMAT")]
+    pub compression: Option<Compression>,
+
+    /// Number of threads used to parse each feed, defaults to: one per CPU
+    #[arg(short = 'j', long, value_name = "N")]
+    pub threads: Option<usize>,
+
+    /// Time to wait when connecting to the feed server, e.g. 30s, 500ms or 2m
+    #[arg(long, value_name = "DURATION", value_parser = cli::parse_duration)]
+    pub connect_timeout: Option<Duration>,
+
+    /// Connect directly, ignoring the HTTP_PROXY, HTTPS_PROXY, ALL_PROXY and NO_PROXY environment variables
+    #[arg(long)]
+    pub no_proxy: bool,
+
+    /// Accept any TLS certificate, e.g. for a lab mirror with a self-signed certificate. Insecure, anyone on the network path could tamper with the feeds
+    #[arg(long)]
+    pub tls_insecure: bool,
+
+    /// Number of redirects in a row to follow before failing, 0 to follow none, defaults to: 10
+    #[arg(long, value_name = "N")]
+    pub max_redirects: Option<usize>,
+
+    /// Comma separated list of the only hosts the sync may contact, even when redirected, defaults to: any host
+    #[arg(long, value_name = "LIST", value_delimiter = ',')]
+    pub allow_hosts: Option<Vec<String>>,
+
+    /// Longest time fetching each feed may take, e.g. 10m, defaults to: no limit
+    #[arg(long, value_name = "DURATION", value_parser = cli::parse_duration)]
+    pub feed_timeout: Option<Duration>,
+
+    /// Longest time the sync may spend fetching feeds, e.g. 1h, defaults to: no limit
+    #[arg(long, value_name = "DURATION", value_parser = cli::parse_duration)]
+    pub deadline: Option<Duration>,
+
+    /// What to do when a feed times out: abort the sync, or continue and skip the feed, defaults to: abort
+    #[arg(long, value_name = "POLICY")]
+    pub on_timeout: Option<TimeoutPolicy>,
+
+    /// Path to a key file used to sign an attestation of the sync, recorded in the local cache
+    #[arg(long, value_name = "FILE")]
+    pub attestation_key: Option<PathBuf>,
+
+    /// Also sync the change events of the CVEs from the NVD's CVE Change History API, or the one at URL, for history --cve
+    #[arg(
+        long,
+        value_name = "URL",
+        num_args = 0..=1,
+        default_missing_value = NVD_CHANGE_HISTORY_URL
+    )]
+    pub change_history: Option<Url>,
+
+    /// Also sync the NVD's CPE match feed, or the one at URL, so that the CPE names its criteria expand to are matched
+    #[arg(
+        long,
+        value_name = "URL",
+        num_args = 0..=1,
+        default_missing_value = NVD_CPE_MATCH_URL
+    )]
+    pub cpe_match: Option<Url>,
+
+    /// Also sync the NVD's official CPE dictionary, or the one at URL, so that the vendor and product names searched are checked
+    #[arg(
+        long,
+        value_name = "URL",
+        num_args = 0..=1,
+        default_missing_value = NVD_CPE_DICTIONARY_URL
+    )]
+    pub cpe_dictionary: Option<Url>,
+
+    /// Also sync CISA's Known Exploited Vulnerabilities catalog, or the one at URL, for search --kev-only
+    #[arg(
+        long,
+        value_name = "URL",
+        num_args = 0..=1,
+        default_missing_value = CISA_KEV_URL
+    )]
+    pub kev: Option<Url>,
+
+    /// Also sync MITRE's CWE list, or the one at URL, naming the weaknesses of CVEs printed by search --full
+    #[arg(
+        long,
+        value_name = "URL",
+        num_args = 0..=1,
+        default_missing_value = MITRE_CWE_URL
+    )]
+    pub cwe_list: Option<Url>,
+
+    /// Also sync FIRST's daily EPSS scores, or those at URL, for search --min-epss
+    #[arg(
+        long,
+        value_name = "URL",
+        num_args = 0..=1,
+        default_missing_value = EPSS_URL
+    )]
+    pub epss: Option<Url>,
+
+    /// Comma separated list of ecosystems whose OSV advisories are also synced, for the package command: crates.io, PyPI, npm
+    #[arg(long, value_name = "LIST", value_delimiter = ',')]
+    pub osv: Option<Vec<Ecosystem>>,
+
+    /// Base URL of the OSV data dumps, defaults to: https://osv-vulnerabilities.storage.googleapis.com/
+    #[arg(long, value_name = "URL", requires = "osv")]
+    pub osv_url: Option<Url>,
+
+    /// Keep the version of each CVE the sync replaces with a modified one, for search --as-of
+    #[arg(long)]
+    pub track_history: bool,
+
+    /// Largest size the cache may take once synced, e.g. 500M or 2G, CVEs are pruned until it fits, defaults to: no limit
+    #[arg(long, value_name = "SIZE", value_parser = cli::parse_size)]
+    pub max_db_size: Option<u64>,
+
+    /// Which CVEs are pruned first to fit --max-db-size: oldest, the CVEs published first, or lowest-severity, defaults to: oldest
+    #[arg(long, value_name = "POLICY", requires = "max_db_size")]
+    pub prune: Option<PrunePolicy>,
+
+    /// Log how long each feed spent downloading, decompressing, parsing and being written
+    #[arg(long)]
+    pub trace_feeds: bool,
+
+    /// Write the timings of each feed to FILE, as folded stacks for flamegraphs if it ends in .folded, otherwise as a Chrome trace
+    #[arg(long, value_name = "FILE")]
+    pub trace: Option<PathBuf>,
+
+    /// Write Prometheus metrics of the cache and of this sync to FILE once it ends, for the textfile collector of node_exporter
+    #[arg(long, value_name = "FILE")]
+    pub metrics_file: Option<PathBuf>,
+
+    /// Log the URL, status, timing and size of each HTTP request
+    #[arg(long)]
+    pub debug_http: bool,
+
+    /// Print verbose logs (Set level with RUST_LOG)
+    #[arg(short, long)]
+    pub verbose: bool,
+}
+
+#[derive(Args)]
+pub struct SearchArgs {
+    /// CVE ID to retrieve, or list all CVE IDs beginning with it when it ends with '*'
+    #[arg(value_name = "CVE", required_unless_present_any = ["text", "criteria"])]
+    pub cve: Option<String>,
+
+    /// Path to SQLite database where CVE feed data will be stored
+    #[arg(short, long, value_name = "FILE")]
+    pub db: Option<PathBuf>,
+
+    /// Comma separated list of sources to search, results are merged and CVE IDs are retrieved
+    /// from the first source containing them
+    #[arg(
+        short = 'S',
+        long,
+        value_name = "LIST",
+        conflicts_with = "db",
+        value_delimiter = ',',
+        value_parser = cli::parse_source
+    )]
+    pub source: Option<Vec<String>>,
+
+    /// Namespace of the overrides and risk scores read, e.g. a business unit, defaults to: the
+    /// default namespace
+    #[arg(long, value_name = "NAME", value_parser = cli::parse_namespace)]
+    pub namespace: Option<String>,
+
+    /// Search the CVE IDs and descriptions instead, or only those of CVEs beginning with a CVE ID
+    /// ending with '*'
+    #[arg(short, long, value_name = "STRING")]
+    pub text: Option<String>,
+
+    /// Open the local cache strictly read-only, e.g. a cache distributed as an immutable artifact
+    #[arg(short = 'r', long)]
+    pub read_only: bool,
+
+    /// Sync the yearly feed of a CVE ID missing from the cache, then search for it again
+    #[arg(long, group = "fetch", requires = "cve", conflicts_with = "read_only")]
+    pub auto_fetch: bool,
+
+    /// Look the CVE ID up in its yearly feed, fetched without syncing it to the cache
+    #[arg(
+        long,
+        group = "fetch",
+        requires = "cve",
+        conflicts_with_all = ["db", "source", "text", "criteria", "read_only"]
+    )]
+    pub online: bool,
+
+    /// URL to use for fetching feeds with --auto-fetch or --online, defaults to: https://nvd.nist.gov/feeds/json/cve/1.1
+    #[arg(short, long, value_name = "URL", requires = "fetch")]
+    pub url: Option<Url>,
+
+    /// Connect directly with --auto-fetch or --online, ignoring the HTTP_PROXY, HTTPS_PROXY, ALL_PROXY and NO_PROXY environment variables
+    #[arg(long, requires = "fetch")]
+    pub no_proxy: bool,
+
+    /// Accept any TLS certificate with --auto-fetch or --online, e.g. for a lab mirror with a self-signed certificate. Insecure
+    #[arg(long, requires = "fetch")]
+    pub tls_insecure: bool,
+
+    /// Retrieve the version of the CVE that was current at this date, e.g. 2023-06-01 (midnight
+    /// UTC) or 2023-06-01T14:30:00Z, among those kept by syncs with --track-history
+    #[arg(
+        long,
+        value_name = "DATE",
+        value_parser = cli::parse_as_of,
+        requires = "cve",
+        conflicts_with_all = ["text", "criteria", "fetch", "fields"]
+    )]
+    pub as_of: Option<DateTime<Utc>>,
+
+    /// List the scored CVEs from the highest risk score to the lowest, each followed by its score
+    /// unless --fields is given
+    #[arg(long, conflicts_with_all = ["fetch", "as_of"])]
+    pub by_risk: bool,
+
+    /// Print the full record of the CVE: along with the CVE, its CVSS metrics, the configurations
+    /// of the products it affects, its weaknesses and its dates
+    #[arg(
+        long,
+        requires = "cve",
+        conflicts_with_all = ["text", "criteria", "online", "as_of", "by_risk", "fields"]
+    )]
+    pub full: bool,
+
+    /// Comma separated list of fields to print for each CVE listed instead of its ID: id,
+    /// severity, score, published, description, kev or epss
+    #[arg(
+        short,
+        long,
+        value_name = "LIST",
+        value_delimiter = ',',
+        conflicts_with = "fetch"
+    )]
+    pub fields: Option<Vec<Field>>,
+
+    /// Separator of the fields printed with --fields, \t stands for a tab
+    #[arg(
+        long,
+        value_name = "STRING",
+        default_value = "\\t",
+        hide_default_value = true,
+        requires = "fields",
+        value_parser = cli::parse_delimiter
+    )]
+    pub delimiter: String,
+
+    /// Template of the line printed for each CVE listed instead of its ID, where {id}, {severity},
+    /// {score}, {published}, {description}, {kev} and {epss} are replaced by those of the CVE, e.g.
+    /// "{id}\t{score}\t{description:.80}", see FORMAT STRINGS in the man page
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        conflicts_with_all = ["fields", "fetch", "as_of", "full"]
+    )]
+    pub format_str: Option<LineTemplate>,
+
+    /// Pick one of the CVEs listed from an interactive fuzzy finder on the terminal, then print
+    /// its full record
+    #[arg(
+        long,
+        conflicts_with_all = ["fields", "format_str", "fetch", "as_of", "full", "by_risk", "online"]
+    )]
+    pub pick: bool,
+
+    /// Print verbose logs (Set level with RUST_LOG)
+    #[arg(short, long)]
+    pub verbose: bool,
+
+    #[command(flatten)]
+    pub criteria: CriteriaArgs,
+}
+
+#[derive(Args)]
+pub struct CompareArgs {
+    /// CVE compared from, followed by @DATE for the version current at that date among those kept
+    /// by syncs with --track-history, e.g. CVE-2021-44228@2021-12-11
+    #[arg(value_name = "CVE")]
+    pub cve: CveVersion,
+
+    /// CVE compared to, optionally followed by @DATE
+    #[arg(value_name = "OTHER")]
+    pub other: CveVersion,
+
+    /// Path to SQLite database where CVE feed data will be stored
+    #[arg(short, long, value_name = "FILE")]
+    pub db: Option<PathBuf>,
+
+    /// Name of the source whose CVEs are compared, defaults to: nvd
+    #[arg(
+        short = 'S',
+        long,
+        value_name = "NAME",
+        conflicts_with = "db",
+        value_parser = cli::parse_source
+    )]
+    pub source: Option<String>,
+
+    /// Open the local cache strictly read-only, e.g. a cache distributed as an immutable artifact
+    #[arg(short = 'r', long)]
+    pub read_only: bool,
+}
+
+#[derive(Args)]
+pub struct ExportArgs {
+    /// Format of the export: json, an array of the CVEs as cached, stix, a STIX 2.1 bundle of vulnerability objects, misp, a MISP feed of an event per CVE, or kv, a compact read-only key-value index
+    #[arg(short, long, value_name = "FORMAT", default_value = "json")]
+    pub format: ExportFormat,
+
+    /// File to write the export to instead of the standard output, or directory of the feed or index with --format misp or kv
+    #[arg(short, long, value_name = "PATH")]
+    pub output: Option<PathBuf>,
+
+    /// Only CVEs whose ID or description contains this text
+    #[arg(short, long, value_name = "STRING")]
+    pub text: Option<String>,
+
+    /// Only CVEs whose ID begins with this, e.g. CVE-2024-
+    #[arg(short, long, value_name = "CVE")]
+    pub prefix: Option<String>,
+
+    /// Path to SQLite database where CVE feed data will be stored
+    #[arg(short, long, value_name = "FILE")]
+    pub db: Option<PathBuf>,
+
+    /// Name of the source whose CVEs are exported, defaults to: nvd
+    #[arg(
+        short = 'S',
+        long,
+        value_name = "NAME",
+        conflicts_with = "db",
+        value_parser = cli::parse_source
+    )]
+    pub source: Option<String>,
+
+    /// Namespace of the risk scores matched by --min-risk, defaults to: the default namespace
+    #[arg(long, value_name = "NAME", value_parser = cli::parse_namespace)]
+    pub namespace: Option<String>,
+
+    /// Open the local cache strictly read-only, e.g. a cache distributed as an immutable artifact
+    #[arg(short = 'r', long)]
+    pub read_only: bool,
+
+    #[command(flatten)]
+    pub criteria: CriteriaArgs,
+}
+
+#[derive(Args)]
+pub struct FilterArgs {
+    /// Directory to write the feed and its Metafile to
+    #[arg(value_name = "DIR")]
+    pub output: PathBuf,
+
+    /// Only CVEs whose ID or description contains this text
+    #[arg(short, long, value_name = "STRING", required_unless_present_any = ["prefix", "criteria"])]
+    pub text: Option<String>,
+
+    /// Only CVEs whose ID begins with this, e.g. CVE-2024-
+    #[arg(short, long, value_name = "CVE")]
+    pub prefix: Option<String>,
+
+    /// URL to use for fetching feeds, defaults to: https://nvd.nist.gov/feeds/json/cve/1.1
+    #[arg(short, long, value_name = "URL")]
+    pub url: Option<Url>,
+
+    /// Comma separated list of CVE feeds to filter, ranges of years like 2018.. included, defaults to: all known feeds
+    #[arg(short = 'l', long, value_name = "LIST", value_delimiter = ',')]
+    pub feeds: Option<Vec<FeedSelector>>,
+
+    /// Compression format of the feeds fetched and written: gzip, zip or zstd, defaults to: gzip
+    #[arg(short, long, value_name = "FORMAT")]
+    pub compression: Option<Compression>,
+
+    /// Time to wait when connecting to the feed server, e.g. 30s, 500ms or 2m
+    #[arg(long, value_name = "DURATION", value_parser = cli::parse_duration)]
+    pub connect_timeout: Option<Duration>,
+
+    /// Connect directly, ignoring the HTTP_PROXY, HTTPS_PROXY, ALL_PROXY and NO_PROXY environment variables
+    #[arg(long)]
+    pub no_proxy: bool,
+
+    /// Accept any TLS certificate, e.g. for a lab mirror with a self-signed certificate. Insecure, anyone on the network path could tamper with the feeds
+    #[arg(long)]
+    pub tls_insecure: bool,
+
+    /// Log the URL, status, timing and size of each HTTP request
+    #[arg(long)]
+    pub debug_http: bool,
+
+    /// Print verbose logs (Set level with RUST_LOG)
+    #[arg(short, long)]
+    pub verbose: bool,
+
+    #[command(flatten)]
+    pub criteria: CriteriaArgs,
+}
+
+#[derive(Args)]
+pub struct TicketsArgs {
+    /// Post each ticket as a JSON object of its cve, summary and description to this webhook
+    #[arg(long, value_name = "URL", required_unless_present = "jira")]
+    pub webhook: Option<Url>,
+
+    /// Create each ticket as an issue of the Jira at this URL, in the project given with --project
+    #[arg(
+        long,
+        value_name = "URL",
+        conflicts_with = "webhook",
+        requires = "project"
+    )]
+    pub jira: Option<Url>,
+
+    /// Key of the Jira project the issues are created in, e.g. SEC
+    #[arg(long, value_name = "KEY", requires = "jira")]
+    pub project: Option<String>,
+
+    /// Type of the Jira issues created, defaults to: Bug
+    #[arg(long, value_name = "TYPE", requires = "jira")]
+    pub issue_type: Option<String>,
+
+    /// Summary of each ticket, where {id}, {severity}, {score}, {published}, {description} and {url} are replaced by those of its CVE, defaults to: {id} ({severity} {score})
+    #[arg(long, value_name = "TEMPLATE")]
+    pub summary: Option<String>,
+
+    /// Description of each ticket, with the same fields as --summary, defaults to: the description, publication date and NVD page of the CVE
+    #[arg(long, value_name = "TEMPLATE")]
+    pub description: Option<String>,
+
+    /// Only CVEs whose ID or description contains this text
+    #[arg(short, long, value_name = "STRING", required_unless_present_any = ["prefix", "criteria"])]
+    pub text: Option<String>,
+
+    /// Only CVEs whose ID begins with this, e.g. CVE-2024-
+    #[arg(short, long, value_name = "CVE")]
+    pub prefix: Option<String>,
+
+    /// Path to SQLite database where CVE feed data will be stored
+    #[arg(short, long, value_name = "FILE")]
+    pub db: Option<PathBuf>,
 
-    eprintln!("Error:\n At least one subcommand required: 'sync' or 'search'\n");
-    eprintln!("{}", matches.usage());
-    std::process::exit(1);
+    /// Name of the source whose CVEs are ticketed, defaults to: nvd
+    #[arg(
+        short = 'S',
+        long,
+        value_name = "NAME",
+        conflicts_with = "db",
+        value_parser = cli::parse_source
+    )]
+    pub source: Option<String>,
+
+    /// Namespace of the overrides and risk scores the watchlist is matched with, defaults to: the
+    /// default namespace
+    #[arg(long, value_name = "NAME", value_parser = cli::parse_namespace)]
+    pub namespace: Option<String>,
+
+    /// Time to wait when connecting to the tracker, e.g. 30s, 500ms or 2m
+    #[arg(long, value_name = "DURATION", value_parser = cli::parse_duration)]
+    pub connect_timeout: Option<Duration>,
+
+    /// Connect directly, ignoring the HTTP_PROXY, HTTPS_PROXY, ALL_PROXY and NO_PROXY environment variables
+    #[arg(long)]
+    pub no_proxy: bool,
+
+    /// Accept any TLS certificate, e.g. for a tracker with a self-signed certificate. Insecure, anyone on the network path could read the Authorization header
+    #[arg(long)]
+    pub tls_insecure: bool,
+
+    /// Print verbose logs (Set level with RUST_LOG)
+    #[arg(short, long)]
+    pub verbose: bool,
+
+    #[command(flatten)]
+    pub criteria: CriteriaArgs,
+}
+
+#[derive(Args)]
+#[group(id = "criteria", multiple = true)]
+#[command(next_help_heading = "CVSS v3 Criteria")]
+pub struct CriteriaArgs {
+    /// Only CVEs with this CVSS v3 attack vector: NETWORK, ADJACENT_NETWORK, LOCAL or PHYSICAL
+    #[arg(long, value_name = "VECTOR")]
+    pub attack_vector: Option<AttackVector>,
+
+    /// Only CVEs with this CVSS v3 attack complexity: LOW or HIGH
+    #[arg(long, value_name = "LEVEL")]
+    pub attack_complexity: Option<AttackComplexity>,
+
+    /// Only CVEs with this CVSS v3 privileges required: NONE, LOW or HIGH
+    #[arg(long, value_name = "LEVEL")]
+    pub privileges_required: Option<PrivilegesRequired>,
+
+    /// Only CVEs with this CVSS v3 user interaction: NONE or REQUIRED
+    #[arg(long, value_name = "UI")]
+    pub user_interaction: Option<UserInteraction>,
+
+    /// Only CVEs exploitable without user interaction
+    #[arg(long, conflicts_with = "user_interaction")]
+    pub no_user_interaction: bool,
+
+    /// Only CVEs with this CVSS v3 scope: UNCHANGED or CHANGED
+    #[arg(long, value_name = "SCOPE")]
+    pub scope: Option<Scope>,
+
+    /// Only CVEs with this CVSS v3 confidentiality impact: NONE, LOW or HIGH
+    #[arg(long, value_name = "LEVEL")]
+    pub confidentiality: Option<ImpactLevel>,
+
+    /// Only CVEs with this CVSS v3 integrity impact: NONE, LOW or HIGH
+    #[arg(long, value_name = "LEVEL")]
+    pub integrity: Option<ImpactLevel>,
+
+    /// Only CVEs with this CVSS v3 availability impact: NONE, LOW or HIGH
+    #[arg(long, value_name = "LEVEL")]
+    pub availability: Option<ImpactLevel>,
+
+    /// Only CVEs matching the components of a full or partial CVSS v3 vector, e.g. AV:N/AC:L/C:H
+    #[arg(long, value_name = "VECTOR")]
+    pub vector: Option<CvssV3Vector>,
+
+    /// Only CVEs with at least this CVSS v3 exploitability subscore
+    #[arg(long, value_name = "SCORE")]
+    pub min_exploitability: Option<f64>,
+
+    /// Only CVEs with at least this CVSS v3 impact subscore
+    #[arg(long, value_name = "SCORE")]
+    pub min_impact: Option<f64>,
+
+    /// Only CVEs with at least this severity, from CVSS v3 or else v2: NONE, LOW, MEDIUM, HIGH or
+    /// CRITICAL
+    #[arg(long, value_name = "SEVERITY")]
+    pub min_severity: Option<Severity>,
+
+    /// Only CVEs with this severity, from CVSS v3 or else v2: NONE, LOW, MEDIUM, HIGH or CRITICAL
+    #[arg(long, value_name = "SEVERITY", conflicts_with = "min_severity")]
+    pub severity: Option<Severity>,
+
+    /// Only CVEs with at least this CVSS base score, from CVSS v3 or else v2, e.g. 9.0
+    #[arg(long, value_name = "SCORE")]
+    pub min_cvss: Option<f64>,
+
+    /// Only CVEs published on or after DATE, e.g. 2024-01-01 or 2024-01-01T12:00:00Z
+    #[arg(long, value_name = "DATE", value_parser = cli::parse_as_of, help_heading = "Date Criteria")]
+    pub published_after: Option<DateTime<Utc>>,
+
+    /// Only CVEs published before DATE
+    #[arg(long, value_name = "DATE", value_parser = cli::parse_as_of, help_heading = "Date Criteria")]
+    pub published_before: Option<DateTime<Utc>>,
+
+    /// Only CVEs last modified on or after DATE
+    #[arg(long, value_name = "DATE", value_parser = cli::parse_as_of, help_heading = "Date Criteria")]
+    pub modified_after: Option<DateTime<Utc>>,
+
+    /// Only CVEs last modified before DATE
+    #[arg(long, value_name = "DATE", value_parser = cli::parse_as_of, help_heading = "Date Criteria")]
+    pub modified_before: Option<DateTime<Utc>>,
+
+    /// Only CVEs assigned by this CNA, e.g. security@apache.org
+    #[arg(long, value_name = "CNA", help_heading = "Product Criteria")]
+    pub assigner: Option<String>,
+
+    /// Only CVEs whose configurations name this CPE vendor, e.g. microsoft
+    #[arg(long, value_name = "VENDOR", help_heading = "Product Criteria")]
+    pub vendor: Option<String>,
+
+    /// Only CVEs whose configurations name this CPE product, e.g. exchange_server, of the vendor
+    /// given with --vendor if any
+    #[arg(long, value_name = "PRODUCT", help_heading = "Product Criteria")]
+    pub product: Option<String>,
+
+    /// Only CVEs referencing this URL or a page beneath it, e.g. an advisory, a repository
+    /// github.com/org/repo or any page of a domain example.com and its subdomains
+    #[arg(long, value_name = "URL", help_heading = "Reference Criteria")]
+    pub reference: Option<String>,
+
+    /// Only CVEs with this weakness listed in their problem types, e.g. CWE-79 or 79
+    #[arg(long, value_name = "CWE", help_heading = "Weakness Criteria")]
+    pub cwe: Option<String>,
+
+    /// Only CVEs referencing a public exploit, in the Exploit Database or the Metasploit Framework
+    #[arg(long, help_heading = "Exploit Criteria")]
+    pub has_public_exploit: bool,
+
+    /// Only CVEs listed in CISA's Known Exploited Vulnerabilities catalog, synced with sync --kev
+    #[arg(long, help_heading = "Exploit Criteria")]
+    pub kev_only: bool,
+
+    /// Only CVEs with at least this EPSS probability of being exploited in the next 30 days, from
+    /// 0 to 1, synced with sync --epss
+    #[arg(long, value_name = "PROBABILITY", help_heading = "Exploit Criteria")]
+    pub min_epss: Option<f64>,
+
+    /// Only CVEs with at least this risk score, computed at sync with the risk_weights of the
+    /// config file
+    #[arg(long, value_name = "SCORE", help_heading = "Risk Criteria")]
+    pub min_risk: Option<f64>,
+}
+
+#[derive(Args)]
+pub struct OverrideArgs {
+    /// CVE ID to override
+    #[arg(value_name = "CVE")]
+    pub cve: String,
+
+    /// Path to SQLite database where CVE feed data will be stored
+    #[arg(short, long, value_name = "FILE")]
+    pub db: Option<PathBuf>,
+
+    /// Name of the source whose cache stores the override, defaults to: nvd
+    #[arg(
+        short = 'S',
+        long,
+        value_name = "NAME",
+        conflicts_with = "db",
+        value_parser = cli::parse_source
+    )]
+    pub source: Option<String>,
+
+    /// Namespace the override is kept in, e.g. a business unit, so that teams sharing a cache each
+    /// keep their own, defaults to: the default namespace
+    #[arg(long, value_name = "NAME", value_parser = cli::parse_namespace)]
+    pub namespace: Option<String>,
+
+    /// Open the local cache strictly read-only, only showing the override
+    #[arg(short = 'r', long, conflicts_with_all = ["severity", "affected", "note", "tags", "clear"])]
+    pub read_only: bool,
+
+    /// Severity to use instead of the feed's: NONE, LOW, MEDIUM, HIGH or CRITICAL
+    #[arg(short, long, value_name = "SEVERITY")]
+    pub severity: Option<Severity>,
+
+    /// Whether the CVE affects internal systems: yes or no
+    #[arg(
+        short,
+        long,
+        value_name = "YES_NO",
+        hide_possible_values = true,
+        value_parser = BoolishValueParser::new()
+    )]
+    pub affected: Option<bool>,
+
+    /// Note explaining the override
+    #[arg(short, long, value_name = "TEXT")]
+    pub note: Option<String>,
+
+    /// Comma separated list of tags of the assets the CVE affects, weighed by the risk_weights of
+    /// the config file, replacing the previous tags. An empty list removes them
+    #[arg(short, long, value_name = "LIST", value_delimiter = ',')]
+    pub tags: Option<Vec<String>>,
+
+    /// Remove the override
+    #[arg(short, long, conflicts_with_all = ["severity", "affected", "note", "tags"])]
+    pub clear: bool,
+}
+
+#[derive(Args)]
+pub struct CsafArgs {
+    /// CSAF 2.0 advisory files to ingest
+    #[arg(value_name = "FILE", required_unless_present = "provider")]
+    pub files: Vec<PathBuf>,
+
+    /// Fetch the advisories listed by the directories of this CSAF provider metadata instead
+    #[arg(long, value_name = "URL", conflicts_with = "files")]
+    pub provider: Option<Url>,
+
+    /// Only fetch the advisories changed since this date, e.g. 2024-01-01 (midnight UTC) or
+    /// 2024-01-01T14:30:00Z
+    #[arg(long, value_name = "DATE", requires = "provider", value_parser = cli::parse_as_of)]
+    pub since: Option<DateTime<Utc>>,
+
+    /// Comma separated list of CPE names of the platforms run, only the statuses of their products
+    /// are recorded, defaults to: every product
+    #[arg(short, long, value_name = "LIST", value_delimiter = ',')]
+    pub platform: Vec<Cpe>,
+
+    /// Path to SQLite database where CVE feed data will be stored
+    #[arg(short, long, value_name = "FILE")]
+    pub db: Option<PathBuf>,
+
+    /// Name of the source whose cache records the advisories, defaults to: nvd
+    #[arg(
+        short = 'S',
+        long,
+        value_name = "NAME",
+        conflicts_with = "db",
+        value_parser = cli::parse_source
+    )]
+    pub source: Option<String>,
+
+    /// Namespace the overrides are ingested to, defaults to: the default namespace
+    #[arg(long, value_name = "NAME", value_parser = cli::parse_namespace)]
+    pub namespace: Option<String>,
+
+    /// Time to wait when connecting to the provider, e.g. 30s, 500ms or 2m
+    #[arg(long, value_name = "DURATION", value_parser = cli::parse_duration)]
+    pub connect_timeout: Option<Duration>,
+
+    /// Connect directly, ignoring the HTTP_PROXY, HTTPS_PROXY, ALL_PROXY and NO_PROXY environment variables
+    #[arg(long)]
+    pub no_proxy: bool,
+
+    /// Accept any TLS certificate, e.g. for a provider with a self-signed certificate. Insecure, anyone on the network path could tamper with the advisories
+    #[arg(long)]
+    pub tls_insecure: bool,
+
+    /// Log the URL, status, timing and size of each HTTP request
+    #[arg(long)]
+    pub debug_http: bool,
+
+    /// Print verbose logs (Set level with RUST_LOG)
+    #[arg(short, long)]
+    pub verbose: bool,
+}
+
+#[derive(Args)]
+pub struct PackageArgs {
+    /// Ecosystem of the package: crates.io, PyPI or npm
+    #[arg(value_name = "ECOSYSTEM")]
+    pub ecosystem: Ecosystem,
+
+    /// Name of the package
+    #[arg(value_name = "PACKAGE")]
+    pub package: String,
+
+    /// Only list the advisories affecting this version of the package, defaults to: any version
+    #[arg(value_name = "VERSION")]
+    pub package_version: Option<String>,
+
+    /// Path to SQLite database where CVE feed data will be stored
+    #[arg(short, long, value_name = "FILE")]
+    pub db: Option<PathBuf>,
+
+    /// Name of the source whose cache is searched, defaults to: nvd
+    #[arg(
+        short = 'S',
+        long,
+        value_name = "NAME",
+        conflicts_with = "db",
+        value_parser = cli::parse_source
+    )]
+    pub source: Option<String>,
+
+    /// Print the advisories as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Args)]
+pub struct ResolveArgs {
+    /// ID to resolve: a CVE ID or an alias of CVEs, such as a GHSA, OSV or vendor advisory ID
+    #[arg(value_name = "ID")]
+    pub id: String,
+
+    /// Path to SQLite database where CVE feed data will be stored
+    #[arg(short, long, value_name = "FILE")]
+    pub db: Option<PathBuf>,
+
+    /// Comma separated list of sources whose caches are merged, each record keeping its source
+    #[arg(
+        short = 'S',
+        long,
+        value_name = "LIST",
+        conflicts_with = "db",
+        value_delimiter = ',',
+        value_parser = cli::parse_source
+    )]
+    pub source: Option<Vec<String>>,
+
+    /// Open the local cache strictly read-only, e.g. a cache distributed as an immutable artifact
+    #[arg(short = 'r', long)]
+    pub read_only: bool,
+}
+
+#[derive(Args)]
+pub struct ReindexArgs {
+    /// Path to SQLite database where CVE feed data will be stored
+    #[arg(short, long, value_name = "FILE")]
+    pub db: Option<PathBuf>,
+
+    /// Name of the source whose cache is reindexed, defaults to: nvd
+    #[arg(
+        short = 'S',
+        long,
+        value_name = "NAME",
+        conflicts_with = "db",
+        value_parser = cli::parse_source
+    )]
+    pub source: Option<String>,
+}
+
+#[derive(Args)]
+pub struct FeedsArgs {
+    /// Path to SQLite database where CVE feed data will be stored
+    #[arg(short, long, value_name = "FILE")]
+    pub db: Option<PathBuf>,
+
+    /// Name of the source whose cached feeds are listed, defaults to: nvd
+    #[arg(
+        short = 'S',
+        long,
+        value_name = "NAME",
+        conflicts_with = "db",
+        value_parser = cli::parse_source
+    )]
+    pub source: Option<String>,
+
+    /// Open the local cache strictly read-only, e.g. a cache distributed as an immutable artifact
+    #[arg(short = 'r', long)]
+    pub read_only: bool,
+}
+
+#[derive(Args)]
+pub struct AttestationsArgs {
+    /// Path to SQLite database where CVE feed data will be stored
+    #[arg(short, long, value_name = "FILE")]
+    pub db: Option<PathBuf>,
+
+    /// Name of the source whose attestations are exported, defaults to: nvd
+    #[arg(
+        short = 'S',
+        long,
+        value_name = "NAME",
+        conflicts_with = "db",
+        value_parser = cli::parse_source
+    )]
+    pub source: Option<String>,
+
+    /// Open the local cache strictly read-only, e.g. a cache distributed as an immutable artifact
+    #[arg(short = 'r', long)]
+    pub read_only: bool,
+
+    /// Verify the signature of each attestation with the key in this file, failing if any is invalid
+    #[arg(long, value_name = "FILE")]
+    pub verify: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct HistoryArgs {
+    /// Path to SQLite database where CVE feed data will be stored
+    #[arg(short, long, value_name = "FILE")]
+    pub db: Option<PathBuf>,
+
+    /// Name of the source whose history is exported, defaults to: nvd
+    #[arg(
+        short = 'S',
+        long,
+        value_name = "NAME",
+        conflicts_with = "db",
+        value_parser = cli::parse_source
+    )]
+    pub source: Option<String>,
+
+    /// Open the local cache strictly read-only, e.g. a cache distributed as an immutable artifact
+    #[arg(short = 'r', long)]
+    pub read_only: bool,
+
+    /// Export the change events of this CVE synced with sync --change-history instead of the syncs
+    #[arg(long, value_name = "CVE")]
+    pub cve: Option<String>,
+
+    /// Fetch the whole official change log of the CVE from the NVD's CVE Change History API instead
+    #[arg(long, requires = "cve")]
+    pub remote: bool,
+
+    /// URL of the CVE Change History API used with --remote, defaults to: https://services.nvd.nist.gov/rest/json/cvehistory/2.0
+    #[arg(short, long, value_name = "URL", requires = "remote")]
+    pub url: Option<Url>,
+
+    /// Connect directly with --remote, ignoring the HTTP_PROXY, HTTPS_PROXY, ALL_PROXY and NO_PROXY environment variables
+    #[arg(long, requires = "remote")]
+    pub no_proxy: bool,
+
+    /// Accept any TLS certificate with --remote. Insecure, as with sync
+    #[arg(long, requires = "remote")]
+    pub tls_insecure: bool,
+}
+
+#[derive(Args)]
+pub struct CheckArgs {
+    /// Path to SQLite database where CVE feed data will be stored
+    #[arg(short, long, value_name = "FILE")]
+    pub db: Option<PathBuf>,
+
+    /// Name of the source whose cache is checked, defaults to: nvd
+    #[arg(
+        short = 'S',
+        long,
+        value_name = "NAME",
+        conflicts_with = "db",
+        value_parser = cli::parse_source
+    )]
+    pub source: Option<String>,
+
+    /// Fail if the last sync finished longer ago than this, e.g. 48h or 2d
+    #[arg(short, long, value_name = "DURATION", value_parser = cli::parse_duration)]
+    pub max_age: Option<Duration>,
+}
+
+#[derive(Args)]
+pub struct DoctorArgs {
+    /// URL of the feeds to check, defaults to: https://nvd.nist.gov/feeds/json/cve/1.1
+    #[arg(short, long, value_name = "URL")]
+    pub url: Option<Url>,
+
+    /// Path to SQLite database where CVE feed data will be stored
+    #[arg(short, long, value_name = "FILE")]
+    pub db: Option<PathBuf>,
+
+    /// Name of the source whose cache is checked, defaults to: nvd
+    #[arg(
+        short = 'S',
+        long,
+        value_name = "NAME",
+        conflicts_with = "db",
+        value_parser = cli::parse_source
+    )]
+    pub source: Option<String>,
+
+    /// Path to the TOML config file syncs use, options given on the command line take precedence over it
+    #[arg(short = 'C', long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Check the cache as opened with --read-only, which needs no write permissions
+    #[arg(long)]
+    pub read_only: bool,
+
+    /// Time to wait when connecting to the feed server, e.g. 30s, 500ms or 2m
+    #[arg(long, value_name = "DURATION", default_value = "10s", value_parser = cli::parse_duration)]
+    pub connect_timeout: Duration,
+
+    /// Connect directly, ignoring the HTTP_PROXY, HTTPS_PROXY, ALL_PROXY and NO_PROXY environment variables
+    #[arg(long)]
+    pub no_proxy: bool,
+
+    /// Print the results as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Args)]
+pub struct InstallServiceArgs {
+    /// Scheduler to write the service for: systemd, cron or windows, defaults to: systemd on Linux,
+    /// windows on Windows and cron elsewhere
+    #[arg(short, long, value_name = "KIND")]
+    pub kind: Option<ServiceKind>,
+
+    /// Path to the TOML config file the service syncs with, defaults to: the default config
+    #[arg(short = 'C', long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Time between two syncs, e.g. 6h or 30m
+    #[arg(short, long, value_name = "DURATION", default_value = "6h", value_parser = cli::parse_duration)]
+    pub interval: Duration,
+
+    /// Directory the service files are written to
+    #[arg(short, long, value_name = "DIR", default_value = ".")]
+    pub output: PathBuf,
+
+    /// Name of the service, used for its files
+    #[arg(short, long, value_name = "NAME", default_value = "nvd_cve-sync")]
+    pub name: String,
+}
+
+#[cfg(feature = "scan-host")]
+#[derive(Args)]
+pub struct ScanHostArgs {
+    /// Path to SQLite database where CVE feed data will be stored
+    #[arg(short, long, value_name = "FILE")]
+    pub db: Option<PathBuf>,
+
+    /// Name of the source whose CVEs are scanned for, defaults to: nvd
+    #[arg(
+        short = 'S',
+        long,
+        value_name = "NAME",
+        conflicts_with = "db",
+        value_parser = cli::parse_source
+    )]
+    pub source: Option<String>,
+
+    /// Open the local cache strictly read-only, e.g. a cache distributed as an immutable artifact
+    #[arg(short = 'r', long)]
+    pub read_only: bool,
+
+    /// Comma separated list of package managers whose packages are scanned, defaults to those of
+    /// the operating system that are installed: dpkg, rpm or homebrew
+    #[arg(short, long, value_name = "LIST", value_delimiter = ',')]
+    pub manager: Option<Vec<PackageManager>>,
+
+    /// Scan the packages listed by the JSON output of syft or of docker image inspect instead, e.g.
+    /// of a container image, or - to read it from the standard input
+    #[arg(short, long, value_name = "FILE", conflicts_with = "manager")]
+    pub input: Option<PathBuf>,
+
+    /// TOML file mapping package names to CPE names in its [packages] table, in addition to the
+    /// built-in dictionary of common packages
+    #[arg(long, value_name = "FILE")]
+    pub dictionary: Option<PathBuf>,
+
+    /// Versioning scheme versions are compared with: auto, semver, debian, rpm or dotted
+    #[arg(long, value_name = "SCHEME", default_value = "auto")]
+    pub scheme: VersionScheme,
+
+    /// JSON or TOML file of accepted findings, listing a cve, component, version, reason and expires
+    /// date for each. Suppressed findings are reported separately until they expire
+    #[arg(long, value_name = "FILE")]
+    pub suppressions: Option<PathBuf>,
+
+    /// Exit with status 1 if any finding isn't suppressed, e.g. to fail a CI job
+    #[arg(long)]
+    pub fail_on_findings: bool,
+
+    /// Format of the results: text, json, the scan with the packages that aren't in the dictionary, openvex, an OpenVEX document, or csaf, a CSAF 2.0 VEX document
+    #[arg(short, long, value_name = "FORMAT", default_value = "text")]
+    pub format: ScanFormat,
+
+    /// Name of the author of VEX documents, e.g. your organization
+    #[arg(long, value_name = "NAME", default_value = env!("CARGO_PKG_NAME"))]
+    pub author: String,
+
+    /// URL identifying the author of CSAF documents, e.g. the website of your organization
+    #[arg(long, value_name = "URL", default_value = env!("CARGO_PKG_REPOSITORY"))]
+    pub namespace: String,
+
+    /// Print the scan as JSON, same as --format json
+    #[arg(long, conflicts_with = "format")]
+    pub json: bool,
+}
+
+#[cfg(feature = "snapshot")]
+#[derive(Args)]
+pub struct SnapshotPublishArgs {
+    /// Path the snapshot is written to, e.g. nvd.tar.zst
+    #[arg(value_name = "FILE")]
+    pub output: PathBuf,
+
+    /// Path to SQLite database where CVE feed data will be stored
+    #[arg(short, long, value_name = "FILE")]
+    pub db: Option<PathBuf>,
+
+    /// Name of the source whose cache is snapshotted, defaults to: nvd
+    #[arg(
+        short = 'S',
+        long,
+        value_name = "NAME",
+        conflicts_with = "db",
+        value_parser = cli::parse_source
+    )]
+    pub source: Option<String>,
+}
+
+#[cfg(feature = "snapshot")]
+#[derive(Args)]
+pub struct SnapshotFetchArgs {
+    /// URL of the snapshot to fetch
+    #[arg(value_name = "URL")]
+    pub url: Url,
+
+    /// The URL is a diff between two snapshots, applied to the snapshot held by the local cache
+    #[arg(long)]
+    pub diff: bool,
+
+    /// Path to SQLite database where CVE feed data will be stored
+    #[arg(short, long, value_name = "FILE")]
+    pub db: Option<PathBuf>,
+
+    /// Name of the source whose cache is replaced, defaults to: nvd
+    #[arg(
+        short = 'S',
+        long,
+        value_name = "NAME",
+        conflicts_with = "db",
+        value_parser = cli::parse_source
+    )]
+    pub source: Option<String>,
+
+    /// Time to wait when connecting to the server, e.g. 30s, 500ms or 2m
+    #[arg(long, value_name = "DURATION", value_parser = cli::parse_duration)]
+    pub connect_timeout: Option<Duration>,
+
+    /// Connect directly, ignoring the HTTP_PROXY, HTTPS_PROXY, ALL_PROXY and NO_PROXY environment variables
+    #[arg(long)]
+    pub no_proxy: bool,
+
+    /// Accept any TLS certificate, e.g. for a lab mirror with a self-signed certificate. Insecure, anyone on the network path could tamper with the feeds
+    #[arg(long)]
+    pub tls_insecure: bool,
+
+    /// Log the URL, status, timing and size of each HTTP request
+    #[arg(long)]
+    pub debug_http: bool,
+}
+
+#[cfg(feature = "snapshot")]
+#[derive(Args)]
+pub struct SnapshotDiffArgs {
+    /// Snapshot held by the caches the diff is applied to
+    #[arg(value_name = "BASE")]
+    pub base: PathBuf,
+
+    /// Snapshot the diff updates the caches to
+    #[arg(value_name = "TARGET")]
+    pub target: PathBuf,
+
+    /// Path the diff is written to, e.g. nvd.diff.tar.zst
+    #[arg(value_name = "FILE")]
+    pub output: PathBuf,
+}
+
+fn main() {
+    match Cli::parse().command {
+        Command::Sync(args) => sync(&args),
+        Command::Search(args) => {
+            // A text search can be limited to CVEs beginning with an ID prefix, but not to one CVE
+            if args.text.is_some() && args.cve.as_ref().is_some_and(|cve| !cve.ends_with('*')) {
+                let mut command = Cli::command();
+                command.build();
+                command
+                    .find_subcommand_mut("search")
+                    .unwrap()
+                    .error(
+                        ErrorKind::ArgumentConflict,
+                        "--text can't be combined with a single CVE ID, end the ID with '*' to \
+                         search only CVEs beginning with it",
+                    )
+                    .exit();
+            }
+            // A version is retrieved for a single CVE, not for each CVE beginning with a prefix
+            if args.as_of.is_some() && args.cve.as_ref().is_some_and(|cve| cve.ends_with('*')) {
+                let mut command = Cli::command();
+                command.build();
+                command
+                    .find_subcommand_mut("search")
+                    .unwrap()
+                    .error(
+                        ErrorKind::ArgumentConflict,
+                        "--as-of retrieves a single CVE, its ID can't end with '*'",
+                    )
+                    .exit();
+            }
+            search(&args)
+        }
+        Command::Compare(args) => compare(&args),
+        Command::Export(args) => {
+            // MISP feeds and key-value indexes are directories of files, they can't be written to
+            // the standard output
+            let written_to = match args.format {
+                ExportFormat::Misp => Some(("misp", "feed")),
+                ExportFormat::Kv => Some(("kv", "index")),
+                _ => None,
+            };
+            if let (Some((format, files)), None) = (written_to, &args.output) {
+                let mut command = Cli::command();
+                command.build();
+                command
+                    .find_subcommand_mut("export")
+                    .unwrap()
+                    .error(
+                        ErrorKind::MissingRequiredArgument,
+                        format!(
+                            "--format {} requires the directory to write the {} to, given with \
+                             --output",
+                            format, files
+                        ),
+                    )
+                    .exit();
+            }
+            export(&args)
+        }
+        Command::Filter(args) => {
+            // Risk scores, the KEV catalog and EPSS scores are kept in the local cache, feeds
+            // don't have them
+            let cache_only = [
+                (
+                    args.criteria.min_risk.is_some(),
+                    "--min-risk can't filter feeds, only the CVEs of the local cache have a risk \
+                     score",
+                ),
+                (
+                    args.criteria.kev_only,
+                    "--kev-only can't filter feeds, only the CVEs of the local cache are looked \
+                     up in the KEV catalog",
+                ),
+                (
+                    args.criteria.min_epss.is_some(),
+                    "--min-epss can't filter feeds, only the CVEs of the local cache have an EPSS \
+                     score",
+                ),
+            ];
+            if let Some((_, message)) = cache_only.iter().find(|(given, _)| *given) {
+                let mut command = Cli::command();
+                command.build();
+                command
+                    .find_subcommand_mut("filter")
+                    .unwrap()
+                    .error(ErrorKind::ArgumentConflict, message)
+                    .exit();
+            }
+            filter(&args)
+        }
+        Command::Tickets(args) => tickets(&args),
+        Command::Override(args) => override_cve(&args),
+        Command::Csaf(args) => csaf(&args),
+        Command::Package(args) => package(&args),
+        Command::Resolve(args) => resolve(&args),
+        Command::Reindex(args) => reindex(&args),
+        Command::Feeds(args) => feeds(&args),
+        Command::Attestations(args) => attestations(&args),
+        Command::History(args) => history(&args),
+        Command::Check(args) => check(&args),
+        Command::Doctor(args) => doctor(&args),
+        Command::InstallService(args) => install_service(&args),
+        #[cfg(feature = "scan-host")]
+        Command::ScanHost(args) => scan_host(&args),
+        #[cfg(feature = "snapshot")]
+        Command::Snapshot(command) => snapshot(&command),
+    }
 }
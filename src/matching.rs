@@ -0,0 +1,502 @@
+use crate::cache::{open, readable_schema, CacheConfig, CacheError};
+use crate::cpe::{Cpe, CpeValue};
+use crate::cpematch::{criteria_expansions, criteria_key, CpeExpansions};
+use crate::cve::{Configuration, CpeMatch, CveContainer, Node, Operator};
+use crate::version::{VersionRange, VersionScheme};
+use rusqlite::{params_from_iter, Connection};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// How sure a match of a platform against the configurations of a CVE is, from the least to the
+/// most certain.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub enum MatchConfidence {
+    /// The CPE match doesn't bound the versions it applies to, as in
+    /// ``cpe:2.3:a:vendor:product:*:*:*:*:*:*:*:*`` without a version range, or only with a
+    /// wildcard such as ``2.*``. Such matches are often a placeholder until the NVD analyzes the
+    /// CVE and may not apply to the version of the platform.
+    VersionWildcard,
+    /// The version of the platform is within the version range of the CPE match.
+    VersionRange,
+    /// The CPE match names the version of the platform.
+    Exact,
+}
+
+impl MatchConfidence {
+    /// Name of the confidence, as serialized
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MatchConfidence::VersionWildcard => "version-wildcard",
+            MatchConfidence::VersionRange => "version-range",
+            MatchConfidence::Exact => "exact",
+        }
+    }
+
+    /// Warning to show along with matches of this confidence, if they should be verified.
+    pub fn warning(&self) -> Option<&'static str> {
+        match self {
+            MatchConfidence::VersionWildcard => Some("version wildcard \u{2014} verify manually"),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for MatchConfidence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A vulnerable CPE match of the configurations of a CVE, and the platform it matched.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlatformMatch {
+    /// CPE the configuration matches, such as ``cpe:2.3:a:apache:log4j:*:*:*:*:*:*:*:*``.
+    pub criteria: String,
+
+    /// Versions the configuration matches, unbounded for CPE matches without a version range.
+    pub range: VersionRange,
+
+    /// Platform matched by the configuration.
+    pub platform: Cpe,
+
+    pub confidence: MatchConfidence,
+}
+
+/// The platforms a CVE applies to among those it was matched against.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Applicability {
+    /// ID of the CVE, such as ``CVE-2021-44228``.
+    pub id: String,
+
+    /// Vulnerable CPE matches of the configurations that apply, in the order of the
+    /// configurations.
+    pub matches: Vec<PlatformMatch>,
+}
+
+impl Applicability {
+    /// Confidence of the most certain of the matches, so that a CVE matched exactly by one of
+    /// its configurations isn't flagged because another one is a version wildcard.
+    pub fn confidence(&self) -> Option<MatchConfidence> {
+        self.matches.iter().map(|m| m.confidence).max()
+    }
+}
+
+/// Match ``platforms``, such as the software installed on a host and its operating system, against
+/// the configurations of ``cve``. Returns ``None`` if none of its configurations applies to them.
+///
+/// Configurations apply as NVD evaluates them: a node with the ``OR`` operator applies if any of
+/// its CPE matches or children does, one with ``AND`` if all of them do, e.g. both a vulnerable
+/// firmware and the hardware running it. Version ranges are compared with ``scheme``.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cpe::Cpe;
+/// use nvd_cve::cve::CveFeed;
+/// use nvd_cve::matching::match_platforms;
+/// use nvd_cve::version::VersionScheme;
+///
+/// let json = std::fs::read("nvdcve-1.1-2021.json").unwrap();
+/// let feed: CveFeed = serde_json::from_slice(&json).unwrap();
+/// let log4j: Cpe = "cpe:2.3:a:apache:log4j:2.14.1:*:*:*:*:*:*:*".parse().unwrap();
+///
+/// for cve in &feed.cve_items {
+///     if let Some(applicability) = match_platforms(cve, &[log4j.clone()], VersionScheme::Auto) {
+///         let confidence = applicability.confidence().unwrap();
+///         let warning = confidence.warning().unwrap_or_default();
+///         println!("{}\t{}\t{}", applicability.id, confidence, warning);
+///     }
+/// }
+/// ```
+pub fn match_platforms(
+    cve: &CveContainer,
+    platforms: &[Cpe],
+    scheme: VersionScheme,
+) -> Option<Applicability> {
+    match_configurations(
+        &cve.cve.cve_data_meta.id,
+        &cve.configurations,
+        platforms,
+        scheme,
+    )
+}
+
+/// Same as ``match_platforms()`` for the ``configurations`` of the CVE ``id``, e.g. as cached.
+pub fn match_configurations(
+    id: &str,
+    configurations: &Configuration,
+    platforms: &[Cpe],
+    scheme: VersionScheme,
+) -> Option<Applicability> {
+    match_expanded_configurations(id, configurations, platforms, scheme, &CpeExpansions::new())
+}
+
+/// Same as ``match_configurations()``, except that CPE matches whose criteria has known CPE names
+/// in ``expansions`` only match the platforms matching one of them
+fn match_expanded_configurations(
+    id: &str,
+    configurations: &Configuration,
+    platforms: &[Cpe],
+    scheme: VersionScheme,
+    expansions: &CpeExpansions,
+) -> Option<Applicability> {
+    let mut matches = vec![];
+    for node in &configurations.nodes {
+        if let Some(node_matches) = match_node(node, platforms, scheme, expansions) {
+            matches.extend(node_matches);
+        }
+    }
+
+    if matches.is_empty() {
+        return None;
+    }
+    Some(Applicability {
+        id: id.to_string(),
+        matches,
+    })
+}
+
+/// Match ``platforms`` against the configurations of each CVE of the local cache, see
+/// ``match_platforms()``. Returns the CVEs that apply to them, sorted by CVE ID.
+///
+/// Once the CPE match feed was synced with ``cpematch::sync_cpe_matches()``, CPE matches whose
+/// criteria lists the CPE names it matches only match the platforms matching one of those names,
+/// exactly, rather than those whose version is in the range of the criteria, which spares the
+/// comparisons of versions that no scheme orders right.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::CacheConfig;
+/// use nvd_cve::cpe::Cpe;
+/// use nvd_cve::matching::search_applicable;
+/// use nvd_cve::version::VersionScheme;
+///
+/// let config = CacheConfig::new();
+/// let openssl: Cpe = "cpe:2.3:a:openssl:openssl:1.1.1k:*:*:*:*:*:*:*".parse().unwrap();
+///
+/// for applicability in search_applicable(&config, &[openssl], VersionScheme::Auto).unwrap() {
+///     println!("{}", applicability.id);
+/// }
+/// ```
+pub fn search_applicable(
+    config: &CacheConfig,
+    platforms: &[Cpe],
+    scheme: VersionScheme,
+) -> Result<Vec<Applicability>, CacheError> {
+    search_configurations(config, platforms, |id, configurations, expansions| {
+        match_expanded_configurations(id, configurations, platforms, scheme, expansions)
+    })
+}
+
+/// Returns the CVEs of the local cache whose configurations apply to ``cpe``, sorted by CVE ID, as
+/// ``search_applicable()`` does for a single platform. The version of ``cpe`` is compared with the
+/// version ranges of the CPE matches, such as ``versionStartIncluding`` and
+/// ``versionEndExcluding``, with ``scheme``.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::CacheConfig;
+/// use nvd_cve::cpe::Cpe;
+/// use nvd_cve::matching::search_by_cpe;
+/// use nvd_cve::version::VersionScheme;
+///
+/// let config = CacheConfig::new();
+/// let openssl: Cpe = "cpe:2.3:a:openssl:openssl:1.1.1k:*:*:*:*:*:*:*".parse().unwrap();
+///
+/// for applicability in search_by_cpe(&config, &openssl, VersionScheme::Auto).unwrap() {
+///     println!("{}\t{}", applicability.id, applicability.confidence().unwrap());
+/// }
+/// ```
+pub fn search_by_cpe(
+    config: &CacheConfig,
+    cpe: &Cpe,
+    scheme: VersionScheme,
+) -> Result<Vec<Applicability>, CacheError> {
+    search_applicable(config, std::slice::from_ref(cpe), scheme)
+}
+
+/// Same as ``fixed_configurations()`` for each CVE of the local cache, sorted by CVE ID, e.g. to
+/// report the CVEs fixed in the installed versions of packages.
+pub fn search_fixed(
+    config: &CacheConfig,
+    platforms: &[Cpe],
+    scheme: VersionScheme,
+) -> Result<Vec<Applicability>, CacheError> {
+    search_configurations(config, platforms, |id, configurations, _| {
+        fixed_configurations(id, configurations, platforms, scheme)
+    })
+}
+
+/// The vulnerable CPE matches of the configurations of the CVE ``id`` whose version range ends
+/// before the version of one of ``platforms``, for the platforms the configurations don't apply
+/// to, or ``None`` if there are none. Those platforms run a version released after the
+/// vulnerable ones, i.e. the version the CVE was fixed in or a later one.
+pub fn fixed_configurations(
+    id: &str,
+    configurations: &Configuration,
+    platforms: &[Cpe],
+    scheme: VersionScheme,
+) -> Option<Applicability> {
+    let applicable: Vec<Cpe> = match_configurations(id, configurations, platforms, scheme)
+        .map(|applicability| {
+            applicability
+                .matches
+                .into_iter()
+                .map(|m| m.platform)
+                .collect()
+        })
+        .unwrap_or_default();
+    let platforms: Vec<&Cpe> = platforms
+        .iter()
+        .filter(|platform| !applicable.contains(platform))
+        .collect();
+
+    let mut matches = vec![];
+    for cpe_match in configurations.cpe_matches() {
+        if !cpe_match.vulnerable {
+            continue;
+        }
+        let Some(criteria) = cpe_match.cpe() else {
+            continue;
+        };
+        let range = cpe_match.range();
+        let unversioned = Cpe {
+            version: CpeValue::Any,
+            ..criteria
+        };
+        for platform in &platforms {
+            if platform.version.has_wildcards() || !unversioned.matches(platform) {
+                continue;
+            }
+            let Some(version) = platform.version.unquoted() else {
+                continue;
+            };
+            if range.is_before(&version, scheme) {
+                matches.push(PlatformMatch {
+                    criteria: cpe_match.cpe23_uri.clone(),
+                    range: range.clone(),
+                    platform: (*platform).clone(),
+                    confidence: MatchConfidence::VersionRange,
+                });
+            }
+        }
+    }
+
+    if matches.is_empty() {
+        return None;
+    }
+    Some(Applicability {
+        id: id.to_string(),
+        matches,
+    })
+}
+
+/// The results of ``matcher`` for the configurations of each CVE of the local cache that may
+/// apply to ``platforms``, along with the CPE names cached for their criteria, sorted by CVE ID
+fn search_configurations<F>(
+    config: &CacheConfig,
+    platforms: &[Cpe],
+    mut matcher: F,
+) -> Result<Vec<Applicability>, CacheError>
+where
+    F: FnMut(&str, &Configuration, &CpeExpansions) -> Option<Applicability>,
+{
+    readable_schema(config)?;
+
+    // Configurations can only apply if they name the product of one of the platforms, which is
+    // much cheaper to look for than parsing them. Products are compared by their longest run of
+    // letters and digits, which is left as is by every binding of CPE names.
+    let products: Option<Vec<String>> = platforms
+        .iter()
+        .map(|platform| {
+            if platform.product.has_wildcards() {
+                return None;
+            }
+            platform
+                .product
+                .unquoted()?
+                .split(|c: char| !c.is_ascii_alphanumeric())
+                .max_by_key(|run| run.len())
+                .map(str::to_ascii_lowercase)
+        })
+        .collect();
+
+    let conn = open(config)?;
+    let expansions = criteria_expansions(&conn, platforms)?;
+    let (candidates, keys) = indexed_candidates(&conn, platforms)?.unwrap_or_default();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, configurations FROM cve WHERE configurations IS NOT NULL{} ORDER BY id",
+        candidates
+    ))?;
+    let mut applicable = vec![];
+    let mut rows = stmt.query(params_from_iter(keys))?;
+    while let Some(row) = rows.next()? {
+        let data: String = row.get(1)?;
+        if let Some(products) = &products {
+            let lowercase = data.to_ascii_lowercase();
+            if !products
+                .iter()
+                .any(|product| lowercase.contains(product.as_str()))
+            {
+                continue;
+            }
+        }
+
+        let id: String = row.get(0)?;
+        let configurations: Configuration = serde_json::from_str(&data)?;
+        if let Some(applicability) = matcher(&id, &configurations, &expansions) {
+            applicable.push(applicability);
+        }
+    }
+    drop(rows);
+    stmt.finalize()?;
+
+    match conn.close() {
+        Ok(_) => Ok(applicable),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Condition restricting the CVEs to those with a CPE match indexed under the product, vendor and
+/// part of one of ``platforms``, along with its parameters. ``None`` if the cache has no
+/// ``cpe_match`` table, as read-only caches made by older versions don't, or if the product of a
+/// platform has wildcards, in which case every configuration has to be parsed.
+fn indexed_candidates(
+    conn: &Connection,
+    platforms: &[Cpe],
+) -> Result<Option<(String, Vec<String>)>, CacheError> {
+    let mut tbl_stmt =
+        conn.prepare("SELECT name FROM sqlite_master where type = 'table' and name = 'cpe_match'")?;
+    let indexed = tbl_stmt.exists([])?;
+    tbl_stmt.finalize()?;
+    if !indexed
+        || platforms.is_empty()
+        || platforms
+            .iter()
+            .any(|platform| platform.product.has_wildcards())
+    {
+        return Ok(None);
+    }
+
+    let mut conditions = vec![];
+    let mut keys = vec![];
+    for platform in platforms {
+        let mut condition = vec!["product IN (?, '*')"];
+        keys.push(platform.product.index_key());
+        for (column, value) in [
+            ("vendor IN (?, '*')", &platform.vendor),
+            ("part IN (?, '*')", &platform.part),
+        ] {
+            if !value.has_wildcards() {
+                condition.push(column);
+                keys.push(value.index_key());
+            }
+        }
+        conditions.push(format!("({})", condition.join(" AND ")));
+    }
+    Ok(Some((
+        format!(
+            " AND id IN (SELECT cve FROM cpe_match WHERE {})",
+            conditions.join(" OR ")
+        ),
+        keys,
+    )))
+}
+
+/// The vulnerable CPE matches of ``node`` if it applies to ``platforms``, ``None`` otherwise
+fn match_node(
+    node: &Node,
+    platforms: &[Cpe],
+    scheme: VersionScheme,
+    expansions: &CpeExpansions,
+) -> Option<Vec<PlatformMatch>> {
+    let mut items = vec![];
+    for child in &node.children {
+        items.push(match_node(child, platforms, scheme, expansions));
+    }
+    for cpe_match in &node.cpe_match {
+        items.push(match_criteria(cpe_match, platforms, scheme, expansions));
+    }
+
+    let applies = match node.operator {
+        Operator::And => !items.is_empty() && items.iter().all(Option::is_some),
+        Operator::Or => items.iter().any(Option::is_some),
+    };
+
+    // A negated node applies when what it lists doesn't, which matches nothing vulnerable
+    match (applies, node.negate) {
+        (true, false) => Some(items.into_iter().flatten().flatten().collect()),
+        (false, true) => Some(vec![]),
+        _ => None,
+    }
+}
+
+/// The matches of ``cpe_match`` against each of ``platforms`` it matches, e.g. two installed
+/// versions of a product, listed only if it is vulnerable, or ``None`` if it matches none of them
+fn match_criteria(
+    cpe_match: &CpeMatch,
+    platforms: &[Cpe],
+    scheme: VersionScheme,
+    expansions: &CpeExpansions,
+) -> Option<Vec<PlatformMatch>> {
+    let criteria = cpe_match.cpe()?;
+    let range = cpe_match.range();
+    let names = expansions
+        .get(&criteria_key(&cpe_match.cpe23_uri, &range))
+        .filter(|names| !names.is_empty());
+
+    let platform_matches: Vec<_> = platforms
+        .iter()
+        .filter_map(|platform| {
+            if let Some(names) = names {
+                return names
+                    .iter()
+                    .any(|name| name.matches(platform))
+                    .then_some((platform, MatchConfidence::Exact));
+            }
+            if range.is_unbounded() {
+                if !criteria.matches(platform) {
+                    return None;
+                }
+                let confidence = if criteria.version.has_wildcards() {
+                    MatchConfidence::VersionWildcard
+                } else {
+                    MatchConfidence::Exact
+                };
+                return Some((platform, confidence));
+            }
+
+            // Ranges bound the versions of criteria matching any version
+            let unversioned = Cpe {
+                version: CpeValue::Any,
+                ..criteria.clone()
+            };
+            if platform.version.has_wildcards() {
+                return None;
+            }
+            let version = platform.version.unquoted()?;
+            if !unversioned.matches(platform) || !range.contains(&version, scheme) {
+                return None;
+            }
+            Some((platform, MatchConfidence::VersionRange))
+        })
+        .collect();
+    if platform_matches.is_empty() {
+        return None;
+    }
+
+    if !cpe_match.vulnerable {
+        return Some(vec![]);
+    }
+    Some(
+        platform_matches
+            .into_iter()
+            .map(|(platform, confidence)| PlatformMatch {
+                criteria: cpe_match.cpe23_uri.clone(),
+                range: range.clone(),
+                platform: platform.clone(),
+                confidence,
+            })
+            .collect(),
+    )
+}
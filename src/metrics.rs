@@ -0,0 +1,225 @@
+use crate::cache::{open, CacheConfig, CacheError};
+use crate::history::last_sync_run;
+use chrono::{DateTime, Utc};
+use log::warn;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Metrics of the local cache and the last attempt to sync it, as read by the textfile collector of
+/// Prometheus' node_exporter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncMetrics {
+    /// Source of the cache, the ``source`` label of every metric.
+    pub source: String,
+
+    /// Whether the last attempt to sync the cache succeeded.
+    pub succeeded: bool,
+
+    /// When the last attempt to sync the cache ended, by the ``clock`` of its config.
+    pub attempted: DateTime<Utc>,
+
+    /// When the last successful sync started and finished, ``None`` if the cache was never synced.
+    pub last_success: Option<(DateTime<Utc>, DateTime<Utc>)>,
+
+    /// Number of CVEs inserted or updated by the last successful sync.
+    pub cves_updated: usize,
+
+    /// Number of CVEs skipped by the last successful sync.
+    pub cves_skipped: usize,
+
+    /// Number of feeds synced to the cache, ``None`` if it can't be read.
+    pub feeds: Option<usize>,
+
+    /// Number of CVEs in the cache, ``None`` if it can't be read.
+    pub cves: Option<usize>,
+
+    /// Size of the database of the cache in bytes, ``None`` if it doesn't exist.
+    pub db_size: Option<u64>,
+}
+
+impl SyncMetrics {
+    /// Collect the metrics of the cache of ``config`` once an attempt to sync it ended. A cache
+    /// that is missing or can't be read only reports the attempt.
+    pub fn collect(config: &CacheConfig, succeeded: bool) -> Self {
+        let mut metrics = Self {
+            source: config.source.clone(),
+            succeeded,
+            attempted: config.clock.now(),
+            last_success: None,
+            cves_updated: 0,
+            cves_skipped: 0,
+            feeds: None,
+            cves: None,
+            db_size: fs::metadata(&config.db).ok().map(|metadata| metadata.len()),
+        };
+
+        if metrics.db_size.is_some() {
+            // Counting is best effort, a failed sync may have left the cache unreadable
+            if let Err(error) = metrics.count(config) {
+                warn!("Failed reading the metrics of {:?}: {:?}", config.db, error);
+            }
+        }
+        metrics
+    }
+
+    /// Read the counts and the last successful sync run of the cache of ``config``
+    fn count(&mut self, config: &CacheConfig) -> Result<(), CacheError> {
+        let conn = open(&CacheConfig {
+            read_only: true,
+            ..config.clone()
+        })?;
+
+        self.feeds = Some(conn.query_row("SELECT count(*) FROM metafile", [], |row| row.get(0))?);
+        self.cves = Some(conn.query_row("SELECT count(*) FROM cve", [], |row| row.get(0))?);
+        if let Some(run) = last_sync_run(&conn)? {
+            let started = DateTime::parse_from_rfc3339(&run.started);
+            let finished = DateTime::parse_from_rfc3339(&run.finished);
+            if let (Ok(started), Ok(finished)) = (started, finished) {
+                self.last_success =
+                    Some((started.with_timezone(&Utc), finished.with_timezone(&Utc)));
+            }
+            self.cves_updated = run.cves_updated;
+            self.cves_skipped = run.cves_skipped;
+        }
+
+        match conn.close() {
+            Ok(_) => Ok(()),
+            Err((_, error)) => Err(CacheError::RusqliteError(error)),
+        }
+    }
+
+    /// Format the metrics in the text exposition format of Prometheus. Metrics of a cache that was
+    /// never synced or can't be read are left out rather than reported as zero.
+    ///
+    /// ## Example:
+    /// ```
+    /// use chrono::{TimeZone, Utc};
+    /// use nvd_cve::metrics::SyncMetrics;
+    ///
+    /// let metrics = SyncMetrics {
+    ///     source: "nvd".to_string(),
+    ///     succeeded: false,
+    ///     attempted: Utc.with_ymd_and_hms(2021, 12, 18, 19, 0, 0).unwrap(),
+    ///     last_success: None,
+    ///     cves_updated: 0,
+    ///     cves_skipped: 0,
+    ///     feeds: None,
+    ///     cves: None,
+    ///     db_size: None,
+    /// };
+    /// let text = metrics.to_text();
+    ///
+    /// assert!(text.contains("nvd_cve_sync_success{source=\"nvd\"} 0\n"));
+    /// assert!(!text.contains("nvd_cve_sync_last_success_timestamp_seconds{"));
+    /// ```
+    pub fn to_text(&self) -> String {
+        let mut samples = vec![
+            (
+                "nvd_cve_sync_success",
+                "Whether the last attempt to sync the cache succeeded.",
+                Some(self.succeeded as u8 as f64),
+            ),
+            (
+                "nvd_cve_sync_last_attempt_timestamp_seconds",
+                "When the last attempt to sync the cache ended, in seconds since the epoch.",
+                Some(self.attempted.timestamp() as f64),
+            ),
+        ];
+        if let Some((started, finished)) = self.last_success {
+            samples.extend([
+                (
+                    "nvd_cve_sync_last_success_timestamp_seconds",
+                    "When the last successful sync of the cache finished, in seconds since the epoch.",
+                    Some(finished.timestamp() as f64),
+                ),
+                (
+                    "nvd_cve_sync_duration_seconds",
+                    "How long the last successful sync of the cache took.",
+                    Some((finished - started).num_milliseconds() as f64 / 1000.0),
+                ),
+                (
+                    "nvd_cve_sync_cves_updated",
+                    "Number of CVEs inserted or updated by the last successful sync.",
+                    Some(self.cves_updated as f64),
+                ),
+                (
+                    "nvd_cve_sync_cves_skipped",
+                    "Number of CVEs skipped by the last successful sync, as they were modified after their feed.",
+                    Some(self.cves_skipped as f64),
+                ),
+            ]);
+        }
+        samples.extend([
+            (
+                "nvd_cve_cache_feeds",
+                "Number of feeds synced to the cache.",
+                self.feeds.map(|feeds| feeds as f64),
+            ),
+            (
+                "nvd_cve_cache_cves",
+                "Number of CVEs in the cache.",
+                self.cves.map(|cves| cves as f64),
+            ),
+            (
+                "nvd_cve_cache_size_bytes",
+                "Size of the database of the cache.",
+                self.db_size.map(|size| size as f64),
+            ),
+        ]);
+
+        let source = self
+            .source
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n");
+        let mut text = String::new();
+        for (name, help, value) in samples {
+            if let Some(value) = value {
+                let _ = writeln!(text, "# HELP {} {}", name, help);
+                let _ = writeln!(text, "# TYPE {} gauge", name);
+                let _ = writeln!(text, "{}{{source=\"{}\"}} {}", name, source, value);
+            }
+        }
+        text
+    }
+}
+
+/// Write the metrics of the cache of ``config`` to ``path`` once an attempt to sync it ended, for
+/// the textfile collector of node_exporter to export, e.g. to
+/// ``/var/lib/node_exporter/nvd.prom``. The file is replaced at once, so the collector never reads
+/// a partly written file.
+///
+/// ## Example:
+/// ```no_run
+/// # #[cfg(feature = "blocking")]
+/// # fn main() {
+/// use nvd_cve::cache::{sync_blocking, CacheConfig};
+/// use nvd_cve::client::{BlockingHttpClient, ReqwestBlockingClient};
+/// use nvd_cve::metrics::write_metrics;
+///
+/// let config = CacheConfig::new();
+/// let client = ReqwestBlockingClient::new(&config.url, None, None, None);
+///
+/// let succeeded = sync_blocking(&config, client).is_ok();
+/// write_metrics(&config, "/var/lib/node_exporter/nvd.prom", succeeded).unwrap();
+/// # }
+/// # #[cfg(not(feature = "blocking"))]
+/// # fn main() {}
+/// ```
+pub fn write_metrics<P: AsRef<Path>>(
+    config: &CacheConfig,
+    path: P,
+    succeeded: bool,
+) -> io::Result<()> {
+    let path = path.as_ref();
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+
+    fs::write(
+        &temp_path,
+        SyncMetrics::collect(config, succeeded).to_text(),
+    )?;
+    fs::rename(&temp_path, path)
+}
@@ -0,0 +1,254 @@
+use crate::cache::{search_cached_cves, CacheConfig, CacheError};
+use crate::cve::Cve;
+use crate::cvss::Severity;
+use crate::datetime::parse_datetime;
+use crate::query::SearchQuery;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use uuid::Uuid;
+
+/// Name of the manifest of a MISP feed, listing its events
+const MANIFEST_FILENAME: &str = "manifest.json";
+
+/// Organisation a MISP event or attribute is attributed to.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct MispOrg {
+    pub name: String,
+    pub uuid: String,
+}
+
+/// Tag of a MISP event, such as ``tlp:white``.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct MispTag {
+    pub name: String,
+}
+
+/// Attribute of a MISP event.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct MispAttribute {
+    pub uuid: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub category: String,
+    pub value: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub comment: String,
+    pub timestamp: String,
+    pub to_ids: bool,
+}
+
+/// A CVE as a MISP event, holding a ``vulnerability`` attribute of the CVE ID and a ``link``
+/// attribute for each of its references.
+///
+/// Its UUID is derived from the CVE ID and its timestamp is the last modification date of the CVE,
+/// so a MISP instance pulling the feed again only updates the events of the CVEs modified since.
+/// Its threat level follows the severity of the CVE: high for ``HIGH`` and ``CRITICAL``, medium
+/// for ``MEDIUM``, low for ``LOW`` and ``NONE``, and undefined for CVEs that were never scored.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct MispEvent {
+    pub uuid: String,
+    pub info: String,
+
+    /// Publication date of the CVE, e.g. ``2021-12-10``.
+    pub date: String,
+
+    /// Last modification date of the CVE, as a Unix timestamp.
+    pub timestamp: String,
+    pub publish_timestamp: String,
+    pub published: bool,
+    pub analysis: String,
+    pub threat_level_id: String,
+
+    #[serde(rename = "Orgc")]
+    pub orgc: MispOrg,
+
+    #[serde(rename = "Tag")]
+    pub tags: Vec<MispTag>,
+
+    #[serde(rename = "Attribute")]
+    pub attributes: Vec<MispAttribute>,
+}
+
+impl MispEvent {
+    /// The event of ``cve``, published and last modified at the given feed dates, e.g.
+    /// ``2021-12-10T10:15Z``, which fall back on the Unix epoch if they can't be parsed.
+    pub fn new(
+        cve: &Cve,
+        published: &str,
+        last_modified: &str,
+        severity: Option<(Severity, f64)>,
+    ) -> Self {
+        let id = &cve.cve_data_meta.id;
+        let uuid = Uuid::new_v5(
+            &Uuid::NAMESPACE_URL,
+            format!("https://nvd.nist.gov/vuln/detail/{}", id).as_bytes(),
+        );
+
+        let published = parse_datetime(published).unwrap_or_default();
+        let timestamp = parse_datetime(last_modified)
+            .unwrap_or_default()
+            .max(published)
+            .timestamp()
+            .to_string();
+
+        let description = cve
+            .description
+            .description_data
+            .iter()
+            .find(|description| description.lang == "en")
+            .map(|description| description.value.clone())
+            .unwrap_or_default();
+
+        let attribute = |kind: &str, value: &str, comment: &str| MispAttribute {
+            uuid: Uuid::new_v5(&uuid, format!("{}:{}", kind, value).as_bytes()).to_string(),
+            kind: kind.to_string(),
+            category: "External analysis".to_string(),
+            value: value.to_string(),
+            comment: comment.to_string(),
+            timestamp: timestamp.clone(),
+            to_ids: false,
+        };
+
+        let mut attributes = vec![attribute("vulnerability", id, &description)];
+        for reference in &cve.references.reference_data {
+            if !attributes
+                .iter()
+                .any(|attribute| attribute.value == reference.url)
+            {
+                attributes.push(attribute("link", &reference.url, ""));
+            }
+        }
+
+        let threat_level_id = match severity.map(|(severity, _)| severity) {
+            Some(Severity::Critical | Severity::High) => "1",
+            Some(Severity::Medium) => "2",
+            Some(Severity::Low | Severity::None) => "3",
+            None => "4",
+        };
+
+        Self {
+            uuid: uuid.to_string(),
+            info: match severity {
+                Some((severity, score)) => format!("{} ({} {:.1})", id, severity, score),
+                None => id.clone(),
+            },
+            date: published.format("%Y-%m-%d").to_string(),
+            publish_timestamp: timestamp.clone(),
+            timestamp,
+            published: true,
+            analysis: "2".to_string(),
+            threat_level_id: threat_level_id.to_string(),
+            orgc: MispOrg {
+                name: "NIST NVD".to_string(),
+                uuid: Uuid::new_v5(&Uuid::NAMESPACE_URL, b"https://nvd.nist.gov/").to_string(),
+            },
+            tags: vec![MispTag {
+                name: "tlp:white".to_string(),
+            }],
+            attributes,
+        }
+    }
+}
+
+/// Entry of an event in the manifest of a MISP feed
+#[derive(Serialize)]
+struct ManifestEntry<'a> {
+    #[serde(rename = "Orgc")]
+    orgc: &'a MispOrg,
+    #[serde(rename = "Tag")]
+    tags: &'a [MispTag],
+    info: &'a str,
+    date: &'a str,
+    analysis: &'a str,
+    threat_level_id: &'a str,
+    timestamp: &'a str,
+}
+
+/// Write the cached CVEs matching ``query``, all of them for an empty query, to ``dir`` as a MISP
+/// feed that a MISP instance pulls on a schedule: an event file per CVE, named after the UUID of
+/// its event, along with the ``manifest.json`` listing them. Returns the number of events written.
+///
+/// Events of a previous feed written to ``dir`` that are no longer listed, e.g. because their CVE
+/// no longer matches, are removed. The manifest is written last, so a feed pulled while it is
+/// being written never lists missing events.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::CacheConfig;
+/// use nvd_cve::cvss::Severity;
+/// use nvd_cve::misp::write_misp_feed;
+/// use nvd_cve::query::SearchQuery;
+///
+/// let config = CacheConfig::new();
+/// let query = SearchQuery {
+///     min_severity: Some(Severity::Critical),
+///     ..Default::default()
+/// };
+///
+/// let events = write_misp_feed(&config, &query, "/srv/www/misp/nvd").unwrap();
+/// println!("Wrote {} events", events);
+/// ```
+pub fn write_misp_feed<P: AsRef<Path>>(
+    config: &CacheConfig,
+    query: &SearchQuery,
+    dir: P,
+) -> Result<usize, CacheError> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir)?;
+
+    let events: Vec<MispEvent> = search_cached_cves(config, query)?
+        .iter()
+        .map(|cached| {
+            MispEvent::new(
+                &cached.cve,
+                &cached.published,
+                &cached.last_modified,
+                cached.severity,
+            )
+        })
+        .collect();
+
+    let mut manifest = BTreeMap::new();
+    for event in &events {
+        let json = serde_json::to_vec(&serde_json::json!({ "Event": event }))?;
+        fs::write(dir.join(format!("{}.json", event.uuid)), json)?;
+        manifest.insert(
+            event.uuid.as_str(),
+            ManifestEntry {
+                orgc: &event.orgc,
+                tags: &event.tags,
+                info: &event.info,
+                date: &event.date,
+                analysis: &event.analysis,
+                threat_level_id: &event.threat_level_id,
+                timestamp: &event.timestamp,
+            },
+        );
+    }
+
+    let manifest_path = dir.join(MANIFEST_FILENAME);
+    if let Ok(previous) = fs::read(&manifest_path) {
+        let previous: BTreeMap<String, Value> =
+            serde_json::from_slice(&previous).unwrap_or_default();
+        for uuid in previous.keys() {
+            if !manifest.contains_key(uuid.as_str()) && Uuid::parse_str(uuid).is_ok() {
+                match fs::remove_file(dir.join(format!("{}.json", uuid))) {
+                    Err(error) if error.kind() != io::ErrorKind::NotFound => {
+                        return Err(error.into())
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    let temp_path = dir.join(format!("{}.tmp", MANIFEST_FILENAME));
+    fs::write(&temp_path, serde_json::to_vec(&manifest)?)?;
+    fs::rename(&temp_path, &manifest_path)?;
+
+    Ok(events.len())
+}
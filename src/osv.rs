@@ -0,0 +1,573 @@
+use crate::aliases::{insert_aliases, Alias};
+use crate::cache::{open, readable_schema, writable_schema, CacheConfig, CacheError};
+use crate::client::HttpError;
+#[cfg(feature = "blocking")]
+use crate::client::ReqwestBlockingClient;
+use crate::version::VersionScheme;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt;
+#[cfg(feature = "blocking")]
+use std::io::{Cursor, Read};
+use std::str::FromStr;
+
+/// Base URL of the OSV.dev data dumps, which hold an ``all.zip`` archive of the advisories of
+/// each ecosystem.
+pub const OSV_URL: &str = "https://osv-vulnerabilities.storage.googleapis.com/";
+
+/// Source recorded for the aliases of CVEs given by OSV advisories.
+pub const OSV_ALIAS_SOURCE: &str = "OSV";
+
+/// Errors related to syncing OSV advisories
+#[derive(Debug)]
+pub enum OsvError {
+    CacheError(CacheError),
+    HttpError(HttpError),
+    JsonError(serde_json::Error),
+    /// The data dump of an ecosystem isn't a valid zip archive.
+    ArchiveError(zip::result::ZipError),
+}
+
+impl From<CacheError> for OsvError {
+    fn from(error: CacheError) -> Self {
+        OsvError::CacheError(error)
+    }
+}
+
+impl From<rusqlite::Error> for OsvError {
+    fn from(error: rusqlite::Error) -> Self {
+        OsvError::CacheError(CacheError::RusqliteError(error))
+    }
+}
+
+impl From<HttpError> for OsvError {
+    fn from(error: HttpError) -> Self {
+        OsvError::HttpError(error)
+    }
+}
+
+impl From<serde_json::Error> for OsvError {
+    fn from(error: serde_json::Error) -> Self {
+        OsvError::JsonError(error)
+    }
+}
+
+impl From<zip::result::ZipError> for OsvError {
+    fn from(error: zip::result::ZipError) -> Self {
+        OsvError::ArchiveError(error)
+    }
+}
+
+/// Package ecosystem whose OSV advisories can be synced.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Ecosystem {
+    #[serde(rename = "crates.io")]
+    CratesIo,
+    #[serde(rename = "PyPI")]
+    PyPI,
+    #[serde(rename = "npm")]
+    Npm,
+}
+
+impl Ecosystem {
+    pub const ALL: [Ecosystem; 3] = [Ecosystem::CratesIo, Ecosystem::PyPI, Ecosystem::Npm];
+
+    /// Name of the ecosystem, as in OSV advisories and the paths of the OSV.dev data dumps
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Ecosystem::CratesIo => "crates.io",
+            Ecosystem::PyPI => "PyPI",
+            Ecosystem::Npm => "npm",
+        }
+    }
+
+    /// Scheme the versions of the ecosystem's packages are compared with. Python versions are
+    /// compared as dotted, which orders their pre-releases such as ``2.0rc1`` as PEP 440 does.
+    pub fn version_scheme(&self) -> VersionScheme {
+        match self {
+            Ecosystem::CratesIo | Ecosystem::Npm => VersionScheme::Semver,
+            Ecosystem::PyPI => VersionScheme::Dotted,
+        }
+    }
+
+    /// Normalize the name of a package, so that the names of a Python package that PyPI treats as
+    /// the same, such as ``Flask_Login`` and ``flask-login``, find its advisories.
+    pub fn normalize_name(&self, name: &str) -> String {
+        match self {
+            Ecosystem::CratesIo | Ecosystem::Npm => name.trim().to_string(),
+            Ecosystem::PyPI => name
+                .trim()
+                .to_lowercase()
+                .split(['-', '_', '.'])
+                .filter(|part| !part.is_empty())
+                .collect::<Vec<_>>()
+                .join("-"),
+        }
+    }
+}
+
+impl fmt::Display for Ecosystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Ecosystem {
+    type Err = String;
+
+    fn from_str(ecosystem: &str) -> Result<Self, Self::Err> {
+        Ecosystem::ALL
+            .into_iter()
+            .find(|known| known.as_str().eq_ignore_ascii_case(ecosystem))
+            .ok_or_else(|| format!("Unknown ecosystem: {}", ecosystem))
+    }
+}
+
+/// An OSV advisory, reduced to what is synced: its ID, aliases and the versions of the packages
+/// it affects.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct OsvVulnerability {
+    /// ID of the advisory, e.g. ``RUSTSEC-2023-0044`` or ``GHSA-xxxx-xxxx-xxxx``.
+    pub id: String,
+
+    /// When the advisory was last modified, in RFC 3339 format.
+    pub modified: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+
+    /// IDs of the same vulnerability in other databases, such as CVE IDs.
+    #[serde(default)]
+    pub aliases: Vec<String>,
+
+    #[serde(default)]
+    pub affected: Vec<Affected>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Affected {
+    pub package: Package,
+
+    #[serde(default)]
+    pub ranges: Vec<Range>,
+
+    /// Versions affected, listed instead of or along with ranges.
+    #[serde(default)]
+    pub versions: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Package {
+    pub ecosystem: String,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Range {
+    /// ``SEMVER`` or ``ECOSYSTEM``, ranges of ``GIT`` commits aren't versions of a package.
+    #[serde(rename = "type")]
+    pub range_type: String,
+
+    #[serde(default)]
+    pub events: Vec<Event>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Event {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub introduced: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fixed: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_affected: Option<String>,
+}
+
+/// A range of versions of a package affected by an advisory.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PackageRange {
+    pub ecosystem: Ecosystem,
+
+    /// Name of the package, normalized as ``Ecosystem::normalize_name()`` does.
+    pub package: String,
+
+    /// First version affected, ``None`` when every version before the end of the range is.
+    pub introduced: Option<String>,
+
+    /// First version no longer affected, ``None`` when the range has no fix.
+    pub fixed: Option<String>,
+
+    /// Last version affected, for ranges without a fix whose later versions may not be affected.
+    pub last_affected: Option<String>,
+}
+
+impl PackageRange {
+    /// Whether ``version`` of the package lies within the range.
+    pub fn contains(&self, version: &str) -> bool {
+        let scheme = self.ecosystem.version_scheme();
+        self.introduced
+            .as_ref()
+            .is_none_or(|introduced| scheme.compare(version, introduced).is_ge())
+            && self
+                .fixed
+                .as_ref()
+                .is_none_or(|fixed| scheme.compare(version, fixed).is_lt())
+            && self
+                .last_affected
+                .as_ref()
+                .is_none_or(|last| scheme.compare(version, last).is_le())
+    }
+}
+
+impl OsvVulnerability {
+    /// Parse an OSV advisory.
+    pub fn from_json(json: &[u8]) -> Result<Self, OsvError> {
+        Ok(serde_json::from_slice(json)?)
+    }
+
+    /// CVE IDs of the advisory, sorted, its own ID included if it is one.
+    pub fn cves(&self) -> Vec<String> {
+        let cves: BTreeSet<&String> = std::iter::once(&self.id)
+            .chain(&self.aliases)
+            .filter(|id| id.starts_with("CVE-"))
+            .collect();
+        cves.into_iter().cloned().collect()
+    }
+
+    /// Aliases of the CVEs of the advisory: its ID along with any other ID that isn't a CVE ID,
+    /// such as that of a GitHub advisory, for each CVE ID.
+    pub fn cve_aliases(&self) -> Vec<Alias> {
+        let cves = self.cves();
+        let others: BTreeSet<&String> = std::iter::once(&self.id)
+            .chain(&self.aliases)
+            .filter(|id| !id.starts_with("CVE-"))
+            .collect();
+
+        let mut aliases = vec![];
+        for alias in others {
+            for cve in &cves {
+                aliases.push(Alias {
+                    alias: alias.to_string(),
+                    cve: cve.to_string(),
+                    source: OSV_ALIAS_SOURCE.to_string(),
+                });
+            }
+        }
+        aliases
+    }
+
+    /// The ranges of versions of the packages of a known ecosystem affected by the advisory.
+    /// Listed versions each make a range of their own, when a package has no range of versions.
+    pub fn ranges(&self) -> Vec<PackageRange> {
+        let mut ranges = vec![];
+        for affected in &self.affected {
+            let Ok(ecosystem) = affected.package.ecosystem.parse::<Ecosystem>() else {
+                continue;
+            };
+            let package = ecosystem.normalize_name(&affected.package.name);
+            let range = |introduced: Option<&String>| PackageRange {
+                ecosystem,
+                package: package.clone(),
+                introduced: introduced.filter(|version| *version != "0").cloned(),
+                fixed: None,
+                last_affected: None,
+            };
+
+            let mut found = false;
+            for events in affected
+                .ranges
+                .iter()
+                .filter(|range| range.range_type != "GIT")
+                .map(|range| &range.events)
+            {
+                found = true;
+                let mut open: Option<PackageRange> = None;
+                for event in events {
+                    if let Some(introduced) = &event.introduced {
+                        ranges.extend(open.replace(range(Some(introduced))));
+                    }
+                    if event.fixed.is_some() || event.last_affected.is_some() {
+                        let mut closed = open.take().unwrap_or_else(|| range(None));
+                        closed.fixed = event.fixed.clone();
+                        closed.last_affected = event.last_affected.clone();
+                        ranges.push(closed);
+                    }
+                }
+                ranges.extend(open);
+            }
+
+            if !found {
+                for version in &affected.versions {
+                    ranges.push(PackageRange {
+                        last_affected: Some(version.clone()),
+                        ..range(Some(version))
+                    });
+                }
+            }
+        }
+        ranges.sort();
+        ranges.dedup();
+        ranges
+    }
+}
+
+/// What ``import_osv`` recorded in the local cache.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OsvReport {
+    /// Number of advisories added or modified.
+    pub vulnerabilities: usize,
+
+    /// Number of advisories skipped as they weren't modified since they were last recorded.
+    pub unchanged: usize,
+
+    /// Number of ranges of affected versions recorded.
+    pub ranges: usize,
+
+    /// Number of aliases recorded.
+    pub aliases: usize,
+}
+
+/// Record OSV ``vulnerabilities`` in the local cache, along with the ranges of the package
+/// versions they affect and the aliases linking them to their CVEs. Advisories modified since they
+/// were last recorded replace them, the others are skipped.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::CacheConfig;
+/// use nvd_cve::osv::{import_osv, OsvVulnerability};
+/// use std::fs;
+///
+/// let config = CacheConfig::new();
+/// let vulnerability =
+///     OsvVulnerability::from_json(&fs::read("RUSTSEC-2023-0044.json").unwrap()).unwrap();
+///
+/// let report = import_osv(&config, &[vulnerability]).unwrap();
+/// println!("Recorded {} ranges", report.ranges);
+/// ```
+pub fn import_osv(
+    config: &CacheConfig,
+    vulnerabilities: &[OsvVulnerability],
+) -> Result<OsvReport, OsvError> {
+    writable_schema(config)?;
+
+    let mut conn = open(config)?;
+    let tx = conn.transaction()?;
+
+    let mut report = OsvReport::default();
+    for vulnerability in vulnerabilities {
+        let modified: Option<String> = tx
+            .query_row(
+                "SELECT modified FROM osv WHERE id = ?1",
+                [&vulnerability.id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if modified.as_ref() == Some(&vulnerability.modified) {
+            report.unchanged += 1;
+            continue;
+        }
+
+        tx.execute(
+            "INSERT INTO osv (id, modified, summary, data) VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT (id) DO UPDATE SET modified = ?2, summary = ?3, data = ?4",
+            params![
+                vulnerability.id,
+                vulnerability.modified,
+                vulnerability.summary,
+                serde_json::to_string(vulnerability)?
+            ],
+        )?;
+
+        tx.execute(
+            "DELETE FROM osv_affected WHERE id = ?1",
+            [&vulnerability.id],
+        )?;
+        let mut stmt = tx.prepare(
+            "INSERT INTO osv_affected (id, ecosystem, package, introduced, fixed, last_affected)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        for range in vulnerability.ranges() {
+            stmt.execute(params![
+                vulnerability.id,
+                range.ecosystem.as_str(),
+                range.package,
+                range.introduced,
+                range.fixed,
+                range.last_affected
+            ])?;
+            report.ranges += 1;
+        }
+        stmt.finalize()?;
+
+        report.aliases += insert_aliases(&tx, &vulnerability.cve_aliases())?;
+        report.vulnerabilities += 1;
+    }
+
+    tx.commit()?;
+
+    match conn.close() {
+        Ok(_) => Ok(report),
+        Err((_, error)) => Err(OsvError::CacheError(CacheError::RusqliteError(error))),
+    }
+}
+
+/// Whether the cache opened by ``conn`` has OSV tables, read-only caches made by older versions
+/// don't
+fn has_osv(conn: &Connection) -> Result<bool, CacheError> {
+    let mut tbl_stmt =
+        conn.prepare("SELECT name FROM sqlite_master where type = 'table' and name = 'osv'")?;
+    let exists = tbl_stmt.exists([])?;
+    tbl_stmt.finalize()?;
+    Ok(exists)
+}
+
+/// Returns the OSV advisory with the given ID from the cache opened by ``conn``, if recorded.
+pub(crate) fn query_osv(
+    conn: &Connection,
+    id: &str,
+) -> Result<Option<OsvVulnerability>, CacheError> {
+    if !has_osv(conn)? {
+        return Ok(None);
+    }
+    let data: Option<String> = conn
+        .query_row("SELECT data FROM osv WHERE id = ?1", [id], |row| row.get(0))
+        .optional()?;
+    Ok(data.map(|data| serde_json::from_str(&data)).transpose()?)
+}
+
+/// Returns the OSV advisory with the given ID, if recorded in the local cache.
+pub fn get_osv(config: &CacheConfig, id: &str) -> Result<Option<OsvVulnerability>, OsvError> {
+    readable_schema(config)?;
+
+    let conn = open(config)?;
+
+    let vulnerability = query_osv(&conn, id)?;
+
+    match conn.close() {
+        Ok(_) => Ok(vulnerability),
+        Err((_, error)) => Err(OsvError::CacheError(CacheError::RusqliteError(error))),
+    }
+}
+
+/// Returns the OSV advisories affecting a package of an ecosystem, sorted by ID. Only those
+/// affecting ``version`` of the package are returned if given, otherwise those affecting any.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::CacheConfig;
+/// use nvd_cve::osv::{search_package, Ecosystem};
+///
+/// let config = CacheConfig::new();
+///
+/// for vulnerability in search_package(&config, Ecosystem::CratesIo, "openssl", Some("0.10.45")).unwrap() {
+///     println!("{} {}", vulnerability.id, vulnerability.cves().join(","));
+/// }
+/// ```
+pub fn search_package(
+    config: &CacheConfig,
+    ecosystem: Ecosystem,
+    package: &str,
+    version: Option<&str>,
+) -> Result<Vec<OsvVulnerability>, OsvError> {
+    readable_schema(config)?;
+
+    let conn = open(config)?;
+    let package = ecosystem.normalize_name(package);
+
+    let mut vulnerabilities = vec![];
+    if has_osv(&conn)? {
+        let mut stmt = conn.prepare(
+            "SELECT id, introduced, fixed, last_affected FROM osv_affected
+            WHERE ecosystem = ?1 AND package = ?2",
+        )?;
+        let ranges = stmt
+            .query_map([ecosystem.as_str(), &package], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    PackageRange {
+                        ecosystem,
+                        package: package.clone(),
+                        introduced: row.get(1)?,
+                        fixed: row.get(2)?,
+                        last_affected: row.get(3)?,
+                    },
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        stmt.finalize()?;
+
+        let ids: BTreeSet<String> = ranges
+            .into_iter()
+            .filter(|(_, range)| version.is_none_or(|version| range.contains(version)))
+            .map(|(id, _)| id)
+            .collect();
+
+        let mut stmt = conn.prepare("SELECT data FROM osv WHERE id = ?1")?;
+        for id in ids {
+            let data: String = stmt.query_row([id], |row| row.get(0))?;
+            vulnerabilities.push(serde_json::from_str(&data)?);
+        }
+        stmt.finalize()?;
+    }
+
+    match conn.close() {
+        Ok(_) => Ok(vulnerabilities),
+        Err((_, error)) => Err(OsvError::CacheError(CacheError::RusqliteError(error))),
+    }
+}
+
+/// Fetch the advisories of an ecosystem from the ``all.zip`` archive of its OSV.dev data dump, at
+/// ``{ecosystem}/all.zip`` relative to the base URL of the ``client``, e.g. ``OSV_URL``.
+#[cfg(feature = "blocking")]
+pub fn fetch_osv(
+    client: &ReqwestBlockingClient,
+    ecosystem: Ecosystem,
+) -> Result<Vec<OsvVulnerability>, OsvError> {
+    let archive = client.get_url(&format!("{}/all.zip", ecosystem))?;
+    let mut archive = zip::ZipArchive::new(Cursor::new(archive))?;
+
+    let mut vulnerabilities = vec![];
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+        if !file.is_file() || !file.name().ends_with(".json") {
+            continue;
+        }
+        let mut json = vec![];
+        file.read_to_end(&mut json)
+            .map_err(|error| OsvError::ArchiveError(error.into()))?;
+        vulnerabilities.push(OsvVulnerability::from_json(&json)?);
+    }
+    Ok(vulnerabilities)
+}
+
+/// Sync the OSV advisories of the ``osv_ecosystems`` of the ``config`` to the local cache, from
+/// the data dumps served at the base URL of the ``client``, such as ``osv_url``.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::CacheConfig;
+/// use nvd_cve::client::{BlockingHttpClient, ReqwestBlockingClient};
+/// use nvd_cve::osv::{sync_osv, Ecosystem};
+///
+/// let mut config = CacheConfig::new();
+/// config.osv_ecosystems = vec![Ecosystem::CratesIo, Ecosystem::PyPI];
+/// let client = ReqwestBlockingClient::new(&config.osv_url, None, None, None);
+///
+/// let report = sync_osv(&config, &client).unwrap();
+/// println!("Synced {} OSV advisories", report.vulnerabilities);
+/// ```
+#[cfg(feature = "blocking")]
+pub fn sync_osv(
+    config: &CacheConfig,
+    client: &ReqwestBlockingClient,
+) -> Result<OsvReport, OsvError> {
+    let mut report = OsvReport::default();
+    for ecosystem in &config.osv_ecosystems {
+        let synced = import_osv(config, &fetch_osv(client, *ecosystem)?)?;
+        report.vulnerabilities += synced.vulnerabilities;
+        report.unchanged += synced.unchanged;
+        report.ranges += synced.ranges;
+        report.aliases += synced.aliases;
+    }
+    Ok(report)
+}
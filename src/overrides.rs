@@ -0,0 +1,209 @@
+use crate::cache::{
+    bump_generation, has_namespaces, open, readable_schema, writable_schema, CacheConfig,
+    CacheError,
+};
+use crate::cve::Cve;
+use crate::cvss::Severity;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+/// Severities that may be set by a local override.
+#[deprecated(note = "use cvss::Severity::ALL")]
+pub const SEVERITIES: &[&str] = &["NONE", "LOW", "MEDIUM", "HIGH", "CRITICAL"];
+
+/// Local analyst assessment of a CVE, kept apart from the feed data so it survives syncs.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct CveOverride {
+    /// Severity to use instead of the one scored by the feed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub severity: Option<Severity>,
+
+    /// Whether the CVE is known to affect internal systems.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub affected: Option<bool>,
+
+    /// Free-form analyst note.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+
+    /// Tags of the assets the CVE affects, such as ``internet-facing``, weighed by risk scorers.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+
+    /// When the override was last changed, RFC 3339 formatted. Set by ``set_override``.
+    #[serde(default)]
+    pub updated: String,
+
+    /// Tracking IDs of the vendor advisories the override was ingested from, ``None`` for
+    /// overrides set by an analyst, which ingesting advisories never replaces.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub advisory: Option<String>,
+}
+
+/// A cached CVE merged with its local override, if any. The override is kept under its own
+/// ``local_override`` key so it is never mistaken for data that came from the feed.
+#[derive(Serialize, Debug, Clone)]
+pub struct CveWithOverride {
+    #[serde(flatten)]
+    pub cve: Cve,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub local_override: Option<CveOverride>,
+}
+
+/// Create or replace the local override of a CVE, in the ``namespace`` of ``config``.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::CacheConfig;
+/// use nvd_cve::overrides::{set_override, CveOverride};
+///
+/// let config = CacheConfig::new();
+///
+/// let assessment = CveOverride {
+///     affected: Some(false),
+///     note: Some("Only the log4j-api jar is deployed".to_string()),
+///     ..Default::default()
+/// };
+///
+/// set_override(&config, "CVE-2021-44228", &assessment).unwrap();
+/// ```
+pub fn set_override(
+    config: &CacheConfig,
+    cve: &str,
+    cve_override: &CveOverride,
+) -> Result<(), CacheError> {
+    writable_schema(config)?;
+
+    let conn = open(config)?;
+    upsert_override(&conn, config, cve, cve_override)?;
+
+    match conn.close() {
+        Ok(_) => Ok(()),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Create or replace the local override of a CVE in the ``namespace`` of ``config`` of the cache
+/// opened by ``conn``, dated by its ``clock``
+pub(crate) fn upsert_override(
+    conn: &Connection,
+    config: &CacheConfig,
+    cve: &str,
+    cve_override: &CveOverride,
+) -> Result<(), CacheError> {
+    let upsert_sql = "
+        insert into
+        overrides (
+            namespace,
+            id,
+            severity,
+            affected,
+            note,
+            updated,
+            tags,
+            advisory
+        )
+        values
+            (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8) on conflict(namespace, id) do
+        update
+        set
+            severity = ?3,
+            affected = ?4,
+            note = ?5,
+            updated = ?6,
+            tags = ?7,
+            advisory = ?8;";
+
+    let mut stmt = conn.prepare(upsert_sql)?;
+    stmt.insert(params![
+        config.namespace,
+        cve,
+        cve_override.severity,
+        cve_override.affected,
+        cve_override.note,
+        config.clock.now().to_rfc3339(),
+        match cve_override.tags.is_empty() {
+            true => None,
+            false => Some(serde_json::to_string(&cve_override.tags)?),
+        },
+        cve_override.advisory,
+    ])?;
+    stmt.finalize()?;
+    bump_generation(conn)?;
+    Ok(())
+}
+
+/// Returns the local override of a CVE in the ``namespace`` of ``config``, or ``None`` if it has not
+/// been overridden there.
+pub fn get_override(config: &CacheConfig, cve: &str) -> Result<Option<CveOverride>, CacheError> {
+    readable_schema(config)?;
+
+    let conn = open(config)?;
+
+    // Read-only caches made by older versions only hold the default namespace
+    let namespaced = has_namespaces(&conn)?;
+    if !namespaced && !config.namespace.is_empty() {
+        return match conn.close() {
+            Ok(_) => Ok(None),
+            Err((_, error)) => Err(CacheError::RusqliteError(error)),
+        };
+    }
+    let mut stmt = conn.prepare(match namespaced {
+        true => "SELECT * FROM overrides where namespace=?1 and id=?2",
+        false => "SELECT * FROM overrides where id=?2",
+    })?;
+
+    let cve_override = stmt
+        .query_row([&config.namespace, cve], |row| {
+            Ok(CveOverride {
+                severity: row.get("severity")?,
+                affected: row.get("affected")?,
+                note: row.get("note")?,
+                // Read-only caches made by older versions have no tags
+                tags: match row.get::<_, Option<String>>("tags") {
+                    Ok(Some(tags)) => serde_json::from_str(&tags).unwrap_or_default(),
+                    _ => vec![],
+                },
+                updated: row.get("updated")?,
+                advisory: row.get("advisory").unwrap_or_default(),
+            })
+        })
+        .optional()?;
+
+    stmt.finalize()?;
+
+    match conn.close() {
+        Ok(_) => Ok(cve_override),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Removes the local override of a CVE in the ``namespace`` of ``config``, returning ``true`` if
+/// there was one to remove.
+pub fn remove_override(config: &CacheConfig, cve: &str) -> Result<bool, CacheError> {
+    writable_schema(config)?;
+
+    let conn = open(config)?;
+
+    let removed = conn.execute(
+        "DELETE FROM overrides where namespace=?1 and id=?2",
+        [&config.namespace, cve],
+    )?;
+    bump_generation(&conn)?;
+
+    match conn.close() {
+        Ok(_) => Ok(removed > 0),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Merge the local override of a CVE in the ``namespace`` of ``config``, if any, over the feed
+/// data.
+pub fn with_override(config: &CacheConfig, cve: Cve) -> Result<CveWithOverride, CacheError> {
+    let local_override = get_override(config, &cve.cve_data_meta.id)?;
+    Ok(CveWithOverride {
+        cve,
+        local_override,
+    })
+}
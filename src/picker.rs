@@ -0,0 +1,269 @@
+use std::io;
+
+/// Score of ``text`` for the fuzzy ``pattern``, ``None`` unless the characters of the pattern all
+/// appear in the text in the same order, ignoring case. Matches at the start of words and runs of
+/// consecutive matches score higher, gaps between matches lower.
+fn score(pattern: &[char], text: &str) -> Option<i64> {
+    let mut score = 0;
+    let mut pattern = pattern.iter().peekable();
+    let mut previous: Option<char> = None;
+    let mut consecutive = false;
+    let mut gap = 0;
+
+    for c in text.chars() {
+        let Some(wanted) = pattern.peek() else {
+            break;
+        };
+        if c.to_lowercase().eq(wanted.to_lowercase()) {
+            score += 16;
+            if previous.is_none_or(|previous| !previous.is_alphanumeric()) {
+                score += 8;
+            }
+            if consecutive {
+                score += 4;
+            }
+            score -= gap.min(8);
+            pattern.next();
+            consecutive = true;
+            gap = 0;
+        } else {
+            consecutive = false;
+            gap += 1;
+        }
+        previous = Some(c);
+    }
+
+    match pattern.peek() {
+        Some(_) => None,
+        None => Some(score),
+    }
+}
+
+/// Indexes of the ``items`` matching ``query``, from the best match to the worst, items scoring
+/// the same keeping their order
+fn matching(items: &[String], query: &str) -> Vec<usize> {
+    let pattern: Vec<char> = query.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut scored: Vec<(i64, usize)> = items
+        .iter()
+        .enumerate()
+        .filter_map(|(index, item)| score(&pattern, item).map(|score| (score, index)))
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    scored.into_iter().map(|(_, index)| index).collect()
+}
+
+/// Let the user pick one of ``items`` from a full screen list on the terminal, narrowed down as
+/// they type a fuzzy query, returning the index of the item picked or ``None`` if they cancelled.
+/// The terminal is used through ``/dev/tty``, so the standard input and output can be piped.
+#[cfg(unix)]
+pub fn pick(items: &[String]) -> io::Result<Option<usize>> {
+    let mut terminal = terminal::Terminal::open()?;
+    let mut query = String::new();
+    let mut matches = matching(items, &query);
+    let (mut selected, mut offset) = (0, 0);
+
+    loop {
+        let (width, height) = terminal.size();
+        let rows = height.saturating_sub(2).max(1);
+        selected = selected.min(matches.len().saturating_sub(1));
+        offset = offset
+            .min(selected)
+            .max((selected + 1).saturating_sub(rows));
+
+        let mut screen = format!(
+            "\x1b[H\x1b[2J> {}\r\n  {}/{}\r\n",
+            query,
+            matches.len(),
+            items.len()
+        );
+        for (row, index) in matches.iter().enumerate().skip(offset).take(rows) {
+            let line: String = items[*index]
+                .chars()
+                .take(width.saturating_sub(2))
+                .collect();
+            match row == selected {
+                true => screen.push_str(&format!("\x1b[7m> {}\x1b[0m\r\n", line)),
+                false => screen.push_str(&format!("  {}\r\n", line)),
+            }
+        }
+        screen.push_str(&format!("\x1b[1;{}H", query.chars().count() + 3));
+        terminal.write(&screen)?;
+
+        match terminal.read_key()? {
+            terminal::Key::Char(c) => {
+                query.push(c);
+                matches = matching(items, &query);
+                selected = 0;
+            }
+            terminal::Key::Backspace => {
+                query.pop();
+                matches = matching(items, &query);
+                selected = 0;
+            }
+            terminal::Key::ClearQuery => {
+                query.clear();
+                matches = matching(items, &query);
+                selected = 0;
+            }
+            terminal::Key::Up => selected = selected.saturating_sub(1),
+            terminal::Key::Down => selected += 1,
+            terminal::Key::Enter if !matches.is_empty() => return Ok(Some(matches[selected])),
+            terminal::Key::Cancel => return Ok(None),
+            _ => {}
+        }
+    }
+}
+
+/// Picking needs a Unix terminal
+#[cfg(not(unix))]
+pub fn pick(_items: &[String]) -> io::Result<Option<usize>> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(unix)]
+mod terminal {
+    use std::fs::{File, OpenOptions};
+    use std::io::{self, Read, Write};
+    use std::os::unix::io::AsRawFd;
+
+    /// A key pressed in the picker
+    pub enum Key {
+        Char(char),
+        Backspace,
+        ClearQuery,
+        Up,
+        Down,
+        Enter,
+        Cancel,
+        Other,
+    }
+
+    /// The controlling terminal in raw mode, showing the alternate screen until dropped
+    pub struct Terminal {
+        tty: File,
+        original: libc::termios,
+    }
+
+    impl Terminal {
+        pub fn open() -> io::Result<Self> {
+            let tty = OpenOptions::new().read(true).write(true).open("/dev/tty")?;
+            let fd = tty.as_raw_fd();
+
+            let mut original: libc::termios = unsafe { std::mem::zeroed() };
+            // SAFETY: ``fd`` is open and ``original`` is only read once filled in
+            if unsafe { libc::tcgetattr(fd, &mut original) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut raw = original;
+            // SAFETY: ``raw`` is a valid termios copied from the terminal
+            unsafe { libc::cfmakeraw(&mut raw) };
+            raw.c_cc[libc::VMIN] = 1;
+            raw.c_cc[libc::VTIME] = 0;
+            // SAFETY: ``fd`` is open and ``raw`` is a valid termios
+            if unsafe { libc::tcsetattr(fd, libc::TCSAFLUSH, &raw) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            let mut terminal = Terminal { tty, original };
+            terminal.write("\x1b[?1049h")?;
+            Ok(terminal)
+        }
+
+        /// Width and height of the terminal in characters, 80 by 24 if it doesn't tell
+        pub fn size(&self) -> (usize, usize) {
+            let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+            // SAFETY: ``size`` is only read once filled in
+            match unsafe { libc::ioctl(self.tty.as_raw_fd(), libc::TIOCGWINSZ, &mut size) } {
+                0 if size.ws_col > 0 && size.ws_row > 0 => {
+                    (size.ws_col as usize, size.ws_row as usize)
+                }
+                _ => (80, 24),
+            }
+        }
+
+        pub fn write(&mut self, text: &str) -> io::Result<()> {
+            self.tty.write_all(text.as_bytes())?;
+            self.tty.flush()
+        }
+
+        fn read_byte(&mut self) -> io::Result<Option<u8>> {
+            let mut byte = [0];
+            match self.tty.read(&mut byte)? {
+                0 => Ok(None),
+                _ => Ok(Some(byte[0])),
+            }
+        }
+
+        /// Read the byte following an escape, waiting a tenth of a second at most so that the
+        /// escape key alone can be told apart from the start of an escape sequence
+        fn read_escaped(&mut self) -> io::Result<Option<u8>> {
+            let fd = self.tty.as_raw_fd();
+            let mut poll = libc::pollfd {
+                fd,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            // SAFETY: ``poll`` describes a single open file descriptor
+            match unsafe { libc::poll(&mut poll, 1, 100) } {
+                1 => self.read_byte(),
+                _ => Ok(None),
+            }
+        }
+
+        pub fn read_key(&mut self) -> io::Result<Key> {
+            let Some(byte) = self.read_byte()? else {
+                return Ok(Key::Cancel);
+            };
+            Ok(match byte {
+                b'\r' | b'\n' => Key::Enter,
+                // Ctrl-C, Ctrl-D, Ctrl-G and Ctrl-Q
+                0x03 | 0x04 | 0x07 | 0x11 => Key::Cancel,
+                // Ctrl-P and Ctrl-K
+                0x10 | 0x0b => Key::Up,
+                // Ctrl-N
+                0x0e => Key::Down,
+                // Ctrl-U
+                0x15 => Key::ClearQuery,
+                0x08 | 0x7f => Key::Backspace,
+                0x1b => match self.read_escaped()? {
+                    None => Key::Cancel,
+                    Some(b'[') | Some(b'O') => match self.read_escaped()? {
+                        Some(b'A') => Key::Up,
+                        Some(b'B') => Key::Down,
+                        _ => Key::Other,
+                    },
+                    Some(_) => Key::Other,
+                },
+                byte if byte < 0x20 => Key::Other,
+                byte => {
+                    // The remaining bytes of a UTF-8 encoded character
+                    let length = match byte {
+                        0xf0.. => 4,
+                        0xe0.. => 3,
+                        0xc0.. => 2,
+                        _ => 1,
+                    };
+                    let mut bytes = vec![byte];
+                    for _ in 1..length {
+                        bytes.extend(self.read_byte()?);
+                    }
+                    match std::str::from_utf8(&bytes)
+                        .ok()
+                        .and_then(|c| c.chars().next())
+                    {
+                        Some(c) => Key::Char(c),
+                        None => Key::Other,
+                    }
+                }
+            })
+        }
+    }
+
+    impl Drop for Terminal {
+        fn drop(&mut self) {
+            self.write("\x1b[?1049l").ok();
+            // SAFETY: ``original`` is the termios the terminal was opened with
+            unsafe { libc::tcsetattr(self.tty.as_raw_fd(), libc::TCSAFLUSH, &self.original) };
+        }
+    }
+}
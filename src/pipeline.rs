@@ -0,0 +1,214 @@
+use crate::cache::{CacheConfig, CacheError, FeedTimings, StageSpan, TimeoutPolicy};
+use crate::client::{BlockingHttpClient, FetchedFeed, HttpError};
+use crate::compression::Compression;
+use crate::cve::CveFeed;
+use crate::feed::{Feed, FeedName, Metafile, MetafileError};
+use log::{debug, warn};
+use std::sync::mpsc::{Receiver, SyncSender};
+use std::time::{Duration, Instant};
+
+/// Number of feeds that can wait between two stages. Along with the feed each stage is working on
+/// this caps the number of feeds held in memory during a sync, whatever the number of feeds.
+pub(crate) const STAGE_BACKLOG: usize = 1;
+
+/// A feed handed from one stage of a sync to the next, stopping the stages that receive an error
+pub(crate) type Handoff = Result<Job, CacheError>;
+
+/// What has been done to a feed so far
+pub(crate) enum Payload {
+    /// The cache already holds the latest version of the feed so it wasn't fetched
+    Skipped,
+    Compressed(Vec<u8>, Compression),
+    Decompressed(Vec<u8>),
+    Parsed(CveFeed),
+}
+
+/// A feed going through the stages of a sync
+pub(crate) struct Job {
+    pub name: FeedName,
+    pub metafile: Metafile,
+    /// Whether the cache held an older version of the feed
+    pub cached: bool,
+    pub payload: Payload,
+    /// Time each stage spent on the feed so far
+    pub timings: FeedTimings,
+}
+
+/// Fetch the Metafile of each feed, then the feed unless the cache already holds its latest
+/// version, within the ``feed_timeout`` and ``sync_deadline`` of ``config``. Returns the time
+/// spent fetching and the feeds that timed out, when the timeout policy is to continue. Spans are
+/// relative to ``epoch``, the start of the sync, as for the other stages.
+pub(crate) fn download<C: BlockingHttpClient>(
+    mut client: C,
+    feeds: Vec<Feed>,
+    config: &CacheConfig,
+    epoch: Instant,
+    output: SyncSender<Handoff>,
+) -> (Duration, Vec<FeedName>) {
+    let sync_deadline = config
+        .sync_deadline
+        .map(|deadline| Instant::now() + deadline);
+    let mut busy = Duration::ZERO;
+    let mut timed_out = vec![];
+
+    for feed in feeds {
+        let name = feed.name;
+        let start = Instant::now();
+        let deadline = match (config.feed_timeout, sync_deadline) {
+            (Some(timeout), Some(deadline)) => Some(deadline.min(start + timeout)),
+            (Some(timeout), None) => Some(start + timeout),
+            (None, deadline) => deadline,
+        };
+
+        let job = fetch(&mut client, feed, config.force_update, deadline).map(|mut job| {
+            job.timings.download = Some(StageSpan::since(epoch, start));
+            job
+        });
+        busy += start.elapsed();
+
+        if let (Err(CacheError::TimedOut(_)), TimeoutPolicy::Continue) = (&job, config.on_timeout) {
+            warn!("[Feed: {}] Timed out, skipping it", name);
+            timed_out.push(name);
+            continue;
+        }
+
+        let failed = job.is_err();
+        if output.send(job).is_err() || failed {
+            break;
+        }
+    }
+    (busy, timed_out)
+}
+
+fn fetch<C: BlockingHttpClient>(
+    client: &mut C,
+    feed: Feed,
+    force_update: bool,
+    deadline: Option<Instant>,
+) -> Result<Job, CacheError> {
+    let name = feed.name;
+    let timed_out = |error: CacheError| match error {
+        CacheError::HttpError(HttpError::TimedOut)
+        | CacheError::MetafileError(MetafileError::FetchError(HttpError::TimedOut)) => {
+            CacheError::TimedOut(name)
+        }
+        error => error,
+    };
+
+    set_timeout(client, name, deadline)?;
+    let metafile = Metafile::from_blocking_http_client(client, &feed.name)
+        .map_err(|error| timed_out(error.into()))?;
+
+    let cached = match feed.metafile {
+        Some(db_metafile) => {
+            if !force_update && (db_metafile.last_modified_date >= metafile.last_modified_date) {
+                debug!(
+                    "Cached Metafile: {} is the latest ({})",
+                    feed.name, metafile.last_modified_date
+                );
+                return Ok(Job {
+                    name: feed.name,
+                    metafile,
+                    cached: true,
+                    payload: Payload::Skipped,
+                    timings: FeedTimings::new(name),
+                });
+            }
+            true
+        }
+        None => false,
+    };
+
+    set_timeout(client, name, deadline)?;
+    let payload = match client
+        .fetch_feed(&feed.name)
+        .map_err(|error| timed_out(error.into()))?
+    {
+        FetchedFeed::Compressed(body, compression) => Payload::Compressed(body, compression),
+        FetchedFeed::Parsed(cve_feed) => Payload::Parsed(cve_feed),
+    };
+
+    Ok(Job {
+        name: feed.name,
+        metafile,
+        cached,
+        payload,
+        timings: FeedTimings::new(name),
+    })
+}
+
+/// Limit the next request of ``client`` to the time left until ``deadline``, failing with
+/// ``CacheError::TimedOut`` if it already passed.
+fn set_timeout<C: BlockingHttpClient>(
+    client: &mut C,
+    name: FeedName,
+    deadline: Option<Instant>,
+) -> Result<(), CacheError> {
+    let timeout = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+    if timeout == Some(Duration::ZERO) {
+        return Err(CacheError::TimedOut(name));
+    }
+    client.set_request_timeout(timeout);
+    Ok(())
+}
+
+/// Decompress the feeds fetched compressed. Returns the time spent decompressing.
+pub(crate) fn decompress(
+    input: Receiver<Handoff>,
+    output: SyncSender<Handoff>,
+    epoch: Instant,
+) -> Duration {
+    stage(input, output, |mut job| {
+        if let Payload::Compressed(body, compression) = job.payload {
+            let start = Instant::now();
+            job.payload =
+                Payload::Decompressed(compression.decompress(&body).map_err(HttpError::from)?);
+            job.timings.decompress = Some(StageSpan::since(epoch, start));
+        }
+        Ok(job)
+    })
+}
+
+/// Parse the decompressed feeds using ``threads`` threads, ``0`` for one per available CPU.
+/// Returns the time spent parsing.
+pub(crate) fn parse(
+    input: Receiver<Handoff>,
+    output: SyncSender<Handoff>,
+    threads: usize,
+    epoch: Instant,
+) -> Duration {
+    stage(input, output, |mut job| {
+        if let Payload::Decompressed(json) = job.payload {
+            let start = Instant::now();
+            job.payload = Payload::Parsed(
+                CveFeed::from_slice_parallel(&json, threads).map_err(HttpError::from)?,
+            );
+            job.timings.parse = Some(StageSpan::since(epoch, start));
+        }
+        Ok(job)
+    })
+}
+
+/// Run ``work`` on each feed received until an error is received or returned, or the next stage
+/// stops. Returns the time spent in ``work``.
+fn stage<F>(input: Receiver<Handoff>, output: SyncSender<Handoff>, mut work: F) -> Duration
+where
+    F: FnMut(Job) -> Result<Job, CacheError>,
+{
+    let mut busy = Duration::ZERO;
+
+    for job in input {
+        let job = job.and_then(|job| {
+            let start = Instant::now();
+            let job = work(job);
+            busy += start.elapsed();
+            job
+        });
+
+        let failed = job.is_err();
+        if output.send(job).is_err() || failed {
+            break;
+        }
+    }
+    busy
+}
@@ -0,0 +1,36 @@
+//! Commonly used types and functions, re-exported so they can be imported in one go:
+//!
+//! ```no_run
+//! # #[cfg(feature = "blocking")]
+//! # fn main() {
+//! use nvd_cve::prelude::*;
+//!
+//! let config = CacheConfig::new();
+//! let client = ReqwestBlockingClient::new(&config.url, None, None, None);
+//! let report = sync_blocking(&config, client).unwrap();
+//! println!("Updated {} CVEs", report.cves_updated);
+//!
+//! let cve = search_by_id(&config, "CVE-2021-44228").unwrap();
+//! # }
+//! # #[cfg(not(feature = "blocking"))]
+//! # fn main() {}
+//! ```
+//!
+//! See the crate documentation for the stability guarantees of the prelude.
+
+#[cfg(feature = "blocking")]
+pub use crate::cache::sync_blocking;
+pub use crate::cache::{
+    get_all, get_all_ids, get_all_summaries, get_cached_feeds, get_metafile, reindex, search,
+    search_by_assigner, search_by_cwe, search_by_id, search_by_id_in_sources, search_by_product,
+    search_by_reference, search_by_severity, search_description, search_id_prefix, search_sources,
+    CacheConfig, CacheError, SyncReport,
+};
+#[cfg(feature = "blocking")]
+pub use crate::client::{BlockingHttpClient, ReqwestBlockingClient};
+pub use crate::compression::Compression;
+pub use crate::cve::{Cve, CveContainer, CveFeed, CveSummary};
+pub use crate::cvss::Severity;
+pub use crate::feed::{Feed, FeedName, Metafile};
+pub use crate::overrides::{get_override, remove_override, set_override, CveOverride};
+pub use crate::query::SearchQuery;
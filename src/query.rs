@@ -0,0 +1,695 @@
+use crate::cve::CveContainer;
+use crate::cvss::{
+    AttackComplexity, AttackVector, CvssV3Vector, ImpactLevel, PrivilegesRequired, Scope, Severity,
+    UserInteraction,
+};
+use crate::cwe::cwe_id;
+use crate::datetime::format_datetime;
+use chrono::{DateTime, Utc};
+use rusqlite::types::Value;
+use url::Url;
+
+/// Criteria used to search the local cache. Every criterion that is set must match for a CVE to
+/// be returned, criteria left as ``None`` are ignored.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::{search, CacheConfig};
+/// use nvd_cve::cvss::{AttackVector, UserInteraction};
+/// use nvd_cve::query::SearchQuery;
+///
+/// let config = CacheConfig::new();
+///
+/// // Remotely exploitable without any user interaction
+/// let query = SearchQuery {
+///     attack_vector: Some(AttackVector::Network),
+///     user_interaction: Some(UserInteraction::None),
+///     ..Default::default()
+/// };
+///
+/// for cve in search(&config, &query).unwrap() {
+///     println!("{}", cve);
+/// }
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SearchQuery {
+    /// Beginning of the CVE's ID, such as ``CVE-2024-123``.
+    pub id_prefix: Option<String>,
+
+    /// Text that must appear within the CVE's ID or its description.
+    pub text: Option<String>,
+
+    /// Weakness listed in the CVE's problem types, such as ``CWE-79`` or only ``79``.
+    pub cwe: Option<String>,
+
+    /// CNA that assigned the CVE, such as ``security@apache.org``, ignoring case.
+    pub assigner: Option<String>,
+
+    /// Vendor named by a CPE of the CVE's configurations, such as ``microsoft``, ignoring case.
+    pub vendor: Option<String>,
+
+    /// Product named by a CPE of the CVE's configurations, such as ``exchange_server``, ignoring
+    /// case. Along with ``vendor``, both must be named by the same CPE.
+    pub product: Option<String>,
+
+    /// URL the CVE references, such as an advisory, or its beginning, such as a repository
+    /// ``github.com/org/repo`` or a domain ``example.com``, which also matches its subdomains. The
+    /// scheme, a leading ``www.``, the query and the fragment are ignored, as is case.
+    pub reference: Option<String>,
+
+    /// CVSS v3 attack vector.
+    pub attack_vector: Option<AttackVector>,
+
+    /// CVSS v3 attack complexity.
+    pub attack_complexity: Option<AttackComplexity>,
+
+    /// CVSS v3 privileges required.
+    pub privileges_required: Option<PrivilegesRequired>,
+
+    /// CVSS v3 user interaction.
+    pub user_interaction: Option<UserInteraction>,
+
+    /// CVSS v3 scope.
+    pub scope: Option<Scope>,
+
+    /// CVSS v3 confidentiality impact.
+    pub confidentiality_impact: Option<ImpactLevel>,
+
+    /// CVSS v3 integrity impact.
+    pub integrity_impact: Option<ImpactLevel>,
+
+    /// CVSS v3 availability impact.
+    pub availability_impact: Option<ImpactLevel>,
+
+    /// Minimum CVSS v3 exploitability subscore.
+    pub min_exploitability_score: Option<f64>,
+
+    /// Minimum CVSS v3 impact subscore.
+    pub min_impact_score: Option<f64>,
+
+    /// Minimum severity, from the CVSS v3 metrics or else the CVSS v2 metrics. Searches of the local
+    /// cache match the severity of the local override of a CVE instead, if it sets one.
+    pub min_severity: Option<Severity>,
+
+    /// Severity, from the CVSS v3 metrics or else the CVSS v2 metrics, or the severity of the local
+    /// override of a CVE as ``min_severity``.
+    pub severity: Option<Severity>,
+
+    /// Minimum base score, from the CVSS v3 metrics or else the CVSS v2 metrics.
+    pub min_base_score: Option<f64>,
+
+    /// Only CVEs published at or after this date.
+    pub published_after: Option<DateTime<Utc>>,
+
+    /// Only CVEs published before this date.
+    pub published_before: Option<DateTime<Utc>>,
+
+    /// Only CVEs last modified at or after this date.
+    pub modified_after: Option<DateTime<Utc>>,
+
+    /// Only CVEs last modified before this date.
+    pub modified_before: Option<DateTime<Utc>>,
+
+    /// Only CVEs referencing a public exploit, see ``Cve::exploit_refs()``.
+    pub has_public_exploit: bool,
+
+    /// Only CVEs listed in CISA's Known Exploited Vulnerabilities catalog, see
+    /// ``kev::sync_kev()``. Whether the CVEs of feeds that aren't cached are listed isn't known,
+    /// so ``matches()`` never matches them.
+    pub known_exploited: bool,
+
+    /// Minimum EPSS probability of being exploited in the next 30 days, from 0 to 1, see
+    /// ``epss::sync_epss()``. CVEs of feeds that aren't cached have no score known, so
+    /// ``matches()`` never matches them.
+    pub min_epss: Option<f64>,
+
+    /// Minimum risk score in the namespace of the cache searched, see ``risk::score_cves()``. CVEs
+    /// of feeds that aren't cached have no risk score, so ``matches()`` never matches them.
+    pub min_risk_score: Option<f64>,
+}
+
+impl SearchQuery {
+    /// Returns ``true`` if no criteria have been set.
+    pub fn is_empty(&self) -> bool {
+        self.where_clause(None, None).0.is_empty()
+    }
+
+    /// Match the components that are set in a (possibly partial) CVSS v3 vector, replacing any
+    /// of the same components already set on this query.
+    ///
+    /// ## Example:
+    /// ```
+    /// use nvd_cve::cvss::{AttackComplexity, AttackVector, ImpactLevel};
+    /// use nvd_cve::query::SearchQuery;
+    ///
+    /// let mut query = SearchQuery::default();
+    /// query.set_vector(&"AV:N/AC:L/C:H".parse().unwrap());
+    ///
+    /// assert_eq!(query.attack_vector, Some(AttackVector::Network));
+    /// assert_eq!(query.attack_complexity, Some(AttackComplexity::Low));
+    /// assert_eq!(query.confidentiality_impact, Some(ImpactLevel::High));
+    /// assert_eq!(query.scope, None);
+    /// ```
+    pub fn set_vector(&mut self, vector: &CvssV3Vector) {
+        self.attack_vector = vector.attack_vector.or(self.attack_vector);
+        self.attack_complexity = vector.attack_complexity.or(self.attack_complexity);
+        self.privileges_required = vector.privileges_required.or(self.privileges_required);
+        self.user_interaction = vector.user_interaction.or(self.user_interaction);
+        self.scope = vector.scope.or(self.scope);
+        self.confidentiality_impact = vector
+            .confidentiality_impact
+            .or(self.confidentiality_impact);
+        self.integrity_impact = vector.integrity_impact.or(self.integrity_impact);
+        self.availability_impact = vector.availability_impact.or(self.availability_impact);
+    }
+
+    /// Returns ``true`` if ``cve`` matches every criterion that is set, as a search of a cache
+    /// holding it would, so that feeds can be filtered without syncing them first.
+    pub fn matches(&self, cve: &CveContainer) -> bool {
+        // Text is matched like SQLite's LIKE, ignoring the case of ASCII letters only
+        let contains = |haystack: &str, needle: &str| {
+            haystack
+                .to_ascii_lowercase()
+                .contains(&needle.to_ascii_lowercase())
+        };
+
+        let id = &cve.cve.cve_data_meta.id;
+        if let Some(id_prefix) = &self.id_prefix {
+            if !id
+                .to_ascii_lowercase()
+                .starts_with(&id_prefix.to_ascii_lowercase())
+            {
+                return false;
+            }
+        }
+
+        if let Some(text) = &self.text {
+            // The cache holds the last English description of each CVE
+            let description = cve
+                .cve
+                .description
+                .description_data
+                .iter()
+                .rev()
+                .find(|description| description.lang == "en");
+            if !contains(id, text)
+                && !description.is_some_and(|description| contains(&description.value, text))
+            {
+                return false;
+            }
+        }
+
+        if let Some(assigner) = &self.assigner {
+            if !cve
+                .cve
+                .cve_data_meta
+                .assigner
+                .eq_ignore_ascii_case(assigner.trim())
+            {
+                return false;
+            }
+        }
+
+        if let Some(cwe) = &self.cwe {
+            if !cve.cve.cwe_ids().contains(&cwe_id(cwe)) {
+                return false;
+            }
+        }
+
+        if self.vendor.is_some() || self.product.is_some() {
+            let vendor = self.vendor.as_deref().map(product_key);
+            let product = self.product.as_deref().map(product_key);
+            let named = cve
+                .configurations
+                .cpe_matches()
+                .iter()
+                .filter_map(|cpe_match| cpe_match.cpe())
+                .any(|cpe| {
+                    vendor.as_ref().is_none_or(|v| *v == cpe.vendor.index_key())
+                        && product
+                            .as_ref()
+                            .is_none_or(|p| *p == cpe.product.index_key())
+                });
+            if !named {
+                return false;
+            }
+        }
+
+        if let Some(reference) = &self.reference {
+            let (host, path) = reference_key(reference);
+            let cited = cve
+                .cve
+                .references
+                .reference_data
+                .iter()
+                .filter_map(|reference| reference_location(&reference.url))
+                .any(|(reference_host, reference_path)| {
+                    let host_matches = match path.is_empty() {
+                        true => {
+                            reference_host == host
+                                || reference_host.ends_with(&format!(".{}", host))
+                        }
+                        false => reference_host == host,
+                    };
+                    host_matches
+                        && (reference_path == path
+                            || reference_path.starts_with(&format!("{}/", path)))
+                });
+            if !cited {
+                return false;
+            }
+        }
+
+        let metric = cve.impact.base_metric_v3.as_ref();
+        let cvss = metric.map(|metric| &metric.cvss_v3);
+        let metrics = [
+            (
+                self.attack_vector.map(|v| v.as_str()),
+                cvss.map(|c| c.attack_vector.as_str()),
+            ),
+            (
+                self.attack_complexity.map(|v| v.as_str()),
+                cvss.map(|c| c.attack_complexity.as_str()),
+            ),
+            (
+                self.privileges_required.map(|v| v.as_str()),
+                cvss.map(|c| c.privileges_required.as_str()),
+            ),
+            (
+                self.user_interaction.map(|v| v.as_str()),
+                cvss.map(|c| c.user_interaction.as_str()),
+            ),
+            (
+                self.scope.map(|v| v.as_str()),
+                cvss.map(|c| c.scope.as_str()),
+            ),
+            (
+                self.confidentiality_impact.map(|v| v.as_str()),
+                cvss.map(|c| c.confidentiality_impact.as_str()),
+            ),
+            (
+                self.integrity_impact.map(|v| v.as_str()),
+                cvss.map(|c| c.integrity_impact.as_str()),
+            ),
+            (
+                self.availability_impact.map(|v| v.as_str()),
+                cvss.map(|c| c.availability_impact.as_str()),
+            ),
+        ];
+        for (wanted, value) in metrics {
+            if wanted.is_some() && wanted != value {
+                return false;
+            }
+        }
+
+        let scores = [
+            (
+                self.min_exploitability_score,
+                metric.as_ref().map(|m| m.exploitability_score),
+            ),
+            (
+                self.min_impact_score,
+                metric.as_ref().map(|m| m.impact_score),
+            ),
+        ];
+        for (min, score) in scores {
+            if min.is_some_and(|min| !score.is_some_and(|score| score >= min)) {
+                return false;
+            }
+        }
+
+        if let Some(min_severity) = self.min_severity {
+            if !matches!(cve.severity(), Some((severity, _)) if severity >= min_severity) {
+                return false;
+            }
+        }
+
+        if let Some(wanted) = self.severity {
+            if !matches!(cve.severity(), Some((severity, _)) if severity == wanted) {
+                return false;
+            }
+        }
+
+        if let Some(min_score) = self.min_base_score {
+            if !matches!(cve.severity(), Some((_, score)) if score >= min_score) {
+                return false;
+            }
+        }
+
+        let dates = [
+            (cve.published(), self.published_after, self.published_before),
+            (
+                cve.last_modified(),
+                self.modified_after,
+                self.modified_before,
+            ),
+        ];
+        for (date, after, before) in dates {
+            if after.is_some_and(|after| date.is_none_or(|date| date < after))
+                || before.is_some_and(|before| date.is_none_or(|date| date >= before))
+            {
+                return false;
+            }
+        }
+
+        if self.has_public_exploit && cve.cve.exploit_refs().is_empty() {
+            return false;
+        }
+
+        if self.known_exploited || self.min_epss.is_some() || self.min_risk_score.is_some() {
+            return false;
+        }
+
+        true
+    }
+
+    /// Build the ``WHERE`` clause and its positional parameters for this query, as planned by
+    /// ``QueryPlan``, matching the risk scores of ``namespace``, or those of any namespace for
+    /// ``None``, see ``CacheConfig::namespace``. Severities are those of the rows of the
+    /// ``overrides`` table meeting the ``overrides`` condition, see ``cache::override_scope()``,
+    /// over those of the feeds.
+    pub(crate) fn where_clause(
+        &self,
+        namespace: Option<&str>,
+        overrides: Option<&str>,
+    ) -> (String, Vec<Value>) {
+        let mut plan = QueryPlan::default();
+
+        if let Some(id_prefix) = &self.id_prefix {
+            plan.column(
+                "id like ? || '%' escape '\\'",
+                vec![Value::Text(escape_like(id_prefix))],
+            );
+        }
+
+        if let Some(assigner) = &self.assigner {
+            plan.column(
+                "assigner = ? COLLATE NOCASE",
+                vec![Value::Text(assigner.trim().to_string())],
+            );
+        }
+
+        let metrics = [
+            ("attack_vector", self.attack_vector.map(|v| v.as_str())),
+            (
+                "attack_complexity",
+                self.attack_complexity.map(|v| v.as_str()),
+            ),
+            (
+                "privileges_required",
+                self.privileges_required.map(|v| v.as_str()),
+            ),
+            (
+                "user_interaction",
+                self.user_interaction.map(|v| v.as_str()),
+            ),
+            ("scope", self.scope.map(|v| v.as_str())),
+            (
+                "confidentiality_impact",
+                self.confidentiality_impact.map(|v| v.as_str()),
+            ),
+            (
+                "integrity_impact",
+                self.integrity_impact.map(|v| v.as_str()),
+            ),
+            (
+                "availability_impact",
+                self.availability_impact.map(|v| v.as_str()),
+            ),
+        ];
+
+        for (column, value) in metrics {
+            if let Some(value) = value {
+                plan.column(
+                    &format!("{} = ?", column),
+                    vec![Value::Text(value.to_string())],
+                );
+            }
+        }
+
+        if let Some(score) = self.min_exploitability_score {
+            plan.column("exploitability_score >= ?", vec![Value::Real(score)]);
+        }
+
+        if let Some(score) = self.min_impact_score {
+            plan.column("impact_score >= ?", vec![Value::Real(score)]);
+        }
+
+        let severities = match (self.min_severity, self.severity) {
+            (Some(min_severity), _) => Severity::ALL
+                .into_iter()
+                .filter(|severity| *severity >= min_severity)
+                .filter(|severity| self.severity.is_none_or(|wanted| wanted == *severity))
+                .collect(),
+            (None, Some(severity)) => vec![severity],
+            (None, None) => vec![],
+        };
+        if self.min_severity.is_some() || self.severity.is_some() {
+            let values: Vec<_> = severities
+                .iter()
+                .map(|severity| Value::Text(severity.as_str().to_string()))
+                .collect();
+            let list = vec!["?"; values.len()].join(", ");
+            match overrides {
+                // The CVEs of the severities, or overridden, are found by their indexes before
+                // the severity of their override is looked up
+                Some(scope) => plan.column(
+                    &format!(
+                        "(base_severity IN ({list}) OR id IN (
+                            SELECT id FROM overrides WHERE severity IS NOT NULL AND {scope}))
+                        AND coalesce((SELECT severity FROM overrides
+                            WHERE overrides.id = cve.id AND {scope}), base_severity) IN ({list})",
+                        list = list,
+                        scope = scope
+                    ),
+                    values.iter().chain(&values).cloned().collect(),
+                ),
+                None => plan.column(&format!("base_severity IN ({})", list), values),
+            }
+        }
+
+        if let Some(score) = self.min_base_score {
+            plan.column("base_score >= ?", vec![Value::Real(score)]);
+        }
+
+        // Dates are cached as ``format_datetime()`` formats them, which sort as they compare, so
+        // the bare columns are compared for their indexes to be used
+        let dates = [
+            ("published_date", ">=", self.published_after),
+            ("published_date", "<", self.published_before),
+            ("last_modified_date", ">=", self.modified_after),
+            ("last_modified_date", "<", self.modified_before),
+        ];
+        for (column, operator, date) in dates {
+            if let Some(date) = date {
+                plan.column(
+                    &format!("{} {} ?", column, operator),
+                    vec![Value::Text(format_datetime(&date))],
+                );
+            }
+        }
+
+        if let Some(text) = &self.text {
+            plan.text(
+                "(id like '%' || ? || '%' escape '\\' or description like '%' || ? || '%' escape '\\')",
+                vec![
+                    Value::Text(escape_like(text)),
+                    Value::Text(escape_like(text)),
+                ],
+            );
+        }
+
+        if let Some(cwe) = &self.cwe {
+            plan.related(
+                "cve_cwe",
+                Some("cve_cwe.cwe = ?"),
+                vec![Value::Text(cwe_id(cwe))],
+            );
+        }
+
+        // Vendors and products are matched by the same row, so by the same CPE
+        if let Some(vendor) = &self.vendor {
+            plan.related(
+                "cpe_match",
+                Some("cpe_match.vendor = ?"),
+                vec![Value::Text(product_key(vendor))],
+            );
+        }
+
+        if let Some(product) = &self.product {
+            plan.related(
+                "cpe_match",
+                Some("cpe_match.product = ?"),
+                vec![Value::Text(product_key(product))],
+            );
+        }
+
+        // A domain alone matches its subdomains, a path the paths beneath it
+        if let Some(reference) = &self.reference {
+            let (host, path) = reference_key(reference);
+            let text = |value: &str| vec![Value::Text(value.to_string()); 3];
+            match path.is_empty() {
+                true => plan.related(
+                    "cve_reference",
+                    Some(
+                        "(cve_reference.host = ? OR \
+                        substr(cve_reference.host, -length(?) - 1) = '.' || ?)",
+                    ),
+                    text(&host),
+                ),
+                false => {
+                    plan.related(
+                        "cve_reference",
+                        Some("cve_reference.host = ?"),
+                        vec![Value::Text(host)],
+                    );
+                    plan.related(
+                        "cve_reference",
+                        Some(
+                            "(cve_reference.path = ? OR \
+                            substr(cve_reference.path, 1, length(?) + 1) = ? || '/')",
+                        ),
+                        text(&path),
+                    );
+                }
+            }
+        }
+
+        if self.has_public_exploit {
+            plan.related("exploit_refs", None, vec![]);
+        }
+
+        if self.known_exploited {
+            plan.related("kev", None, vec![]);
+        }
+
+        if let Some(epss) = self.min_epss {
+            plan.related("epss", Some("epss.score >= ?"), vec![Value::Real(epss)]);
+        }
+
+        if let Some(score) = self.min_risk_score {
+            if let Some(namespace) = namespace {
+                plan.related(
+                    "risk_scores",
+                    Some("risk_scores.namespace = ?"),
+                    vec![Value::Text(namespace.to_string())],
+                );
+            }
+            plan.related(
+                "risk_scores",
+                Some("risk_scores.score >= ?"),
+                vec![Value::Real(score)],
+            );
+        }
+
+        plan.where_clause()
+    }
+}
+
+/// Plan of the ``WHERE`` clause of a query, so that any combination of criteria is answered by a
+/// single statement on the ``cve`` table, returning each matching CVE once in the order asked for.
+///
+/// Criteria are added by kind and ordered from the cheapest to the most expensive to evaluate:
+/// comparisons of the columns of the ``cve`` table first, then the text search, which scans the
+/// descriptions, and last the criteria on the tables derived from the ``cve`` table, such as
+/// ``exploit_refs``. Those are never joined, which would return a CVE once per matching row, but
+/// matched by a correlated ``EXISTS`` subquery per table, holding all the criteria on that table so
+/// that they must be met by the same row.
+#[derive(Default)]
+struct QueryPlan {
+    columns: Vec<(String, Vec<Value>)>,
+    text: Option<(String, Vec<Value>)>,
+    related: Vec<(&'static str, Vec<String>, Vec<Value>)>,
+}
+
+impl QueryPlan {
+    /// Match a ``condition`` on the columns of the ``cve`` table.
+    fn column(&mut self, condition: &str, values: Vec<Value>) {
+        self.columns.push((condition.to_string(), values));
+    }
+
+    /// Match the text search ``condition``.
+    fn text(&mut self, condition: &str, values: Vec<Value>) {
+        self.text = Some((condition.to_string(), values));
+    }
+
+    /// Match CVEs having a row in the derived ``table``, whose ``cve`` column references the
+    /// ``cve`` table, meeting ``condition`` if any, along with the other conditions on ``table``.
+    fn related(&mut self, table: &'static str, condition: Option<&str>, values: Vec<Value>) {
+        let index = match self.related.iter().position(|(name, _, _)| *name == table) {
+            Some(index) => index,
+            None => {
+                self.related.push((table, vec![], vec![]));
+                self.related.len() - 1
+            }
+        };
+        let (_, conditions, related_values) = &mut self.related[index];
+        conditions.extend(condition.map(str::to_string));
+        related_values.extend(values);
+    }
+
+    /// Render the planned ``WHERE`` clause, empty when no criterion was added, and its
+    /// positional parameters.
+    fn where_clause(self) -> (String, Vec<Value>) {
+        let mut conditions = vec![];
+        let mut values = vec![];
+
+        for (condition, condition_values) in self.columns.into_iter().chain(self.text) {
+            conditions.push(condition);
+            values.extend(condition_values);
+        }
+
+        for (table, table_conditions, table_values) in self.related {
+            let mut subquery = format!(
+                "EXISTS (SELECT 1 FROM {table} WHERE {table}.cve = cve.id",
+                table = table
+            );
+            for condition in table_conditions {
+                subquery.push_str(&format!(" AND {}", condition));
+            }
+            subquery.push(')');
+            conditions.push(subquery);
+            values.extend(table_values);
+        }
+
+        if conditions.is_empty() {
+            (String::new(), values)
+        } else {
+            (format!(" WHERE {}", conditions.join(" AND ")), values)
+        }
+    }
+}
+
+/// Key of a vendor or product name in the ``cpe_match`` table, see ``CpeValue::index_key()``
+fn product_key(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// Host and path a reference ``url`` is indexed by in the ``cve_reference`` table, in lowercase
+/// and without a leading ``www.`` or a trailing ``/``, ``None`` if it isn't a URL with a host.
+pub(crate) fn reference_location(url: &str) -> Option<(String, String)> {
+    let url = Url::parse(url.trim()).ok()?;
+    let host = url.host_str()?.to_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host).to_string();
+    let path = url.path().trim_end_matches('/').to_lowercase();
+    Some((host, path))
+}
+
+/// Host and path searched for a ``reference`` criterion, which may leave out the scheme. Text that
+/// isn't a URL is searched as a host, matching none.
+fn reference_key(reference: &str) -> (String, String) {
+    let reference = reference.trim();
+    let location = match reference.contains("://") {
+        true => reference_location(reference),
+        false => reference_location(&format!("https://{}", reference)),
+    };
+    location.unwrap_or_else(|| (reference.to_lowercase(), String::new()))
+}
+
+/// Escape the wildcard characters of a ``LIKE`` pattern so they are matched literally.
+fn escape_like(pattern: &str) -> String {
+    pattern
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
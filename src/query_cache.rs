@@ -0,0 +1,142 @@
+use crate::cache::{generation, open, search, CacheConfig, CacheError};
+use crate::cve::CveSummary;
+use crate::query::SearchQuery;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// Number of results kept by ``QueryCache::new()``.
+pub const DEFAULT_CAPACITY: usize = 128;
+
+/// Results kept by a ``QueryCache`` and the generation of the cache they were searched in
+#[derive(Default)]
+struct Entries {
+    generation: Option<i64>,
+    /// Most recently used first
+    results: VecDeque<(SearchQuery, Vec<CveSummary>)>,
+}
+
+/// Least recently used cache of search results, for long running programs repeatedly running the
+/// same searches against the local cache. All results are dropped once the tables searched are
+/// written to, e.g. by a sync of the feeds, the KEV catalog or the EPSS scores, by scoring risks,
+/// setting overrides or deleting CVEs. Checking for a change costs a single lookup instead of a
+/// search.
+///
+/// Caches replaced without a sync, e.g. by installing a snapshot, should be followed by a call to
+/// ``invalidate()``.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::CacheConfig;
+/// use nvd_cve::cvss::AttackVector;
+/// use nvd_cve::query::SearchQuery;
+/// use nvd_cve::query_cache::QueryCache;
+///
+/// let cache = QueryCache::new(CacheConfig::new());
+///
+/// let query = SearchQuery {
+///     attack_vector: Some(AttackVector::Network),
+///     ..Default::default()
+/// };
+///
+/// // Only the first search runs against SQLite
+/// for _ in 0..10 {
+///     println!("{}", cache.search(&query).unwrap().len());
+/// }
+/// ```
+pub struct QueryCache {
+    config: CacheConfig,
+    capacity: usize,
+    entries: Mutex<Entries>,
+}
+
+impl QueryCache {
+    /// Cache the results of up to ``DEFAULT_CAPACITY`` searches of the local cache of ``config``.
+    pub fn new(config: CacheConfig) -> Self {
+        Self::with_capacity(config, DEFAULT_CAPACITY)
+    }
+
+    /// Cache the results of up to ``capacity`` searches of the local cache of ``config``.
+    pub fn with_capacity(config: CacheConfig, capacity: usize) -> Self {
+        Self {
+            config,
+            capacity,
+            entries: Mutex::new(Entries::default()),
+        }
+    }
+
+    /// Same as ``cache::search()``, returning the results of an identical query if they are still
+    /// cached.
+    pub fn search(&self, query: &SearchQuery) -> Result<Vec<CveSummary>, CacheError> {
+        let generation = self.generation()?;
+
+        {
+            let mut entries = self
+                .entries
+                .lock()
+                .unwrap_or_else(|error| error.into_inner());
+            if entries.generation != generation {
+                entries.results.clear();
+                entries.generation = generation;
+            }
+
+            if let Some(index) = entries
+                .results
+                .iter()
+                .position(|(cached, _)| cached == query)
+            {
+                let entry = entries.results.remove(index).unwrap();
+                let results = entry.1.clone();
+                entries.results.push_front(entry);
+                return Ok(results);
+            }
+        }
+
+        // The lock isn't held while searching, so concurrent searches aren't serialized
+        let results = search(&self.config, query)?;
+
+        let mut entries = self
+            .entries
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        if entries.generation == generation && self.capacity > 0 {
+            entries.results.retain(|(cached, _)| cached != query);
+            entries.results.truncate(self.capacity - 1);
+            entries.results.push_front((query.clone(), results.clone()));
+        }
+
+        Ok(results)
+    }
+
+    /// Drop all the cached results.
+    pub fn invalidate(&self) {
+        let mut entries = self
+            .entries
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        entries.results.clear();
+    }
+
+    /// Number of searches whose results are cached.
+    pub fn len(&self) -> usize {
+        let entries = self
+            .entries
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        entries.results.len()
+    }
+
+    /// Returns ``true`` if no results are cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn generation(&self) -> Result<Option<i64>, CacheError> {
+        let conn = open(&self.config)?;
+        let generation = generation(&conn)?;
+
+        match conn.close() {
+            Ok(_) => Ok(generation),
+            Err((_, error)) => Err(CacheError::RusqliteError(error)),
+        }
+    }
+}
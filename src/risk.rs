@@ -0,0 +1,309 @@
+use crate::cache::{
+    bump_generation, open, override_scope, readable_schema, risk_namespace, summary_columns,
+    summary_from_row, writable_schema, CacheConfig, CacheError,
+};
+use crate::cve::CveSummary;
+use crate::cvss::{AttackVector, Severity};
+use crate::query::SearchQuery;
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Transaction, TransactionBehavior};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// What is known of a cached CVE when computing its risk score.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RiskInput<'a> {
+    /// ID of the CVE, such as ``CVE-2021-44228``.
+    pub id: &'a str,
+
+    /// CVSS v3 base severity, or the CVSS v2 severity for CVEs that were never scored with v3.
+    pub severity: Option<Severity>,
+
+    /// CVSS base score matching ``severity``.
+    pub cvss_score: Option<f64>,
+
+    /// CVSS v3 attack vector.
+    pub attack_vector: Option<AttackVector>,
+
+    /// EPSS probability of the CVE being exploited in the next 30 days, when known.
+    pub epss: Option<f64>,
+
+    /// Whether the CVE is listed in CISA's Known Exploited Vulnerabilities catalog, when known.
+    pub known_exploited: Option<bool>,
+
+    /// Whether the CVE references a public exploit, see ``Cve::exploit_refs()``.
+    pub public_exploit: bool,
+
+    /// Tags of the assets the CVE affects, as set by its local override.
+    pub asset_tags: &'a [String],
+}
+
+/// An organisation's own formula prioritizing CVEs, computed for every cached CVE by
+/// ``score_cves()``. Closures taking a ``&RiskInput`` are scorers too.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::CacheConfig;
+/// use nvd_cve::risk::{score_cves, RiskInput};
+///
+/// let config = CacheConfig::new();
+///
+/// // Only CVEs of internet facing assets matter, twice as much once exploited
+/// let scorer = |cve: &RiskInput| {
+///     if !cve.asset_tags.iter().any(|tag| tag == "internet-facing") {
+///         return None;
+///     }
+///     let exploited = cve.public_exploit || cve.known_exploited == Some(true);
+///     Some(cve.cvss_score.unwrap_or(5.0) * if exploited { 2.0 } else { 1.0 })
+/// };
+///
+/// score_cves(&config, &scorer).unwrap();
+/// ```
+pub trait RiskScorer {
+    /// The risk score of a CVE, or ``None`` to leave it unscored.
+    fn score(&self, input: &RiskInput) -> Option<f64>;
+}
+
+impl<F: Fn(&RiskInput) -> Option<f64>> RiskScorer for F {
+    fn score(&self, input: &RiskInput) -> Option<f64> {
+        self(input)
+    }
+}
+
+/// A ``RiskScorer`` adding up weighted factors, which can be set in config files so that the
+/// ``sync`` command scores the CVEs it syncs. Every CVE is scored, those never scored with CVSS
+/// count a CVSS score of 0.
+///
+/// ## Example:
+/// ```toml
+/// [risk_weights]
+/// public_exploit = 3.0
+/// known_exploited = 5.0
+///
+/// [risk_weights.asset_tags]
+/// internet-facing = 4.0
+/// lab = -2.0
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+pub struct RiskWeights {
+    /// Multiplies the CVSS score, 1 by default.
+    pub cvss: f64,
+
+    /// Multiplies the EPSS probability.
+    pub epss: f64,
+
+    /// Added for CVEs listed as known exploited.
+    pub known_exploited: f64,
+
+    /// Added for CVEs referencing a public exploit.
+    pub public_exploit: f64,
+
+    /// Added for each of the asset tags of a CVE, by tag. Tags that aren't listed add nothing.
+    pub asset_tags: BTreeMap<String, f64>,
+}
+
+impl Default for RiskWeights {
+    fn default() -> Self {
+        Self {
+            cvss: 1.0,
+            epss: 0.0,
+            known_exploited: 0.0,
+            public_exploit: 0.0,
+            asset_tags: BTreeMap::new(),
+        }
+    }
+}
+
+impl RiskScorer for RiskWeights {
+    fn score(&self, input: &RiskInput) -> Option<f64> {
+        let mut score = self.cvss * input.cvss_score.unwrap_or_default()
+            + self.epss * input.epss.unwrap_or_default();
+        if input.known_exploited == Some(true) {
+            score += self.known_exploited;
+        }
+        if input.public_exploit {
+            score += self.public_exploit;
+        }
+        for tag in input.asset_tags {
+            score += self.asset_tags.get(tag).copied().unwrap_or_default();
+        }
+        Some(score)
+    }
+}
+
+/// A cached CVE along with its risk score.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScoredCve {
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub summary: CveSummary,
+
+    /// Score given by the ``RiskScorer`` the cache was last scored with.
+    pub risk_score: f64,
+}
+
+/// Compute the risk score of every cached CVE with ``scorer``, replacing the scores computed
+/// before, and return the number of CVEs scored. Syncs with ``risk_weights`` in their config score
+/// the cache once the feeds are written, other scorers are run after syncing.
+///
+/// Scores only change when the cache is scored again, so a cache should be rescored once the asset
+/// tags of its overrides change. Only the scores of the ``namespace`` of ``config`` are replaced,
+/// computed from the tags of its overrides.
+pub fn score_cves<S: RiskScorer + ?Sized>(
+    config: &CacheConfig,
+    scorer: &S,
+) -> Result<usize, CacheError> {
+    writable_schema(config)?;
+
+    let mut conn = open(config)?;
+
+    // Whether CVEs are known exploited is only known once the catalog was synced
+    let kev_synced: bool =
+        conn.query_row("SELECT EXISTS (SELECT 1 FROM kev)", [], |row| row.get(0))?;
+
+    let mut stmt = conn.prepare(
+        "SELECT cve.id, base_severity, base_score, attack_vector,
+            EXISTS (SELECT 1 FROM exploit_refs WHERE exploit_refs.cve = cve.id),
+            overrides.tags,
+            EXISTS (SELECT 1 FROM kev WHERE kev.cve = cve.id),
+            (SELECT score FROM epss WHERE epss.cve = cve.id)
+        FROM cve LEFT JOIN overrides ON overrides.id = cve.id AND overrides.namespace = ?1",
+    )?;
+    let rows = stmt
+        .query_map([&config.namespace], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<Severity>>(1)?,
+                row.get::<_, Option<f64>>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, bool>(4)?,
+                row.get::<_, Option<String>>(5)?,
+                row.get::<_, bool>(6)?,
+                row.get::<_, Option<f64>>(7)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    stmt.finalize()?;
+
+    let mut scores = vec![];
+    for (id, severity, cvss_score, attack_vector, public_exploit, tags, known_exploited, epss) in
+        &rows
+    {
+        let asset_tags: Vec<String> = match tags {
+            Some(tags) => serde_json::from_str(tags)?,
+            None => vec![],
+        };
+        let input = RiskInput {
+            id,
+            severity: *severity,
+            cvss_score: *cvss_score,
+            attack_vector: attack_vector
+                .as_deref()
+                .and_then(|vector| vector.parse().ok()),
+            epss: *epss,
+            known_exploited: kev_synced.then_some(*known_exploited),
+            public_exploit: *public_exploit,
+            asset_tags: &asset_tags,
+        };
+        if let Some(score) = scorer.score(&input) {
+            scores.push((id, score));
+        }
+    }
+
+    let tx = Transaction::new(&mut conn, TransactionBehavior::Immediate)?;
+    tx.execute(
+        "DELETE FROM risk_scores WHERE namespace = ?1",
+        [&config.namespace],
+    )?;
+    {
+        let mut insert =
+            tx.prepare("INSERT INTO risk_scores (namespace, cve, score) VALUES (?1, ?2, ?3)")?;
+        for (id, score) in &scores {
+            insert.execute(params![config.namespace, id, score])?;
+        }
+    }
+    bump_generation(&tx)?;
+    tx.commit()?;
+
+    match conn.close() {
+        Ok(_) => Ok(scores.len()),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
+
+/// Returns the scored CVEs matching ``query``, all of them for an empty query, from the highest
+/// risk score to the lowest and then by CVE ID. CVEs left unscored aren't returned.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::CacheConfig;
+/// use nvd_cve::query::SearchQuery;
+/// use nvd_cve::risk::search_by_risk;
+///
+/// let config = CacheConfig::new();
+/// let query = SearchQuery {
+///     min_risk_score: Some(12.0),
+///     ..Default::default()
+/// };
+///
+/// for cve in search_by_risk(&config, &query).unwrap() {
+///     println!("{:.1}\t{}", cve.risk_score, cve.summary.id);
+/// }
+/// ```
+pub fn search_by_risk(
+    config: &CacheConfig,
+    query: &SearchQuery,
+) -> Result<Vec<ScoredCve>, CacheError> {
+    readable_schema(config)?;
+
+    let conn = open(config)?;
+
+    // Read-only caches made by older versions may have never been scored
+    let mut tbl_stmt = conn
+        .prepare("SELECT name FROM sqlite_master where type = 'table' and name = 'risk_scores'")?;
+    let scored = tbl_stmt.exists([])?;
+    tbl_stmt.finalize()?;
+    if !scored {
+        return match conn.close() {
+            Ok(_) => Ok(vec![]),
+            Err((_, error)) => Err(CacheError::RusqliteError(error)),
+        };
+    }
+
+    // The scores joined are those of the namespace, which the query matches too
+    let namespace = risk_namespace(&conn, config)?;
+    let overrides = override_scope(&conn, config)?;
+    let (where_clause, values) = query.where_clause(namespace, overrides.as_deref());
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}, risk_scores.score AS risk_score
+        FROM cve JOIN risk_scores ON risk_scores.cve = cve.id{}{}
+        ORDER BY risk_scores.score DESC, id",
+        summary_columns(&conn, config)?,
+        match namespace {
+            Some(_) => " AND risk_scores.namespace = ?",
+            None => "",
+        },
+        where_clause
+    ))?;
+    let namespace = namespace.map(|namespace| Value::Text(namespace.to_string()));
+
+    let cves = stmt
+        .query_map(
+            params_from_iter(namespace.into_iter().chain(values)),
+            |row| {
+                Ok(ScoredCve {
+                    summary: summary_from_row(row)?,
+                    risk_score: row.get("risk_score")?,
+                })
+            },
+        )?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    stmt.finalize()?;
+
+    match conn.close() {
+        Ok(_) => Ok(cves),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
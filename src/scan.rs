@@ -0,0 +1,836 @@
+use crate::cache::{CacheConfig, CacheError};
+use crate::cpe::{Cpe, CpeError, CpeValue};
+use crate::matching::{search_applicable, search_fixed, Applicability, MatchConfidence};
+use crate::version::VersionScheme;
+use crate::vex::VexJustification;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+use std::str::FromStr;
+
+/// CPE names of the software most commonly found on hosts and in container images, by the name of
+/// its package or of the source package it is built from. CPEs name the upstream project, so distribution package names
+/// such as ``libssl3`` are mapped through their source package.
+const BUILTIN_DICTIONARY: &[(&str, &str)] = &[
+    ("apache-log4j2", "cpe:/a:apache:log4j"),
+    ("apache2", "cpe:/a:apache:http_server"),
+    ("bash", "cpe:/a:gnu:bash"),
+    ("bind", "cpe:/a:isc:bind"),
+    ("bind9", "cpe:/a:isc:bind"),
+    ("curl", "cpe:/a:haxx:curl"),
+    ("expat", "cpe:/a:libexpat_project:libexpat"),
+    ("git", "cpe:/a:git-scm:git"),
+    ("glibc", "cpe:/a:gnu:glibc"),
+    ("gnu-tar", "cpe:/a:gnu:tar"),
+    ("gnutls", "cpe:/a:gnu:gnutls"),
+    ("gnutls28", "cpe:/a:gnu:gnutls"),
+    ("golang", "cpe:/a:golang:go"),
+    ("haproxy", "cpe:/a:haproxy:haproxy"),
+    ("httpd", "cpe:/a:apache:http_server"),
+    ("libssh", "cpe:/a:libssh:libssh"),
+    ("libssh2", "cpe:/a:libssh2:libssh2"),
+    ("libxml2", "cpe:/a:xmlsoft:libxml2"),
+    ("log4j", "cpe:/a:apache:log4j"),
+    ("mariadb", "cpe:/a:mariadb:mariadb"),
+    ("memcached", "cpe:/a:memcached:memcached"),
+    ("mysql", "cpe:/a:oracle:mysql"),
+    ("nginx", "cpe:/a:f5:nginx"),
+    ("node", "cpe:/a:nodejs:node.js"),
+    ("nodejs", "cpe:/a:nodejs:node.js"),
+    ("ntp", "cpe:/a:ntp:ntp"),
+    ("openssh", "cpe:/a:openbsd:openssh"),
+    ("openssl", "cpe:/a:openssl:openssl"),
+    ("php", "cpe:/a:php:php"),
+    ("policykit-1", "cpe:/a:polkit_project:polkit"),
+    ("polkit", "cpe:/a:polkit_project:polkit"),
+    ("postgresql", "cpe:/a:postgresql:postgresql"),
+    ("python", "cpe:/a:python:python"),
+    ("redis", "cpe:/a:redis:redis"),
+    ("ruby", "cpe:/a:ruby-lang:ruby"),
+    ("samba", "cpe:/a:samba:samba"),
+    ("sqlite", "cpe:/a:sqlite:sqlite"),
+    ("sqlite3", "cpe:/a:sqlite:sqlite"),
+    ("sudo", "cpe:/a:sudo_project:sudo"),
+    ("systemd", "cpe:/a:systemd_project:systemd"),
+    ("tar", "cpe:/a:gnu:tar"),
+    ("tomcat", "cpe:/a:apache:tomcat"),
+    ("vim", "cpe:/a:vim:vim"),
+    ("wget", "cpe:/a:gnu:wget"),
+    ("xz", "cpe:/a:tukaani:xz"),
+    ("xz-utils", "cpe:/a:tukaani:xz"),
+    ("zlib", "cpe:/a:zlib:zlib"),
+];
+
+/// Errors related to scanning the packages installed on a host
+#[derive(Debug)]
+pub enum ScanError {
+    CacheError(CacheError),
+    IOError(io::Error),
+    /// The package manager exited with an error, along with what it wrote to its standard error.
+    CommandFailed(PackageManager, String),
+    /// A CPE dictionary couldn't be parsed, or maps a package to an invalid CPE name.
+    InvalidDictionary(String),
+    /// A package list isn't the JSON output of syft or of ``docker image inspect``.
+    InvalidInput(String),
+    /// A suppressions file couldn't be parsed, or has an invalid expiry date.
+    InvalidSuppressions(String),
+}
+
+impl From<CacheError> for ScanError {
+    fn from(error: CacheError) -> Self {
+        ScanError::CacheError(error)
+    }
+}
+
+impl From<io::Error> for ScanError {
+    fn from(error: io::Error) -> Self {
+        ScanError::IOError(error)
+    }
+}
+
+/// Package managers whose installed packages can be listed
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageManager {
+    /// Debian, Ubuntu and their derivatives
+    Dpkg,
+    /// Red Hat Enterprise Linux, Fedora, SUSE and their derivatives
+    Rpm,
+    /// macOS, and Linux through Linuxbrew
+    Homebrew,
+}
+
+impl PackageManager {
+    /// All package managers.
+    pub const ALL: [PackageManager; 3] = [
+        PackageManager::Dpkg,
+        PackageManager::Rpm,
+        PackageManager::Homebrew,
+    ];
+
+    /// Name of the package manager, as parsed by ``from_str()``
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PackageManager::Dpkg => "dpkg",
+            PackageManager::Rpm => "rpm",
+            PackageManager::Homebrew => "homebrew",
+        }
+    }
+
+    /// The package managers that may be installed on the operating system this was built for.
+    /// Those that turn out not to be installed are skipped by ``installed_packages()``.
+    pub fn for_host() -> &'static [PackageManager] {
+        if cfg!(target_os = "linux") {
+            &[
+                PackageManager::Dpkg,
+                PackageManager::Rpm,
+                PackageManager::Homebrew,
+            ]
+        } else if cfg!(target_os = "macos") {
+            &[PackageManager::Homebrew]
+        } else {
+            &[]
+        }
+    }
+
+    /// Command listing the installed packages, in the format read by ``parse_packages()``
+    fn command(&self) -> Command {
+        let (program, args): (&str, &[&str]) = match self {
+            PackageManager::Dpkg => (
+                "dpkg-query",
+                &[
+                    "--show",
+                    "--showformat",
+                    "${Status}\\t${Package}\\t${source:Package}\\t${Version}\\n",
+                ],
+            ),
+            PackageManager::Rpm => (
+                "rpm",
+                &[
+                    "--query",
+                    "--all",
+                    "--queryformat",
+                    "%{NAME}\\t%{SOURCERPM}\\t%{VERSION}\\n",
+                ],
+            ),
+            PackageManager::Homebrew => ("brew", &["list", "--versions"]),
+        };
+        let mut command = Command::new(program);
+        command.args(args);
+        command
+    }
+
+    /// Parse the packages listed by the command of the package manager, skipping the lines it
+    /// can't make sense of. Versions are reduced to the version of the upstream project, which is
+    /// the one CPEs name, e.g. ``1:2.4.52-1ubuntu4.3`` is ``2.4.52`` and ``3.1.4_1`` is ``3.1.4``.
+    pub fn parse_packages(&self, output: &str) -> Vec<Package> {
+        let mut packages = vec![];
+        for line in output.lines() {
+            match self {
+                PackageManager::Dpkg => {
+                    let fields: Vec<_> = line.split('\t').collect();
+                    if let [status, name, source, version] = fields[..] {
+                        // Removed packages are listed until purged
+                        if !status.ends_with(" installed") {
+                            continue;
+                        }
+                        packages.push(Package::new(
+                            self.as_str(),
+                            name,
+                            source,
+                            debian_upstream(version),
+                        ));
+                    }
+                }
+                PackageManager::Rpm => {
+                    let fields: Vec<_> = line.split('\t').collect();
+                    if let [name, source_rpm, version] = fields[..] {
+                        let source = source_rpm_name(source_rpm);
+                        packages.push(Package::new(self.as_str(), name, source, version));
+                    }
+                }
+                PackageManager::Homebrew => {
+                    // Every version kept installed is listed after the formula
+                    let mut fields = line.split_whitespace();
+                    let Some(name) = fields.next() else {
+                        continue;
+                    };
+                    // Versioned formulae such as openssl@3 are built from the same source
+                    let source = name.split('@').next().unwrap_or_default();
+                    for version in fields {
+                        let version = version.split('_').next().unwrap_or_default();
+                        packages.push(Package::new(self.as_str(), name, source, version));
+                    }
+                }
+            }
+        }
+        packages
+    }
+}
+
+impl fmt::Display for PackageManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for PackageManager {
+    type Err = String;
+
+    /// Parse the name of a package manager, ignoring case
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        PackageManager::ALL
+            .into_iter()
+            .find(|manager| manager.as_str().eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                format!(
+                    "unknown package manager {:?}, expected dpkg, rpm or homebrew",
+                    name
+                )
+            })
+    }
+}
+
+/// Version of the upstream project of an RPM package version, without its epoch or release
+fn rpm_upstream(version: &str) -> &str {
+    let version = version
+        .split_once(':')
+        .map_or(version, |(_, version)| version);
+    version
+        .split_once('-')
+        .map_or(version, |(version, _)| version)
+}
+
+/// Name of the source package of an RPM package, given the filename of the source RPM, e.g.
+/// ``openssl`` for ``openssl-1.1.1k-6.el8.src.rpm``
+fn source_rpm_name(source_rpm: &str) -> &str {
+    // Source packages are named NAME-VERSION-RELEASE.src.rpm
+    source_rpm.rsplitn(3, '-').nth(2).unwrap_or_default()
+}
+
+/// Version of the upstream project of a Debian package version, without its epoch, revision or
+/// the suffix marking a repacked source
+fn debian_upstream(version: &str) -> &str {
+    let version = version
+        .split_once(':')
+        .map_or(version, |(_, version)| version);
+    let version = version
+        .rsplit_once('-')
+        .map_or(version, |(version, _)| version);
+    ["+dfsg", ".dfsg", "+ds"]
+        .iter()
+        .find_map(|suffix| version.find(suffix).map(|index| &version[..index]))
+        .unwrap_or(version)
+}
+
+/// A package installed on a host or in a container image
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Package {
+    /// What the package is: the package manager that installed it, such as ``dpkg``, the type
+    /// syft gives it, such as ``npm``, or ``image`` for software named by the environment of a
+    /// container image.
+    pub kind: String,
+
+    /// Name of the package, such as ``libssl3``.
+    pub name: String,
+
+    /// Name of the source package it was built from, such as ``openssl``, if known and different
+    /// from its name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+
+    /// Version of the upstream project, such as ``3.0.2``.
+    pub version: String,
+
+    /// CPE names the package was identified by when it was listed, e.g. by syft, used when it
+    /// isn't in the dictionary. Their versions are replaced by the version of the package.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cpes: Vec<String>,
+}
+
+impl Package {
+    fn new(kind: &str, name: &str, source: &str, version: &str) -> Self {
+        Self {
+            kind: kind.to_string(),
+            name: name.to_string(),
+            source: Some(source.to_string()).filter(|source| !source.is_empty() && source != name),
+            version: version.to_string(),
+            cpes: vec![],
+        }
+    }
+}
+
+/// List the packages installed by each of ``managers``, skipping those that aren't installed.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::scan::{installed_packages, PackageManager};
+///
+/// for package in installed_packages(PackageManager::for_host()).unwrap() {
+///     println!("{} {}", package.name, package.version);
+/// }
+/// ```
+pub fn installed_packages(managers: &[PackageManager]) -> Result<Vec<Package>, ScanError> {
+    let mut packages = vec![];
+    for manager in managers {
+        let output = match manager.command().output() {
+            Ok(output) => output,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+            Err(error) => return Err(error.into()),
+        };
+        if !output.status.success() {
+            return Err(ScanError::CommandFailed(
+                *manager,
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+        packages.extend(manager.parse_packages(&String::from_utf8_lossy(&output.stdout)));
+    }
+    Ok(packages)
+}
+
+/// Read the packages listed by ``json``, either the JSON output of syft (``syft -o json``) or of
+/// ``docker image inspect``, so that the contents of container images can be scanned without
+/// running them.
+///
+/// The packages of syft are those of every ecosystem it catalogs, along with the CPE names it
+/// identified them by. Images only name the software they were built with in their environment,
+/// as the official images do with variables such as ``NGINX_VERSION=1.25.3``, which lists the
+/// package ``nginx`` of kind ``image``.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::scan::packages_from_json;
+///
+/// let json = std::fs::read_to_string("sbom.syft.json").unwrap();
+/// for package in packages_from_json(&json).unwrap() {
+///     println!("{}\t{} {}", package.kind, package.name, package.version);
+/// }
+/// ```
+pub fn packages_from_json(json: &str) -> Result<Vec<Package>, ScanError> {
+    let value: Value =
+        serde_json::from_str(json).map_err(|error| ScanError::InvalidInput(error.to_string()))?;
+
+    if let Some(artifacts) = value.get("artifacts").and_then(Value::as_array) {
+        return Ok(artifacts.iter().filter_map(syft_package).collect());
+    }
+
+    // docker image inspect lists the images inspected, each with its config
+    let images = match &value {
+        Value::Array(images) => images.as_slice(),
+        image => std::slice::from_ref(image),
+    };
+    if images.is_empty() || !images.iter().all(|image| image.get("Config").is_some()) {
+        return Err(ScanError::InvalidInput(String::from(
+            "expected the JSON output of syft or of docker image inspect",
+        )));
+    }
+    Ok(images.iter().flat_map(image_packages).collect())
+}
+
+/// The package of an artifact listed by syft, ``None`` if it has no name or version
+fn syft_package(artifact: &Value) -> Option<Package> {
+    fn field<'a>(value: &'a Value, key: &str) -> Option<&'a str> {
+        value.get(key).and_then(Value::as_str)
+    }
+    let name = field(artifact, "name").filter(|name| !name.is_empty())?;
+    let version = field(artifact, "version").filter(|version| !version.is_empty())?;
+    let kind = field(artifact, "type").unwrap_or_default();
+    let metadata = artifact.get("metadata").unwrap_or(&Value::Null);
+
+    let (source, version) = match kind {
+        "deb" => (
+            field(metadata, "source").unwrap_or_default(),
+            debian_upstream(version),
+        ),
+        "rpm" => (
+            field(metadata, "sourceRpm").map_or("", source_rpm_name),
+            rpm_upstream(version),
+        ),
+        // Alpine releases are numbered -rN
+        "apk" => (
+            field(metadata, "originPackage").unwrap_or_default(),
+            version
+                .rsplit_once("-r")
+                .map_or(version, |(version, _)| version),
+        ),
+        _ => ("", version),
+    };
+
+    let mut package = Package::new(kind, name, source, version);
+    // CPEs are listed as names by older versions of syft, and along with their source by newer
+    // ones
+    package.cpes = artifact
+        .get("cpes")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|cpe| cpe.as_str().or_else(|| field(cpe, "cpe")))
+        .map(str::to_string)
+        .collect();
+    Some(package)
+}
+
+/// The software named by the ``*_VERSION`` variables of the environment of an image inspected by
+/// docker
+fn image_packages(image: &Value) -> Vec<Package> {
+    let env = image
+        .pointer("/Config/Env")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    env.iter()
+        .filter_map(Value::as_str)
+        .filter_map(|variable| {
+            let (name, version) = variable.split_once('=')?;
+            let name = name
+                .strip_suffix("_VERSION")
+                .filter(|name| !name.is_empty())?;
+            if version.is_empty() {
+                return None;
+            }
+            let name = name.to_ascii_lowercase().replace('_', "-");
+            Some(Package::new("image", &name, "", version))
+        })
+        .collect()
+}
+
+/// Maps the names of packages to the CPE names of the software they install, with any version.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CpeDictionary {
+    entries: BTreeMap<String, Cpe>,
+}
+
+/// A CPE dictionary as written in TOML files
+#[derive(Deserialize)]
+struct DictionaryFile {
+    #[serde(default)]
+    packages: BTreeMap<String, String>,
+}
+
+impl CpeDictionary {
+    /// The dictionary of the software most commonly found on hosts and in container images, by
+    /// the names of their packages on Debian, Red Hat and Homebrew.
+    pub fn builtin() -> Self {
+        let mut dictionary = Self::default();
+        for (name, cpe) in BUILTIN_DICTIONARY {
+            dictionary.insert(name, cpe.parse().expect("Invalid built-in CPE name"));
+        }
+        dictionary
+    }
+
+    /// Parse a dictionary from TOML, mapping package names to CPE names bound to formatted strings
+    /// or URIs in its ``packages`` table:
+    ///
+    /// ```toml
+    /// [packages]
+    /// libexample = "cpe:/a:example:example"
+    /// ```
+    pub fn from_toml(toml: &str) -> Result<Self, ScanError> {
+        let file: DictionaryFile = toml::from_str(toml)
+            .map_err(|error| ScanError::InvalidDictionary(error.to_string()))?;
+
+        let mut dictionary = Self::default();
+        for (name, cpe) in file.packages {
+            let cpe = cpe.parse().map_err(|error: CpeError| {
+                ScanError::InvalidDictionary(format!("{}: {}", name, error))
+            })?;
+            dictionary.insert(&name, cpe);
+        }
+        Ok(dictionary)
+    }
+
+    /// Read a dictionary from the TOML file at ``path``, see ``from_toml()``.
+    pub fn read(path: &Path) -> Result<Self, ScanError> {
+        Self::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    /// Map the package ``name`` to ``cpe``, replacing what it was mapped to.
+    pub fn insert(&mut self, name: &str, cpe: Cpe) {
+        self.entries.insert(name.to_string(), cpe);
+    }
+
+    /// Add the entries of ``other``, which replace those of the same packages.
+    pub fn extend(&mut self, other: CpeDictionary) {
+        self.entries.extend(other.entries);
+    }
+
+    /// The CPE name of the installed version of ``package``, looked up by its name and then by
+    /// the name of its source package. ``None`` if neither is in the dictionary.
+    pub fn cpe(&self, package: &Package) -> Option<Cpe> {
+        let cpe = self
+            .entries
+            .get(&package.name)
+            .or_else(|| self.entries.get(package.source.as_ref()?))?;
+        Some(Cpe {
+            version: CpeValue::literal(&package.version),
+            ..cpe.clone()
+        })
+    }
+
+    /// The CPE names of the installed version of ``package``: its ``cpe()`` if it is in the
+    /// dictionary, otherwise the valid ``cpes`` it was identified by. Empty if it has neither.
+    pub fn cpes(&self, package: &Package) -> Vec<Cpe> {
+        if let Some(cpe) = self.cpe(package) {
+            return vec![cpe];
+        }
+        let mut cpes = vec![];
+        for name in &package.cpes {
+            if let Ok(cpe) = name.parse::<Cpe>() {
+                let cpe = Cpe {
+                    version: CpeValue::literal(&package.version),
+                    ..cpe
+                };
+                if !cpes.contains(&cpe) {
+                    cpes.push(cpe);
+                }
+            }
+        }
+        cpes
+    }
+}
+
+/// A CVE potentially applicable to an installed package.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Finding {
+    /// ID of the CVE, such as ``CVE-2021-44228``.
+    pub cve: String,
+
+    pub package: Package,
+
+    /// CPE name the package was mapped to.
+    pub cpe: String,
+
+    /// Confidence of the most certain match of the package against the configurations of the
+    /// CVE.
+    pub confidence: MatchConfidence,
+
+    /// Warning to verify the finding manually, see ``MatchConfidence::warning()``.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+}
+
+/// Results of a host scan
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HostScan {
+    /// Number of packages scanned.
+    pub packages: usize,
+
+    /// Packages that couldn't be mapped to a CPE name, and so weren't matched against any CVE.
+    pub unmapped: Vec<Package>,
+
+    /// CVEs potentially applicable to the packages, sorted by CVE ID and package name.
+    pub findings: Vec<Finding>,
+
+    /// Findings accepted by a suppression, see ``suppress()``, sorted as the findings.
+    #[serde(default)]
+    pub suppressed: Vec<SuppressedFinding>,
+
+    /// CVEs the packages were vulnerable to in earlier versions, whose version ranges end before
+    /// the installed version, sorted as the findings.
+    #[serde(default)]
+    pub fixed: Vec<Finding>,
+}
+
+impl HostScan {
+    /// Move the findings accepted by one of ``suppressions`` still in effect on ``today`` from
+    /// ``findings`` to ``suppressed``. Returns the suppressions that expired before ``today``,
+    /// whose findings are reported again.
+    pub fn suppress<'a>(
+        &mut self,
+        suppressions: &'a Suppressions,
+        today: NaiveDate,
+    ) -> Vec<&'a Suppression> {
+        let findings = std::mem::take(&mut self.findings);
+        for finding in findings {
+            match suppressions.find(&finding, today) {
+                Some(suppression) => self.suppressed.push(SuppressedFinding {
+                    finding,
+                    suppression: suppression.clone(),
+                }),
+                None => self.findings.push(finding),
+            }
+        }
+        suppressions.expired(today)
+    }
+}
+
+/// A finding accepted as an exception, such as a CVE whose vulnerable code isn't reachable.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SuppressedFinding {
+    #[serde(flatten)]
+    pub finding: Finding,
+
+    pub suppression: Suppression,
+}
+
+/// Acceptance of the findings of a CVE, for a single package or all of them.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Suppression {
+    /// ID of the CVE, such as ``CVE-2021-44228``, matched case-insensitively.
+    pub cve: String,
+
+    /// Name of the package, or of the source package it is built from, the suppression applies
+    /// to. ``None`` suppresses the CVE for every package.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub component: Option<String>,
+
+    /// Version of the package the suppression applies to, so that upgrading it to another
+    /// vulnerable version reports the CVE again. ``None`` applies to any version.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+
+    /// Why the finding is accepted, kept for audits.
+    pub reason: String,
+
+    /// Why the package isn't affected, given as the justification of VEX statements.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub justification: Option<VexJustification>,
+
+    /// Last day the suppression applies, as ``YYYY-MM-DD``. ``None`` never expires.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<String>,
+}
+
+impl Suppression {
+    /// Whether the suppression accepts ``finding``, regardless of its expiry
+    pub fn matches(&self, finding: &Finding) -> bool {
+        let package = &finding.package;
+        self.cve.eq_ignore_ascii_case(&finding.cve)
+            && self.component.as_ref().is_none_or(|component| {
+                component == &package.name || Some(component) == package.source.as_ref()
+            })
+            && self
+                .version
+                .as_ref()
+                .is_none_or(|version| version == &package.version)
+    }
+
+    /// Whether the suppression expired before ``today``
+    pub fn is_expired(&self, today: NaiveDate) -> bool {
+        self.expiry_date().is_some_and(|expires| expires < today)
+    }
+
+    fn expiry_date(&self) -> Option<NaiveDate> {
+        NaiveDate::parse_from_str(self.expires.as_deref()?, "%Y-%m-%d").ok()
+    }
+}
+
+/// The suppressions of a suppressions file
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Suppressions {
+    #[serde(default)]
+    pub suppressions: Vec<Suppression>,
+}
+
+impl Suppressions {
+    /// Parse suppressions from JSON, either an object listing them in its ``suppressions`` array
+    /// or the array itself:
+    ///
+    /// ```json
+    /// {
+    ///   "suppressions": [
+    ///     {
+    ///       "cve": "CVE-2023-4911",
+    ///       "component": "glibc",
+    ///       "reason": "Containers never run setuid binaries",
+    ///       "expires": "2024-06-30"
+    ///     }
+    ///   ]
+    /// }
+    /// ```
+    pub fn from_json(json: &str) -> Result<Self, ScanError> {
+        let invalid = |error: serde_json::Error| ScanError::InvalidSuppressions(error.to_string());
+        let suppressions = match serde_json::from_str::<Value>(json).map_err(invalid)? {
+            Value::Array(list) => Self {
+                suppressions: serde_json::from_value(Value::Array(list)).map_err(invalid)?,
+            },
+            value => serde_json::from_value(value).map_err(invalid)?,
+        };
+        suppressions.validate()
+    }
+
+    /// Parse suppressions from TOML, listed as ``[[suppressions]]`` tables with the fields of the
+    /// JSON objects of ``from_json()``.
+    pub fn from_toml(toml: &str) -> Result<Self, ScanError> {
+        let suppressions: Self = toml::from_str(toml)
+            .map_err(|error| ScanError::InvalidSuppressions(error.to_string()))?;
+        suppressions.validate()
+    }
+
+    /// Read suppressions from the file at ``path``, parsed as TOML if its extension is ``.toml``
+    /// and as JSON otherwise.
+    pub fn read(path: &Path) -> Result<Self, ScanError> {
+        let contents = std::fs::read_to_string(path)?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Self::from_toml(&contents),
+            _ => Self::from_json(&contents),
+        }
+    }
+
+    /// The first suppression still in effect on ``today`` accepting ``finding``
+    pub fn find(&self, finding: &Finding, today: NaiveDate) -> Option<&Suppression> {
+        self.suppressions
+            .iter()
+            .find(|suppression| !suppression.is_expired(today) && suppression.matches(finding))
+    }
+
+    /// The suppressions that expired before ``today``
+    pub fn expired(&self, today: NaiveDate) -> Vec<&Suppression> {
+        self.suppressions
+            .iter()
+            .filter(|suppression| suppression.is_expired(today))
+            .collect()
+    }
+
+    /// Reject suppressions without a reason, or whose expiry date can't be parsed and so would
+    /// never expire
+    fn validate(self) -> Result<Self, ScanError> {
+        for suppression in &self.suppressions {
+            if suppression.expires.is_some() && suppression.expiry_date().is_none() {
+                return Err(ScanError::InvalidSuppressions(format!(
+                    "{}: invalid expiry date {:?}, expected YYYY-MM-DD",
+                    suppression.cve,
+                    suppression.expires.as_deref().unwrap_or_default()
+                )));
+            }
+            if suppression.reason.trim().is_empty() {
+                return Err(ScanError::InvalidSuppressions(format!(
+                    "{}: missing reason",
+                    suppression.cve
+                )));
+            }
+        }
+        Ok(self)
+    }
+}
+
+/// Map ``packages`` to CPE names with ``dictionary`` and match them against the configurations of
+/// the CVEs of the local cache, comparing versions with ``scheme``. Entirely offline, the cache is
+/// expected to have been synced beforehand.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::CacheConfig;
+/// use nvd_cve::scan::{installed_packages, scan_packages, CpeDictionary, PackageManager};
+/// use nvd_cve::version::VersionScheme;
+///
+/// let config = CacheConfig::new();
+/// let packages = installed_packages(PackageManager::for_host()).unwrap();
+/// let scan = scan_packages(&config, &packages, &CpeDictionary::builtin(), VersionScheme::Auto)
+///     .unwrap();
+///
+/// for finding in scan.findings {
+///     println!("{}\t{}\t{}", finding.cve, finding.package.name, finding.confidence);
+/// }
+/// ```
+pub fn scan_packages(
+    config: &CacheConfig,
+    packages: &[Package],
+    dictionary: &CpeDictionary,
+    scheme: VersionScheme,
+) -> Result<HostScan, ScanError> {
+    let mut mapped = vec![];
+    let mut unmapped = vec![];
+    for package in packages {
+        let cpes = dictionary.cpes(package);
+        if cpes.is_empty() {
+            unmapped.push(package.clone());
+        } else {
+            mapped.push((package, cpes));
+        }
+    }
+
+    let platforms: Vec<Cpe> = mapped.iter().flat_map(|(_, cpes)| cpes.clone()).collect();
+    let (findings, fixed) = match platforms.is_empty() {
+        true => (vec![], vec![]),
+        false => (
+            package_findings(search_applicable(config, &platforms, scheme)?, &mapped),
+            package_findings(search_fixed(config, &platforms, scheme)?, &mapped),
+        ),
+    };
+
+    Ok(HostScan {
+        packages: packages.len(),
+        unmapped,
+        findings,
+        suppressed: vec![],
+        fixed,
+    })
+}
+
+/// The findings of each package matched by each of ``applicable``, as sure as its most certain
+/// match, sorted by CVE ID and package name
+fn package_findings(
+    applicable: Vec<Applicability>,
+    mapped: &[(&Package, Vec<Cpe>)],
+) -> Vec<Finding> {
+    let mut findings = vec![];
+    for applicability in applicable {
+        for (package, cpes) in mapped {
+            let best = applicability
+                .matches
+                .iter()
+                .filter(|m| cpes.contains(&m.platform))
+                .max_by_key(|m| m.confidence);
+            if let Some(best) = best {
+                findings.push(Finding {
+                    cve: applicability.id.clone(),
+                    package: (*package).clone(),
+                    cpe: best.platform.to_string(),
+                    confidence: best.confidence,
+                    warning: best.confidence.warning().map(str::to_string),
+                });
+            }
+        }
+    }
+    findings.sort_by(|a, b| (&a.cve, &a.package.name).cmp(&(&b.cve, &b.package.name)));
+    findings
+}
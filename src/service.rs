@@ -0,0 +1,249 @@
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Scheduler a periodic sync is installed for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceKind {
+    /// A systemd service run by a timer
+    Systemd,
+    /// A crontab entry
+    Cron,
+    /// A Windows Task Scheduler task, imported from XML
+    Windows,
+}
+
+impl ServiceKind {
+    /// The scheduler of the current OS: the Task Scheduler on Windows, systemd on Linux and cron
+    /// elsewhere
+    pub fn native() -> Self {
+        if cfg!(windows) {
+            ServiceKind::Windows
+        } else if cfg!(target_os = "linux") {
+            ServiceKind::Systemd
+        } else {
+            ServiceKind::Cron
+        }
+    }
+}
+
+impl FromStr for ServiceKind {
+    type Err = String;
+
+    fn from_str(kind: &str) -> Result<Self, Self::Err> {
+        match kind.to_lowercase().as_str() {
+            "systemd" => Ok(ServiceKind::Systemd),
+            "cron" => Ok(ServiceKind::Cron),
+            "windows" => Ok(ServiceKind::Windows),
+            _ => Err(format!(
+                "unknown service kind {:?}, expected systemd, cron or windows",
+                kind
+            )),
+        }
+    }
+}
+
+/// A file of a service, to be written to the output directory
+pub struct ServiceFile {
+    pub name: String,
+    pub contents: String,
+}
+
+/// Periodic sync of the local cache, run by a scheduler
+pub struct Service<'a> {
+    /// Name of the service, used for its files
+    pub name: &'a str,
+    /// Path of the ``nvd_cve`` binary run by the service
+    pub program: &'a Path,
+    /// Arguments given to the binary
+    pub args: Vec<String>,
+    /// Time between two syncs
+    pub interval: Duration,
+}
+
+/// Quote ``arg`` for a systemd ``ExecStart`` line, if needed. A ``%`` starts a specifier even
+/// within quotes so it is always doubled.
+fn double_quote(arg: &str) -> String {
+    let arg = arg.replace('%', "%%");
+    if !arg.is_empty() && !arg.contains(|c: char| c.is_whitespace() || c == '"' || c == '\\') {
+        return arg;
+    }
+    format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Quote ``arg`` for a Windows command line, if needed. Backslashes are only special before quotes
+/// so paths are left as they are.
+fn windows_quote(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains(|c: char| c.is_whitespace() || c == '"') {
+        return arg.to_string();
+    }
+    format!("\"{}\"", arg.replace('"', "\\\""))
+}
+
+/// Quote ``arg`` for a POSIX shell, as run by cron, if needed. Cron turns a ``%`` into a newline
+/// before the shell sees it, even within quotes, unless it is escaped as ``\%``.
+fn single_quote(arg: &str) -> String {
+    if !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,".contains(c))
+    {
+        return arg.to_string();
+    }
+    format!("'{}'", arg.replace('\'', "'\\''").replace('%', "\\%"))
+}
+
+/// Escape ``text`` for an XML element
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl Service<'_> {
+    /// Files of the service for the ``kind`` of scheduler, with a hint on how to enable it
+    pub fn files(&self, kind: ServiceKind) -> Result<(Vec<ServiceFile>, String), String> {
+        match kind {
+            ServiceKind::Systemd => Ok(self.systemd()),
+            ServiceKind::Cron => self.cron(),
+            ServiceKind::Windows => Ok(self.windows()),
+        }
+    }
+
+    fn command_line(&self, quote: fn(&str) -> String) -> String {
+        let mut command = vec![quote(&self.program.to_string_lossy())];
+        command.extend(self.args.iter().map(|arg| quote(arg)));
+        command.join(" ")
+    }
+
+    fn systemd(&self) -> (Vec<ServiceFile>, String) {
+        let service = ServiceFile {
+            name: format!("{}.service", self.name),
+            contents: format!(
+                "[Unit]
+Description=Sync the nvd_cve CVE cache
+Wants=network-online.target
+After=network-online.target
+
+[Service]
+Type=oneshot
+ExecStart={}
+",
+                self.command_line(double_quote)
+            ),
+        };
+
+        let timer = ServiceFile {
+            name: format!("{}.timer", self.name),
+            contents: format!(
+                "[Unit]
+Description=Periodically sync the nvd_cve CVE cache
+
+[Timer]
+OnBootSec=5min
+OnUnitActiveSec={}s
+Unit={}
+
+[Install]
+WantedBy=timers.target
+",
+                self.interval.as_secs(),
+                service.name
+            ),
+        };
+
+        let hint = format!(
+            "systemctl daemon-reload && systemctl enable --now {}",
+            timer.name
+        );
+        (vec![service, timer], hint)
+    }
+
+    /// Cron schedule running every ``interval``, which must evenly divide an hour or a day
+    fn cron_schedule(&self) -> Result<String, String> {
+        let minutes = self.interval.as_secs() / 60;
+        if minutes > 0 && self.interval.as_secs() % 60 == 0 {
+            match minutes {
+                1 => return Ok("* * * * *".to_string()),
+                2..=59 if 60 % minutes == 0 => return Ok(format!("*/{} * * * *", minutes)),
+                60 => return Ok("0 * * * *".to_string()),
+                1440 => return Ok("0 0 * * *".to_string()),
+                _ if minutes % 60 == 0 && 24 % (minutes / 60) == 0 => {
+                    return Ok(format!("0 */{} * * *", minutes / 60))
+                }
+                _ => {}
+            }
+        }
+        Err(format!(
+            "an interval of {}s can't be scheduled with cron, use a number of minutes or hours \
+             that evenly divides an hour or a day",
+            self.interval.as_secs()
+        ))
+    }
+
+    fn cron(&self) -> Result<(Vec<ServiceFile>, String), String> {
+        let entry = ServiceFile {
+            name: format!("{}.cron", self.name),
+            contents: format!(
+                "# Sync the nvd_cve CVE cache\n{} {}\n",
+                self.cron_schedule()?,
+                self.command_line(single_quote)
+            ),
+        };
+        let hint = format!("(crontab -l; cat {}) | crontab -", entry.name);
+        Ok((vec![entry], hint))
+    }
+
+    fn windows(&self) -> (Vec<ServiceFile>, String) {
+        let arguments = self
+            .args
+            .iter()
+            .map(|arg| windows_quote(arg))
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        let task = ServiceFile {
+            name: format!("{}.xml", self.name),
+            contents: format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<Task version="1.2" xmlns="http://schemas.microsoft.com/windows/2004/02/mit/task">
+  <RegistrationInfo>
+    <Description>Sync the nvd_cve CVE cache</Description>
+  </RegistrationInfo>
+  <Triggers>
+    <TimeTrigger>
+      <StartBoundary>2000-01-01T00:00:00</StartBoundary>
+      <Repetition>
+        <Interval>PT{}S</Interval>
+      </Repetition>
+      <Enabled>true</Enabled>
+    </TimeTrigger>
+  </Triggers>
+  <Settings>
+    <MultipleInstancesPolicy>IgnoreNew</MultipleInstancesPolicy>
+    <StartWhenAvailable>true</StartWhenAvailable>
+    <RunOnlyIfNetworkAvailable>true</RunOnlyIfNetworkAvailable>
+  </Settings>
+  <Actions>
+    <Exec>
+      <Command>{}</Command>
+      <Arguments>{}</Arguments>
+    </Exec>
+  </Actions>
+</Task>
+"#,
+                self.interval.as_secs(),
+                xml_escape(&self.program.to_string_lossy()),
+                xml_escape(&arguments)
+            ),
+        };
+        let hint = format!("schtasks /Create /TN {} /XML {}", self.name, task.name);
+        (vec![task], hint)
+    }
+}
+
+/// Absolute path of ``path``, which is kept as is if it can't be resolved
+pub fn absolute(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
@@ -0,0 +1,618 @@
+use crate::cache::{
+    bump_generation, open, rebuild_derived_rows, CacheConfig, CacheError, SCHEMA_VERSION,
+};
+use crate::client::HttpError;
+#[cfg(feature = "blocking")]
+use crate::client::ReqwestBlockingClient;
+use chrono::Utc;
+use rusqlite::types::ValueRef;
+use rusqlite::Connection;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+#[cfg(feature = "blocking")]
+use std::io::Cursor;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Name of the manifest within a snapshot or diff archive, always its first entry.
+pub const MANIFEST_NAME: &str = "manifest.json";
+
+/// Name of the SQLite database within a snapshot archive.
+pub const DB_NAME: &str = "cache.sqlite3";
+
+/// Name of the SQLite database of changed rows within a diff archive.
+pub const DIFF_NAME: &str = "diff.sqlite3";
+
+/// Zstandard level snapshots are compressed with.
+const COMPRESSION_LEVEL: i32 = 19;
+
+/// Tables synced from the feeds, by name and primary key, which are the ones compared by digests
+/// and diffs. Overrides and attestations are local to each cache.
+const SYNCED_TABLES: &[(&str, &str)] = &[("cve", "id"), ("metafile", "feed")];
+
+/// Description of a snapshot, stored in its archive along with the database it was taken of.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotManifest {
+    /// Name of the source whose cache was snapshotted.
+    pub source: String,
+
+    /// Version of ``nvd_cve`` that took the snapshot.
+    pub app_version: String,
+
+    /// Schema version of the snapshotted database.
+    pub schema_version: String,
+
+    /// When the snapshot was taken, RFC 3339 formatted.
+    pub created: String,
+
+    /// Size of the uncompressed database in bytes.
+    pub size: u64,
+
+    /// Hex encoded SHA256 sum of the uncompressed database.
+    pub sha256: String,
+
+    /// Hex encoded SHA256 of the CVEs and Metafiles in the database, which unlike ``sha256`` is
+    /// unaffected by how SQLite stores them. Diffs are applied to caches with a given digest.
+    pub digest: String,
+}
+
+/// Description of a diff between two snapshots, stored in its archive along with a database of
+/// the rows that changed between them.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DiffManifest {
+    /// Name of the source whose snapshots were compared.
+    pub source: String,
+
+    /// Version of ``nvd_cve`` that compared the snapshots.
+    pub app_version: String,
+
+    /// When the snapshots were compared, RFC 3339 formatted.
+    pub created: String,
+
+    /// Digest of the snapshot the diff applies to.
+    pub base: String,
+
+    /// Digest of the snapshot the diff produces.
+    pub target: String,
+
+    /// Number of CVEs and Metafiles inserted or updated by the diff.
+    pub upserted: usize,
+
+    /// Number of CVEs and Metafiles removed by the diff.
+    pub deleted: usize,
+
+    /// Size of the uncompressed database of changed rows in bytes.
+    pub size: u64,
+
+    /// Hex encoded SHA256 sum of the uncompressed database of changed rows.
+    pub sha256: String,
+}
+
+/// Manifests recording the size and SHA256 sum of the database archived along with them
+trait Manifest: Serialize + DeserializeOwned {
+    fn checksum(&self) -> (u64, &str);
+}
+
+impl Manifest for SnapshotManifest {
+    fn checksum(&self) -> (u64, &str) {
+        (self.size, &self.sha256)
+    }
+}
+
+impl Manifest for DiffManifest {
+    fn checksum(&self) -> (u64, &str) {
+        (self.size, &self.sha256)
+    }
+}
+
+/// Errors related to cache snapshots
+#[derive(Debug)]
+pub enum SnapshotError {
+    CacheError(CacheError),
+    IOError(io::Error),
+    HttpError(HttpError),
+    JsonError(serde_json::Error),
+    /// The archive isn't a snapshot, e.g. its manifest or database is missing
+    InvalidArchive(String),
+    /// The database in the archive doesn't match the size or SHA256 sum of its manifest
+    ChecksumMismatch {
+        expected: String,
+        actual: String,
+    },
+    /// The cache doesn't hold the snapshot a diff applies to, or doesn't hold the snapshot the diff
+    /// produces once applied
+    DigestMismatch {
+        expected: String,
+        actual: String,
+    },
+}
+
+impl From<CacheError> for SnapshotError {
+    fn from(error: CacheError) -> Self {
+        SnapshotError::CacheError(error)
+    }
+}
+
+impl From<rusqlite::Error> for SnapshotError {
+    fn from(error: rusqlite::Error) -> Self {
+        SnapshotError::CacheError(CacheError::RusqliteError(error))
+    }
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(error: io::Error) -> Self {
+        SnapshotError::IOError(error)
+    }
+}
+
+impl From<HttpError> for SnapshotError {
+    fn from(error: HttpError) -> Self {
+        SnapshotError::HttpError(error)
+    }
+}
+
+impl From<serde_json::Error> for SnapshotError {
+    fn from(error: serde_json::Error) -> Self {
+        SnapshotError::JsonError(error)
+    }
+}
+
+/// Path next to ``path`` with ``suffix`` appended to its file name, for files written before being
+/// moved into place
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Copy ``reader`` to ``writer``, returning the number of bytes copied and their hex encoded SHA256
+fn copy_hashed<R: Read, W: Write>(reader: &mut R, writer: &mut W) -> io::Result<(u64, String)> {
+    let mut hasher = Sha256::new();
+    let mut buffer = [0; 64 * 1024];
+    let mut size = 0;
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            return Ok((size, hex::encode(hasher.finalize())));
+        }
+        hasher.update(&buffer[..read]);
+        writer.write_all(&buffer[..read])?;
+        size += read as u64;
+    }
+}
+
+/// SHA256 of the rows of the ``SYNCED_TABLES`` in the database attached as ``schema``, in the order
+/// of their primary keys
+fn content_digest(conn: &Connection, schema: &str) -> Result<String, SnapshotError> {
+    let mut hasher = Sha256::new();
+
+    for (table, key) in SYNCED_TABLES {
+        hasher.update(table.as_bytes());
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT * FROM {}.{} ORDER BY {}",
+            schema, table, key
+        ))?;
+        let columns = stmt.column_count();
+        let mut rows = stmt.query([])?;
+
+        // Each value is tagged with its type, and text and blobs with their length
+        while let Some(row) = rows.next()? {
+            for column in 0..columns {
+                match row.get_ref(column)? {
+                    ValueRef::Null => hasher.update([0]),
+                    ValueRef::Integer(value) => {
+                        hasher.update([1]);
+                        hasher.update(value.to_le_bytes());
+                    }
+                    ValueRef::Real(value) => {
+                        hasher.update([2]);
+                        hasher.update(value.to_bits().to_le_bytes());
+                    }
+                    ValueRef::Text(value) => {
+                        hasher.update([3]);
+                        hasher.update((value.len() as u64).to_le_bytes());
+                        hasher.update(value);
+                    }
+                    ValueRef::Blob(value) => {
+                        hasher.update([4]);
+                        hasher.update((value.len() as u64).to_le_bytes());
+                        hasher.update(value);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Close ``conn``, returning any error closing it
+fn close(conn: Connection) -> Result<(), SnapshotError> {
+    match conn.close() {
+        Ok(_) => Ok(()),
+        Err((_, error)) => Err(error.into()),
+    }
+}
+
+/// Takes a snapshot of the local cache, written to ``path`` as a Zstandard compressed tar archive
+/// of a manifest followed by a consistent copy of the database, and returns its manifest.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::CacheConfig;
+/// use nvd_cve::snapshot::publish_snapshot;
+///
+/// let config = CacheConfig::new();
+///
+/// let manifest = publish_snapshot(&config, "/srv/www/nvd.tar.zst").unwrap();
+/// println!("{}", manifest.sha256);
+/// ```
+pub fn publish_snapshot<P: AsRef<Path>>(
+    config: &CacheConfig,
+    path: P,
+) -> Result<SnapshotManifest, SnapshotError> {
+    let path = path.as_ref();
+    let copy = sibling(path, ".sqlite3.tmp");
+    fs::remove_file(&copy).ok();
+
+    // Vacuuming into a copy of the cache is consistent even while it's being synced
+    let conn = Connection::open(&config.db)?;
+    conn.execute("VACUUM INTO ?1", [copy.to_string_lossy()])?;
+    close(conn)?;
+
+    let result = (|| {
+        let conn = Connection::open(&copy)?;
+        let digest = content_digest(&conn, "main")?;
+        close(conn)?;
+
+        let (size, sha256) = copy_hashed(&mut File::open(&copy)?, &mut io::sink())?;
+
+        let manifest = SnapshotManifest {
+            source: config.source.clone(),
+            app_version: option_env!("CARGO_PKG_VERSION")
+                .unwrap_or("?.?.?")
+                .to_string(),
+            schema_version: SCHEMA_VERSION.to_string(),
+            created: config.clock.now().to_rfc3339(),
+            size,
+            sha256,
+            digest,
+        };
+        write_archive(&manifest, &copy, DB_NAME, path)?;
+        Ok(manifest)
+    })();
+
+    fs::remove_file(&copy)?;
+    result
+}
+
+/// Write an archive of ``manifest`` followed by the database at ``db``, named ``name``, to ``path``
+fn write_archive<M: Manifest>(
+    manifest: &M,
+    db: &Path,
+    name: &str,
+    path: &Path,
+) -> Result<(), SnapshotError> {
+    let manifest_json = serde_json::to_vec_pretty(manifest)?;
+
+    let partial = sibling(path, ".tmp");
+    let encoder = zstd::Encoder::new(File::create(&partial)?, COMPRESSION_LEVEL)?;
+    let mut archive = tar::Builder::new(encoder);
+
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest_json.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(Utc::now().timestamp() as u64);
+    archive.append_data(&mut header, MANIFEST_NAME, manifest_json.as_slice())?;
+    archive.append_path_with_name(db, name)?;
+
+    archive.into_inner()?.finish()?.sync_all()?;
+    fs::rename(&partial, path)?;
+
+    Ok(())
+}
+
+/// Next entry of an archive, which must be named ``name``
+fn next_entry<'a, R: Read>(
+    entries: &mut tar::Entries<'a, R>,
+    name: &str,
+) -> Result<tar::Entry<'a, R>, SnapshotError> {
+    match entries.next() {
+        Some(Ok(entry)) if entry.path()?.as_ref() == Path::new(name) => Ok(entry),
+        Some(Err(error)) => Err(error.into()),
+        _ => Err(SnapshotError::InvalidArchive(format!(
+            "{} is missing",
+            name
+        ))),
+    }
+}
+
+/// Unpack the manifest of ``archive`` and its database, named ``name``, to ``dest`` once it has
+/// been verified against the manifest. Nothing is left at ``dest`` when unpacking fails.
+fn unpack<M: Manifest, R: Read>(archive: R, name: &str, dest: &Path) -> Result<M, SnapshotError> {
+    let mut archive = tar::Archive::new(zstd::Decoder::new(archive)?);
+    let mut entries = archive.entries()?;
+
+    let mut manifest_entry = next_entry(&mut entries, MANIFEST_NAME)?;
+    let manifest: M = serde_json::from_reader(&mut manifest_entry)?;
+
+    let mut db_entry = next_entry(&mut entries, name)?;
+
+    let copied = File::create(dest).and_then(|mut file| {
+        let copied = copy_hashed(&mut db_entry, &mut file)?;
+        file.sync_all()?;
+        Ok(copied)
+    });
+    let (size, sha256) = match copied {
+        Ok(copied) => copied,
+        Err(error) => {
+            fs::remove_file(dest).ok();
+            return Err(error.into());
+        }
+    };
+
+    let (expected_size, expected_sha256) = manifest.checksum();
+    if size != expected_size || sha256 != expected_sha256 {
+        let expected = expected_sha256.to_string();
+        fs::remove_file(dest)?;
+        return Err(SnapshotError::ChecksumMismatch {
+            expected,
+            actual: sha256,
+        });
+    }
+
+    Ok(manifest)
+}
+
+/// Names of the columns of ``table`` in the database attached as ``schema``
+fn table_columns(
+    conn: &Connection,
+    schema: &str,
+    table: &str,
+) -> Result<Vec<String>, SnapshotError> {
+    let mut stmt = conn.prepare(&format!("PRAGMA {}.table_info({})", schema, table))?;
+    let columns = stmt
+        .query_map([], |row| row.get::<_, String>("name"))?
+        .collect::<Result<Vec<String>, _>>()?;
+    stmt.finalize()?;
+    Ok(columns)
+}
+
+/// Path of the local cache, creating the directory it's in
+fn cache_path(config: &CacheConfig) -> Result<PathBuf, SnapshotError> {
+    let db_path = PathBuf::from(&config.db);
+    if let Some(parent) = db_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    Ok(db_path)
+}
+
+/// Replaces the local cache with the database of a snapshot archive read from ``archive``, once
+/// its size and SHA256 sum have been verified against the archive's manifest. The cache is
+/// replaced in a single rename, so it is never left partially written. Fails with
+/// ``CacheError::ReadOnly`` if the config is ``read_only``.
+pub fn install_snapshot<R: Read>(
+    config: &CacheConfig,
+    archive: R,
+) -> Result<SnapshotManifest, SnapshotError> {
+    if config.read_only {
+        return Err(CacheError::ReadOnly.into());
+    }
+
+    let db_path = cache_path(config)?;
+    let partial = sibling(&db_path, ".snapshot");
+
+    let manifest = unpack(archive, DB_NAME, &partial)?;
+    fs::rename(&partial, &db_path)?;
+
+    Ok(manifest)
+}
+
+/// Fetches the snapshot archive at ``url`` with ``client`` and installs it as the local cache, see
+/// ``install_snapshot``.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::CacheConfig;
+/// use nvd_cve::client::{BlockingHttpClient, ReqwestBlockingClient};
+/// use nvd_cve::snapshot::fetch_snapshot;
+///
+/// let config = CacheConfig::new();
+/// let client = ReqwestBlockingClient::new(&config.url, None, None, None);
+///
+/// fetch_snapshot(&config, &client, "https://mirror.example.com/nvd.tar.zst").unwrap();
+/// ```
+#[cfg(feature = "blocking")]
+pub fn fetch_snapshot(
+    config: &CacheConfig,
+    client: &ReqwestBlockingClient,
+    url: &str,
+) -> Result<SnapshotManifest, SnapshotError> {
+    let archive = client.get_url(url)?;
+    install_snapshot(config, Cursor::new(archive))
+}
+
+/// Compares the snapshot archives at ``base`` and ``target``, writing the rows of the CVEs and
+/// Metafiles that changed between them to ``path`` as a diff archive, and returns its manifest.
+/// Applying the diff to a cache holding the ``base`` snapshot updates it to the ``target`` one.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::snapshot::publish_diff;
+///
+/// let manifest = publish_diff("nvd-1.tar.zst", "nvd-2.tar.zst", "nvd-1-2.diff.tar.zst").unwrap();
+/// println!("{} CVEs changed", manifest.upserted + manifest.deleted);
+/// ```
+pub fn publish_diff<P: AsRef<Path>>(
+    base: P,
+    target: P,
+    path: P,
+) -> Result<DiffManifest, SnapshotError> {
+    let path = path.as_ref();
+    let base_db = sibling(path, ".base.tmp");
+    let target_db = sibling(path, ".target.tmp");
+    let diff_db = sibling(path, ".diff.tmp");
+    fs::remove_file(&diff_db).ok();
+
+    let result = (|| {
+        let base: SnapshotManifest = unpack(File::open(base)?, DB_NAME, &base_db)?;
+        let target: SnapshotManifest = unpack(File::open(target)?, DB_NAME, &target_db)?;
+
+        let conn = Connection::open(&target_db)?;
+        conn.execute("ATTACH ?1 AS base", [base_db.to_string_lossy()])?;
+        conn.execute("ATTACH ?1 AS diff", [diff_db.to_string_lossy()])?;
+
+        conn.execute(
+            "CREATE TABLE diff.deleted (tbl VARCHAR NOT NULL, key VARCHAR NOT NULL)",
+            [],
+        )?;
+
+        let (mut upserted, mut deleted) = (0, 0);
+        for (table, key) in SYNCED_TABLES {
+            conn.execute(
+                &format!(
+                    "CREATE TABLE diff.{0} AS SELECT * FROM main.{0} WHERE 0",
+                    table
+                ),
+                [],
+            )?;
+            upserted += conn.execute(
+                &format!(
+                    "INSERT INTO diff.{0} SELECT * FROM main.{0} EXCEPT SELECT * FROM base.{0}",
+                    table
+                ),
+                [],
+            )?;
+            deleted += conn.execute(
+                &format!(
+                    "INSERT INTO diff.deleted SELECT ?1, {1} FROM base.{0}
+                     WHERE {1} NOT IN (SELECT {1} FROM main.{0})",
+                    table, key
+                ),
+                [table],
+            )?;
+        }
+        close(conn)?;
+
+        let (size, sha256) = copy_hashed(&mut File::open(&diff_db)?, &mut io::sink())?;
+
+        let manifest = DiffManifest {
+            source: target.source,
+            app_version: option_env!("CARGO_PKG_VERSION")
+                .unwrap_or("?.?.?")
+                .to_string(),
+            created: Utc::now().to_rfc3339(),
+            base: base.digest,
+            target: target.digest,
+            upserted,
+            deleted,
+            size,
+            sha256,
+        };
+        write_archive(&manifest, &diff_db, DIFF_NAME, path)?;
+        Ok(manifest)
+    })();
+
+    for tmp in [&base_db, &target_db, &diff_db] {
+        fs::remove_file(tmp).ok();
+    }
+    result
+}
+
+/// Applies a diff archive read from ``archive`` to the local cache, which must hold the snapshot
+/// the diff applies to. The diff is applied in a single transaction, which is only committed once
+/// the cache holds the snapshot the diff produces.
+pub fn apply_diff<R: Read>(
+    config: &CacheConfig,
+    archive: R,
+) -> Result<DiffManifest, SnapshotError> {
+    if config.read_only {
+        return Err(CacheError::ReadOnly.into());
+    }
+
+    let db_path = cache_path(config)?;
+    let diff_db = sibling(&db_path, ".diff");
+
+    let manifest: DiffManifest = unpack(archive, DIFF_NAME, &diff_db)?;
+
+    let result = (|| {
+        // Foreign keys are enforced, for the rows derived from the deleted CVEs to be deleted along
+        let mut conn = open(config)?;
+        conn.execute("ATTACH ?1 AS diff", [diff_db.to_string_lossy()])?;
+
+        let tx = conn.transaction()?;
+
+        let digest = content_digest(&tx, "main")?;
+        if digest != manifest.base {
+            return Err(SnapshotError::DigestMismatch {
+                expected: manifest.base.clone(),
+                actual: digest,
+            });
+        }
+
+        for (table, key) in SYNCED_TABLES {
+            tx.execute(
+                &format!(
+                    "DELETE FROM main.{0} WHERE {1} IN (SELECT key FROM diff.deleted WHERE tbl = ?1)",
+                    table, key
+                ),
+                [table],
+            )?;
+            // Upserted rather than replaced, which would delete the rows referencing them
+            let columns = table_columns(&tx, "diff", table)?;
+            let updates = columns
+                .iter()
+                .filter(|column| column != key)
+                .map(|column| format!("{0} = excluded.{0}", column))
+                .collect::<Vec<_>>();
+            tx.execute(
+                &format!(
+                    "INSERT INTO main.{0} ({1}) SELECT {1} FROM diff.{0} WHERE true
+                    ON CONFLICT ({2}) DO UPDATE SET {3}",
+                    table,
+                    columns.join(", "),
+                    key,
+                    updates.join(", ")
+                ),
+                [],
+            )?;
+        }
+
+        // Exploit references and CPE matches are derived from the CVEs rather than diffed
+        rebuild_derived_rows(
+            &tx,
+            "SELECT id FROM diff.cve UNION SELECT key FROM diff.deleted WHERE tbl = 'cve'",
+        )?;
+
+        let digest = content_digest(&tx, "main")?;
+        if digest != manifest.target {
+            return Err(SnapshotError::DigestMismatch {
+                expected: manifest.target.clone(),
+                actual: digest,
+            });
+        }
+
+        bump_generation(&tx)?;
+        tx.commit()?;
+        conn.execute("DETACH diff", [])?;
+        close(conn)
+    })();
+
+    fs::remove_file(&diff_db)?;
+    result.map(|_| manifest)
+}
+
+/// Fetches the diff archive at ``url`` with ``client`` and applies it to the local cache, see
+/// ``apply_diff``.
+#[cfg(feature = "blocking")]
+pub fn fetch_diff(
+    config: &CacheConfig,
+    client: &ReqwestBlockingClient,
+    url: &str,
+) -> Result<DiffManifest, SnapshotError> {
+    let archive = client.get_url(url)?;
+    apply_diff(config, Cursor::new(archive))
+}
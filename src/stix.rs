@@ -0,0 +1,211 @@
+use crate::cache::{search_cached_cves, CacheConfig, CacheError};
+use crate::cve::{Cve, CveContainer};
+use crate::cvss::Severity;
+use crate::datetime::parse_datetime;
+use crate::query::SearchQuery;
+use serde::Serialize;
+use uuid::Uuid;
+
+/// Namespace of the UUIDs of STIX identifiers derived from the properties of an object, defined by
+/// the STIX 2.1 specification.
+const STIX_NAMESPACE: Uuid = Uuid::from_u128(0x00abedb4_aa42_466c_9c01_fed23315a9b7);
+
+/// Format of STIX timestamps, in UTC with millisecond precision
+const STIX_TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3fZ";
+
+/// A STIX 2.1 external reference, linking an object to the CVE it describes or to a reference of
+/// that CVE.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ExternalReference {
+    pub source_name: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+}
+
+/// A CVE as a STIX 2.1 ``vulnerability`` object.
+///
+/// Its ID is derived from the CVE ID as OpenCTI derives the IDs of the vulnerabilities it creates,
+/// so exporting the same CVE twice, or importing it into a platform that already knows it, updates
+/// a single object. The CVSS severity, score and attack vector are given by the custom properties
+/// OpenCTI reads them from.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct StixVulnerability {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub spec_version: &'static str,
+    pub id: String,
+
+    /// Publication date of the CVE.
+    pub created: String,
+
+    /// Last modification date of the CVE.
+    pub modified: String,
+
+    /// ID of the CVE, such as ``CVE-2021-44228``.
+    pub name: String,
+
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub description: String,
+
+    /// The CVE itself on the NVD, followed by the references of the CVE.
+    pub external_references: Vec<ExternalReference>,
+
+    /// CVSS v3 base score, or the CVSS v2 base score for CVEs that were never scored with v3.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x_opencti_base_score: Option<f64>,
+
+    /// Severity matching ``x_opencti_base_score``.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x_opencti_base_severity: Option<Severity>,
+
+    /// CVSS v3 attack vector, such as ``NETWORK``.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x_opencti_attack_vector: Option<String>,
+}
+
+impl StixVulnerability {
+    /// The vulnerability object of ``cve``, published and last modified at the given feed dates,
+    /// e.g. ``2021-12-10T10:15Z``, which fall back on the Unix epoch if they can't be parsed.
+    pub fn new(
+        cve: &Cve,
+        published: &str,
+        last_modified: &str,
+        severity: Option<(Severity, f64)>,
+        attack_vector: Option<String>,
+    ) -> Self {
+        let id = &cve.cve_data_meta.id;
+
+        let mut external_references = vec![ExternalReference {
+            source_name: "cve".to_string(),
+            url: Some(format!("https://nvd.nist.gov/vuln/detail/{}", id)),
+            external_id: Some(id.clone()),
+        }];
+        external_references.extend(cve.references.reference_data.iter().map(|reference| {
+            ExternalReference {
+                source_name: match reference.ref_source.as_str() {
+                    "" => "nvd".to_string(),
+                    source => source.to_string(),
+                },
+                url: Some(reference.url.clone()),
+                external_id: None,
+            }
+        }));
+
+        let description = cve
+            .description
+            .description_data
+            .iter()
+            .find(|description| description.lang == "en")
+            .map(|description| description.value.clone())
+            .unwrap_or_default();
+
+        let created = stix_timestamp(published);
+        Self {
+            kind: "vulnerability",
+            spec_version: "2.1",
+            id: format!("vulnerability--{}", vulnerability_uuid(id)),
+            modified: stix_timestamp(last_modified).max(created.clone()),
+            created,
+            name: id.clone(),
+            description,
+            external_references,
+            x_opencti_base_score: severity.map(|(_, score)| score),
+            x_opencti_base_severity: severity.map(|(severity, _)| severity),
+            x_opencti_attack_vector: attack_vector,
+        }
+    }
+}
+
+impl From<&CveContainer> for StixVulnerability {
+    fn from(cve: &CveContainer) -> Self {
+        Self::new(
+            &cve.cve,
+            &cve.published_date,
+            &cve.last_modified_date,
+            cve.severity(),
+            cve.impact
+                .base_metric_v3
+                .as_ref()
+                .map(|metric| metric.cvss_v3.attack_vector.as_str().to_string()),
+        )
+    }
+}
+
+/// UUID of the STIX identifier of the vulnerability ``id``: a version 5 UUID of its lowercase CVE
+/// ID, in the canonical JSON form OpenCTI hashes
+fn vulnerability_uuid(id: &str) -> Uuid {
+    let name = serde_json::json!({ "name": id.trim().to_lowercase() }).to_string();
+    Uuid::new_v5(&STIX_NAMESPACE, name.as_bytes())
+}
+
+/// Convert a date of a feed to a STIX timestamp
+fn stix_timestamp(date: &str) -> String {
+    parse_datetime(date)
+        .unwrap_or_default()
+        .format(STIX_TIMESTAMP_FORMAT)
+        .to_string()
+}
+
+/// A STIX 2.1 bundle of vulnerabilities, such as an export of the local cache to be imported by
+/// threat intelligence platforms like OpenCTI or MISP.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::CacheConfig;
+/// use nvd_cve::query::SearchQuery;
+/// use nvd_cve::stix::export_stix;
+///
+/// let config = CacheConfig::new();
+/// let query = SearchQuery {
+///     text: Some("openssl".to_string()),
+///     ..Default::default()
+/// };
+///
+/// let bundle = export_stix(&config, &query).unwrap();
+/// println!("{}", serde_json::to_string(&bundle).unwrap());
+/// ```
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct StixBundle {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub id: String,
+    pub objects: Vec<StixVulnerability>,
+}
+
+impl StixBundle {
+    /// A bundle of ``objects``, whose ID is derived from theirs so the same objects are always
+    /// bundled alike.
+    pub fn new(objects: Vec<StixVulnerability>) -> Self {
+        let ids: Vec<&str> = objects.iter().map(|object| object.id.as_str()).collect();
+        Self {
+            kind: "bundle",
+            id: format!(
+                "bundle--{}",
+                Uuid::new_v5(&STIX_NAMESPACE, ids.join(",").as_bytes())
+            ),
+            objects,
+        }
+    }
+}
+
+/// Export the cached CVEs matching ``query``, all of them for an empty query, as a STIX bundle of
+/// vulnerabilities sorted by CVE ID.
+pub fn export_stix(config: &CacheConfig, query: &SearchQuery) -> Result<StixBundle, CacheError> {
+    let objects = search_cached_cves(config, query)?
+        .iter()
+        .map(|cached| {
+            StixVulnerability::new(
+                &cached.cve,
+                &cached.published,
+                &cached.last_modified,
+                cached.severity,
+                cached.attack_vector.clone(),
+            )
+        })
+        .collect();
+    Ok(StixBundle::new(objects))
+}
@@ -0,0 +1,143 @@
+use std::str::FromStr;
+
+/// A line of text in which placeholders such as ``{score}`` are replaced by the values of the
+/// fields ``F`` they name, as in the tickets of a watchlist and the lines of
+/// ``search --format-str``. A placeholder can be formatted as ``{field:<WIDTH.PRECISION}``: values
+/// are cut to their first PRECISION characters, then padded to at least WIDTH characters, on the
+/// right by default or with ``<``, on the left with ``>``. ``{{`` and ``}}`` are literal braces.
+///
+/// ## Example:
+/// ```
+/// use nvd_cve::template::Template;
+///
+/// let template: Template<String> = "{{{id:>6}}} {name:.3}".parse().unwrap();
+/// assert_eq!(template.render(|field| field.to_uppercase()), "{    ID} NAM");
+///
+/// assert!("{id".parse::<Template<String>>().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template<F>(Vec<Segment<F>>);
+
+/// Text of a ``Template`` or one of its placeholders
+#[derive(Debug, Clone, PartialEq)]
+enum Segment<F> {
+    Text(String),
+    Placeholder {
+        field: F,
+        right_aligned: bool,
+        width: usize,
+        precision: Option<usize>,
+    },
+}
+
+impl<F: FromStr> FromStr for Template<F>
+where
+    F::Err: ToString,
+{
+    type Err = String;
+
+    fn from_str(template: &str) -> Result<Self, Self::Err> {
+        let mut segments = vec![];
+        let mut text = String::new();
+        let mut chars = template.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '}' => return Err(String::from("unmatched }, write }} for a literal brace")),
+                '{' => {
+                    let rest = chars.as_str();
+                    let end = rest
+                        .find('}')
+                        .ok_or_else(|| String::from("unclosed {, write {{ for a literal brace"))?;
+                    if !text.is_empty() {
+                        segments.push(Segment::Text(std::mem::take(&mut text)));
+                    }
+                    segments.push(Segment::placeholder(&rest[..end])?);
+                    chars = rest[end + 1..].chars();
+                }
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+        Ok(Template(segments))
+    }
+}
+
+impl<F: FromStr> Segment<F>
+where
+    F::Err: ToString,
+{
+    /// Parse a placeholder, ``field`` or ``field:spec``, without its braces
+    fn placeholder(placeholder: &str) -> Result<Self, String> {
+        let (field, spec) = placeholder.split_once(':').unwrap_or((placeholder, ""));
+        let invalid_spec = || {
+            format!(
+                "invalid format {:?} of {{{}}}, e.g. {{{}:.80}} or {{{}:>10}}",
+                spec, field, field, field
+            )
+        };
+
+        let (right_aligned, spec_rest) = match spec.strip_prefix('>') {
+            Some(rest) => (true, rest),
+            None => (false, spec.strip_prefix('<').unwrap_or(spec)),
+        };
+        let (width, precision) = match spec_rest.split_once('.') {
+            Some((width, precision)) => (width, Some(precision)),
+            None => (spec_rest, None),
+        };
+        let number = |digits: &str| match digits.chars().all(|c| c.is_ascii_digit()) {
+            true => digits.parse::<usize>().map_err(|_| invalid_spec()),
+            false => Err(invalid_spec()),
+        };
+
+        Ok(Segment::Placeholder {
+            field: field.parse().map_err(|error: F::Err| error.to_string())?,
+            right_aligned,
+            width: match width {
+                "" => 0,
+                width => number(width)?,
+            },
+            precision: precision.map(number).transpose()?,
+        })
+    }
+}
+
+impl<F> Template<F> {
+    /// The text of the template with each placeholder replaced by the ``value`` of its field
+    pub fn render(&self, value: impl Fn(&F) -> String) -> String {
+        let mut rendered = String::new();
+        for segment in &self.0 {
+            match segment {
+                Segment::Text(text) => rendered.push_str(text),
+                Segment::Placeholder {
+                    field,
+                    right_aligned,
+                    width,
+                    precision,
+                } => {
+                    let value = value(field);
+                    let value: String = match precision {
+                        Some(precision) => value.chars().take(*precision).collect(),
+                        None => value,
+                    };
+                    let padding = " ".repeat(width.saturating_sub(value.chars().count()));
+                    match right_aligned {
+                        true => rendered.push_str(&(padding + &value)),
+                        false => rendered.push_str(&(value + &padding)),
+                    }
+                }
+            }
+        }
+        rendered
+    }
+}
@@ -0,0 +1,408 @@
+use crate::cache::{open, readable_schema, search, writable_schema, CacheConfig, CacheError};
+use crate::client::HttpError;
+#[cfg(feature = "blocking")]
+use crate::client::ReqwestBlockingClient;
+use crate::cve::CveSummary;
+use crate::query::SearchQuery;
+use crate::template::Template;
+use log::debug;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "blocking")]
+use serde_json::json;
+use std::str::FromStr;
+
+/// Summary of the tickets created when none is given, see ``TicketTemplate``.
+pub const DEFAULT_SUMMARY_TEMPLATE: &str = "{id} ({severity} {score})";
+
+/// Description of the tickets created when none is given, see ``TicketTemplate``.
+pub const DEFAULT_DESCRIPTION_TEMPLATE: &str = "{description}\n\nPublished: {published}\n{url}";
+
+/// Longest summary Jira accepts, longer summaries are truncated.
+#[cfg(feature = "blocking")]
+const JIRA_SUMMARY_LENGTH: usize = 255;
+
+/// Errors related to creating tickets
+#[derive(Debug)]
+pub enum TicketError {
+    CacheError(CacheError),
+    HttpError(HttpError),
+    /// A template refers to a field CVEs don't have, or has an unmatched brace
+    InvalidTemplate(String),
+    /// The query matches every CVE, which would open a ticket for each of them
+    EmptyWatchlist,
+}
+
+impl From<CacheError> for TicketError {
+    fn from(error: CacheError) -> Self {
+        TicketError::CacheError(error)
+    }
+}
+
+impl From<rusqlite::Error> for TicketError {
+    fn from(error: rusqlite::Error) -> Self {
+        TicketError::CacheError(CacheError::RusqliteError(error))
+    }
+}
+
+impl From<HttpError> for TicketError {
+    fn from(error: HttpError) -> Self {
+        TicketError::HttpError(error)
+    }
+}
+
+impl From<serde_json::Error> for TicketError {
+    fn from(_: serde_json::Error) -> Self {
+        TicketError::HttpError(HttpError::JsonError)
+    }
+}
+
+/// Summary and description of the tickets created for the CVEs of a watchlist, in which
+/// ``{id}``, ``{severity}``, ``{score}``, ``{published}``, ``{description}`` and ``{url}`` (the
+/// CVE's page on the NVD) are replaced by the values of each CVE, or ``-`` when unknown as with
+/// ``search --fields``. Fields can be cut and padded as in ``search --format-str``, e.g.
+/// ``{description:.80}``, see ``Template``. ``{{`` and ``}}`` are a literal brace.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TicketTemplate {
+    summary: Template<TicketField>,
+    description: Template<TicketField>,
+}
+
+/// Field of a CVE in a ``TicketTemplate``
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TicketField {
+    Id,
+    Severity,
+    Score,
+    Published,
+    Description,
+    Url,
+}
+
+impl FromStr for TicketField {
+    type Err = String;
+
+    fn from_str(field: &str) -> Result<Self, Self::Err> {
+        match field {
+            "id" => Ok(TicketField::Id),
+            "severity" => Ok(TicketField::Severity),
+            "score" => Ok(TicketField::Score),
+            "published" => Ok(TicketField::Published),
+            "description" => Ok(TicketField::Description),
+            "url" => Ok(TicketField::Url),
+            _ => Err(format!(
+                "unknown field {:?}, expected id, severity, score, published, description or url",
+                field
+            )),
+        }
+    }
+}
+
+impl TicketField {
+    /// Value of the field for ``cve``, ``-`` if it is unknown
+    fn value(&self, cve: &CveSummary) -> String {
+        match self {
+            TicketField::Id => cve.id.clone(),
+            TicketField::Severity => cve
+                .assessed_severity()
+                .map_or("-".to_string(), |s| s.to_string()),
+            TicketField::Score => cve.score.map_or("-".to_string(), |s| format!("{:.1}", s)),
+            TicketField::Published => cve.published.clone().unwrap_or_else(|| "-".to_string()),
+            TicketField::Description => cve.description.clone(),
+            TicketField::Url => format!("https://nvd.nist.gov/vuln/detail/{}", cve.id),
+        }
+    }
+}
+
+impl Default for TicketTemplate {
+    fn default() -> Self {
+        Self::new(DEFAULT_SUMMARY_TEMPLATE, DEFAULT_DESCRIPTION_TEMPLATE)
+            .expect("Invalid default ticket template")
+    }
+}
+
+impl TicketTemplate {
+    /// A template of the given summary and description, failing with
+    /// ``TicketError::InvalidTemplate`` if either refers to an unknown field or has an unmatched
+    /// brace.
+    ///
+    /// ## Example:
+    /// ```
+    /// use nvd_cve::cve::CveSummary;
+    /// use nvd_cve::ticket::TicketTemplate;
+    ///
+    /// let template = TicketTemplate::new("Patch {id}", "{description}").unwrap();
+    /// let cve = CveSummary {
+    ///     id: "CVE-2021-44228".to_string(),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(template.render(&cve).summary, "Patch CVE-2021-44228");
+    ///
+    /// assert!(TicketTemplate::new("Patch {cve}", "").is_err());
+    /// ```
+    pub fn new<S: Into<String>>(summary: S, description: S) -> Result<Self, TicketError> {
+        let parse = |template: String| {
+            template
+                .parse()
+                .map_err(|_| TicketError::InvalidTemplate(template))
+        };
+        Ok(Self {
+            summary: parse(summary.into())?,
+            description: parse(description.into())?,
+        })
+    }
+
+    /// The ticket of ``cve``.
+    pub fn render(&self, cve: &CveSummary) -> Ticket {
+        Ticket {
+            cve: cve.id.clone(),
+            summary: self.summary.render(|field| field.value(cve)),
+            description: self.description.render(|field| field.value(cve)),
+        }
+    }
+}
+
+/// A ticket to open for a CVE of a watchlist.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Ticket {
+    /// ID of the CVE the ticket is about.
+    pub cve: String,
+    pub summary: String,
+    pub description: String,
+}
+
+/// A ticket created for a CVE, as recorded in the local cache.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CreatedTicket {
+    /// ID of the CVE the ticket is about.
+    pub cve: String,
+
+    /// Key of the ticket in the tracker, such as ``SEC-42``, if the tracker returned one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+
+    /// When the ticket was created, RFC 3339 formatted.
+    pub created: String,
+}
+
+/// Trait for the issue trackers and webhooks tickets are created in.
+pub trait TicketTracker {
+    /// Name of the tracker the tickets it created are recorded under, e.g. its URL, so that a
+    /// CVE gets one ticket in each tracker.
+    fn name(&self) -> String;
+
+    /// Create ``ticket``, returning its key in the tracker if it has one.
+    fn create_ticket(&mut self, ticket: &Ticket) -> Result<Option<String>, TicketError>;
+}
+
+/// Posts each ticket to a webhook as a JSON object of its ``cve``, ``summary`` and
+/// ``description``. The ``key`` or ``id`` of the JSON object returned, if any, is recorded as the
+/// ticket's key.
+#[cfg(feature = "blocking")]
+pub struct WebhookTracker {
+    client: ReqwestBlockingClient,
+    url: String,
+    authorization: Option<String>,
+}
+
+#[cfg(feature = "blocking")]
+impl WebhookTracker {
+    /// A tracker posting to the webhook at ``url`` with ``client``.
+    pub fn new<S: Into<String>>(client: ReqwestBlockingClient, url: S) -> Self {
+        Self {
+            client,
+            url: url.into(),
+            authorization: None,
+        }
+    }
+
+    /// Send this as the ``Authorization`` header of each request, e.g. ``Bearer TOKEN``.
+    pub fn with_authorization<S: Into<String>>(mut self, authorization: S) -> Self {
+        self.authorization = Some(authorization.into());
+        self
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl TicketTracker for WebhookTracker {
+    fn name(&self) -> String {
+        self.url.clone()
+    }
+
+    fn create_ticket(&mut self, ticket: &Ticket) -> Result<Option<String>, TicketError> {
+        let body = self
+            .client
+            .post_json(&self.url, ticket, self.authorization.as_deref())?;
+        let response: serde_json::Value = serde_json::from_slice(&body).unwrap_or_default();
+        Ok(["key", "id"].iter().find_map(|name| match &response[name] {
+            serde_json::Value::String(key) => Some(key.clone()),
+            serde_json::Value::Number(key) => Some(key.to_string()),
+            _ => None,
+        }))
+    }
+}
+
+/// Creates each ticket as an issue of a Jira project through its REST API, labelled with the ID
+/// of its CVE.
+///
+/// ## Example:
+/// ```no_run
+/// use nvd_cve::cache::CacheConfig;
+/// use nvd_cve::client::{BlockingHttpClient, ReqwestBlockingClient};
+/// use nvd_cve::query::SearchQuery;
+/// use nvd_cve::ticket::{create_tickets, JiraTracker, TicketTemplate};
+///
+/// let config = CacheConfig::new();
+/// let query = SearchQuery {
+///     text: Some("openssl".to_string()),
+///     ..Default::default()
+/// };
+///
+/// let client = ReqwestBlockingClient::new("https://jira.example.com/", None, None, None);
+/// let mut jira = JiraTracker::new(client, "https://jira.example.com/", "SEC")
+///     .with_authorization("Bearer TOKEN");
+/// let created = create_tickets(&config, &query, &TicketTemplate::default(), &mut jira).unwrap();
+/// ```
+#[cfg(feature = "blocking")]
+pub struct JiraTracker {
+    client: ReqwestBlockingClient,
+    url: String,
+    project: String,
+    issue_type: String,
+    authorization: Option<String>,
+}
+
+#[cfg(feature = "blocking")]
+impl JiraTracker {
+    /// A tracker creating Bug issues in the ``project`` of the Jira at ``url`` with ``client``.
+    pub fn new<S: Into<String>>(client: ReqwestBlockingClient, url: S, project: S) -> Self {
+        Self {
+            client,
+            url: url.into(),
+            project: project.into(),
+            issue_type: "Bug".to_string(),
+            authorization: None,
+        }
+    }
+
+    /// Create issues of this type instead of Bug, e.g. Task.
+    pub fn with_issue_type<S: Into<String>>(mut self, issue_type: S) -> Self {
+        self.issue_type = issue_type.into();
+        self
+    }
+
+    /// Send this as the ``Authorization`` header of each request, e.g. ``Bearer TOKEN`` for a
+    /// personal access token or ``Basic`` and the base64 of ``email:token`` for Jira Cloud.
+    pub fn with_authorization<S: Into<String>>(mut self, authorization: S) -> Self {
+        self.authorization = Some(authorization.into());
+        self
+    }
+}
+
+/// Response of Jira to a created issue
+#[cfg(feature = "blocking")]
+#[derive(Deserialize)]
+struct JiraIssue {
+    key: String,
+}
+
+#[cfg(feature = "blocking")]
+impl TicketTracker for JiraTracker {
+    fn name(&self) -> String {
+        format!("{}#{}", self.url, self.project)
+    }
+
+    fn create_ticket(&mut self, ticket: &Ticket) -> Result<Option<String>, TicketError> {
+        let summary: String = ticket.summary.chars().take(JIRA_SUMMARY_LENGTH).collect();
+        let issue = json!({
+            "fields": {
+                "project": { "key": self.project },
+                "issuetype": { "name": self.issue_type },
+                "summary": summary,
+                "description": ticket.description,
+                "labels": [ticket.cve],
+            }
+        });
+
+        let url = format!("{}/rest/api/2/issue", self.url.trim_end_matches('/'));
+        let body = self
+            .client
+            .post_json(&url, &issue, self.authorization.as_deref())?;
+        let issue: JiraIssue = serde_json::from_slice(&body)?;
+        Ok(Some(issue.key))
+    }
+}
+
+/// Create a ticket in ``tracker`` for each cached CVE matching the ``watchlist`` query, e.g. right
+/// after a sync, returning the tickets created. CVEs that already have a ticket in the tracker are
+/// skipped, as recorded in the local cache, so running it after every sync only opens tickets for
+/// the CVEs that newly match. Each ticket is recorded as soon as it is created, so a failure
+/// leaves no CVE with two tickets once it is run again.
+pub fn create_tickets<T: TicketTracker>(
+    config: &CacheConfig,
+    watchlist: &SearchQuery,
+    template: &TicketTemplate,
+    tracker: &mut T,
+) -> Result<Vec<CreatedTicket>, TicketError> {
+    if watchlist.is_empty() {
+        return Err(TicketError::EmptyWatchlist);
+    }
+    writable_schema(config)?;
+
+    let name = tracker.name();
+    let known = get_tickets(config, &name)?;
+    let cves = search(config, watchlist)?;
+
+    let conn = open(config)?;
+    let mut insert =
+        conn.prepare("INSERT INTO tickets (tracker, cve, key, created) VALUES (?1, ?2, ?3, ?4)")?;
+
+    let mut created = vec![];
+    for cve in cves {
+        if known.iter().any(|ticket| ticket.cve == cve.id) {
+            continue;
+        }
+
+        let key = tracker.create_ticket(&template.render(&cve))?;
+        debug!("Created ticket {:?} for {} in {}", key, cve.id, name);
+
+        let ticket = CreatedTicket {
+            cve: cve.id,
+            key,
+            created: config.clock.now().to_rfc3339(),
+        };
+        insert.execute(params![name, ticket.cve, ticket.key, ticket.created])?;
+        created.push(ticket);
+    }
+    insert.finalize()?;
+
+    match conn.close() {
+        Ok(_) => Ok(created),
+        Err((_, error)) => Err(CacheError::RusqliteError(error).into()),
+    }
+}
+
+/// The tickets created in the tracker named ``tracker``, see ``TicketTracker::name()``, ordered by
+/// CVE ID.
+pub fn get_tickets(config: &CacheConfig, tracker: &str) -> Result<Vec<CreatedTicket>, CacheError> {
+    readable_schema(config)?;
+
+    let conn = open(config)?;
+    let mut stmt =
+        conn.prepare("SELECT cve, key, created FROM tickets WHERE tracker = ?1 ORDER BY cve")?;
+    let tickets = stmt
+        .query_map([tracker], |row| {
+            Ok(CreatedTicket {
+                cve: row.get(0)?,
+                key: row.get(1)?,
+                created: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<CreatedTicket>, rusqlite::Error>>()?;
+    stmt.finalize()?;
+
+    match conn.close() {
+        Ok(_) => Ok(tickets),
+        Err((_, error)) => Err(CacheError::RusqliteError(error)),
+    }
+}
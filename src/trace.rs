@@ -0,0 +1,104 @@
+use crate::cache::{FeedTimings, StageSpan};
+use serde_json::json;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Stages of a sync, in the order a feed goes through them
+const STAGES: [&str; 4] = ["download", "decompress", "parse", "write"];
+
+/// The stages of a sync along with their span for a feed
+fn stages(timings: &FeedTimings) -> [(&'static str, Option<StageSpan>); 4] {
+    let spans = [
+        timings.download,
+        timings.decompress,
+        timings.parse,
+        timings.write,
+    ];
+    [0, 1, 2, 3].map(|stage| (STAGES[stage], spans[stage]))
+}
+
+/// Write the stage timings of the feeds of a sync to ``path``, as folded stacks
+/// (``sync;<feed>;<stage> <microseconds>``) read by ``flamegraph.pl`` and inferno if its extension
+/// is ``.folded``, otherwise as a Chrome Trace Event file read by Perfetto, speedscope and
+/// ``chrome://tracing``, with a track per stage so that the stages working concurrently on
+/// different feeds can be told apart.
+///
+/// ## Example:
+/// ```no_run
+/// # #[cfg(feature = "blocking")]
+/// # fn main() {
+/// use nvd_cve::cache::{sync_blocking, CacheConfig};
+/// use nvd_cve::client::{BlockingHttpClient, ReqwestBlockingClient};
+/// use nvd_cve::trace::write_trace;
+///
+/// let mut config = CacheConfig::new();
+/// config.trace_feeds = true;
+/// let client = ReqwestBlockingClient::new(&config.url, None, None, None);
+///
+/// let report = sync_blocking(&config, client).unwrap();
+/// write_trace("sync.trace.json", &report.feed_timings).unwrap();
+/// # }
+/// # #[cfg(not(feature = "blocking"))]
+/// # fn main() {}
+/// ```
+pub fn write_trace<P: AsRef<Path>>(path: P, timings: &[FeedTimings]) -> io::Result<()> {
+    let path = path.as_ref();
+    let trace = if path.extension().is_some_and(|ext| ext == "folded") {
+        folded_stacks(timings)
+    } else {
+        trace_events(timings)
+    };
+    fs::write(path, trace)
+}
+
+fn folded_stacks(timings: &[FeedTimings]) -> String {
+    let mut folded = String::new();
+    for feed in timings {
+        for (stage, span) in stages(feed) {
+            if let Some(span) = span {
+                folded.push_str(&format!(
+                    "sync;{};{} {}\n",
+                    feed.feed,
+                    stage,
+                    span.duration.as_micros()
+                ));
+            }
+        }
+    }
+    folded
+}
+
+fn trace_events(timings: &[FeedTimings]) -> String {
+    let mut events: Vec<_> = STAGES
+        .iter()
+        .enumerate()
+        .map(|(tid, stage)| {
+            json!({
+                "name": "thread_name",
+                "ph": "M",
+                "pid": 1,
+                "tid": tid + 1,
+                "args": {"name": stage},
+            })
+        })
+        .collect();
+
+    for feed in timings {
+        for (tid, (stage, span)) in stages(feed).into_iter().enumerate() {
+            if let Some(span) = span {
+                events.push(json!({
+                    "name": feed.feed.to_string(),
+                    "cat": stage,
+                    "ph": "X",
+                    "ts": span.start.as_micros() as u64,
+                    "dur": span.duration.as_micros() as u64,
+                    "pid": 1,
+                    "tid": tid + 1,
+                }));
+            }
+        }
+    }
+
+    json!({"traceEvents": events, "displayTimeUnit": "ms"}).to_string()
+}
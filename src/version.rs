@@ -0,0 +1,569 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// Scheme used to order the versions of a product, such as those bounding the CPE version ranges
+/// of a CVE. Versions are compared piece by piece rather than as strings, so that ``1.10`` comes
+/// after ``1.9``.
+///
+/// ## Example:
+/// ```
+/// use nvd_cve::version::VersionScheme;
+/// use std::cmp::Ordering;
+///
+/// assert_eq!(VersionScheme::Auto.compare("1.10", "1.9"), Ordering::Greater);
+/// assert_eq!(VersionScheme::Auto.compare("1.1.1k", "1.1.1"), Ordering::Greater);
+/// assert_eq!(VersionScheme::Auto.compare("2.0.0-rc.1", "2.0.0"), Ordering::Less);
+/// assert_eq!(VersionScheme::Debian.compare("1:1.0", "2.0"), Ordering::Greater);
+/// assert_eq!(VersionScheme::Rpm.compare("1.0~rc1", "1.0"), Ordering::Less);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum VersionScheme {
+    /// Detect the scheme of the versions compared, see ``VersionScheme::detect()``.
+    #[default]
+    Auto,
+    /// Semantic versions, such as ``2.0.0-rc.1+build.5``: pre-releases come before the release
+    /// and build metadata is ignored. A leading ``v`` and missing minor or patch versions are
+    /// tolerated.
+    Semver,
+    /// Debian package versions, such as ``1:2.4.52-1ubuntu4.3``, ordered as ``dpkg`` does.
+    Debian,
+    /// RPM package versions, such as ``1:1.1.1k-6.el8``, ordered as ``rpmvercmp`` does.
+    Rpm,
+    /// Dotted numbers as found in most CPE names, such as ``8.0.6001``. Letters following a
+    /// number come after it, as in OpenSSL's ``1.1.1k``, unless they mark a pre-release such as
+    /// ``rc1`` or ``beta``, and missing components count as zero.
+    Dotted,
+}
+
+impl VersionScheme {
+    /// All schemes, ``Auto`` first.
+    pub const ALL: [VersionScheme; 5] = [
+        VersionScheme::Auto,
+        VersionScheme::Semver,
+        VersionScheme::Debian,
+        VersionScheme::Rpm,
+        VersionScheme::Dotted,
+    ];
+
+    /// Name of the scheme, as parsed by ``from_str()``
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VersionScheme::Auto => "auto",
+            VersionScheme::Semver => "semver",
+            VersionScheme::Debian => "debian",
+            VersionScheme::Rpm => "rpm",
+            VersionScheme::Dotted => "dotted",
+        }
+    }
+
+    /// The scheme ``version`` most likely follows, never ``Auto``. Releases such as ``.el8`` or a
+    /// caret make it an RPM version, an epoch, a tilde or a revision starting with a digit a Debian
+    /// version, other pre-releases or build metadata following three numbers a semantic version,
+    /// and anything else is dotted.
+    pub fn detect(version: &str) -> Self {
+        let version = version.trim();
+        let (upstream, release) = match version.rsplit_once('-') {
+            Some((upstream, release)) => (upstream, Some(release)),
+            None => (version, None),
+        };
+
+        let rpm_release = |release: &str| {
+            release.split('.').any(|part| {
+                ["el", "fc", "amzn", "mga"].iter().any(|dist| {
+                    part.strip_prefix(dist)
+                        .is_some_and(|n| n.starts_with(|c: char| c.is_ascii_digit()))
+                })
+            })
+        };
+        if version.contains('^') || release.is_some_and(rpm_release) {
+            return VersionScheme::Rpm;
+        }
+
+        let epoch = upstream
+            .split_once(':')
+            .is_some_and(|(epoch, _)| is_number(epoch));
+        if epoch || version.contains('~') || release.is_some_and(is_revision) {
+            return VersionScheme::Debian;
+        }
+
+        if is_semver(version) && (release.is_some() || version.contains('+')) {
+            return VersionScheme::Semver;
+        }
+
+        VersionScheme::Dotted
+    }
+
+    /// Compare the versions ``a`` and ``b``. With ``Auto``, they are compared with the scheme
+    /// detected for them, or if they are detected differently with the scheme of the one that
+    /// isn't dotted, as dotted versions are also valid in every other scheme. Versions of two
+    /// different other schemes are compared as dotted.
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        let (a, b) = (a.trim(), b.trim());
+        match self {
+            VersionScheme::Auto => {
+                let scheme = match (Self::detect(a), Self::detect(b)) {
+                    (VersionScheme::Dotted, scheme) | (scheme, VersionScheme::Dotted) => scheme,
+                    (a, b) if a == b => a,
+                    _ => VersionScheme::Dotted,
+                };
+                scheme.compare(a, b)
+            }
+            VersionScheme::Semver => compare_semver(a, b),
+            VersionScheme::Debian => compare_debian(a, b),
+            VersionScheme::Rpm => compare_rpm(a, b),
+            VersionScheme::Dotted => compare_dotted(a, b),
+        }
+    }
+}
+
+impl fmt::Display for VersionScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for VersionScheme {
+    type Err = String;
+
+    /// Parse the name of a scheme, ignoring case
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        VersionScheme::ALL
+            .into_iter()
+            .find(|scheme| scheme.as_str().eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                format!(
+                    "unknown version scheme {:?}, expected auto, semver, debian, rpm or dotted",
+                    name
+                )
+            })
+    }
+}
+
+/// Range of versions bounded as in the CPE matches of NVD configurations, e.g.
+/// ``versionStartIncluding`` and ``versionEndExcluding``. Bounds left to ``None`` don't bound the
+/// range, so the default range contains every version.
+///
+/// ## Example:
+/// ```
+/// use nvd_cve::version::{VersionRange, VersionScheme};
+///
+/// let range = VersionRange {
+///     start_including: Some("2.0-beta9".to_string()),
+///     end_excluding: Some("2.15.0".to_string()),
+///     ..Default::default()
+/// };
+///
+/// assert!(range.contains("2.14.1", VersionScheme::Auto));
+/// assert!(!range.contains("2.15.0", VersionScheme::Auto));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct VersionRange {
+    pub start_including: Option<String>,
+    pub start_excluding: Option<String>,
+    pub end_including: Option<String>,
+    pub end_excluding: Option<String>,
+}
+
+impl VersionRange {
+    /// Returns ``true`` if no bound is set.
+    pub fn is_unbounded(&self) -> bool {
+        self.start_including.is_none()
+            && self.start_excluding.is_none()
+            && self.end_including.is_none()
+            && self.end_excluding.is_none()
+    }
+
+    /// Returns ``true`` if ``version`` comes after the end of the range, e.g. the version a
+    /// vulnerability was fixed in, compared with ``scheme``. Always ``false`` for ranges without
+    /// an end.
+    pub fn is_before(&self, version: &str, scheme: VersionScheme) -> bool {
+        let compare = |bound: &Option<String>| bound.as_ref().map(|b| scheme.compare(version, b));
+        matches!(compare(&self.end_including), Some(Ordering::Greater))
+            || matches!(
+                compare(&self.end_excluding),
+                Some(Ordering::Greater | Ordering::Equal)
+            )
+    }
+
+    /// Returns ``true`` if ``version`` is within every bound of the range, compared with
+    /// ``scheme``.
+    pub fn contains(&self, version: &str, scheme: VersionScheme) -> bool {
+        let compare = |bound: &Option<String>| bound.as_ref().map(|b| scheme.compare(version, b));
+        !matches!(compare(&self.start_including), Some(Ordering::Less))
+            && !matches!(
+                compare(&self.start_excluding),
+                Some(Ordering::Less | Ordering::Equal)
+            )
+            && !matches!(compare(&self.end_including), Some(Ordering::Greater))
+            && !matches!(
+                compare(&self.end_excluding),
+                Some(Ordering::Greater | Ordering::Equal)
+            )
+    }
+}
+
+/// Returns ``true`` if ``version`` is a semantic version, tolerating a leading ``v``
+fn is_semver(version: &str) -> bool {
+    let version = version.strip_prefix(['v', 'V']).unwrap_or(version);
+    let (version, build) = match version.split_once('+') {
+        Some((version, build)) => (version, Some(build)),
+        None => (version, None),
+    };
+    let (core, pre) = match version.split_once('-') {
+        Some((core, pre)) => (core, Some(pre)),
+        None => (version, None),
+    };
+
+    let identifiers = |part: &str| {
+        part.split('.').all(|identifier| {
+            !identifier.is_empty()
+                && identifier
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+    };
+    let numbers: Vec<&str> = core.split('.').collect();
+
+    numbers.len() == 3
+        && numbers
+            .iter()
+            .all(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+        && pre.is_none_or(identifiers)
+        && build.is_none_or(identifiers)
+}
+
+/// Returns ``true`` if ``release`` looks like the revision of a Debian package, e.g. ``1``,
+/// ``1ubuntu4.3`` or ``0+deb11u5``
+fn is_revision(release: &str) -> bool {
+    release.starts_with(|c: char| c.is_ascii_digit())
+        && release
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '~'))
+}
+
+/// Compare numbers of any length given as digits
+fn compare_numbers(a: &str, b: &str) -> Ordering {
+    let (a, b) = (a.trim_start_matches('0'), b.trim_start_matches('0'));
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+/// Split ``version`` at its first ``separator`` into the part before and the part after, or the
+/// whole version and ``default``
+fn split_first<'a>(version: &'a str, separator: char, default: &'a str) -> (&'a str, &'a str) {
+    version.split_once(separator).unwrap_or((version, default))
+}
+
+/// Compare semantic versions by precedence
+fn compare_semver(a: &str, b: &str) -> Ordering {
+    let parse = |version: &str| {
+        let version = version.strip_prefix(['v', 'V']).unwrap_or(version);
+        let (version, _build) = split_first(version, '+', "");
+        let (core, pre) = split_first(version, '-', "");
+        let mut numbers: Vec<String> = core.split('.').map(str::to_string).collect();
+        numbers.resize(3.max(numbers.len()), "0".to_string());
+        (numbers, pre.to_string())
+    };
+    let ((a_numbers, a_pre), (b_numbers, b_pre)) = (parse(a), parse(b));
+
+    for (a, b) in a_numbers.iter().zip(&b_numbers) {
+        let ordering = if is_number(a) && is_number(b) {
+            compare_numbers(a, b)
+        } else {
+            compare_dotted(a, b)
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    let ordering = a_numbers.len().cmp(&b_numbers.len());
+    if ordering != Ordering::Equal {
+        return ordering;
+    }
+
+    // Releases come after their pre-releases
+    match (a_pre.is_empty(), b_pre.is_empty()) {
+        (true, true) => return Ordering::Equal,
+        (true, false) => return Ordering::Greater,
+        (false, true) => return Ordering::Less,
+        (false, false) => (),
+    }
+
+    let mut a_identifiers = a_pre.split('.');
+    let mut b_identifiers = b_pre.split('.');
+    loop {
+        let ordering = match (a_identifiers.next(), b_identifiers.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a), Some(b)) => match (is_number(a), is_number(b)) {
+                (true, true) => compare_numbers(a, b),
+                (true, false) => Ordering::Less,
+                (false, true) => Ordering::Greater,
+                (false, false) => a.cmp(b),
+            },
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+fn is_number(identifier: &str) -> bool {
+    !identifier.is_empty() && identifier.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Split a package version into its epoch, version and revision or release
+fn split_package_version(version: &str) -> (&str, &str, &str) {
+    let (epoch, version) = match version.split_once(':') {
+        Some((epoch, version)) if is_number(epoch) => (epoch, version),
+        _ => ("0", version),
+    };
+    let (version, release) = version.rsplit_once('-').unwrap_or((version, ""));
+    (epoch, version, release)
+}
+
+/// Compare Debian package versions as ``dpkg --compare-versions`` does
+fn compare_debian(a: &str, b: &str) -> Ordering {
+    let (a_epoch, a_upstream, a_revision) = split_package_version(a);
+    let (b_epoch, b_upstream, b_revision) = split_package_version(b);
+
+    compare_numbers(a_epoch, b_epoch)
+        .then_with(|| compare_dpkg_part(a_upstream, b_upstream))
+        .then_with(|| compare_dpkg_part(a_revision, b_revision))
+}
+
+/// Compare an upstream version or a revision as ``dpkg``'s ``verrevcmp`` does: alternating runs
+/// of non-digits, where ``~`` sorts before anything and letters before other characters, and of
+/// digits compared as numbers
+fn compare_dpkg_part(a: &str, b: &str) -> Ordering {
+    fn order(c: Option<&u8>) -> i32 {
+        match c {
+            None => 0,
+            Some(b'~') => -1,
+            Some(c) if c.is_ascii_digit() => 0,
+            Some(c) if c.is_ascii_alphabetic() => *c as i32,
+            Some(c) => *c as i32 + 256,
+        }
+    }
+
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let (mut i, mut j) = (0, 0);
+
+    while i < a.len() || j < b.len() {
+        while (i < a.len() && !a[i].is_ascii_digit()) || (j < b.len() && !b[j].is_ascii_digit()) {
+            let ordering = order(a.get(i)).cmp(&order(b.get(j)));
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+            i += 1;
+            j += 1;
+        }
+
+        let digits = |version: &[u8], start: usize| {
+            let end = version[start.min(version.len())..]
+                .iter()
+                .position(|c| !c.is_ascii_digit())
+                .map_or(version.len(), |length| start + length);
+            String::from_utf8_lossy(&version[start.min(version.len())..end]).into_owned()
+        };
+        let (a_digits, b_digits) = (digits(a, i), digits(b, j));
+        let ordering = compare_numbers(&a_digits, &b_digits);
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+        i += a_digits.len();
+        j += b_digits.len();
+    }
+
+    Ordering::Equal
+}
+
+/// Compare RPM package versions, epoch first, then version and release as ``rpmvercmp`` does
+fn compare_rpm(a: &str, b: &str) -> Ordering {
+    let (a_epoch, a_version, a_release) = split_package_version(a);
+    let (b_epoch, b_version, b_release) = split_package_version(b);
+
+    compare_numbers(a_epoch, b_epoch)
+        .then_with(|| compare_rpm_part(a_version, b_version))
+        .then_with(|| compare_rpm_part(a_release, b_release))
+}
+
+/// Compare a version or a release as ``rpmvercmp`` does: segments of letters or digits are
+/// compared in turn, digits as numbers and newer than letters, ``~`` sorts before anything and
+/// ``^`` after the end of a version but before anything else
+fn compare_rpm_part(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+
+    let separator = |c: char| !c.is_ascii_alphanumeric() && c != '~' && c != '^';
+    let (mut a, mut b) = (a, b);
+
+    loop {
+        a = a.trim_start_matches(separator);
+        b = b.trim_start_matches(separator);
+
+        if a.starts_with('~') || b.starts_with('~') {
+            if !a.starts_with('~') {
+                return Ordering::Greater;
+            }
+            if !b.starts_with('~') {
+                return Ordering::Less;
+            }
+            a = &a[1..];
+            b = &b[1..];
+            continue;
+        }
+
+        if a.starts_with('^') || b.starts_with('^') {
+            if a.is_empty() {
+                return Ordering::Less;
+            }
+            if b.is_empty() {
+                return Ordering::Greater;
+            }
+            if !a.starts_with('^') {
+                return Ordering::Greater;
+            }
+            if !b.starts_with('^') {
+                return Ordering::Less;
+            }
+            a = &a[1..];
+            b = &b[1..];
+            continue;
+        }
+
+        if a.is_empty() || b.is_empty() {
+            break;
+        }
+
+        let numeric = a.starts_with(|c: char| c.is_ascii_digit());
+        fn segment(version: &str, numeric: bool) -> (&str, &str) {
+            let end = version
+                .find(|c: char| {
+                    if numeric {
+                        !c.is_ascii_digit()
+                    } else {
+                        !c.is_ascii_alphabetic()
+                    }
+                })
+                .unwrap_or(version.len());
+            version.split_at(end)
+        }
+        let ((a_segment, a_rest), (b_segment, b_rest)) = (segment(a, numeric), segment(b, numeric));
+
+        // Segments of different types, numbers are newer
+        if b_segment.is_empty() {
+            return if numeric {
+                Ordering::Greater
+            } else {
+                Ordering::Less
+            };
+        }
+
+        let ordering = if numeric {
+            compare_numbers(a_segment, b_segment)
+        } else {
+            a_segment.cmp(b_segment)
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+        a = a_rest;
+        b = b_rest;
+    }
+
+    match (a.is_empty(), b.is_empty()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Less,
+        _ => Ordering::Greater,
+    }
+}
+
+/// Words marking a pre-release in dotted versions, from the earliest to the latest
+const PRE_RELEASES: [&str; 7] = ["dev", "alpha", "beta", "pre", "preview", "rc", "cr"];
+
+/// A run of digits or of letters of a dotted version
+#[derive(Debug, PartialEq, Eq)]
+enum Token {
+    Number(String),
+    Word(String),
+}
+
+impl Token {
+    /// Order of the token against a missing one: zeros are equal to nothing, pre-releases come
+    /// before and anything else after
+    fn against_missing(&self) -> Ordering {
+        match self {
+            Token::Number(number) if number.trim_start_matches('0').is_empty() => Ordering::Equal,
+            Token::Word(word) if PRE_RELEASES.contains(&word.as_str()) => Ordering::Less,
+            _ => Ordering::Greater,
+        }
+    }
+
+    fn cmp(&self, other: &Token) -> Ordering {
+        match (self, other) {
+            (Token::Number(a), Token::Number(b)) => compare_numbers(a, b),
+            (Token::Word(a), Token::Word(b)) => {
+                let rank = |word: &str| PRE_RELEASES.iter().position(|pre| *pre == word);
+                match (rank(a), rank(b)) {
+                    (Some(a), Some(b)) => a.cmp(&b),
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => a.cmp(b),
+                }
+            }
+            // Pre-releases come before numbers, other words after
+            (Token::Word(_), Token::Number(_)) => self.against_missing(),
+            (Token::Number(_), Token::Word(_)) => other.against_missing().reverse(),
+        }
+    }
+}
+
+/// Split a dotted version into components at its separators, and each component into runs of
+/// digits and of lower case letters
+fn dotted_components(version: &str) -> Vec<Vec<Token>> {
+    version
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|component| !component.is_empty())
+        .map(|component| {
+            let mut tokens: Vec<Token> = vec![];
+            for c in component.chars() {
+                let c = c.to_ascii_lowercase();
+                match (tokens.last_mut(), c.is_ascii_digit()) {
+                    (Some(Token::Number(number)), true) => number.push(c),
+                    (Some(Token::Word(word)), false) => word.push(c),
+                    (_, true) => tokens.push(Token::Number(c.to_string())),
+                    (_, false) => tokens.push(Token::Word(c.to_string())),
+                }
+            }
+            tokens
+        })
+        .collect()
+}
+
+/// Compare dotted versions component by component
+fn compare_dotted(a: &str, b: &str) -> Ordering {
+    let (a, b) = (dotted_components(a), dotted_components(b));
+    let empty = vec![];
+
+    for index in 0..a.len().max(b.len()) {
+        let a_tokens = a.get(index).unwrap_or(&empty);
+        let b_tokens = b.get(index).unwrap_or(&empty);
+
+        for token in 0..a_tokens.len().max(b_tokens.len()) {
+            let ordering = match (a_tokens.get(token), b_tokens.get(token)) {
+                (Some(a), Some(b)) => a.cmp(b),
+                (Some(a), None) => a.against_missing(),
+                (None, Some(b)) => b.against_missing().reverse(),
+                (None, None) => Ordering::Equal,
+            };
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+    }
+
+    Ordering::Equal
+}
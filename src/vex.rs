@@ -0,0 +1,585 @@
+use crate::matching::MatchConfidence;
+use crate::scan::{Finding, HostScan, Package};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// Context of OpenVEX documents, naming the version of the specification they follow.
+pub const OPENVEX_CONTEXT: &str = "https://openvex.dev/ns/v0.2.0";
+
+/// Format of the timestamps of VEX documents, in UTC
+const VEX_TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%SZ";
+
+/// Name and version of the tool generating the documents
+const TOOLING: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// Status of a product with regard to a vulnerability, as named by OpenVEX.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum VexStatus {
+    /// A finding accepted by a suppression.
+    NotAffected,
+    /// A finding of the scan.
+    Affected,
+    /// A CVE the product was vulnerable to in an earlier version.
+    Fixed,
+}
+
+/// Why a product isn't affected by a vulnerability, as named by both OpenVEX and CSAF.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum VexJustification {
+    ComponentNotPresent,
+    VulnerableCodeNotPresent,
+    VulnerableCodeNotInExecutePath,
+    VulnerableCodeCannotBeControlledByAdversary,
+    InlineMitigationsAlreadyExist,
+}
+
+impl VexJustification {
+    pub const ALL: [VexJustification; 5] = [
+        VexJustification::ComponentNotPresent,
+        VexJustification::VulnerableCodeNotPresent,
+        VexJustification::VulnerableCodeNotInExecutePath,
+        VexJustification::VulnerableCodeCannotBeControlledByAdversary,
+        VexJustification::InlineMitigationsAlreadyExist,
+    ];
+
+    /// Name of the justification, as serialized
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VexJustification::ComponentNotPresent => "component_not_present",
+            VexJustification::VulnerableCodeNotPresent => "vulnerable_code_not_present",
+            VexJustification::VulnerableCodeNotInExecutePath => {
+                "vulnerable_code_not_in_execute_path"
+            }
+            VexJustification::VulnerableCodeCannotBeControlledByAdversary => {
+                "vulnerable_code_cannot_be_controlled_by_adversary"
+            }
+            VexJustification::InlineMitigationsAlreadyExist => "inline_mitigations_already_exist",
+        }
+    }
+}
+
+impl fmt::Display for VexJustification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for VexJustification {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|justification| justification.as_str().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!("Unknown VEX justification: {}", s))
+    }
+}
+
+/// Who publishes a VEX document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VexPublisher {
+    /// Name of the person or organization, such as ``Example Corp Security``.
+    pub name: String,
+
+    /// URL of the publisher, e.g. its website, required by CSAF to tell publishers apart.
+    pub namespace: String,
+}
+
+/// The status of a package with regard to a CVE, along with what OpenVEX and CSAF require for
+/// it: the justification or impact of findings that don't affect the package, the action to take
+/// for those that do.
+#[derive(Debug, Clone, PartialEq)]
+struct Statement<'a> {
+    cve: &'a str,
+    package: &'a Package,
+    cpe: &'a str,
+    status: VexStatus,
+    justification: Option<VexJustification>,
+    impact: Option<&'a str>,
+    action: Option<String>,
+}
+
+/// The statements of ``scan``, sorted by CVE ID, status and package name
+fn statements(scan: &HostScan) -> Vec<Statement<'_>> {
+    fn statement(finding: &Finding, status: VexStatus) -> Statement<'_> {
+        Statement {
+            cve: &finding.cve,
+            package: &finding.package,
+            cpe: &finding.cpe,
+            status,
+            justification: None,
+            impact: None,
+            action: None,
+        }
+    }
+
+    let mut statements = vec![];
+    for finding in &scan.findings {
+        let package = &finding.package;
+        let action = match finding.confidence {
+            MatchConfidence::VersionWildcard => format!(
+                "Verify whether {} {} is affected, the CVE doesn't bound the vulnerable versions",
+                package.name, package.version
+            ),
+            _ => format!(
+                "Update {} {} to a version outside the vulnerable versions",
+                package.name, package.version
+            ),
+        };
+        statements.push(Statement {
+            action: Some(action),
+            ..statement(finding, VexStatus::Affected)
+        });
+    }
+    for suppressed in &scan.suppressed {
+        statements.push(Statement {
+            justification: suppressed.suppression.justification,
+            impact: Some(&suppressed.suppression.reason),
+            ..statement(&suppressed.finding, VexStatus::NotAffected)
+        });
+    }
+    for finding in &scan.fixed {
+        statements.push(statement(finding, VexStatus::Fixed));
+    }
+
+    statements.sort_by(|a, b| {
+        (a.cve, a.status, &a.package.name).cmp(&(b.cve, b.status, &b.package.name))
+    });
+    statements
+}
+
+/// Package URL of ``package``, for the kinds of packages whose purl type is known
+pub fn purl(package: &Package) -> Option<String> {
+    let kind = match package.kind.as_str() {
+        "dpkg" | "deb" => "deb",
+        "rpm" => "rpm",
+        "apk" => "apk",
+        "npm" => "npm",
+        "python" => "pypi",
+        "gem" => "gem",
+        "rust-crate" => "cargo",
+        _ => return None,
+    };
+    Some(format!(
+        "pkg:{}/{}@{}",
+        kind,
+        percent_encode(&package.name),
+        percent_encode(&package.version)
+    ))
+}
+
+/// Percent-encode the characters of a purl component other than letters, digits and ``.-_~``,
+/// e.g. the ``+`` of ``1.2+dfsg``
+fn percent_encode(component: &str) -> String {
+    let mut encoded = String::new();
+    for byte in component.bytes() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'.' | b'-' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Identifier of a document derived from its statements, so the same scan is always given the
+/// same identifier
+fn document_uuid(publisher: &VexPublisher, statements: &[Statement]) -> Uuid {
+    let mut name = publisher.namespace.clone();
+    for statement in statements {
+        name.push_str(&format!(
+            "\n{}\t{}\t{}\t{:?}",
+            statement.cve, statement.package.name, statement.package.version, statement.status
+        ));
+    }
+    Uuid::new_v5(&Uuid::NAMESPACE_URL, name.as_bytes())
+}
+
+/// An OpenVEX document.
+///
+/// ## Example:
+/// ```no_run
+/// use chrono::Utc;
+/// use nvd_cve::cache::CacheConfig;
+/// use nvd_cve::scan::{installed_packages, scan_packages, CpeDictionary, PackageManager};
+/// use nvd_cve::version::VersionScheme;
+/// use nvd_cve::vex::{OpenVexDocument, VexPublisher};
+///
+/// let config = CacheConfig::new();
+/// let packages = installed_packages(PackageManager::for_host()).unwrap();
+/// let scan = scan_packages(&config, &packages, &CpeDictionary::builtin(), VersionScheme::Auto)
+///     .unwrap();
+///
+/// let publisher = VexPublisher {
+///     name: "Example Corp Security".to_string(),
+///     namespace: "https://example.com".to_string(),
+/// };
+/// let document = OpenVexDocument::new(&scan, &publisher, Utc::now());
+/// println!("{}", serde_json::to_string_pretty(&document).unwrap());
+/// ```
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct OpenVexDocument {
+    #[serde(rename = "@context")]
+    pub context: &'static str,
+
+    #[serde(rename = "@id")]
+    pub id: String,
+
+    pub author: String,
+
+    pub timestamp: String,
+
+    pub version: u32,
+
+    pub tooling: &'static str,
+
+    pub statements: Vec<OpenVexStatement>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct OpenVexStatement {
+    pub vulnerability: OpenVexVulnerability,
+
+    pub products: Vec<OpenVexProduct>,
+
+    pub status: VexStatus,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub justification: Option<VexJustification>,
+
+    /// Why the product isn't affected, the reason of the suppression.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub impact_statement: Option<String>,
+
+    /// What to do about an affected product.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action_statement: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct OpenVexVulnerability {
+    /// ID of the CVE, such as ``CVE-2021-44228``.
+    pub name: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct OpenVexProduct {
+    /// Package URL of the package, or its CPE name for packages without one.
+    #[serde(rename = "@id")]
+    pub id: String,
+
+    pub identifiers: ProductIdentifiers,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ProductIdentifiers {
+    /// CPE name the package was mapped to.
+    #[serde(rename = "cpe23")]
+    pub cpe: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub purl: Option<String>,
+}
+
+impl ProductIdentifiers {
+    fn new(package: &Package, cpe: &str) -> Self {
+        Self {
+            cpe: cpe.to_string(),
+            purl: purl(package),
+        }
+    }
+}
+
+impl OpenVexDocument {
+    /// The document of the findings of ``scan``, affecting their packages, of its suppressed
+    /// findings, not affecting them, and of the CVEs fixed in the installed versions, published
+    /// by ``publisher`` at ``timestamp``.
+    pub fn new(scan: &HostScan, publisher: &VexPublisher, timestamp: DateTime<Utc>) -> Self {
+        let statements = statements(scan);
+        Self {
+            context: OPENVEX_CONTEXT,
+            id: format!("urn:uuid:{}", document_uuid(publisher, &statements)),
+            author: publisher.name.clone(),
+            timestamp: timestamp.format(VEX_TIMESTAMP_FORMAT).to_string(),
+            version: 1,
+            tooling: TOOLING,
+            statements: statements
+                .into_iter()
+                .map(|statement| {
+                    let identifiers = ProductIdentifiers::new(statement.package, statement.cpe);
+                    OpenVexStatement {
+                        vulnerability: OpenVexVulnerability {
+                            name: statement.cve.to_string(),
+                        },
+                        products: vec![OpenVexProduct {
+                            id: identifiers.purl.clone().unwrap_or(identifiers.cpe.clone()),
+                            identifiers,
+                        }],
+                        status: statement.status,
+                        justification: statement.justification,
+                        impact_statement: statement.impact.map(str::to_string),
+                        action_statement: statement.action,
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A CSAF 2.0 document of the ``csaf_vex`` profile, see ``OpenVexDocument`` for the statements
+/// it holds. Each package gets a product ID of its own, listed in the product tree.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CsafDocument {
+    pub document: CsafMetadata,
+    pub product_tree: CsafProductTree,
+    pub vulnerabilities: Vec<CsafVulnerability>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CsafMetadata {
+    pub category: &'static str,
+    pub csaf_version: &'static str,
+    pub notes: Vec<CsafNote>,
+    pub publisher: CsafPublisher,
+    pub title: String,
+    pub tracking: CsafTracking,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CsafNote {
+    pub category: &'static str,
+    pub text: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CsafPublisher {
+    pub category: &'static str,
+    pub name: String,
+    pub namespace: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CsafTracking {
+    pub current_release_date: String,
+    pub generator: CsafGenerator,
+    pub id: String,
+    pub initial_release_date: String,
+    pub revision_history: Vec<CsafRevision>,
+    pub status: &'static str,
+    pub version: &'static str,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CsafGenerator {
+    pub engine: CsafEngine,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CsafEngine {
+    pub name: &'static str,
+    pub version: &'static str,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CsafRevision {
+    pub date: String,
+    pub number: &'static str,
+    pub summary: &'static str,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CsafProductTree {
+    pub full_product_names: Vec<CsafProduct>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CsafProduct {
+    /// Name and version of the package, such as ``libssl3 3.0.2``.
+    pub name: String,
+    pub product_id: String,
+    pub product_identification_helper: ProductIdentifiers,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CsafVulnerability {
+    pub cve: String,
+
+    pub product_status: CsafProductStatus,
+
+    /// Justifications of the products that aren't affected.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub flags: Vec<CsafFlag>,
+
+    /// Impact statements of the products that aren't affected, the reasons of the suppressions.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub threats: Vec<CsafThreat>,
+
+    /// Actions to take for the affected products.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub remediations: Vec<CsafRemediation>,
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct CsafProductStatus {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fixed: Vec<String>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub known_affected: Vec<String>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub known_not_affected: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CsafFlag {
+    pub label: VexJustification,
+    pub product_ids: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CsafThreat {
+    pub category: &'static str,
+    pub details: String,
+    pub product_ids: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CsafRemediation {
+    pub category: &'static str,
+    pub details: String,
+    pub product_ids: Vec<String>,
+}
+
+impl CsafDocument {
+    /// The document of ``scan``, published by ``publisher`` at ``timestamp``, see
+    /// ``OpenVexDocument::new()``.
+    pub fn new(scan: &HostScan, publisher: &VexPublisher, timestamp: DateTime<Utc>) -> Self {
+        let statements = statements(scan);
+        let timestamp = timestamp.format(VEX_TIMESTAMP_FORMAT).to_string();
+
+        // Product IDs follow the order of the packages, by name and version
+        let mut products = BTreeMap::new();
+        for statement in &statements {
+            let package = statement.package;
+            products
+                .entry((&package.name, &package.version))
+                .or_insert((package, statement.cpe));
+        }
+        let product_ids: BTreeMap<_, _> = products
+            .keys()
+            .enumerate()
+            .map(|(index, key)| (*key, format!("CSAFPID-{:04}", index + 1)))
+            .collect();
+        let full_product_names = products
+            .iter()
+            .map(|(key, (package, cpe))| CsafProduct {
+                name: format!("{} {}", package.name, package.version),
+                product_id: product_ids[key].clone(),
+                product_identification_helper: ProductIdentifiers::new(package, cpe),
+            })
+            .collect();
+
+        let mut vulnerabilities: Vec<CsafVulnerability> = vec![];
+        for statement in &statements {
+            if vulnerabilities
+                .last()
+                .is_none_or(|last| last.cve != statement.cve)
+            {
+                vulnerabilities.push(CsafVulnerability {
+                    cve: statement.cve.to_string(),
+                    product_status: CsafProductStatus::default(),
+                    flags: vec![],
+                    threats: vec![],
+                    remediations: vec![],
+                });
+            }
+            let vulnerability = vulnerabilities.last_mut().unwrap();
+            let package = statement.package;
+            let product_id = product_ids[&(&package.name, &package.version)].clone();
+
+            match statement.status {
+                VexStatus::Affected => {
+                    vulnerability
+                        .product_status
+                        .known_affected
+                        .push(product_id.clone());
+                    vulnerability.remediations.push(CsafRemediation {
+                        category: "vendor_fix",
+                        details: statement.action.clone().unwrap_or_default(),
+                        product_ids: vec![product_id],
+                    });
+                }
+                VexStatus::NotAffected => {
+                    vulnerability
+                        .product_status
+                        .known_not_affected
+                        .push(product_id.clone());
+                    if let Some(label) = statement.justification {
+                        vulnerability.flags.push(CsafFlag {
+                            label,
+                            product_ids: vec![product_id.clone()],
+                        });
+                    }
+                    vulnerability.threats.push(CsafThreat {
+                        category: "impact",
+                        details: statement.impact.unwrap_or_default().to_string(),
+                        product_ids: vec![product_id],
+                    });
+                }
+                VexStatus::Fixed => vulnerability.product_status.fixed.push(product_id),
+            }
+        }
+
+        let uuid = document_uuid(publisher, &statements);
+        Self {
+            document: CsafMetadata {
+                category: "csaf_vex",
+                csaf_version: "2.0",
+                notes: vec![CsafNote {
+                    category: "summary",
+                    text: format!(
+                        "Scan of {} packages: {} findings, {} suppressed, {} fixed",
+                        scan.packages,
+                        scan.findings.len(),
+                        scan.suppressed.len(),
+                        scan.fixed.len()
+                    ),
+                }],
+                publisher: CsafPublisher {
+                    category: "user",
+                    name: publisher.name.clone(),
+                    namespace: publisher.namespace.clone(),
+                },
+                title: format!("VEX of a scan by {}", publisher.name),
+                tracking: CsafTracking {
+                    current_release_date: timestamp.clone(),
+                    generator: CsafGenerator {
+                        engine: CsafEngine {
+                            name: env!("CARGO_PKG_NAME"),
+                            version: env!("CARGO_PKG_VERSION"),
+                        },
+                    },
+                    id: format!("{}-{}", env!("CARGO_PKG_NAME"), uuid),
+                    initial_release_date: timestamp.clone(),
+                    revision_history: vec![CsafRevision {
+                        date: timestamp,
+                        number: "1",
+                        summary: "Initial version",
+                    }],
+                    status: "final",
+                    version: "1",
+                },
+            },
+            product_tree: CsafProductTree { full_product_names },
+            vulnerabilities,
+        }
+    }
+}
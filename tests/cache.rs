@@ -1,12 +1,65 @@
-use nvd_cve::cache::{search_by_id, CacheConfig};
+#![cfg(feature = "blocking")]
+
+use nvd_cve::cache::{
+    check, delete_cve, delete_cves, delete_feed_records, enforce_size_budget, explain_search,
+    get_all, get_all_ids, get_all_summaries, get_cached_feeds, get_change_events,
+    get_configurations, get_impact, get_metafile, reindex, resolve, resolve_in_sources, search,
+    search_by_assigner, search_by_cwe, search_by_id, search_by_id_as_of, search_by_id_in_sources,
+    search_by_product, search_by_reference, search_by_severity, search_container_by_id,
+    search_cves, search_description, search_id_prefix, search_modified_between,
+    search_published_between, search_sources, sync_change_history, CacheConfig, CacheError,
+    FeedChanges, PrunePolicy, Record, Resolution, SyncReport, TimeoutPolicy,
+};
 use std::fs;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
 mod util;
+use chrono::{DateTime, TimeZone, Utc};
 use home::home_dir;
-use nvd_cve::cache::sync_blocking;
-use nvd_cve::cve::CveFeed;
+use nvd_cve::attestation::get_attestations;
+use nvd_cve::cache::{sync_async, sync_blocking};
+use nvd_cve::client::{
+    AsyncHttpClient, BlockingHttpClient, HttpError, ReqwestAsyncClient, ReqwestBlockingClient,
+};
+use nvd_cve::clock::ManualClock;
+use nvd_cve::compare::{get_record, Change, CveComparison};
+use nvd_cve::cpedict::{
+    get_dictionary_entries, import_cpe_dictionary, is_known_product, parse_cpe_dictionary,
+    sync_cpe_dictionary,
+};
+use nvd_cve::cpematch::{import_cpe_matches, sync_cpe_matches, CpeMatchFeed};
+use nvd_cve::cve::{Cve, CveFeed, CveSummary};
+use nvd_cve::cvss::{
+    AttackVector, ImpactLevel, PrivilegesRequired, Scope, Severity, UserInteraction,
+};
+use nvd_cve::cwe::{get_cwe_name, get_weaknesses, import_cwes, parse_cwe_list, Weakness};
+use nvd_cve::epss::{get_epss, import_epss, sync_epss, EpssError, EpssScores};
+use nvd_cve::exploit::ExploitSource;
+use nvd_cve::feed::{FeedName, MetafileError};
+use nvd_cve::history::{get_last_sync_run, get_sync_runs};
+use nvd_cve::kev::{get_known_exploited, import_kev, sync_kev, KevCatalog};
+use nvd_cve::kv::{write_kv_index, KvIndex, KvIndexError};
+use nvd_cve::matching::{search_by_cpe, MatchConfidence};
+use nvd_cve::misp::{write_misp_feed, MispEvent};
+use nvd_cve::osv::{import_osv, OsvVulnerability};
+use nvd_cve::overrides::{get_override, remove_override, set_override, with_override, CveOverride};
+use nvd_cve::query::SearchQuery;
+use nvd_cve::query_cache::QueryCache;
+use nvd_cve::risk::{score_cves, search_by_risk, RiskInput, RiskWeights};
+use nvd_cve::stix::{export_stix, StixVulnerability};
+use nvd_cve::ticket::{
+    create_tickets, get_tickets, Ticket, TicketError, TicketTemplate, TicketTracker,
+};
+use nvd_cve::version::VersionScheme;
+use std::collections::{BTreeMap, HashMap};
 use std::env;
 use std::path::PathBuf;
-use util::MockBlockingClient;
+use std::time::Duration;
+use util::{gzip, MockBlockingClient, MockFeedServer};
+
+fn ids(cves: Vec<CveSummary>) -> Vec<String> {
+    cves.into_iter().map(|cve| cve.id).collect()
+}
 
 #[test]
 fn test_sync_config_defaults() {
@@ -27,18 +80,57 @@ fn test_sync_config_defaults() {
     assert_eq!(config.db.as_str(), db_path.to_str().unwrap());
 }
 
+#[cfg(feature = "serde")]
+#[test]
+fn test_config_feed_ranges() {
+    let config: CacheConfig =
+        serde_json::from_str(r#"{"feeds": ["2023..", "2024", "recent", "modified"]}"#).unwrap();
+    assert_eq!(
+        config.feeds,
+        vec![
+            FeedName::Year(2023),
+            FeedName::Year(2024),
+            FeedName::Recent,
+            FeedName::Modified
+        ]
+    );
+
+    assert!(serde_json::from_str::<CacheConfig>(r#"{"feeds": ["2024..2023"]}"#).is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_config_timeouts() {
+    let config: CacheConfig = serde_json::from_str(
+        r#"{"feed_timeout": 600, "sync_deadline": 3600, "on_timeout": "continue"}"#,
+    )
+    .unwrap();
+    assert_eq!(config.feed_timeout, Some(Duration::from_secs(600)));
+    assert_eq!(config.sync_deadline, Some(Duration::from_secs(3600)));
+    assert_eq!(config.on_timeout, TimeoutPolicy::Continue);
+
+    let json = serde_json::to_value(&config).unwrap();
+    assert_eq!(json["feed_timeout"], 600);
+    assert_eq!(json["on_timeout"], "continue");
+
+    let config = CacheConfig::default();
+    assert_eq!(config.feed_timeout, None);
+    assert_eq!(config.on_timeout, TimeoutPolicy::Abort);
+}
+
 #[test]
 fn test_sync_blocking() {
     // Set location of test cache DB:
-    let mut config = CacheConfig::default();
-    config.db = "./tests/files/.cache/nvd/nvd2.sqlite3".to_string();
-    config.url = "http://nowhere.nope".to_string();
+    let config = CacheConfig {
+        db: "./tests/files/.cache/nvd/nvd2.sqlite3".to_string(),
+        url: "http://nowhere.nope".to_string(),
+        feeds: vec![FeedName::Recent],
+        ..Default::default()
+    };
 
     // Remove any existing DB
     fs::remove_file(&config.db).ok();
 
-    config.feeds = vec![String::from("recent")];
-
     let mut client = MockBlockingClient::default();
 
     let metafile = fs::read_to_string("./tests/files/nvdcve-1.1-recent.meta")
@@ -49,17 +141,3513 @@ fn test_sync_blocking() {
     let body = fs::read_to_string("./tests/files/nvdcve-1.1-recent.json")
         .expect("Failed reading feed json");
 
-    let cve_feed: CveFeed = serde_json::from_str(&*body).expect("Failed parsing cve feed json");
+    let cve_feed: CveFeed = serde_json::from_str(&body).expect("Failed parsing cve feed json");
     client.get_feed_response = Ok(cve_feed);
 
     sync_blocking(&config, client).expect("Failed to sync to local cache");
 
     if let Err(error) = search_by_id(&config, "CVE-2021-43437") {
-        assert!(false, "failed to find CVE: {:?}", error);
+        panic!("failed to find CVE: {:?}", error);
     }
 
     // Cleanup
     if let Err(e) = fs::remove_file(&config.db) {
-        assert!(false, "{:?}", e);
+        panic!("{:?}", e);
+    }
+}
+
+/// Sync the sample feed, which includes CVSS impact data, into a fresh cache at ``db``
+fn sync_sample_feed(db: &str) -> CacheConfig {
+    sync_feed_file(db, "./tests/files/nvdcve-1.1-sample.json")
+}
+
+/// Sync the feed JSON file at ``path`` into a fresh cache at ``db``
+fn sync_feed_file(db: &str, path: &str) -> CacheConfig {
+    let config = CacheConfig {
+        db: db.to_string(),
+        url: "http://nowhere.nope".to_string(),
+        feeds: vec![FeedName::Recent],
+        show_progress: false,
+        ..Default::default()
+    };
+
+    fs::remove_file(&config.db).ok();
+
+    sync_blocking(&config, feed_file_client(path)).expect("Failed to sync to local cache");
+
+    config
+}
+
+/// Mock client serving the feed JSON file at ``path`` along with the recent feed's metafile
+fn feed_file_client(path: &str) -> MockBlockingClient {
+    let mut client = MockBlockingClient::default();
+
+    let metafile = fs::read_to_string("./tests/files/nvdcve-1.1-recent.meta")
+        .expect("Failed reading metafile");
+    client.get_metafile_response = Ok(metafile);
+
+    let body = fs::read_to_string(path).expect("Failed reading feed json");
+    let cve_feed: CveFeed = serde_json::from_str(&body).expect("Failed parsing cve feed json");
+    client.get_feed_response = Ok(cve_feed);
+
+    client
+}
+
+#[test]
+fn test_sync_report() {
+    let config = CacheConfig {
+        db: "./tests/files/.cache/nvd/report.sqlite3".to_string(),
+        url: "http://nowhere.nope".to_string(),
+        feeds: vec![FeedName::Recent],
+        show_progress: false,
+        ..Default::default()
+    };
+    fs::remove_file(&config.db).ok();
+
+    let client = feed_file_client("./tests/files/nvdcve-1.1-sample.json");
+    let report = sync_blocking(&config, client).expect("Failed to sync to local cache");
+    assert_eq!(
+        report,
+        SyncReport {
+            updated_feeds: vec![FeedName::Recent],
+            skipped_feeds: vec![],
+            cves_updated: 6,
+            cves_added: 6,
+            cves_modified: 0,
+            cves_skipped: 0,
+            feed_changes: vec![FeedChanges {
+                feed: FeedName::Recent,
+                added: 6,
+                modified: 0,
+                unchanged: 0,
+                skipped: 0,
+            }],
+            timed_out_feeds: vec![],
+            cves_pruned: 0,
+            timings: report.timings,
+            feed_timings: vec![],
+        }
+    );
+
+    // The cached Metafile is the latest so the feed is not fetched again
+    let client = feed_file_client("./tests/files/nvdcve-1.1-sample.json");
+    let report = sync_blocking(&config, client).expect("Failed to sync to local cache");
+    assert_eq!(report.skipped_feeds, vec![FeedName::Recent]);
+    assert!(report.updated_feeds.is_empty());
+    assert_eq!(report.cves_updated, 0);
+
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["skipped_feeds"], serde_json::json!(["recent"]));
+        assert_eq!(serde_json::from_value::<SyncReport>(json).unwrap(), report);
+
+        let json = serde_json::to_value(&config).unwrap();
+        assert_eq!(json["feeds"], serde_json::json!(["recent"]));
+        assert_eq!(json["compression"], "gzip");
+        let partial: CacheConfig =
+            serde_json::from_str(r#"{"source": "mirror", "feeds": ["2021", "modified"]}"#).unwrap();
+        assert_eq!(partial.source, "mirror");
+        assert_eq!(
+            partial.feeds,
+            vec![FeedName::Year(2021), FeedName::Modified]
+        );
+        assert_eq!(partial.url, CacheConfig::new().url);
+    }
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_by_impact() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/impact.sqlite3");
+
+    let query = SearchQuery {
+        attack_vector: Some(AttackVector::Network),
+        user_interaction: Some(UserInteraction::None),
+        ..Default::default()
+    };
+    let cves = search(&config, &query).expect("Failed searching by impact");
+    assert_eq!(
+        ids(cves),
+        vec!["CVE-2021-26855", "CVE-2021-3711", "CVE-2021-44228"]
+    );
+
+    let query = SearchQuery {
+        user_interaction: Some(UserInteraction::Required),
+        ..Default::default()
+    };
+    let cves = search(&config, &query).expect("Failed searching by impact");
+    assert_eq!(ids(cves), vec!["CVE-2022-30190"]);
+
+    let query = SearchQuery {
+        privileges_required: Some(PrivilegesRequired::None),
+        min_impact_score: Some(6.0),
+        ..Default::default()
+    };
+    let cves = search(&config, &query).expect("Failed searching by impact");
+    assert_eq!(ids(cves), vec!["CVE-2021-44228"]);
+
+    let query = SearchQuery {
+        text: Some(String::from("OpenSSL")),
+        min_exploitability_score: Some(3.0),
+        ..Default::default()
+    };
+    let cves = search(&config, &query).expect("Failed searching by impact");
+    assert_eq!(ids(cves), vec!["CVE-2021-3711"]);
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_by_vector_components() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/vector.sqlite3");
+
+    let mut query = SearchQuery::default();
+    query.set_vector(&"AV:N/AC:L/C:H".parse().expect("Failed parsing vector"));
+    let cves = search(&config, &query).expect("Failed searching by vector");
+    assert_eq!(
+        ids(cves),
+        vec!["CVE-2021-26855", "CVE-2021-3711", "CVE-2021-44228"]
+    );
+
+    let query = SearchQuery {
+        scope: Some(Scope::Changed),
+        ..Default::default()
+    };
+    let cves = search(&config, &query).expect("Failed searching by vector");
+    assert_eq!(ids(cves), vec!["CVE-2021-44228"]);
+
+    let mut query = SearchQuery {
+        integrity_impact: Some(ImpactLevel::Low),
+        ..Default::default()
+    };
+    assert!(search(&config, &query)
+        .expect("Failed searching by vector")
+        .is_empty());
+
+    // Components of the vector replace those already set on the query
+    query.set_vector(&"CVSS:3.1/AV:A/I:H".parse().expect("Failed parsing vector"));
+    let cves = search(&config, &query).expect("Failed searching by vector");
+    assert_eq!(ids(cves), vec!["CVE-2019-12780"]);
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_multiple_sources() {
+    let nvd = sync_sample_feed("./tests/files/.cache/nvd/source_nvd.sqlite3");
+    let mut mirror = sync_feed_file(
+        "./tests/files/.cache/nvd/source_mirror.sqlite3",
+        "./tests/files/nvdcve-1.1-recent.json",
+    );
+    mirror.source = "mirror".to_string();
+
+    assert!(CacheConfig::for_source("mirror")
+        .db
+        .ends_with("mirror.sqlite3"));
+
+    // Records from one source are not visible in the other
+    assert!(search_by_id(&nvd, "CVE-2021-43437").is_err());
+    let (source, _) = search_by_id_in_sources(&[nvd.clone(), mirror.clone()], "CVE-2021-43437")
+        .expect("Failed finding CVE in merged sources");
+    assert_eq!(source, "mirror");
+
+    let query = SearchQuery {
+        text: Some("remote".to_string()),
+        ..Default::default()
+    };
+    let nvd_cves = search(&nvd, &query).expect("Failed searching source");
+    let mirror_cves = search(&mirror, &query).expect("Failed searching source");
+    let merged = search_sources(&[nvd.clone(), mirror.clone()], &query)
+        .expect("Failed searching merged sources");
+    assert_eq!(merged.len(), nvd_cves.len() + mirror_cves.len());
+    assert!(merged.windows(2).all(|pair| pair[0] < pair[1]));
+
+    fs::remove_file(&nvd.db).expect("Failed removing test cache");
+    fs::remove_file(&mirror.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_overrides_survive_sync() {
+    let mut config = sync_sample_feed("./tests/files/.cache/nvd/overrides.sqlite3");
+
+    assert_eq!(get_override(&config, "CVE-2021-44228").unwrap(), None);
+
+    let assessment = CveOverride {
+        severity: Some(Severity::Low),
+        affected: Some(false),
+        note: Some("Only the log4j-api jar is deployed".to_string()),
+        ..Default::default()
+    };
+    set_override(&config, "CVE-2021-44228", &assessment).expect("Failed setting override");
+
+    // Force a full sync of the same feed, the override must be left untouched
+    config.force_update = true;
+    let client = feed_file_client("./tests/files/nvdcve-1.1-sample.json");
+    sync_blocking(&config, client).expect("Failed to sync to local cache");
+
+    let cve = search_by_id(&config, "CVE-2021-44228").expect("Failed finding CVE");
+    let merged = with_override(&config, cve).expect("Failed merging override");
+
+    // Provenance is kept by nesting the override apart from the feed data
+    let json = serde_json::to_value(&merged).expect("Failed serializing CVE");
+    assert_eq!(json["cve_data_meta"]["id"], "CVE-2021-44228");
+    assert_eq!(json["local_override"]["affected"], false);
+
+    let local_override = merged.local_override.expect("Override was lost by sync");
+    assert_eq!(local_override.severity, Some(Severity::Low));
+    assert_eq!(local_override.affected, Some(false));
+    assert!(!local_override.updated.is_empty());
+
+    assert!(remove_override(&config, "CVE-2021-44228").unwrap());
+    assert!(!remove_override(&config, "CVE-2021-44228").unwrap());
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_enforce_size_budget() {
+    let db = "./tests/files/.cache/nvd/budget.sqlite3";
+    let oldest = [
+        "CVE-2014-0160",
+        "CVE-2019-12780",
+        "CVE-2021-26855",
+        "CVE-2021-3711",
+        "CVE-2021-44228",
+        "CVE-2022-30190",
+    ];
+    let lowest_severity = [
+        "CVE-2014-0160",
+        "CVE-2022-30190",
+        "CVE-2019-12780",
+        "CVE-2021-26855",
+        "CVE-2021-3711",
+        "CVE-2021-44228",
+    ];
+
+    for (policy, order) in [
+        (PrunePolicy::Oldest, oldest),
+        (PrunePolicy::LowestSeverity, lowest_severity),
+    ] {
+        let mut config = sync_sample_feed(db);
+        config.prune_policy = policy;
+        assert_eq!(enforce_size_budget(&config).unwrap(), 0);
+
+        // The first budget may be met by converting the cache to incremental vacuum alone
+        for _ in 0..2 {
+            let budget = fs::metadata(db).unwrap().len() - 1;
+            config.max_db_size = Some(budget);
+            enforce_size_budget(&config).expect("Failed pruning cache");
+            assert!(fs::metadata(db).unwrap().len() <= budget);
+        }
+
+        // The CVEs left are the last ones in the order of the policy
+        let ids = get_all_ids(&config).unwrap();
+        assert!(ids.len() < order.len());
+        let mut kept = order[order.len() - ids.len()..].to_vec();
+        kept.sort();
+        assert_eq!(ids, kept, "{} pruned the wrong CVEs", policy);
+
+        fs::remove_file(db).expect("Failed removing test cache");
+    }
+
+    // A sync prunes the CVEs once written, even every one of them when the budget is that small
+    let config = CacheConfig {
+        db: db.to_string(),
+        feeds: vec![FeedName::Recent],
+        show_progress: false,
+        max_db_size: Some(1),
+        ..Default::default()
+    };
+    let client = feed_file_client("./tests/files/nvdcve-1.1-sample.json");
+    let report = sync_blocking(&config, client).expect("Failed to sync to local cache");
+    assert_eq!(report.cves_added, 6);
+    assert_eq!(report.cves_pruned, 6);
+    assert!(get_all_ids(&config).unwrap().is_empty());
+
+    assert_eq!("lowest-severity".parse(), Ok(PrunePolicy::LowestSeverity));
+    assert!("newest".parse::<PrunePolicy>().is_err());
+
+    fs::remove_file(db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_container_by_id() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/container.sqlite3");
+
+    let container = search_container_by_id(&config, "CVE-2021-44228").expect("Failed finding CVE");
+    assert_eq!(container.cve.cve_data_meta.id, "CVE-2021-44228");
+    assert_eq!(container.severity(), Some((Severity::Critical, 10.0)));
+    assert_eq!(container.published_date, "2021-12-10T10:15:00Z");
+    assert!(!container.last_modified_date.is_empty());
+
+    let cpe_matches = container.configurations.cpe_matches();
+    assert_eq!(
+        cpe_matches[0].cpe23_uri,
+        "cpe:2.3:a:apache:log4j:*:*:*:*:*:*:*:*"
+    );
+    assert_eq!(
+        cpe_matches[0].version_start_including.as_deref(),
+        Some("2.0.1")
+    );
+
+    assert!(search_container_by_id(&config, "CVE-1999-0001").is_err());
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_get_impact_and_configurations() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/raw_json.sqlite3");
+
+    let impact = get_impact(&config, "CVE-2021-44228").expect("Failed getting impact");
+    assert_eq!(
+        impact["baseMetricV3"]["cvssV3"]["vectorString"],
+        "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:C/C:H/I:H/A:H"
+    );
+
+    // The stored JSON is that of the feed, whatever the container parses of it
+    let sample: serde_json::Value =
+        serde_json::from_slice(&fs::read("./tests/files/nvdcve-1.1-sample.json").unwrap()).unwrap();
+    let item = &sample["CVE_Items"][4];
+    assert_eq!(item["cve"]["CVE_data_meta"]["ID"], "CVE-2021-44228");
+    let configurations =
+        get_configurations(&config, "CVE-2021-44228").expect("Failed getting configurations");
+    assert_eq!(configurations, item["configurations"]);
+    assert_eq!(impact, item["impact"]);
+
+    let container = search_container_by_id(&config, "CVE-2021-44228").unwrap();
+    assert_eq!(container.raw_impact.as_ref(), Some(&item["impact"]));
+    assert_eq!(
+        container.impact.base_metric_v3.unwrap().cvss_v3.base_score,
+        10.0
+    );
+
+    assert!(get_impact(&config, "CVE-1999-0001")
+        .unwrap_err()
+        .is_not_found());
+    assert!(get_configurations(&config, "CVE-1999-0001")
+        .unwrap_err()
+        .is_not_found());
+
+    // CVEs cached before the columns were added have none until synced again
+    let conn = rusqlite::Connection::open(&config.db).expect("Failed opening test cache");
+    conn.execute("UPDATE cve SET impact = NULL, configurations = NULL", [])
+        .unwrap();
+    conn.close().expect("Failed closing test cache");
+    assert_eq!(
+        get_impact(&config, "CVE-2021-44228").unwrap(),
+        serde_json::Value::Null
+    );
+    assert_eq!(
+        get_configurations(&config, "CVE-2021-44228").unwrap(),
+        serde_json::Value::Null
+    );
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_resolve() {
+    let nvd = sync_sample_feed("./tests/files/.cache/nvd/resolve.sqlite3");
+    let internal = CacheConfig {
+        source: "internal".to_string(),
+        ..sync_sample_feed("./tests/files/.cache/nvd/resolve-internal.sqlite3")
+    };
+
+    let log4shell = OsvVulnerability::from_json(
+        serde_json::json!({
+            "id": "GHSA-jfh8-c2jp-5v3q",
+            "modified": "2024-03-01T00:00:00Z",
+            "aliases": ["CVE-2021-44228"],
+        })
+        .to_string()
+        .as_bytes(),
+    )
+    .unwrap();
+    let lodash = fs::read("./tests/files/osv/GHSA-35jh-r3h4-6jhm.json").unwrap();
+    let lodash = OsvVulnerability::from_json(&lodash).unwrap();
+    import_osv(&nvd, &[log4shell, lodash]).expect("Failed importing advisories");
+
+    let sources = |resolution: &Resolution| {
+        resolution
+            .records
+            .iter()
+            .map(|record| (record.source.clone(), record.id.clone()))
+            .collect::<Vec<_>>()
+    };
+    let record = |source: &str, id: &str| (source.to_string(), id.to_string());
+
+    // The CVE ID and its aliases resolve to the same records
+    for id in ["CVE-2021-44228", "cve-2021-44228", "GHSA-jfh8-c2jp-5v3q"] {
+        let resolution = resolve(&nvd, id).expect("Failed resolving");
+        assert_eq!(resolution.cves, vec!["CVE-2021-44228"]);
+        assert_eq!(resolution.aliases.len(), 1);
+        assert_eq!(resolution.aliases[0].source, "OSV");
+        assert_eq!(
+            sources(&resolution),
+            vec![
+                record("nvd", "CVE-2021-44228"),
+                record("OSV", "GHSA-jfh8-c2jp-5v3q")
+            ]
+        );
+        match &resolution.records[0].record {
+            Record::Cve(container) => assert!(container.severity().is_some()),
+            record => panic!("Expected a CVE, got {:?}", record),
+        }
+    }
+
+    // An advisory resolves to its CVE even when it isn't cached
+    let resolution = resolve(&nvd, "GHSA-35jh-r3h4-6jhm").unwrap();
+    assert_eq!(resolution.cves, vec!["CVE-2021-23337"]);
+    assert_eq!(
+        sources(&resolution),
+        vec![record("OSV", "GHSA-35jh-r3h4-6jhm")]
+    );
+
+    assert!(resolve(&nvd, "GHSA-0000-0000-0000")
+        .unwrap_err()
+        .is_not_found());
+    assert!(resolve(&nvd, "CVE-1999-0001").unwrap_err().is_not_found());
+
+    // Each source keeps its record of the CVE
+    let resolution =
+        resolve_in_sources(&[internal.clone(), nvd.clone()], "GHSA-jfh8-c2jp-5v3q").unwrap();
+    assert_eq!(resolution.cves, vec!["CVE-2021-44228"]);
+    assert_eq!(
+        sources(&resolution),
+        vec![
+            record("nvd", "CVE-2021-44228"),
+            record("OSV", "GHSA-jfh8-c2jp-5v3q")
+        ]
+    );
+    let resolution =
+        resolve_in_sources(&[internal.clone(), nvd.clone()], "CVE-2021-44228").unwrap();
+    assert_eq!(
+        sources(&resolution),
+        vec![
+            record("internal", "CVE-2021-44228"),
+            record("nvd", "CVE-2021-44228"),
+            record("OSV", "GHSA-jfh8-c2jp-5v3q")
+        ]
+    );
+    assert!(resolve_in_sources(&[internal.clone(), nvd.clone()], "GHSA-0000-0000-0000").is_err());
+
+    fs::remove_file(&nvd.db).expect("Failed removing test cache");
+    fs::remove_file(&internal.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_score_cves() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/risk.sqlite3");
+
+    let tags = |tags: &[&str]| CveOverride {
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        ..Default::default()
+    };
+    set_override(&config, "CVE-2021-44228", &tags(&["internet-facing"])).unwrap();
+    set_override(&config, "CVE-2022-30190", &tags(&["lab"])).unwrap();
+    assert_eq!(
+        get_override(&config, "CVE-2022-30190")
+            .unwrap()
+            .unwrap()
+            .tags,
+        vec!["lab"]
+    );
+
+    let weights = RiskWeights {
+        public_exploit: 3.0,
+        asset_tags: BTreeMap::from([
+            ("internet-facing".to_string(), 5.0),
+            ("lab".to_string(), -10.0),
+        ]),
+        ..Default::default()
+    };
+    assert_eq!(score_cves(&config, &weights).unwrap(), 6);
+
+    // Heartbleed and CVE-2019-12780 reference exploits, Heartbleed only has a CVSS v2 score
+    let scored = search_by_risk(&config, &SearchQuery::default()).unwrap();
+    let ranked: Vec<_> = scored.iter().map(|cve| cve.summary.id.as_str()).collect();
+    assert_eq!(
+        ranked,
+        vec![
+            "CVE-2021-44228",
+            "CVE-2019-12780",
+            "CVE-2021-26855",
+            "CVE-2021-3711",
+            "CVE-2014-0160",
+            "CVE-2022-30190"
+        ]
+    );
+    assert_eq!(scored[0].risk_score, 15.0);
+    assert_eq!(scored[4].risk_score, 8.0);
+
+    let query = SearchQuery {
+        attack_vector: Some(AttackVector::Network),
+        min_risk_score: Some(9.8),
+        ..Default::default()
+    };
+    assert_eq!(
+        ids(search(&config, &query).unwrap()),
+        vec!["CVE-2021-26855", "CVE-2021-3711", "CVE-2021-44228"]
+    );
+
+    // Scoring again replaces every score, CVEs a scorer skips are left unscored
+    let scorer = |cve: &RiskInput| {
+        cve.asset_tags
+            .contains(&"internet-facing".to_string())
+            .then(|| cve.cvss_score.unwrap_or_default() * 2.0)
+    };
+    assert_eq!(score_cves(&config, &scorer).unwrap(), 1);
+    let scored = search_by_risk(&config, &SearchQuery::default()).unwrap();
+    assert_eq!(scored.len(), 1);
+    assert_eq!(scored[0].risk_score, 20.0);
+
+    assert!(delete_cve(&config, "CVE-2021-44228").unwrap());
+    assert!(search_by_risk(&config, &SearchQuery::default())
+        .unwrap()
+        .is_empty());
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_override_namespaces() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/namespaces.sqlite3");
+    let payments = CacheConfig {
+        namespace: "payments".to_string(),
+        ..config.clone()
+    };
+
+    let tags = |tags: &[&str]| CveOverride {
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        ..Default::default()
+    };
+    set_override(&config, "CVE-2021-44228", &tags(&["lab"])).unwrap();
+    set_override(&payments, "CVE-2021-44228", &tags(&["internet-facing"])).unwrap();
+    set_override(&payments, "CVE-2022-30190", &tags(&["lab"])).unwrap();
+
+    // Each namespace only sees its own overrides
+    let tags_of = |config: &CacheConfig, cve: &str| {
+        get_override(config, cve)
+            .unwrap()
+            .map(|cve_override| cve_override.tags)
+    };
+    assert_eq!(
+        tags_of(&config, "CVE-2021-44228"),
+        Some(vec!["lab".to_string()])
+    );
+    assert_eq!(
+        tags_of(&payments, "CVE-2021-44228"),
+        Some(vec!["internet-facing".to_string()])
+    );
+    assert_eq!(tags_of(&config, "CVE-2022-30190"), None);
+
+    // Risk scores are computed and searched per namespace, from the tags of its overrides
+    let weights = RiskWeights {
+        asset_tags: BTreeMap::from([
+            ("internet-facing".to_string(), 5.0),
+            ("lab".to_string(), -10.0),
+        ]),
+        ..Default::default()
+    };
+    assert_eq!(score_cves(&config, &weights).unwrap(), 6);
+    assert_eq!(score_cves(&payments, &weights).unwrap(), 6);
+    let query = SearchQuery {
+        min_risk_score: Some(12.0),
+        ..Default::default()
+    };
+    assert!(search(&config, &query).unwrap().is_empty());
+    assert_eq!(
+        ids(search(&payments, &query).unwrap()),
+        vec!["CVE-2021-44228"]
+    );
+    let top = |config: &CacheConfig| {
+        let scored = search_by_risk(config, &SearchQuery::default()).unwrap();
+        assert_eq!(scored.len(), 6);
+        (scored[0].summary.id.clone(), scored[0].risk_score)
+    };
+    assert_eq!(top(&config), ("CVE-2021-26855".to_string(), 9.8));
+    assert_eq!(top(&payments), ("CVE-2021-44228".to_string(), 15.0));
+
+    assert!(remove_override(&payments, "CVE-2021-44228").unwrap());
+    assert!(!remove_override(&payments, "CVE-2021-44228").unwrap());
+    assert!(get_override(&config, "CVE-2021-44228").unwrap().is_some());
+
+    // Caches made before namespaces keep their overrides in the default namespace
+    let conn = rusqlite::Connection::open(&config.db).expect("Failed opening test cache");
+    conn.execute_batch(
+        "DROP TABLE overrides;
+        CREATE TABLE overrides (
+            id VARCHAR PRIMARY KEY,
+            severity VARCHAR,
+            affected INTEGER,
+            note TEXT,
+            updated VARCHAR NOT NULL);
+        INSERT INTO overrides VALUES ('CVE-2014-0160', 'LOW', 0, 'Patched', '2021-12-18T19:00:00Z');",
+    )
+    .unwrap();
+    conn.close().expect("Failed closing test cache");
+
+    let read_only = CacheConfig {
+        read_only: true,
+        ..config.clone()
+    };
+    let note = |config: &CacheConfig| {
+        get_override(config, "CVE-2014-0160")
+            .unwrap()
+            .and_then(|cve_override| cve_override.note)
+    };
+    assert_eq!(note(&read_only).as_deref(), Some("Patched"));
+    let read_only_payments = CacheConfig {
+        read_only: true,
+        ..payments.clone()
+    };
+    assert_eq!(note(&read_only_payments), None);
+
+    reindex(&config).expect("Failed reindexing test cache");
+    assert_eq!(note(&config).as_deref(), Some("Patched"));
+    assert_eq!(note(&payments), None);
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_text_matches_ids() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/text.sqlite3");
+
+    let query = SearchQuery {
+        text: Some("2021-44228".to_string()),
+        ..Default::default()
+    };
+    let cves = search(&config, &query).expect("Failed searching text");
+    assert_eq!(ids(cves), vec!["CVE-2021-44228"]);
+
+    // IDs mentioned in the description of another CVE match it as well
+    let query = SearchQuery {
+        text: Some("2021-2685".to_string()),
+        ..Default::default()
+    };
+    let cves = search(&config, &query).expect("Failed searching text");
+    assert_eq!(ids(cves), vec!["CVE-2021-26855"]);
+
+    let query = SearchQuery {
+        text: Some("Crock-Pot".to_string()),
+        ..Default::default()
+    };
+    let cves = search(&config, &query).expect("Failed searching text");
+    assert_eq!(ids(cves), vec!["CVE-2019-12780"]);
+
+    // Wildcards of LIKE patterns are matched literally
+    for text in ["%", "2021_44228"] {
+        let query = SearchQuery {
+            text: Some(text.to_string()),
+            ..Default::default()
+        };
+        let cves = search(&config, &query).expect("Failed searching text");
+        assert!(cves.is_empty(), "{:?} matched {:?}", text, ids(cves));
+    }
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_id_prefix() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/prefix.sqlite3");
+
+    let cves = search_id_prefix(&config, "CVE-2021-").expect("Failed searching prefix");
+    assert_eq!(
+        ids(cves),
+        vec!["CVE-2021-26855", "CVE-2021-3711", "CVE-2021-44228"]
+    );
+
+    let cves = search_id_prefix(&config, "cve-2021-4").expect("Failed searching prefix");
+    assert_eq!(ids(cves), vec!["CVE-2021-44228"]);
+
+    // Prefixes are matched literally, not as patterns, and only at the beginning of the ID
+    assert!(search_id_prefix(&config, "CVE-20%-4").unwrap().is_empty());
+    assert!(search_id_prefix(&config, "CVE_2021").unwrap().is_empty());
+    assert!(search_id_prefix(&config, "2021-44228").unwrap().is_empty());
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_by_severity() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/severity.sqlite3");
+
+    let at_least = |severity| {
+        let query = SearchQuery {
+            min_severity: Some(severity),
+            ..Default::default()
+        };
+        search(&config, &query).expect("Failed searching by severity")
+    };
+
+    let critical = at_least(Severity::Critical);
+    assert_eq!(
+        ids(critical.clone()),
+        vec!["CVE-2021-26855", "CVE-2021-3711", "CVE-2021-44228"]
+    );
+    assert_eq!(at_least(Severity::None).len(), 6);
+
+    let high = at_least(Severity::High);
+    let high_count = high.len();
+    assert!(high.len() > critical.len());
+    assert!(high
+        .iter()
+        .all(|cve| cve.severity.unwrap() >= Severity::High));
+
+    // Severities are matched exactly, rated from CVSS v2 for CVEs without v3 metrics
+    let high = search_by_severity(&config, Severity::High).expect("Failed searching by severity");
+    assert_eq!(ids(high), vec!["CVE-2019-12780", "CVE-2022-30190"]);
+    let medium = search_by_severity(&config, Severity::Medium).unwrap();
+    assert_eq!(ids(medium), vec!["CVE-2014-0160"]);
+    assert!(search_by_severity(&config, Severity::Low)
+        .unwrap()
+        .is_empty());
+
+    let query = SearchQuery {
+        severity: Some(Severity::High),
+        min_base_score: Some(8.0),
+        ..Default::default()
+    };
+    assert_eq!(
+        ids(search(&config, &query).unwrap()),
+        vec!["CVE-2019-12780"]
+    );
+    let query = SearchQuery {
+        min_base_score: Some(10.0),
+        ..Default::default()
+    };
+    assert_eq!(
+        ids(search(&config, &query).unwrap()),
+        vec!["CVE-2021-44228"]
+    );
+
+    // The severity of an analyst's override is matched instead of the one of the feed, in the
+    // namespace of the config only
+    let downgraded = CveOverride {
+        severity: Some(Severity::Low),
+        ..Default::default()
+    };
+    set_override(&config, "CVE-2021-44228", &downgraded).unwrap();
+    assert_eq!(
+        ids(search_by_severity(&config, Severity::Critical).unwrap()),
+        vec!["CVE-2021-26855", "CVE-2021-3711"]
+    );
+    let low = search_by_severity(&config, Severity::Low).unwrap();
+    assert_eq!(ids(low.clone()), vec!["CVE-2021-44228"]);
+    assert_eq!(low[0].override_severity, Some(Severity::Low));
+    assert_eq!(low[0].severity, Some(Severity::Critical));
+    assert_eq!(at_least(Severity::High).len(), high_count - 1);
+
+    let team = CacheConfig {
+        namespace: "team".to_string(),
+        ..config.clone()
+    };
+    assert_eq!(
+        ids(search_by_severity(&team, Severity::Critical).unwrap()),
+        ids(critical)
+    );
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_export_stix() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/stix.sqlite3");
+
+    let bundle = export_stix(&config, &SearchQuery::default()).expect("Failed exporting STIX");
+    assert_eq!(bundle.kind, "bundle");
+    assert_eq!(bundle.objects.len(), 6);
+    assert_eq!(
+        bundle,
+        export_stix(&config, &SearchQuery::default()).unwrap()
+    );
+
+    let log4shell = &bundle.objects[4];
+    assert_eq!(log4shell.name, "CVE-2021-44228");
+    assert_eq!(
+        log4shell.id,
+        "vulnerability--693c68fd-9a33-5d6d-b44e-407b6a48b05b"
+    );
+    assert_eq!(log4shell.created, "2021-12-10T10:15:00.000Z");
+    assert_eq!(log4shell.modified, "2022-07-12T17:42:00.000Z");
+    assert_eq!(log4shell.x_opencti_base_score, Some(10.0));
+    assert_eq!(log4shell.x_opencti_base_severity, Some(Severity::Critical));
+    assert_eq!(
+        log4shell.x_opencti_attack_vector.as_deref(),
+        Some("NETWORK")
+    );
+    assert_eq!(log4shell.external_references.len(), 4);
+    assert_eq!(
+        log4shell.external_references[0].external_id.as_deref(),
+        Some("CVE-2021-44228")
+    );
+
+    // The cache exports CVEs as they are converted straight from the feed
+    let json = fs::read("./tests/files/nvdcve-1.1-sample.json").unwrap();
+    let feed: CveFeed = serde_json::from_slice(&json).unwrap();
+    let mut objects: Vec<StixVulnerability> = feed.cve_items.iter().map(Into::into).collect();
+    objects.sort_by(|a, b| a.name.cmp(&b.name));
+    assert_eq!(bundle.objects, objects);
+
+    let query = SearchQuery {
+        has_public_exploit: true,
+        ..Default::default()
+    };
+    let exploited = export_stix(&config, &query).unwrap();
+    assert_eq!(exploited.objects.len(), 2);
+    assert_ne!(exploited.id, bundle.id);
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_write_misp_feed() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/misp.sqlite3");
+    let dir = PathBuf::from("./tests/files/.cache/misp");
+    let _ = fs::remove_dir_all(&dir);
+
+    let events =
+        write_misp_feed(&config, &SearchQuery::default(), &dir).expect("Failed writing feed");
+    assert_eq!(events, 6);
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 7);
+
+    let manifest: serde_json::Value =
+        serde_json::from_slice(&fs::read(dir.join("manifest.json")).unwrap()).unwrap();
+    assert_eq!(manifest.as_object().unwrap().len(), 6);
+
+    // Events are named after a UUID derived from the CVE ID, so they're stable across exports
+    let uuid = "6d1ebc4a-b86d-57e0-b684-34920f932aa7";
+    assert_eq!(manifest[uuid]["info"], "CVE-2021-44228 (CRITICAL 10.0)");
+    assert_eq!(manifest[uuid]["threat_level_id"], "1");
+    assert_eq!(manifest[uuid]["timestamp"], "1657647720");
+
+    let event: serde_json::Value =
+        serde_json::from_slice(&fs::read(dir.join(format!("{}.json", uuid))).unwrap()).unwrap();
+    let event = &event["Event"];
+    assert_eq!(event["uuid"], uuid);
+    assert_eq!(event["date"], "2021-12-10");
+    assert_eq!(event["Orgc"]["name"], "NIST NVD");
+    assert_eq!(event["Tag"][0]["name"], "tlp:white");
+    let attributes = event["Attribute"].as_array().unwrap();
+    assert_eq!(attributes.len(), 4);
+    assert_eq!(attributes[0]["type"], "vulnerability");
+    assert_eq!(attributes[0]["value"], "CVE-2021-44228");
+    assert_eq!(attributes[1]["type"], "link");
+
+    let heartbleed = MispEvent::new(
+        &search_by_id(&config, "CVE-2014-0160").unwrap(),
+        "2014-04-07T22:55Z",
+        "2020-11-16T14:53Z",
+        Some((Severity::Medium, 5.0)),
+    );
+    assert_eq!(heartbleed.threat_level_id, "2");
+    assert_eq!(manifest[&heartbleed.uuid]["info"], heartbleed.info);
+
+    // Events of CVEs that no longer match are removed from the feed
+    let query = SearchQuery {
+        has_public_exploit: true,
+        ..Default::default()
+    };
+    assert_eq!(write_misp_feed(&config, &query, &dir).unwrap(), 2);
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+    assert!(!dir.join(format!("{}.json", uuid)).exists());
+    assert!(dir.join(format!("{}.json", heartbleed.uuid)).exists());
+
+    fs::remove_dir_all(&dir).expect("Failed removing test feed");
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_write_kv_index() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/kv.sqlite3");
+    let dir = PathBuf::from("./tests/files/.cache/kv");
+    let _ = fs::remove_dir_all(&dir);
+
+    let cves =
+        write_kv_index(&config, &SearchQuery::default(), &dir).expect("Failed writing index");
+    assert_eq!(cves, 6);
+
+    let index = KvIndex::open(&dir).expect("Failed opening index");
+    assert_eq!(index.len(), 6);
+    assert!(index.contains("CVE-2021-44228"));
+    assert!(!index.contains("CVE-2021-4422"));
+    assert_eq!(
+        index.ids_with_prefix("CVE-2021-"),
+        vec!["CVE-2021-26855", "CVE-2021-3711", "CVE-2021-44228"]
+    );
+
+    // The index holds the CVEs as cached
+    for id in index.ids_with_prefix("") {
+        let cve = index.get(&id).unwrap().expect("CVE missing from index");
+        assert_eq!(
+            serde_json::to_value(cve).unwrap(),
+            serde_json::to_value(search_by_id(&config, &id).unwrap()).unwrap()
+        );
+    }
+    assert!(index.get_raw("CVE-1999-0001").unwrap().is_none());
+
+    // Writing the index again replaces it
+    let query = SearchQuery {
+        has_public_exploit: true,
+        ..Default::default()
+    };
+    assert_eq!(write_kv_index(&config, &query, &dir).unwrap(), 2);
+    let index = KvIndex::open(&dir).unwrap();
+    assert_eq!(
+        index.ids_with_prefix(""),
+        vec!["CVE-2014-0160", "CVE-2019-12780"]
+    );
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+    // Values that don't hold the records of the IDs are rejected
+    fs::write(dir.join("cves.bin"), b"NVDCVEKV").unwrap();
+    let index = KvIndex::open(&dir).unwrap();
+    assert!(matches!(
+        index.get("CVE-2014-0160"),
+        Err(KvIndexError::InvalidIndex(_))
+    ));
+    fs::write(dir.join("cves.bin"), b"SQLite format 3").unwrap();
+    assert!(matches!(
+        KvIndex::open(&dir),
+        Err(KvIndexError::InvalidIndex(_))
+    ));
+
+    fs::remove_dir_all(&dir).expect("Failed removing test index");
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_skip_cves_modified_after_metafile() {
+    let db = "./tests/files/.cache/nvd/modified_after.sqlite3";
+    let sample: serde_json::Value =
+        serde_json::from_slice(&fs::read("./tests/files/nvdcve-1.1-sample.json").unwrap()).unwrap();
+    let path = "./tests/files/.cache/nvdcve-1.1-modified-after.json";
+
+    // The Metafile was last modified at 2021-12-18T19:00:00Z, before three CVEs of the sample feed
+    // were, CVE-2014-0160 being modified at each date in turn
+    for (last_modified, skipped) in [
+        ("2021-12-18T19:01Z", 4),
+        ("2021-12-18T19:00:30Z", 4),
+        ("2021-12-18T20:01+01:00", 4),
+        ("2021-12-18T19:00:00.500", 4),
+        ("2021-12-18T19:00Z", 3),
+        ("2021-12-18T20:00+01:00", 3),
+        ("2021-12-18T18:59:59Z", 3),
+        // Dates that don't parse are written rather than skipped
+        ("18/12/2021 20:00", 3),
+    ] {
+        // Only CVEs of feeds synced before are skipped, as the cache may hold newer versions
+        let mut config = sync_sample_feed(db);
+        config.force_update = true;
+
+        let mut feed = sample.clone();
+        feed["CVE_Items"][0]["lastModifiedDate"] = last_modified.into();
+        fs::write(path, serde_json::to_vec(&feed).unwrap()).unwrap();
+
+        let report =
+            sync_blocking(&config, feed_file_client(path)).expect("Failed to sync to local cache");
+        assert_eq!(
+            report.feed_changes[0].skipped, skipped,
+            "CVE last modified at {}",
+            last_modified
+        );
+    }
+
+    fs::remove_file(path).ok();
+    fs::remove_file(db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_by_id_as_of() {
+    let mut config = sync_sample_feed("./tests/files/.cache/nvd/as_of.sqlite3");
+    let original = search_by_id(&config, "CVE-2021-44228").unwrap();
+
+    // Sync two later versions of Log4Shell, keeping the versions they replace
+    config.track_history = true;
+    config.force_update = true;
+    let sample: serde_json::Value =
+        serde_json::from_slice(&fs::read("./tests/files/nvdcve-1.1-sample.json").unwrap()).unwrap();
+    let path = "./tests/files/.cache/nvdcve-1.1-as-of.json";
+    for (last_modified, description) in [
+        ("2023-03-01T09:00Z", "Second version"),
+        ("2024-01-15T12:00Z", "Third version"),
+    ] {
+        let mut feed = sample.clone();
+        feed["CVE_Items"][4]["lastModifiedDate"] = last_modified.into();
+        feed["CVE_Items"][4]["cve"]["description"]["description_data"][0]["value"] =
+            description.into();
+        fs::write(path, serde_json::to_vec(&feed).unwrap()).unwrap();
+
+        // CVEs modified after the Metafile of their feed are skipped
+        let mut client = feed_file_client(path);
+        client.get_metafile_response =
+            Ok(fs::read_to_string("./tests/files/nvdcve-1.1-recent.meta")
+                .unwrap()
+                .replace("2021-12-18", "2024-02-01"));
+        sync_blocking(&config, client).expect("Failed to sync to local cache");
+    }
+
+    let as_of = |date: &str| {
+        let date = DateTime::parse_from_rfc3339(date)
+            .unwrap()
+            .with_timezone(&Utc);
+        search_by_id_as_of(&config, "CVE-2021-44228", &date)
+    };
+    let description = |cve: Cve| cve.description.description_data[0].value.clone();
+
+    assert!(as_of("2021-12-01T00:00:00Z").unwrap_err().is_not_found());
+    assert_eq!(
+        description(as_of("2022-08-01T00:00:00Z").unwrap()),
+        description(original)
+    );
+    assert_eq!(
+        description(as_of("2023-03-01T09:00:00Z").unwrap()),
+        "Second version"
+    );
+    assert_eq!(
+        description(as_of("2024-01-15T11:59:00Z").unwrap()),
+        "Second version"
+    );
+    assert_eq!(
+        description(as_of("2026-01-01T00:00:00Z").unwrap()),
+        "Third version"
+    );
+
+    // CVEs that were never modified have a single version
+    let heartbleed = search_by_id_as_of(&config, "CVE-2014-0160", &Utc::now()).unwrap();
+    assert_eq!(
+        serde_json::to_value(heartbleed).unwrap(),
+        serde_json::to_value(search_by_id(&config, "CVE-2014-0160").unwrap()).unwrap()
+    );
+
+    // Versions are kept once the CVE is deleted
+    delete_cve(&config, "CVE-2021-44228").unwrap();
+    assert_eq!(
+        description(as_of("2023-06-01T00:00:00Z").unwrap()),
+        "Second version"
+    );
+    assert!(check(&config).unwrap().integrity_errors.is_empty());
+
+    fs::remove_file(path).expect("Failed removing test feed");
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_history_blobs() {
+    let mut config = sync_sample_feed("./tests/files/.cache/nvd/blobs.sqlite3");
+    config.track_history = true;
+    config.force_update = true;
+
+    // Log4Shell is modified back and forth between two descriptions, the versions kept with the
+    // same data share their blob
+    let sample: serde_json::Value =
+        serde_json::from_slice(&fs::read("./tests/files/nvdcve-1.1-sample.json").unwrap()).unwrap();
+    let original = sample["CVE_Items"][4]["cve"]["description"]["description_data"][0]["value"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let path = "./tests/files/.cache/nvdcve-1.1-blobs.json";
+    for (last_modified, description) in [
+        ("2023-03-01T09:00Z", "Second version"),
+        ("2023-06-01T09:00Z", original.as_str()),
+        ("2023-09-01T09:00Z", "Second version"),
+    ] {
+        let mut feed = sample.clone();
+        feed["CVE_Items"][4]["lastModifiedDate"] = last_modified.into();
+        feed["CVE_Items"][4]["cve"]["description"]["description_data"][0]["value"] =
+            description.into();
+        fs::write(path, serde_json::to_vec(&feed).unwrap()).unwrap();
+
+        let mut client = feed_file_client(path);
+        client.get_metafile_response =
+            Ok(fs::read_to_string("./tests/files/nvdcve-1.1-recent.meta")
+                .unwrap()
+                .replace("2021-12-18", "2024-02-01"));
+        sync_blocking(&config, client).expect("Failed to sync to local cache");
+    }
+
+    let count = |table: &str| -> usize {
+        let conn = rusqlite::Connection::open(&config.db).expect("Failed opening test cache");
+        conn.query_row(&format!("SELECT count(*) FROM {}", table), [], |row| {
+            row.get(0)
+        })
+        .unwrap()
+    };
+    assert_eq!(count("cve_history"), 3);
+    assert_eq!(count("blobs"), 2);
+
+    let description = |date: &str| {
+        let date = DateTime::parse_from_rfc3339(date)
+            .unwrap()
+            .with_timezone(&Utc);
+        let cve = search_by_id_as_of(&config, "CVE-2021-44228", &date).unwrap();
+        cve.description.description_data[0].value.clone()
+    };
+    assert_eq!(description("2023-04-01T00:00:00Z"), "Second version");
+    assert_eq!(description("2023-07-01T00:00:00Z"), original);
+    assert!(check(&config).unwrap().integrity_errors.is_empty());
+
+    // Versions kept with their data by older versions are moved to blobs
+    {
+        let conn = rusqlite::Connection::open(&config.db).expect("Failed opening test cache");
+        conn.execute_batch(
+            "DROP TABLE cve_history;
+            DELETE FROM blobs;
+            CREATE TABLE cve_history (
+                cve VARCHAR NOT NULL,
+                last_modified_date VARCHAR NOT NULL,
+                data TEXT NOT NULL,
+                replaced VARCHAR NOT NULL,
+                PRIMARY KEY (cve, last_modified_date));
+            INSERT INTO cve_history (cve, last_modified_date, data, replaced)
+            SELECT id, '2022-01-01T00:00Z', data, '2022-02-01T00:00:00Z' FROM cve
+            WHERE id = 'CVE-2021-44228';",
+        )
+        .unwrap();
+    }
+    assert_eq!(description("2022-06-01T00:00:00Z"), "Second version");
+    assert_eq!(count("cve_history"), 1);
+    assert_eq!(count("blobs"), 1);
+
+    fs::remove_file(path).expect("Failed removing test feed");
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_history_of_new_cache() {
+    // The modified feed replaces Log4Shell as written by the recent feed, in a single sync of a
+    // new cache, however it is populated
+    let sample: serde_json::Value =
+        serde_json::from_slice(&fs::read("./tests/files/nvdcve-1.1-sample.json").unwrap()).unwrap();
+    let mut modified = sample.clone();
+    modified["CVE_Items"][4]["lastModifiedDate"] = "2023-03-01T09:00Z".into();
+    modified["CVE_Items"][4]["cve"]["description"]["description_data"][0]["value"] =
+        "Second version".into();
+
+    let metafile = fs::read("./tests/files/nvdcve-1.1-recent.meta").unwrap();
+    let mut files = HashMap::new();
+    for (name, feed) in [(FeedName::Recent, &sample), (FeedName::Modified, &modified)] {
+        files.insert(name.metafile_filename(), metafile.clone());
+        files.insert(
+            name.feed_filename(),
+            gzip(&serde_json::to_vec(feed).unwrap()),
+        );
+    }
+    let server = MockFeedServer::start(files);
+
+    let config = CacheConfig {
+        db: "./tests/files/.cache/nvd/new_history.sqlite3".to_string(),
+        feeds: vec![FeedName::Recent, FeedName::Modified],
+        show_progress: false,
+        track_history: true,
+        ..CacheConfig::new()
+    };
+    fs::remove_file(&config.db).ok();
+    let report = sync_blocking(
+        &config,
+        ReqwestBlockingClient::new(&server.url, None, None, None),
+    )
+    .expect("Failed to sync to local cache");
+    assert_eq!(report.cves_modified, 1);
+
+    let replaced = DateTime::parse_from_rfc3339("2023-01-01T00:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+    let cve = search_by_id_as_of(&config, "CVE-2021-44228", &replaced).unwrap();
+    assert!(cve.description.description_data[0]
+        .value
+        .starts_with("Apache Log4j2"));
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_history_of_rescored_cve() {
+    let mut config = sync_sample_feed("./tests/files/.cache/nvd/rescored_history.sqlite3");
+    config.track_history = true;
+    config.force_update = true;
+
+    // The NVD re-scores Log4Shell without changing its description or references
+    let mut feed: serde_json::Value =
+        serde_json::from_slice(&fs::read("./tests/files/nvdcve-1.1-sample.json").unwrap()).unwrap();
+    feed["CVE_Items"][4]["lastModifiedDate"] = "2023-03-01T09:00Z".into();
+    feed["CVE_Items"][4]["impact"]["baseMetricV3"]["cvssV3"]["baseScore"] = 9.0.into();
+    let path = "./tests/files/.cache/nvdcve-1.1-rescored-history.json";
+    fs::write(path, serde_json::to_vec(&feed).unwrap()).unwrap();
+
+    let mut client = feed_file_client(path);
+    client.get_metafile_response = Ok(fs::read_to_string("./tests/files/nvdcve-1.1-recent.meta")
+        .unwrap()
+        .replace("2021-12-18", "2024-02-01"));
+    sync_blocking(&config, client).expect("Failed to sync to local cache");
+
+    let triaged = Utc.with_ymd_and_hms(2022, 8, 1, 0, 0, 0).unwrap();
+    let then = get_record(&config, "CVE-2021-44228", Some(&triaged)).unwrap();
+    let now = get_record(&config, "CVE-2021-44228", None).unwrap();
+    assert_eq!(then.score, Some(10.0));
+    assert_eq!(now.score, Some(9.0));
+    assert_eq!(then.severity, Some(Severity::Critical));
+
+    fs::remove_file(path).expect("Failed removing test feed");
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_cached_dates() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/dates.sqlite3");
+    let dates = || -> (String, String) {
+        let conn = rusqlite::Connection::open(&config.db).expect("Failed opening test cache");
+        conn.query_row(
+            "SELECT published_date, last_modified_date FROM cve WHERE id = 'CVE-2021-44228'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap()
+    };
+
+    // Dates of the feeds are cached with seconds, as any other cached date
+    let formatted = (
+        "2021-12-10T10:15:00Z".to_string(),
+        "2022-07-12T17:42:00Z".to_string(),
+    );
+    assert_eq!(dates(), formatted);
+
+    // The version current at the exact time it was modified is the cached one
+    let modified = DateTime::parse_from_rfc3339("2022-07-12T17:42:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+    assert!(search_by_id_as_of(&config, "CVE-2021-44228", &modified).is_ok());
+
+    // Dates cached as given by the feeds by older versions are formatted alike once
+    {
+        let conn = rusqlite::Connection::open(&config.db).expect("Failed opening test cache");
+        conn.execute_batch(
+            "UPDATE cve SET published_date = '2021-12-10T10:15Z',
+                last_modified_date = '2022-07-12T17:42Z'
+            WHERE id = 'CVE-2021-44228';
+            DELETE FROM migration;
+            INSERT INTO migration (schema_version, app_version, status) VALUES ('0.1.0', '0.1.0', 0);",
+        )
+        .unwrap();
     }
+    assert!(search_by_id_as_of(&config, "CVE-2021-44228", &modified).is_ok());
+    assert_eq!(dates(), formatted);
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_compare_cves() {
+    let mut config = sync_sample_feed("./tests/files/.cache/nvd/compare.sqlite3");
+
+    let log4shell = get_record(&config, "CVE-2021-44228", None).unwrap();
+    let openssl = get_record(&config, "CVE-2021-3711", None).unwrap();
+
+    let comparison = CveComparison::new(&log4shell, &openssl);
+    assert_eq!(comparison.from, "CVE-2021-44228");
+    assert_eq!(comparison.to, "CVE-2021-3711");
+    assert_eq!(comparison.severity, None);
+    assert_eq!(
+        comparison.score,
+        Some(Change {
+            from: Some(10.0),
+            to: Some(9.8)
+        })
+    );
+    assert!(comparison.description.is_some());
+    assert_eq!(
+        comparison.references_removed.len(),
+        log4shell.cve.references.reference_data.len()
+    );
+    assert!(CveComparison::new(&log4shell, &log4shell).is_identical());
+
+    // Sync a later version of Log4Shell replacing its first reference, keeping the version it
+    // replaces
+    config.track_history = true;
+    config.force_update = true;
+    let mut feed: serde_json::Value =
+        serde_json::from_slice(&fs::read("./tests/files/nvdcve-1.1-sample.json").unwrap()).unwrap();
+    let removed = feed["CVE_Items"][4]["cve"]["references"]["reference_data"][0]["url"].clone();
+    feed["CVE_Items"][4]["lastModifiedDate"] = "2023-03-01T09:00Z".into();
+    feed["CVE_Items"][4]["cve"]["references"]["reference_data"][0]["url"] =
+        "https://example.com/advisory".into();
+    let path = "./tests/files/.cache/nvdcve-1.1-compare.json";
+    fs::write(path, serde_json::to_vec(&feed).unwrap()).unwrap();
+
+    let mut client = feed_file_client(path);
+    client.get_metafile_response = Ok(fs::read_to_string("./tests/files/nvdcve-1.1-recent.meta")
+        .unwrap()
+        .replace("2021-12-18", "2024-02-01"));
+    sync_blocking(&config, client).expect("Failed to sync to local cache");
+
+    let triaged = DateTime::parse_from_rfc3339("2022-08-01T00:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+    let then = get_record(&config, "CVE-2021-44228", Some(&triaged)).unwrap();
+    let now = get_record(&config, "CVE-2021-44228", None).unwrap();
+
+    let comparison = CveComparison::new(&then, &now);
+    assert_eq!(
+        comparison.last_modified,
+        Some(Change {
+            from: Some("2022-07-12T17:42:00Z".to_string()),
+            to: Some("2023-03-01T09:00:00Z".to_string())
+        })
+    );
+    assert_eq!(
+        comparison.references_added,
+        vec!["https://example.com/advisory"]
+    );
+    assert_eq!(
+        comparison.references_removed,
+        vec![removed.as_str().unwrap()]
+    );
+
+    // Kept versions keep their scores and publication date
+    assert_eq!(comparison.score, None);
+    assert_eq!(comparison.published, None);
+    assert_eq!(comparison.description, None);
+
+    let json = serde_json::to_value(&comparison).unwrap();
+    assert!(json.get("severity").is_none());
+    assert_eq!(json["last_modified"]["to"], "2023-03-01T09:00:00Z");
+
+    assert!(get_record(&config, "CVE-1999-0001", None)
+        .unwrap_err()
+        .is_not_found());
+
+    fs::remove_file(path).expect("Failed removing test feed");
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_public_exploits() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/exploits.sqlite3");
+
+    let query = SearchQuery {
+        has_public_exploit: true,
+        ..Default::default()
+    };
+    let cves = search(&config, &query).expect("Failed searching public exploits");
+    assert_eq!(ids(cves), vec!["CVE-2014-0160", "CVE-2019-12780"]);
+
+    let refs = search_by_id(&config, "CVE-2014-0160")
+        .unwrap()
+        .exploit_refs();
+    assert_eq!(refs.len(), 1);
+    assert_eq!(refs[0].source, ExploitSource::ExploitDb);
+
+    // Deleting a CVE deletes its exploit references along with it
+    delete_cve(&config, "CVE-2014-0160").unwrap();
+    assert_eq!(
+        ids(search(&config, &query).unwrap()),
+        vec!["CVE-2019-12780"]
+    );
+    assert!(check(&config).unwrap().integrity_errors.is_empty());
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_combined_criteria() {
+    // Heartbleed references two exploits, which must not list it twice
+    let mut feed: serde_json::Value =
+        serde_json::from_slice(&fs::read("./tests/files/nvdcve-1.1-sample.json").unwrap()).unwrap();
+    feed["CVE_Items"][0]["cve"]["references"]["reference_data"]
+        .as_array_mut()
+        .unwrap()
+        .push(serde_json::json!({
+            "url": "https://www.exploit-db.com/exploits/32764",
+            "name": "32764",
+            "refsource": "EXPLOIT-DB",
+            "tags": []
+        }));
+    let path = "./tests/files/.cache/nvdcve-1.1-exploits.json";
+    fs::write(path, serde_json::to_vec(&feed).unwrap()).unwrap();
+    let config = sync_feed_file("./tests/files/.cache/nvd/combined.sqlite3", path);
+    assert_eq!(
+        search_by_id(&config, "CVE-2014-0160")
+            .unwrap()
+            .exploit_refs()
+            .len(),
+        2
+    );
+
+    let query = SearchQuery {
+        has_public_exploit: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        ids(search(&config, &query).unwrap()),
+        vec!["CVE-2014-0160", "CVE-2019-12780"]
+    );
+    assert_eq!(search_cves(&config, &query).unwrap().len(), 2);
+
+    let query = SearchQuery {
+        text: Some("openssl".to_string()),
+        min_severity: Some(Severity::Medium),
+        has_public_exploit: true,
+        ..Default::default()
+    };
+    assert_eq!(ids(search(&config, &query).unwrap()), vec!["CVE-2014-0160"]);
+
+    let query = SearchQuery {
+        id_prefix: Some("CVE-2021-".to_string()),
+        text: Some("openssl".to_string()),
+        has_public_exploit: true,
+        ..Default::default()
+    };
+    assert!(search(&config, &query).unwrap().is_empty());
+
+    fs::remove_file(path).expect("Failed removing test feed");
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+/// Tracker recording the tickets it is asked to create
+#[derive(Default)]
+struct MockTracker {
+    tickets: Vec<Ticket>,
+}
+
+impl TicketTracker for MockTracker {
+    fn name(&self) -> String {
+        "mock".to_string()
+    }
+
+    fn create_ticket(&mut self, ticket: &Ticket) -> Result<Option<String>, TicketError> {
+        self.tickets.push(ticket.clone());
+        Ok(Some(format!("SEC-{}", self.tickets.len())))
+    }
+}
+
+#[test]
+fn test_create_tickets() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/tickets.sqlite3");
+    let template = TicketTemplate::new("{id} ({severity})", "{url}").unwrap();
+    let mut tracker = MockTracker::default();
+
+    let watchlist = SearchQuery {
+        text: Some("openssl".to_string()),
+        ..Default::default()
+    };
+    let created = create_tickets(&config, &watchlist, &template, &mut tracker)
+        .expect("Failed creating tickets");
+    assert_eq!(created.len(), tracker.tickets.len());
+    assert_eq!(
+        tracker.tickets[0],
+        Ticket {
+            cve: "CVE-2014-0160".to_string(),
+            summary: "CVE-2014-0160 (MEDIUM)".to_string(),
+            description: "https://nvd.nist.gov/vuln/detail/CVE-2014-0160".to_string(),
+        }
+    );
+
+    // CVEs already ticketed are skipped, only those newly matching the watchlist get one
+    let watchlist = SearchQuery {
+        min_severity: Some(Severity::Critical),
+        ..Default::default()
+    };
+    let again = create_tickets(&config, &watchlist, &template, &mut tracker).unwrap();
+    assert_eq!(
+        again.iter().map(|ticket| &ticket.cve).collect::<Vec<_>>(),
+        vec!["CVE-2021-26855", "CVE-2021-44228"]
+    );
+    assert!(create_tickets(&config, &watchlist, &template, &mut tracker)
+        .unwrap()
+        .is_empty());
+
+    let tickets = get_tickets(&config, "mock").unwrap();
+    assert_eq!(tickets.len(), created.len() + again.len());
+    assert_eq!(tickets[0].key.as_deref(), Some("SEC-1"));
+
+    assert!(matches!(
+        create_tickets(&config, &SearchQuery::default(), &template, &mut tracker),
+        Err(TicketError::EmptyWatchlist)
+    ));
+    for invalid in ["{id", "id}", "{id:.x}"] {
+        assert!(matches!(
+            TicketTemplate::new(invalid, ""),
+            Err(TicketError::InvalidTemplate(_))
+        ));
+    }
+
+    // Fields are formatted as with search --format-str
+    let template = TicketTemplate::new("{id:>16}", "{description:.12}").unwrap();
+    let ticket = template.render(&search_id_prefix(&config, "CVE-2021-44228").unwrap()[0]);
+    assert_eq!(ticket.summary, "  CVE-2021-44228");
+    assert_eq!(ticket.description, "Apache Log4j");
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_summaries() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/summaries.sqlite3");
+
+    let cves = search_id_prefix(&config, "CVE-").expect("Failed searching prefix");
+    assert!(cves.windows(2).all(|pair| pair[0] < pair[1]));
+
+    let log4shell = cves
+        .iter()
+        .find(|cve| cve.id == "CVE-2021-44228")
+        .expect("Summary not found");
+    assert_eq!(log4shell.severity, Some(Severity::Critical));
+    assert_eq!(log4shell.score, Some(10.0));
+    assert_eq!(log4shell.published.as_deref(), Some("2021-12-10T10:15:00Z"));
+    assert!(log4shell.description.starts_with("Apache Log4j2"));
+    assert!(log4shell
+        .to_string()
+        .starts_with("CVE-2021-44228   CRITICAL 10.0 2021-12-10 Apache Log4j2"));
+
+    // CVEs never scored with CVSS v3 are summarized from their v2 metrics
+    let heartbleed = &cves[0];
+    assert_eq!(heartbleed.id, "CVE-2014-0160");
+    assert_eq!(heartbleed.severity, Some(Severity::Medium));
+    assert_eq!(heartbleed.score, Some(5.0));
+
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_value(log4shell).unwrap();
+        assert_eq!(json["severity"], "CRITICAL");
+        assert_eq!(json["score"], 10.0);
+        let parsed: CveSummary = serde_json::from_value(json).unwrap();
+        assert_eq!(&parsed, log4shell);
+    }
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_populate_new_cache() {
+    // A new cache may be populated differently than an existing one is updated (see the
+    // bulk-import feature), both must store the same CVEs
+    let mut config = sync_sample_feed("./tests/files/.cache/nvd/populate.sqlite3");
+    let populated = search_id_prefix(&config, "CVE-").expect("Failed searching prefix");
+    let populated_cves = serde_json::to_value(get_all(&config).unwrap()).unwrap();
+    assert_eq!(populated.len(), 6);
+
+    config.force_update = true;
+    let client = feed_file_client("./tests/files/nvdcve-1.1-sample.json");
+    sync_blocking(&config, client).expect("Failed to sync to local cache");
+
+    assert_eq!(search_id_prefix(&config, "CVE-").unwrap(), populated);
+    assert_eq!(
+        serde_json::to_value(get_all(&config).unwrap()).unwrap(),
+        populated_cves
+    );
+
+    let heartbleed = &populated[0];
+    assert_eq!(heartbleed.id, "CVE-2014-0160");
+    assert_eq!(heartbleed.score, Some(5.0));
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_get_all_projections() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/projections.sqlite3");
+
+    let all_ids = get_all_ids(&config).expect("Failed getting all IDs");
+    assert_eq!(all_ids.len(), 6);
+    assert_eq!(all_ids[0], "CVE-2014-0160");
+    assert!(all_ids.windows(2).all(|pair| pair[0] < pair[1]));
+
+    let summaries = get_all_summaries(&config).expect("Failed getting all summaries");
+    assert_eq!(ids(summaries.clone()), all_ids);
+    assert_eq!(
+        summaries,
+        search_id_prefix(&config, "CVE-").expect("Failed searching prefix")
+    );
+
+    let mut cves: Vec<String> = get_all(&config)
+        .expect("Failed getting all CVEs")
+        .into_iter()
+        .map(|cve| cve.cve_data_meta.id)
+        .collect();
+    cves.sort();
+    assert_eq!(cves, all_ids);
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+/// Details of the query plan of the statement ``search()`` runs for ``query``, one line per step
+fn query_plan(config: &CacheConfig, query: &SearchQuery) -> String {
+    explain_search(config, query)
+        .expect("Failed explaining query")
+        .join("\n")
+}
+
+#[test]
+fn test_search_query_plans() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/plans.sqlite3");
+
+    // Listings and text searches only read the covering index, never the JSON data
+    let query = SearchQuery {
+        text: Some("log".to_string()),
+        ..Default::default()
+    };
+    let plan = query_plan(&config, &query);
+    assert!(plan.contains("COVERING INDEX cve_summary"), "{}", plan);
+
+    let query = SearchQuery {
+        severity: Some(Severity::High),
+        min_base_score: Some(8.0),
+        ..Default::default()
+    };
+    let plan = query_plan(&config, &query);
+    assert!(plan.contains("INDEX cve_severity"), "{}", plan);
+
+    let date = |date: &str| {
+        DateTime::parse_from_rfc3339(date)
+            .unwrap()
+            .with_timezone(&Utc)
+    };
+    let query = SearchQuery {
+        published_after: Some(date("2021-01-01T00:00:00Z")),
+        published_before: Some(date("2022-01-01T00:00:00Z")),
+        ..Default::default()
+    };
+    let plan = query_plan(&config, &query);
+    assert!(plan.contains("INDEX cve_published_date"), "{}", plan);
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_reindex() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/reindex.sqlite3");
+
+    // A dropped index is recreated along with all the others being rebuilt
+    let conn = rusqlite::Connection::open(&config.db).expect("Failed opening test cache");
+    conn.execute("DROP INDEX cve_summary", [])
+        .expect("Failed dropping index");
+    conn.close().expect("Failed closing test cache");
+
+    reindex(&config).expect("Failed reindexing cache");
+
+    let plan = query_plan(&config, &SearchQuery::default());
+    assert!(plan.contains("COVERING INDEX cve_summary"), "{}", plan);
+    assert_eq!(get_all_ids(&config).unwrap().len(), 6);
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_cached_metafiles() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/metafiles.sqlite3");
+
+    let metafile = get_metafile(&config, &FeedName::Recent)
+        .expect("Failed getting metafile")
+        .expect("Metafile not cached");
+    assert_eq!(
+        metafile.sha256,
+        "0EA38A9771747DD51A3E009FB8738732144266C4EF4EDC548B70F33555CC1586"
+    );
+    assert_eq!(metafile.size, 1744779);
+    assert_eq!(metafile.gz_size, 116031);
+    assert_eq!(metafile.format_last_modified_date(), "2021-12-18T19:00:00Z");
+
+    // Dates cached by older versions are read as UTC, dates that don't parse fail
+    let conn = rusqlite::Connection::open(&config.db).expect("Failed opening test cache");
+    conn.execute(
+        "UPDATE metafile SET last_modified_date = '2021-12-18T19:00:00' WHERE feed = 'recent'",
+        [],
+    )
+    .unwrap();
+    assert_eq!(
+        get_metafile(&config, &FeedName::Recent).unwrap(),
+        Some(metafile.clone())
+    );
+    conn.execute(
+        "UPDATE metafile SET last_modified_date = 'yesterday' WHERE feed = 'recent'",
+        [],
+    )
+    .unwrap();
+    assert!(get_metafile(&config, &FeedName::Recent).is_err());
+    conn.execute(
+        "UPDATE metafile SET last_modified_date = ?1 WHERE feed = 'recent'",
+        [metafile.format_last_modified_date()],
+    )
+    .unwrap();
+    drop(conn);
+
+    assert!(get_metafile(&config, &FeedName::Year(2021))
+        .expect("Failed getting metafile")
+        .is_none());
+
+    let feeds = get_cached_feeds(&config).expect("Failed getting cached feeds");
+    assert_eq!(feeds.len(), 1);
+    assert_eq!(feeds[0].name, FeedName::Recent);
+    assert_eq!(feeds[0].metafile.as_ref().unwrap().sha256, metafile.sha256);
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_sync_attestations() {
+    let key_path = "./tests/files/.cache/nvd/attestation.key";
+    fs::create_dir_all("./tests/files/.cache/nvd").unwrap();
+    fs::write(key_path, "correct horse battery staple").unwrap();
+
+    let config = CacheConfig {
+        db: "./tests/files/.cache/nvd/attestation.sqlite3".to_string(),
+        url: "http://nowhere.nope".to_string(),
+        feeds: vec![FeedName::Recent],
+        show_progress: false,
+        attestation_key: Some(key_path.to_string()),
+        ..Default::default()
+    };
+    fs::remove_file(&config.db).ok();
+
+    for _ in 0..2 {
+        let client = feed_file_client("./tests/files/nvdcve-1.1-sample.json");
+        sync_blocking(&config, client).expect("Failed to sync to local cache");
+    }
+
+    let attestations = get_attestations(&config).expect("Failed getting attestations");
+    assert_eq!(attestations.len(), 2);
+
+    let (first, second) = (&attestations[0], &attestations[1]);
+    assert_eq!(first.source, "nvd");
+    assert_eq!(first.cves_updated, 6);
+    assert_eq!(first.feeds[0].feed, "recent");
+    assert_eq!(
+        first.feeds[0].sha256,
+        "0EA38A9771747DD51A3E009FB8738732144266C4EF4EDC548B70F33555CC1586"
+    );
+    assert!(first.feeds[0].updated);
+    assert!(!second.feeds[0].updated);
+    assert_eq!(second.cves_updated, 0);
+    assert!(first.started <= first.finished);
+
+    assert!(first.verify(b"correct horse battery staple"));
+    assert!(second.verify(b"correct horse battery staple"));
+    assert!(!first.verify(b"wrong key"));
+
+    let mut tampered = first.clone();
+    tampered.cves_updated += 1;
+    assert!(!tampered.verify(b"correct horse battery staple"));
+
+    // A sync without the key file fails before touching the cache
+    fs::remove_file(key_path).unwrap();
+    let client = feed_file_client("./tests/files/nvdcve-1.1-sample.json");
+    assert!(sync_blocking(&config, client).is_err());
+    assert_eq!(get_attestations(&config).unwrap().len(), 2);
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_read_only_cache() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/read_only.sqlite3");
+    let override_ = CveOverride {
+        affected: Some(false),
+        ..Default::default()
+    };
+    set_override(&config, "CVE-2021-44228", &override_).expect("Failed setting override");
+
+    let frozen = CacheConfig {
+        read_only: true,
+        ..config.clone()
+    };
+
+    // Everything can be read, as the cache was synced
+    assert_eq!(get_all_ids(&frozen).unwrap().len(), 6);
+    assert!(search_by_id(&frozen, "CVE-2021-44228").is_ok());
+    assert!(get_override(&frozen, "CVE-2021-44228").unwrap().is_some());
+    assert_eq!(get_cached_feeds(&frozen).unwrap().len(), 1);
+
+    // Nothing can be written, and a sync fails before fetching anything
+    let client = MockBlockingClient::default();
+    assert!(matches!(
+        sync_blocking(&frozen, client),
+        Err(CacheError::ReadOnly)
+    ));
+    assert!(matches!(
+        set_override(&frozen, "CVE-2021-44228", &override_),
+        Err(CacheError::ReadOnly)
+    ));
+    assert!(matches!(
+        remove_override(&frozen, "CVE-2021-44228"),
+        Err(CacheError::ReadOnly)
+    ));
+    assert!(matches!(reindex(&frozen), Err(CacheError::ReadOnly)));
+    assert!(get_override(&config, "CVE-2021-44228").unwrap().is_some());
+
+    // A read-only cache is never created
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+    assert!(get_all_ids(&frozen).is_err());
+    assert!(!PathBuf::from(&config.db).exists());
+}
+
+#[cfg(feature = "snapshot")]
+#[test]
+fn test_snapshots() {
+    use nvd_cve::snapshot::{install_snapshot, publish_snapshot, SnapshotError};
+
+    let config = sync_sample_feed("./tests/files/.cache/nvd/snapshot.sqlite3");
+    let archive = "./tests/files/.cache/nvd/snapshot.tar.zst";
+
+    let manifest = publish_snapshot(&config, archive).expect("Failed publishing snapshot");
+    assert_eq!(manifest.source, "nvd");
+    assert_eq!(manifest.sha256.len(), 64);
+
+    let installed = CacheConfig {
+        db: "./tests/files/.cache/nvd/snapshot_installed.sqlite3".to_string(),
+        ..config.clone()
+    };
+    fs::remove_file(&installed.db).ok();
+
+    let bytes = fs::read(archive).unwrap();
+    let installed_manifest =
+        install_snapshot(&installed, bytes.as_slice()).expect("Failed installing snapshot");
+    assert_eq!(installed_manifest, manifest);
+    assert_eq!(
+        get_all_ids(&installed).unwrap(),
+        get_all_ids(&config).unwrap()
+    );
+    assert_eq!(get_cached_feeds(&installed).unwrap().len(), 1);
+
+    // A corrupted database is never installed over the cache
+    let mut tar = zstd::decode_all(bytes.as_slice()).unwrap();
+    let middle = tar.len() / 2;
+    tar[middle] ^= 0xff;
+    let corrupted = zstd::encode_all(tar.as_slice(), 0).unwrap();
+    assert!(matches!(
+        install_snapshot(&installed, corrupted.as_slice()),
+        Err(SnapshotError::ChecksumMismatch { .. })
+    ));
+    assert_eq!(get_all_ids(&installed).unwrap().len(), 6);
+
+    let not_a_snapshot = zstd::encode_all(&b"not a tar archive"[..], 0).unwrap();
+    assert!(install_snapshot(&installed, not_a_snapshot.as_slice()).is_err());
+
+    // A read-only cache is never replaced
+    fs::remove_file(&installed.db).expect("Failed removing test cache");
+    let read_only = CacheConfig {
+        read_only: true,
+        ..installed.clone()
+    };
+    assert!(matches!(
+        install_snapshot(&read_only, bytes.as_slice()),
+        Err(SnapshotError::CacheError(CacheError::ReadOnly))
+    ));
+    assert!(!std::path::Path::new(&installed.db).exists());
+    install_snapshot(&installed, bytes.as_slice()).expect("Failed installing snapshot");
+
+    fs::remove_file(archive).expect("Failed removing test snapshot");
+    fs::remove_file(&installed.db).expect("Failed removing test cache");
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[cfg(feature = "snapshot")]
+#[test]
+fn test_snapshot_diffs() {
+    use nvd_cve::snapshot::{
+        apply_diff, install_snapshot, publish_diff, publish_snapshot, SnapshotError,
+    };
+
+    let dir = "./tests/files/.cache/nvd";
+    let base = sync_sample_feed(&format!("{}/diff_base.sqlite3", dir));
+    let target = sync_sample_feed(&format!("{}/diff_target.sqlite3", dir));
+
+    let ids = get_all_ids(&base).unwrap();
+    let conn = rusqlite::Connection::open(&target.db).expect("Failed opening test cache");
+    conn.execute(
+        "UPDATE cve SET description = 'Updated' WHERE id = ?1",
+        [&ids[0]],
+    )
+    .unwrap();
+    conn.execute("DELETE FROM cve WHERE id = ?1", [&ids[1]])
+        .unwrap();
+    conn.close().expect("Failed closing test cache");
+
+    let base_archive = format!("{}/diff_base.tar.zst", dir);
+    let target_archive = format!("{}/diff_target.tar.zst", dir);
+    let diff_archive = format!("{}/diff.tar.zst", dir);
+    let base_manifest = publish_snapshot(&base, &base_archive).unwrap();
+    let target_manifest = publish_snapshot(&target, &target_archive).unwrap();
+    assert_ne!(base_manifest.digest, target_manifest.digest);
+
+    let manifest = publish_diff(&base_archive, &target_archive, &diff_archive)
+        .expect("Failed publishing diff");
+    assert_eq!(manifest.base, base_manifest.digest);
+    assert_eq!(manifest.target, target_manifest.digest);
+    assert_eq!((manifest.upserted, manifest.deleted), (1, 1));
+
+    // An agent holding the base snapshot is updated to the target, keeping its overrides
+    let agent = CacheConfig {
+        db: format!("{}/diff_agent.sqlite3", dir),
+        ..base.clone()
+    };
+    install_snapshot(&agent, fs::File::open(&base_archive).unwrap()).unwrap();
+    let assessment = CveOverride {
+        affected: Some(false),
+        ..Default::default()
+    };
+    set_override(&agent, &ids[0], &assessment).unwrap();
+    assert_eq!(score_cves(&agent, &RiskWeights::default()).unwrap(), 6);
+
+    let diff = fs::read(&diff_archive).unwrap();
+    apply_diff(&agent, diff.as_slice()).expect("Failed applying diff");
+    assert_eq!(get_all_ids(&agent).unwrap(), get_all_ids(&target).unwrap());
+    let summaries = get_all_summaries(&agent).unwrap();
+    assert_eq!(summaries[0].description, "Updated");
+    assert!(get_override(&agent, &ids[0]).unwrap().is_some());
+
+    // Updated CVEs keep their risk scores, those of the deleted CVE are deleted along
+    let scored = search_by_risk(&agent, &SearchQuery::default()).unwrap();
+    assert_eq!(scored.len(), 5);
+    assert!(check(&agent).unwrap().integrity_errors.is_empty());
+    let exploits = SearchQuery {
+        has_public_exploit: true,
+        ..Default::default()
+    };
+    let exploit_ids = |config| -> Vec<String> {
+        let cves = search(config, &exploits).unwrap();
+        cves.into_iter().map(|cve| cve.id).collect()
+    };
+    assert_eq!(exploit_ids(&agent), exploit_ids(&target));
+
+    // The agent no longer holds the base snapshot, so the diff can't be applied again
+    assert!(matches!(
+        apply_diff(&agent, diff.as_slice()),
+        Err(SnapshotError::DigestMismatch { .. })
+    ));
+    assert_eq!(get_all_ids(&agent).unwrap().len(), 5);
+
+    for path in [&base_archive, &target_archive, &diff_archive] {
+        fs::remove_file(path).expect("Failed removing test snapshot");
+    }
+    for config in [&base, &target, &agent] {
+        fs::remove_file(&config.db).expect("Failed removing test cache");
+    }
+}
+
+#[test]
+fn test_check() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/check.sqlite3");
+
+    let health = check(&config).expect("Failed checking cache");
+    assert!(health.is_consistent());
+    assert!(health.is_fresh(Duration::from_secs(60)));
+    assert_eq!(health.feeds, 1);
+    assert_eq!(health.cves, 6);
+    assert!(health.integrity_errors.is_empty());
+
+    // Every sync run is recorded, even one finding all feeds up to date
+    let client = feed_file_client("./tests/files/nvdcve-1.1-sample.json");
+    sync_blocking(&config, client).expect("Failed syncing up to date cache");
+    let runs = get_sync_runs(&config).unwrap();
+    assert_eq!(runs.len(), 2);
+    assert_eq!(runs[0].cves_updated, 6);
+    assert_eq!(runs[1].cves_updated, 0);
+    assert_eq!(get_last_sync_run(&config).unwrap().as_ref(), runs.last());
+
+    // A cache is stale once its last sync is older than the max age
+    let mut health = check(&config).unwrap();
+    health.last_sync.as_mut().unwrap().finished = "2021-12-10T00:00:00+00:00".to_string();
+    assert!(!health.is_fresh(Duration::from_secs(48 * 60 * 60)));
+    health.last_sync = None;
+    assert!(!health.is_fresh(Duration::from_secs(48 * 60 * 60)));
+
+    // A missing cache fails the check and isn't created by it
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+    assert!(check(&config).is_err());
+    assert!(!PathBuf::from(&config.db).exists());
+}
+
+#[test]
+fn test_manual_clock() {
+    let mut config = sync_sample_feed("./tests/files/.cache/nvd/manual_clock.sqlite3");
+    let synced = Utc.with_ymd_and_hms(2021, 12, 18, 20, 0, 0).unwrap();
+    let clock = ManualClock::new(synced);
+    config.clock = Arc::new(clock.clone());
+    config.force_update = true;
+
+    // Sync runs are dated by the clock of the config
+    let client = feed_file_client("./tests/files/nvdcve-1.1-sample.json");
+    sync_blocking(&config, client).expect("Failed syncing cache");
+    let run = get_last_sync_run(&config).unwrap().unwrap();
+    assert_eq!(run.started, synced.to_rfc3339());
+    assert_eq!(run.finished, synced.to_rfc3339());
+
+    // The cache goes stale as the clock moves on, without waiting for it
+    let health = check(&config).unwrap();
+    assert_eq!(health.checked_at, synced);
+    assert_eq!(health.age(), Some(chrono::Duration::zero()));
+    assert!(health.is_fresh(Duration::from_secs(60 * 60)));
+
+    clock.advance(chrono::Duration::hours(2));
+    let health = check(&config).unwrap();
+    assert_eq!(health.age(), Some(chrono::Duration::hours(2)));
+    assert!(!health.is_fresh(Duration::from_secs(60 * 60)));
+    assert!(health.is_fresh(Duration::from_secs(2 * 60 * 60)));
+
+    // A clock set back before the last sync finds the cache fresh
+    clock.set(synced - chrono::Duration::days(1));
+    assert!(check(&config).unwrap().is_fresh(Duration::from_secs(60)));
+
+    // Overrides are dated by it too
+    set_override(&config, "CVE-2021-44228", &CveOverride::default()).unwrap();
+    let updated = get_override(&config, "CVE-2021-44228")
+        .unwrap()
+        .unwrap()
+        .updated;
+    assert_eq!(updated, (synced - chrono::Duration::days(1)).to_rfc3339());
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_sync_history() {
+    let mut config = sync_sample_feed("./tests/files/.cache/nvd/history.sqlite3");
+
+    let ids = get_all_ids(&config).unwrap();
+    let conn = rusqlite::Connection::open(&config.db).expect("Failed opening test cache");
+    conn.execute("UPDATE cve SET data = '{}' WHERE id = ?1", [&ids[0]])
+        .unwrap();
+    conn.execute("DELETE FROM cve WHERE id = ?1", [&ids[1]])
+        .unwrap();
+    conn.close().expect("Failed closing test cache");
+
+    config.force_update = true;
+    let client = feed_file_client("./tests/files/nvdcve-1.1-sample.json");
+    let report = sync_blocking(&config, client).expect("Failed to sync to local cache");
+    let changes = FeedChanges {
+        feed: FeedName::Recent,
+        added: 1,
+        modified: 1,
+        unchanged: 1,
+        skipped: 3,
+    };
+    assert_eq!(report.feed_changes, vec![changes.clone()]);
+    assert_eq!(changes.updated(), report.cves_updated);
+    assert_eq!((report.cves_added, report.cves_modified), (1, 1));
+
+    // The changes of every run are kept, to be compared across runs
+    let runs = get_sync_runs(&config).unwrap();
+    assert_eq!(runs.len(), 2);
+    assert_eq!(runs[0].feeds[0].added, 6);
+    assert_eq!(runs[1].feeds, vec![changes]);
+    assert_eq!(get_last_sync_run(&config).unwrap().as_ref(), runs.last());
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_sync_rescored_cves() {
+    // Only the CVSS v3 score of Log4Shell differs in the modified feed, its data and last
+    // modification date are the same
+    let sample: serde_json::Value =
+        serde_json::from_slice(&fs::read("./tests/files/nvdcve-1.1-sample.json").unwrap()).unwrap();
+    let mut rescored = sample.clone();
+    rescored["CVE_Items"][4]["impact"]["baseMetricV3"]["cvssV3"]["baseScore"] = 9.0.into();
+
+    let metafile = fs::read("./tests/files/nvdcve-1.1-recent.meta").unwrap();
+    let mut files = HashMap::new();
+    for (name, feed) in [(FeedName::Recent, &sample), (FeedName::Modified, &rescored)] {
+        files.insert(name.metafile_filename(), metafile.clone());
+        files.insert(
+            name.feed_filename(),
+            gzip(&serde_json::to_vec(feed).unwrap()),
+        );
+    }
+    let server = MockFeedServer::start(files);
+
+    // A new cache, however it is populated
+    let mut config = CacheConfig {
+        db: "./tests/files/.cache/nvd/rescored.sqlite3".to_string(),
+        feeds: vec![FeedName::Recent, FeedName::Modified],
+        show_progress: false,
+        ..CacheConfig::new()
+    };
+    fs::remove_file(&config.db).ok();
+    let report = sync_blocking(
+        &config,
+        ReqwestBlockingClient::new(&server.url, None, None, None),
+    )
+    .expect("Failed to sync to local cache");
+    assert_eq!(report.feed_changes[1].modified, 1);
+    assert_eq!(report.feed_changes[1].unchanged, 5);
+
+    // An existing cache, re-scored back by the recent feed
+    config.feeds = vec![FeedName::Recent];
+    config.force_update = true;
+    let path = "./tests/files/.cache/nvdcve-1.1-rescored.json";
+    fs::write(path, serde_json::to_vec(&sample).unwrap()).unwrap();
+    let mut client = feed_file_client(path);
+    client.get_metafile_response = Ok(fs::read_to_string("./tests/files/nvdcve-1.1-recent.meta")
+        .unwrap()
+        .replace("2021-12-18", "2024-02-01"));
+    let report = sync_blocking(&config, client).expect("Failed to sync to local cache");
+    assert_eq!(report.feed_changes[0].modified, 1);
+    assert_eq!(report.feed_changes[0].unchanged, 5);
+    assert_eq!(
+        search_id_prefix(&config, "CVE-2021-44228").unwrap()[0].score,
+        Some(10.0)
+    );
+
+    fs::remove_file(path).expect("Failed removing test feed");
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_delete_cves() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/delete.sqlite3");
+    let ids = get_all_ids(&config).unwrap();
+    set_override(&config, &ids[0], &CveOverride::default()).expect("Failed setting override");
+
+    // Stands in for a table derived from the CVEs, such as an index of their products
+    let conn = rusqlite::Connection::open(&config.db).expect("Failed opening test cache");
+    conn.execute_batch(
+        "CREATE TABLE cve_note (id VARCHAR NOT NULL REFERENCES cve (id) ON DELETE CASCADE);
+         INSERT INTO cve_note SELECT id FROM cve;",
+    )
+    .unwrap();
+    conn.close().expect("Failed closing test cache");
+
+    let deleted = delete_cves(&config, &[ids[0].as_str(), "CVE-1999-0000"]).unwrap();
+    assert_eq!(deleted, 1);
+    assert_eq!(get_all_ids(&config).unwrap(), ids[1..]);
+    assert!(get_override(&config, &ids[0]).unwrap().is_some());
+
+    let conn = rusqlite::Connection::open(&config.db).expect("Failed opening test cache");
+    let notes: usize = conn
+        .query_row("SELECT count(*) FROM cve_note", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(notes, ids.len() - 1);
+    assert!(check(&config).unwrap().integrity_errors.is_empty());
+
+    // Writers that don't enforce foreign keys can leave orphans behind
+    conn.pragma_update(None, "foreign_keys", false).unwrap();
+    conn.execute("INSERT INTO cve_note VALUES (?1)", [&ids[0]])
+        .unwrap();
+    conn.close().expect("Failed closing test cache");
+    assert_eq!(check(&config).unwrap().integrity_errors.len(), 1);
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_delete_feed_records() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/delete_feed.sqlite3");
+    let ids = get_all_ids(&config).unwrap();
+
+    assert!(delete_cve(&config, &ids[0]).unwrap());
+    assert!(!delete_cve(&config, &ids[0]).unwrap());
+
+    assert_eq!(
+        delete_feed_records(&config, &FeedName::Year(2021)).unwrap(),
+        0
+    );
+    assert_eq!(
+        delete_feed_records(&config, &FeedName::Recent).unwrap(),
+        ids.len() - 1
+    );
+    assert!(get_all_ids(&config).unwrap().is_empty());
+    assert!(get_metafile(&config, &FeedName::Recent).unwrap().is_none());
+
+    // Without its Metafile the feed is synced again, even though it wasn't updated
+    let client = feed_file_client("./tests/files/nvdcve-1.1-sample.json");
+    let report = sync_blocking(&config, client).expect("Failed to sync to local cache");
+    assert_eq!(report.cves_added, ids.len());
+    assert_eq!(get_all_ids(&config).unwrap(), ids);
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_sync_change_history() {
+    let config = CacheConfig {
+        db: "./tests/files/.cache/nvd/change_history.sqlite3".to_string(),
+        show_progress: false,
+        ..Default::default()
+    };
+    fs::remove_file(&config.db).ok();
+
+    let page = fs::read("./tests/files/cvehistory-sample.json").unwrap();
+    let server = MockFeedServer::start(HashMap::from([("2.0".to_string(), page)]));
+    let client = ReqwestBlockingClient::new(
+        format!("{}rest/json/cvehistory/2.0", server.url),
+        None,
+        None,
+        None,
+    );
+
+    assert!(get_change_events(&config, "CVE-2021-44228")
+        .unwrap()
+        .is_empty());
+    assert_eq!(sync_change_history(&config, &client).unwrap(), 3);
+
+    let events = get_change_events(&config, "CVE-2021-44228").unwrap();
+    let names: Vec<_> = events
+        .iter()
+        .map(|event| event.event_name.as_str())
+        .collect();
+    assert_eq!(names, ["Initial Analysis", "CVE Modified"]);
+    assert_eq!(events[0].details[0].kind, "CVSS V3.1");
+    assert_eq!(events[0].details[0].old_value, None);
+
+    // Events fetched again by the next sync are only cached once
+    sync_change_history(&config, &client).unwrap();
+    assert_eq!(
+        get_change_events(&config, "CVE-2021-44228").unwrap(),
+        events
+    );
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_query_cache() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/query_cache.sqlite3");
+    let cache = QueryCache::with_capacity(config.clone(), 2);
+
+    let all = SearchQuery::default();
+    let remote = SearchQuery {
+        text: Some("remote".to_string()),
+        ..Default::default()
+    };
+    let expected = ids(search(&config, &all).unwrap());
+    assert_eq!(ids(cache.search(&all).unwrap()), expected);
+    assert_eq!(cache.len(), 1);
+
+    // Cached results are returned until the next sync, even if the cache changed since
+    let conn = rusqlite::Connection::open(&config.db).expect("Failed opening test cache");
+    conn.execute("DELETE FROM cve WHERE id = ?1", [&expected[0]])
+        .unwrap();
+    conn.close().expect("Failed closing test cache");
+    assert_eq!(ids(cache.search(&all).unwrap()), expected);
+
+    // The least recently used results are dropped once the capacity is reached
+    cache.search(&remote).unwrap();
+    cache.search(&all).unwrap();
+    cache
+        .search(&SearchQuery {
+            id_prefix: Some("CVE-2021".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(cache.len(), 2);
+    assert_eq!(ids(cache.search(&all).unwrap()), expected);
+
+    cache.invalidate();
+    assert!(cache.is_empty());
+    assert_eq!(cache.search(&all).unwrap().len(), expected.len() - 1);
+
+    // A completed sync drops all the results
+    let mut forced = config.clone();
+    forced.force_update = true;
+    let client = feed_file_client("./tests/files/nvdcve-1.1-sample.json");
+    sync_blocking(&forced, client).expect("Failed to sync to local cache");
+    assert_eq!(ids(cache.search(&all).unwrap()), expected);
+    assert_eq!(cache.len(), 1);
+
+    // So do other writes to the tables searched, such as deleting a CVE or syncing the KEV catalog
+    delete_cve(&config, &expected[0]).unwrap();
+    assert_eq!(ids(cache.search(&all).unwrap()), expected[1..]);
+
+    let known_exploited = SearchQuery {
+        known_exploited: true,
+        ..Default::default()
+    };
+    assert!(cache.search(&known_exploited).unwrap().is_empty());
+    let server = MockFeedServer::start(HashMap::from([(
+        "known_exploited_vulnerabilities.json".to_string(),
+        fs::read("./tests/files/known_exploited_vulnerabilities-sample.json").unwrap(),
+    )]));
+    let client = ReqwestBlockingClient::new(&server.url, None, None, None);
+    sync_kev(&config, &client).unwrap();
+    assert_eq!(
+        ids(cache.search(&known_exploited).unwrap()),
+        vec!["CVE-2021-26855", "CVE-2021-44228"]
+    );
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_stable_ordering() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/ordering.sqlite3");
+
+    // Rows are rewritten in reverse order, so listings can't rely on the order rows were inserted
+    let conn = rusqlite::Connection::open(&config.db).expect("Failed opening test cache");
+    conn.execute_batch(
+        "CREATE TEMP TABLE reversed AS SELECT * FROM cve ORDER BY id DESC;
+         DELETE FROM cve;
+         INSERT INTO cve SELECT * FROM reversed;",
+    )
+    .unwrap();
+    conn.close().expect("Failed closing test cache");
+
+    let sorted = get_all_ids(&config).unwrap();
+    assert!(sorted.windows(2).all(|pair| pair[0] < pair[1]));
+
+    let all: Vec<String> = get_all(&config)
+        .unwrap()
+        .into_iter()
+        .map(|cve| cve.cve_data_meta.id)
+        .collect();
+    assert_eq!(all, sorted);
+
+    let matching = search_description(&config, "a").unwrap();
+    assert!(matching.len() > 1);
+    assert!(matching.windows(2).all(|pair| pair[0].id < pair[1].id));
+
+    let summaries = ids(get_all_summaries(&config).unwrap());
+    assert_eq!(summaries, sorted);
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_sync_pipeline() {
+    let config = CacheConfig {
+        db: "./tests/files/.cache/nvd/pipeline.sqlite3".to_string(),
+        feeds: vec![FeedName::Modified, FeedName::Recent],
+        show_progress: false,
+        ..Default::default()
+    };
+    fs::remove_file(&config.db).ok();
+
+    let metafile = fs::read("./tests/files/nvdcve-1.1-recent.meta").unwrap();
+    let json = fs::read("./tests/files/nvdcve-1.1-sample.json").unwrap();
+
+    let mut files = HashMap::new();
+    for feed in &config.feeds {
+        files.insert(feed.metafile_filename(), metafile.clone());
+        files.insert(feed.feed_filename(), gzip(&json));
+    }
+    let server = MockFeedServer::start(files.clone());
+
+    let client = ReqwestBlockingClient::new(&server.url, None, None, None);
+    let report = sync_blocking(&config, client).expect("Failed to sync to local cache");
+    assert_eq!(report.updated_feeds, config.feeds);
+    assert_eq!(report.cves_updated, 12);
+    assert!(report.timings.decompress > Duration::ZERO);
+    assert!(report.timings.parse > Duration::ZERO);
+    assert!(report.timings.write > Duration::ZERO);
+
+    // A feed that fails to decompress stops the sync, the feeds before it are still written
+    fs::remove_file(&config.db).unwrap();
+    files.insert(FeedName::Recent.feed_filename(), b"not gzipped".to_vec());
+    let server = MockFeedServer::start(files);
+
+    let client = ReqwestBlockingClient::new(&server.url, None, None, None);
+    assert!(matches!(
+        sync_blocking(&config, client),
+        Err(CacheError::HttpError(_))
+    ));
+
+    let feeds = get_cached_feeds(&config).expect("Failed getting cached feeds");
+    assert_eq!(
+        feeds.iter().map(|feed| feed.name).collect::<Vec<_>>(),
+        vec![FeedName::Modified]
+    );
+    assert!(get_sync_runs(&config).unwrap().is_empty());
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_sync_unexpected_content() {
+    let config = CacheConfig {
+        db: "./tests/files/.cache/nvd/unexpected_content.sqlite3".to_string(),
+        feeds: vec![FeedName::Recent],
+        show_progress: false,
+        ..Default::default()
+    };
+    fs::remove_file(&config.db).ok();
+
+    // A captive portal answering every request with its login page
+    let page =
+        b"\n  <!DOCTYPE html>\n<html>\n  <head><title>Sign in to Guest Wi-Fi</title></head>\n  \
+        <body><form method=\"post\"></form></body>\n</html>\n";
+    let server = MockFeedServer::start(HashMap::from([
+        (FeedName::Recent.metafile_filename(), page.to_vec()),
+        (FeedName::Recent.feed_filename(), page.to_vec()),
+    ]));
+    let preview = "<!DOCTYPE html> <html> <head><title>Sign in to Guest Wi-Fi</ti";
+
+    let client = ReqwestBlockingClient::new(&server.url, None, None, None);
+    for result in [
+        client.get_metafile(&FeedName::Recent).map(|_| ()),
+        client.get_feed(&FeedName::Recent).map(|_| ()),
+        client.fetch_feed(&FeedName::Recent).map(|_| ()),
+    ] {
+        match result {
+            Err(HttpError::UnexpectedContent(content)) => assert_eq!(content, preview),
+            other => panic!("Expected unexpected content, got {:?}", other.err()),
+        }
+    }
+
+    let client = ReqwestAsyncClient::new(&server.url, None, None, None);
+    assert!(matches!(
+        block_on(client.get_feed(&FeedName::Recent)),
+        Err(HttpError::UnexpectedContent(_))
+    ));
+
+    let client = ReqwestBlockingClient::new(&server.url, None, None, None);
+    let error = sync_blocking(&config, client).unwrap_err();
+    assert!(
+        format!("{:?}", error).contains("Sign in to Guest Wi-Fi"),
+        "{:?}",
+        error
+    );
+
+    fs::remove_file(&config.db).ok();
+}
+
+#[test]
+fn test_redirects() {
+    let server = MockFeedServer::with_recent_feed("./tests/files/nvdcve-1.1-sample.json");
+    let port = server.url.rsplit(':').next().unwrap();
+    let moved = MockFeedServer::start_redirecting(Some(format!("http://localhost:{}", port)));
+
+    // Redirects are followed to the allowed hosts only
+    let client = ReqwestBlockingClient::new(&moved.url, None, None, None);
+    assert!(client.get_metafile(&FeedName::Recent).is_ok());
+    let client = client.with_allowed_hosts(&["127.0.0.1", "LOCALHOST"]);
+    assert!(client.get_metafile(&FeedName::Recent).is_ok());
+
+    let client = client.with_allowed_hosts(&["127.0.0.1"]);
+    assert!(matches!(
+        client.get_metafile(&FeedName::Recent),
+        Err(HttpError::HostNotAllowed(host)) if host == "localhost"
+    ));
+    assert!(matches!(
+        client.fetch_feed(&FeedName::Recent),
+        Err(HttpError::HostNotAllowed(host)) if host == "localhost"
+    ));
+
+    // Requests to other hosts fail before connecting
+    let client = ReqwestBlockingClient::new(&server.url, None, None, None)
+        .with_allowed_hosts(&["mirror.example.com"]);
+    assert!(matches!(
+        client.get_metafile(&FeedName::Recent),
+        Err(HttpError::HostNotAllowed(host)) if host == "127.0.0.1"
+    ));
+
+    // Redirects are followed up to the limit
+    let client = ReqwestBlockingClient::new(&moved.url, None, None, None).with_max_redirects(0);
+    assert!(matches!(
+        client.get_metafile(&FeedName::Recent),
+        Err(HttpError::TooManyRedirects(0))
+    ));
+    let looping = MockFeedServer::start_redirecting(None);
+    let client = ReqwestBlockingClient::new(&looping.url, None, None, None).with_max_redirects(3);
+    assert!(matches!(
+        client.get_metafile(&FeedName::Recent),
+        Err(HttpError::TooManyRedirects(3))
+    ));
+
+    let client =
+        ReqwestAsyncClient::new(&moved.url, None, None, None).with_allowed_hosts(&["127.0.0.1"]);
+    assert!(matches!(
+        block_on(client.get_metafile(&FeedName::Recent)),
+        Err(HttpError::HostNotAllowed(host)) if host == "localhost"
+    ));
+    let client = ReqwestAsyncClient::new(&looping.url, None, None, None);
+    assert!(matches!(
+        block_on(client.get_metafile(&FeedName::Recent)),
+        Err(HttpError::TooManyRedirects(10))
+    ));
+
+    // Syncs apply the limits of their config to their client
+    let mut config = CacheConfig {
+        db: "./tests/files/.cache/nvd/redirects.sqlite3".to_string(),
+        feeds: vec![FeedName::Recent],
+        show_progress: false,
+        allowed_hosts: vec!["127.0.0.1".to_string()],
+        ..CacheConfig::new()
+    };
+    fs::remove_file(&config.db).ok();
+    assert!(matches!(
+        sync_blocking(&config, ReqwestBlockingClient::new(&moved.url, None, None, None)),
+        Err(CacheError::MetafileError(MetafileError::FetchError(HttpError::HostNotAllowed(host))))
+            if host == "localhost"
+    ));
+    config.allowed_hosts = vec![];
+    config.max_redirects = 0;
+    assert!(matches!(
+        block_on(sync_async(
+            &config,
+            ReqwestAsyncClient::new(&moved.url, None, None, None)
+        )),
+        Err(CacheError::HttpError(HttpError::TooManyRedirects(0)))
+    ));
+    config.max_redirects = 1;
+    sync_blocking(
+        &config,
+        ReqwestBlockingClient::new(&moved.url, None, None, None),
+    )
+    .expect("Failed syncing through a redirect");
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+/// Events of the ``nvd_cve::client`` target formatted as text, as by ``--debug-http``
+#[derive(Clone, Default)]
+struct ClientEvents(Arc<Mutex<Vec<u8>>>);
+
+impl Write for ClientEvents {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_client_events() {
+    let server = MockFeedServer::with_recent_feed("./tests/files/nvdcve-1.1-sample.json");
+    let metafile = fs::read_to_string("./tests/files/nvdcve-1.1-recent.meta").unwrap();
+    let base_url = server.url.replace("http://", "http://mirror:s3cret@");
+
+    let events = ClientEvents::default();
+    let writer = events.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::DEBUG)
+        .with_ansi(false)
+        .with_writer(move || writer.clone())
+        .finish();
+    tracing::subscriber::with_default(subscriber, || {
+        ReqwestBlockingClient::new(&base_url, None, None, None)
+            .get_metafile(&FeedName::Recent)
+            .expect("Failed fetching metafile");
+    });
+    let events = String::from_utf8(events.0.lock().unwrap().clone()).unwrap();
+
+    // The URL is logged without credentials, along with the status and size but not the body
+    let url = format!("{}{}", server.url, FeedName::Recent.metafile_filename());
+    assert!(events.contains(&format!("url={}\n", url)), "{}", events);
+    assert!(
+        !events.contains("mirror") && !events.contains("s3cret"),
+        "{}",
+        events
+    );
+    assert!(events.contains("nvd_cve::client"), "{}", events);
+    assert!(events.contains("status=200"), "{}", events);
+    assert!(
+        events.contains(&format!("bytes={}", metafile.len())),
+        "{}",
+        events
+    );
+    assert!(!events.contains("sha256"), "{}", events);
+}
+
+#[test]
+fn test_sync_trace() {
+    let mut config = CacheConfig {
+        db: "./tests/files/.cache/nvd/trace.sqlite3".to_string(),
+        feeds: vec![FeedName::Recent],
+        show_progress: false,
+        ..Default::default()
+    };
+    fs::remove_file(&config.db).ok();
+
+    // Feed timings are only kept when asked for
+    let server = MockFeedServer::with_recent_feed("./tests/files/nvdcve-1.1-sample.json");
+    let client = ReqwestBlockingClient::new(&server.url, None, None, None);
+    let report = sync_blocking(&config, client).expect("Failed to sync to local cache");
+    assert!(report.feed_timings.is_empty());
+
+    fs::remove_file(&config.db).unwrap();
+    config.trace_feeds = true;
+    let client = ReqwestBlockingClient::new(&server.url, None, None, None);
+    let report = sync_blocking(&config, client).expect("Failed to sync to local cache");
+    assert_eq!(report.feed_timings.len(), 1);
+    let timings = &report.feed_timings[0];
+    assert_eq!(timings.feed, FeedName::Recent);
+    let download = timings.download.expect("No download span");
+    let decompress = timings.decompress.expect("No decompress span");
+    let parse = timings.parse.expect("No parse span");
+    let write = timings.write.expect("No write span");
+    assert!(download.start + download.duration <= decompress.start);
+    assert!(decompress.start + decompress.duration <= parse.start);
+    assert!(parse.start + parse.duration <= write.start);
+
+    // Feeds already up to date were only downloaded
+    config.trace_file = Some("./tests/files/.cache/nvd/trace.json".to_string());
+    let client = ReqwestBlockingClient::new(&server.url, None, None, None);
+    let report = sync_blocking(&config, client).expect("Failed to sync to local cache");
+    assert_eq!(report.skipped_feeds, vec![FeedName::Recent]);
+    let timings = &report.feed_timings[0];
+    assert!(timings.download.is_some());
+    assert!(timings.decompress.is_none() && timings.parse.is_none() && timings.write.is_none());
+
+    let trace: serde_json::Value =
+        serde_json::from_slice(&fs::read("./tests/files/.cache/nvd/trace.json").unwrap())
+            .expect("Trace isn't JSON");
+    let events = trace["traceEvents"].as_array().unwrap();
+    let spans: Vec<_> = events.iter().filter(|event| event["ph"] == "X").collect();
+    assert_eq!(spans.len(), 1);
+    assert_eq!(spans[0]["name"], "recent");
+    assert_eq!(spans[0]["cat"], "download");
+    fs::remove_file("./tests/files/.cache/nvd/trace.json").unwrap();
+
+    // ... or as folded stacks
+    fs::remove_file(&config.db).unwrap();
+    config.trace_feeds = false;
+    config.trace_file = Some("./tests/files/.cache/nvd/trace.folded".to_string());
+    let client = ReqwestBlockingClient::new(&server.url, None, None, None);
+    sync_blocking(&config, client).expect("Failed to sync to local cache");
+    let folded = fs::read_to_string("./tests/files/.cache/nvd/trace.folded").unwrap();
+    let stacks: Vec<_> = folded
+        .lines()
+        .map(|line| line.rsplit_once(' ').unwrap().0)
+        .collect();
+    assert_eq!(
+        stacks,
+        vec![
+            "sync;recent;download",
+            "sync;recent;decompress",
+            "sync;recent;parse",
+            "sync;recent;write"
+        ]
+    );
+    fs::remove_file("./tests/files/.cache/nvd/trace.folded").unwrap();
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_sync_timeouts() {
+    let mut config = CacheConfig {
+        db: "./tests/files/.cache/nvd/timeouts.sqlite3".to_string(),
+        feeds: vec![FeedName::Year(2023), FeedName::Recent],
+        show_progress: false,
+        feed_timeout: Some(Duration::from_millis(500)),
+        on_timeout: TimeoutPolicy::Continue,
+        ..Default::default()
+    };
+    fs::remove_file(&config.db).ok();
+
+    let metafile = fs::read("./tests/files/nvdcve-1.1-recent.meta").unwrap();
+    let json = fs::read("./tests/files/nvdcve-1.1-sample.json").unwrap();
+
+    let mut files = HashMap::new();
+    for feed in &config.feeds {
+        files.insert(feed.metafile_filename(), metafile.clone());
+        files.insert(feed.feed_filename(), gzip(&json));
+    }
+    // The download of the 2023 feed hangs
+    let server = MockFeedServer::start_stalling(files, &[FeedName::Year(2023).feed_filename()]);
+
+    // The hung feed is skipped and the sync goes on with the next one
+    let client = ReqwestBlockingClient::new(&server.url, None, None, None);
+    let report = sync_blocking(&config, client).expect("Failed to sync to local cache");
+    assert_eq!(report.timed_out_feeds, vec![FeedName::Year(2023)]);
+    assert_eq!(report.updated_feeds, vec![FeedName::Recent]);
+
+    // ... or fails the sync
+    fs::remove_file(&config.db).unwrap();
+    config.on_timeout = TimeoutPolicy::Abort;
+    let client = ReqwestBlockingClient::new(&server.url, None, None, None);
+    assert!(matches!(
+        sync_blocking(&config, client),
+        Err(CacheError::TimedOut(FeedName::Year(2023)))
+    ));
+    assert!(get_cached_feeds(&config).unwrap().is_empty());
+
+    // Feeds left once the deadline passed time out without being fetched
+    fs::remove_file(&config.db).unwrap();
+    config.feed_timeout = None;
+    config.sync_deadline = Some(Duration::from_millis(500));
+    config.on_timeout = TimeoutPolicy::Continue;
+    let client = ReqwestBlockingClient::new(&server.url, None, None, None);
+    let start = std::time::Instant::now();
+    let report = sync_blocking(&config, client).expect("Failed to sync to local cache");
+    assert!(start.elapsed() < Duration::from_secs(10));
+    assert_eq!(report.timed_out_feeds, config.feeds);
+    assert!(report.updated_feeds.is_empty());
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+/// Run ``future`` to completion on a new Tokio runtime
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("Failed building Tokio runtime")
+        .block_on(future)
+}
+
+#[test]
+fn test_sync_async() {
+    let mut config = CacheConfig {
+        db: "./tests/files/.cache/nvd/async.sqlite3".to_string(),
+        feeds: vec![FeedName::Modified, FeedName::Recent],
+        show_progress: false,
+        ..Default::default()
+    };
+    fs::remove_file(&config.db).ok();
+
+    let metafile = fs::read("./tests/files/nvdcve-1.1-recent.meta").unwrap();
+    let json = fs::read("./tests/files/nvdcve-1.1-sample.json").unwrap();
+
+    let mut files = HashMap::new();
+    for feed in [FeedName::Modified, FeedName::Recent, FeedName::Year(2023)] {
+        files.insert(feed.metafile_filename(), metafile.clone());
+        files.insert(feed.feed_filename(), gzip(&json));
+    }
+    let server = MockFeedServer::start_stalling(files, &[FeedName::Year(2023).feed_filename()]);
+
+    // The sync can run on a task of a multi-threaded runtime
+    fn assert_send<T: Send>(_: &T) {}
+    let client = ReqwestAsyncClient::new(&server.url, None, None, None);
+    assert_send(&sync_async(&config, client));
+
+    let client = ReqwestAsyncClient::new(&server.url, None, None, None);
+    let report = block_on(sync_async(&config, client)).expect("Failed to sync to local cache");
+    assert_eq!(report.updated_feeds, config.feeds);
+    assert_eq!(report.cves_updated, 12);
+    assert!(report.timings.download > Duration::ZERO);
+    assert!(report.timings.write > Duration::ZERO);
+    assert!(search_by_id(&config, "CVE-2021-44228").is_ok());
+    assert_eq!(get_sync_runs(&config).unwrap().len(), 1);
+
+    // Feeds already cached are skipped
+    let client = ReqwestAsyncClient::new(&server.url, None, None, None);
+    let report = block_on(sync_async(&config, client)).expect("Failed to sync to local cache");
+    assert_eq!(report.skipped_feeds, config.feeds);
+    assert_eq!(report.cves_updated, 0);
+
+    // A hung feed times out as with the blocking sync
+    config.feeds = vec![FeedName::Year(2023)];
+    config.feed_timeout = Some(Duration::from_millis(500));
+    config.on_timeout = TimeoutPolicy::Continue;
+    let client = ReqwestAsyncClient::new(&server.url, None, None, None);
+    let report = block_on(sync_async(&config, client)).expect("Failed to sync to local cache");
+    assert_eq!(report.timed_out_feeds, vec![FeedName::Year(2023)]);
+
+    config.on_timeout = TimeoutPolicy::Abort;
+    let client = ReqwestAsyncClient::new(&server.url, None, None, None);
+    assert!(matches!(
+        block_on(sync_async(&config, client)),
+        Err(CacheError::TimedOut(FeedName::Year(2023)))
+    ));
+
+    config.read_only = true;
+    let client = ReqwestAsyncClient::new(&server.url, None, None, None);
+    assert!(matches!(
+        block_on(sync_async(&config, client)),
+        Err(CacheError::ReadOnly)
+    ));
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_by_cpe() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/search_by_cpe.sqlite3");
+    let ids = |cpe: &str| -> Vec<String> {
+        search_by_cpe(&config, &cpe.parse().unwrap(), VersionScheme::Auto)
+            .expect("Failed searching by CPE")
+            .into_iter()
+            .map(|applicability| applicability.id)
+            .collect()
+    };
+
+    assert_eq!(
+        ids("cpe:2.3:a:openssl:openssl:1.1.1k:*:*:*:*:*:*:*"),
+        vec!["CVE-2021-3711"]
+    );
+    assert_eq!(
+        ids("cpe:2.3:a:openssl:openssl:1.0.1f:*:*:*:*:*:*:*"),
+        vec!["CVE-2014-0160"]
+    );
+    assert!(ids("cpe:2.3:a:openssl:openssl:1.1.1l:*:*:*:*:*:*:*").is_empty());
+    assert_eq!(
+        ids("cpe:2.3:a:apache:log4j:2.14.1:*:*:*:*:*:*:*"),
+        vec!["CVE-2021-44228"]
+    );
+
+    // Caches synced before the index existed are indexed when next opened for writing
+    let conn = rusqlite::Connection::open(&config.db).unwrap();
+    conn.execute("DROP TABLE cpe_match", []).unwrap();
+    conn.close().unwrap();
+    reindex(&config).expect("Failed migrating the cache");
+    let conn = rusqlite::Connection::open(&config.db).unwrap();
+    let rows: i64 = conn
+        .query_row("SELECT COUNT(*) FROM cpe_match", [], |row| row.get(0))
+        .unwrap();
+    assert!(rows > 0);
+    conn.close().unwrap();
+    assert_eq!(
+        ids("cpe:2.3:a:openssl:openssl:1.1.1k:*:*:*:*:*:*:*"),
+        vec!["CVE-2021-3711"]
+    );
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_cpe_match_feed() {
+    let mut config = sync_sample_feed("./tests/files/.cache/nvd/cpe_match_feed.sqlite3");
+    let confidences = |config: &CacheConfig, cpe: &str| -> Vec<(String, MatchConfidence)> {
+        search_by_cpe(config, &cpe.parse().unwrap(), VersionScheme::Auto)
+            .expect("Failed searching by CPE")
+            .into_iter()
+            .map(|applicability| {
+                let confidence = applicability.confidence().unwrap();
+                (applicability.id, confidence)
+            })
+            .collect()
+    };
+    let openssl_1_1_1k = "cpe:2.3:a:openssl:openssl:1.1.1k:*:*:*:*:*:*:*";
+
+    assert_eq!(
+        confidences(&config, openssl_1_1_1k),
+        vec![("CVE-2021-3711".to_string(), MatchConfidence::VersionRange)]
+    );
+
+    let server = MockFeedServer::start(HashMap::from([
+        (
+            "nvdcpematch-1.0.meta".to_string(),
+            fs::read("./tests/files/nvdcpematch-1.0.meta").unwrap(),
+        ),
+        (
+            "nvdcpematch-1.0.json.gz".to_string(),
+            gzip(&fs::read("./tests/files/nvdcpematch-1.0-sample.json").unwrap()),
+        ),
+    ]));
+    let client = ReqwestBlockingClient::new(&server.url, None, None, None);
+    assert_eq!(sync_cpe_matches(&config, &client).unwrap(), 3);
+    // The feed is only fetched again once its Metafile changed
+    assert_eq!(sync_cpe_matches(&config, &client).unwrap(), 0);
+    config.force_update = true;
+    assert_eq!(sync_cpe_matches(&config, &client).unwrap(), 3);
+
+    // Criteria expanding to CPE names match the platforms named exactly, versions in their range
+    // without a name of their own don't match
+    assert_eq!(
+        confidences(&config, openssl_1_1_1k),
+        vec![("CVE-2021-3711".to_string(), MatchConfidence::Exact)]
+    );
+    assert!(confidences(
+        &config,
+        "cpe:2.3:a:openssl:openssl:1.1.1k-dev:*:*:*:*:*:*:*"
+    )
+    .is_empty());
+
+    // Criteria without any CPE name fall back to their version range
+    assert_eq!(
+        confidences(&config, "cpe:2.3:a:openssl:openssl:1.0.1f:*:*:*:*:*:*:*"),
+        vec![("CVE-2014-0160".to_string(), MatchConfidence::VersionRange)]
+    );
+
+    // Importing a feed replaces the criteria of the previous one
+    assert_eq!(
+        import_cpe_matches(&config, &CpeMatchFeed::default()).unwrap(),
+        0
+    );
+    assert_eq!(
+        confidences(&config, openssl_1_1_1k),
+        vec![("CVE-2021-3711".to_string(), MatchConfidence::VersionRange)]
+    );
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_kev() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/kev.sqlite3");
+    let query = SearchQuery {
+        known_exploited: true,
+        ..Default::default()
+    };
+    let ids = |cves: Vec<CveSummary>| cves.into_iter().map(|cve| cve.id).collect::<Vec<_>>();
+
+    // No CVE is known exploited until the catalog is synced
+    assert!(search(&config, &query).unwrap().is_empty());
+    assert!(search(&config, &SearchQuery::default())
+        .unwrap()
+        .iter()
+        .all(|cve| !cve.known_exploited));
+
+    let server = MockFeedServer::start(HashMap::from([(
+        "known_exploited_vulnerabilities.json".to_string(),
+        fs::read("./tests/files/known_exploited_vulnerabilities-sample.json").unwrap(),
+    )]));
+    let client = ReqwestBlockingClient::new(&server.url, None, None, None);
+    assert_eq!(sync_kev(&config, &client).unwrap(), 3);
+
+    // CVEs of the catalog that aren't cached are left out
+    assert_eq!(
+        ids(search(&config, &query).unwrap()),
+        vec!["CVE-2021-26855", "CVE-2021-44228"]
+    );
+    let query = SearchQuery {
+        known_exploited: true,
+        text: Some("log4j".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(
+        ids(search(&config, &query).unwrap()),
+        vec!["CVE-2021-44228"]
+    );
+
+    let flagged: Vec<_> = search_description(&config, "")
+        .unwrap()
+        .into_iter()
+        .filter(|cve| cve.known_exploited)
+        .collect();
+    assert_eq!(ids(flagged), vec!["CVE-2021-26855", "CVE-2021-44228"]);
+
+    let kev = get_known_exploited(&config, "CVE-2021-44228")
+        .unwrap()
+        .expect("CVE-2021-44228 isn't known exploited");
+    assert_eq!(kev.product, "Log4j2");
+    assert_eq!(kev.due_date, "2021-12-24");
+    assert_eq!(kev.known_ransomware_campaign_use, "Known");
+    assert!(get_known_exploited(&config, "CVE-2014-0160")
+        .unwrap()
+        .is_none());
+
+    // Risk scores account for the catalog once it is synced
+    let weights = RiskWeights {
+        cvss: 0.0,
+        known_exploited: 5.0,
+        ..Default::default()
+    };
+    score_cves(&config, &weights).unwrap();
+    let scored: Vec<_> = search_by_risk(&config, &SearchQuery::default())
+        .unwrap()
+        .into_iter()
+        .filter(|cve| cve.risk_score == 5.0)
+        .map(|cve| cve.summary.id)
+        .collect();
+    assert_eq!(scored, vec!["CVE-2021-26855", "CVE-2021-44228"]);
+
+    // Syncing the catalog again replaces it
+    assert_eq!(import_kev(&config, &KevCatalog::default()).unwrap(), 0);
+    assert!(search(&config, &query).unwrap().is_empty());
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_epss() {
+    let mut config = sync_sample_feed("./tests/files/.cache/nvd/epss.sqlite3");
+    let query = SearchQuery {
+        min_epss: Some(0.5),
+        ..Default::default()
+    };
+    let ids = |cves: Vec<CveSummary>| cves.into_iter().map(|cve| cve.id).collect::<Vec<_>>();
+
+    // No CVE is scored until the scores are synced
+    assert!(search(&config, &query).unwrap().is_empty());
+    assert!(get_epss(&config, "CVE-2021-44228").unwrap().is_none());
+
+    let csv = fs::read("./tests/files/epss_scores-sample.csv").unwrap();
+    let server = MockFeedServer::start(HashMap::from([(
+        "epss_scores-current.csv.gz".to_string(),
+        gzip(&csv),
+    )]));
+    let client = ReqwestBlockingClient::new(&server.url, None, None, None);
+    assert_eq!(sync_epss(&config, &client).unwrap(), 6);
+
+    // Scores computed on the same day aren't written again
+    assert_eq!(sync_epss(&config, &client).unwrap(), 0);
+    config.force_update = true;
+    assert_eq!(sync_epss(&config, &client).unwrap(), 6);
+
+    // CVEs scored that aren't cached are left out
+    assert_eq!(
+        ids(search(&config, &query).unwrap()),
+        vec!["CVE-2014-0160", "CVE-2021-26855", "CVE-2021-44228"]
+    );
+
+    let summaries = search_description(&config, "").unwrap();
+    let log4shell = summaries
+        .iter()
+        .find(|cve| cve.id == "CVE-2021-44228")
+        .unwrap();
+    assert_eq!(log4shell.epss, Some(0.97565));
+    let follina = summaries
+        .iter()
+        .find(|cve| cve.id == "CVE-2022-30190")
+        .unwrap();
+    assert_eq!(follina.epss, None);
+
+    let score = get_epss(&config, "CVE-2021-3711")
+        .unwrap()
+        .expect("CVE-2021-3711 isn't scored");
+    assert_eq!(score.epss, 0.02386);
+    assert_eq!(score.percentile, 0.89914);
+    assert!(get_epss(&config, "CVE-2022-30190").unwrap().is_none());
+
+    // Risk scores account for the EPSS scores once they are synced
+    let weights = RiskWeights {
+        cvss: 0.0,
+        epss: 10.0,
+        ..Default::default()
+    };
+    score_cves(&config, &weights).unwrap();
+    let scored = search_by_risk(&config, &SearchQuery::default()).unwrap();
+    assert_eq!(scored[0].summary.id, "CVE-2021-44228");
+    assert_eq!(scored[0].risk_score, 9.7565);
+
+    let scores = EpssScores::from_csv(&csv).unwrap();
+    assert_eq!(scores.model_version.as_deref(), Some("v2023.03.01"));
+    assert_eq!(
+        scores.score_date.as_deref(),
+        Some("2024-06-12T00:00:00+0000")
+    );
+    assert!(matches!(
+        EpssScores::from_csv(b"cve,epss,percentile\nCVE-2021-44228,high,0.99996\n"),
+        Err(EpssError::InvalidLine(2))
+    ));
+
+    // Syncing the scores again replaces them
+    assert_eq!(import_epss(&config, &EpssScores::default()).unwrap(), 0);
+    assert!(search(&config, &query).unwrap().is_empty());
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_by_date() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/date.sqlite3");
+    let ids = |cves: Vec<Cve>| {
+        cves.into_iter()
+            .map(|cve| cve.cve_data_meta.id)
+            .collect::<Vec<_>>()
+    };
+    let date = |datetime: &str| Some(datetime.parse::<DateTime<Utc>>().unwrap());
+
+    let published = search_published_between(&config, date("2021-01-01T00:00:00Z"), None)
+        .expect("Failed searching by publication date");
+    assert_eq!(
+        ids(published),
+        vec![
+            "CVE-2021-26855",
+            "CVE-2021-3711",
+            "CVE-2021-44228",
+            "CVE-2022-30190"
+        ]
+    );
+
+    // Ranges include their start and exclude their end, to the second of the minutes of feeds
+    let published = search_published_between(
+        &config,
+        date("2021-03-03T00:15:00Z"),
+        date("2021-12-10T10:15:00Z"),
+    )
+    .unwrap();
+    assert_eq!(ids(published), vec!["CVE-2021-26855", "CVE-2021-3711"]);
+    let published = search_published_between(
+        &config,
+        date("2021-03-03T00:15:01Z"),
+        date("2021-12-10T10:15:01Z"),
+    )
+    .unwrap();
+    assert_eq!(ids(published), vec!["CVE-2021-3711", "CVE-2021-44228"]);
+
+    // Bounds with an offset are compared in UTC
+    let modified =
+        search_modified_between(&config, None, date("2021-01-01T01:00:00+01:00")).unwrap();
+    assert_eq!(ids(modified), vec!["CVE-2014-0160", "CVE-2019-12780"]);
+    assert!(
+        search_modified_between(&config, date("2023-01-01T00:00:00Z"), None)
+            .unwrap()
+            .is_empty()
+    );
+
+    let query = SearchQuery {
+        published_before: date("2022-01-01T00:00:00Z"),
+        modified_after: date("2022-01-01T00:00:00Z"),
+        ..Default::default()
+    };
+    let cached: Vec<_> = search(&config, &query)
+        .unwrap()
+        .into_iter()
+        .map(|cve| cve.id)
+        .collect();
+    assert_eq!(cached, vec!["CVE-2021-3711", "CVE-2021-44228"]);
+
+    // Feeds are matched alike without being cached
+    let body = fs::read_to_string("./tests/files/nvdcve-1.1-sample.json").unwrap();
+    let cve_feed: CveFeed = serde_json::from_str(&body).unwrap();
+    let matched: Vec<_> = cve_feed
+        .cve_items
+        .iter()
+        .filter(|cve| query.matches(cve))
+        .map(|cve| cve.cve.cve_data_meta.id.clone())
+        .collect();
+    assert_eq!(matched, cached);
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_by_assigner() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/assigner.sqlite3");
+    let ids = |cves: Vec<CveSummary>| cves.into_iter().map(|cve| cve.id).collect::<Vec<_>>();
+
+    let apache =
+        search_by_assigner(&config, "security@apache.org").expect("Failed searching by assigner");
+    assert_eq!(ids(apache), vec!["CVE-2021-44228"]);
+
+    // Assigners are matched whole, ignoring case
+    assert_eq!(
+        ids(search_by_assigner(&config, " Secure@Microsoft.com").unwrap()),
+        vec!["CVE-2021-26855", "CVE-2022-30190"]
+    );
+    assert!(search_by_assigner(&config, "microsoft.com")
+        .unwrap()
+        .is_empty());
+
+    // Feeds are matched alike without being cached
+    let body = fs::read_to_string("./tests/files/nvdcve-1.1-sample.json").unwrap();
+    let cve_feed: CveFeed = serde_json::from_str(&body).unwrap();
+    for assigner in ["cve@mitre.org", "SECALERT@redhat.com", "redhat.com"] {
+        let query = SearchQuery {
+            assigner: Some(assigner.to_string()),
+            ..Default::default()
+        };
+        let matched: Vec<_> = cve_feed
+            .cve_items
+            .iter()
+            .filter(|cve| query.matches(cve))
+            .map(|cve| cve.cve.cve_data_meta.id.clone())
+            .collect();
+        assert_eq!(
+            matched,
+            ids(search(&config, &query).unwrap()),
+            "{}",
+            assigner
+        );
+    }
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_by_product() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/product.sqlite3");
+    let ids = |cves: Vec<CveSummary>| cves.into_iter().map(|cve| cve.id).collect::<Vec<_>>();
+
+    let exchange = search_by_product(&config, Some("microsoft"), "exchange_server")
+        .expect("Failed searching by product");
+    assert_eq!(ids(exchange), vec!["CVE-2021-26855"]);
+
+    // Names are matched whole, ignoring case
+    assert_eq!(
+        ids(search_by_product(&config, None, "OpenSSL").unwrap()),
+        vec!["CVE-2014-0160", "CVE-2021-3711"]
+    );
+    assert!(search_by_product(&config, None, "exchange")
+        .unwrap()
+        .is_empty());
+    assert!(search_by_product(&config, Some("apache"), "openssl")
+        .unwrap()
+        .is_empty());
+
+    // The vendor and the product must be named by the same CPE
+    let query = SearchQuery {
+        vendor: Some("microsoft".to_string()),
+        ..Default::default()
+    };
+    let cached: Vec<_> = ids(search(&config, &query).unwrap());
+    assert_eq!(cached, vec!["CVE-2021-26855", "CVE-2022-30190"]);
+    let query = SearchQuery {
+        vendor: Some("belkin".to_string()),
+        product: Some("exchange_server".to_string()),
+        ..Default::default()
+    };
+    assert!(search(&config, &query).unwrap().is_empty());
+
+    // Feeds are matched alike without being cached
+    let body = fs::read_to_string("./tests/files/nvdcve-1.1-sample.json").unwrap();
+    let cve_feed: CveFeed = serde_json::from_str(&body).unwrap();
+    for (vendor, product) in [
+        (Some("microsoft"), None),
+        (None, Some("OPENSSL")),
+        (Some("belkin"), Some("wemo_crock-pot")),
+        (Some("belkin"), Some("exchange_server")),
+    ] {
+        let query = SearchQuery {
+            vendor: vendor.map(str::to_string),
+            product: product.map(str::to_string),
+            ..Default::default()
+        };
+        let matched: Vec<_> = cve_feed
+            .cve_items
+            .iter()
+            .filter(|cve| query.matches(cve))
+            .map(|cve| cve.cve.cve_data_meta.id.clone())
+            .collect();
+        assert_eq!(matched, ids(search(&config, &query).unwrap()));
+    }
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_by_reference() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/reference.sqlite3");
+    let ids = |cves: Vec<CveSummary>| cves.into_iter().map(|cve| cve.id).collect::<Vec<_>>();
+
+    // Repositories, domains and whole URLs, with or without their scheme and www.
+    for (reference, expected) in [
+        ("github.com/openssl/openssl", vec!["CVE-2021-3711"]),
+        ("github.com/OpenSSL/", vec!["CVE-2021-3711"]),
+        ("openssl.org", vec!["CVE-2014-0160", "CVE-2021-3711"]),
+        (
+            "https://www.openssl.org/news/secadv/20140407.txt",
+            vec!["CVE-2014-0160"],
+        ),
+        ("https://heartbleed.com", vec!["CVE-2014-0160"]),
+        (
+            "packetstormsecurity.com/files/165225",
+            vec!["CVE-2021-44228"],
+        ),
+        // Subdomains are matched by their domain alone
+        ("microsoft.com", vec!["CVE-2021-26855", "CVE-2022-30190"]),
+        ("msrc.microsoft.com/en-us", vec![]),
+        // Paths are matched whole
+        ("github.com/opens", vec![]),
+        ("github.com/openssl/openssl/commit/59f5e75f3bce", vec![]),
+        ("not a url", vec![]),
+    ] {
+        assert_eq!(
+            ids(search_by_reference(&config, reference).expect("Failed searching by reference")),
+            expected,
+            "{}",
+            reference
+        );
+    }
+
+    // Feeds are matched alike without being cached
+    let body = fs::read_to_string("./tests/files/nvdcve-1.1-sample.json").unwrap();
+    let cve_feed: CveFeed = serde_json::from_str(&body).unwrap();
+    for reference in [
+        "github.com",
+        "openssl.org/news",
+        "microsoft.com",
+        "github.com/opens",
+    ] {
+        let query = SearchQuery {
+            reference: Some(reference.to_string()),
+            ..Default::default()
+        };
+        let matched: Vec<_> = cve_feed
+            .cve_items
+            .iter()
+            .filter(|cve| query.matches(cve))
+            .map(|cve| cve.cve.cve_data_meta.id.clone())
+            .collect();
+        assert_eq!(
+            matched,
+            ids(search(&config, &query).unwrap()),
+            "{}",
+            reference
+        );
+    }
+
+    // Caches made before the references were indexed index those of their CVEs once upgraded
+    let conn = rusqlite::Connection::open(&config.db).expect("Failed opening test cache");
+    conn.execute("DROP TABLE cve_reference", []).unwrap();
+    conn.close().expect("Failed closing test cache");
+    reindex(&config).expect("Failed reindexing test cache");
+    assert_eq!(
+        ids(search_by_reference(&config, "github.com/openssl").unwrap()),
+        vec!["CVE-2021-3711"]
+    );
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_by_cwe() {
+    let config = sync_sample_feed("./tests/files/.cache/nvd/cwe.sqlite3");
+    let ids = |cves: Vec<CveSummary>| cves.into_iter().map(|cve| cve.id).collect::<Vec<_>>();
+
+    // CWE IDs can be given without their prefix, in any case
+    for cwe in ["CWE-20", "20", "cwe-20", "CWE-502"] {
+        assert_eq!(
+            ids(search_by_cwe(&config, cwe).unwrap()),
+            vec!["CVE-2021-44228"],
+            "{}",
+            cwe
+        );
+    }
+    assert_eq!(
+        ids(search_by_cwe(&config, "CWE-125").unwrap()),
+        vec!["CVE-2014-0160"]
+    );
+    // Placeholders aren't weaknesses
+    assert!(search_by_cwe(&config, "NVD-CWE-noinfo").unwrap().is_empty());
+    assert!(search_by_cwe(&config, "CWE-79").unwrap().is_empty());
+
+    let query = SearchQuery {
+        cwe: Some("918".to_string()),
+        text: Some("exchange".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(
+        ids(search(&config, &query).unwrap()),
+        vec!["CVE-2021-26855"]
+    );
+
+    // Feeds are matched alike without being cached
+    let body = fs::read_to_string("./tests/files/nvdcve-1.1-sample.json").unwrap();
+    let cve_feed: CveFeed = serde_json::from_str(&body).unwrap();
+    let matched: Vec<_> = cve_feed
+        .cve_items
+        .iter()
+        .filter(|cve| query.matches(cve))
+        .map(|cve| cve.cve.cve_data_meta.id.as_str())
+        .collect();
+    assert_eq!(matched, vec!["CVE-2021-26855"]);
+
+    // Weaknesses are only named once the CWE list is synced
+    let unnamed = |id: &str| Weakness {
+        id: id.to_string(),
+        name: None,
+    };
+    assert_eq!(
+        get_weaknesses(&config, "CVE-2021-44228").unwrap(),
+        vec![unnamed("CWE-502"), unnamed("CWE-400"), unnamed("CWE-20")]
+    );
+    assert!(get_weaknesses(&config, "CVE-2022-30190")
+        .unwrap()
+        .is_empty());
+
+    // Caches made before the weaknesses were indexed index those of their CVEs
+    let conn = rusqlite::Connection::open(&config.db).expect("Failed opening test cache");
+    conn.execute("DROP TABLE cve_cwe", []).unwrap();
+    conn.close().expect("Failed closing test cache");
+
+    let xml = fs::read_to_string("./tests/files/cwec-sample.xml").unwrap();
+    assert_eq!(import_cwes(&config, &parse_cwe_list(&xml)).unwrap(), 7);
+    let names: Vec<_> = get_weaknesses(&config, "CVE-2021-44228")
+        .unwrap()
+        .into_iter()
+        .map(|weakness| weakness.name.unwrap_or_default())
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            "Deserialization of Untrusted Data",
+            "Uncontrolled Resource Consumption",
+            "Improper Input Validation"
+        ]
+    );
+    // The CWE list of the sample doesn't name CWE-120
+    assert_eq!(
+        get_weaknesses(&config, "CVE-2021-3711").unwrap(),
+        vec![unnamed("CWE-120")]
+    );
+    assert_eq!(
+        get_cwe_name(&config, "1219").unwrap().as_deref(),
+        Some("File Handling Issues")
+    );
+    assert_eq!(get_cwe_name(&config, "CWE-120").unwrap(), None);
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_cpe_dictionary() {
+    let path = "./tests/files/.cache/nvd/cpe_dictionary.sqlite3";
+    let _ = fs::remove_file(path);
+    let mut config = CacheConfig {
+        db: path.to_string(),
+        ..CacheConfig::new()
+    };
+
+    // Nothing is known of products until the dictionary is synced
+    assert_eq!(is_known_product(&config, None, "openssl").unwrap(), None);
+    assert!(get_dictionary_entries(&config, None, "openssl")
+        .unwrap()
+        .is_empty());
+
+    let server = MockFeedServer::start(HashMap::from([
+        (
+            "official-cpe-dictionary_v2.3.meta".to_string(),
+            fs::read("./tests/files/official-cpe-dictionary_v2.3.meta").unwrap(),
+        ),
+        (
+            "official-cpe-dictionary_v2.3.xml.gz".to_string(),
+            gzip(&fs::read("./tests/files/official-cpe-dictionary_v2.3-sample.xml").unwrap()),
+        ),
+    ]));
+    let client = ReqwestBlockingClient::new(&server.url, None, None, None);
+    assert_eq!(sync_cpe_dictionary(&config, &client).unwrap(), 4);
+    // The dictionary is only fetched again once its Metafile changed
+    assert_eq!(sync_cpe_dictionary(&config, &client).unwrap(), 0);
+    config.force_update = true;
+    assert_eq!(sync_cpe_dictionary(&config, &client).unwrap(), 4);
+
+    // Names are looked up ignoring case, along with their vendor if given
+    assert_eq!(
+        is_known_product(&config, None, "OpenSSL").unwrap(),
+        Some(true)
+    );
+    assert_eq!(
+        is_known_product(&config, Some("openssl"), "openssl").unwrap(),
+        Some(true)
+    );
+    assert_eq!(
+        is_known_product(&config, Some("opensll"), "openssl").unwrap(),
+        Some(false)
+    );
+    assert_eq!(
+        is_known_product(&config, None, "log4shell").unwrap(),
+        Some(false)
+    );
+
+    let entries = get_dictionary_entries(&config, Some("openssl"), "openssl").unwrap();
+    assert_eq!(
+        entries
+            .iter()
+            .map(|entry| entry.cpe23_uri.as_str())
+            .collect::<Vec<_>>(),
+        vec![
+            "cpe:2.3:a:openssl:openssl:1.1.1k:*:*:*:*:*:*:*",
+            "cpe:2.3:a:openssl:openssl:1.1.1l:*:*:*:*:*:*:*"
+        ]
+    );
+    // The English title is kept among those of other languages
+    assert_eq!(
+        entries[0].title.as_deref(),
+        Some("OpenSSL Project OpenSSL 1.1.1k")
+    );
+
+    let exchange = get_dictionary_entries(&config, None, "exchange_server").unwrap();
+    assert!(exchange[0].deprecated);
+    assert_eq!(
+        exchange[0].title.as_deref(),
+        Some("Microsoft Exchange Server 2019 Cumulative Update 8 & Earlier")
+    );
+
+    // Importing a dictionary replaces the names of the previous one
+    let xml = fs::read_to_string("./tests/files/official-cpe-dictionary_v2.3-sample.xml").unwrap();
+    let log4j: Vec<_> = parse_cpe_dictionary(&xml)
+        .into_iter()
+        .filter(|entry| entry.cpe23_uri.contains(":log4j:"))
+        .collect();
+    assert_eq!(import_cpe_dictionary(&config, &log4j).unwrap(), 1);
+    assert_eq!(
+        is_known_product(&config, None, "openssl").unwrap(),
+        Some(false)
+    );
+    assert_eq!(
+        is_known_product(&config, None, "log4j").unwrap(),
+        Some(true)
+    );
+
+    fs::remove_file(&config.db).expect("Failed removing test cache");
 }
@@ -0,0 +1,1850 @@
+#![cfg(feature = "blocking")]
+
+use assert_cmd::Command;
+#[cfg(feature = "serde")]
+use nvd_cve::cache::CacheConfig;
+use nvd_cve::feed::FeedName;
+use predicates::prelude::*;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::PathBuf;
+mod util;
+use util::{gzip, MockFeedServer};
+
+fn nvd_cve() -> Command {
+    Command::cargo_bin("nvd_cve").expect("Failed finding binary")
+}
+
+/// Sync the sample feed from a mock feed server into a fresh cache at ``db`` using the binary
+fn synced_cache(db: &str) -> String {
+    fs::remove_file(db).ok();
+
+    let server = MockFeedServer::with_recent_feed("./tests/files/nvdcve-1.1-sample.json");
+
+    nvd_cve()
+        .args(["sync", "-n", "-l", "recent", "-u", &server.url, "-d", db])
+        .assert()
+        .success()
+        .stdout("");
+
+    db.to_string()
+}
+
+/// Compare ``output`` with the snapshot of the same name in ``tests/files/snapshots``. Set
+/// ``UPDATE_SNAPSHOTS`` to write the current output to the snapshot instead.
+fn assert_snapshot(name: &str, output: &[u8]) {
+    let mut path = PathBuf::from("./tests/files/snapshots");
+    path.push(name);
+
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, output).expect("Failed writing snapshot");
+        return;
+    }
+
+    let snapshot = fs::read_to_string(&path).expect("Failed reading snapshot");
+    assert_eq!(
+        String::from_utf8_lossy(output),
+        snapshot,
+        "Output differs from snapshot {:?}, set UPDATE_SNAPSHOTS=1 to update it",
+        path
+    );
+}
+
+#[test]
+fn test_sync_and_search() {
+    let db = synced_cache("./tests/files/.cache/nvd/cli_search.sqlite3");
+
+    let searches: &[(&str, &[&str])] = &[
+        ("search_id.json", &["CVE-2021-44228"]),
+        ("search_prefix.txt", &["CVE-2021-*"]),
+        ("search_text.txt", &["-t", "remote"]),
+        (
+            "search_vector.txt",
+            &["--vector", "AV:N/AC:L", "--min-impact", "5.9"],
+        ),
+    ];
+
+    for (snapshot, args) in searches {
+        let output = nvd_cve()
+            .args(["search", "-d", &db])
+            .args(*args)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        assert_snapshot(snapshot, &output);
+    }
+
+    nvd_cve()
+        .args(["search", "-d", &db, "CVE-1999-0001"])
+        .assert()
+        .code(3)
+        .stdout("");
+
+    // Syncing again is a no-op, the cached feed is the latest
+    let server = MockFeedServer::with_recent_feed("./tests/files/nvdcve-1.1-sample.json");
+    nvd_cve()
+        .args(["sync", "-n", "-l", "recent", "-u", &server.url, "-d", &db])
+        .assert()
+        .success();
+
+    fs::remove_file(&db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_sync_missing_feed() {
+    let db = "./tests/files/.cache/nvd/cli_missing.sqlite3";
+    fs::remove_file(db).ok();
+
+    let server = MockFeedServer::with_recent_feed("./tests/files/nvdcve-1.1-sample.json");
+    nvd_cve()
+        .args(["sync", "-n", "-l", "2021", "-u", &server.url, "-d", db])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::starts_with("Fatal Error: "));
+
+    fs::remove_file(db).ok();
+}
+
+#[test]
+fn test_sync_allow_hosts() {
+    let db = "./tests/files/.cache/nvd/cli_allow_hosts.sqlite3";
+    fs::remove_file(db).ok();
+
+    let server = MockFeedServer::with_recent_feed("./tests/files/nvdcve-1.1-sample.json");
+    let port = server.url.rsplit(':').next().unwrap();
+    let moved = MockFeedServer::start_redirecting(Some(format!("http://localhost:{}", port)));
+    let sync = |args: &[&str]| {
+        nvd_cve()
+            .args(["sync", "-n", "-l", "recent", "-u", &moved.url, "-d", db])
+            .args(args)
+            .assert()
+    };
+
+    sync(&["--allow-hosts", "127.0.0.1"])
+        .code(1)
+        .stderr(predicate::str::contains("HostNotAllowed(\"localhost\")"));
+    sync(&["--max-redirects", "0"])
+        .code(1)
+        .stderr(predicate::str::contains("TooManyRedirects(0)"));
+    sync(&[
+        "--allow-hosts",
+        "127.0.0.1,localhost",
+        "--max-redirects",
+        "1",
+    ])
+    .success();
+
+    fs::remove_file(db).expect("Failed removing test cache");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_sync_show_default() {
+    let config = PathBuf::from("./tests/files/.cache/nvd/cli_show_default.toml");
+    let db = "./tests/files/.cache/nvd/cli_show_default.sqlite3";
+    fs::remove_file(db).ok();
+
+    let shown = nvd_cve()
+        .args(["sync", "--show-default"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    fs::write(&config, &shown).unwrap();
+
+    // The config printed reads back as the default one
+    let read: CacheConfig = toml::from_str(&fs::read_to_string(&config).unwrap()).unwrap();
+    assert_eq!(format!("{:?}", read), format!("{:?}", CacheConfig::new()));
+
+    // And is accepted by sync, which only fails on the NVD's host not being allowed
+    nvd_cve()
+        .args(["sync", "-n", "-C", config.to_str().unwrap(), "-d", db])
+        .args(["--allow-hosts", "127.0.0.1"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("HostNotAllowed(\"nvd.nist.gov\")"));
+
+    fs::remove_file(db).ok();
+    fs::remove_file(&config).expect("Failed removing test config");
+}
+
+#[test]
+fn test_search_unknown_product() {
+    let db = "./tests/files/.cache/nvd/cli_cpe_dictionary.sqlite3";
+    fs::remove_file(db).ok();
+
+    let server = MockFeedServer::with_recent_feed("./tests/files/nvdcve-1.1-sample.json");
+    let dictionary = MockFeedServer::start(HashMap::from([
+        (
+            "official-cpe-dictionary_v2.3.meta".to_string(),
+            fs::read("./tests/files/official-cpe-dictionary_v2.3.meta").unwrap(),
+        ),
+        (
+            "official-cpe-dictionary_v2.3.xml.gz".to_string(),
+            gzip(&fs::read("./tests/files/official-cpe-dictionary_v2.3-sample.xml").unwrap()),
+        ),
+    ]));
+    let search = |args: &[&str]| nvd_cve().args(["search", "-d", db]).args(args).assert();
+
+    nvd_cve()
+        .args(["sync", "-n", "-l", "recent", "-u", &server.url, "-d", db])
+        .assert()
+        .success();
+    // Without a dictionary, a product matching nothing isn't known to be misspelled
+    search(&["--product", "opensll"])
+        .code(1)
+        .stderr(predicate::str::contains("Warning").not());
+
+    nvd_cve()
+        .args(["sync", "-n", "-l", "recent", "-u", &server.url, "-d", db])
+        .args(["--cpe-dictionary", &dictionary.url])
+        .assert()
+        .success();
+    search(&["--vendor", "openssl", "--product", "opensll"])
+        .code(1)
+        .stderr(predicate::str::contains(
+            "Warning: the CPE dictionary names no product opensll of vendor openssl",
+        ));
+    search(&["--product", "log4j", "--vendor", "apache", "--cwe", "CWE-1"])
+        .code(1)
+        .stderr(predicate::str::contains("Warning").not());
+
+    fs::remove_file(db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_sync_metrics_file() {
+    let db = "./tests/files/.cache/nvd/cli_metrics.sqlite3";
+    let metrics = "./tests/files/.cache/nvd/cli_metrics.prom";
+    fs::remove_file(db).ok();
+
+    let server = MockFeedServer::with_recent_feed("./tests/files/nvdcve-1.1-sample.json");
+    nvd_cve()
+        .args(["sync", "-n", "-l", "recent", "-u", &server.url, "-d", db])
+        .args(["--metrics-file", metrics])
+        .assert()
+        .success();
+
+    let text = fs::read_to_string(metrics).expect("Failed reading metrics");
+    assert!(text.contains("# TYPE nvd_cve_sync_success gauge\n"));
+    assert!(text.contains("nvd_cve_sync_success{source=\"nvd\"} 1\n"));
+    assert!(text.contains("nvd_cve_sync_cves_updated{source=\"nvd\"} 6\n"));
+    assert!(text.contains("nvd_cve_cache_cves{source=\"nvd\"} 6\n"));
+    assert!(text.contains("nvd_cve_cache_feeds{source=\"nvd\"} 1\n"));
+    let last_success = |text: &str| {
+        text.lines()
+            .find(|line| line.starts_with("nvd_cve_sync_last_success_timestamp_seconds{"))
+            .map(|line| line.to_string())
+    };
+    let succeeded = last_success(&text).expect("Missing last success");
+
+    // A failed sync is reported while keeping the time of the last successful one
+    nvd_cve()
+        .args(["sync", "-n", "-l", "2021", "-u", &server.url, "-d", db])
+        .args(["--metrics-file", metrics])
+        .assert()
+        .code(1);
+
+    let text = fs::read_to_string(metrics).expect("Failed reading metrics");
+    assert!(text.contains("nvd_cve_sync_success{source=\"nvd\"} 0\n"));
+    assert_eq!(last_success(&text), Some(succeeded));
+    assert!(!PathBuf::from(format!("{}.tmp", metrics)).exists());
+
+    fs::remove_file(db).expect("Failed removing test cache");
+    fs::remove_file(metrics).expect("Failed removing test metrics");
+}
+
+#[test]
+fn test_sync_feed_range() {
+    let db = "./tests/files/.cache/nvd/cli_range.sqlite3";
+    fs::remove_file(db).ok();
+
+    nvd_cve()
+        .args(["sync", "-n", "-l", "2020..2018,recent", "-d", db])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid range of feeds 2020..2018",
+        ));
+
+    // The server only has the 2023 and 2024 feeds, any other feed would fail the sync
+    let metafile = fs::read("./tests/files/nvdcve-1.1-recent.meta").unwrap();
+    let json = fs::read("./tests/files/nvdcve-1.1-sample.json").unwrap();
+    let mut files = std::collections::HashMap::new();
+    for feed in [FeedName::Year(2023), FeedName::Year(2024)] {
+        files.insert(feed.metafile_filename(), metafile.clone());
+        files.insert(feed.feed_filename(), gzip(&json));
+    }
+    let server = MockFeedServer::start(files);
+
+    nvd_cve()
+        .args(["sync", "-n", "-l", "2023..", "-u", &server.url, "-d", db])
+        .assert()
+        .success();
+
+    nvd_cve()
+        .args(["feeds", "-d", db])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2023").and(predicate::str::contains("2024")));
+
+    fs::remove_file(db).ok();
+}
+
+#[test]
+fn test_filter_feed() {
+    let db = synced_cache("./tests/files/.cache/nvd/cli_filter.sqlite3");
+    let filtered_db = "./tests/files/.cache/nvd/cli_filtered.sqlite3";
+    let dir = "./tests/files/.cache/filter";
+    fs::remove_file(filtered_db).ok();
+    fs::remove_dir_all(dir).ok();
+
+    let server = MockFeedServer::with_recent_feed("./tests/files/nvdcve-1.1-sample.json");
+
+    nvd_cve()
+        .args(["filter", "-l", "recent", "-u", &server.url, dir])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "required arguments were not provided",
+        ));
+
+    nvd_cve()
+        .args(["filter", "-l", "recent", "-u", &server.url])
+        .args(["--min-severity", "CRITICAL", dir])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("Wrote "));
+
+    // The reduced feed and its Metafile can be synced like any other feed
+    let mut files = std::collections::HashMap::new();
+    for name in ["nvdcve-1.1-custom.meta", "nvdcve-1.1-custom.json.gz"] {
+        files.insert(
+            name.to_string(),
+            fs::read(format!("{}/{}", dir, name)).unwrap(),
+        );
+    }
+    let mirror = MockFeedServer::start(files);
+
+    nvd_cve()
+        .args([
+            "sync",
+            "-n",
+            "-l",
+            "custom",
+            "-u",
+            &mirror.url,
+            "-d",
+            filtered_db,
+        ])
+        .assert()
+        .success();
+
+    let ids = |db: &str, criteria: &str| {
+        let output = nvd_cve()
+            .args(["search", "-d", db, "--min-severity", criteria])
+            .output()
+            .unwrap();
+        let mut ids: Vec<String> = String::from_utf8(output.stdout)
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect();
+        ids.sort();
+        ids
+    };
+
+    let critical = ids(&db, "CRITICAL");
+    assert!(!critical.is_empty());
+    assert_eq!(ids(filtered_db, "NONE"), critical);
+
+    fs::remove_file(&db).ok();
+    fs::remove_file(filtered_db).ok();
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn test_sync_through_proxy() {
+    let db = "./tests/files/.cache/nvd/cli_proxy.sqlite3";
+    fs::remove_file(db).ok();
+
+    // The mock server serves its files whatever the host, so it can stand in for a proxy
+    let proxy = MockFeedServer::with_recent_feed("./tests/files/nvdcve-1.1-sample.json");
+    let sync = |args: &[&str], no_proxy: &str| {
+        let mut command = nvd_cve();
+        for variable in [
+            "https_proxy",
+            "HTTPS_PROXY",
+            "all_proxy",
+            "ALL_PROXY",
+            "no_proxy",
+        ] {
+            command.env_remove(variable);
+        }
+        command
+            .env("HTTP_PROXY", &proxy.url)
+            .env("NO_PROXY", no_proxy)
+            .args([
+                "sync",
+                "-n",
+                "-l",
+                "recent",
+                "-u",
+                "http://nvd.invalid/feeds/",
+            ])
+            .args(["--connect-timeout", "5s", "-d", db])
+            .args(args)
+            .assert()
+    };
+
+    sync(&[], "").success();
+    fs::remove_file(db).unwrap();
+
+    // Hosts listed in NO_PROXY are connected to directly, as with --no-proxy
+    sync(&[], "example.com,nvd.invalid").code(1);
+    fs::remove_file(db).ok();
+    sync(&["--no-proxy"], "").code(1);
+
+    fs::remove_file(db).ok();
+}
+
+#[test]
+fn test_tls_insecure() {
+    let db = "./tests/files/.cache/nvd/cli_tls_insecure.sqlite3";
+    fs::remove_file(db).ok();
+
+    let server = MockFeedServer::with_recent_feed("./tests/files/nvdcve-1.1-sample.json");
+
+    nvd_cve()
+        .args(["sync", "-n", "-l", "recent", "-u", &server.url, "-d", db])
+        .arg("--tls-insecure")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "WARNING: TLS certificate verification is disabled",
+        ));
+
+    // Only searches that fetch feeds connect to a server
+    nvd_cve()
+        .args(["search", "-d", db, "--tls-insecure", "CVE-2021-44228"])
+        .assert()
+        .code(2);
+
+    fs::remove_file(db).ok();
+}
+
+#[test]
+fn test_change_history() {
+    let db = "./tests/files/.cache/nvd/cli_change_history.sqlite3";
+    fs::remove_file(db).ok();
+
+    let mut files = HashMap::new();
+    files.insert(
+        FeedName::Recent.metafile_filename(),
+        fs::read("./tests/files/nvdcve-1.1-recent.meta").unwrap(),
+    );
+    files.insert(
+        FeedName::Recent.feed_filename(),
+        gzip(&fs::read("./tests/files/nvdcve-1.1-sample.json").unwrap()),
+    );
+    files.insert(
+        "2.0".to_string(),
+        fs::read("./tests/files/cvehistory-sample.json").unwrap(),
+    );
+    let server = MockFeedServer::start(files);
+    let api = format!("{}rest/json/cvehistory/2.0", server.url);
+
+    // Without --change-history no change events are synced
+    nvd_cve()
+        .args(["sync", "-n", "-l", "recent", "-u", &server.url, "-d", db])
+        .assert()
+        .success();
+    nvd_cve()
+        .args(["history", "-d", db, "--cve", "CVE-2021-44228"])
+        .assert()
+        .success()
+        .stdout("[]\n");
+
+    nvd_cve()
+        .args(["sync", "-n", "-l", "recent", "-u", &server.url, "-d", db])
+        .args(["--change-history", &api])
+        .assert()
+        .success();
+    nvd_cve()
+        .args(["history", "-d", db, "--cve", "CVE-2021-44228"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "\"eventName\": \"Initial Analysis\"",
+        ))
+        .stdout(predicate::str::contains("CVE-2021-3711").not());
+
+    // The remote change log is fetched as it is, without touching the cache
+    nvd_cve()
+        .args(["history", "--cve", "CVE-2021-44228", "--remote", "-u", &api])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"cveChangeId\""));
+
+    nvd_cve()
+        .args(["history", "-d", db, "--remote"])
+        .assert()
+        .code(2);
+
+    fs::remove_file(db).ok();
+}
+
+#[test]
+fn test_package() {
+    let db = "./tests/files/.cache/nvd/cli_package.sqlite3";
+    fs::remove_file(db).ok();
+
+    let mut archive = zip::ZipWriter::new(Cursor::new(vec![]));
+    archive
+        .start_file(
+            "GHSA-35jh-r3h4-6jhm.json",
+            zip::write::SimpleFileOptions::default(),
+        )
+        .unwrap();
+    archive
+        .write_all(&fs::read("./tests/files/osv/GHSA-35jh-r3h4-6jhm.json").unwrap())
+        .unwrap();
+
+    let mut files = HashMap::new();
+    files.insert(
+        FeedName::Recent.metafile_filename(),
+        fs::read("./tests/files/nvdcve-1.1-recent.meta").unwrap(),
+    );
+    files.insert(
+        FeedName::Recent.feed_filename(),
+        gzip(&fs::read("./tests/files/nvdcve-1.1-sample.json").unwrap()),
+    );
+    files.insert(
+        "all.zip".to_string(),
+        archive.finish().unwrap().into_inner(),
+    );
+    let server = MockFeedServer::start(files);
+
+    nvd_cve()
+        .args(["sync", "-n", "-l", "recent", "-u", &server.url, "-d", db])
+        .args(["--osv", "npm", "--osv-url", &server.url])
+        .assert()
+        .success();
+
+    nvd_cve()
+        .args(["package", "npm", "lodash", "4.17.20", "-d", db])
+        .assert()
+        .success()
+        .stdout("GHSA-35jh-r3h4-6jhm\tCVE-2021-23337\tCommand Injection in lodash\n");
+    nvd_cve()
+        .args(["package", "npm", "lodash", "4.17.21", "-d", db])
+        .assert()
+        .success()
+        .stdout("");
+    nvd_cve()
+        .args(["package", "npm", "lodash-es", "-d", db, "--json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"id\": \"GHSA-35jh-r3h4-6jhm\""));
+
+    nvd_cve()
+        .args(["package", "maven", "log4j", "-d", db])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("Unknown ecosystem: maven"));
+
+    fs::remove_file(db).ok();
+}
+
+#[test]
+fn test_search_text_with_cve() {
+    let db = synced_cache("./tests/files/.cache/nvd/cli_text.sqlite3");
+
+    // Text searches are limited to the CVEs beginning with a wildcard ID
+    nvd_cve()
+        .args(["search", "--db", &db, "CVE-2021-*", "--text", "openssl"])
+        .assert()
+        .success()
+        .stdout("CVE-2021-3711\n");
+
+    nvd_cve()
+        .args(["search", "--db", &db, "CVE-2019-*", "-t", "openssl"])
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr("No results found\n");
+
+    // ... while a single CVE ID is rejected rather than silently ignored
+    nvd_cve()
+        .args(["search", "--db", &db, "CVE-2021-3711", "-t", "openssl"])
+        .assert()
+        .code(2)
+        .stdout("")
+        .stderr(predicate::str::contains(
+            "--text can't be combined with a single CVE ID",
+        ));
+
+    nvd_cve()
+        .args(["search", "--db", &db, "-t", "Crock-Pot"])
+        .assert()
+        .success()
+        .stdout("CVE-2019-12780\n");
+
+    fs::remove_file(&db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_public_exploits() {
+    let db = synced_cache("./tests/files/.cache/nvd/cli_exploits.sqlite3");
+
+    nvd_cve()
+        .args(["search", "-d", &db, "--has-public-exploit"])
+        .assert()
+        .success()
+        .stdout("CVE-2014-0160\nCVE-2019-12780\n");
+
+    nvd_cve()
+        .args(["search", "-d", &db, "-t", "openssl", "--has-public-exploit"])
+        .assert()
+        .success()
+        .stdout("CVE-2014-0160\n");
+
+    fs::remove_file(&db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_kev() {
+    let db = synced_cache("./tests/files/.cache/nvd/cli_kev.sqlite3");
+
+    let server = MockFeedServer::start(HashMap::from([
+        (
+            FeedName::Recent.metafile_filename(),
+            fs::read("./tests/files/nvdcve-1.1-recent.meta").unwrap(),
+        ),
+        (
+            "known_exploited_vulnerabilities.json".to_string(),
+            fs::read("./tests/files/known_exploited_vulnerabilities-sample.json").unwrap(),
+        ),
+    ]));
+    // The feeds are up to date, only the catalog is synced
+    nvd_cve()
+        .args(["sync", "-n", "-l", "recent", "-u", &server.url, "-d", &db])
+        .args(["--kev", &server.url])
+        .assert()
+        .success();
+
+    nvd_cve()
+        .args(["search", "-d", &db, "--kev-only"])
+        .assert()
+        .success()
+        .stdout("CVE-2021-26855\nCVE-2021-44228\n");
+
+    nvd_cve()
+        .args(["search", "-d", &db, "-t", "openssl", "-f", "id,kev"])
+        .assert()
+        .success()
+        .stdout("CVE-2014-0160\tno\nCVE-2021-3711\tno\n");
+
+    nvd_cve()
+        .args(["search", "-d", &db, "-t", "log4j", "-f", "id,kev"])
+        .assert()
+        .success()
+        .stdout("CVE-2021-44228\tyes\n");
+
+    nvd_cve()
+        .args([
+            "filter",
+            "./tests/files/nvdcve-1.1-sample.json",
+            "--kev-only",
+        ])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("--kev-only can't filter feeds"));
+
+    fs::remove_file(&db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_epss() {
+    let db = synced_cache("./tests/files/.cache/nvd/cli_epss.sqlite3");
+
+    let server = MockFeedServer::start(HashMap::from([
+        (
+            FeedName::Recent.metafile_filename(),
+            fs::read("./tests/files/nvdcve-1.1-recent.meta").unwrap(),
+        ),
+        (
+            "epss_scores-current.csv.gz".to_string(),
+            gzip(&fs::read("./tests/files/epss_scores-sample.csv").unwrap()),
+        ),
+    ]));
+    nvd_cve()
+        .args(["sync", "-n", "-l", "recent", "-u", &server.url, "-d", &db])
+        .args(["--epss", &server.url])
+        .assert()
+        .success();
+
+    nvd_cve()
+        .args(["search", "-d", &db, "--min-epss", "0.5", "-f", "id,epss"])
+        .assert()
+        .success()
+        .stdout("CVE-2014-0160\t0.97471\nCVE-2021-26855\t0.97485\nCVE-2021-44228\t0.97565\n");
+
+    nvd_cve()
+        .args(["search", "-d", &db, "-t", "MSDT", "-f", "id,epss"])
+        .assert()
+        .success()
+        .stdout("CVE-2022-30190\t-\n");
+
+    nvd_cve()
+        .args([
+            "filter",
+            "./tests/files/nvdcve-1.1-sample.json",
+            "--min-epss",
+            "0.5",
+        ])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("--min-epss can't filter feeds"));
+
+    fs::remove_file(&db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_severity() {
+    let db = synced_cache("./tests/files/.cache/nvd/cli_severity.sqlite3");
+
+    nvd_cve()
+        .args([
+            "search",
+            "-d",
+            &db,
+            "--min-cvss",
+            "9.0",
+            "--severity",
+            "critical",
+        ])
+        .assert()
+        .success()
+        .stdout("CVE-2021-26855\nCVE-2021-3711\nCVE-2021-44228\n");
+
+    nvd_cve()
+        .args(["search", "-d", &db, "--severity", "HIGH", "--min-cvss", "8"])
+        .args(["-f", "id,severity,score"])
+        .assert()
+        .success()
+        .stdout("CVE-2019-12780\tHIGH\t8.8\n");
+
+    nvd_cve()
+        .args([
+            "search",
+            "-d",
+            &db,
+            "--severity",
+            "high",
+            "--min-severity",
+            "low",
+        ])
+        .assert()
+        .code(2);
+
+    fs::remove_file(&db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_dates() {
+    let db = synced_cache("./tests/files/.cache/nvd/cli_dates.sqlite3");
+
+    nvd_cve()
+        .args(["search", "-d", &db, "--published-after", "2021-01-01"])
+        .args(["--modified-before", "2022-06-01"])
+        .assert()
+        .success()
+        .stdout("CVE-2021-26855\nCVE-2021-3711\n");
+
+    nvd_cve()
+        .args([
+            "search",
+            "-d",
+            &db,
+            "--modified-after",
+            "2022-07-12T17:42:00Z",
+        ])
+        .args(["--published-before", "2022-01-01T00:00:00+01:00"])
+        .assert()
+        .success()
+        .stdout("CVE-2021-44228\n");
+
+    nvd_cve()
+        .args(["search", "-d", &db, "--published-after", "01/01/2021"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("invalid date"));
+
+    fs::remove_file(&db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_format_str() {
+    let db = synced_cache("./tests/files/.cache/nvd/cli_format_str.sqlite3");
+
+    nvd_cve()
+        .args(["search", "-d", &db, "--min-severity", "critical"])
+        .args(["--format-str", r"{id:<15}|{score:>5}\t{description:.13}"])
+        .assert()
+        .success()
+        .stdout(
+            "CVE-2021-26855 |  9.8\tMicrosoft Exc\n\
+             CVE-2021-3711  |  9.8\tIn order to d\n\
+             CVE-2021-44228 | 10.0\tApache Log4j2\n",
+        );
+
+    // Single CVEs are listed alone, literal braces are doubled
+    nvd_cve()
+        .args(["search", "-d", &db, "CVE-2014-0160"])
+        .args(["--format-str", "{{{id}}} {severity} {kev} {epss}"])
+        .assert()
+        .success()
+        .stdout("{CVE-2014-0160} MEDIUM no -\n");
+
+    for (template, error) in [
+        ("{cve}", "unknown field"),
+        ("{id", "unclosed {"),
+        ("id}", "unmatched }"),
+        ("{score:.x}", "invalid format"),
+        (r"{id}\x", "unknown escape"),
+    ] {
+        nvd_cve()
+            .args(["search", "-d", &db, "-t", "log4j", "--format-str", template])
+            .assert()
+            .code(2)
+            .stderr(predicate::str::contains(error));
+    }
+
+    fs::remove_file(&db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_pick() {
+    let db = synced_cache("./tests/files/.cache/nvd/cli_pick.sqlite3");
+
+    // Nothing to pick from, the terminal is left alone
+    nvd_cve()
+        .args(["search", "-d", &db, "-t", "no such text", "--pick"])
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr(predicate::str::contains("No results found"));
+
+    let conflicting: [&[&str]; 3] = [&["--fields", "id"], &["--format-str", "{id}"], &["--full"]];
+    for args in conflicting {
+        nvd_cve()
+            .args(["search", "-d", &db, "-t", "log4j", "--pick"])
+            .args(args)
+            .assert()
+            .code(2)
+            .stderr(predicate::str::contains("cannot be used with"));
+    }
+
+    fs::remove_file(&db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_assigner() {
+    let db = synced_cache("./tests/files/.cache/nvd/cli_assigner.sqlite3");
+
+    nvd_cve()
+        .args(["search", "-d", &db, "--assigner", "secure@microsoft.com"])
+        .assert()
+        .success()
+        .stdout("CVE-2021-26855\nCVE-2022-30190\n");
+
+    nvd_cve()
+        .args(["search", "-d", &db, "--assigner", "security@apache.org"])
+        .args(["--min-severity", "critical", "-f", "id,score"])
+        .assert()
+        .success()
+        .stdout("CVE-2021-44228\t10.0\n");
+
+    nvd_cve()
+        .args(["search", "-d", &db, "--assigner", "apache.org"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("No results found"));
+
+    fs::remove_file(&db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_product() {
+    let db = synced_cache("./tests/files/.cache/nvd/cli_product.sqlite3");
+
+    nvd_cve()
+        .args(["search", "-d", &db, "--vendor", "microsoft"])
+        .args(["--product", "exchange_server"])
+        .assert()
+        .success()
+        .stdout("CVE-2021-26855\n");
+
+    nvd_cve()
+        .args([
+            "search",
+            "-d",
+            &db,
+            "--vendor",
+            "Microsoft",
+            "--min-severity",
+            "high",
+        ])
+        .args(["-f", "id,score"])
+        .assert()
+        .success()
+        .stdout("CVE-2021-26855\t9.8\nCVE-2022-30190\t7.8\n");
+
+    nvd_cve()
+        .args([
+            "search",
+            "-d",
+            &db,
+            "--vendor",
+            "apache",
+            "--product",
+            "openssl",
+        ])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("No results found"));
+
+    fs::remove_file(&db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_reference() {
+    let db = synced_cache("./tests/files/.cache/nvd/cli_reference.sqlite3");
+
+    nvd_cve()
+        .args([
+            "search",
+            "-d",
+            &db,
+            "--reference",
+            "github.com/openssl/openssl",
+        ])
+        .assert()
+        .success()
+        .stdout("CVE-2021-3711\n");
+
+    nvd_cve()
+        .args([
+            "search",
+            "-d",
+            &db,
+            "--reference",
+            "https://packetstormsecurity.com/",
+        ])
+        .args(["--min-severity", "critical"])
+        .assert()
+        .success()
+        .stdout("CVE-2021-26855\nCVE-2021-44228\n");
+
+    fs::remove_file(&db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_cwe() {
+    let db = synced_cache("./tests/files/.cache/nvd/cli_cwe.sqlite3");
+
+    let mut archive = zip::ZipWriter::new(Cursor::new(vec![]));
+    archive
+        .start_file("cwec_v4.14.xml", zip::write::SimpleFileOptions::default())
+        .unwrap();
+    archive
+        .write_all(&fs::read("./tests/files/cwec-sample.xml").unwrap())
+        .unwrap();
+    let server = MockFeedServer::start(HashMap::from([
+        (
+            FeedName::Recent.metafile_filename(),
+            fs::read("./tests/files/nvdcve-1.1-recent.meta").unwrap(),
+        ),
+        (
+            "cwec_latest.xml.zip".to_string(),
+            archive.finish().unwrap().into_inner(),
+        ),
+    ]));
+
+    nvd_cve()
+        .args(["search", "-d", &db, "--cwe", "cwe-78"])
+        .assert()
+        .success()
+        .stdout("CVE-2019-12780\n");
+
+    nvd_cve()
+        .args(["search", "-d", &db, "--full", "CVE-2021-44228"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"id\": \"CWE-502\""))
+        .stdout(predicate::str::contains("Deserialization of Untrusted Data").not());
+
+    nvd_cve()
+        .args(["sync", "-n", "-l", "recent", "-u", &server.url, "-d", &db])
+        .args(["--cwe-list", &server.url])
+        .assert()
+        .success();
+
+    nvd_cve()
+        .args(["search", "-d", &db, "--full", "CVE-2021-44228"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "\"name\": \"Deserialization of Untrusted Data\"",
+        ));
+
+    nvd_cve()
+        .args(["search", "-d", &db, "--cwe", "CWE-79"])
+        .assert()
+        .code(1);
+
+    fs::remove_file(&db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_as_of() {
+    let db = "./tests/files/.cache/nvd/cli_as_of.sqlite3";
+    fs::remove_file(db).ok();
+
+    let server = MockFeedServer::with_recent_feed("./tests/files/nvdcve-1.1-sample.json");
+    nvd_cve()
+        .args(["sync", "-n", "-l", "recent", "-u", &server.url, "-d", db])
+        .arg("--track-history")
+        .assert()
+        .success();
+
+    let output = nvd_cve()
+        .args([
+            "search",
+            "-d",
+            db,
+            "CVE-2021-44228",
+            "--as-of",
+            "2023-06-01",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let cve: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(cve["cve_data_meta"]["id"], "CVE-2021-44228");
+
+    // Log4Shell was last modified in 2022, no earlier version was kept
+    nvd_cve()
+        .args(["search", "-d", db, "CVE-2021-44228", "--as-of"])
+        .arg("2021-12-11T09:00:00+01:00")
+        .assert()
+        .code(1)
+        .stderr("No results found\n");
+
+    nvd_cve()
+        .args(["search", "-d", db, "CVE-2021-*", "--as-of", "2023-06-01"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("its ID can't end with '*'"));
+
+    nvd_cve()
+        .args(["search", "-d", db, "CVE-2021-44228", "--as-of", "yesterday"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("invalid date"));
+
+    fs::remove_file(db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_compare() {
+    let db = synced_cache("./tests/files/.cache/nvd/cli_compare.sqlite3");
+
+    let output = nvd_cve()
+        .args(["compare", "-d", &db, "CVE-2021-44228", "CVE-2021-3711"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let comparison: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(comparison["from"], "CVE-2021-44228");
+    assert_eq!(comparison["score"]["from"], 10.0);
+    assert_eq!(comparison["score"]["to"], 9.8);
+    assert!(comparison.get("severity").is_none());
+
+    nvd_cve()
+        .args([
+            "compare",
+            "-d",
+            &db,
+            "CVE-2021-44228@2021-12-11",
+            "CVE-2021-44228",
+        ])
+        .assert()
+        .code(1)
+        .stderr("No results found for CVE-2021-44228\n");
+
+    nvd_cve()
+        .args([
+            "compare",
+            "-d",
+            &db,
+            "CVE-2021-44228@yesterday",
+            "CVE-2021-3711",
+        ])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("invalid date"));
+
+    fs::remove_file(&db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_export() {
+    let db = synced_cache("./tests/files/.cache/nvd/cli_export.sqlite3");
+
+    let output = nvd_cve()
+        .args(["export", "-d", &db, "-t", "openssl"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let cves: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(cves[0]["cve_data_meta"]["id"], "CVE-2014-0160");
+    assert_eq!(cves[1]["cve_data_meta"]["id"], "CVE-2021-3711");
+
+    let output = nvd_cve()
+        .args([
+            "export",
+            "-d",
+            &db,
+            "--format",
+            "stix",
+            "--has-public-exploit",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let bundle: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(bundle["type"], "bundle");
+    let objects = bundle["objects"].as_array().unwrap();
+    assert_eq!(objects.len(), 2);
+    assert_eq!(objects[0]["type"], "vulnerability");
+    assert_eq!(objects[0]["spec_version"], "2.1");
+    assert_eq!(objects[1]["name"], "CVE-2019-12780");
+
+    nvd_cve()
+        .args(["export", "-d", &db, "-f", "csv"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("expected json, stix, misp or kv"));
+
+    nvd_cve()
+        .args(["export", "-d", &db, "-f", "misp"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("requires the directory"));
+
+    let dir = "./tests/files/.cache/cli_misp";
+    nvd_cve()
+        .args([
+            "export", "-d", &db, "-f", "misp", "-o", dir, "-t", "openssl",
+        ])
+        .assert()
+        .success()
+        .stdout(format!("Wrote 2 events to {}\n", dir));
+    let manifest: serde_json::Value =
+        serde_json::from_slice(&fs::read(format!("{}/manifest.json", dir)).unwrap()).unwrap();
+    assert_eq!(manifest.as_object().unwrap().len(), 2);
+    fs::remove_dir_all(dir).expect("Failed removing test feed");
+
+    let dir = "./tests/files/.cache/cli_kv";
+    nvd_cve()
+        .args(["export", "-d", &db, "-f", "kv", "-o", dir])
+        .assert()
+        .success()
+        .stdout(format!("Wrote 6 CVEs to {}\n", dir));
+    assert!(PathBuf::from(dir).join("cves.fst").exists());
+    assert!(PathBuf::from(dir).join("cves.bin").exists());
+    fs::remove_dir_all(dir).expect("Failed removing test index");
+
+    nvd_cve()
+        .args(["export", "-d", &db, "-f", "kv"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("write the index to"));
+
+    let file = "./tests/files/.cache/cli_export.json";
+    nvd_cve()
+        .args(["export", "-d", &db, "-o", file, "-p", "CVE-2021-"])
+        .assert()
+        .success()
+        .stdout("");
+    let cves: serde_json::Value = serde_json::from_slice(&fs::read(file).unwrap()).unwrap();
+    assert_eq!(cves.as_array().unwrap().len(), 3);
+    fs::remove_file(file).expect("Failed removing test export");
+
+    fs::remove_file(&db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_tickets() {
+    let db = synced_cache("./tests/files/.cache/nvd/cli_tickets.sqlite3");
+
+    let mut files = HashMap::new();
+    files.insert(
+        "issue".to_string(),
+        br#"{"id":"10000","key":"SEC-1"}"#.to_vec(),
+    );
+    files.insert("hook".to_string(), vec![]);
+    let server = MockFeedServer::start(files);
+    let jira = format!("{}jira/", server.url);
+    let webhook = format!("{}hook", server.url);
+
+    nvd_cve()
+        .args(["tickets", "-d", &db, "--jira", &jira, "--project", "SEC"])
+        .args(["-t", "openssl", "--has-public-exploit"])
+        .assert()
+        .success()
+        .stdout("CVE-2014-0160\tSEC-1\nCreated 1 tickets\n");
+
+    // The CVE already has an issue in the project...
+    nvd_cve()
+        .args(["tickets", "-d", &db, "--jira", &jira, "--project", "SEC"])
+        .args(["-t", "openssl", "--has-public-exploit"])
+        .assert()
+        .success()
+        .stdout("No new CVEs to ticket\n");
+
+    // ... but not through the webhook
+    nvd_cve()
+        .args([
+            "tickets",
+            "-d",
+            &db,
+            "--webhook",
+            &webhook,
+            "-p",
+            "CVE-2014-",
+        ])
+        .assert()
+        .success()
+        .stdout("CVE-2014-0160\t-\nCreated 1 tickets\n");
+
+    nvd_cve()
+        .args(["tickets", "-d", &db, "--webhook", &webhook, "-t", "openssl"])
+        .args(["--summary", "{cve}"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("InvalidTemplate"));
+
+    fs::remove_file(&db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_auto_fetch() {
+    let db = "./tests/files/.cache/nvd/cli_auto_fetch.sqlite3";
+    fs::remove_file(db).ok();
+
+    // A CVE missing from the cache suggests the feed to sync
+    nvd_cve()
+        .args(["search", "-d", db, "CVE-2021-44228"])
+        .assert()
+        .code(3)
+        .stdout("")
+        .stderr(predicate::str::contains("nvd_cve sync -l 2021"));
+
+    // ... which is synced before searching again with --auto-fetch
+    let server =
+        MockFeedServer::with_feed(FeedName::Year(2021), "./tests/files/nvdcve-1.1-sample.json");
+    nvd_cve()
+        .args(["search", "-d", db, "--auto-fetch", "-u", &server.url])
+        .arg("CVE-2021-44228")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""id": "CVE-2021-44228""#));
+
+    // Once synced, the feed isn't needed anymore
+    nvd_cve()
+        .args(["search", "-d", db, "CVE-2021-3711"])
+        .assert()
+        .success();
+
+    nvd_cve()
+        .args(["search", "-d", db, "--text", "openssl", "--auto-fetch"])
+        .assert()
+        .code(2);
+
+    fs::remove_file(db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_online() {
+    let server =
+        MockFeedServer::with_feed(FeedName::Year(2021), "./tests/files/nvdcve-1.1-sample.json");
+
+    nvd_cve()
+        .args(["search", "--online", "-u", &server.url, "cve-2021-44228"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""id": "CVE-2021-44228""#));
+
+    nvd_cve()
+        .args(["search", "--online", "-u", &server.url, "CVE-2021-0001"])
+        .assert()
+        .code(3)
+        .stdout("")
+        .stderr("CVE-2021-0001 not found in the 2021 feed\n");
+
+    // Only the 2021 feed is served
+    nvd_cve()
+        .args(["search", "--online", "-u", &server.url, "CVE-2019-12780"])
+        .assert()
+        .code(3)
+        .stderr(predicate::str::starts_with("Fatal Error: "));
+
+    nvd_cve()
+        .args(["search", "--online", "--auto-fetch", "CVE-2021-44228"])
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn test_feeds() {
+    let db = synced_cache("./tests/files/.cache/nvd/cli_feeds.sqlite3");
+
+    nvd_cve()
+        .args(["feeds", "-d", &db])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("FEED "))
+        .stdout(predicate::str::contains(
+            "recent   2021-12-18T19:00:00Z      1744779       116171       116031 \
+             0EA38A9771747DD51A3E009FB8738732144266C4EF4EDC548B70F33555CC1586\n",
+        ));
+
+    fs::remove_file(&db).expect("Failed removing test cache");
+
+    nvd_cve()
+        .args(["feeds", "-d", &db])
+        .assert()
+        .code(1)
+        .stderr("No feeds synced\n");
+
+    fs::remove_file(&db).ok();
+}
+
+#[test]
+fn test_read_only() {
+    let db = synced_cache("./tests/files/.cache/nvd/cli_read_only.sqlite3");
+
+    nvd_cve()
+        .args(["search", "-r", "-d", &db, "CVE-2021-44228"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"CVE-2021-44228\""));
+
+    nvd_cve()
+        .args(["feeds", "--read-only", "-d", &db])
+        .assert()
+        .success();
+
+    nvd_cve()
+        .args(["override", "-r", "-d", &db, "CVE-2021-44228"])
+        .assert()
+        .code(1)
+        .stderr("No override found\n");
+
+    let config = PathBuf::from(&db).with_extension("toml");
+    fs::write(&config, format!("db = {:?}\nread_only = true\n", db)).unwrap();
+
+    nvd_cve()
+        .args(["sync", "-n", "-C", config.to_str().unwrap()])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("is read-only and can't be synced"));
+
+    fs::remove_file(&config).ok();
+    fs::remove_file(&db).expect("Failed removing test cache");
+}
+
+#[cfg(feature = "snapshot")]
+#[test]
+fn test_snapshot() {
+    let db = synced_cache("./tests/files/.cache/nvd/cli_snapshot.sqlite3");
+    let archive = "./tests/files/.cache/nvd/cli_snapshot.tar.zst";
+    let fetched = "./tests/files/.cache/nvd/cli_snapshot_fetched.sqlite3";
+    fs::remove_file(fetched).ok();
+
+    nvd_cve()
+        .args(["snapshot", "publish", "-d", &db, archive])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"sha256\""));
+
+    let mut files = std::collections::HashMap::new();
+    files.insert("nvd.tar.zst".to_string(), fs::read(archive).unwrap());
+    let server = MockFeedServer::start(files);
+
+    nvd_cve()
+        .args(["snapshot", "fetch", "-d", fetched])
+        .arg(format!("{}nvd.tar.zst", server.url))
+        .assert()
+        .success();
+
+    nvd_cve()
+        .args(["search", "-d", fetched, "CVE-2021-44228"])
+        .assert()
+        .success();
+
+    // Nothing is installed from a missing snapshot
+    fs::remove_file(fetched).unwrap();
+    nvd_cve()
+        .args(["snapshot", "fetch", "-d", fetched])
+        .arg(format!("{}missing.tar.zst", server.url))
+        .assert()
+        .code(1);
+    assert!(!PathBuf::from(fetched).exists());
+
+    fs::remove_file(archive).ok();
+    fs::remove_file(&db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_install_service() {
+    let dir = PathBuf::from("./tests/files/.cache/nvd/service");
+    fs::create_dir_all(&dir).unwrap();
+    let config = dir.join("nvd_cve.toml");
+    fs::write(&config, "source = \"internal\"\n").unwrap();
+
+    nvd_cve()
+        .args(["install-service", "--kind", "systemd", "-i", "12h", "-C"])
+        .arg(&config)
+        .arg("-o")
+        .arg(&dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "systemctl enable --now nvd_cve-sync.timer",
+        ));
+
+    let service = fs::read_to_string(dir.join("nvd_cve-sync.service")).unwrap();
+    let config_path = config.canonicalize().unwrap();
+    assert!(service.contains(&format!(
+        "nvd_cve sync --no-progress --config {}\n",
+        config_path.display()
+    )));
+    let timer = fs::read_to_string(dir.join("nvd_cve-sync.timer")).unwrap();
+    assert!(timer.contains("OnUnitActiveSec=43200s\n"));
+    assert!(timer.contains("Unit=nvd_cve-sync.service\n"));
+
+    nvd_cve()
+        .args([
+            "install-service",
+            "--kind",
+            "cron",
+            "-i",
+            "30m",
+            "-n",
+            "nightly",
+            "-o",
+        ])
+        .arg(&dir)
+        .assert()
+        .success();
+    let cron = fs::read_to_string(dir.join("nightly.cron")).unwrap();
+    assert!(cron.contains("\n*/30 * * * * "));
+    assert!(cron.ends_with("nvd_cve sync --no-progress\n"));
+
+    // A '%' is a specifier to systemd and a newline to cron, wherever it is
+    let percent_config = dir.join("50%.toml");
+    fs::write(&percent_config, "source = \"internal\"\n").unwrap();
+    let percent_path = percent_config.canonicalize().unwrap();
+    for kind in ["systemd", "cron"] {
+        nvd_cve()
+            .args(["install-service", "--kind", kind, "-n", "percent", "-C"])
+            .arg(&percent_config)
+            .arg("-o")
+            .arg(&dir)
+            .assert()
+            .success();
+    }
+    let service = fs::read_to_string(dir.join("percent.service")).unwrap();
+    assert!(service.contains(&format!(
+        "--config {}\n",
+        percent_path.display().to_string().replace('%', "%%")
+    )));
+    let cron = fs::read_to_string(dir.join("percent.cron")).unwrap();
+    assert!(cron.ends_with(&format!(
+        "--config '{}'\n",
+        percent_path.display().to_string().replace('%', "\\%")
+    )));
+
+    // Cron can only run at intervals evenly dividing an hour or a day
+    nvd_cve()
+        .args(["install-service", "--kind", "cron", "-i", "7m", "-o"])
+        .arg(&dir)
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("can't be scheduled with cron"));
+
+    nvd_cve()
+        .args(["install-service", "--kind", "windows", "-o"])
+        .arg(&dir)
+        .assert()
+        .success();
+    let task = fs::read_to_string(dir.join("nvd_cve-sync.xml")).unwrap();
+    assert!(task.contains("<Interval>PT21600S</Interval>"));
+    assert!(task.contains("<Arguments>sync --no-progress</Arguments>"));
+
+    fs::remove_dir_all(&dir).expect("Failed removing test service files");
+}
+
+#[test]
+fn test_override() {
+    let db = synced_cache("./tests/files/.cache/nvd/cli_override.sqlite3");
+
+    nvd_cve()
+        .args(["override", "-d", &db, "CVE-2021-44228"])
+        .assert()
+        .code(1)
+        .stderr("No override found\n");
+
+    nvd_cve()
+        .args([
+            "override",
+            "-d",
+            &db,
+            "CVE-2021-44228",
+            "-s",
+            "low",
+            "-a",
+            "no",
+        ])
+        .args(["-n", "Only the log4j-api jar is deployed"])
+        .assert()
+        .success();
+
+    nvd_cve()
+        .args(["search", "-d", &db, "CVE-2021-44228"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""severity": "LOW""#))
+        .stdout(predicate::str::contains(r#""affected": false"#));
+
+    nvd_cve()
+        .args(["override", "-d", &db, "CVE-2021-44228", "--clear"])
+        .assert()
+        .success();
+
+    nvd_cve()
+        .args(["search", "-d", &db, "CVE-2021-44228"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("local_override").not());
+
+    fs::remove_file(&db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_override_namespace() {
+    let db = synced_cache("./tests/files/.cache/nvd/cli_override_namespace.sqlite3");
+
+    nvd_cve()
+        .args(["override", "-d", &db, "CVE-2021-44228", "-a", "no"])
+        .args(["--namespace", "payments", "-n", "Not deployed"])
+        .assert()
+        .success();
+
+    nvd_cve()
+        .args([
+            "search",
+            "-d",
+            &db,
+            "--namespace",
+            "payments",
+            "CVE-2021-44228",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""note": "Not deployed""#));
+
+    // Other namespaces, the default one included, don't see it
+    nvd_cve()
+        .args(["search", "-d", &db, "CVE-2021-44228"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("local_override").not());
+    nvd_cve()
+        .args([
+            "override",
+            "-d",
+            &db,
+            "--namespace",
+            "retail",
+            "CVE-2021-44228",
+        ])
+        .assert()
+        .code(1)
+        .stderr("No override found\n");
+
+    nvd_cve()
+        .args(["override", "-d", &db, "--namespace", "", "CVE-2021-44228"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("namespace names can't be empty"));
+
+    fs::remove_file(&db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_by_risk() {
+    let db = "./tests/files/.cache/nvd/cli_risk.sqlite3";
+    fs::remove_file(db).ok();
+
+    nvd_cve()
+        .args(["override", "-d", db, "CVE-2021-44228", "--tags"])
+        .arg("internet-facing,pci")
+        .assert()
+        .success();
+
+    nvd_cve()
+        .args(["override", "-d", db, "CVE-2021-44228"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""internet-facing","#));
+
+    let config = PathBuf::from(db).with_extension("toml");
+    fs::write(
+        &config,
+        format!(
+            "db = {:?}\n\n[risk_weights]\npublic_exploit = 3.0\n\n\
+             [risk_weights.asset_tags]\ninternet-facing = 5.0\n",
+            db
+        ),
+    )
+    .unwrap();
+
+    let server = MockFeedServer::with_recent_feed("./tests/files/nvdcve-1.1-sample.json");
+    nvd_cve()
+        .args(["sync", "-n", "-l", "recent", "-u", &server.url])
+        .args(["-C", config.to_str().unwrap()])
+        .assert()
+        .success();
+
+    nvd_cve()
+        .args(["search", "-d", db, "--by-risk", "--min-risk", "11"])
+        .assert()
+        .success()
+        .stdout("CVE-2021-44228\t15.0\nCVE-2019-12780\t11.8\n");
+
+    nvd_cve()
+        .args([
+            "search",
+            "-d",
+            db,
+            "--by-risk",
+            "CVE-2014-0160",
+            "-f",
+            "id,score",
+        ])
+        .assert()
+        .success()
+        .stdout("CVE-2014-0160\t5.0\n");
+
+    nvd_cve()
+        .args([
+            "filter",
+            "./tests/files/.cache/nvd/cli_risk",
+            "--min-risk",
+            "11",
+        ])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("--min-risk can't filter feeds"));
+
+    fs::remove_file(&config).ok();
+    fs::remove_file(db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_check() {
+    let db = synced_cache("./tests/files/.cache/nvd/cli_check.sqlite3");
+
+    nvd_cve()
+        .args(["check", "--max-age", "48h", "-d", &db])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("OK: 6 CVEs from 1 feeds"));
+
+    nvd_cve()
+        .args(["check", "-m", "0s", "-d", &db])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::starts_with("WARNING: Last synced at"));
+
+    fs::remove_file(&db).expect("Failed removing test cache");
+
+    nvd_cve()
+        .args(["check", "-d", &db])
+        .assert()
+        .code(2)
+        .stdout(predicate::str::starts_with(
+            "CRITICAL: Failed reading the cache",
+        ));
+}
+
+#[test]
+fn test_doctor() {
+    let db = synced_cache("./tests/files/.cache/nvd/cli_doctor.sqlite3");
+    let server =
+        MockFeedServer::with_feed(FeedName::Year(2002), "./tests/files/nvdcve-1.1-sample.json");
+
+    // Feeds fetched over plain HTTP are only a warning
+    nvd_cve()
+        .args(["doctor", "--url", &server.url, "-d", &db])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("OK      http"))
+        .stdout(predicate::str::contains("WARNING tls"))
+        .stdout(predicate::str::contains(
+            "OK      schema      Schema version",
+        ));
+
+    // A URL not serving the feeds and a file that isn't a cache fail, with hints
+    let server = MockFeedServer::start(HashMap::new());
+    fs::write(&db, "not a cache").unwrap();
+    let output = nvd_cve()
+        .args(["doctor", "--json", "--url", &server.url, "-d", &db])
+        .output()
+        .expect("Failed running doctor");
+    assert_eq!(output.status.code(), Some(2));
+
+    let diagnoses: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let diagnosis = |check: &str| {
+        diagnoses
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|diagnosis| diagnosis["check"] == check)
+            .unwrap()
+            .clone()
+    };
+    assert_eq!(diagnosis("connect")["status"], "ok");
+    assert_eq!(diagnosis("http")["status"], "error");
+    assert!(diagnosis("http")["detail"]
+        .as_str()
+        .unwrap()
+        .ends_with("404 Not Found"));
+    assert_eq!(diagnosis("schema")["status"], "error");
+    assert!(diagnosis("schema")["hint"].is_string());
+    assert_eq!(diagnosis("permissions")["status"], "ok");
+
+    fs::remove_file(&db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_history() {
+    let db = synced_cache("./tests/files/.cache/nvd/cli_history.sqlite3");
+
+    let output = nvd_cve()
+        .args(["history", "-r", "-d", &db])
+        .output()
+        .expect("Failed running history");
+    assert!(output.status.success());
+
+    let runs: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(runs.as_array().unwrap().len(), 1);
+    assert_eq!(runs[0]["cves_updated"], 6);
+    assert_eq!(
+        runs[0]["feeds"],
+        serde_json::json!([
+            {"feed": "recent", "added": 6, "modified": 0, "unchanged": 0, "skipped": 0}
+        ])
+    );
+
+    fs::remove_file(&db).expect("Failed removing test cache");
+}
+
+#[test]
+fn test_search_fields() {
+    let db = synced_cache("./tests/files/.cache/nvd/cli_fields.sqlite3");
+
+    nvd_cve()
+        .args([
+            "search",
+            "-d",
+            &db,
+            "CVE-2021-44228",
+            "-f",
+            "id,severity,score",
+        ])
+        .assert()
+        .success()
+        .stdout("CVE-2021-44228\tCRITICAL\t10.0\n");
+
+    nvd_cve()
+        .args([
+            "search",
+            "-d",
+            &db,
+            "--min-severity",
+            "critical",
+            "--fields",
+            "published,id",
+            "--delimiter",
+            ",",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "2021-12-10T10:15:00Z,CVE-2021-44228\n",
+        ));
+
+    // Every CVE is a single line, whatever its description holds
+    let output = nvd_cve()
+        .args(["search", "-d", &db, "CVE-*", "-f", "id,description"])
+        .output()
+        .expect("Failed running search");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().count(), 6);
+    assert!(stdout.lines().all(|line| line.matches('\t').count() == 1));
+
+    nvd_cve()
+        .args(["search", "-d", &db, "CVE-2021-44228", "-f", "id,cwe"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("unknown field \"cwe\""));
+
+    // The severity of an analyst's override is printed over the one of the feed
+    nvd_cve()
+        .args(["override", "-d", &db, "CVE-2021-44228", "-s", "low"])
+        .assert()
+        .success();
+    nvd_cve()
+        .args([
+            "search",
+            "-d",
+            &db,
+            "CVE-2021-44228",
+            "-f",
+            "id,severity,score",
+        ])
+        .assert()
+        .success()
+        .stdout("CVE-2021-44228\tLOW\t10.0\n");
+    nvd_cve()
+        .args(["search", "-d", &db, "CVE-2021-44228"])
+        .args(["--format-str", "{id} {severity}"])
+        .assert()
+        .success()
+        .stdout("CVE-2021-44228 LOW\n");
+
+    fs::remove_file(&db).expect("Failed removing test cache");
+}
@@ -0,0 +1,127 @@
+use flate2::write::GzEncoder;
+use nvd_cve::compression::{Compression, CompressionError};
+use nvd_cve::feed::FeedName;
+use std::fs;
+use std::io::{Cursor, Write};
+
+fn feed_json() -> Vec<u8> {
+    fs::read("./tests/files/nvdcve-1.1-sample.json").expect("Failed reading feed json")
+}
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(vec![], flate2::Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[test]
+fn test_decompress_gzip() {
+    let json = feed_json();
+    assert_eq!(Compression::Gzip.decompress(&gzip(&json)).unwrap(), json);
+}
+
+#[test]
+fn test_decompress_gzip_members_and_trailing_data() {
+    let json = feed_json();
+    let (head, tail) = json.split_at(json.len() / 2);
+
+    // Concatenated members are decompressed in full
+    let mut compressed = gzip(head);
+    compressed.extend(gzip(tail));
+    assert_eq!(Compression::Gzip.decompress(&compressed).unwrap(), json);
+
+    // Junk appended by proxies is ignored
+    compressed.extend(b"<html>502 Bad Gateway</html>\n\0\0\0");
+    assert_eq!(Compression::Gzip.decompress(&compressed).unwrap(), json);
+
+    // Malformed input fails without panicking
+    assert!(Compression::Gzip.decompress(&json).is_err());
+    assert!(Compression::Gzip.decompress(&[]).is_err());
+    assert!(Compression::Gzip
+        .decompress(&compressed[..compressed.len() / 3])
+        .is_err());
+    let mut corrupted = gzip(&json);
+    let middle = corrupted.len() / 2;
+    corrupted[middle] ^= 0xff;
+    assert!(Compression::Gzip.decompress(&corrupted).is_err());
+}
+
+#[test]
+fn test_decompress_zip() {
+    let json = feed_json();
+    let mut writer = zip::ZipWriter::new(Cursor::new(vec![]));
+    writer
+        .start_file(
+            "nvdcve-1.1-sample.json",
+            zip::write::SimpleFileOptions::default(),
+        )
+        .unwrap();
+    writer.write_all(&json).unwrap();
+    let compressed = writer.finish().unwrap().into_inner();
+
+    assert_eq!(Compression::Zip.decompress(&compressed).unwrap(), json);
+    assert!(Compression::Zip.decompress(&json).is_err());
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn test_decompress_zstd() {
+    let json = feed_json();
+    let (head, tail) = json.split_at(json.len() / 2);
+    let mut compressed = zstd::encode_all(head, 0).unwrap();
+    compressed.extend(zstd::encode_all(tail, 0).unwrap());
+    assert_eq!(Compression::Zstd.decompress(&compressed).unwrap(), json);
+
+    compressed.extend(b"trailing junk");
+    assert_eq!(Compression::Zstd.decompress(&compressed).unwrap(), json);
+
+    assert!(Compression::Zstd.decompress(&json).is_err());
+    assert!(Compression::Zstd
+        .decompress(&compressed[..compressed.len() / 3])
+        .is_err());
+}
+
+#[test]
+fn test_selecting_compression() {
+    assert_eq!("gzip".parse(), Ok(Compression::Gzip));
+    assert_eq!(".GZ".parse(), Ok(Compression::Gzip));
+    assert_eq!("zip".parse(), Ok(Compression::Zip));
+    assert_eq!(
+        "bzip2".parse::<Compression>(),
+        Err(CompressionError::UnknownFormat("bzip2".to_string()))
+    );
+
+    assert_eq!(
+        Compression::from_path("nvdcve-1.1-recent.json.gz"),
+        Some(Compression::Gzip)
+    );
+    assert_eq!(Compression::from_path("nvdcve-1.1-recent.meta"), None);
+
+    assert_eq!(
+        FeedName::Recent.compressed_feed_filename(Compression::Zip),
+        "nvdcve-1.1-recent.json.zip"
+    );
+
+    #[cfg(feature = "zstd")]
+    assert_eq!(
+        Compression::from_path("nvdcve-1.1-2021.json.zst"),
+        Some(Compression::Zstd)
+    );
+
+    #[cfg(not(feature = "zstd"))]
+    assert_eq!(
+        "zst".parse::<Compression>(),
+        Err(CompressionError::UnsupportedFormat("zst".to_string()))
+    );
+
+    // Without the feature, the format is known but fails to compress or decompress
+    #[cfg(not(feature = "zstd"))]
+    assert_eq!(
+        Compression::Zstd.decompress(b"").unwrap_err().kind(),
+        std::io::ErrorKind::Unsupported
+    );
+    #[cfg(not(feature = "zstd"))]
+    assert!(Compression::Zstd
+        .compress(b"{}", "nvdcve-1.1-custom.json")
+        .is_err());
+}
@@ -0,0 +1,296 @@
+use nvd_cve::cpe::{Cpe, CpeError, CpeValue};
+
+fn value(wfn: &str) -> CpeValue {
+    CpeValue::Value(wfn.to_string())
+}
+
+#[test]
+fn test_bind_cpe() {
+    // Examples of NISTIR 7695 binding well-formed names to URIs and formatted strings
+    let cpe = Cpe {
+        part: value("a"),
+        vendor: value("microsoft"),
+        product: value("internet_explorer"),
+        version: value(r"8\.0\.6001"),
+        update: value("beta"),
+        ..Default::default()
+    };
+    assert_eq!(
+        cpe.to_uri(),
+        "cpe:/a:microsoft:internet_explorer:8.0.6001:beta"
+    );
+    assert_eq!(
+        cpe.to_string(),
+        "cpe:2.3:a:microsoft:internet_explorer:8.0.6001:beta:*:*:*:*:*:*"
+    );
+
+    let cpe = Cpe {
+        version: value(r"8\.*"),
+        update: value("sp?"),
+        ..cpe
+    };
+    assert_eq!(
+        cpe.to_uri(),
+        "cpe:/a:microsoft:internet_explorer:8.%02:sp%01"
+    );
+    assert_eq!(
+        cpe.to_string(),
+        "cpe:2.3:a:microsoft:internet_explorer:8.*:sp?:*:*:*:*:*:*"
+    );
+
+    let cpe = Cpe {
+        part: value("a"),
+        vendor: value("hp"),
+        product: value("insight_diagnostics"),
+        version: value(r"7\.4\.0\.1570"),
+        update: CpeValue::NotApplicable,
+        sw_edition: value("online"),
+        target_sw: value("win2003"),
+        target_hw: value("x64"),
+        ..Default::default()
+    };
+    assert_eq!(
+        cpe.to_uri(),
+        "cpe:/a:hp:insight_diagnostics:7.4.0.1570:-:~~online~win2003~x64~"
+    );
+    assert_eq!(
+        cpe.to_string(),
+        "cpe:2.3:a:hp:insight_diagnostics:7.4.0.1570:-:*:*:online:win2003:x64:*"
+    );
+
+    let cpe = Cpe {
+        part: value("a"),
+        vendor: value(r"foo\\bar"),
+        product: value(r"big\$money_manager_2010"),
+        sw_edition: value("special"),
+        target_sw: value("ipod_touch"),
+        target_hw: value("80gb"),
+        ..Default::default()
+    };
+    assert_eq!(
+        cpe.to_uri(),
+        "cpe:/a:foo%5cbar:big%24money_manager_2010:::~~special~ipod_touch~80gb~"
+    );
+    assert_eq!(
+        cpe.to_string(),
+        r"cpe:2.3:a:foo\\bar:big\$money_manager_2010:*:*:*:*:special:ipod_touch:80gb:*"
+    );
+
+    // A lone hyphen isn't NA
+    let cpe = Cpe {
+        update: CpeValue::literal("-"),
+        ..Default::default()
+    };
+    assert_eq!(cpe.to_string(), r"cpe:2.3:*:*:*:*:\-:*:*:*:*:*:*");
+    assert_eq!(cpe.to_uri(), "cpe:/::::%2d");
+    assert_eq!(cpe.to_string().parse::<Cpe>().unwrap(), cpe);
+    assert_eq!(cpe.to_uri().parse::<Cpe>().unwrap(), cpe);
+}
+
+#[test]
+fn test_parse_formatted_string() {
+    let cpe = Cpe::from_formatted_string(
+        "cpe:2.3:a:microsoft:internet_explorer:8.0.6001:beta:*:*:*:*:*:*",
+    )
+    .unwrap();
+    assert_eq!(cpe.part, value("a"));
+    assert_eq!(cpe.product, value("internet_explorer"));
+    assert_eq!(cpe.version, value(r"8\.0\.6001"));
+    assert_eq!(cpe.version.unquoted().as_deref(), Some("8.0.6001"));
+    assert_eq!(cpe.update, value("beta"));
+    assert_eq!(cpe.edition, CpeValue::Any);
+    assert_eq!(cpe.other, CpeValue::Any);
+
+    let cpe: Cpe = "cpe:2.3:a:microsoft:internet_explorer:8.*:sp?:*:*:*:*:*:*"
+        .parse()
+        .unwrap();
+    assert_eq!(cpe.version, value(r"8\.*"));
+    assert_eq!(cpe.update, value("sp?"));
+    assert!(cpe.version.has_wildcards());
+    assert!(!CpeValue::literal("8.*").has_wildcards());
+
+    let cpe: Cpe = r"cpe:2.3:a:foo\\bar:big\$money:2010:*:*:*:special:ipod_touch:80gb:*"
+        .parse()
+        .unwrap();
+    assert_eq!(cpe.vendor, value(r"foo\\bar"));
+    assert_eq!(cpe.product, value(r"big\$money"));
+    assert_eq!(cpe.sw_edition, value("special"));
+    assert_eq!(cpe.target_hw, value("80gb"));
+
+    // Quoted colons are part of the values
+    let cpe: Cpe = r"cpe:2.3:a:vendor:product\:name:1.0:*:*:*:*:*:*:*"
+        .parse()
+        .unwrap();
+    assert_eq!(cpe.product.unquoted().as_deref(), Some("product:name"));
+    assert_eq!(cpe.version, value(r"1\.0"));
+
+    let cpe: Cpe = "cpe:2.3:a:hp:insight_diagnostics:8.0~:*:*:*:*:*:*:*"
+        .parse()
+        .unwrap();
+    assert_eq!(cpe.version, value(r"8\.0\~"));
+    assert_eq!(cpe.update, CpeValue::Any);
+
+    for (name, error) in [
+        (
+            "cpe:2.3:a:hp:insight_diagnostics:7.4.*.1570:*:*:*:*:*:*:*",
+            CpeError::InvalidValue("7.4.*.1570".to_string()),
+        ),
+        (
+            "cpe:2.3:a:hp:insight_diagnostics:7.4.0.1570:*:*:*:*:*:*",
+            CpeError::InvalidComponentCount(10),
+        ),
+        (
+            "cpe:2.3:x:hp:insight_diagnostics:*:*:*:*:*:*:*:*",
+            CpeError::InvalidPart("x".to_string()),
+        ),
+        (
+            "cpe:2.3:a:hp:insight diagnostics:*:*:*:*:*:*:*:*",
+            CpeError::InvalidValue("insight diagnostics".to_string()),
+        ),
+        (
+            "cpe:2.3:a:hp:insight_diagnostics:1?2:*:*:*:*:*:*:*",
+            CpeError::InvalidValue("1?2".to_string()),
+        ),
+        (
+            "cpe:2.3:*:0:a??a:A:~:A:a:a:a:0:A",
+            CpeError::InvalidValue("a??a".to_string()),
+        ),
+        (
+            "cpe:2.3:o:*:a:_??!:!:a:*:-:!:$:a",
+            CpeError::InvalidValue("_??!".to_string()),
+        ),
+        (
+            "cpe:2.3:a:hp::*:*:*:*:*:*:*:*",
+            CpeError::InvalidValue(String::new()),
+        ),
+        (
+            "cpe:2.2:a:hp:insight_diagnostics",
+            CpeError::UnknownBinding("cpe:2.2:a:hp:insight_diagnostics".to_string()),
+        ),
+    ] {
+        assert_eq!(name.parse::<Cpe>(), Err(error), "{}", name);
+    }
+}
+
+#[test]
+fn test_parse_uri() {
+    let cpe = Cpe::from_uri("cpe:/a:microsoft:internet_explorer:8.0.6001:beta").unwrap();
+    assert_eq!(
+        cpe.to_string(),
+        "cpe:2.3:a:microsoft:internet_explorer:8.0.6001:beta:*:*:*:*:*:*"
+    );
+
+    let cpe: Cpe = "cpe:/a:Microsoft:Internet_Explorer:8.%2a:sp%3f"
+        .parse()
+        .unwrap();
+    assert_eq!(cpe.vendor, value("microsoft"));
+    assert_eq!(cpe.version, value(r"8\.\*"));
+    assert_eq!(cpe.update, value(r"sp\?"));
+    assert!(!cpe.version.has_wildcards());
+
+    let cpe: Cpe = "cpe:/a:microsoft:internet_explorer:8.%02:sp%01"
+        .parse()
+        .unwrap();
+    assert_eq!(cpe.version, value(r"8\.*"));
+    assert_eq!(cpe.update, value("sp?"));
+
+    let cpe: Cpe = "cpe:/a:hp:insight_diagnostics:7.4.0.1570::~~online~win2003~x64~"
+        .parse()
+        .unwrap();
+    assert_eq!(cpe.update, CpeValue::Any);
+    assert_eq!(cpe.edition, CpeValue::Any);
+    assert_eq!(cpe.sw_edition, value("online"));
+    assert_eq!(cpe.target_sw, value("win2003"));
+    assert_eq!(cpe.target_hw, value("x64"));
+    assert_eq!(cpe.other, CpeValue::Any);
+    assert_eq!(
+        cpe.to_uri(),
+        "cpe:/a:hp:insight_diagnostics:7.4.0.1570::~~online~win2003~x64~"
+    );
+
+    // Language follows the packed edition in URIs, but comes before the extended attributes
+    let cpe: Cpe = "cpe:/a:hp:openview_network_manager:7.51:-:~~~linux~~:en-us"
+        .parse()
+        .unwrap();
+    assert_eq!(cpe.update, CpeValue::NotApplicable);
+    assert_eq!(cpe.language, value(r"en\-us"));
+    assert_eq!(cpe.target_sw, value("linux"));
+    assert_eq!(
+        cpe.to_string(),
+        "cpe:2.3:a:hp:openview_network_manager:7.51:-:*:en-us:*:linux:*:*"
+    );
+
+    // Editions with tildes that don't pack the extended attributes are legacy editions
+    let cpe: Cpe = "cpe:/a:vendor:product:1.0:-:a~b".parse().unwrap();
+    assert_eq!(cpe.edition, value(r"a\~b"));
+
+    for (name, error) in [
+        (
+            "cpe:/a:foo%5cbar:big%24money_2010%07:::~~special~ipod_touch~80gb~",
+            CpeError::InvalidValue("big%24money_2010%07".to_string()),
+        ),
+        (
+            "cpe:/a:microsoft:internet_explorer:8.%02.1",
+            CpeError::InvalidValue("8.%02.1".to_string()),
+        ),
+        (
+            "cpe:/a:microsoft:internet_explorer:8.0:sp%2",
+            CpeError::InvalidValue("sp%2".to_string()),
+        ),
+        (
+            "cpe:/a:hp:insight_diagnostics:7.4.0.1570::~~online~win2003~x64",
+            CpeError::InvalidValue("~~online~win2003~x64".to_string()),
+        ),
+        (
+            "cpe:/a:hp:insight_diagnostics:7.4.0.1570::~~online~win2003~x64~:en:extra",
+            CpeError::InvalidComponentCount(8),
+        ),
+        (
+            "cpe:/a:hp:insight$diagnostics",
+            CpeError::InvalidValue("insight$diagnostics".to_string()),
+        ),
+    ] {
+        assert_eq!(name.parse::<Cpe>(), Err(error), "{}", name);
+    }
+}
+
+#[test]
+fn test_match_cpe() {
+    let installed: Cpe = "cpe:2.3:a:openssl:openssl:1.1.1k:*:*:*:*:*:*:*"
+        .parse()
+        .unwrap();
+
+    for (source, matches) in [
+        ("cpe:2.3:a:openssl:openssl:*:*:*:*:*:*:*:*", true),
+        ("cpe:2.3:a:openssl:openssl:1.1.1k:*:*:*:*:*:*:*", true),
+        ("cpe:2.3:a:OpenSSL:OpenSSL:1.1.1K:*:*:*:*:*:*:*", true),
+        ("cpe:2.3:a:openssl:openssl:1.1.1*:*:*:*:*:*:*:*", true),
+        ("cpe:2.3:a:openssl:openssl:*1k:*:*:*:*:*:*:*", true),
+        ("cpe:2.3:a:openssl:openssl:1.1.1?:*:*:*:*:*:*:*", true),
+        ("cpe:2.3:a:openssl:openssl:1.1.1k?:*:*:*:*:*:*:*", true),
+        ("cpe:2.3:a:openssl:openssl:1.1.?:*:*:*:*:*:*:*", false),
+        ("cpe:2.3:a:openssl:openssl:1.1.1:*:*:*:*:*:*:*", false),
+        ("cpe:2.3:a:openssl:openssl:1.1.1k:-:*:*:*:*:*:*", false),
+        ("cpe:2.3:o:openssl:openssl:*:*:*:*:*:*:*:*", false),
+        ("cpe:/a:openssl", true),
+        ("cpe:/a:openssl:openssl:1.1.1%02", true),
+    ] {
+        let source: Cpe = source.parse().unwrap();
+        assert_eq!(source.matches(&installed), matches, "{}", source);
+    }
+
+    // Only ANY matches a range of versions
+    let range: Cpe = "cpe:2.3:a:openssl:openssl:1.1.*:*:*:*:*:*:*:*"
+        .parse()
+        .unwrap();
+    assert!(!installed.matches(&range));
+    assert!(!range.matches(&range));
+    assert!(Cpe::default().matches(&range));
+
+    assert!(CpeValue::NotApplicable.matches(&CpeValue::NotApplicable));
+    assert!(!CpeValue::NotApplicable.matches(&CpeValue::Any));
+    assert!(!CpeValue::literal("beta").matches(&CpeValue::NotApplicable));
+    assert!(value("??ta").matches(&value("beta")));
+    assert!(value("??ta").matches(&value("ta")));
+    assert!(!value("?ta").matches(&value("beta")));
+}
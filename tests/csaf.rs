@@ -0,0 +1,217 @@
+#![cfg(feature = "blocking")]
+
+use chrono::{TimeZone, Utc};
+use nvd_cve::aliases::{get_aliases, resolve_alias, Alias};
+use nvd_cve::cache::CacheConfig;
+use nvd_cve::client::{BlockingHttpClient, ReqwestBlockingClient};
+use nvd_cve::cpe::Cpe;
+use nvd_cve::csaf::{
+    fetch_provider_advisories, ingest_advisories, Advisory, AdvisoryStatus, CsafError,
+    IngestReport, VendorStatus,
+};
+use nvd_cve::overrides::{get_override, set_override, CveOverride};
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs;
+mod util;
+use util::MockFeedServer;
+
+fn sample_advisory() -> Advisory {
+    let json = fs::read("./tests/files/csaf/rhsa-2023_1405.json").expect("Failed reading advisory");
+    Advisory::from_json(&json).expect("Failed parsing advisory")
+}
+
+fn cpes(names: &[&str]) -> Vec<Cpe> {
+    names.iter().map(|name| name.parse().unwrap()).collect()
+}
+
+#[test]
+fn test_parse_advisory() {
+    let advisory = sample_advisory();
+    assert_eq!(advisory.id(), "RHSA-2023:1405");
+    assert_eq!(advisory.vulnerabilities.len(), 4);
+
+    let aliases = advisory.aliases();
+    let alias = |alias: &str, cve: &str, source: &str| Alias {
+        alias: alias.to_string(),
+        cve: cve.to_string(),
+        source: source.to_string(),
+    };
+    assert_eq!(
+        aliases,
+        vec![
+            alias("2164440", "CVE-2023-0286", "Red Hat Bugzilla ID"),
+            alias("2164494", "CVE-2023-0215", "Red Hat Bugzilla ID"),
+            alias(
+                "RHSA-2023:1405",
+                "CVE-2022-4304",
+                "Red Hat Product Security"
+            ),
+            alias(
+                "RHSA-2023:1405",
+                "CVE-2023-0215",
+                "Red Hat Product Security"
+            ),
+            alias(
+                "RHSA-2023:1405",
+                "CVE-2023-0286",
+                "Red Hat Product Security"
+            ),
+        ]
+    );
+
+    // Products composed of a product run are run too
+    let rhel9 =
+        "openssl-1:3.0.7-6.el9_1.x86_64 as a component of Red Hat Enterprise Linux BaseOS (v. 9)";
+    let statuses = advisory.statuses(&cpes(&["cpe:/o:redhat:enterprise_linux:9"]));
+    let status = |cve: &str, status: VendorStatus| AdvisoryStatus {
+        advisory: "RHSA-2023:1405".to_string(),
+        cve: cve.to_string(),
+        product: rhel9.to_string(),
+        status,
+    };
+    assert_eq!(
+        statuses,
+        vec![
+            status("CVE-2023-0215", VendorStatus::Fixed),
+            status("CVE-2023-0286", VendorStatus::Fixed),
+        ]
+    );
+
+    // Without platforms, every product is
+    assert_eq!(advisory.statuses(&[]).len(), 5);
+    assert!(advisory
+        .statuses(&cpes(&["cpe:2.3:o:microsoft:windows_10:*:*:*:*:*:*:*:*"]))
+        .is_empty());
+
+    assert_eq!("known_affected".parse(), Ok(VendorStatus::KnownAffected));
+    assert_eq!(VendorStatus::UnderInvestigation.affected(), None);
+    assert!("affected".parse::<VendorStatus>().is_err());
+
+    let mut json: serde_json::Value =
+        serde_json::from_slice(&fs::read("./tests/files/csaf/rhsa-2023_1405.json").unwrap())
+            .unwrap();
+    json["document"]["csaf_version"] = json!("1.2");
+    assert!(matches!(
+        Advisory::from_json(json.to_string().as_bytes()),
+        Err(CsafError::InvalidDocument(_))
+    ));
+}
+
+#[test]
+fn test_ingest_advisories() {
+    let config = CacheConfig {
+        db: "./tests/files/.cache/nvd/csaf.sqlite3".to_string(),
+        show_progress: false,
+        ..Default::default()
+    };
+    fs::remove_file(&config.db).ok();
+
+    let advisory = sample_advisory();
+    let analyst = CveOverride {
+        affected: Some(true),
+        note: Some("Statically linked in the appliance firmware".to_string()),
+        ..Default::default()
+    };
+    set_override(&config, "CVE-2023-0215", &analyst).unwrap();
+
+    let report = ingest_advisories(
+        &config,
+        std::slice::from_ref(&advisory),
+        &cpes(&["cpe:/o:redhat:enterprise_linux:9"]),
+    )
+    .unwrap();
+    assert_eq!(
+        report,
+        IngestReport {
+            aliases: 5,
+            statuses: 2,
+            overrides: vec!["CVE-2023-0286".to_string()],
+            kept: vec!["CVE-2023-0215".to_string()],
+        }
+    );
+
+    let ingested = get_override(&config, "CVE-2023-0286").unwrap().unwrap();
+    assert_eq!(ingested.affected, Some(false));
+    assert_eq!(ingested.advisory.as_deref(), Some("RHSA-2023:1405"));
+    assert!(ingested
+        .note
+        .unwrap()
+        .starts_with("RHSA-2023:1405: fixed openssl-1:3.0.7-6.el9_1.x86_64"));
+    assert_eq!(
+        get_override(&config, "CVE-2023-0215")
+            .unwrap()
+            .unwrap()
+            .note,
+        analyst.note
+    );
+
+    assert_eq!(
+        resolve_alias(&config, "RHSA-2023:1405").unwrap(),
+        vec!["CVE-2022-4304", "CVE-2023-0215", "CVE-2023-0286"]
+    );
+    assert_eq!(
+        get_aliases(&config, "CVE-2023-0286")
+            .unwrap()
+            .iter()
+            .map(|alias| alias.alias.as_str())
+            .collect::<Vec<_>>(),
+        vec!["2164440", "RHSA-2023:1405"]
+    );
+    assert!(resolve_alias(&config, "RHSA-1999:0001").unwrap().is_empty());
+
+    // Ingesting the advisory again replaces its statuses, RHEL 8 is still affected
+    let report = ingest_advisories(
+        &config,
+        &[advisory],
+        &cpes(&["cpe:/o:redhat:enterprise_linux:8"]),
+    )
+    .unwrap();
+    assert_eq!(report.statuses, 3);
+    assert_eq!(report.overrides, vec!["CVE-2023-0286".to_string()]);
+    let ingested = get_override(&config, "CVE-2023-0286").unwrap().unwrap();
+    assert_eq!(ingested.affected, Some(true));
+    assert!(!ingested.note.unwrap().contains("el9"));
+
+    // Products under investigation aren't enough to override a CVE
+    assert_eq!(get_override(&config, "CVE-2022-4304").unwrap(), None);
+
+    let frozen = CacheConfig {
+        read_only: true,
+        ..config.clone()
+    };
+    assert!(ingest_advisories(&frozen, &[sample_advisory()], &[]).is_err());
+
+    fs::remove_file(&config.db).ok();
+}
+
+#[test]
+fn test_fetch_provider_advisories() {
+    let advisory = fs::read("./tests/files/csaf/rhsa-2023_1405.json").unwrap();
+    let metadata = json!({
+        "canonical_url": "https://security.example.com/provider-metadata.json",
+        "distributions": [{"directory_url": "advisories/"}],
+        "metadata_version": "2.0",
+        "role": "csaf_provider"
+    });
+    let changes = "\"2023/rhsa-2023_1405.json\",\"2023-03-22T10:40:00+00:00\"\n\
+        \"2022/rhsa-2022_0001.json\",\"2022-01-04T08:00:00+00:00\"\n";
+
+    let server = MockFeedServer::start(HashMap::from([
+        (
+            "provider-metadata.json".to_string(),
+            metadata.to_string().into_bytes(),
+        ),
+        ("changes.csv".to_string(), changes.as_bytes().to_vec()),
+        ("rhsa-2023_1405.json".to_string(), advisory),
+    ]));
+    let metadata_url = format!("{}provider-metadata.json", server.url);
+    let client = ReqwestBlockingClient::new(&metadata_url, None, None, None);
+
+    let since = Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap();
+    let advisories = fetch_provider_advisories(&client, &metadata_url, Some(since)).unwrap();
+    assert_eq!(advisories, vec![sample_advisory()]);
+
+    // The advisory of 2022 isn't served, so fetching every advisory fails
+    assert!(fetch_provider_advisories(&client, &metadata_url, None).is_err());
+}
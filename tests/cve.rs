@@ -2,9 +2,21 @@ use std::fs;
 
 mod util;
 
-use nvd_cve::cve::CveFeed;
+use chrono::{DateTime, Utc};
+use nvd_cve::clock::ManualClock;
+use nvd_cve::compression::Compression;
+use nvd_cve::cve::{Configuration, CveContainer, CveFeed, Operator};
+use nvd_cve::cvss::Severity;
+use nvd_cve::datetime::format_datetime;
+#[cfg(feature = "blocking")]
+use nvd_cve::feed::FeedName;
+use nvd_cve::filter::FilteredFeed;
+use nvd_cve::query::SearchQuery;
+use std::sync::Arc;
+#[cfg(feature = "blocking")]
 use util::MockBlockingClient;
 
+#[cfg(feature = "blocking")]
 #[test]
 fn test_get_feed_from_client() {
     let mut client = MockBlockingClient::default();
@@ -12,10 +24,230 @@ fn test_get_feed_from_client() {
     let body = fs::read_to_string("./tests/files/nvdcve-1.1-recent.json")
         .expect("Failed reading feed json");
 
-    let cve_feed: CveFeed = serde_json::from_str(&*body).expect("Failed parsing cve feed json");
+    let cve_feed: CveFeed = serde_json::from_str(&body).expect("Failed parsing cve feed json");
     client.get_feed_response = Ok(cve_feed);
 
-    if let Err(error) = CveFeed::from_blocking_http_client(&client, "recent") {
-        assert!(false, "Failed fetching CveFeed: {:?}", error);
+    if let Err(error) = CveFeed::from_blocking_http_client(&client, &FeedName::Recent) {
+        panic!("Failed fetching CveFeed: {:?}", error);
     }
 }
+
+#[test]
+fn test_parse_feed_in_parallel() {
+    let json = fs::read("./tests/files/nvdcve-1.1-recent.json").expect("Failed reading feed json");
+    let serial: CveFeed = serde_json::from_slice(&json).expect("Failed parsing cve feed json");
+    let serial = serde_json::to_value(serial).unwrap();
+
+    for threads in [0, 1, 3, 1000] {
+        let parallel =
+            CveFeed::from_slice_parallel(&json, threads).expect("Failed parsing cve feed json");
+        assert_eq!(serde_json::to_value(parallel).unwrap(), serial);
+    }
+
+    // A malformed item fails the whole feed
+    let body = String::from_utf8(json).unwrap();
+    let malformed = body.replacen("\"cve\" : {", "\"cve\" : 42, \"x\" : {", 1);
+    assert_ne!(malformed, body);
+    assert!(CveFeed::from_slice_parallel(malformed.as_bytes(), 2).is_err());
+}
+
+#[test]
+fn test_find_feed_item() {
+    let body = fs::read_to_string("./tests/files/nvdcve-1.1-sample.json")
+        .expect("Failed reading feed json");
+    let cve_feed: CveFeed = serde_json::from_str(&body).expect("Failed parsing cve feed json");
+
+    let item = cve_feed.find("cve-2021-44228").expect("CVE not found");
+    assert_eq!(item.cve.cve_data_meta.id, "CVE-2021-44228");
+    assert!(cve_feed.find("CVE-2021-4422").is_none());
+}
+
+#[test]
+fn test_severity() {
+    assert_eq!("critical".parse(), Ok(Severity::Critical));
+    assert_eq!("Medium".parse(), Ok(Severity::Medium));
+    assert!("severe".parse::<Severity>().is_err());
+    assert_eq!(Severity::High.to_string(), "HIGH");
+
+    assert!(Severity::None < Severity::Low);
+    assert!(Severity::High < Severity::Critical);
+    assert_eq!(Severity::ALL.iter().max(), Some(&Severity::Critical));
+
+    assert_eq!(Severity::from_v3_score(0.0), Severity::None);
+    assert_eq!(Severity::from_v3_score(3.9), Severity::Low);
+    assert_eq!(Severity::from_v3_score(4.0), Severity::Medium);
+    assert_eq!(Severity::from_v3_score(8.9), Severity::High);
+    assert_eq!(Severity::from_v3_score(9.0), Severity::Critical);
+    assert_eq!(Severity::from_v2_score(0.0), Severity::Low);
+    assert_eq!(Severity::from_v2_score(6.9), Severity::Medium);
+    assert_eq!(Severity::from_v2_score(10.0), Severity::High);
+
+    assert_eq!(
+        serde_json::to_string(&Severity::Medium).unwrap(),
+        "\"MEDIUM\""
+    );
+    assert_eq!(
+        serde_json::from_str::<Severity>("\"LOW\"").unwrap(),
+        Severity::Low
+    );
+}
+
+#[test]
+fn test_impact() {
+    let body = fs::read_to_string("./tests/files/nvdcve-1.1-sample.json")
+        .expect("Failed reading feed json");
+    let cve_feed: CveFeed = serde_json::from_str(&body).expect("Failed parsing cve feed json");
+
+    let heartbleed = &cve_feed.find("CVE-2014-0160").unwrap().impact;
+    assert!(heartbleed.base_metric_v3.is_none());
+    let metric = heartbleed.base_metric_v2.as_ref().unwrap();
+    assert_eq!(metric.cvss_v2.vector_string, "AV:N/AC:L/Au:N/C:P/I:N/A:N");
+    assert_eq!(metric.cvss_v2.base_score, 5.0);
+    assert_eq!(metric.severity.as_deref(), Some("MEDIUM"));
+    assert_eq!(metric.ac_insuf_info, Some(false));
+
+    let crock_pot = cve_feed.find("CVE-2019-12780").unwrap();
+    let metric = crock_pot.impact.base_metric_v3.as_ref().unwrap();
+    assert_eq!(
+        metric.cvss_v3.vector_string,
+        "CVSS:3.0/AV:A/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H"
+    );
+    assert_eq!(metric.impact_score, 5.9);
+    assert_eq!(crock_pot.severity(), Some((Severity::High, 8.8)));
+
+    // Metrics survive a round trip through the crate's own serialization
+    let json = serde_json::to_value(crock_pot).unwrap();
+    assert_eq!(
+        json["impact"]["base_metric_v2"]["cvss_v2"]["base_score"],
+        8.3
+    );
+    let parsed: CveContainer = serde_json::from_value(json).unwrap();
+    assert_eq!(parsed.severity(), Some((Severity::High, 8.8)));
+
+    // An invalid metric is dropped rather than failing the whole item
+    let malformed = body.replacen("\"ADJACENT_NETWORK\"", "\"SATELLITE\"", 1);
+    assert_ne!(malformed, body);
+    let cve_feed: CveFeed = serde_json::from_str(&malformed).unwrap();
+    let crock_pot = cve_feed.find("CVE-2019-12780").unwrap();
+    assert!(crock_pot.impact.base_metric_v3.is_none());
+    assert_eq!(crock_pot.severity(), Some((Severity::High, 8.3)));
+}
+
+#[test]
+fn test_configurations() {
+    let body = fs::read_to_string("./tests/files/nvdcve-1.1-sample.json")
+        .expect("Failed reading feed json");
+    let cve_feed: CveFeed = serde_json::from_str(&body).expect("Failed parsing cve feed json");
+
+    let crock_pot = &cve_feed.find("CVE-2019-12780").unwrap().configurations;
+    let node = &crock_pot.nodes[0];
+    assert_eq!(node.operator, Operator::And);
+    assert!(!node.negate);
+    assert!(node.cpe_match.is_empty());
+    assert_eq!(node.children.len(), 2);
+    assert_eq!(node.children[0].operator, Operator::Or);
+
+    // CPE matches are listed depth first, in the order of the nodes
+    let cpe_matches = crock_pot.cpe_matches();
+    assert_eq!(cpe_matches.len(), 2);
+    assert_eq!(
+        cpe_matches[0].cpe23_uri,
+        "cpe:2.3:o:belkin:wemo_crock-pot_firmware:2.00.11055:*:*:*:*:*:*:*"
+    );
+    assert!(cpe_matches[0].vulnerable);
+    assert!(!cpe_matches[1].vulnerable);
+    assert_eq!(
+        cpe_matches[0].cpe().unwrap().product.unquoted().as_deref(),
+        Some("wemo_crock-pot_firmware")
+    );
+    assert!(cpe_matches[0].range().is_unbounded());
+
+    let log4shell = &cve_feed.find("CVE-2021-44228").unwrap().configurations;
+    let range = log4shell.cpe_matches()[0].range();
+    assert_eq!(range.start_including.as_deref(), Some("2.0.1"));
+    assert_eq!(range.end_excluding.as_deref(), Some("2.3.1"));
+
+    // Configurations survive a round trip through the crate's own serialization
+    let json = serde_json::to_value(log4shell).unwrap();
+    assert_eq!(
+        json["nodes"][0]["cpe_match"][0]["version_start_including"],
+        "2.0.1"
+    );
+    assert!(json["nodes"][0].get("negate").is_none());
+    let parsed: Configuration = serde_json::from_value(json).unwrap();
+    assert_eq!(parsed.cpe_matches().len(), log4shell.cpe_matches().len());
+}
+
+#[test]
+fn test_filtered_feed() {
+    let json = fs::read("./tests/files/nvdcve-1.1-sample.json").expect("Failed reading feed json");
+
+    let query = SearchQuery {
+        text: Some("openssl".to_string()),
+        ..Default::default()
+    };
+    let mut filtered = FilteredFeed::new(query);
+    assert!(filtered.is_empty());
+
+    let matched = filtered.add_feed(&json).expect("Failed filtering feed");
+    assert_eq!(matched, 2);
+    assert_eq!(filtered.len(), 2);
+
+    // A later version of a CVE replaces it, and removes it once it no longer matches
+    let mut modified: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    modified["CVE_Items"] = serde_json::json!([modified["CVE_Items"][0].clone()]);
+    modified["CVE_Items"][0]["cve"]["description"]["description_data"][0]["value"] =
+        serde_json::json!("Heartbleed");
+    let matched = filtered
+        .add_feed(&serde_json::to_vec(&modified).unwrap())
+        .expect("Failed filtering feed");
+    assert_eq!(matched, 0);
+    assert_eq!(filtered.len(), 1);
+
+    let timestamp: DateTime<Utc> = "2024-05-01T12:30:00Z".parse().unwrap();
+    let feed: CveFeed = serde_json::from_slice(&filtered.to_json(timestamp)).unwrap();
+    assert_eq!(feed.cve_data_number_of_cves, "1");
+    assert_eq!(feed.cve_data_timestamp, "2024-05-01T12:30Z");
+    assert_eq!(feed.cve_items[0].cve.cve_data_meta.id, "CVE-2021-3711");
+
+    // Written feeds are dated by the clock of the feed, to the minute
+    let clock = ManualClock::new("2024-05-01T12:30:45Z".parse().unwrap());
+    let dir = "./tests/files/.cache/nvd/filtered_feed";
+    let metafile = filtered
+        .with_clock(Arc::new(clock))
+        .write_to_dir(dir, Compression::Gzip)
+        .expect("Failed writing feed");
+    assert_eq!(metafile.last_modified_date, timestamp);
+    fs::remove_dir_all(dir).expect("Failed removing test feed");
+}
+
+#[test]
+fn test_feed_item_dates() {
+    for path in [
+        "./tests/files/nvdcve-1.1-sample.json",
+        "./tests/files/nvdcve-1.1-recent.json",
+    ] {
+        let cve_feed = CveFeed::from_slice_parallel(&fs::read(path).unwrap(), 1).unwrap();
+        for item in &cve_feed.cve_items {
+            let published = item
+                .published()
+                .unwrap_or_else(|| panic!("{} has no publication date", item.cve.cve_data_meta.id));
+            let last_modified = item.last_modified().unwrap_or_else(|| {
+                panic!("{} has no last modified date", item.cve.cve_data_meta.id)
+            });
+            assert!(last_modified >= published);
+        }
+    }
+
+    let body = fs::read_to_string("./tests/files/nvdcve-1.1-sample.json").unwrap();
+    let cve_feed: CveFeed = serde_json::from_str(&body).unwrap();
+    let item = cve_feed.find("CVE-2021-44228").unwrap();
+    assert_eq!(
+        format_datetime(&item.published().unwrap()),
+        "2021-12-10T10:15:00Z"
+    );
+    assert_eq!(
+        format_datetime(&item.last_modified().unwrap()),
+        "2022-07-12T17:42:00Z"
+    );
+}
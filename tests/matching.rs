@@ -0,0 +1,178 @@
+use nvd_cve::cpe::Cpe;
+use nvd_cve::cve::{CpeMatch, CveContainer, CveFeed, Node};
+use nvd_cve::matching::{fixed_configurations, match_platforms, MatchConfidence};
+use nvd_cve::version::VersionScheme;
+use serde_json::json;
+use std::fs;
+
+fn sample_cve(id: &str) -> CveContainer {
+    let json = fs::read("./tests/files/nvdcve-1.1-sample.json").expect("Failed reading feed json");
+    let feed: CveFeed = serde_json::from_slice(&json).expect("Failed parsing cve feed json");
+    feed.cve_items
+        .into_iter()
+        .find(|item| item.cve.cve_data_meta.id == id)
+        .expect("CVE missing from the sample feed")
+}
+
+fn cpes(names: &[&str]) -> Vec<Cpe> {
+    names.iter().map(|name| name.parse().unwrap()).collect()
+}
+
+#[test]
+fn test_match_platforms() {
+    let log4shell = sample_cve("CVE-2021-44228");
+
+    let platforms = cpes(&[
+        "cpe:2.3:a:openssl:openssl:1.1.1k:*:*:*:*:*:*:*",
+        "cpe:2.3:a:apache:log4j:2.14.1:*:*:*:*:*:*:*",
+    ]);
+    let applicability = match_platforms(&log4shell, &platforms, VersionScheme::Auto).unwrap();
+    assert_eq!(applicability.id, "CVE-2021-44228");
+    assert_eq!(applicability.matches.len(), 1);
+    assert_eq!(applicability.matches[0].platform, platforms[1]);
+    assert_eq!(
+        applicability.matches[0].range.end_excluding.as_deref(),
+        Some("2.15.0")
+    );
+    assert_eq!(
+        applicability.confidence(),
+        Some(MatchConfidence::VersionRange)
+    );
+
+    // Versions are compared rather than their strings, 2.2 is before 2.12.2
+    let applicability = match_platforms(
+        &log4shell,
+        &cpes(&["cpe:2.3:a:apache:log4j:2.2:*:*:*:*:*:*:*"]),
+        VersionScheme::Auto,
+    );
+    assert!(applicability.is_some());
+
+    let applicability = match_platforms(
+        &log4shell,
+        &cpes(&["cpe:2.3:a:apache:log4j:2.0:beta9:*:*:*:*:*:*"]),
+        VersionScheme::Auto,
+    )
+    .unwrap();
+    assert_eq!(
+        applicability.matches[0].criteria,
+        "cpe:2.3:a:apache:log4j:2.0:beta9:*:*:*:*:*:*"
+    );
+    assert_eq!(applicability.confidence(), Some(MatchConfidence::Exact));
+    assert_eq!(MatchConfidence::Exact.warning(), None);
+
+    for version in ["2.15.0", "2.3.1", "1.2.17"] {
+        let platform = format!("cpe:2.3:a:apache:log4j:{}:*:*:*:*:*:*:*", version);
+        assert_eq!(
+            match_platforms(&log4shell, &cpes(&[&platform]), VersionScheme::Auto),
+            None,
+            "{}",
+            version
+        );
+    }
+
+    // Platforms of unknown versions don't match version ranges
+    let applicability = match_platforms(
+        &log4shell,
+        &cpes(&["cpe:2.3:a:apache:log4j:*:*:*:*:*:*:*:*"]),
+        VersionScheme::Auto,
+    );
+    assert_eq!(applicability, None);
+
+    let heartbleed = sample_cve("CVE-2014-0160");
+    let platforms = cpes(&["cpe:/a:openssl:openssl:1.0.1f"]);
+    assert!(match_platforms(&heartbleed, &platforms, VersionScheme::Auto).is_some());
+    let platforms = cpes(&["cpe:/a:openssl:openssl:1.0.1g"]);
+    assert!(match_platforms(&heartbleed, &platforms, VersionScheme::Auto).is_none());
+}
+
+#[test]
+fn test_match_running_on_configuration() {
+    // The firmware is only vulnerable running on the hardware
+    let crock_pot = sample_cve("CVE-2019-12780");
+    let firmware = "cpe:2.3:o:belkin:wemo_crock-pot_firmware:2.00.11055:*:*:*:*:*:*:*";
+    let hardware = "cpe:2.3:h:belkin:wemo_crock-pot:-:*:*:*:*:*:*:*";
+
+    assert_eq!(
+        match_platforms(&crock_pot, &cpes(&[firmware]), VersionScheme::Auto),
+        None
+    );
+
+    let applicability = match_platforms(
+        &crock_pot,
+        &cpes(&[hardware, firmware]),
+        VersionScheme::Auto,
+    )
+    .unwrap();
+    // The hardware isn't vulnerable by itself
+    assert_eq!(applicability.matches.len(), 1);
+    assert_eq!(applicability.matches[0].criteria, firmware);
+    assert_eq!(applicability.confidence(), Some(MatchConfidence::Exact));
+}
+
+#[test]
+fn test_match_version_wildcard() {
+    let mut cve = sample_cve("CVE-2021-3711");
+    cve.configurations.nodes = vec![serde_json::from_value(json!({
+        "operator": "OR",
+        "children": [],
+        "cpe_match": [
+            {"vulnerable": true, "cpe23Uri": "cpe:2.3:a:openssl:openssl:*:*:*:*:*:*:*:*"},
+            {"vulnerable": true, "cpe23Uri": "cpe:2.3:a:openssl:openssl:1.1.*:*:*:*:*:*:*:*"},
+        ]
+    }))
+    .unwrap()];
+
+    let platforms = cpes(&["cpe:2.3:a:openssl:openssl:1.1.1k:*:*:*:*:*:*:*"]);
+    let applicability = match_platforms(&cve, &platforms, VersionScheme::Auto).unwrap();
+    assert_eq!(applicability.matches.len(), 2);
+    for platform_match in &applicability.matches {
+        assert_eq!(platform_match.confidence, MatchConfidence::VersionWildcard);
+        assert!(platform_match.range.is_unbounded());
+    }
+    assert_eq!(
+        applicability.confidence().and_then(|c| c.warning()),
+        Some("version wildcard \u{2014} verify manually")
+    );
+
+    // A single exact match is enough to trust the CVE applies
+    cve.configurations.nodes.push(Node {
+        cpe_match: vec![CpeMatch {
+            cpe23_uri: "cpe:2.3:a:openssl:openssl:1.1.1k:*:*:*:*:*:*:*".to_string(),
+            ..Default::default()
+        }],
+        ..Default::default()
+    });
+    let applicability = match_platforms(&cve, &platforms, VersionScheme::Auto).unwrap();
+    assert_eq!(applicability.matches.len(), 3);
+    assert_eq!(applicability.confidence(), Some(MatchConfidence::Exact));
+
+    assert_eq!(
+        serde_json::to_value(MatchConfidence::VersionWildcard).unwrap(),
+        "version-wildcard"
+    );
+}
+
+#[test]
+fn test_fixed_configurations() {
+    let log4shell = sample_cve("CVE-2021-44228");
+    let fixed = |version: &str| {
+        let platform = format!("cpe:2.3:a:apache:log4j:{}:*:*:*:*:*:*:*", version);
+        fixed_configurations(
+            "CVE-2021-44228",
+            &log4shell.configurations,
+            &cpes(&[&platform]),
+            VersionScheme::Auto,
+        )
+    };
+
+    let applicability = fixed("2.17.1").unwrap();
+    assert_eq!(applicability.id, "CVE-2021-44228");
+    assert!(applicability
+        .matches
+        .iter()
+        .all(|m| m.confidence == MatchConfidence::VersionRange));
+
+    // Vulnerable versions aren't fixed, nor are those released before the vulnerable ones
+    assert_eq!(fixed("2.14.1"), None);
+    assert_eq!(fixed("1.2.17"), None);
+}
@@ -1,15 +1,18 @@
-use nvd_cve::feed::{Metafile, MetafileError};
+use nvd_cve::compression::Compression;
+use nvd_cve::datetime::{format_datetime, parse_datetime};
+use nvd_cve::feed::{FeedName, FeedNameError, FeedSelector, Metafile, MetafileError};
 use std::fs;
 
 mod util;
 
+#[cfg(feature = "blocking")]
 use util::MockBlockingClient;
 
 #[test]
 fn test_parsing_metafile_from_file() {
     let metafile = Metafile::from_file("./tests/files/nvdcve-1.1-recent.meta")
         .expect("Failed to parse metafile");
-    assert_eq!(metafile.format_last_modified_date(), "2021-12-18T19:00:00");
+    assert_eq!(metafile.format_last_modified_date(), "2021-12-18T19:00:00Z");
     assert_eq!(metafile.size, 1744779);
     assert_eq!(metafile.zip_size, 116171);
     assert_eq!(metafile.gz_size, 116031);
@@ -19,15 +22,87 @@ fn test_parsing_metafile_from_file() {
     );
 }
 
+#[test]
+fn test_parsing_metafile_variants() {
+    let variants = [
+        // Byte order mark and CRLF line endings
+        "\u{feff}lastModifiedDate:2021-12-18T14:00:00-05:00\r\nsize:1744779\r\nzipSize:116171\r\n\
+         gzSize:116031\r\nsha256:0EA38A9771747DD51A3E009FB8738732144266C4EF4EDC548B70F33555CC1586\r\n",
+        // Spaces around keys and values, and blank lines
+        "lastModifiedDate: 2021-12-18T14:00:00-05:00\n size : 1744779 \nzipSize:\t116171\n\n\
+         gzSize:   116031\nsha256: 0EA38A9771747DD51A3E009FB8738732144266C4EF4EDC548B70F33555CC1586  \n\n",
+        // Fields in another order, with an unknown field
+        "sha256:0EA38A9771747DD51A3E009FB8738732144266C4EF4EDC548B70F33555CC1586\ngzSize:116031\n\
+         mirroredBy:example.com\nzipSize:116171\nsize:1744779\nlastModifiedDate:2021-12-18T14:00:00-05:00",
+    ];
+
+    for variant in variants {
+        let metafile = Metafile::from_string(variant.to_string())
+            .unwrap_or_else(|error| panic!("Failed parsing {:?}: {:?}", variant, error));
+        assert_eq!(metafile.format_last_modified_date(), "2021-12-18T19:00:00Z");
+        assert_eq!(metafile.size, 1744779);
+        assert_eq!(metafile.zip_size, 116171);
+        assert_eq!(metafile.gz_size, 116031);
+        assert_eq!(
+            metafile.sha256,
+            "0EA38A9771747DD51A3E009FB8738732144266C4EF4EDC548B70F33555CC1586"
+        );
+    }
+
+    assert!(matches!(
+        Metafile::from_string(
+            "\u{feff}size:1\r\nzipSize:1\r\ngzSize:1\r\nsha256:00\r\n".to_string()
+        ),
+        Err(MetafileError::LineError)
+    ));
+
+    // Dates that don't parse fail rather than being read as the epoch, which would skip the feed
+    // from then on
+    assert!(matches!(
+        Metafile::from_string(
+            "lastModifiedDate:Sat, 18 Dec 2021 19:00:00 GMT\nsize:1\nzipSize:1\ngzSize:1\nsha256:00"
+                .to_string()
+        ),
+        Err(MetafileError::ParseDateTimeError(_))
+    ));
+}
+
+#[test]
+fn test_parse_datetime() {
+    let dates = [
+        // Metafiles
+        ("2021-12-18T14:00:00-05:00", "2021-12-18T19:00:00Z"),
+        // Feed items and timestamps
+        ("2021-12-18T19:15Z", "2021-12-18T19:15:00Z"),
+        ("2021-12-18T20:15+01:00", "2021-12-18T19:15:00Z"),
+        ("2021-12-18T19:15", "2021-12-18T19:15:00Z"),
+        // Rows of the local cache, as stored by this version and older ones
+        ("2021-12-18T19:15:42Z", "2021-12-18T19:15:42Z"),
+        ("2021-12-18T19:15:42", "2021-12-18T19:15:42Z"),
+        // NVD APIs
+        ("2021-12-18T19:15:42.123", "2021-12-18T19:15:42Z"),
+        (" 2021-12-18T19:15Z\n", "2021-12-18T19:15:00Z"),
+    ];
+    for (date, expected) in dates {
+        let parsed = parse_datetime(date)
+            .unwrap_or_else(|error| panic!("Failed parsing {:?}: {:?}", date, error));
+        assert_eq!(format_datetime(&parsed), expected);
+    }
+
+    for date in ["", "2021-12-18", "18/12/2021 19:15", "2021-12-18T19:15Q"] {
+        assert!(parse_datetime(date).is_err(), "Parsed {:?}", date);
+    }
+}
+
 #[test]
 fn test_parsing_metafile_from_file_split_error() {
     if let Err(e) = Metafile::from_file("./tests/files/nvdcve-1.1-recent.meta.broken_split") {
         match e {
             MetafileError::SplitError => (),
-            _ => assert!(false, "Should have returned SplitError"),
+            _ => panic!("Should have returned SplitError"),
         }
     } else {
-        assert!(false, "Splitting line should have failed");
+        panic!("Splitting line should have failed");
     }
 }
 
@@ -36,10 +111,10 @@ fn test_parsing_metafile_from_file_line_error() {
     if let Err(e) = Metafile::from_file("./tests/files/nvdcve-1.1-recent.meta.broken_line") {
         match e {
             MetafileError::LineError => (),
-            _ => assert!(false, "Should have returned LineError"),
+            _ => panic!("Should have returned LineError"),
         }
     } else {
-        assert!(false, "Parsing file should have failed");
+        panic!("Parsing file should have failed");
     }
 }
 
@@ -48,10 +123,10 @@ fn test_parsing_metafile_from_file_parse_error() {
     if let Err(e) = Metafile::from_file("./tests/files/nvdcve-1.1-recent.meta.broken_parse") {
         match e {
             MetafileError::ParseIntError(_) => (),
-            _ => assert!(false, "Should have returned ParseIntError"),
+            _ => panic!("Should have returned ParseIntError"),
         }
     } else {
-        assert!(false, "Parsing Int should have failed");
+        panic!("Parsing Int should have failed");
     }
 }
 
@@ -60,13 +135,14 @@ fn test_parsing_metafile_from_file_parse_file_error() {
     if let Err(e) = Metafile::from_file("./tests/files/nope") {
         match e {
             MetafileError::FileError(_) => (),
-            _ => assert!(false, "Should have returned FileError"),
+            _ => panic!("Should have returned FileError"),
         }
     } else {
-        assert!(false, "Opening file should have failed");
+        panic!("Opening file should have failed");
     }
 }
 
+#[cfg(feature = "blocking")]
 #[test]
 fn test_parsing_metafile_from_url_blocking() {
     let mut client = MockBlockingClient::default();
@@ -76,10 +152,10 @@ fn test_parsing_metafile_from_url_blocking() {
 
     client.get_metafile_response = Ok(body);
 
-    let metafile =
-        Metafile::from_blocking_http_client(&client, "recent").expect("Failed to parse metafile");
+    let metafile = Metafile::from_blocking_http_client(&client, &FeedName::Recent)
+        .expect("Failed to parse metafile");
 
-    assert_eq!(metafile.format_last_modified_date(), "2021-12-18T19:00:00");
+    assert_eq!(metafile.format_last_modified_date(), "2021-12-18T19:00:00Z");
     assert_eq!(metafile.size, 1744779);
     assert_eq!(metafile.zip_size, 116171);
     assert_eq!(metafile.gz_size, 116031);
@@ -88,3 +164,137 @@ fn test_parsing_metafile_from_url_blocking() {
         "0EA38A9771747DD51A3E009FB8738732144266C4EF4EDC548B70F33555CC1586"
     );
 }
+
+#[test]
+fn test_parsing_feed_names() {
+    assert_eq!("2019".parse(), Ok(FeedName::Year(2019)));
+    assert_eq!("Recent".parse(), Ok(FeedName::Recent));
+    assert_eq!("modified".parse(), Ok(FeedName::Modified));
+    assert_eq!(
+        "1999".parse::<FeedName>(),
+        Err(FeedNameError::InvalidYear(1999))
+    );
+    assert_eq!(
+        "latest".parse::<FeedName>(),
+        Err(FeedNameError::UnknownFeed("latest".to_string()))
+    );
+
+    assert_eq!(
+        FeedName::Year(2019).metafile_filename(),
+        "nvdcve-1.1-2019.meta"
+    );
+    assert_eq!(
+        FeedName::Recent.feed_filename(),
+        "nvdcve-1.1-recent.json.gz"
+    );
+    assert_eq!(FeedName::all().last(), Some(&FeedName::Modified));
+
+    assert_eq!(
+        FeedName::for_cve_id("CVE-2016-1234"),
+        Some(FeedName::Year(2016))
+    );
+    assert_eq!(
+        FeedName::for_cve_id("cve-2021-44228"),
+        Some(FeedName::Year(2021))
+    );
+    assert_eq!(
+        FeedName::for_cve_id("CVE-1999-0001"),
+        Some(FeedName::Year(2002))
+    );
+    assert_eq!(FeedName::for_cve_id("CVE-2016-*"), None);
+    assert_eq!(FeedName::for_cve_id("GHSA-2016-1234"), None);
+    assert_eq!(FeedName::for_cve_id("CVE-2016"), None);
+}
+
+#[test]
+fn test_feed_selectors() {
+    assert_eq!("recent".parse(), Ok(FeedSelector::Feed(FeedName::Recent)));
+    assert_eq!("2018..2020".parse(), Ok(FeedSelector::Years(2018, 2020)));
+    assert_eq!(
+        "2018..".parse(),
+        Ok(FeedSelector::Years(2018, nvd_cve::feed::LAST_FEED_YEAR))
+    );
+    assert_eq!(
+        "..2004".parse(),
+        Ok(FeedSelector::Years(nvd_cve::feed::FIRST_FEED_YEAR, 2004))
+    );
+    assert_eq!(
+        "2020..2018".parse::<FeedSelector>(),
+        Err(FeedNameError::InvalidRange(2020, 2018))
+    );
+    assert_eq!(
+        "1999..".parse::<FeedSelector>(),
+        Err(FeedNameError::InvalidYear(1999))
+    );
+    assert_eq!(
+        "recent..".parse::<FeedSelector>(),
+        Err(FeedNameError::UnknownFeed("recent..".to_string()))
+    );
+    assert_eq!(FeedSelector::Years(2018, 2020).to_string(), "2018..2020");
+
+    let selectors = vec![
+        FeedSelector::Years(2019, 2020),
+        FeedSelector::Feed(FeedName::Year(2020)),
+        FeedSelector::Feed(FeedName::Recent),
+    ];
+    assert_eq!(
+        FeedSelector::expand(&selectors),
+        vec![FeedName::Year(2019), FeedName::Year(2020), FeedName::Recent]
+    );
+}
+
+#[test]
+fn test_writing_metafile() {
+    let metafile = Metafile::from_file("./tests/files/nvdcve-1.1-recent.meta")
+        .expect("Failed to parse metafile");
+    assert_eq!(
+        metafile.to_string(),
+        "lastModifiedDate:2021-12-18T19:00:00+00:00\r\nsize:1744779\r\nzipSize:116171\r\n\
+         gzSize:116031\r\nsha256:0EA38A9771747DD51A3E009FB8738732144266C4EF4EDC548B70F33555CC1586\r\n"
+    );
+
+    let path = "./tests/files/.cache/nvd/written.meta";
+    fs::create_dir_all("./tests/files/.cache/nvd").unwrap();
+    metafile
+        .write_to_file(path)
+        .expect("Failed writing metafile");
+    assert_eq!(Metafile::from_file(path).unwrap(), metafile);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_generating_metafile() {
+    let json = fs::read("./tests/files/nvdcve-1.1-sample.json").unwrap();
+
+    let metafile = Metafile::from_feed_file("./tests/files/nvdcve-1.1-sample.json")
+        .expect("Failed generating metafile");
+    assert_eq!(metafile.format_last_modified_date(), "2022-08-17T07:00:00Z");
+    assert_eq!(metafile.size, json.len() as u64);
+    assert_eq!(metafile.sha256.len(), 64);
+    assert_eq!(metafile.sha256, metafile.sha256.to_uppercase());
+
+    let gz_path = "./tests/files/.cache/nvd/nvdcve-1.1-sample.json.gz";
+    fs::create_dir_all("./tests/files/.cache/nvd").unwrap();
+    let gzipped = Compression::Gzip
+        .compress(&json, "nvdcve-1.1-sample.json")
+        .unwrap();
+    fs::write(gz_path, &gzipped).unwrap();
+
+    // The compressed file describes the same feed
+    let from_gz = Metafile::from_feed_file(gz_path).expect("Failed generating metafile");
+    assert_eq!(from_gz, metafile);
+    assert_eq!(from_gz.gz_size, gzipped.len() as u64);
+    assert_eq!(
+        Compression::Zip
+            .compress(&json, "nvdcve-1.1-sample.json")
+            .unwrap()
+            .len() as u64,
+        from_gz.zip_size
+    );
+    fs::remove_file(gz_path).unwrap();
+
+    assert!(matches!(
+        Metafile::from_feed_file("./tests/files/nvdcve-1.1-recent.meta"),
+        Err(MetafileError::FileError(_))
+    ));
+}
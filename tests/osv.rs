@@ -0,0 +1,225 @@
+#![cfg(feature = "blocking")]
+
+use nvd_cve::aliases::{get_aliases, resolve_alias};
+use nvd_cve::cache::CacheConfig;
+use nvd_cve::client::{BlockingHttpClient, ReqwestBlockingClient};
+use nvd_cve::osv::{
+    get_osv, import_osv, search_package, sync_osv, Ecosystem, OsvReport, OsvVulnerability,
+    PackageRange,
+};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Cursor, Write};
+mod util;
+use util::MockFeedServer;
+
+fn sample_vulnerability(id: &str) -> OsvVulnerability {
+    let json = fs::read(format!("./tests/files/osv/{}.json", id)).expect("Failed reading advisory");
+    OsvVulnerability::from_json(&json).expect("Failed parsing advisory")
+}
+
+fn ids(vulnerabilities: &[OsvVulnerability]) -> Vec<&str> {
+    vulnerabilities
+        .iter()
+        .map(|vulnerability| vulnerability.id.as_str())
+        .collect()
+}
+
+#[test]
+fn test_package_ranges() {
+    let lodash = sample_vulnerability("GHSA-35jh-r3h4-6jhm");
+    assert_eq!(lodash.cves(), vec!["CVE-2021-23337"]);
+    let ranges = lodash.ranges();
+    assert_eq!(ranges.len(), 2);
+    assert_eq!(
+        ranges[0],
+        PackageRange {
+            ecosystem: Ecosystem::Npm,
+            package: "lodash".to_string(),
+            introduced: None,
+            fixed: Some("4.17.21".to_string()),
+            last_affected: None,
+        }
+    );
+    assert!(ranges[0].contains("4.17.20"));
+    assert!(ranges[0].contains("1.0.0"));
+    assert!(!ranges[0].contains("4.17.21"));
+
+    // Git ranges and packages of other ecosystems are skipped, listed versions are covered by
+    // the range of versions
+    let flask_login = sample_vulnerability("PYSEC-2023-0001");
+    let ranges = flask_login.ranges();
+    assert_eq!(
+        ranges,
+        vec![PackageRange {
+            ecosystem: Ecosystem::PyPI,
+            package: "flask-login".to_string(),
+            introduced: Some("0.5.0".to_string()),
+            fixed: None,
+            last_affected: Some("0.6.2".to_string()),
+        }]
+    );
+    assert!(ranges[0].contains("0.6.2"));
+    assert!(ranges[0].contains("0.6.0rc1"));
+    assert!(!ranges[0].contains("0.6.3"));
+    assert!(!ranges[0].contains("0.4.1"));
+
+    let aliases = flask_login.cve_aliases();
+    assert_eq!(
+        aliases
+            .iter()
+            .map(|alias| (alias.alias.as_str(), alias.cve.as_str()))
+            .collect::<Vec<_>>(),
+        vec![
+            ("GHSA-aaaa-bbbb-cccc", "CVE-2023-0001"),
+            ("PYSEC-2023-0001", "CVE-2023-0001")
+        ]
+    );
+
+    // Advisories without a CVE ID have no CVE to alias
+    assert!(sample_vulnerability("RUSTSEC-2023-0044")
+        .cve_aliases()
+        .is_empty());
+
+    assert_eq!("pypi".parse(), Ok(Ecosystem::PyPI));
+    assert_eq!("crates.io".parse(), Ok(Ecosystem::CratesIo));
+    assert!("Maven".parse::<Ecosystem>().is_err());
+    assert_eq!(
+        Ecosystem::PyPI.normalize_name("Flask__Login"),
+        "flask-login"
+    );
+    assert_eq!(Ecosystem::Npm.normalize_name("@babel/core"), "@babel/core");
+}
+
+#[test]
+fn test_import_osv() {
+    let config = CacheConfig {
+        db: "./tests/files/.cache/nvd/osv.sqlite3".to_string(),
+        show_progress: false,
+        ..Default::default()
+    };
+    fs::remove_file(&config.db).ok();
+
+    let vulnerabilities: Vec<OsvVulnerability> = [
+        "GHSA-35jh-r3h4-6jhm",
+        "PYSEC-2023-0001",
+        "RUSTSEC-2023-0044",
+    ]
+    .into_iter()
+    .map(sample_vulnerability)
+    .collect();
+
+    let report = import_osv(&config, &vulnerabilities).unwrap();
+    assert_eq!(
+        report,
+        OsvReport {
+            vulnerabilities: 3,
+            unchanged: 0,
+            ranges: 4,
+            aliases: 3,
+        }
+    );
+
+    let search =
+        |ecosystem, package, version| search_package(&config, ecosystem, package, version).unwrap();
+    assert_eq!(
+        ids(&search(Ecosystem::Npm, "lodash", Some("4.17.15"))),
+        vec!["GHSA-35jh-r3h4-6jhm"]
+    );
+    assert!(search(Ecosystem::Npm, "lodash", Some("4.17.21")).is_empty());
+    assert_eq!(
+        ids(&search(Ecosystem::CratesIo, "openssl", Some("0.10.45"))),
+        vec!["RUSTSEC-2023-0044"]
+    );
+    assert!(search(Ecosystem::CratesIo, "openssl", Some("0.9.24")).is_empty());
+    assert_eq!(
+        ids(&search(Ecosystem::PyPI, "flask.login", None)),
+        vec!["PYSEC-2023-0001"]
+    );
+    assert!(search(Ecosystem::PyPI, "lodash", None).is_empty());
+
+    // The advisories are linked to their CVE, along with their other IDs
+    assert_eq!(
+        resolve_alias(&config, "GHSA-35jh-r3h4-6jhm").unwrap(),
+        vec!["CVE-2021-23337"]
+    );
+    assert_eq!(
+        get_aliases(&config, "CVE-2023-0001")
+            .unwrap()
+            .iter()
+            .map(|alias| alias.alias.as_str())
+            .collect::<Vec<_>>(),
+        vec!["GHSA-aaaa-bbbb-cccc", "PYSEC-2023-0001"]
+    );
+
+    // Advisories that weren't modified are skipped, modified ones replace their ranges
+    let mut modified = vulnerabilities[0].clone();
+    modified.modified = "2024-06-01T00:00:00Z".to_string();
+    modified.affected.truncate(1);
+    let report = import_osv(&config, &[vulnerabilities[1].clone(), modified.clone()]).unwrap();
+    assert_eq!(report.unchanged, 1);
+    assert_eq!(report.vulnerabilities, 1);
+    assert_eq!(report.ranges, 1);
+    assert!(search(Ecosystem::Npm, "lodash-es", None).is_empty());
+    assert_eq!(
+        get_osv(&config, "GHSA-35jh-r3h4-6jhm").unwrap(),
+        Some(modified)
+    );
+    assert_eq!(get_osv(&config, "GHSA-0000-0000-0000").unwrap(), None);
+
+    let frozen = CacheConfig {
+        read_only: true,
+        ..config.clone()
+    };
+    assert!(import_osv(&frozen, &vulnerabilities).is_err());
+
+    fs::remove_file(&config.db).ok();
+}
+
+#[test]
+fn test_sync_osv() {
+    let mut archive = zip::ZipWriter::new(Cursor::new(vec![]));
+    for id in ["GHSA-35jh-r3h4-6jhm", "PYSEC-2023-0001"] {
+        archive
+            .start_file(
+                format!("{}.json", id),
+                zip::write::SimpleFileOptions::default(),
+            )
+            .unwrap();
+        archive
+            .write_all(&fs::read(format!("./tests/files/osv/{}.json", id)).unwrap())
+            .unwrap();
+    }
+    let archive = archive.finish().unwrap().into_inner();
+
+    let server = MockFeedServer::start(HashMap::from([("all.zip".to_string(), archive)]));
+    let config = CacheConfig {
+        db: "./tests/files/.cache/nvd/osv-sync.sqlite3".to_string(),
+        show_progress: false,
+        osv_ecosystems: vec![Ecosystem::Npm],
+        osv_url: server.url.clone(),
+        ..Default::default()
+    };
+    fs::remove_file(&config.db).ok();
+
+    let client = ReqwestBlockingClient::new(&config.osv_url, None, None, None);
+    let report = sync_osv(&config, &client).unwrap();
+    assert_eq!(report.vulnerabilities, 2);
+    assert_eq!(
+        ids(&search_package(&config, Ecosystem::PyPI, "Flask-Login", Some("0.6.1")).unwrap()),
+        vec!["PYSEC-2023-0001"]
+    );
+
+    let report = sync_osv(&config, &client).unwrap();
+    assert_eq!(report.unchanged, 2);
+
+    // Anything but a zip archive fails to sync
+    let server = MockFeedServer::start(HashMap::from([(
+        "all.zip".to_string(),
+        b"not a zip archive".to_vec(),
+    )]));
+    let client = ReqwestBlockingClient::new(&server.url, None, None, None);
+    assert!(sync_osv(&config, &client).is_err());
+
+    fs::remove_file(&config.db).ok();
+}
@@ -0,0 +1,321 @@
+#![cfg(feature = "blocking")]
+
+use nvd_cve::cache::{search, search_by_id, sync_blocking, CacheConfig};
+use nvd_cve::cpe::Cpe;
+use nvd_cve::cve::{
+    BaseMetricV3, Configuration, Cve, CveContainer, CveFeed, CveMeta, CvssV3, Description,
+    DescriptionData, Impact, ProblemType, ProblemTypeData, ReferenceData, References,
+};
+use nvd_cve::cvss::{
+    AttackComplexity, AttackVector, ImpactLevel, PrivilegesRequired, Scope, Severity,
+    UserInteraction,
+};
+use nvd_cve::feed::FeedName;
+use nvd_cve::query::SearchQuery;
+use nvd_cve::version::VersionScheme;
+use proptest::prelude::*;
+use proptest::sample::select;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fs;
+mod util;
+use util::MockBlockingClient;
+
+fn cve_id() -> impl Strategy<Value = String> {
+    "CVE-(199[9]|20[0-2][0-9])-[0-9]{4,7}"
+}
+
+/// Free text including the characters that have special meaning in ``LIKE`` patterns
+fn text() -> impl Strategy<Value = String> {
+    "[a-zA-Z0-9 .,:%_\\\\-]{0,60}"
+}
+
+fn score() -> impl Strategy<Value = f64> {
+    (0..=100u8).prop_map(|score| f64::from(score) / 10.0)
+}
+
+fn impact_level() -> impl Strategy<Value = ImpactLevel> {
+    select(vec![ImpactLevel::None, ImpactLevel::Low, ImpactLevel::High])
+}
+
+fn base_metric_v3() -> impl Strategy<Value = BaseMetricV3> {
+    (
+        (
+            select(vec![
+                AttackVector::Network,
+                AttackVector::AdjacentNetwork,
+                AttackVector::Local,
+                AttackVector::Physical,
+            ]),
+            select(vec![AttackComplexity::Low, AttackComplexity::High]),
+            select(vec![
+                PrivilegesRequired::None,
+                PrivilegesRequired::Low,
+                PrivilegesRequired::High,
+            ]),
+            select(vec![UserInteraction::None, UserInteraction::Required]),
+            select(vec![Scope::Unchanged, Scope::Changed]),
+        ),
+        (impact_level(), impact_level(), impact_level()),
+        (score(), score(), score()),
+    )
+        .prop_map(
+            |((av, ac, pr, ui, s), (c, i, a), (base_score, exploitability, impact))| BaseMetricV3 {
+                cvss_v3: CvssV3 {
+                    version: "3.1".to_string(),
+                    vector_string: format!(
+                        "CVSS:3.1/AV:{}/AC:{}/PR:{}/UI:{}/S:{}/C:{}/I:{}/A:{}",
+                        av.abbreviation(),
+                        ac.abbreviation(),
+                        pr.abbreviation(),
+                        ui.abbreviation(),
+                        s.abbreviation(),
+                        c.abbreviation(),
+                        i.abbreviation(),
+                        a.abbreviation()
+                    ),
+                    attack_vector: av,
+                    attack_complexity: ac,
+                    privileges_required: pr,
+                    user_interaction: ui,
+                    scope: s,
+                    confidentiality_impact: c,
+                    integrity_impact: i,
+                    availability_impact: a,
+                    base_score,
+                    base_severity: "HIGH".to_string(),
+                },
+                exploitability_score: exploitability,
+                impact_score: impact,
+            },
+        )
+}
+
+fn cve_container(id: String) -> impl Strategy<Value = CveContainer> {
+    (
+        text(),
+        prop::collection::vec("CWE-[0-9]{1,4}", 0..3),
+        prop::collection::vec(
+            ("https://[a-z]{1,12}\\.example/[a-z0-9]{0,12}", text()),
+            0..3,
+        ),
+        prop::option::of(base_metric_v3()),
+    )
+        .prop_map(
+            move |(description, cwes, references, metric)| CveContainer {
+                cve: Cve {
+                    data_type: "CVE".to_string(),
+                    data_format: "MITRE".to_string(),
+                    data_version: "4.0".to_string(),
+                    cve_data_meta: CveMeta {
+                        id: id.clone(),
+                        assigner: "cve@mitre.org".to_string(),
+                    },
+                    problem_type: ProblemType {
+                        problem_type_data: vec![ProblemTypeData {
+                            description: cwes
+                                .into_iter()
+                                .map(|cwe| json!({ "lang": "en", "value": cwe }))
+                                .collect(),
+                        }],
+                    },
+                    references: References {
+                        reference_data: references
+                            .into_iter()
+                            .map(|(url, name)| ReferenceData {
+                                url,
+                                name,
+                                ref_source: "MISC".to_string(),
+                                tags: vec![json!("Third Party Advisory")],
+                            })
+                            .collect(),
+                    },
+                    description: Description {
+                        description_data: vec![DescriptionData {
+                            lang: "en".to_string(),
+                            value: description,
+                        }],
+                    },
+                },
+                configurations: Configuration {
+                    cve_data_version: "4.0".to_string(),
+                    nodes: vec![],
+                },
+                impact: Impact {
+                    base_metric_v3: metric,
+                    base_metric_v2: None,
+                },
+                published_date: "2021-12-10T10:15Z".to_string(),
+                last_modified_date: "2022-02-04T14:23Z".to_string(),
+                raw_configurations: None,
+                raw_impact: None,
+            },
+        )
+}
+
+/// CVEs with distinct IDs
+fn cve_containers() -> impl Strategy<Value = Vec<CveContainer>> {
+    prop::collection::btree_set(cve_id(), 1..12)
+        .prop_flat_map(|ids| ids.into_iter().map(cve_container).collect::<Vec<_>>())
+}
+
+fn sync_cves(db: &str, cves: &[CveContainer]) -> CacheConfig {
+    let config = CacheConfig {
+        db: db.to_string(),
+        url: "http://nowhere.nope".to_string(),
+        feeds: vec![FeedName::Recent],
+        show_progress: false,
+        ..Default::default()
+    };
+
+    fs::remove_file(&config.db).ok();
+
+    let metafile = fs::read_to_string("./tests/files/nvdcve-1.1-recent.meta")
+        .expect("Failed reading metafile");
+
+    let client = MockBlockingClient {
+        get_metafile_response: Ok(metafile),
+        get_feed_response: Ok(CveFeed {
+            cve_data_type: "CVE".to_string(),
+            cve_data_format: "MITRE".to_string(),
+            cve_data_version: "4.0".to_string(),
+            cve_data_number_of_cves: cves.len().to_string(),
+            cve_data_timestamp: "2022-02-04T14:23Z".to_string(),
+            cve_items: cves.to_vec(),
+        }),
+    };
+
+    sync_blocking(&config, client).expect("Failed to sync to local cache");
+
+    config
+}
+
+proptest! {
+    #[test]
+    fn test_cve_serde_round_trip(cve in cve_id().prop_flat_map(cve_container)) {
+        let json = serde_json::to_value(&cve).unwrap();
+        let parsed: CveContainer = serde_json::from_value(json.clone()).unwrap();
+        prop_assert_eq!(serde_json::to_value(&parsed).unwrap(), json);
+
+        // Without v2 metrics, the score is always the v3 one
+        let score = cve.impact.base_metric_v3.as_ref().map(|metric| metric.cvss_v3.base_score);
+        prop_assert_eq!(cve.severity().map(|(_, score)| score), score);
+    }
+}
+
+proptest! {
+    #[test]
+    fn test_cpe_binding_round_trip(
+        part in "[aoh*]",
+        attributes in prop::collection::vec(r"[a-zA-Z0-9_\\.*?%~$!-]{1,4}", 10)
+    ) {
+        let name = format!("cpe:2.3:{}:{}", part, attributes.join(":"));
+        if let Ok(cpe) = name.parse::<Cpe>() {
+            prop_assert_eq!(cpe.to_string().parse::<Cpe>(), Ok(cpe.clone()));
+
+            // URIs are lower case
+            let uri = cpe.to_uri().to_lowercase();
+            prop_assert_eq!(Cpe::from_uri(&uri).map(|cpe| cpe.to_uri()), Ok(uri));
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn test_version_order_is_antisymmetric(
+        scheme in select(VersionScheme::ALL.to_vec()),
+        a in r"[0-9a-z.~^+:_-]{0,10}",
+        b in r"[0-9a-z.~^+:_-]{0,10}",
+    ) {
+        prop_assert_eq!(scheme.compare(&a, &b), scheme.compare(&b, &a).reverse());
+        prop_assert_eq!(scheme.compare(&a, &a), std::cmp::Ordering::Equal);
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(16))]
+
+    #[test]
+    fn test_synced_cves_are_retrievable(cves in cve_containers()) {
+        let config = sync_cves("./tests/files/.cache/nvd/properties.sqlite3", &cves);
+
+        let mut by_vector: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+
+        for container in &cves {
+            let id = &container.cve.cve_data_meta.id;
+
+            // Every synced CVE is retrievable by its ID, unchanged
+            let cve = search_by_id(&config, id).expect("Synced CVE not found");
+            prop_assert_eq!(
+                serde_json::to_value(&cve).unwrap(),
+                serde_json::to_value(&container.cve).unwrap()
+            );
+
+            // ... and by its description, however unusual the text
+            let description = &container.cve.description.description_data[0].value;
+            let query = SearchQuery {
+                text: Some(description.clone()),
+                ..Default::default()
+            };
+            prop_assert!(search(&config, &query).unwrap().iter().any(|cve| &cve.id == id));
+
+            if let Some(metric) = &container.impact.base_metric_v3 {
+                by_vector
+                    .entry(metric.cvss_v3.attack_vector.as_str())
+                    .or_default()
+                    .push(id.clone());
+            }
+        }
+
+        // Searching by a metric returns exactly the CVEs scored with it
+        for (attack_vector, mut ids) in by_vector {
+            ids.sort();
+            let query = SearchQuery {
+                attack_vector: Some(attack_vector.parse().unwrap()),
+                ..Default::default()
+            };
+            let found: Vec<String> = search(&config, &query).unwrap().into_iter().map(|cve| cve.id).collect();
+            prop_assert_eq!(found, ids);
+        }
+
+        // Matching a query in memory agrees with searching the cache
+        for min_severity in Severity::ALL {
+            let query = SearchQuery {
+                text: Some(cves[0].cve.description.description_data[0].value.clone()),
+                min_severity: Some(min_severity),
+                ..Default::default()
+            };
+            let mut matched: Vec<String> = cves
+                .iter()
+                .filter(|cve| query.matches(cve))
+                .map(|cve| cve.cve.cve_data_meta.id.clone())
+                .collect();
+            matched.sort();
+            let mut found: Vec<String> = search(&config, &query).unwrap().into_iter().map(|cve| cve.id).collect();
+            found.sort();
+            prop_assert_eq!(matched, found);
+        }
+
+        fs::remove_file(&config.db).expect("Failed removing test cache");
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+proptest! {
+    #[test]
+    fn test_arbitrary_cve_feed_round_trip(data in prop::collection::vec(any::<u8>(), 0..4096)) {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        if let Ok(feed) = CveFeed::arbitrary(&mut Unstructured::new(&data)) {
+            let json = serde_json::to_value(&feed).unwrap();
+            let parsed: CveFeed = serde_json::from_value(json.clone()).unwrap();
+            prop_assert_eq!(serde_json::to_value(&parsed).unwrap(), json);
+
+            for item in &feed.cve_items {
+                if let Some(metric) = &item.impact.base_metric_v3 {
+                    prop_assert_eq!(item.severity().map(|(_, score)| score), Some(metric.cvss_v3.base_score));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,611 @@
+#![cfg(all(feature = "scan-host", feature = "blocking"))]
+
+use chrono::{NaiveDate, TimeZone, Utc};
+use nvd_cve::cache::{sync_blocking, CacheConfig};
+use nvd_cve::cve::CveFeed;
+use nvd_cve::feed::FeedName;
+use nvd_cve::matching::MatchConfidence;
+use nvd_cve::scan::{
+    packages_from_json, scan_packages, CpeDictionary, Finding, HostScan, Package, PackageManager,
+    ScanError, Suppressions,
+};
+use nvd_cve::version::VersionScheme;
+use nvd_cve::vex::{purl, CsafDocument, OpenVexDocument, VexJustification, VexPublisher};
+use serde_json::json;
+use std::fs;
+mod util;
+use util::MockBlockingClient;
+
+/// Sync the sample feed into a fresh cache at ``db``
+fn sync_sample_feed(db: &str) -> CacheConfig {
+    let config = CacheConfig {
+        db: db.to_string(),
+        url: "http://nowhere.nope".to_string(),
+        feeds: vec![FeedName::Recent],
+        show_progress: false,
+        ..Default::default()
+    };
+    fs::remove_file(&config.db).ok();
+
+    let metafile = fs::read_to_string("./tests/files/nvdcve-1.1-recent.meta")
+        .expect("Failed reading metafile");
+    let body = fs::read("./tests/files/nvdcve-1.1-sample.json").expect("Failed reading feed json");
+    let feed: CveFeed = serde_json::from_slice(&body).expect("Failed parsing cve feed json");
+    let client = MockBlockingClient {
+        get_metafile_response: Ok(metafile),
+        get_feed_response: Ok(feed),
+    };
+
+    sync_blocking(&config, client).expect("Failed to sync to local cache");
+    config
+}
+
+fn package(kind: &str, name: &str, source: Option<&str>, version: &str) -> Package {
+    Package {
+        kind: kind.to_string(),
+        name: name.to_string(),
+        source: source.map(str::to_string),
+        version: version.to_string(),
+        cpes: vec![],
+    }
+}
+
+#[test]
+fn test_parse_packages() {
+    let dpkg = "install ok installed\tlibssl3\topenssl\t3.0.2-0ubuntu1.10
+install ok installed\tapache2\tapache2\t2.4.52-1ubuntu4.3
+deinstall ok config-files\tlibssl1.1\topenssl\t1.1.1f-1ubuntu2
+install ok installed\tzlib1g\tzlib\t1:1.2.11.dfsg-2ubuntu9.2
+install ok installed\tunexpected
+";
+    assert_eq!(
+        PackageManager::Dpkg.parse_packages(dpkg),
+        vec![
+            package("dpkg", "libssl3", Some("openssl"), "3.0.2"),
+            package("dpkg", "apache2", None, "2.4.52"),
+            package("dpkg", "zlib1g", Some("zlib"), "1.2.11"),
+        ]
+    );
+
+    let rpm = "openssl-libs\topenssl-1.1.1k-6.el8.src.rpm\t1.1.1k
+bash\tbash-4.4.20-4.el8_6.src.rpm\t4.4.20
+gpg-pubkey\t(none)\t8483c65d
+";
+    assert_eq!(
+        PackageManager::Rpm.parse_packages(rpm),
+        vec![
+            package("rpm", "openssl-libs", Some("openssl"), "1.1.1k"),
+            package("rpm", "bash", None, "4.4.20"),
+            package("rpm", "gpg-pubkey", None, "8483c65d"),
+        ]
+    );
+
+    let brew = "openssl@3 3.1.4 3.1.3_1\ncurl 8.4.0\n\n";
+    assert_eq!(
+        PackageManager::Homebrew.parse_packages(brew),
+        vec![
+            package("homebrew", "openssl@3", Some("openssl"), "3.1.4"),
+            package("homebrew", "openssl@3", Some("openssl"), "3.1.3"),
+            package("homebrew", "curl", None, "8.4.0"),
+        ]
+    );
+
+    for manager in PackageManager::ALL {
+        assert_eq!(manager.to_string().parse(), Ok(manager));
+    }
+    assert!("pacman".parse::<PackageManager>().is_err());
+}
+
+#[test]
+fn test_cpe_dictionary() {
+    let dictionary = CpeDictionary::builtin();
+    let libssl = package("dpkg", "libssl3", Some("openssl"), "3.0.2");
+    assert_eq!(
+        dictionary.cpe(&libssl).unwrap().to_string(),
+        "cpe:2.3:a:openssl:openssl:3.0.2:*:*:*:*:*:*:*"
+    );
+    let node = package("homebrew", "node", None, "20.9.0");
+    assert_eq!(
+        dictionary.cpe(&node).unwrap().to_string(),
+        "cpe:2.3:a:nodejs:node.js:20.9.0:*:*:*:*:*:*:*"
+    );
+    let unknown = package("rpm", "libexample", None, "1.0");
+    assert_eq!(dictionary.cpe(&unknown), None);
+
+    let dictionary = CpeDictionary::from_toml(
+        "[packages]\nlibexample = \"cpe:/a:example:example\"\n\
+         libssl3 = \"cpe:2.3:a:example:libssl:*:*:*:*:*:*:*:*\"\n",
+    )
+    .unwrap();
+    assert_eq!(
+        dictionary.cpe(&unknown).unwrap().to_string(),
+        "cpe:2.3:a:example:example:1.0:*:*:*:*:*:*:*"
+    );
+    assert_eq!(
+        dictionary.cpe(&libssl).unwrap().to_string(),
+        "cpe:2.3:a:example:libssl:3.0.2:*:*:*:*:*:*:*"
+    );
+
+    // Entries added to the built-in dictionary replace its own
+    let mut builtin = CpeDictionary::builtin();
+    builtin.extend(dictionary.clone());
+    assert_eq!(builtin.cpe(&libssl), dictionary.cpe(&libssl));
+    assert!(builtin.cpe(&node).is_some());
+
+    assert!(matches!(
+        CpeDictionary::from_toml("[packages]\nlibexample = \"example\"\n"),
+        Err(ScanError::InvalidDictionary(_))
+    ));
+    assert!(matches!(
+        CpeDictionary::from_toml("[packages"),
+        Err(ScanError::InvalidDictionary(_))
+    ));
+}
+
+#[test]
+fn test_scan_packages() {
+    let config = sync_sample_feed("./tests/files/scan.sqlite3");
+
+    let packages = vec![
+        package("dpkg", "liblog4j2-java", Some("apache-log4j2"), "2.14.1"),
+        package("dpkg", "libssl1.0.0", Some("openssl"), "1.0.1f"),
+        package("dpkg", "libexample", None, "1.0"),
+        package("homebrew", "curl", None, "8.4.0"),
+    ];
+    let scan = scan_packages(
+        &config,
+        &packages,
+        &CpeDictionary::builtin(),
+        VersionScheme::Auto,
+    )
+    .unwrap();
+
+    assert_eq!(scan.packages, 4);
+    assert_eq!(scan.unmapped, vec![packages[2].clone()]);
+
+    let log4shell = scan
+        .findings
+        .iter()
+        .find(|finding| finding.cve == "CVE-2021-44228")
+        .unwrap();
+    assert_eq!(log4shell.package, packages[0]);
+    assert_eq!(log4shell.cpe, "cpe:2.3:a:apache:log4j:2.14.1:*:*:*:*:*:*:*");
+    assert_eq!(log4shell.confidence, MatchConfidence::VersionRange);
+    assert_eq!(log4shell.warning, None);
+
+    let heartbleed = scan
+        .findings
+        .iter()
+        .find(|finding| finding.cve == "CVE-2014-0160")
+        .unwrap();
+    assert_eq!(heartbleed.package, packages[1]);
+    assert_eq!(heartbleed.confidence, MatchConfidence::VersionRange);
+
+    assert!(scan
+        .findings
+        .windows(2)
+        .all(|pair| pair[0].cve <= pair[1].cve));
+    assert!(scan
+        .findings
+        .iter()
+        .all(|finding| finding.package != packages[3]));
+
+    // Patched versions aren't reported
+    let patched = vec![package(
+        "dpkg",
+        "liblog4j2-java",
+        Some("apache-log4j2"),
+        "2.17.1",
+    )];
+    let scan = scan_packages(
+        &config,
+        &patched,
+        &CpeDictionary::builtin(),
+        VersionScheme::Auto,
+    )
+    .unwrap();
+    assert!(scan
+        .findings
+        .iter()
+        .all(|finding| finding.cve != "CVE-2021-44228"));
+    // ... but listed as fixed
+    let fixed = scan
+        .fixed
+        .iter()
+        .find(|finding| finding.cve == "CVE-2021-44228")
+        .unwrap();
+    assert_eq!(fixed.package, patched[0]);
+    assert_eq!(fixed.confidence, MatchConfidence::VersionRange);
+
+    fs::remove_file(&config.db).ok();
+}
+
+#[test]
+fn test_packages_from_json() {
+    let syft = json!({
+        "artifacts": [
+            {
+                "name": "libssl3",
+                "version": "3.0.11-1~deb12u2",
+                "type": "deb",
+                "cpes": [{"cpe": "cpe:2.3:a:libssl3:libssl3:3.0.11-1\\~deb12u2:*:*:*:*:*:*:*", "source": "syft-generated"}],
+                "metadata": {"package": "libssl3", "source": "openssl"}
+            },
+            {
+                "name": "openssl-libs",
+                "version": "1:1.1.1k-6.el8",
+                "type": "rpm",
+                "metadata": {"sourceRpm": "openssl-1.1.1k-6.el8.src.rpm"}
+            },
+            {
+                "name": "libcrypto3",
+                "version": "3.1.4-r1",
+                "type": "apk",
+                "metadata": {"originPackage": "openssl"}
+            },
+            {
+                "name": "log4j-core",
+                "version": "2.14.1",
+                "type": "java-archive",
+                "cpes": ["cpe:2.3:a:apache:log4j:2.14.1:*:*:*:*:*:*:*"]
+            },
+            {"name": "unversioned", "version": "", "type": "npm"}
+        ],
+        "source": {"type": "image"}
+    });
+    let packages = packages_from_json(&syft.to_string()).unwrap();
+    assert_eq!(
+        packages
+            .iter()
+            .map(|p| (
+                p.kind.as_str(),
+                p.name.as_str(),
+                p.source.as_deref(),
+                p.version.as_str()
+            ))
+            .collect::<Vec<_>>(),
+        vec![
+            ("deb", "libssl3", Some("openssl"), "3.0.11"),
+            ("rpm", "openssl-libs", Some("openssl"), "1.1.1k"),
+            ("apk", "libcrypto3", Some("openssl"), "3.1.4"),
+            ("java-archive", "log4j-core", None, "2.14.1"),
+        ]
+    );
+    assert_eq!(
+        packages[0].cpes,
+        vec!["cpe:2.3:a:libssl3:libssl3:3.0.11-1\\~deb12u2:*:*:*:*:*:*:*"]
+    );
+
+    // CPEs identified by syft are only used for packages missing from the dictionary
+    let dictionary = CpeDictionary::builtin();
+    assert_eq!(
+        dictionary.cpes(&packages[0])[0].to_string(),
+        "cpe:2.3:a:openssl:openssl:3.0.11:*:*:*:*:*:*:*"
+    );
+    assert_eq!(
+        dictionary.cpes(&packages[3])[0].to_string(),
+        "cpe:2.3:a:apache:log4j:2.14.1:*:*:*:*:*:*:*"
+    );
+
+    let inspect = json!([{
+        "Id": "sha256:a8758716bb6a",
+        "RepoTags": ["nginx:1.25"],
+        "Config": {
+            "Env": [
+                "PATH=/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin",
+                "NGINX_VERSION=1.25.3",
+                "NJS_VERSION=0.8.2",
+                "PKG_RELEASE=1~bookworm",
+                "EMPTY_VERSION="
+            ]
+        }
+    }]);
+    assert_eq!(
+        packages_from_json(&inspect.to_string()).unwrap(),
+        vec![
+            package("image", "nginx", None, "1.25.3"),
+            package("image", "njs", None, "0.8.2"),
+        ]
+    );
+
+    for invalid in ["{\"packages\": []}", "[]", "not json"] {
+        assert!(
+            matches!(packages_from_json(invalid), Err(ScanError::InvalidInput(_))),
+            "{}",
+            invalid
+        );
+    }
+}
+
+#[test]
+fn test_scan_image_packages() {
+    let config = sync_sample_feed("./tests/files/scan-image.sqlite3");
+
+    let mut log4j = package("java-archive", "log4j-core", None, "2.14.1");
+    log4j.cpes = vec!["cpe:2.3:a:apache:log4j:*:*:*:*:*:*:*:*".to_string()];
+    // Both versions of a package installed side by side are matched
+    let packages = vec![
+        log4j,
+        package("homebrew", "openssl@1.0", Some("openssl"), "1.0.1g"),
+        package("homebrew", "openssl@1.0", Some("openssl"), "1.0.1f"),
+    ];
+    let scan = scan_packages(
+        &config,
+        &packages,
+        &CpeDictionary::builtin(),
+        VersionScheme::Auto,
+    )
+    .unwrap();
+    assert!(scan.unmapped.is_empty());
+
+    let finding = |cve: &str| {
+        scan.findings
+            .iter()
+            .filter(|finding| finding.cve == cve)
+            .map(|finding| finding.package.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(finding("CVE-2021-44228"), vec![packages[0].clone()]);
+    assert_eq!(finding("CVE-2014-0160"), vec![packages[2].clone()]);
+
+    fs::remove_file(&config.db).ok();
+}
+
+#[test]
+fn test_suppressions() {
+    let finding = |cve: &str, package: Package| Finding {
+        cve: cve.to_string(),
+        package,
+        cpe: "cpe:2.3:a:gnu:glibc:2.36:*:*:*:*:*:*:*".to_string(),
+        confidence: MatchConfidence::VersionRange,
+        warning: None,
+    };
+    let glibc = package("dpkg", "libc6", Some("glibc"), "2.36-9");
+    let curl = package("dpkg", "curl", None, "7.88.1-10");
+    let mut scan = HostScan {
+        packages: 2,
+        unmapped: vec![],
+        findings: vec![
+            finding("CVE-2023-4527", glibc.clone()),
+            finding("CVE-2023-4911", glibc.clone()),
+            finding("CVE-2023-38545", curl.clone()),
+            finding("CVE-2023-38546", curl.clone()),
+        ],
+        suppressed: vec![],
+        fixed: vec![],
+    };
+
+    let suppressions = Suppressions::from_json(
+        &json!([
+            // Components are matched by their source package too
+            {"cve": "cve-2023-4911", "component": "glibc", "reason": "No setuid binaries"},
+            {"cve": "CVE-2023-4527", "component": "libc6", "version": "2.36-8", "reason": "Other version"},
+            {"cve": "CVE-2023-38545", "reason": "No SOCKS5 proxies", "expires": "2023-12-31"},
+            {"cve": "CVE-2023-38546", "component": "curl", "reason": "Expired", "expires": "2023-10-31"},
+        ])
+        .to_string(),
+    )
+    .unwrap();
+
+    let today = NaiveDate::from_ymd_opt(2023, 11, 15).unwrap();
+    let expired = scan.suppress(&suppressions, today);
+    assert_eq!(expired, vec![&suppressions.suppressions[3]]);
+
+    let ids = |findings: Vec<&Finding>| -> Vec<String> {
+        findings.iter().map(|finding| finding.cve.clone()).collect()
+    };
+    assert_eq!(
+        ids(scan.findings.iter().collect()),
+        vec!["CVE-2023-4527", "CVE-2023-38546"]
+    );
+    assert_eq!(
+        ids(scan.suppressed.iter().map(|s| &s.finding).collect()),
+        vec!["CVE-2023-4911", "CVE-2023-38545"]
+    );
+    assert_eq!(scan.suppressed[1].suppression.reason, "No SOCKS5 proxies");
+
+    // Suppressed findings are reported separately in the JSON output
+    let value = serde_json::to_value(&scan).unwrap();
+    assert_eq!(value["suppressed"][0]["cve"], "CVE-2023-4911");
+    assert_eq!(
+        value["suppressed"][0]["suppression"]["reason"],
+        "No setuid binaries"
+    );
+    let parsed: HostScan = serde_json::from_value(value).unwrap();
+    assert_eq!(parsed, scan);
+
+    let toml = r#"
+        [[suppressions]]
+        cve = "CVE-2023-4911"
+        component = "glibc"
+        reason = "No setuid binaries"
+        expires = "2024-06-30"
+    "#;
+    let suppressions = Suppressions::from_toml(toml).unwrap();
+    assert_eq!(
+        suppressions.suppressions[0].expires.as_deref(),
+        Some("2024-06-30")
+    );
+    let object =
+        json!({"suppressions": [{"cve": "CVE-2023-4911", "reason": "No setuid binaries"}]});
+    assert_eq!(
+        Suppressions::from_json(&object.to_string())
+            .unwrap()
+            .suppressions
+            .len(),
+        1
+    );
+
+    for invalid in [
+        json!([{"cve": "CVE-2023-4911", "reason": "Bad date", "expires": "30/06/2024"}]),
+        json!([{"cve": "CVE-2023-4911", "reason": " "}]),
+        json!([{"cve": "CVE-2023-4911"}]),
+    ] {
+        assert!(matches!(
+            Suppressions::from_json(&invalid.to_string()),
+            Err(ScanError::InvalidSuppressions(_))
+        ));
+    }
+}
+
+#[test]
+fn test_vex_documents() {
+    let finding = |cve: &str, package: Package, confidence| Finding {
+        cve: cve.to_string(),
+        package,
+        cpe: "cpe:2.3:a:openssl:openssl:3.0.2:*:*:*:*:*:*:*".to_string(),
+        confidence,
+        warning: None,
+    };
+    let libssl = package("dpkg", "libssl3", Some("openssl"), "3.0.2");
+    let openssl = package("dpkg", "openssl", None, "3.0.2");
+    let mut scan = HostScan {
+        packages: 2,
+        unmapped: vec![],
+        findings: vec![
+            finding(
+                "CVE-2022-3602",
+                libssl.clone(),
+                MatchConfidence::VersionRange,
+            ),
+            finding(
+                "CVE-2022-3602",
+                openssl.clone(),
+                MatchConfidence::VersionRange,
+            ),
+            finding(
+                "CVE-2023-0286",
+                libssl.clone(),
+                MatchConfidence::VersionWildcard,
+            ),
+        ],
+        suppressed: vec![],
+        fixed: vec![finding(
+            "CVE-2022-0778",
+            libssl.clone(),
+            MatchConfidence::VersionRange,
+        )],
+    };
+    let suppressions = Suppressions::from_json(
+        &json!([{
+            "cve": "CVE-2022-3602",
+            "component": "libssl3",
+            "reason": "No certificates are verified",
+            "justification": "vulnerable_code_not_in_execute_path"
+        }])
+        .to_string(),
+    )
+    .unwrap();
+    scan.suppress(&suppressions, NaiveDate::from_ymd_opt(2023, 1, 1).unwrap());
+
+    let publisher = VexPublisher {
+        name: "Example Corp".to_string(),
+        namespace: "https://example.com".to_string(),
+    };
+    let timestamp = Utc.with_ymd_and_hms(2023, 1, 2, 3, 4, 5).unwrap();
+
+    let openvex = serde_json::to_value(OpenVexDocument::new(&scan, &publisher, timestamp)).unwrap();
+    assert_eq!(openvex["@context"], "https://openvex.dev/ns/v0.2.0");
+    assert_eq!(openvex["author"], "Example Corp");
+    assert_eq!(openvex["timestamp"], "2023-01-02T03:04:05Z");
+    assert!(openvex["@id"].as_str().unwrap().starts_with("urn:uuid:"));
+
+    let statements = openvex["statements"].as_array().unwrap();
+    let summary: Vec<(&str, &str, &str)> = statements
+        .iter()
+        .map(|statement| {
+            (
+                statement["vulnerability"]["name"].as_str().unwrap(),
+                statement["products"][0]["@id"].as_str().unwrap(),
+                statement["status"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("CVE-2022-0778", "pkg:deb/libssl3@3.0.2", "fixed"),
+            ("CVE-2022-3602", "pkg:deb/libssl3@3.0.2", "not_affected"),
+            ("CVE-2022-3602", "pkg:deb/openssl@3.0.2", "affected"),
+            ("CVE-2023-0286", "pkg:deb/libssl3@3.0.2", "affected"),
+        ]
+    );
+    assert_eq!(
+        statements[1]["justification"],
+        "vulnerable_code_not_in_execute_path"
+    );
+    assert_eq!(
+        statements[1]["impact_statement"],
+        "No certificates are verified"
+    );
+    assert!(statements[2]["action_statement"]
+        .as_str()
+        .unwrap()
+        .starts_with("Update openssl 3.0.2"));
+    assert!(statements[3]["action_statement"]
+        .as_str()
+        .unwrap()
+        .starts_with("Verify whether libssl3 3.0.2"));
+    assert_eq!(
+        statements[0]["products"][0]["identifiers"]["cpe23"],
+        "cpe:2.3:a:openssl:openssl:3.0.2:*:*:*:*:*:*:*"
+    );
+
+    // The same scan always gets the same document ID
+    let again = serde_json::to_value(OpenVexDocument::new(&scan, &publisher, Utc::now())).unwrap();
+    assert_eq!(again["@id"], openvex["@id"]);
+
+    let csaf = serde_json::to_value(CsafDocument::new(&scan, &publisher, timestamp)).unwrap();
+    assert_eq!(csaf["document"]["category"], "csaf_vex");
+    assert_eq!(
+        csaf["document"]["publisher"]["namespace"],
+        "https://example.com"
+    );
+    assert_eq!(
+        csaf["document"]["tracking"]["current_release_date"],
+        "2023-01-02T03:04:05Z"
+    );
+    let products = csaf["product_tree"]["full_product_names"]
+        .as_array()
+        .unwrap();
+    assert_eq!(products.len(), 2);
+    assert_eq!(products[0]["name"], "libssl3 3.0.2");
+    assert_eq!(products[0]["product_id"], "CSAFPID-0001");
+    assert_eq!(
+        products[1]["product_identification_helper"]["purl"],
+        "pkg:deb/openssl@3.0.2"
+    );
+
+    let vulnerabilities = csaf["vulnerabilities"].as_array().unwrap();
+    assert_eq!(vulnerabilities.len(), 3);
+    assert_eq!(
+        vulnerabilities[0]["product_status"],
+        json!({"fixed": ["CSAFPID-0001"]})
+    );
+    let heap_overflow = &vulnerabilities[1];
+    assert_eq!(heap_overflow["cve"], "CVE-2022-3602");
+    assert_eq!(
+        heap_overflow["product_status"],
+        json!({"known_affected": ["CSAFPID-0002"], "known_not_affected": ["CSAFPID-0001"]})
+    );
+    assert_eq!(
+        heap_overflow["flags"],
+        json!([{"label": "vulnerable_code_not_in_execute_path", "product_ids": ["CSAFPID-0001"]}])
+    );
+    assert_eq!(heap_overflow["threats"][0]["category"], "impact");
+    assert_eq!(
+        heap_overflow["remediations"][0]["product_ids"],
+        json!(["CSAFPID-0002"])
+    );
+
+    assert_eq!(
+        "Vulnerable_Code_Not_Present".parse(),
+        Ok(VexJustification::VulnerableCodeNotPresent)
+    );
+    assert!("not_vulnerable".parse::<VexJustification>().is_err());
+    assert_eq!(
+        purl(&package("dpkg", "libc6", Some("glibc"), "2.36+dfsg")).as_deref(),
+        Some("pkg:deb/libc6@2.36%2Bdfsg")
+    );
+    assert_eq!(purl(&package("image", "nginx", None, "1.25.3")), None);
+}
@@ -1,12 +1,28 @@
+// Each test crate only uses some of these helpers
+#![allow(dead_code)]
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+#[cfg(feature = "blocking")]
 use nvd_cve::client::{BlockingHttpClient, HttpError};
+#[cfg(feature = "blocking")]
 use nvd_cve::cve::CveFeed;
+use nvd_cve::feed::FeedName;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
 use std::time::Duration;
 
+#[cfg(feature = "blocking")]
 pub struct MockBlockingClient {
     pub get_metafile_response: Result<String, HttpError>,
     pub get_feed_response: Result<CveFeed, HttpError>,
 }
 
+#[cfg(feature = "blocking")]
 impl BlockingHttpClient for MockBlockingClient {
     fn new<S: Into<String>>(
         _: S,
@@ -19,16 +35,159 @@ impl BlockingHttpClient for MockBlockingClient {
             get_feed_response: Err(HttpError::ParseError),
         }
     }
-    fn get_metafile(&self, _: &str) -> Result<String, HttpError> {
+    fn get_metafile(&self, _: &FeedName) -> Result<String, HttpError> {
         self.get_metafile_response.clone()
     }
-    fn get_feed(&self, _: &str) -> Result<CveFeed, HttpError> {
+    fn get_feed(&self, _: &FeedName) -> Result<CveFeed, HttpError> {
         self.get_feed_response.clone()
     }
 }
 
+#[cfg(feature = "blocking")]
 impl Default for MockBlockingClient {
     fn default() -> Self {
         Self::new("http://127.0.0.1/nvd/feeds/json/cve/1.1/", None, None, None)
     }
 }
+
+/// Minimal HTTP server serving feed files, so the binary can be run against it like a mirror
+pub struct MockFeedServer {
+    pub url: String,
+}
+
+impl MockFeedServer {
+    /// Serve the given files, by name, from a random local port until the test process exits
+    pub fn start(files: HashMap<String, Vec<u8>>) -> Self {
+        Self::start_stalling(files, &[])
+    }
+
+    /// Serve the given files like ``start``, except for the ``stalled`` files whose body is never
+    /// sent, like a hung download
+    pub fn start_stalling(files: HashMap<String, Vec<u8>>, stalled: &[String]) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed binding mock feed server");
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let files = Arc::new(files);
+        let stalled = Arc::new(stalled.to_vec());
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let files = Arc::clone(&files);
+                let stalled = Arc::clone(&stalled);
+                thread::spawn(move || respond(stream, &files, &stalled));
+            }
+        });
+
+        Self { url }
+    }
+
+    /// Redirect every request to the file of the same name at the base URL ``to``, or back to this
+    /// server for ``None``, like a redirect loop
+    pub fn start_redirecting(to: Option<String>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed binding mock feed server");
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let to = to.unwrap_or_else(|| url.clone());
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let mut reader = BufReader::new(&stream);
+                let mut request_line = String::new();
+                let mut header = String::new();
+                if reader.read_line(&mut request_line).is_err() {
+                    continue;
+                }
+                while reader.read_line(&mut header).is_ok() && header.trim() != "" {
+                    header.clear();
+                }
+
+                let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+                let name = path.rsplit('/').next().unwrap_or_default();
+                let _ = write!(
+                    &stream,
+                    "HTTP/1.1 302 Found\r\nLocation: {}{}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    to, name
+                );
+            }
+        });
+
+        Self { url }
+    }
+
+    /// Serve the recent feed's Metafile along with the feed JSON file at ``path``, GZipped
+    pub fn with_recent_feed(path: &str) -> Self {
+        Self::with_feed(FeedName::Recent, path)
+    }
+
+    /// Serve the recent feed's Metafile along with the feed JSON file at ``path``, GZipped, as
+    /// the files of the ``name`` feed
+    pub fn with_feed(name: FeedName, path: &str) -> Self {
+        let metafile =
+            fs::read("./tests/files/nvdcve-1.1-recent.meta").expect("Failed reading metafile");
+        let json = fs::read(path).expect("Failed reading feed json");
+
+        let mut files = HashMap::new();
+        files.insert(name.metafile_filename(), metafile);
+        files.insert(name.feed_filename(), gzip(&json));
+
+        Self::start(files)
+    }
+}
+
+/// GZip ``data``, as feeds are served
+pub fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(vec![], Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn respond(stream: TcpStream, files: &HashMap<String, Vec<u8>>, stalled: &[String]) {
+    let mut reader = BufReader::new(&stream);
+
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+
+    // Skip the request headers, and the body of requests such as POSTs to a mock tracker
+    let mut header = String::new();
+    let mut content_length = 0;
+    while reader.read_line(&mut header).is_ok() && header.trim() != "" {
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or_default();
+            }
+        }
+        header.clear();
+    }
+    let mut body = vec![0; content_length];
+    if reader.read_exact(&mut body).is_err() {
+        return;
+    }
+
+    // Files are served whatever the query string, e.g. the same page of an API for any query
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+    let path = path.split('?').next().unwrap_or_default();
+    let name = path.rsplit('/').next().unwrap_or_default();
+
+    let mut stream = &stream;
+    if stalled.iter().any(|stalled| stalled == name) {
+        let _ = write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Length: 1024\r\nConnection: close\r\n\r\n"
+        );
+        thread::sleep(Duration::from_secs(60));
+        return;
+    }
+
+    let _ = match files.get(name) {
+        Some(body) => write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            body.len()
+        )
+        .and_then(|_| stream.write_all(body)),
+        None => write!(
+            stream,
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+        ),
+    };
+}
@@ -0,0 +1,275 @@
+use nvd_cve::version::{VersionRange, VersionScheme};
+use std::cmp::Ordering::{self, Equal, Greater, Less};
+
+/// Assert that ``scheme`` orders each pair of versions as given, both ways around
+fn assert_ordered(scheme: VersionScheme, vectors: &[(&str, Ordering, &str)]) {
+    for (a, ordering, b) in vectors {
+        assert_eq!(
+            scheme.compare(a, b),
+            *ordering,
+            "{} {} {:?} {}",
+            scheme,
+            a,
+            ordering,
+            b
+        );
+        assert_eq!(
+            scheme.compare(b, a),
+            ordering.reverse(),
+            "{} {} {:?} {}",
+            scheme,
+            b,
+            ordering.reverse(),
+            a
+        );
+    }
+}
+
+#[test]
+fn test_compare_semver() {
+    // Precedence example of the Semantic Versioning 2.0.0 specification
+    let precedence = [
+        "1.0.0-alpha",
+        "1.0.0-alpha.1",
+        "1.0.0-alpha.beta",
+        "1.0.0-beta",
+        "1.0.0-beta.2",
+        "1.0.0-beta.11",
+        "1.0.0-rc.1",
+        "1.0.0",
+        "2.0.0",
+        "2.1.0",
+        "2.1.1",
+    ];
+    for (index, a) in precedence.iter().enumerate() {
+        for b in &precedence[index + 1..] {
+            assert_ordered(VersionScheme::Semver, &[(a, Less, b)]);
+        }
+    }
+
+    assert_ordered(
+        VersionScheme::Semver,
+        &[
+            ("1.0.0", Equal, "1.0.0"),
+            ("1.0.0+20130313144700", Equal, "1.0.0"),
+            ("1.0.0-beta+exp.sha.5114f85", Equal, "1.0.0-beta"),
+            ("v1.2.3", Equal, "1.2.3"),
+            ("1.2", Equal, "1.2.0"),
+            ("1.10.0", Greater, "1.9.0"),
+            ("1.0.10", Greater, "1.0.9"),
+            ("10.0.0", Greater, "9.99.99"),
+            ("1.0.0-rc.10", Greater, "1.0.0-rc.9"),
+            ("1.0.0-rc.1", Less, "1.0.0-rc.1.1"),
+            ("1.0.0-1", Less, "1.0.0-alpha"),
+            ("1.0.0-Alpha", Less, "1.0.0-alpha"),
+            ("0.0.1", Greater, "0.0.0"),
+        ],
+    );
+}
+
+#[test]
+fn test_compare_debian() {
+    assert_ordered(
+        VersionScheme::Debian,
+        &[
+            ("1.0", Equal, "1.0"),
+            ("0:1.0", Equal, "1.0"),
+            ("1.0-0", Equal, "1.0"),
+            ("1.0", Less, "1.1"),
+            ("1.9", Less, "1.10"),
+            ("1.0", Less, "1.0-1"),
+            ("1.0-1", Less, "1.0-2"),
+            ("1.0-9", Less, "1.0-10"),
+            ("1:0.1", Greater, "2.0"),
+            ("2:1.0", Greater, "1:9.9"),
+            ("1.0~rc1", Less, "1.0"),
+            ("1.0~rc1", Less, "1.0~rc2"),
+            ("1.0~~", Less, "1.0~"),
+            ("1.0~~a", Less, "1.0~"),
+            ("1.0~", Less, "1.0"),
+            ("1.2.3~rc1-1", Less, "1.2.3-1"),
+            ("1.0a", Greater, "1.0"),
+            ("1.0+dfsg", Greater, "1.0"),
+            ("1.0+dfsg-1", Less, "1.0+dfsg1-1"),
+            ("1.0.0", Greater, "1.0"),
+            ("1.0a", Less, "1.0."),
+            ("1.0a", Less, "1.0+"),
+            ("001", Equal, "1"),
+            ("2.30-1ubuntu1", Less, "2.30-1ubuntu1.1"),
+            ("2.30-1ubuntu1.1", Less, "2.30-2"),
+            ("2.4.52-1ubuntu4.3", Greater, "2.4.52-1ubuntu4"),
+            ("1.1.1n-0+deb11u5", Greater, "1.1.1n-0+deb11u4"),
+            ("1.1.1n-0+deb11u5", Less, "1.1.1o-0"),
+            ("7.68.0-1ubuntu2.18", Less, "7.68.0-1ubuntu2.20"),
+        ],
+    );
+}
+
+#[test]
+fn test_compare_rpm() {
+    // Test vectors of rpm's rpmvercmp
+    assert_ordered(
+        VersionScheme::Rpm,
+        &[
+            ("1.0", Equal, "1.0"),
+            ("1.0", Less, "2.0"),
+            ("2.0.1", Greater, "2.0"),
+            ("2.0.1a", Greater, "2.0.1"),
+            ("5.5p1", Less, "5.5p2"),
+            ("5.5p10", Greater, "5.5p1"),
+            ("10xyz", Less, "10.1xyz"),
+            ("xyz10", Less, "xyz10.1"),
+            ("xyz.4", Less, "8"),
+            ("1.0aa", Greater, "1.0a"),
+            ("6.0.rc1", Greater, "6.0"),
+            ("6.0.rc1", Less, "6.0.1"),
+            ("10b2", Greater, "10a1"),
+            ("1b.fc17", Equal, "1b.fc17"),
+            ("1b.fc17", Less, "1.fc17"),
+            ("1g.fc17", Less, "1.1.fc17"),
+            ("1.0~rc1", Less, "1.0"),
+            ("1.0~rc1", Less, "1.0~rc2"),
+            ("1.0~rc1~git123", Less, "1.0~rc1"),
+            ("1.0^", Greater, "1.0"),
+            ("1.0^git1", Greater, "1.0"),
+            ("1.0^git1", Less, "1.01"),
+            ("1.0^20160101", Less, "1.0.1"),
+            ("1.0^20160101^git1", Greater, "1.0^20160101"),
+            ("1.0~rc1^git1", Greater, "1.0~rc1"),
+            ("1.0~rc1^git1", Less, "1.0"),
+            ("1.0^git1~pre", Less, "1.0^git1"),
+            ("2_0", Equal, "2_0"),
+            ("2.0", Equal, "2_0"),
+            ("a+", Equal, "a_"),
+            ("+a", Equal, "_a"),
+            ("+_", Equal, "_+"),
+            ("1:1.0", Greater, "2.0"),
+            ("1.1.1k-6.el8", Greater, "1.1.1k-5.el8"),
+            ("1.1.1k-6.el8", Less, "1.1.1k-6.el8_5"),
+            ("1:1.1.1k-7.el8", Greater, "1:1.1.1k-6.el8_5"),
+        ],
+    );
+}
+
+#[test]
+fn test_compare_dotted() {
+    assert_ordered(
+        VersionScheme::Dotted,
+        &[
+            ("1.0", Equal, "1.0"),
+            ("1.0", Equal, "1.0.0"),
+            ("1.9", Less, "1.10"),
+            ("8.0.6001", Less, "10.0"),
+            ("2.14.1", Less, "2.15.0"),
+            ("2021.1", Less, "2021.10"),
+            ("1.1.1", Less, "1.1.1a"),
+            ("1.1.1a", Less, "1.1.1k"),
+            ("1.1.1k", Less, "1.1.2"),
+            ("1.1.1z", Less, "1.1.1za"),
+            ("2.0-beta9", Less, "2.0-rc1"),
+            ("2.0-alpha1", Less, "2.0-beta1"),
+            ("2.0-dev", Less, "2.0-alpha1"),
+            ("2.0-rc1", Less, "2.0-rc2"),
+            ("2.0-rc1", Less, "2.0"),
+            ("2.0rc1", Less, "2.0"),
+            ("2.0", Less, "2.0.1"),
+            ("2.0-rc1", Less, "2.0.1"),
+            ("1.0RC1", Equal, "1.0rc1"),
+            ("5.5p1", Less, "5.5p10"),
+            ("1.0_1", Equal, "1.0.1"),
+            ("1.0", Greater, "0.99"),
+            ("7.4.0.1570", Greater, "7.4.0"),
+        ],
+    );
+}
+
+#[test]
+fn test_detect_version_scheme() {
+    for (version, scheme) in [
+        ("8.0.6001", VersionScheme::Dotted),
+        ("1.1.1k", VersionScheme::Dotted),
+        ("2.0-beta9", VersionScheme::Dotted),
+        ("2.0.0-rc.1", VersionScheme::Semver),
+        ("1.0.0+20130313144700", VersionScheme::Semver),
+        ("v1.2.3-beta", VersionScheme::Semver),
+        ("1.2.3-1", VersionScheme::Debian),
+        ("1:2.4.52-1ubuntu4.3", VersionScheme::Debian),
+        ("2.4.52-1ubuntu4.3", VersionScheme::Debian),
+        ("1.1.1n-0+deb11u5", VersionScheme::Debian),
+        ("1.0~rc1", VersionScheme::Debian),
+        ("1.1.1k-6.el8", VersionScheme::Rpm),
+        ("1:1.1.1k-7.el8_6", VersionScheme::Rpm),
+        ("5.14.10-300.fc35", VersionScheme::Rpm),
+        ("1.0^git1", VersionScheme::Rpm),
+    ] {
+        assert_eq!(VersionScheme::detect(version), scheme, "{}", version);
+    }
+
+    // Versions of different schemes are compared with the one that isn't dotted
+    assert_ordered(
+        VersionScheme::Auto,
+        &[
+            ("1.10", Greater, "1.9"),
+            ("2.0.0-rc.1", Less, "2.0"),
+            ("2.0.0-rc.1", Less, "2.0.0"),
+            ("2.4.52-1ubuntu4.3", Greater, "2.4.52"),
+            ("1.0~rc1", Less, "1.0"),
+            ("1.1.1k-6.el8", Greater, "1.1.1k"),
+            ("1.1.1k", Greater, "1.1.1"),
+        ],
+    );
+
+    for scheme in VersionScheme::ALL {
+        assert_eq!(scheme.to_string().parse(), Ok(scheme));
+    }
+    assert_eq!("RPM".parse(), Ok(VersionScheme::Rpm));
+    assert!("calver".parse::<VersionScheme>().is_err());
+}
+
+#[test]
+fn test_version_range() {
+    let log4shell = VersionRange {
+        start_including: Some("2.0-beta9".to_string()),
+        end_excluding: Some("2.15.0".to_string()),
+        ..Default::default()
+    };
+    for (version, contained) in [
+        ("2.0-beta8", false),
+        ("2.0-beta9", true),
+        ("2.0-rc1", true),
+        ("2.0", true),
+        ("2.9.1", true),
+        ("2.14.1", true),
+        ("2.15.0", false),
+        ("2.16.0", false),
+        ("1.2.17", false),
+    ] {
+        assert_eq!(
+            log4shell.contains(version, VersionScheme::Auto),
+            contained,
+            "{}",
+            version
+        );
+    }
+
+    let range = VersionRange {
+        start_excluding: Some("1.1.1".to_string()),
+        end_including: Some("1.1.1k".to_string()),
+        ..Default::default()
+    };
+    assert!(!range.contains("1.1.1", VersionScheme::Dotted));
+    assert!(range.contains("1.1.1a", VersionScheme::Dotted));
+    assert!(range.contains("1.1.1k", VersionScheme::Dotted));
+    assert!(!range.contains("1.1.1l", VersionScheme::Dotted));
+    assert!(!range.is_unbounded());
+
+    // Naive string comparison would order 1.10 before 1.9
+    let range = VersionRange {
+        end_excluding: Some("1.10".to_string()),
+        ..Default::default()
+    };
+    assert!(range.contains("1.9", VersionScheme::Auto));
+
+    assert!(VersionRange::default().is_unbounded());
+    assert!(VersionRange::default().contains("anything", VersionScheme::Auto));
+}